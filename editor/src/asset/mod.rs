@@ -84,6 +84,7 @@ pub mod menu;
 pub mod preview;
 mod selection;
 pub mod selector;
+pub mod validator;
 
 fn show_in_explorer<P: AsRef<Path>>(path: P) {
     // opener crate is bugged on Windows, so using explorer's command directly.
@@ -0,0 +1,352 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A window that shows references to missing resources in the project and allows to remap them
+//! to other resources in bulk. Scanning and remapping may take a while on large projects, so it
+//! is done on the task pool and the resulting report is sent back via [`Message::ResourceReferenceReport`].
+
+use crate::{
+    fyrox::{
+        asset::{
+            manager::ResourceManager,
+            validation::{DanglingReason, ResourceReferenceReport},
+        },
+        core::{log::Log, pool::Handle, task::TaskPool, uuid::Uuid},
+        fxhash::FxHashMap,
+        gui::{
+            button::{Button, ButtonBuilder, ButtonMessage},
+            grid::{Column, GridBuilder, Row},
+            message::UiMessage,
+            scroll_viewer::{ScrollViewer, ScrollViewerBuilder, ScrollViewerMessage},
+            stack_panel::StackPanelBuilder,
+            text::{Text, TextBuilder, TextMessage},
+            text_box::{TextBox, TextBoxBuilder},
+            widget::{WidgetBuilder, WidgetMessage},
+            window::{Window, WindowAlignment, WindowBuilder, WindowMessage, WindowTitle},
+            BuildContext, HorizontalAlignment, Orientation, Thickness, UiNode, UserInterface,
+            VerticalAlignment,
+        },
+    },
+    message::MessageSender,
+    Message,
+};
+use std::sync::Arc;
+
+struct RemapEntry {
+    uuid: Uuid,
+    text_box: Handle<TextBox>,
+    new_path: String,
+}
+
+pub struct ReferenceValidatorWindow {
+    pub window: Handle<Window>,
+    summary: Handle<Text>,
+    scroll_viewer: Handle<ScrollViewer>,
+    rescan: Handle<Button>,
+    apply: Handle<Button>,
+    close: Handle<Button>,
+    entries: Vec<RemapEntry>,
+    task_pool: Arc<TaskPool>,
+    sender: MessageSender,
+}
+
+pub enum ReferenceValidatorWindowAction {
+    None,
+    Remove,
+}
+
+fn make_button(text: &str, ctx: &mut BuildContext) -> Handle<Button> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_width(100.0)
+            .with_height(22.0)
+            .with_margin(Thickness::uniform(1.0)),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+fn make_text(text: String, margin: Thickness, ctx: &mut BuildContext) -> Handle<UiNode> {
+    TextBuilder::new(
+        WidgetBuilder::new()
+            .with_margin(margin)
+            .with_vertical_alignment(VerticalAlignment::Center),
+    )
+    .with_text(text)
+    .build(ctx)
+    .to_base()
+}
+
+fn summary(report: &ResourceReferenceReport) -> String {
+    format!(
+        "Scanned resources: {}. Dangling references: {} (missing resources: {}). Failed to load: {}.",
+        report.scanned.len(),
+        report.dangling.len(),
+        report.missing_uuids().len(),
+        report.failed.len()
+    )
+}
+
+impl ReferenceValidatorWindow {
+    pub fn new(task_pool: Arc<TaskPool>, sender: MessageSender, ctx: &mut BuildContext) -> Self {
+        let summary;
+        let scroll_viewer;
+        let rescan;
+        let apply;
+        let close;
+        let window = WindowBuilder::new(WidgetBuilder::new().with_width(600.0).with_height(500.0))
+            .open(false)
+            .with_title(WindowTitle::text("Resource References Validator"))
+            .with_content(
+                GridBuilder::new(
+                    WidgetBuilder::new()
+                        .with_child({
+                            summary = TextBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(0)
+                                    .with_margin(Thickness::uniform(2.0)),
+                            )
+                            .build(ctx);
+                            summary
+                        })
+                        .with_child({
+                            scroll_viewer =
+                                ScrollViewerBuilder::new(WidgetBuilder::new().on_row(1)).build(ctx);
+                            scroll_viewer
+                        })
+                        .with_child(
+                            StackPanelBuilder::new(
+                                WidgetBuilder::new()
+                                    .with_margin(Thickness::uniform(2.0))
+                                    .with_horizontal_alignment(HorizontalAlignment::Right)
+                                    .on_row(2)
+                                    .with_child({
+                                        rescan = make_button("Rescan", ctx);
+                                        rescan
+                                    })
+                                    .with_child({
+                                        apply = make_button("Apply Remap", ctx);
+                                        apply
+                                    })
+                                    .with_child({
+                                        close = make_button("Close", ctx);
+                                        close
+                                    }),
+                            )
+                            .with_orientation(Orientation::Horizontal)
+                            .build(ctx),
+                        ),
+                )
+                .add_row(Row::auto())
+                .add_row(Row::stretch())
+                .add_row(Row::strict(28.0))
+                .add_column(Column::stretch())
+                .build(ctx),
+            )
+            .build(ctx);
+
+        Self {
+            window,
+            summary,
+            scroll_viewer,
+            rescan,
+            apply,
+            close,
+            entries: Default::default(),
+            task_pool,
+            sender,
+        }
+    }
+
+    pub fn open(&mut self, resource_manager: &ResourceManager, ui: &mut UserInterface) {
+        ui.send(
+            self.window,
+            WindowMessage::Open {
+                alignment: WindowAlignment::Center,
+                modal: false,
+                focus_content: true,
+            },
+        );
+        self.scan(resource_manager, ui);
+    }
+
+    fn set_busy(&self, ui: &UserInterface) {
+        ui.send(self.summary, TextMessage::Text("Scanning...".to_string()));
+        for button in [self.rescan, self.apply] {
+            ui.send(button, WidgetMessage::Enabled(false));
+        }
+    }
+
+    fn scan(&mut self, resource_manager: &ResourceManager, ui: &mut UserInterface) {
+        self.set_busy(ui);
+        let resource_manager = resource_manager.clone();
+        let sender = self.sender.clone();
+        self.task_pool.spawn_task(async move {
+            let report = resource_manager.validate_references().await;
+            sender.send(Message::ResourceReferenceReport(report));
+        });
+    }
+
+    /// Shows the report of a scan, that was started by the window.
+    pub fn set_report(&mut self, report: &ResourceReferenceReport, ui: &mut UserInterface) {
+        ui.send(self.summary, TextMessage::Text(summary(report)));
+        ui.send(self.rescan, WidgetMessage::Enabled(true));
+
+        let ctx = &mut ui.build_ctx();
+        let mut children = Vec::new();
+
+        self.entries.clear();
+        let mut missing = report.missing_uuids().into_iter().collect::<Vec<_>>();
+        missing.sort();
+        for uuid in missing {
+            let mut references = report.references_to(uuid).peekable();
+            let reason = references
+                .peek()
+                .map(|r| r.reason.clone())
+                .unwrap_or(DanglingReason::UnknownUuid);
+
+            let text_box = TextBoxBuilder::new(
+                WidgetBuilder::new()
+                    .on_column(1)
+                    .with_height(22.0)
+                    .with_margin(Thickness::uniform(1.0)),
+            )
+            .build(ctx);
+            children.push(
+                GridBuilder::new(
+                    WidgetBuilder::new()
+                        .with_child(make_text(
+                            format!("{uuid} ({reason})"),
+                            Thickness::uniform(1.0),
+                            ctx,
+                        ))
+                        .with_child(text_box),
+                )
+                .add_row(Row::auto())
+                .add_column(Column::stretch())
+                .add_column(Column::strict(200.0))
+                .build(ctx)
+                .to_base(),
+            );
+
+            for reference in references {
+                children.push(make_text(
+                    format!("{}: {}", reference.owner.display(), reference.field_path),
+                    Thickness::left(16.0),
+                    ctx,
+                ));
+            }
+
+            self.entries.push(RemapEntry {
+                uuid,
+                text_box,
+                new_path: Default::default(),
+            });
+        }
+
+        for (path, reason) in report.failed.iter() {
+            children.push(make_text(
+                format!("Failed to load {}: {reason}", path.display()),
+                Thickness::uniform(1.0),
+                ctx,
+            ));
+        }
+
+        let content =
+            StackPanelBuilder::new(WidgetBuilder::new().with_children(children)).build(ctx);
+        ui.send(
+            self.scroll_viewer,
+            ScrollViewerMessage::Content(content.to_base()),
+        );
+        ui.send(self.apply, WidgetMessage::Enabled(!self.entries.is_empty()));
+    }
+
+    fn apply(&mut self, resource_manager: &ResourceManager, ui: &mut UserInterface) {
+        let replacements = self
+            .entries
+            .iter()
+            .filter(|entry| !entry.new_path.trim().is_empty())
+            .map(|entry| (entry.uuid, entry.new_path.trim().to_string()))
+            .collect::<Vec<_>>();
+
+        self.set_busy(ui);
+        let resource_manager = resource_manager.clone();
+        let sender = self.sender.clone();
+        self.task_pool.spawn_task(async move {
+            let mut remap = FxHashMap::default();
+            for (uuid, new_path) in replacements {
+                match resource_manager.request_untyped(&new_path).await {
+                    Ok(resource) => {
+                        remap.insert(uuid, resource);
+                    }
+                    Err(err) => Log::err(format!(
+                        "Unable to use {new_path} as a replacement for {uuid}. Reason: {err:?}",
+                    )),
+                }
+            }
+
+            let report = resource_manager.remap_references(&remap).await;
+            Log::info(format!(
+                "{} references were remapped in {} resources.",
+                report.replaced,
+                report.modified.len()
+            ));
+            for (path, reason) in report.failed {
+                Log::err(format!(
+                    "Unable to remap references in {}. Reason: {reason}",
+                    path.display()
+                ));
+            }
+
+            let report = resource_manager.validate_references().await;
+            sender.send(Message::ResourceReferenceReport(report));
+        });
+    }
+
+    pub fn handle_ui_message(
+        &mut self,
+        message: &UiMessage,
+        resource_manager: &ResourceManager,
+        ui: &mut UserInterface,
+    ) -> ReferenceValidatorWindowAction {
+        if let Some(ButtonMessage::Click) = message.data() {
+            if message.destination() == self.rescan {
+                self.scan(resource_manager, ui);
+            } else if message.destination() == self.apply {
+                self.apply(resource_manager, ui);
+            } else if message.destination() == self.close {
+                ui.send(self.window, WindowMessage::Close);
+            }
+        } else if let Some(WindowMessage::Close) = message.data() {
+            if message.destination() == self.window {
+                ui.send(self.window, WidgetMessage::Remove);
+                return ReferenceValidatorWindowAction::Remove;
+            }
+        }
+
+        for entry in self.entries.iter_mut() {
+            if let Some(TextMessage::Text(text)) = message.data_from(entry.text_box) {
+                entry.new_path.clone_from(text);
+            }
+        }
+
+        ReferenceValidatorWindowAction::None
+    }
+}
@@ -68,7 +68,8 @@ use crate::{
     message::MessageSender,
     scene::{
         commands::terrain::{
-            ModifyTerrainHeightCommand, ModifyTerrainHolesCommand, ModifyTerrainLayerMaskCommand,
+            ModifyTerrainFoliageMaskCommand, ModifyTerrainHeightCommand, ModifyTerrainHolesCommand,
+            ModifyTerrainLayerMaskCommand,
         },
         controller::SceneController,
         GameScene, Selection, SelectionContainer,
@@ -103,6 +104,9 @@ fn handle_undo_chunks(undo_chunks: UndoData, sender: &MessageSender) {
             undo_chunks.chunks,
             layer,
         )),
+        BrushTarget::FoliageMask { layer } => sender.do_command(
+            ModifyTerrainFoliageMaskCommand::new(undo_chunks.node, undo_chunks.chunks, layer),
+        ),
     }
 }
 
@@ -195,6 +199,11 @@ impl TerrainInteractionMode {
                 return;
             }
         }
+        if let BrushTarget::FoliageMask { layer } = brush.target {
+            if layer >= terrain.foliage_layers().len() {
+                return;
+            }
+        }
         // Reverse the behavior of a brush when shift is held.
         if shift {
             match &mut brush.mode {
@@ -220,12 +229,16 @@ impl TerrainInteractionMode {
         };
         let position = match self.brush.target {
             BrushTarget::HeightMap => terrain.local_to_height_pixel(position),
-            BrushTarget::LayerMask { .. } => terrain.local_to_mask_pixel(position),
+            BrushTarget::LayerMask { .. } | BrushTarget::FoliageMask { .. } => {
+                terrain.local_to_mask_pixel(position)
+            }
             BrushTarget::HoleMask => terrain.local_to_hole_pixel(position),
         };
         let scale = match self.brush.target {
             BrushTarget::HeightMap => terrain.height_grid_scale(),
-            BrushTarget::LayerMask { .. } => terrain.mask_grid_scale(),
+            BrushTarget::LayerMask { .. } | BrushTarget::FoliageMask { .. } => {
+                terrain.mask_grid_scale()
+            }
             BrushTarget::HoleMask => terrain.hole_grid_scale(),
         };
         if let Some(sender) = &self.brush_sender {
@@ -522,12 +535,16 @@ impl InteractionMode for TerrainInteractionMode {
             self.modify_brush_opacity(1.0);
             processed = true;
         } else if hotkey == &key_bindings.prev_layer {
-            if let BrushTarget::LayerMask { layer, .. } = &mut self.brush.target {
+            if let BrushTarget::LayerMask { layer, .. } | BrushTarget::FoliageMask { layer } =
+                &mut self.brush.target
+            {
                 *layer = layer.saturating_sub(1);
             }
             processed = true;
         } else if hotkey == &key_bindings.next_layer {
-            if let BrushTarget::LayerMask { layer, .. } = &mut self.brush.target {
+            if let BrushTarget::LayerMask { layer, .. } | BrushTarget::FoliageMask { layer } =
+                &mut self.brush.target
+            {
                 *layer = layer.saturating_add(1);
             }
             processed = true;
@@ -598,18 +615,21 @@ fn make_brush_target_enum_property_editor_definition() -> EnumPropertyEditorDefi
             0 => BrushTarget::HeightMap,
             1 => BrushTarget::LayerMask { layer: 0 },
             2 => BrushTarget::HoleMask,
+            3 => BrushTarget::FoliageMask { layer: 0 },
             _ => unreachable!(),
         },
         index_generator: |v| match v {
             BrushTarget::HeightMap => 0,
             BrushTarget::LayerMask { .. } => 1,
             BrushTarget::HoleMask => 2,
+            BrushTarget::FoliageMask { .. } => 3,
         },
         names_generator: || {
             vec![
                 "Height Map".to_string(),
                 "Layer Mask".to_string(),
                 "Holes".to_string(),
+                "Foliage Mask".to_string(),
             ]
         },
     }
@@ -58,7 +58,11 @@ pub mod world;
 pub use fyrox;
 
 use crate::{
-    asset::{item::AssetItem, AssetBrowser},
+    asset::{
+        item::AssetItem,
+        validator::{ReferenceValidatorWindow, ReferenceValidatorWindowAction},
+        AssetBrowser,
+    },
     audio::{preview::AudioPreviewPanel, AudioPanel},
     camera::panel::CameraPreviewControlPanel,
     command::{panel::CommandStackViewer, Command, CommandTrait},
@@ -162,6 +166,7 @@ use crate::{
         settings::SettingsPlugin,
         stats::EditorStatisticsPlugin,
         tilemap::TileMapEditorPlugin,
+        vehicle::VehiclePlugin,
     },
     scene::{
         commands::{
@@ -665,6 +670,7 @@ pub struct Editor {
     pub highlighter: Option<Rc<RefCell<HighlightRenderPass>>>,
     pub export_window: Option<ExportWindow>,
     pub statistics_window: Option<StatisticsWindow>,
+    pub reference_validator: Option<ReferenceValidatorWindow>,
     pub surface_data_viewer: Option<SurfaceDataViewer>,
     pub processed_ui_messages: usize,
     pub styles: FxHashMap<EditorStyle, StyleResource>,
@@ -1080,6 +1086,7 @@ impl Editor {
                 .with(EditorStatisticsPlugin::default())
                 .with(CurveEditorPlugin::default())
                 .with(ReflectionProbePlugin::default())
+                .with(VehiclePlugin)
                 .with(inspector_plugin),
             // Apparently, some window managers (like Wayland), does not send `Focused` event after the window
             // was created. So we must assume that the editor is focused by default, otherwise editor's thread
@@ -1093,6 +1100,7 @@ impl Editor {
             highlighter: None,
             export_window: None,
             statistics_window: None,
+            reference_validator: None,
             surface_data_viewer: None,
             processed_ui_messages: 0,
             styles,
@@ -1410,6 +1418,7 @@ impl Editor {
                     scene_settings: &self.scene_settings,
                     export_window: &mut self.export_window,
                     statistics_window: &mut self.statistics_window,
+                    reference_validator: &mut self.reference_validator,
                 },
                 settings: &mut self.settings,
                 icon_request_sender: self.asset_browser.preview_sender.clone(),
@@ -1445,6 +1454,13 @@ impl Editor {
                 self.statistics_window.take();
             }
         }
+        if let Some(validator) = self.reference_validator.as_mut() {
+            if let ReferenceValidatorWindowAction::Remove =
+                validator.handle_ui_message(message, &engine.resource_manager, ui)
+            {
+                self.reference_validator.take();
+            }
+        }
         self.log.handle_ui_message(message, ui);
 
         self.command_stack_viewer.handle_ui_message(message);
@@ -2744,6 +2760,11 @@ impl Editor {
                         viewer.open(data, &mut self.engine);
                         self.surface_data_viewer = Some(viewer);
                     }
+                    Message::ResourceReferenceReport(report) => {
+                        if let Some(validator) = self.reference_validator.as_mut() {
+                            validator.set_report(&report, self.engine.user_interfaces.first_mut());
+                        }
+                    }
                     Message::SyncInteractionModes => {
                         self.scene_viewer.sync_interaction_modes(
                             self.scenes.current_scene_entry_mut(),
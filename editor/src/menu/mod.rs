@@ -19,7 +19,7 @@
 // SOFTWARE.
 
 use crate::{
-    asset::preview::cache::IconRequest,
+    asset::{preview::cache::IconRequest, validator::ReferenceValidatorWindow},
     export::ExportWindow,
     fyrox::{
         core::{algebra::Vector2, pool::Handle},
@@ -83,6 +83,7 @@ pub struct Panels<'b> {
     pub scene_settings: &'b SceneSettingsWindow,
     pub export_window: &'b mut Option<ExportWindow>,
     pub statistics_window: &'b mut Option<StatisticsWindow>,
+    pub reference_validator: &'b mut Option<ReferenceValidatorWindow>,
 }
 
 pub struct MenuContext<'a, 'b> {
@@ -247,7 +248,8 @@ impl Menu {
         self.utils_menu.handle_ui_message(
             message,
             &mut ctx.panels,
-            ctx.engine.user_interfaces.first_mut(),
+            &self.message_sender,
+            ctx.engine,
         );
         self.file_menu.handle_ui_message(
             message,
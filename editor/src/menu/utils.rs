@@ -19,11 +19,14 @@
 // SOFTWARE.
 
 use crate::{
+    asset::validator::ReferenceValidatorWindow,
     fyrox::{
         asset::core::pool::Handle,
-        gui::{menu::MenuItemMessage, message::UiMessage, BuildContext, UserInterface},
+        engine::Engine,
+        gui::{menu::MenuItemMessage, message::UiMessage, BuildContext},
     },
     menu::{create_menu_item, create_root_menu_item, Panels},
+    message::MessageSender,
     stats::StatisticsWindow,
 };
 use fyrox::core::uuid::{uuid, Uuid};
@@ -32,32 +35,47 @@ use fyrox::gui::menu::MenuItem;
 pub struct UtilsMenu {
     pub menu: Handle<MenuItem>,
     pub rendering_statistics: Handle<MenuItem>,
+    pub validate_references: Handle<MenuItem>,
 }
 
 impl UtilsMenu {
     pub const UTILS: Uuid = uuid!("f6a9a297-6efc-4b62-83b6-3955c0c43a00");
     pub const RENDERING_STATISTICS: Uuid = uuid!("ecf0bdb9-f97f-4df0-b17f-7ec07bdebd4d");
+    pub const VALIDATE_REFERENCES: Uuid = uuid!("3f1c9e52-7b4d-4a8e-b2d6-5c0e9f7a1d84");
 
     pub fn new(ctx: &mut BuildContext) -> Self {
         let rendering_statistics;
+        let validate_references;
         let menu = create_root_menu_item(
             "Utils",
             Self::UTILS,
-            vec![{
-                rendering_statistics = create_menu_item(
-                    "Rendering Statistics",
-                    Self::RENDERING_STATISTICS,
-                    vec![],
-                    ctx,
-                );
-                rendering_statistics
-            }],
+            vec![
+                {
+                    rendering_statistics = create_menu_item(
+                        "Rendering Statistics",
+                        Self::RENDERING_STATISTICS,
+                        vec![],
+                        ctx,
+                    );
+                    rendering_statistics
+                },
+                {
+                    validate_references = create_menu_item(
+                        "Validate Resource References",
+                        Self::VALIDATE_REFERENCES,
+                        vec![],
+                        ctx,
+                    );
+                    validate_references
+                },
+            ],
             ctx,
         );
 
         Self {
             menu,
             rendering_statistics,
+            validate_references,
         }
     }
 
@@ -65,14 +83,26 @@ impl UtilsMenu {
         &mut self,
         message: &UiMessage,
         panels: &mut Panels,
-        ui: &mut UserInterface,
+        sender: &MessageSender,
+        engine: &mut Engine,
     ) {
+        let ui = engine.user_interfaces.first_mut();
         if let Some(MenuItemMessage::Click) = message.data::<MenuItemMessage>() {
             if message.destination() == self.rendering_statistics {
                 *panels.statistics_window = Some(StatisticsWindow::new(
                     &mut ui.build_ctx(),
                     panels.scene_frame,
                 ))
+            } else if message.destination() == self.validate_references
+                && panels.reference_validator.is_none()
+            {
+                let mut validator = ReferenceValidatorWindow::new(
+                    engine.task_pool.inner().clone(),
+                    sender.clone(),
+                    &mut ui.build_ctx(),
+                );
+                validator.open(&engine.resource_manager, ui);
+                *panels.reference_validator = Some(validator);
             }
         }
     }
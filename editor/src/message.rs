@@ -21,6 +21,7 @@
 use crate::{
     command::{Command, CommandGroup, CommandTrait},
     fyrox::{
+        asset::validation::ResourceReferenceReport,
         core::{
             log::Log,
             pool::{ErasedHandle, Handle},
@@ -122,6 +123,8 @@ pub enum Message {
     ViewSurfaceData(SurfaceResource),
     SyncInteractionModes,
     SetAssetBrowserCurrentDir(PathBuf),
+    /// A report of the resource references validator, see [`crate::asset::validator`].
+    ResourceReferenceReport(ResourceReferenceReport),
 }
 
 #[derive(Clone, Debug, Reflect)]
@@ -290,7 +290,7 @@ static GIZMO_SHADER: LazyLock<ShaderResource> = LazyLock::new(|| {
     .unwrap()
 });
 
-fn make_handle(scene: &mut Scene, root: Handle<Pivot>, visible: bool) -> Handle<Sprite> {
+pub(crate) fn make_handle(scene: &mut Scene, root: Handle<Pivot>, visible: bool) -> Handle<Sprite> {
     let mut material = Material::from_shader(GIZMO_SHADER.clone());
 
     material.bind(
@@ -136,7 +136,7 @@ use crate::{
             },
             pivot::Pivot,
             probe::UpdateMode,
-            ragdoll::{Limb, Ragdoll},
+            ragdoll::{Limb, LimbMode, Ragdoll},
             rigidbody::{RigidBody, RigidBodyMassPropertiesType, RigidBodyType},
             skybox::SkyBox,
             sound::{
@@ -146,18 +146,25 @@ use crate::{
                     HighShelfFilterEffect, LowPassFilterEffect, LowShelfFilterEffect,
                 },
                 listener::Listener,
+                occlusion::SoundOcclusionMaterial,
                 reverb::Reverb,
+                reverb_zone::{ReverbPreset, ReverbZoneShape},
                 Attenuate, AudioBus, Biquad, DistanceModel, Effect, Sound, SoundBuffer,
                 SoundBufferResource, Status,
             },
             sprite::Sprite,
-            terrain::{Chunk, Layer, Terrain},
+            terrain::{
+                foliage::{FoliageCollider, FoliageLayer},
+                Chunk, Layer, Terrain,
+            },
             tilemap::{
                 brush::{TileMapBrush, TileMapBrushResource},
                 tileset::TileSet,
                 Tile, TileCollider, TileDefinitionHandle, TileMap,
             },
             transform::Transform,
+            vehicle::{AntiRollBar, Tire, Wheel},
+            water::{WaterSurface, Wave},
             EnvironmentLightingSource,
         },
     },
@@ -340,6 +347,9 @@ pub fn make_property_editors_container(
     container.register_inheritable_vec_collection::<Layer>();
     container.register_inheritable_inspectable::<Layer>();
 
+    container.register_inheritable_vec_collection::<FoliageLayer>();
+    container.register_inheritable_inspectable::<FoliageLayer>();
+
     container.register_inheritable_vec_collection::<Emitter>();
 
     container.register_inheritable_vec_collection::<LevelOfDetail>();
@@ -504,11 +514,14 @@ pub fn make_property_editors_container(
     container.register_inheritable_enum::<DistanceModel, _>();
     container.register_enum::<sound::Renderer, _>();
     container.register_inheritable_enum::<RenderPath, _>();
+    container.register_inheritable_enum::<FoliageCollider, _>();
     container.register_inheritable_enum::<TexturePixelKind, _>();
     container.register_inheritable_enum::<EnvironmentLightingSource, _>();
     container.register_inheritable_enum::<CoordinateSystem, _>();
     container.register_inheritable_enum::<UpdateMode, _>();
     container.register_inheritable_enum::<LuminanceCalculationMethod, _>();
+    container.register_inheritable_enum::<ReverbZoneShape, _>();
+    container.register_inheritable_enum::<WaterSurface, _>();
 
     container.insert(EnumPropertyEditorDefinition::<Vec<ScriptRecord>>::new_optional());
     container.insert(VecCollectionPropertyEditorDefinition::<ScriptRecord>::new());
@@ -537,6 +550,8 @@ pub fn make_property_editors_container(
     container.register_inheritable_inspectable::<dim2::collider::TileMapShape>();
     container.register_inheritable_inspectable::<ConvexPolyhedronShape>();
     container.register_inheritable_inspectable::<JointMotorParams>();
+    container.register_inheritable_inspectable::<SoundOcclusionMaterial>();
+    container.register_inheritable_inspectable::<ReverbPreset>();
     container.register_inheritable_inspectable::<dim2::joint::JointMotorParams>();
     container.insert(SpriteSheetFramesContainerEditorDefinition);
 
@@ -583,6 +598,21 @@ pub fn make_property_editors_container(
 
     container.register_inheritable_inspectable::<Limb>();
     container.insert(VecCollectionPropertyEditorDefinition::<Limb>::new());
+    container.insert(EnumPropertyEditorDefinition::<LimbMode>::new());
+
+    container.register_inheritable_inspectable::<Tire>();
+    container.register_inheritable_inspectable::<Wheel>();
+    container.register_inheritable_vec_collection::<Wheel>();
+    container.register_inheritable_inspectable::<AntiRollBar>();
+    container.register_inheritable_vec_collection::<AntiRollBar>();
+    container.register_inheritable_inspectable::<dim2::vehicle::Wheel>();
+    container.register_inheritable_vec_collection::<dim2::vehicle::Wheel>();
+
+    // Per-vertex weights of cloth.
+    container.register_inheritable_vec_collection::<f32>();
+
+    container.register_inheritable_inspectable::<Wave>();
+    container.register_inheritable_vec_collection::<Wave>();
 
     container.register_inheritable_enum::<BatchingMode, _>();
 
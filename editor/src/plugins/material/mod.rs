@@ -695,15 +695,34 @@ impl MaterialEditor {
         icon_request_sender: &Sender<IconRequest>,
     ) {
         for event in receiver.try_iter() {
-            if let ResourceEvent::Reloaded(resource) = event {
-                let shader = some_or_continue!(resource.try_cast::<Shader>());
-                let material_resource = some_or_continue!(self.material.as_ref());
-                let material_data = material_resource.data_ref();
-                let material = some_or_continue!(material_data.as_loaded_ref());
-                if material.shader() == &shader {
-                    drop(material_data);
-                    self.set_material(self.material.clone(), sender, icon_request_sender, engine);
+            match event {
+                ResourceEvent::Reloaded(resource) => {
+                    let shader = some_or_continue!(resource.try_cast::<Shader>());
+                    let material_resource = some_or_continue!(self.material.as_ref());
+                    let material_data = material_resource.data_ref();
+                    let material = some_or_continue!(material_data.as_loaded_ref());
+                    if material.shader() == &shader {
+                        drop(material_data);
+                        self.set_material(
+                            self.material.clone(),
+                            sender,
+                            icon_request_sender,
+                            engine,
+                        );
+                    }
+                }
+                ResourceEvent::DependencyReloaded { resource, .. } => {
+                    let material_resource = some_or_continue!(self.material.as_ref());
+                    if material_resource.as_ref() == &resource {
+                        self.set_material(
+                            self.material.clone(),
+                            sender,
+                            icon_request_sender,
+                            engine,
+                        );
+                    }
                 }
+                _ => (),
             }
         }
 
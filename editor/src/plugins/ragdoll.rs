@@ -906,29 +906,37 @@ impl RagdollPreset {
         graph[ragdoll].root_limb.set_value_and_mark_modified(Limb {
             bone: self.hips,
             physical_bone: hips,
+            mode: Default::default(),
             children: vec![
                 Limb {
                     bone: self.spine,
                     physical_bone: spine,
+                    mode: Default::default(),
                     children: vec![Limb {
                         bone: self.spine1,
                         physical_bone: spine1,
+                        mode: Default::default(),
                         children: vec![Limb {
                             bone: self.spine2,
                             physical_bone: spine2,
+                            mode: Default::default(),
                             children: vec![
                                 Limb {
                                     bone: self.left_shoulder,
                                     physical_bone: left_shoulder,
+                                    mode: Default::default(),
                                     children: vec![Limb {
                                         bone: self.left_arm,
                                         physical_bone: left_arm,
+                                        mode: Default::default(),
                                         children: vec![Limb {
                                             bone: self.left_fore_arm,
                                             physical_bone: left_fore_arm,
+                                            mode: Default::default(),
                                             children: vec![Limb {
                                                 bone: self.left_hand,
                                                 physical_bone: left_hand,
+                                                mode: Default::default(),
                                                 children: vec![],
                                             }],
                                         }],
@@ -937,15 +945,19 @@ impl RagdollPreset {
                                 Limb {
                                     bone: self.right_shoulder,
                                     physical_bone: right_shoulder,
+                                    mode: Default::default(),
                                     children: vec![Limb {
                                         bone: self.right_arm,
                                         physical_bone: right_arm,
+                                        mode: Default::default(),
                                         children: vec![Limb {
                                             bone: self.right_fore_arm,
                                             physical_bone: right_fore_arm,
+                                            mode: Default::default(),
                                             children: vec![Limb {
                                                 bone: self.right_hand,
                                                 physical_bone: right_hand,
+                                                mode: Default::default(),
                                                 children: vec![],
                                             }],
                                         }],
@@ -954,9 +966,11 @@ impl RagdollPreset {
                                 Limb {
                                     bone: self.neck,
                                     physical_bone: neck,
+                                    mode: Default::default(),
                                     children: vec![Limb {
                                         bone: self.head,
                                         physical_bone: head,
+                                        mode: Default::default(),
                                         children: vec![],
                                     }],
                                 },
@@ -967,12 +981,15 @@ impl RagdollPreset {
                 Limb {
                     bone: self.left_up_leg,
                     physical_bone: left_up_leg,
+                    mode: Default::default(),
                     children: vec![Limb {
                         bone: self.left_leg,
                         physical_bone: left_leg,
+                        mode: Default::default(),
                         children: vec![Limb {
                             bone: self.left_foot,
                             physical_bone: left_foot,
+                            mode: Default::default(),
                             children: vec![],
                         }],
                     }],
@@ -980,12 +997,15 @@ impl RagdollPreset {
                 Limb {
                     bone: self.right_up_leg,
                     physical_bone: right_up_leg,
+                    mode: Default::default(),
                     children: vec![Limb {
                         bone: self.right_leg,
                         physical_bone: right_leg,
+                        mode: Default::default(),
                         children: vec![Limb {
                             bone: self.right_foot,
                             physical_bone: right_foot,
+                            mode: Default::default(),
                             children: vec![],
                         }],
                     }],
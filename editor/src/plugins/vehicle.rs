@@ -0,0 +1,424 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Wheel placement plugin for raycast vehicles.
+
+use crate::{
+    camera::PickingOptions,
+    command::SetPropertyCommand,
+    fyrox::{
+        core::{
+            algebra::{Vector2, Vector3},
+            color::Color,
+            pool::Handle,
+            reflect::prelude::*,
+            some_or_return,
+            uuid::Uuid,
+        },
+        engine::Engine,
+        graph::SceneGraph,
+        gui::{button::Button, BuildContext},
+        scene::{dim2, node::Node, sprite::Sprite, vehicle::Vehicle, Scene},
+    },
+    interaction::{
+        calculate_gizmo_distance_scaling, gizmo::move_gizmo::MoveGizmo,
+        make_interaction_mode_button, plane::PlaneKind, InteractionMode,
+    },
+    message::MessageSender,
+    plugin::EditorPlugin,
+    plugins::collider::make_handle,
+    scene::{commands::GameSceneContext, controller::SceneController, GameScene, Selection},
+    settings::Settings,
+    Editor, Message,
+};
+
+fn wheel_positions(vehicle: Handle<Node>, scene: &Scene) -> Option<Vec<Vector3<f32>>> {
+    if let Ok(vehicle) = scene.graph.try_get_of_type::<Vehicle>(vehicle) {
+        Some(vehicle.wheels().iter().map(|w| w.position).collect())
+    } else if let Ok(vehicle) = scene
+        .graph
+        .try_get_of_type::<dim2::vehicle::Vehicle>(vehicle)
+    {
+        Some(
+            vehicle
+                .wheels()
+                .iter()
+                .map(|w| w.position.push(0.0))
+                .collect(),
+        )
+    } else {
+        None
+    }
+}
+
+fn set_wheel_position(
+    vehicle: Handle<Node>,
+    scene: &mut Scene,
+    index: usize,
+    position: Vector3<f32>,
+) {
+    if let Ok(vehicle) = scene.graph.try_get_mut_of_type::<Vehicle>(vehicle) {
+        if let Some(wheel) = vehicle.wheels_mut().get_mut(index) {
+            wheel.position = position;
+        }
+    } else if let Ok(vehicle) = scene
+        .graph
+        .try_get_mut_of_type::<dim2::vehicle::Vehicle>(vehicle)
+    {
+        if let Some(wheel) = vehicle.wheels_mut().get_mut(index) {
+            wheel.position = position.xy();
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Reflect, Debug)]
+#[reflect(non_cloneable, type_uuid = "3f1490d9-f68c-467f-b78b-5412b352e915")]
+enum InitialWheels {
+    TwoD(Vec<dim2::vehicle::Wheel>),
+    ThreeD(Vec<fyrox::scene::vehicle::Wheel>),
+}
+
+impl InitialWheels {
+    fn new(vehicle: Handle<Node>, scene: &Scene) -> Option<Self> {
+        if let Ok(vehicle) = scene.graph.try_get_of_type::<Vehicle>(vehicle) {
+            Some(Self::ThreeD(vehicle.wheels().to_vec()))
+        } else {
+            scene
+                .graph
+                .try_get_of_type::<dim2::vehicle::Vehicle>(vehicle)
+                .ok()
+                .map(|vehicle| Self::TwoD(vehicle.wheels().to_vec()))
+        }
+    }
+
+    // Puts the initial wheels back and returns the current ones.
+    fn swap(self, vehicle: Handle<Node>, scene: &mut Scene) -> Option<Box<dyn Reflect>> {
+        match self {
+            InitialWheels::ThreeD(wheels) => scene
+                .graph
+                .try_get_mut_of_type::<Vehicle>(vehicle)
+                .ok()
+                .map(|vehicle| Box::new(vehicle.set_wheels(wheels)) as Box<dyn Reflect>),
+            InitialWheels::TwoD(wheels) => scene
+                .graph
+                .try_get_mut_of_type::<dim2::vehicle::Vehicle>(vehicle)
+                .ok()
+                .map(|vehicle| Box::new(vehicle.set_wheels(wheels)) as Box<dyn Reflect>),
+        }
+    }
+}
+
+#[derive(PartialEq, Reflect, Debug)]
+#[reflect(non_cloneable, type_uuid = "7a69ae38-db20-4041-aaa7-e916a3bfc495")]
+struct DragContext {
+    wheel: usize,
+    plane_kind: PlaneKind,
+    initial_wheels: InitialWheels,
+}
+
+#[derive(PartialEq, Reflect, Debug)]
+#[reflect(non_cloneable, type_uuid = "9b9614e2-35b4-4567-a6df-34866e6e0640")]
+pub struct VehicleInteractionMode {
+    vehicle: Handle<Node>,
+    wheel_handles: Vec<Handle<Sprite>>,
+    selected_wheel: Option<usize>,
+    move_gizmo: MoveGizmo,
+    message_sender: MessageSender,
+    #[reflect(hidden)]
+    drag_context: Option<DragContext>,
+}
+
+impl VehicleInteractionMode {
+    fn destroy(self, scene: &mut Scene) {
+        for handle in self.wheel_handles {
+            scene.graph.remove_node(handle);
+        }
+        self.move_gizmo.destroy(&mut scene.graph)
+    }
+
+    fn set_visible(&self, controller: &dyn SceneController, engine: &mut Engine, visible: bool) {
+        let game_scene = some_or_return!(controller.downcast_ref::<GameScene>());
+        let scene = &mut engine.scenes[game_scene.scene];
+        for &handle in self.wheel_handles.iter() {
+            scene.graph[handle].set_visibility(visible);
+        }
+        self.move_gizmo
+            .set_visible(&mut scene.graph, visible && self.selected_wheel.is_some());
+    }
+}
+
+impl InteractionMode for VehicleInteractionMode {
+    fn on_left_mouse_button_down(
+        &mut self,
+        _editor_selection: &Selection,
+        controller: &mut dyn SceneController,
+        engine: &mut Engine,
+        mouse_position: Vector2<f32>,
+        _frame_size: Vector2<f32>,
+        settings: &Settings,
+    ) {
+        let game_scene = some_or_return!(controller.downcast_mut::<GameScene>());
+        let scene = &mut engine.scenes[game_scene.scene];
+
+        let result = some_or_return!(game_scene.camera_controller.pick(
+            &scene.graph,
+            PickingOptions {
+                cursor_pos: mouse_position,
+                editor_only: true,
+                filter: Some(&mut |handle, _| handle != self.move_gizmo.origin),
+                ignore_back_faces: false,
+                use_picking_loop: false,
+                method: Default::default(),
+                settings: &settings.selection,
+            },
+        ));
+
+        if let Some(index) = self
+            .wheel_handles
+            .iter()
+            .position(|h| *h == result.node.to_variant::<Sprite>())
+        {
+            self.selected_wheel = Some(index);
+        } else if let Some(plane_kind) = self.move_gizmo.handle_pick(result.node, &mut scene.graph)
+        {
+            let wheel = some_or_return!(self.selected_wheel);
+            let initial_wheels = some_or_return!(InitialWheels::new(self.vehicle, scene));
+            self.drag_context = Some(DragContext {
+                wheel,
+                plane_kind,
+                initial_wheels,
+            });
+        }
+    }
+
+    fn on_left_mouse_button_up(
+        &mut self,
+        _editor_selection: &Selection,
+        controller: &mut dyn SceneController,
+        engine: &mut Engine,
+        _mouse_pos: Vector2<f32>,
+        _frame_size: Vector2<f32>,
+        _settings: &Settings,
+    ) {
+        let game_scene = some_or_return!(controller.downcast_mut::<GameScene>());
+        let scene = &mut engine.scenes[game_scene.scene];
+
+        let drag_context = some_or_return!(self.drag_context.take());
+        let vehicle = self.vehicle;
+        let value = some_or_return!(drag_context.initial_wheels.swap(vehicle, scene));
+        let command = SetPropertyCommand::new("wheels".into(), value, move |ctx| {
+            ctx.get_mut::<GameSceneContext>()
+                .scene
+                .graph
+                .try_get_node_mut(vehicle)
+                .ok()
+                .map(|n| n as &mut dyn Reflect)
+        });
+        self.message_sender.do_command(command);
+    }
+
+    fn on_mouse_move(
+        &mut self,
+        mouse_offset: Vector2<f32>,
+        mouse_position: Vector2<f32>,
+        _editor_selection: &Selection,
+        controller: &mut dyn SceneController,
+        engine: &mut Engine,
+        frame_size: Vector2<f32>,
+        settings: &Settings,
+    ) {
+        let game_scene = some_or_return!(controller.downcast_mut::<GameScene>());
+        let scene = &mut engine.scenes[game_scene.scene];
+
+        for (i, &handle) in self.wheel_handles.iter().enumerate() {
+            let color = if self.selected_wheel == Some(i) {
+                Color::GREEN
+            } else {
+                Color::MAROON
+            };
+            scene.graph[handle].set_color(color);
+        }
+        self.move_gizmo.reset_state(&mut scene.graph);
+
+        if let Some(result) = game_scene.camera_controller.pick(
+            &scene.graph,
+            PickingOptions {
+                cursor_pos: mouse_position,
+                editor_only: true,
+                filter: Some(&mut |handle, _| handle != self.move_gizmo.origin),
+                ignore_back_faces: false,
+                use_picking_loop: false,
+                method: Default::default(),
+                settings: &settings.selection,
+            },
+        ) {
+            if self.wheel_handles.contains(&result.node.to_variant()) {
+                scene.graph[result.node]
+                    .as_sprite_mut()
+                    .set_color(Color::RED);
+            }
+
+            self.move_gizmo.handle_pick(result.node, &mut scene.graph);
+        }
+
+        if let Some(drag_context) = self.drag_context.as_ref() {
+            let positions = some_or_return!(wheel_positions(self.vehicle, scene));
+            let position = some_or_return!(positions.get(drag_context.wheel));
+
+            let global_offset = self.move_gizmo.calculate_offset(
+                &scene.graph,
+                game_scene.camera_controller.camera,
+                mouse_offset,
+                mouse_position,
+                frame_size,
+                drag_context.plane_kind,
+            );
+            let local_offset = scene.graph[self.vehicle]
+                .global_transform()
+                .try_inverse()
+                .unwrap_or_default()
+                .transform_vector(&global_offset);
+
+            set_wheel_position(
+                self.vehicle,
+                scene,
+                drag_context.wheel,
+                position + local_offset,
+            );
+        }
+    }
+
+    fn update(
+        &mut self,
+        _editor_selection: &Selection,
+        controller: &mut dyn SceneController,
+        engine: &mut Engine,
+        _settings: &Settings,
+    ) {
+        let game_scene = some_or_return!(controller.downcast_mut::<GameScene>());
+        let scene = &mut engine.scenes[game_scene.scene];
+
+        let positions = some_or_return!(wheel_positions(self.vehicle, scene));
+        if positions.len() != self.wheel_handles.len() {
+            for handle in self.wheel_handles.drain(..) {
+                scene.graph.remove_node(handle);
+            }
+            for _ in 0..positions.len() {
+                let handle = make_handle(scene, game_scene.editor_objects_root, true);
+                self.wheel_handles.push(handle);
+            }
+            if self.selected_wheel.is_some_and(|i| i >= positions.len()) {
+                self.selected_wheel = None;
+            }
+        }
+
+        let transform = scene.graph[self.vehicle].global_transform();
+        for (handle, position) in self.wheel_handles.iter().zip(positions.iter()) {
+            let scale = calculate_gizmo_distance_scaling(
+                &scene.graph,
+                game_scene.camera_controller.camera,
+                *handle,
+            );
+            let sprite = &mut scene.graph[*handle];
+            sprite
+                .local_transform_mut()
+                .set_position(transform.transform_point(&(*position).into()).coords)
+                .set_scale(scale);
+            sprite.set_size(0.05 * scale.x);
+        }
+
+        self.move_gizmo
+            .set_visible(&mut scene.graph, self.selected_wheel.is_some());
+        if let Some(position) = self.selected_wheel.and_then(|i| positions.get(i)) {
+            let scale = calculate_gizmo_distance_scaling(
+                &scene.graph,
+                game_scene.camera_controller.camera,
+                self.move_gizmo.origin,
+            );
+            self.move_gizmo
+                .transform(&mut scene.graph)
+                .set_position(transform.transform_point(&(*position).into()).coords)
+                .set_scale(scale);
+        }
+    }
+
+    fn activate(&mut self, controller: &dyn SceneController, engine: &mut Engine) {
+        self.set_visible(controller, engine, true);
+    }
+
+    fn deactivate(&mut self, controller: &dyn SceneController, engine: &mut Engine) {
+        self.set_visible(controller, engine, false);
+    }
+
+    fn make_button(&mut self, ctx: &mut BuildContext, selected: bool) -> Handle<Button> {
+        make_interaction_mode_button(
+            ctx,
+            include_bytes!("../../resources/triangle.png"),
+            "Edit Vehicle Wheels",
+            selected,
+        )
+    }
+
+    fn uuid(&self) -> Uuid {
+        <Self as Reflect>::type_info().type_uuid
+    }
+}
+
+#[derive(Default)]
+pub struct VehiclePlugin;
+
+impl EditorPlugin for VehiclePlugin {
+    fn on_message(&mut self, message: &Message, editor: &mut Editor) {
+        if !matches!(message, Message::SelectionChanged { .. }) {
+            return;
+        }
+
+        let entry = editor.scenes.current_scene_entry_mut();
+        let game_scene = some_or_return!(entry.controller.downcast_mut::<GameScene>());
+        let scene = &mut editor.engine.scenes[game_scene.scene];
+
+        if let Some(mode) = entry
+            .interaction_modes
+            .remove_typed::<VehicleInteractionMode>()
+        {
+            mode.destroy(scene);
+        }
+
+        let selected_vehicle = entry.selection.as_graph().and_then(|s| {
+            s.nodes()
+                .iter()
+                .find(|h| {
+                    scene.graph.is_or_has_field::<Vehicle>(**h)
+                        || scene.graph.is_or_has_field::<dim2::vehicle::Vehicle>(**h)
+                })
+                .cloned()
+        });
+
+        if let Some(selected_vehicle) = selected_vehicle {
+            entry.interaction_modes.add(VehicleInteractionMode {
+                vehicle: selected_vehicle,
+                wheel_handles: Default::default(),
+                selected_wheel: None,
+                move_gizmo: MoveGizmo::new(game_scene, &mut editor.engine),
+                message_sender: editor.message_sender.clone(),
+                drag_context: None,
+            });
+        }
+    }
+}
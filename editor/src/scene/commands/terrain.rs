@@ -250,3 +250,49 @@ impl CommandTrait for ModifyTerrainLayerMaskCommand {
         self.swap(context);
     }
 }
+
+#[derive(Debug)]
+pub struct ModifyTerrainFoliageMaskCommand {
+    terrain: Handle<Node>,
+    masks: Vec<ChunkData>,
+    layer: usize,
+    skip_first_execute: bool,
+}
+
+impl ModifyTerrainFoliageMaskCommand {
+    pub fn new(terrain: Handle<Node>, masks: Vec<ChunkData>, layer: usize) -> Self {
+        Self {
+            terrain,
+            masks,
+            layer,
+            skip_first_execute: true,
+        }
+    }
+
+    pub fn swap(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<GameSceneContext>();
+        let terrain = context.scene.graph[self.terrain].as_terrain_mut();
+        let current_chunks = terrain.chunks_mut();
+        for c in self.masks.iter_mut() {
+            c.swap_foliage_mask_from_list(current_chunks, self.layer);
+        }
+    }
+}
+
+impl CommandTrait for ModifyTerrainFoliageMaskCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Modify Terrain Foliage Mask".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        if self.skip_first_execute {
+            self.skip_first_execute = false;
+            return;
+        }
+        self.swap(context);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        self.swap(context);
+    }
+}
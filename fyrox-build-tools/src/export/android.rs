@@ -20,7 +20,7 @@
 
 use crate::export::{asset, utils, ExportOptions};
 use cargo_metadata::{camino::Utf8Path, Metadata, Package};
-use fyrox_core::{log::Log, platform::TargetPlatform};
+use fyrox_core::log::Log;
 use fyrox_resource::manager::ResourceManager;
 use std::{
     ffi::OsStr,
@@ -53,12 +53,19 @@ pub fn copy_binaries(
     metadata: &Metadata,
     package_name: &str,
     destination_folder: &Path,
+    enable_optimization: bool,
 ) -> Result<(), String> {
     Log::info("Trying to copy the apk...");
 
     let mut binary_paths = vec![];
-    for entry in fs::read_dir(metadata.target_directory.join("release/apk"))
-        .unwrap()
+    let profile_dir = if enable_optimization {
+        "release/apk"
+    } else {
+        "debug/apk"
+    };
+    let binaries_dir = metadata.target_directory.join(profile_dir);
+    for entry in fs::read_dir(&binaries_dir)
+        .map_err(|err| format!("Unable to read {binaries_dir}. Reason: {err}"))?
         .flatten()
     {
         if let Ok(file_metadata) = entry.metadata() {
@@ -132,7 +139,7 @@ pub fn copy_assets(
     temp_folders: &mut Vec<PathBuf>,
     resource_manager: &ResourceManager,
     convert: bool,
-) -> Result<(), String> {
+) -> Result<asset::CookingReport, String> {
     // Asset management on Android is quite annoying, because all other target platforms
     // uses the workspace manifest path as a root directory and all paths in code/assets
     // stored relatively to it. On Android, however, all your assets must be in unified
@@ -168,24 +175,12 @@ pub fn copy_assets(
 
         temp_folders.push(temp_assets_storage.clone());
 
-        for folder in &export_options.assets_folders {
-            Log::info(format!(
-                "Trying to copy assets from {} to {}...",
-                folder.display(),
-                temp_assets_storage.display()
-            ));
-
-            Log::verify(asset::copy_and_convert_assets(
-                folder,
-                temp_assets_storage.join(folder),
-                TargetPlatform::Android,
-                &|_| true,
-                resource_manager,
-                convert,
-            ));
-        }
-
-        Ok(())
+        Ok(asset::export_assets(
+            export_options,
+            &temp_assets_storage,
+            convert,
+            resource_manager,
+        ))
     } else {
         Err("Android executor must specify assets folder in \
                     [package.metadata.android] section"
@@ -20,13 +20,182 @@
 
 //! Asset processing module.
 
-use crate::export::utils;
+use crate::export::{cache::AssetCache, utils, ExportOptions};
 use fyrox_core::{
-    futures::executor, futures::future::join_all, log::Log, platform::TargetPlatform, SafeLock,
+    futures::executor, log::Log, parking_lot::Mutex, platform::TargetPlatform, SafeLock,
 };
-use fyrox_resource::manager::ResourceManager;
-use std::{fs, io, path::Path};
+use fyrox_resource::{graph::ResourceDependencyGraph, manager::ResourceManager};
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// An asset that failed to be converted.
+#[derive(Clone, Debug, Serialize)]
+pub struct CookingFailure {
+    /// Path of the source asset.
+    pub path: PathBuf,
+    /// The reason of the failure.
+    pub reason: String,
+}
+
+/// Summary of asset processing. Failures are sorted by path, so the report does not depend on the
+/// order in which the assets were processed.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CookingReport {
+    /// Total amount of the assets that were processed by resource loaders.
+    pub total: usize,
+    /// Amount of the assets that were converted.
+    pub converted: usize,
+    /// Amount of the assets that were restored from the asset cache.
+    pub reused: usize,
+    /// A list of the assets that failed to be converted or copied.
+    pub failed: Vec<CookingFailure>,
+}
+
+impl CookingReport {
+    fn merge(&mut self, other: CookingReport) {
+        self.total += other.total;
+        self.converted += other.converted;
+        self.reused += other.reused;
+        self.failed.extend(other.failed);
+        self.failed.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+}
+
+/// A set of assets that are reachable from a set of root assets (usually scenes). See
+/// [`UsedAssets::collect`] docs for more info.
+#[derive(Default, Debug)]
+pub struct UsedAssets {
+    paths: HashSet<PathBuf>,
+}
+
+impl UsedAssets {
+    /// Loads every root asset and walks its dependency graph to collect every asset used by it
+    /// (directly or indirectly).
+    pub fn collect(roots: &[PathBuf], resource_manager: &ResourceManager) -> Self {
+        let mut paths = HashSet::new();
+        for root in roots {
+            let resource = resource_manager.request_untyped(root);
+            match executor::block_on(resource) {
+                Ok(resource) => ResourceDependencyGraph::new(&resource).for_each(|dependency| {
+                    if let Some(path) = resource_manager
+                        .resource_path(dependency)
+                        .and_then(|path| path.canonicalize().ok())
+                    {
+                        paths.insert(path);
+                    }
+                }),
+                Err(err) => Log::err(format!(
+                    "Unable to load {} to collect its dependencies. Reason: {err}",
+                    root.display()
+                )),
+            }
+        }
+        Self { paths }
+    }
+
+    /// Returns the amount of the used assets.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Returns `true` if there's no used assets, `false` - otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Checks whether the given file must be included in the final build. Files that are not
+    /// resources (configs, registry, etc.) are always included, because there's no way to tell if
+    /// they're used or not. Sidecar files (import options and metadata) follow their resources.
+    pub fn is_needed(&self, path: &Path, resource_manager: &ResourceManager) -> bool {
+        if path.is_dir() {
+            return true;
+        }
+        let resource_path = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("options" | "meta") => path.with_extension(""),
+            _ => path.to_path_buf(),
+        };
+        if !resource_manager.is_supported_resource(&resource_path) {
+            return true;
+        }
+        resource_path
+            .canonicalize()
+            .is_ok_and(|path| self.paths.contains(&path))
+    }
+}
+
+/// Recursively searches for the files with the given extension in the given folder.
+pub fn find_files_with_extension(folder: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Ok(entries) = fs::read_dir(folder) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                files.extend(find_files_with_extension(&path, extension));
+            } else if path.extension().is_some_and(|ext| ext == extension) {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
 
+struct CookingJob {
+    src: PathBuf,
+    dst: PathBuf,
+}
+
+/// Converts a single asset or restores it from the cache. Returns `true` if the cached version was
+/// used.
+fn cook(
+    job: &CookingJob,
+    target_platform: TargetPlatform,
+    resource_manager: &ResourceManager,
+    cache: Option<&AssetCache>,
+) -> Result<bool, String> {
+    let key = match cache {
+        Some(cache) => {
+            let key = cache
+                .key(&job.src, target_platform)
+                .map_err(|err| err.to_string())?;
+            if cache
+                .restore(key, &job.dst)
+                .map_err(|err| err.to_string())?
+            {
+                return Ok(true);
+            }
+            Some(key)
+        }
+        None => None,
+    };
+
+    // Conversion futures are not `Send`, so each one is created and polled on the worker thread.
+    let future = {
+        let state = resource_manager.state();
+        let io = state.resource_io.clone();
+        let loaders = state.loaders.safe_lock();
+        let loader = loaders
+            .loader_for(&job.src)
+            .ok_or_else(|| format!("There's no loader for {}", job.src.display()))?;
+        loader.convert(job.src.clone(), job.dst.clone(), target_platform, io)
+    };
+    executor::block_on(future).map_err(|err| err.to_string())?;
+
+    if let (Some(cache), Some(key)) = (cache, key) {
+        cache.store(key, &job.dst).map_err(|err| err.to_string())?;
+    }
+
+    Ok(false)
+}
+
+/// Copies the assets from the source folder to the destination folder. If `convert` is `true`,
+/// every supported resource is converted to its "shipping" version for the given platform. The
+/// conversion runs in parallel on all available cores; converted assets are put into the given
+/// cache (if any), so unchanged assets are not converted again on next export.
 pub fn copy_and_convert_assets(
     src_folder: impl AsRef<Path>,
     dst_folder: impl AsRef<Path>,
@@ -34,41 +203,270 @@ pub fn copy_and_convert_assets(
     filter: &dyn Fn(&Path) -> bool,
     resource_manager: &ResourceManager,
     convert: bool,
-) -> io::Result<()> {
+    cache: Option<&AssetCache>,
+) -> io::Result<CookingReport> {
     if convert {
-        let rm = resource_manager.state();
-        let io = rm.resource_io.clone();
-        let loaders = rm.loaders.safe_lock();
-        let mut tasks = Vec::new();
+        let mut jobs = Vec::new();
 
-        // Iterate over the file system and try to convert all the supported resources.
+        // Copy everything that cannot be converted right away and collect the rest.
         utils::copy_dir_ex(
             src_folder,
             dst_folder,
             &filter,
             &mut |src_file, dst_file| {
-                if let Some(loader) = loaders.loader_for(src_file) {
-                    tasks.push(loader.convert(
-                        src_file.to_path_buf(),
-                        dst_file.to_path_buf(),
-                        target_platform,
-                        io.clone(),
-                    ));
-                    Ok(())
+                if resource_manager.is_supported_resource(src_file) {
+                    jobs.push(CookingJob {
+                        src: src_file.to_path_buf(),
+                        dst: dst_file.to_path_buf(),
+                    });
                 } else {
                     fs::copy(src_file, dst_file)?;
-                    Ok(())
                 }
+                Ok(())
             },
         )?;
 
-        // Wait until everything is converted and copied.
-        for result in executor::block_on(join_all(tasks)) {
-            Log::verify(result);
+        let total = jobs.len();
+        let next = AtomicUsize::new(0);
+        let processed = AtomicUsize::new(0);
+        let reused = AtomicUsize::new(0);
+        let failed = Mutex::new(Vec::new());
+        let worker_count = std::thread::available_parallelism()
+            .map(|count| count.get())
+            .unwrap_or(1)
+            .min(total.max(1));
+
+        std::thread::scope(|scope| {
+            for _ in 0..worker_count {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(job) = jobs.get(index) else {
+                        break;
+                    };
+                    let result = cook(job, target_platform, resource_manager, cache);
+                    let done = processed.fetch_add(1, Ordering::Relaxed) + 1;
+                    match result {
+                        Ok(from_cache) => {
+                            if from_cache {
+                                reused.fetch_add(1, Ordering::Relaxed);
+                            }
+                            Log::info(format!(
+                                "[{done}/{total}] {} {}",
+                                if from_cache { "Reused" } else { "Converted" },
+                                job.src.display()
+                            ))
+                        }
+                        Err(err) => {
+                            Log::err(format!(
+                                "[{done}/{total}] Unable to convert {}. Reason: {err}",
+                                job.src.display()
+                            ));
+                            failed.safe_lock().push(CookingFailure {
+                                path: job.src.clone(),
+                                reason: err,
+                            });
+                        }
+                    }
+                });
+            }
+        });
+
+        let reused = reused.into_inner();
+        let mut failed = failed.into_inner();
+        failed.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Log::info(format!(
+            "{total} assets processed, {reused} of them reused from the cache."
+        ));
+
+        Ok(CookingReport {
+            total,
+            converted: total - reused - failed.len(),
+            reused,
+            failed,
+        })
+    } else {
+        utils::copy_dir(src_folder, dst_folder, &filter)?;
+        Ok(Default::default())
+    }
+}
+
+/// Copies (and converts, if `convert` is `true`) all the asset folders specified in the export
+/// options to the given destination folder. If the options require to include only used assets,
+/// then every resource that is not reachable from the exported scenes is skipped.
+pub fn export_assets(
+    export_options: &ExportOptions,
+    destination: &Path,
+    convert: bool,
+    resource_manager: &ResourceManager,
+) -> CookingReport {
+    let mut report = CookingReport::default();
+
+    let used_assets = export_options.include_used_assets.then(|| {
+        let mut scenes = export_options.scenes.clone();
+        if scenes.is_empty() {
+            for folder in export_options.assets_folders.iter() {
+                scenes.extend(find_files_with_extension(folder, "rgs"));
+            }
         }
+        Log::info(format!(
+            "Trying to collect assets used by {} scene(s)...",
+            scenes.len()
+        ));
+        let used_assets = UsedAssets::collect(&scenes, resource_manager);
+        Log::info(format!("{} used assets found.", used_assets.len()));
+        used_assets
+    });
 
-        Ok(())
+    let cache = if convert && export_options.use_asset_cache {
+        match AssetCache::new(&export_options.asset_cache_folder) {
+            Ok(cache) => Some(cache),
+            Err(err) => {
+                Log::err(format!(
+                    "Unable to create asset cache at {}. Reason: {err}",
+                    export_options.asset_cache_folder.display()
+                ));
+                None
+            }
+        }
     } else {
-        utils::copy_dir(src_folder, dst_folder, &filter)
+        None
+    };
+
+    let filter = |path: &Path| {
+        used_assets
+            .as_ref()
+            .is_none_or(|used_assets| used_assets.is_needed(path, resource_manager))
+    };
+
+    for folder in export_options.assets_folders.iter() {
+        Log::info(format!(
+            "Trying to copy assets from {} to {}...",
+            folder.display(),
+            destination.display()
+        ));
+
+        match copy_and_convert_assets(
+            folder,
+            destination.join(folder),
+            export_options.target_platform,
+            &filter,
+            resource_manager,
+            convert,
+            cache.as_ref(),
+        ) {
+            Ok(folder_report) => report.merge(folder_report),
+            Err(err) => {
+                Log::err(format!(
+                    "Unable to copy assets from {}. Reason: {err}",
+                    folder.display()
+                ));
+                report.merge(CookingReport {
+                    failed: vec![CookingFailure {
+                        path: folder.clone(),
+                        reason: err.to_string(),
+                    }],
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::clean_folder;
+    use fyrox_core::{task::TaskPool, uuid::Uuid};
+    use fyrox_resource::{
+        io::{FsResourceIo, ResourceIo},
+        loader::{BoxedLoaderFuture, ResourceLoader},
+        state::LoadError,
+    };
+    use std::{future::Future, pin::Pin, sync::Arc};
+
+    /// "Converts" text files to upper case and counts the conversions.
+    struct UpperCaseLoader {
+        conversions: Arc<AtomicUsize>,
+    }
+
+    impl ResourceLoader for UpperCaseLoader {
+        fn extensions(&self) -> &[&str] {
+            &["txt"]
+        }
+
+        fn data_type_uuid(&self) -> Uuid {
+            Uuid::nil()
+        }
+
+        fn load(&self, _path: PathBuf, _io: Arc<dyn ResourceIo>) -> BoxedLoaderFuture {
+            Box::pin(async move { Err(LoadError::new("Loading is not supported!")) })
+        }
+
+        fn convert(
+            &self,
+            src_path: PathBuf,
+            dest_path: PathBuf,
+            _platform: TargetPlatform,
+            _io: Arc<dyn ResourceIo>,
+        ) -> Pin<Box<dyn Future<Output = Result<(), fyrox_core::io::FileError>>>> {
+            self.conversions.fetch_add(1, Ordering::Relaxed);
+            Box::pin(async move {
+                let content = fs::read_to_string(src_path)?;
+                fs::write(dest_path, content.to_uppercase())?;
+                Ok(())
+            })
+        }
+    }
+
+    #[test]
+    fn test_cache_miss_falls_back_to_conversion() {
+        let folder = clean_folder("test_output/convert_assets");
+        let src = folder.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("asset.txt"), "text").unwrap();
+        fs::write(src.join("data.bin"), "binary").unwrap();
+
+        let conversions = Arc::new(AtomicUsize::new(0));
+        let resource_manager =
+            ResourceManager::new(Arc::new(FsResourceIo), Arc::new(TaskPool::new()));
+        resource_manager.add_loader(UpperCaseLoader {
+            conversions: conversions.clone(),
+        });
+        let cache = AssetCache::new(folder.join("cache")).unwrap();
+
+        let export = |dst: &str| {
+            let dst = folder.join(dst);
+            let report = copy_and_convert_assets(
+                &src,
+                &dst,
+                TargetPlatform::PC,
+                &|_| true,
+                &resource_manager,
+                true,
+                Some(&cache),
+            )
+            .unwrap();
+            assert!(report.failed.is_empty());
+            assert_eq!(fs::read_to_string(dst.join("asset.txt")).unwrap(), "TEXT");
+            assert_eq!(fs::read_to_string(dst.join("data.bin")).unwrap(), "binary");
+            (report.converted, report.reused)
+        };
+
+        // Empty cache, the asset is converted.
+        assert_eq!(export("dst1"), (1, 0));
+        assert_eq!(conversions.load(Ordering::Relaxed), 1);
+
+        // The asset is restored from the cache.
+        assert_eq!(export("dst2"), (0, 1));
+        assert_eq!(conversions.load(Ordering::Relaxed), 1);
+
+        // The cached entry is lost, the asset is converted again.
+        fs::remove_dir_all(cache.folder()).unwrap();
+        fs::create_dir_all(cache.folder()).unwrap();
+        assert_eq!(export("dst3"), (1, 0));
+        assert_eq!(conversions.load(Ordering::Relaxed), 2);
     }
 }
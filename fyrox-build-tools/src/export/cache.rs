@@ -0,0 +1,230 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Derived data cache for exported assets. See [`AssetCache`] docs for more info.
+
+use fyrox_core::{append_extension, platform::TargetPlatform};
+use fyrox_resource::options::OPTIONS_EXTENSION;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use twox_hash::XxHash3_128;
+
+/// Version of the cache layout. Must be increased every time when the format of converted assets
+/// changes, so the stale data will not be reused.
+const CACHE_VERSION: u32 = 1;
+
+/// Content-addressed storage for converted ("cooked") assets. Every converted asset is stored
+/// in the cache folder under a key, that is calculated from the content of the source file, its
+/// import options and the target platform. When the same asset is exported again and neither of
+/// these was changed, the converted version is copied from the cache instead of running the
+/// conversion again.
+pub struct AssetCache {
+    folder: PathBuf,
+}
+
+/// A key of a converted asset in the [`AssetCache`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AssetCacheKey(u128);
+
+impl AssetCache {
+    /// Creates a new cache that uses the given folder as storage. The folder will be created if
+    /// it does not exist.
+    pub fn new(folder: impl AsRef<Path>) -> io::Result<Self> {
+        fs::create_dir_all(folder.as_ref())?;
+        Ok(Self {
+            folder: folder.as_ref().to_path_buf(),
+        })
+    }
+
+    /// Returns the folder used as storage by the cache.
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Calculates a cache key for the given source asset. The key depends on the content of the
+    /// asset, the content of its import options (if any) and the target platform.
+    pub fn key(&self, src_path: &Path, platform: TargetPlatform) -> io::Result<AssetCacheKey> {
+        Self::key_with_version(src_path, platform, CACHE_VERSION)
+    }
+
+    fn key_with_version(
+        src_path: &Path,
+        platform: TargetPlatform,
+        cache_version: u32,
+    ) -> io::Result<AssetCacheKey> {
+        let mut hasher = XxHash3_128::new();
+        hasher.write(&cache_version.to_le_bytes());
+        hasher.write(env!("CARGO_PKG_VERSION").as_bytes());
+        hasher.write(format!("{platform:?}").as_bytes());
+        if let Some(extension) = src_path.extension() {
+            hasher.write(extension.as_encoded_bytes());
+        }
+        hasher.write(&fs::read(src_path)?);
+        if let Ok(options) = fs::read(append_extension(src_path, OPTIONS_EXTENSION)) {
+            hasher.write(&options);
+        }
+        Ok(AssetCacheKey(hasher.finish_128()))
+    }
+
+    fn entry_path(&self, key: AssetCacheKey) -> PathBuf {
+        self.folder.join(format!("{:032x}", key.0))
+    }
+
+    /// Copies the file to a unique temporary file in the cache folder first and then moves it to
+    /// the destination path. Renaming is atomic, so the destination path either does not exist or
+    /// has the full content, even if the export was interrupted or another thread is storing the
+    /// same entry at the same time.
+    fn copy_atomically(&self, src_path: &Path, dst_path: &Path) -> io::Result<()> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let temp_path = self.folder.join(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let result = fs::copy(src_path, &temp_path).and_then(|_| fs::rename(&temp_path, dst_path));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    /// Tries to copy a converted asset with the given key to the destination path. The import
+    /// options of the converted asset (if any) are copied as well. Returns `false` if there's no
+    /// such asset in the cache.
+    pub fn restore(&self, key: AssetCacheKey, dst_path: &Path) -> io::Result<bool> {
+        let entry_path = self.entry_path(key);
+        if !entry_path.exists() {
+            return Ok(false);
+        }
+        fs::copy(&entry_path, dst_path)?;
+        let entry_options_path = append_extension(&entry_path, OPTIONS_EXTENSION);
+        if entry_options_path.exists() {
+            fs::copy(
+                entry_options_path,
+                append_extension(dst_path, OPTIONS_EXTENSION),
+            )?;
+        }
+        Ok(true)
+    }
+
+    /// Puts a converted asset located at the given path into the cache under the given key. The
+    /// import options of the converted asset (if any) are stored as well.
+    pub fn store(&self, key: AssetCacheKey, converted_path: &Path) -> io::Result<()> {
+        let entry_path = self.entry_path(key);
+        let converted_options_path = append_extension(converted_path, OPTIONS_EXTENSION);
+        if converted_options_path.exists() {
+            self.copy_atomically(
+                &converted_options_path,
+                &append_extension(&entry_path, OPTIONS_EXTENSION),
+            )?;
+        }
+        // The asset itself is stored last, because its existence marks the entry as complete.
+        self.copy_atomically(converted_path, &entry_path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::clean_folder;
+
+    #[test]
+    fn test_key_depends_on_inputs() {
+        let folder = clean_folder("test_output/cache_key");
+        let cache = AssetCache::new(folder.join("cache")).unwrap();
+        let path = folder.join("asset.txt");
+        fs::write(&path, "content").unwrap();
+
+        let key = cache.key(&path, TargetPlatform::PC).unwrap();
+        assert_eq!(cache.key(&path, TargetPlatform::PC).unwrap(), key);
+
+        let mut keys = vec![key];
+        let mut check_unique = |new_key: AssetCacheKey| {
+            assert!(!keys.contains(&new_key));
+            keys.push(new_key);
+        };
+
+        check_unique(
+            AssetCache::key_with_version(&path, TargetPlatform::PC, CACHE_VERSION + 1).unwrap(),
+        );
+        check_unique(cache.key(&path, TargetPlatform::Android).unwrap());
+
+        let other_extension = folder.join("asset.bin");
+        fs::write(&other_extension, "content").unwrap();
+        check_unique(cache.key(&other_extension, TargetPlatform::PC).unwrap());
+
+        fs::write(&path, "new content").unwrap();
+        check_unique(cache.key(&path, TargetPlatform::PC).unwrap());
+
+        let options_path = append_extension(&path, OPTIONS_EXTENSION);
+        fs::write(&options_path, "(compression: NoCompression)").unwrap();
+        check_unique(cache.key(&path, TargetPlatform::PC).unwrap());
+        fs::write(&options_path, "(compression: Quality)").unwrap();
+        check_unique(cache.key(&path, TargetPlatform::PC).unwrap());
+
+        assert!(cache
+            .key(&folder.join("missing.txt"), TargetPlatform::PC)
+            .is_err());
+    }
+
+    #[test]
+    fn test_store_and_restore() {
+        let folder = clean_folder("test_output/cache_store");
+        let cache = AssetCache::new(folder.join("cache")).unwrap();
+        let source = folder.join("source.txt");
+        fs::write(&source, "source").unwrap();
+        let key = cache.key(&source, TargetPlatform::PC).unwrap();
+
+        let restored = folder.join("restored.txt");
+        assert!(!cache.restore(key, &restored).unwrap());
+        assert!(!restored.exists());
+
+        let converted = folder.join("converted.txt");
+        fs::write(&converted, "converted").unwrap();
+        fs::write(append_extension(&converted, OPTIONS_EXTENSION), "options").unwrap();
+        cache.store(key, &converted).unwrap();
+        // Storing the same entry again replaces it.
+        cache.store(key, &converted).unwrap();
+
+        // No temporary files are left behind.
+        let mut entries = fs::read_dir(cache.folder())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        entries.sort();
+        let entry_name = format!("{:032x}", key.0);
+        assert_eq!(
+            entries,
+            [
+                entry_name.clone(),
+                format!("{entry_name}.{OPTIONS_EXTENSION}")
+            ]
+        );
+
+        assert!(cache.restore(key, &restored).unwrap());
+        assert_eq!(fs::read_to_string(&restored).unwrap(), "converted");
+        assert_eq!(
+            fs::read_to_string(append_extension(&restored, OPTIONS_EXTENSION)).unwrap(),
+            "options"
+        );
+    }
+}
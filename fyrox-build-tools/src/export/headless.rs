@@ -0,0 +1,657 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Headless (command-line) project export, that is suitable for build farms and CI/CD. See
+//! [`headless_export`] docs for more info.
+
+use crate::export::{
+    asset::CookingReport, build_package, copy_assets, copy_binaries, executor_package_name,
+    find_executor_package, utils, ExportOptions, TargetPlatform,
+};
+use clap::Parser;
+use fyrox_core::{futures::executor::block_on, log::Log};
+use fyrox_resource::{manager::ResourceManager, validation::ResourceReferenceReport};
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+use twox_hash::XxHash3_128;
+
+/// Name of the package manifest file, that is written to the output folder.
+pub const PACKAGE_MANIFEST_FILE_NAME: &str = "package-manifest.json";
+
+/// Maximum amount of time to wait until the resource registry is loaded.
+const REGISTRY_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct HeadlessExportArgs {
+    /// Path to the root folder of the project (the one with the workspace manifest). All the other
+    /// paths are relative to this folder.
+    #[clap(long, default_value = ".")]
+    pub project: PathBuf,
+
+    /// The target platform to build the game to. Must be one of: pc, android, wasm.
+    #[clap(long, default_value = "pc")]
+    pub target_platform: String,
+
+    /// The name of the build target. See `cargo run -p export-cli -- --help` for more info.
+    #[clap(long, default_value = "default")]
+    pub build_target: String,
+
+    /// Build profile. Must be one of: dev, release.
+    #[clap(long, default_value = "release")]
+    pub profile: String,
+
+    /// The output folder for the package. Its previous content will be erased.
+    #[clap(long, default_value = "./build/")]
+    pub output: PathBuf,
+
+    /// Asset folders, that will be included in the package.
+    #[clap(long, default_value = "./data/")]
+    pub assets_folders: Vec<PathBuf>,
+
+    /// If specified, only the assets, that are reachable from the exported scenes will be included
+    /// in the package.
+    #[clap(long, default_value = "false")]
+    pub include_used_assets: bool,
+
+    /// A set of scenes that are used as roots to search for used assets. If empty, all the scenes
+    /// from the assets folders are used.
+    #[clap(long)]
+    pub scenes: Vec<PathBuf>,
+
+    /// If specified, the asset cache will not be used and all the assets will be converted.
+    #[clap(long, default_value = "false")]
+    pub no_asset_cache: bool,
+
+    /// A folder, that is used to store converted assets between exports.
+    #[clap(long, default_value = "./target/asset_cache/")]
+    pub asset_cache_folder: PathBuf,
+
+    /// If specified, only the assets will be cooked and the game will not be built.
+    #[clap(long, default_value = "false")]
+    pub skip_build: bool,
+
+    /// Path to the machine-readable (JSON) export report.
+    #[clap(long, default_value = "./export-report.json")]
+    pub report: PathBuf,
+}
+
+/// A stage of the headless export.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportStage {
+    /// Validation of the command line arguments, the project layout and the resource references.
+    Validation,
+    /// Asset conversion.
+    Cooking,
+    /// Compilation of the game.
+    Build,
+    /// Copying of the binaries and writing the package manifest.
+    Packaging,
+}
+
+impl ExportStage {
+    /// Returns the process exit code, that is used when the stage fails.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ExportStage::Validation => 2,
+            ExportStage::Cooking => 3,
+            ExportStage::Build => 4,
+            ExportStage::Packaging => 5,
+        }
+    }
+}
+
+/// A file of the exported package.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PackageEntry {
+    /// Path of the file relative to the output folder, always with `/` separators.
+    pub path: String,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// Content hash of the file.
+    pub hash: String,
+}
+
+/// Machine-readable report of the headless export. The report does not contain any timings or
+/// absolute paths, so two exports of the same project produce the same reports.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ExportReport {
+    /// `true` if the export was successful.
+    pub success: bool,
+    /// Exit code of the export process.
+    pub exit_code: u8,
+    /// A stage at which the export has failed.
+    pub failed_stage: Option<ExportStage>,
+    /// A list of errors.
+    pub errors: Vec<String>,
+    /// Target platform of the export.
+    pub target_platform: String,
+    /// Build profile.
+    pub profile: String,
+    /// Asset processing summary.
+    pub assets: CookingReport,
+    /// Sorted list of the files of the package.
+    pub package: Vec<PackageEntry>,
+    /// Combined hash of all the files of the package.
+    pub package_hash: String,
+}
+
+impl ExportReport {
+    fn fail(&mut self, stage: ExportStage, errors: impl IntoIterator<Item = String>) {
+        self.success = false;
+        self.failed_stage = Some(stage);
+        self.exit_code = stage.exit_code();
+        for error in errors {
+            Log::err(format!("Export failed at {stage:?} stage: {error}"));
+            self.errors.push(error);
+        }
+    }
+}
+
+fn hash_to_string(hash: u128) -> String {
+    format!("{hash:032x}")
+}
+
+fn collect_package_entries(
+    root: &Path,
+    folder: &Path,
+    entries: &mut Vec<PackageEntry>,
+) -> std::io::Result<()> {
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_package_entries(root, &path, entries)?;
+        } else {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let path_string = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if path_string == PACKAGE_MANIFEST_FILE_NAME {
+                continue;
+            }
+            let data = fs::read(&path)?;
+            entries.push(PackageEntry {
+                path: path_string,
+                size: data.len() as u64,
+                hash: hash_to_string(XxHash3_128::oneshot(&data)),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Collects all the files of the package in the given folder and calculates their hashes. The
+/// entries are sorted by their paths, which makes the manifest independent of the file system.
+/// Returns the entries and the combined hash of the package.
+pub fn make_package_manifest(folder: &Path) -> std::io::Result<(Vec<PackageEntry>, String)> {
+    let mut entries = Vec::new();
+    collect_package_entries(folder, folder, &mut entries)?;
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut hasher = XxHash3_128::new();
+    for entry in entries.iter() {
+        hasher.write(entry.path.as_bytes());
+        hasher.write(&entry.size.to_le_bytes());
+        hasher.write(entry.hash.as_bytes());
+    }
+
+    Ok((entries, hash_to_string(hasher.finish_128())))
+}
+
+fn parse_target_platform(name: &str) -> Result<TargetPlatform, String> {
+    match name {
+        "android" => Ok(TargetPlatform::Android),
+        "pc" => Ok(TargetPlatform::PC),
+        "wasm" => Ok(TargetPlatform::WebAssembly),
+        _ => Err(format!(
+            "Unknown target platform {name}! Must be one of: pc, android, wasm."
+        )),
+    }
+}
+
+fn parse_profile(name: &str) -> Result<bool, String> {
+    match name {
+        "dev" => Ok(false),
+        "release" => Ok(true),
+        _ => Err(format!(
+            "Unknown build profile {name}! Must be one of: dev, release."
+        )),
+    }
+}
+
+fn wait_for_registry(resource_manager: &ResourceManager) -> Result<(), String> {
+    resource_manager.update_or_load_registry();
+    let start = Instant::now();
+    while !resource_manager.registry_is_loaded() {
+        if start.elapsed() > REGISTRY_TIMEOUT {
+            return Err("Timed out waiting for the resource registry.".to_string());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    Ok(())
+}
+
+fn reference_errors(report: &ResourceReferenceReport) -> Vec<String> {
+    report
+        .dangling
+        .iter()
+        .map(|reference| format!("Dangling resource reference. {reference}"))
+        .chain(report.failed.iter().map(|(path, err)| {
+            format!("Unable to load {} resource. Reason: {err}", path.display())
+        }))
+        .collect()
+}
+
+fn validate(
+    args: &HeadlessExportArgs,
+    report: &mut ExportReport,
+) -> Result<ExportOptions, Vec<String>> {
+    let mut errors = Vec::new();
+
+    let target_platform = parse_target_platform(&args.target_platform)
+        .map_err(|err| errors.push(err))
+        .unwrap_or_default();
+    let enable_optimization = parse_profile(&args.profile)
+        .map_err(|err| errors.push(err))
+        .unwrap_or(true);
+    report.target_platform = args.target_platform.clone();
+    report.profile = args.profile.clone();
+
+    if let Err(err) = std::env::set_current_dir(&args.project) {
+        errors.push(format!(
+            "Unable to open the project at {}. Reason: {err}",
+            args.project.display()
+        ));
+        return Err(errors);
+    }
+
+    if !Path::new("Cargo.toml").exists() {
+        errors.push(format!(
+            "{} does not contain a Cargo.toml manifest.",
+            args.project.display()
+        ));
+    }
+
+    if !args.skip_build {
+        match utils::read_metadata() {
+            Ok(metadata) => {
+                if let Err(err) = find_executor_package(&metadata, target_platform) {
+                    errors.push(err);
+                }
+            }
+            Err(err) => errors.push(err),
+        }
+    }
+
+    for folder in args.assets_folders.iter() {
+        if !folder.is_dir() {
+            errors.push(format!(
+                "Assets folder {} does not exist.",
+                folder.display()
+            ));
+        }
+    }
+
+    for scene in args.scenes.iter() {
+        if !scene.is_file() {
+            errors.push(format!("Scene {} does not exist.", scene.display()));
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(ExportOptions {
+        target_platform,
+        destination_folder: args.output.clone(),
+        include_used_assets: args.include_used_assets,
+        scenes: args.scenes.clone(),
+        assets_folders: args.assets_folders.clone(),
+        build_target: args.build_target.clone(),
+        run_after_build: false,
+        open_destination_folder: false,
+        convert_assets: true,
+        use_asset_cache: !args.no_asset_cache,
+        asset_cache_folder: args.asset_cache_folder.clone(),
+        enable_optimization,
+        ..Default::default()
+    })
+}
+
+fn run(
+    args: &HeadlessExportArgs,
+    resource_manager: impl FnOnce() -> ResourceManager,
+    report: &mut ExportReport,
+) {
+    let export_options = match validate(args, report) {
+        Ok(options) => options,
+        Err(errors) => return report.fail(ExportStage::Validation, errors),
+    };
+
+    let resource_manager = resource_manager();
+    if let Err(err) = wait_for_registry(&resource_manager) {
+        return report.fail(ExportStage::Validation, [err]);
+    }
+
+    let errors = reference_errors(&block_on(resource_manager.validate_references()));
+    if !errors.is_empty() {
+        return report.fail(ExportStage::Validation, errors);
+    }
+
+    if let Err(err) = utils::prepare_build_dir(&export_options.destination_folder) {
+        return report.fail(ExportStage::Packaging, [err]);
+    }
+
+    let metadata = if args.skip_build {
+        None
+    } else {
+        match utils::read_metadata() {
+            Ok(metadata) => Some(metadata),
+            Err(err) => return report.fail(ExportStage::Validation, [err]),
+        }
+    };
+    let package = metadata
+        .as_ref()
+        .and_then(|metadata| find_executor_package(metadata, export_options.target_platform).ok());
+
+    let mut temp_folders = Vec::new();
+    let cooking_result = match package {
+        Some(package) => copy_assets(
+            &export_options,
+            package,
+            &mut temp_folders,
+            &resource_manager,
+        ),
+        None => Ok(crate::export::asset::export_assets(
+            &export_options,
+            &export_options.destination_folder,
+            true,
+            &resource_manager,
+        )),
+    };
+
+    let stage_result = match cooking_result {
+        Ok(assets) => {
+            let failures = assets
+                .failed
+                .iter()
+                .map(|failure| format!("{}: {}", failure.path.display(), failure.reason))
+                .collect::<Vec<_>>();
+            report.assets = assets;
+            if failures.is_empty() {
+                Ok(())
+            } else {
+                Err((ExportStage::Cooking, failures))
+            }
+        }
+        Err(err) => Err((ExportStage::Cooking, vec![err])),
+    }
+    .and_then(|_| match (metadata.as_ref(), package) {
+        (Some(metadata), Some(package)) => {
+            build_package(
+                executor_package_name(export_options.target_platform),
+                &export_options.build_target,
+                package.manifest_path.as_path().parent().unwrap(),
+                export_options.target_platform,
+                Default::default(),
+                export_options.enable_optimization,
+            )
+            .map_err(|err| (ExportStage::Build, vec![err]))?;
+            copy_binaries(&export_options, metadata, package)
+                .map_err(|err| (ExportStage::Packaging, vec![err]))
+        }
+        _ => Ok(()),
+    });
+
+    for temp_folder in temp_folders {
+        Log::verify(fs::remove_dir_all(temp_folder));
+    }
+
+    if let Err((stage, errors)) = stage_result {
+        return report.fail(stage, errors);
+    }
+
+    let manifest =
+        make_package_manifest(&export_options.destination_folder).and_then(|(entries, hash)| {
+            let json = serde_json::to_string_pretty(&entries).map_err(std::io::Error::other)?;
+            fs::write(
+                export_options
+                    .destination_folder
+                    .join(PACKAGE_MANIFEST_FILE_NAME),
+                json,
+            )?;
+            Ok((entries, hash))
+        });
+    match manifest {
+        Ok((entries, hash)) => {
+            report.package = entries;
+            report.package_hash = hash;
+        }
+        Err(err) => {
+            return report.fail(
+                ExportStage::Packaging,
+                [format!(
+                    "Unable to write the package manifest. Reason: {err}"
+                )],
+            )
+        }
+    }
+
+    report.success = true;
+    report.exit_code = 0;
+}
+
+/// Exports the project without any user interaction. This function parses the command line
+/// arguments (run with `--help` to see them), validates the project, cooks the assets, builds the
+/// game and writes a package with a manifest (`package-manifest.json`) to the output folder. A
+/// machine-readable report is written to the path specified by `--report` argument. The returned
+/// exit code is zero on success, otherwise it is [`ExportStage::exit_code`] of the failed stage.
+///
+/// The validation checks the arguments, the presence of the project manifest and the executor
+/// package, and that the specified asset folders and scenes exist. Then it loads every native
+/// resource of the project and checks that all the resource references in them could be resolved
+/// (see [`fyrox_resource::validation::validate_references`]). Dangling references and resources,
+/// that failed to load, fail the export with the validation exit code.
+///
+/// The resource manager is created by the given closure after the working directory is changed
+/// to the project folder. The resource manager must have all the loaders and the serialization
+/// context of the game registered, so usually it is the resource manager of a headless executor
+/// with the game plugin added (`Executor::new(None)`).
+pub fn headless_export(resource_manager: impl FnOnce() -> ResourceManager) -> ExitCode {
+    let args = HeadlessExportArgs::parse();
+
+    let mut report = ExportReport::default();
+    run(&args, resource_manager, &mut report);
+
+    // The working directory could be changed to the project folder, and the report path is
+    // relative to it.
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => {
+            if let Err(err) = fs::write(&args.report, json) {
+                Log::err(format!(
+                    "Unable to write the export report to {}. Reason: {err}",
+                    args.report.display()
+                ));
+            }
+        }
+        Err(err) => Log::err(format!("Unable to serialize the export report. {err}")),
+    }
+
+    if report.success {
+        Log::info("The project was exported successfully.");
+    }
+
+    ExitCode::from(report.exit_code)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::export::clean_folder;
+    use fyrox_core::uuid::Uuid;
+    use fyrox_resource::validation::{DanglingReason, DanglingReference};
+
+    #[test]
+    fn test_parse_target_platform() {
+        assert_eq!(parse_target_platform("pc"), Ok(TargetPlatform::PC));
+        assert_eq!(
+            parse_target_platform("android"),
+            Ok(TargetPlatform::Android)
+        );
+        assert_eq!(
+            parse_target_platform("wasm"),
+            Ok(TargetPlatform::WebAssembly)
+        );
+        assert!(parse_target_platform("PC").is_err());
+        assert!(parse_target_platform("xbox").is_err());
+        assert!(parse_target_platform("").is_err());
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        assert_eq!(parse_profile("dev"), Ok(false));
+        assert_eq!(parse_profile("release"), Ok(true));
+        assert!(parse_profile("debug").is_err());
+        assert!(parse_profile("").is_err());
+    }
+
+    #[test]
+    fn test_invalid_arguments_fail_validation() {
+        let args = HeadlessExportArgs::parse_from([
+            "export",
+            "--target-platform",
+            "xbox",
+            "--profile",
+            "debug",
+            "--project",
+            "test_output/this_project_does_not_exist",
+        ]);
+        let mut report = ExportReport::default();
+        run(
+            &args,
+            || unreachable!("The resource manager must not be created on invalid input."),
+            &mut report,
+        );
+
+        assert!(!report.success);
+        assert_eq!(report.failed_stage, Some(ExportStage::Validation));
+        assert_eq!(report.exit_code, ExportStage::Validation.exit_code());
+        assert_ne!(report.exit_code, 0);
+        assert_eq!(report.errors.len(), 3);
+        assert!(report.errors[0].contains("xbox"));
+        assert!(report.errors[1].contains("debug"));
+    }
+
+    #[test]
+    fn test_reference_errors() {
+        assert!(reference_errors(&ResourceReferenceReport::default()).is_empty());
+
+        let missing = Uuid::new_v4();
+        let report = ResourceReferenceReport {
+            scanned: vec![PathBuf::from("data/level.rgs")],
+            dangling: vec![DanglingReference {
+                uuid: missing,
+                reason: DanglingReason::UnknownUuid,
+                owner: PathBuf::from("data/level.rgs"),
+                field_path: "background".to_string(),
+            }],
+            failed: vec![(PathBuf::from("data/broken.rgs"), "corrupted".to_string())],
+        };
+        let errors = reference_errors(&report);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains(&missing.to_string()));
+        assert!(errors[0].contains("background"));
+        assert!(errors[1].contains("data/broken.rgs"));
+
+        // Any reference error fails the validation stage.
+        let mut export_report = ExportReport::default();
+        export_report.fail(ExportStage::Validation, errors);
+        assert_eq!(export_report.exit_code, 2);
+    }
+
+    #[test]
+    fn test_exit_codes_are_non_zero_and_unique() {
+        let codes = [
+            ExportStage::Validation,
+            ExportStage::Cooking,
+            ExportStage::Build,
+            ExportStage::Packaging,
+        ]
+        .map(ExportStage::exit_code);
+        for (i, code) in codes.iter().enumerate() {
+            assert_ne!(*code, 0);
+            assert!(!codes[i + 1..].contains(code));
+        }
+    }
+
+    #[test]
+    fn test_package_manifest_is_deterministic() {
+        let files = [
+            ("b.bin", b"second".as_slice()),
+            ("data/a.txt", b"first".as_slice()),
+            ("data/nested/c.txt", b"third".as_slice()),
+        ];
+
+        // Write the same files in a different order to make sure that the manifest does not
+        // depend on the creation order or the order returned by the file system.
+        let first = clean_folder("test_output/manifest_a");
+        let second = clean_folder("test_output/manifest_b");
+        for (folder, files) in [
+            (&first, files.iter().collect::<Vec<_>>()),
+            (&second, files.iter().rev().collect::<Vec<_>>()),
+        ] {
+            for (path, data) in files {
+                let path = folder.join(path);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, data).unwrap();
+            }
+        }
+        // The manifest itself must be excluded.
+        fs::write(second.join(PACKAGE_MANIFEST_FILE_NAME), "[]").unwrap();
+
+        let (entries_a, hash_a) = make_package_manifest(&first).unwrap();
+        let (entries_b, hash_b) = make_package_manifest(&second).unwrap();
+
+        assert_eq!(
+            entries_a
+                .iter()
+                .map(|e| e.path.as_str())
+                .collect::<Vec<_>>(),
+            ["b.bin", "data/a.txt", "data/nested/c.txt"]
+        );
+        assert_eq!(hash_a, hash_b);
+        assert_eq!(
+            serde_json::to_vec_pretty(&entries_a).unwrap(),
+            serde_json::to_vec_pretty(&entries_b).unwrap()
+        );
+        assert_eq!(make_package_manifest(&first).unwrap().1, hash_a);
+
+        fs::write(first.join("data/a.txt"), "changed").unwrap();
+        assert_ne!(make_package_manifest(&first).unwrap().1, hash_a);
+    }
+}
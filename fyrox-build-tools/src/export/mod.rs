@@ -18,7 +18,7 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use cargo_metadata::camino::Utf8Path;
+use cargo_metadata::{camino::Utf8Path, Metadata, Package};
 use clap::Parser;
 pub use fyrox_core::platform::TargetPlatform;
 use fyrox_core::{
@@ -39,6 +39,8 @@ use std::{
 
 pub mod android;
 pub mod asset;
+pub mod cache;
+pub mod headless;
 pub mod pc;
 pub mod utils;
 pub mod wasm;
@@ -56,6 +58,7 @@ pub struct ExportOptions {
     pub target_platform: TargetPlatform,
     pub destination_folder: PathBuf,
     pub include_used_assets: bool,
+    pub scenes: Vec<PathBuf>,
     pub assets_folders: Vec<PathBuf>,
     pub ignored_extensions: Vec<String>,
     #[reflect(hidden)]
@@ -63,6 +66,8 @@ pub struct ExportOptions {
     pub run_after_build: bool,
     pub open_destination_folder: bool,
     pub convert_assets: bool,
+    pub use_asset_cache: bool,
+    pub asset_cache_folder: PathBuf,
     pub enable_optimization: bool,
 }
 
@@ -73,11 +78,14 @@ impl Default for ExportOptions {
             destination_folder: "./build/".into(),
             assets_folders: vec!["./data/".into()],
             include_used_assets: false,
+            scenes: Default::default(),
             ignored_extensions: vec!["log".to_string()],
             build_target: "default".to_string(),
             run_after_build: false,
             open_destination_folder: true,
             convert_assets: true,
+            use_asset_cache: true,
+            asset_cache_folder: "./target/asset_cache/".into(),
             enable_optimization: true,
         }
     }
@@ -145,67 +153,110 @@ pub fn build_package(
     Ok(())
 }
 
-pub fn export(
-    export_options: ExportOptions,
-    cancel_flag: Arc<AtomicBool>,
-    resource_manager: ResourceManager,
-) -> BuildResult {
-    Log::info("Building the game...");
-
-    utils::prepare_build_dir(&export_options.destination_folder)?;
-    let metadata = utils::read_metadata()?;
-
-    let package_name = match export_options.target_platform {
+/// Returns the name of the executor package for the given platform.
+pub fn executor_package_name(target_platform: TargetPlatform) -> &'static str {
+    match target_platform {
         TargetPlatform::PC => "executor",
         TargetPlatform::WebAssembly => "executor-wasm",
         TargetPlatform::Android => "executor-android",
-    };
+    }
+}
 
-    let Some(package) = metadata
+/// Tries to find the executor package for the given platform in the workspace metadata.
+pub fn find_executor_package(
+    metadata: &Metadata,
+    target_platform: TargetPlatform,
+) -> Result<&Package, String> {
+    let package_name = executor_package_name(target_platform);
+    metadata
         .packages
         .iter()
         .find(|p| p.name.as_ref() == package_name)
-    else {
-        return Err(format!(
-            "The project does not have `{package_name}` package."
-        ));
-    };
+        .ok_or_else(|| format!("The project does not have `{package_name}` package."))
+}
 
+/// Copies (and converts if needed) the assets to the location, that is expected by the executor
+/// of the target platform. Temporary folders that must be removed after the build are added to
+/// `temp_folders`.
+pub fn copy_assets(
+    export_options: &ExportOptions,
+    package: &Package,
+    temp_folders: &mut Vec<PathBuf>,
+    resource_manager: &ResourceManager,
+) -> Result<asset::CookingReport, String> {
     let package_dir_path = package.manifest_path.as_path().parent().unwrap();
-
-    let mut temp_folders = Vec::new();
-
-    // Copy assets
     match export_options.target_platform {
         TargetPlatform::PC | TargetPlatform::WebAssembly => {
             Log::info("Trying to copy the assets...");
 
-            for folder in export_options.assets_folders {
-                Log::info(format!(
-                    "Trying to copy assets from {} to {}...",
-                    folder.display(),
-                    export_options.destination_folder.display()
-                ));
-
-                Log::verify(asset::copy_and_convert_assets(
-                    &folder,
-                    export_options.destination_folder.join(&folder),
-                    export_options.target_platform,
-                    &|_| true,
-                    &resource_manager,
-                    export_options.convert_assets,
-                ));
-            }
+            Ok(asset::export_assets(
+                export_options,
+                &export_options.destination_folder,
+                export_options.convert_assets,
+                resource_manager,
+            ))
         }
         TargetPlatform::Android => android::copy_assets(
-            &export_options,
+            export_options,
             package,
             package_dir_path,
-            &mut temp_folders,
-            &resource_manager,
+            temp_folders,
+            resource_manager,
             export_options.convert_assets,
-        )?,
+        ),
+    }
+}
+
+/// Copies the binaries of the built executor package to the destination folder.
+pub fn copy_binaries(
+    export_options: &ExportOptions,
+    metadata: &Metadata,
+    package: &Package,
+) -> Result<(), String> {
+    let package_name = executor_package_name(export_options.target_platform);
+    let package_dir_path = package.manifest_path.as_path().parent().unwrap();
+    match export_options.target_platform {
+        TargetPlatform::PC => pc::copy_binaries(
+            metadata,
+            package_name,
+            &export_options.destination_folder,
+            export_options.enable_optimization,
+        ),
+        TargetPlatform::WebAssembly => wasm::copy_binaries(
+            package_dir_path.as_std_path(),
+            &export_options.destination_folder,
+        ),
+        TargetPlatform::Android => android::copy_binaries(
+            metadata,
+            package_name,
+            &export_options.destination_folder,
+            export_options.enable_optimization,
+        ),
     }
+}
+
+pub fn export(
+    export_options: ExportOptions,
+    cancel_flag: Arc<AtomicBool>,
+    resource_manager: ResourceManager,
+) -> BuildResult {
+    Log::info("Building the game...");
+
+    utils::prepare_build_dir(&export_options.destination_folder)?;
+    let metadata = utils::read_metadata()?;
+
+    let package_name = executor_package_name(export_options.target_platform);
+    let package = find_executor_package(&metadata, export_options.target_platform)?;
+    let package_dir_path = package.manifest_path.as_path().parent().unwrap();
+
+    let mut temp_folders = Vec::new();
+
+    copy_assets(
+        &export_options,
+        package,
+        &mut temp_folders,
+        &resource_manager,
+    )?;
 
     build_package(
         package_name,
@@ -216,18 +267,7 @@ pub fn export(
         export_options.enable_optimization,
     )?;
 
-    match export_options.target_platform {
-        TargetPlatform::PC => {
-            pc::copy_binaries(&metadata, package_name, &export_options.destination_folder)?
-        }
-        TargetPlatform::WebAssembly => wasm::copy_binaries(
-            package_dir_path.as_std_path(),
-            &export_options.destination_folder,
-        )?,
-        TargetPlatform::Android => {
-            android::copy_binaries(&metadata, package_name, &export_options.destination_folder)?
-        }
-    }
+    copy_binaries(&export_options, &metadata, package)?;
 
     // Remove all temp folders.
     for temp_folder in temp_folders {
@@ -301,6 +341,12 @@ struct CliExportOptions {
     #[clap(long, default_value = "false")]
     pub include_used_assets: bool,
 
+    /// A set of scenes that are used as roots to search for used assets, when
+    /// `include_used_assets` is specified. If empty, all the scenes from the assets folders are
+    /// used.
+    #[clap(long)]
+    pub scenes: Vec<PathBuf>,
+
     // TODO: This is should be checked for usefulness.
     #[clap(long, default_value = "./data/")]
     pub assets_folders: Vec<PathBuf>,
@@ -325,6 +371,15 @@ struct CliExportOptions {
     #[clap(short, long, default_value = "true")]
     pub convert_assets: bool,
 
+    /// If specified, converted assets will be stored in a cache and reused on next exports,
+    /// unless their content, import options or the target platform change.
+    #[clap(long, default_value = "true")]
+    pub use_asset_cache: bool,
+
+    /// A folder, that is used to store converted assets between exports.
+    #[clap(long, default_value = "./target/asset_cache/")]
+    pub asset_cache_folder: PathBuf,
+
     /// If specified, enables all possible optimizations for the build.
     #[clap(short, long, default_value = "true")]
     pub enable_optimization: bool,
@@ -342,14 +397,29 @@ pub fn cli_export(resource_manager: ResourceManager) {
         },
         destination_folder: args.destination_folder,
         include_used_assets: args.include_used_assets,
+        scenes: args.scenes,
         assets_folders: args.assets_folders,
         ignored_extensions: args.ignored_extensions,
         build_target: args.build_target,
         run_after_build: args.run_after_build,
         open_destination_folder: args.open_destination_folder,
         convert_assets: args.convert_assets,
+        use_asset_cache: args.use_asset_cache,
+        asset_cache_folder: args.asset_cache_folder,
         enable_optimization: args.enable_optimization,
     };
 
     export(options, Default::default(), resource_manager).unwrap();
 }
+
+/// Removes the given folder (if it exists) and creates an empty one instead, so every test starts
+/// from scratch.
+#[cfg(test)]
+pub(crate) fn clean_folder(path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    if path.exists() {
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+    std::fs::create_dir_all(&path).unwrap();
+    path
+}
@@ -41,12 +41,19 @@ pub fn copy_binaries(
     metadata: &Metadata,
     package_name: &str,
     destination_folder: &Path,
+    enable_optimization: bool,
 ) -> Result<(), String> {
     Log::info("Trying to copy the executable...");
 
     let mut binary_paths = vec![];
-    for entry in fs::read_dir(metadata.target_directory.join("release"))
-        .unwrap()
+    let profile_dir = if enable_optimization {
+        "release"
+    } else {
+        "debug"
+    };
+    let binaries_dir = metadata.target_directory.join(profile_dir);
+    for entry in fs::read_dir(&binaries_dir)
+        .map_err(|err| format!("Unable to read {binaries_dir}. Reason: {err}"))?
         .flatten()
     {
         if let Ok(file_metadata) = entry.metadata() {
@@ -129,6 +129,11 @@ impl From<PixelKind> for PixelDescriptor {
             PixelKind::DXT5RGBA => (0, 0, GL_COMPRESSED_RGBA_S3TC_DXT5_EXT, None),
             PixelKind::R8RGTC => (0, 0, COMPRESSED_RED_RGTC1, None),
             PixelKind::RG8RGTC => (0, 0, COMPRESSED_RG_RGTC2, None),
+            PixelKind::BC6HRGBF => (0, 0, glow::COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT, None),
+            PixelKind::BC7RGBA => (0, 0, glow::COMPRESSED_RGBA_BPTC_UNORM, None),
+            PixelKind::ETC2RGB8 => (0, 0, glow::COMPRESSED_RGB8_ETC2, None),
+            PixelKind::ETC2RGBA8 => (0, 0, glow::COMPRESSED_RGBA8_ETC2_EAC, None),
+            PixelKind::ASTC4x4RGBA => (0, 0, glow::COMPRESSED_RGBA_ASTC_4x4_KHR, None),
             PixelKind::RGB32F => (glow::FLOAT, glow::RGB, glow::RGB32F, None),
             PixelKind::RGBA32F => (glow::FLOAT, glow::RGBA, glow::RGBA32F, None),
             PixelKind::RGBA16F => (glow::HALF_FLOAT, glow::RGBA, glow::RGBA16F, None),
@@ -132,6 +132,16 @@ pub enum PixelKind {
     R11G11B10F,
     /// Red, Green, Blue (8-bit) + Alpha (2-bit).
     RGB10A2,
+    /// Compressed unsigned floating-point RGB texture (BC6H).
+    BC6HRGBF,
+    /// Compressed RGBA texture (BC7).
+    BC7RGBA,
+    /// Compressed RGB texture (ETC2).
+    ETC2RGB8,
+    /// Compressed RGBA texture (ETC2 + EAC alpha).
+    ETC2RGBA8,
+    /// Compressed RGBA texture (ASTC with 4x4 blocks).
+    ASTC4x4RGBA,
 }
 
 /// Element kind of pixel.
@@ -180,7 +190,12 @@ impl PixelKind {
             | Self::DXT3RGBA
             | Self::DXT5RGBA
             | Self::R8RGTC
-            | Self::RG8RGTC => None,
+            | Self::RG8RGTC
+            | Self::BC6HRGBF
+            | Self::BC7RGBA
+            | Self::ETC2RGB8
+            | Self::ETC2RGBA8
+            | Self::ASTC4x4RGBA => None,
         }
     }
 
@@ -192,7 +207,12 @@ impl PixelKind {
             | Self::DXT3RGBA
             | Self::DXT5RGBA
             | Self::R8RGTC
-            | Self::RG8RGTC => true,
+            | Self::RG8RGTC
+            | Self::BC6HRGBF
+            | Self::BC7RGBA
+            | Self::ETC2RGB8
+            | Self::ETC2RGBA8
+            | Self::ASTC4x4RGBA => true,
             // Explicit match for rest of formats instead of _ will help to not forget
             // to add new entry here.
             Self::RGBA16
@@ -239,7 +259,8 @@ impl PixelKind {
             | Self::RGBA16F
             | Self::RGB16F
             | Self::D32F
-            | Self::R11G11B10F => PixelElementKind::Float,
+            | Self::R11G11B10F
+            | Self::BC6HRGBF => PixelElementKind::Float,
             Self::D16
             | Self::D24S8
             | Self::RGBA8
@@ -260,6 +281,10 @@ impl PixelKind {
             | Self::DXT5RGBA
             | Self::R8RGTC
             | Self::RG8RGTC
+            | Self::BC7RGBA
+            | Self::ETC2RGB8
+            | Self::ETC2RGBA8
+            | Self::ASTC4x4RGBA
             | Self::RGB10A2
             | Self::LA8
             | Self::L8
@@ -308,11 +333,17 @@ pub fn image_3d_size_bytes(
         | PixelKind::D16
         | PixelKind::R16F => 2 * pixel_count,
         PixelKind::R8 | PixelKind::L8 | PixelKind::R8UI => pixel_count,
-        PixelKind::DXT1RGB | PixelKind::DXT1RGBA | PixelKind::R8RGTC => {
+        PixelKind::DXT1RGB | PixelKind::DXT1RGBA | PixelKind::R8RGTC | PixelKind::ETC2RGB8 => {
             let block_size = 8;
             ceil_div_4(width) * ceil_div_4(height) * ceil_div_4(depth) * block_size
         }
-        PixelKind::DXT3RGBA | PixelKind::DXT5RGBA | PixelKind::RG8RGTC => {
+        PixelKind::DXT3RGBA
+        | PixelKind::DXT5RGBA
+        | PixelKind::RG8RGTC
+        | PixelKind::BC6HRGBF
+        | PixelKind::BC7RGBA
+        | PixelKind::ETC2RGBA8
+        | PixelKind::ASTC4x4RGBA => {
             let block_size = 16;
             ceil_div_4(width) * ceil_div_4(height) * ceil_div_4(depth) * block_size
         }
@@ -348,11 +379,17 @@ pub fn image_2d_size_bytes(pixel_kind: PixelKind, width: usize, height: usize) -
         | PixelKind::D16
         | PixelKind::R16F => 2 * pixel_count,
         PixelKind::R8 | PixelKind::L8 | PixelKind::R8UI => pixel_count,
-        PixelKind::DXT1RGB | PixelKind::DXT1RGBA | PixelKind::R8RGTC => {
+        PixelKind::DXT1RGB | PixelKind::DXT1RGBA | PixelKind::R8RGTC | PixelKind::ETC2RGB8 => {
             let block_size = 8;
             ceil_div_4(width) * ceil_div_4(height) * block_size
         }
-        PixelKind::DXT3RGBA | PixelKind::DXT5RGBA | PixelKind::RG8RGTC => {
+        PixelKind::DXT3RGBA
+        | PixelKind::DXT5RGBA
+        | PixelKind::RG8RGTC
+        | PixelKind::BC6HRGBF
+        | PixelKind::BC7RGBA
+        | PixelKind::ETC2RGBA8
+        | PixelKind::ASTC4x4RGBA => {
             let block_size = 16;
             ceil_div_4(width) * ceil_div_4(height) * block_size
         }
@@ -387,11 +424,17 @@ pub fn image_1d_size_bytes(pixel_kind: PixelKind, length: usize) -> usize {
         | PixelKind::D16
         | PixelKind::R16F => 2 * length,
         PixelKind::R8 | PixelKind::L8 | PixelKind::R8UI => length,
-        PixelKind::DXT1RGB | PixelKind::DXT1RGBA | PixelKind::R8RGTC => {
+        PixelKind::DXT1RGB | PixelKind::DXT1RGBA | PixelKind::R8RGTC | PixelKind::ETC2RGB8 => {
             let block_size = 8;
             ceil_div_4(length) * block_size
         }
-        PixelKind::DXT3RGBA | PixelKind::DXT5RGBA | PixelKind::RG8RGTC => {
+        PixelKind::DXT3RGBA
+        | PixelKind::DXT5RGBA
+        | PixelKind::RG8RGTC
+        | PixelKind::BC6HRGBF
+        | PixelKind::BC7RGBA
+        | PixelKind::ETC2RGBA8
+        | PixelKind::ASTC4x4RGBA => {
             let block_size = 16;
             ceil_div_4(length) * block_size
         }
@@ -212,7 +212,7 @@ impl Executor {
         Log::info("Initializing resource registry.");
         self.engine.resource_manager.update_or_load_registry();
 
-        let engine = self.engine;
+        let mut engine = self.engine;
         let event_loop = self.event_loop;
         let throttle_threshold = self.throttle_threshold;
         let throttle_frame_interval = self.throttle_frame_interval;
@@ -233,6 +233,8 @@ impl Executor {
             }
         }
 
+        engine.set_fixed_time_step(1.0 / self.desired_update_rate);
+
         let args = Args::try_parse().unwrap_or_default();
 
         match event_loop {
@@ -501,6 +503,10 @@ fn game_loop_iteration(
             time_step = fixed_time_step;
         }
 
+        // Reload the resources changed on disk, if hot reloading is enabled. Does nothing if
+        // there's no file system watcher.
+        engine.resource_manager.state().process_filesystem_events();
+
         engine.update(time_step, controller, lag, Default::default());
 
         // Additional check is needed, because the `update` call above could modify
@@ -513,6 +519,11 @@ fn game_loop_iteration(
         }
     }
 
+    // The remaining lag is the time that has passed since the last update, take it into account
+    // so the rendering could interpolate between the last and the next fixed update.
+    engine.fixed_update_alpha =
+        ((engine.fixed_time_accumulator + *lag) / engine.fixed_time_step).clamp(0.0, 1.0);
+
     if let GraphicsContext::Initialized(ref ctx) = engine.graphics_context {
         ctx.window.request_redraw();
     }
@@ -78,7 +78,8 @@ use crate::{
     resource::{
         curve::{loader::CurveLoader, CurveResourceState},
         gltf::material::GLTF_SHADER,
-        model::{loader::ModelLoader, Model, ModelResource},
+        level::{LdtkLoader, TmxLoader},
+        model::{loader::ModelLoader, Model},
         texture::{
             self, loader::TextureLoader, CompressionOptions, Texture, TextureImportOptions,
             TextureKind, TextureMinificationFilter, TextureResource, TextureResourceExtension,
@@ -116,13 +117,14 @@ use fyrox_graphics::server::SharedGraphicsServer;
 
 use fyrox_sound::{
     buffer::{loader::SoundBufferLoader, SoundBuffer},
+    event::{loader::SoundEventLoader, SoundEvent},
     renderer::hrtf::{HrirSphereLoader, HrirSphereResourceData},
 };
 use std::fmt::Debug;
 use std::{
     any::TypeId,
     cell::Cell,
-    collections::{HashSet, VecDeque},
+    collections::VecDeque,
     fmt::{Display, Formatter},
     io::Cursor,
     ops::{Deref, DerefMut},
@@ -346,6 +348,17 @@ pub struct Engine {
     // Amount of time (in seconds) that passed from creation of the engine.
     elapsed_time: f32,
 
+    // Time step (in seconds) of fixed updates.
+    fixed_time_step: f32,
+
+    // Amount of time (in seconds) that was not yet consumed by fixed updates.
+    fixed_time_accumulator: f32,
+
+    max_fixed_updates_per_frame: usize,
+
+    // Interpolation factor between the last and the next fixed update.
+    fixed_update_alpha: f32,
+
     input_state: InputState,
 
     /// A special container that is able to create nodes by their type UUID. Use a copy of this
@@ -649,6 +662,7 @@ impl ScriptProcessor {
         user_interfaces: &mut UiContainer,
         dt: f32,
         elapsed_time: f32,
+        fixed_update_alpha: f32,
         input_state: &InputState,
         error_queue: &mut ErrorQueue,
     ) {
@@ -711,6 +725,7 @@ impl ScriptProcessor {
                 let mut context = ScriptContext {
                     dt,
                     elapsed_time,
+                    fixed_update_alpha,
                     plugins: PluginsRefMut(plugins),
                     handle: Default::default(),
                     scene,
@@ -931,78 +946,6 @@ impl ScriptProcessor {
     }
 }
 
-struct ResourceGraphVertex {
-    resource: ModelResource,
-    children: Vec<ResourceGraphVertex>,
-}
-
-impl ResourceGraphVertex {
-    pub fn new(model: ModelResource, resource_manager: ResourceManager) -> Self {
-        let mut children = Vec::new();
-
-        // Look for dependent resources.
-        let mut dependent_resources = HashSet::new();
-        for resource in resource_manager.state().iter() {
-            if let Some(other_model) = resource.try_cast::<Model>() {
-                let mut state = other_model.state();
-                if let Some(model_data) = state.data() {
-                    if model_data
-                        .get_scene()
-                        .graph
-                        .linear_iter()
-                        .any(|n| n.resource.as_ref() == Some(&model))
-                    {
-                        dependent_resources.insert(other_model.clone());
-                    }
-                }
-            }
-        }
-
-        children.extend(
-            dependent_resources
-                .into_iter()
-                .map(|r| ResourceGraphVertex::new(r, resource_manager.clone())),
-        );
-
-        Self {
-            resource: model,
-            children,
-        }
-    }
-
-    pub fn resolve(&self) {
-        Log::info(format!(
-            "Resolving {} resource from dependency graph...",
-            self.resource.resource_uuid()
-        ));
-
-        // Wait until resource is fully loaded, then resolve.
-        if block_on(self.resource.clone()).is_ok() {
-            self.resource.data_ref().get_scene_mut();
-
-            for child in self.children.iter() {
-                child.resolve();
-            }
-        }
-    }
-}
-
-struct ResourceDependencyGraph {
-    root: ResourceGraphVertex,
-}
-
-impl ResourceDependencyGraph {
-    pub fn new(model: ModelResource, resource_manager: ResourceManager) -> Self {
-        Self {
-            root: ResourceGraphVertex::new(model, resource_manager),
-        }
-    }
-
-    pub fn resolve(&self) {
-        self.root.resolve()
-    }
-}
-
 /// A result returned by a graphics server constructor.
 pub type GraphicsServerConstructorResult = Result<(Window, SharedGraphicsServer), FrameworkError>;
 
@@ -1213,6 +1156,7 @@ pub(crate) fn process_scripts<T>(
     user_interfaces: &mut UiContainer,
     dt: f32,
     elapsed_time: f32,
+    fixed_update_alpha: f32,
     input_state: &InputState,
     error_queue: &mut ErrorQueue,
     mut func: T,
@@ -1222,6 +1166,7 @@ pub(crate) fn process_scripts<T>(
     let mut context = ScriptContext {
         dt,
         elapsed_time,
+        fixed_update_alpha,
         plugins: PluginsRefMut(plugins),
         handle: Default::default(),
         scene,
@@ -1311,6 +1256,7 @@ pub(crate) fn initialize_resource_manager_loaders(
     state.constructors_container.add::<Model>();
     state.constructors_container.add::<CurveResourceState>();
     state.constructors_container.add::<SoundBuffer>();
+    state.constructors_container.add::<SoundEvent>();
     state.constructors_container.add::<HrirSphereResourceData>();
     state.constructors_container.add::<Material>();
     state.constructors_container.add::<Font>();
@@ -1329,6 +1275,12 @@ pub(crate) fn initialize_resource_manager_loaders(
         default_import_options: Default::default(),
     };
     loaders.set(gltf_loader);
+    loaders.set(TmxLoader {
+        resource_manager: resource_manager.clone(),
+    });
+    loaders.set(LdtkLoader {
+        resource_manager: resource_manager.clone(),
+    });
     loaders.set(model_loader);
     loaders.set(TextureLoader {
         default_import_options: Default::default(),
@@ -1336,6 +1288,9 @@ pub(crate) fn initialize_resource_manager_loaders(
     loaders.set(SoundBufferLoader {
         default_import_options: Default::default(),
     });
+    loaders.set(SoundEventLoader {
+        resource_manager: resource_manager.clone(),
+    });
     loaders.set(ShaderLoader);
     loaders.set(CurveLoader);
     loaders.set(HrirSphereLoader);
@@ -1400,6 +1355,14 @@ impl ApplicationLoopController<'_> {
 }
 
 impl Engine {
+    /// Default time step (in seconds) of fixed updates. See [`Self::set_fixed_time_step`] for more
+    /// info.
+    pub const DEFAULT_FIXED_TIME_STEP: f32 = 1.0 / 60.0;
+
+    /// Default maximum amount of fixed updates per single update call. See
+    /// [`Self::set_max_fixed_updates_per_frame`] for more info.
+    pub const DEFAULT_MAX_FIXED_UPDATES_PER_FRAME: usize = 8;
+
     /// Creates new instance of engine from given initialization parameters. Automatically creates all sub-systems
     /// (sound, ui, resource manager, etc.) **except** graphics context. Graphics context should be created manually
     /// only on [`Event::Resumed`] by calling [`Engine::initialize_graphics_context`] and destroyed on [`Event::Suspended`]
@@ -1487,6 +1450,10 @@ impl Engine {
             script_processor: Default::default(),
             plugins_enabled: false,
             elapsed_time: 0.0,
+            fixed_time_step: Self::DEFAULT_FIXED_TIME_STEP,
+            fixed_time_accumulator: 0.0,
+            max_fixed_updates_per_frame: Self::DEFAULT_MAX_FIXED_UPDATES_PER_FRAME,
+            fixed_update_alpha: 0.0,
             task_pool: TaskPoolHandler::new(task_pool),
             input_state: Default::default(),
             error_queue: Default::default(),
@@ -1618,6 +1585,39 @@ impl Engine {
         self.elapsed_time
     }
 
+    /// Sets a new time step (in seconds) of fixed updates. Fixed updates ([`Plugin::fixed_update`],
+    /// [`ScriptTrait::on_fixed_update`] and physics) are performed with this time step as many
+    /// times per [`Self::update`] call as needed to consume the passed time. The executor sets
+    /// this value to `1.0 / desired_update_rate`.
+    pub fn set_fixed_time_step(&mut self, time_step: f32) {
+        self.fixed_time_step = time_step.abs().max(f32::EPSILON);
+    }
+
+    /// Returns current time step (in seconds) of fixed updates. See [`Self::set_fixed_time_step`]
+    /// for more info.
+    pub fn fixed_time_step(&self) -> f32 {
+        self.fixed_time_step
+    }
+
+    /// Sets the maximum amount of fixed updates that could be performed in a single update call.
+    /// The rest of the accumulated time will be discarded. This prevents the "spiral of death",
+    /// when a slow fixed update causes even more fixed updates on the next frame.
+    pub fn set_max_fixed_updates_per_frame(&mut self, max: usize) {
+        self.max_fixed_updates_per_frame = max.max(1);
+    }
+
+    /// Returns the maximum amount of fixed updates that could be performed in a single update call.
+    pub fn max_fixed_updates_per_frame(&self) -> usize {
+        self.max_fixed_updates_per_frame
+    }
+
+    /// Returns interpolation factor in `[0; 1]` range that tells how far the current moment is
+    /// between the last and the next fixed update. It can be used to interpolate visual transforms
+    /// of physical objects between two physics steps.
+    pub fn fixed_update_alpha(&self) -> f32 {
+        self.fixed_update_alpha
+    }
+
     /// Performs single update tick with given time delta. Engine internally will perform update
     /// of all scenes, sub-systems, user interface, etc. Must be called in order to get engine
     /// functioning.
@@ -1695,6 +1695,10 @@ impl Engine {
         self.update_plugins(dt, controller, lag);
         self.handle_scripts(dt);
 
+        // Fixed updates are performed after the regular ones, so they could react to the input
+        // collected in the regular updates. Physics is stepped here as well.
+        self.fixed_update(dt, controller, lag, &switches);
+
         // Now that the plugins and scripts have made whatever changes are needed, we must respond
         // to those changes by updating the scenes and the state of the engine.
 
@@ -1724,11 +1728,125 @@ impl Engine {
                         }
                     });
 
-            scene.update(
-                frame_size,
+            let switches = GraphUpdateSwitches {
+                // Physics was already updated in fixed updates.
+                physics: false,
+                physics2d: false,
+                ..switches.get(&handle).cloned().unwrap_or_default()
+            };
+
+            scene.update(frame_size, dt, switches);
+        }
+    }
+
+    fn fixed_update(
+        &mut self,
+        dt: f32,
+        controller: ApplicationLoopController,
+        lag: &mut f32,
+        switches: &FxHashMap<Handle<Scene>, GraphUpdateSwitches>,
+    ) {
+        let time_step = self.fixed_time_step;
+
+        self.fixed_time_accumulator += dt;
+
+        // A tiny tolerance is needed to prevent missing a step because of floating-point errors, when
+        // the passed time is a multiple of the time step.
+        let tolerance = time_step * 1.0e-3;
+
+        let mut steps = 0;
+        while self.fixed_time_accumulator + tolerance >= time_step {
+            if steps >= self.max_fixed_updates_per_frame {
+                // Discard the time that cannot be consumed on this frame.
+                self.fixed_time_accumulator %= time_step;
+                break;
+            }
+
+            self.fixed_update_plugins(time_step, controller, lag);
+            self.handle_fixed_update_scripts(time_step);
+
+            for (handle, scene) in self.scenes.pair_iter_mut().filter(|(_, s)| *s.enabled) {
+                scene.graph.update_physics(
+                    time_step,
+                    &switches.get(&handle).cloned().unwrap_or_default(),
+                );
+            }
+
+            self.fixed_time_accumulator = (self.fixed_time_accumulator - time_step).max(0.0);
+            steps += 1;
+        }
+
+        self.fixed_update_alpha = (self.fixed_time_accumulator / time_step).clamp(0.0, 1.0);
+    }
+
+    fn fixed_update_plugins(
+        &mut self,
+        dt: f32,
+        controller: ApplicationLoopController,
+        lag: &mut f32,
+    ) {
+        if self.plugins_enabled {
+            let mut context = PluginContext {
+                scenes: &mut self.scenes,
+                resource_manager: &self.resource_manager,
+                graphics_context: &mut self.graphics_context,
                 dt,
-                switches.get(&handle).cloned().unwrap_or_default(),
-            );
+                lag,
+                user_interfaces: &mut self.user_interfaces,
+                serialization_context: &self.serialization_context,
+                widget_constructors: &self.widget_constructors,
+                dyn_type_constructors: &self.dyn_type_constructors,
+                performance_statistics: &self.performance_statistics,
+                elapsed_time: self.elapsed_time,
+                fixed_update_alpha: self.fixed_update_alpha,
+                script_processor: &self.script_processor,
+                loop_controller: controller,
+                task_pool: &mut self.task_pool,
+                input_state: &self.input_state,
+            };
+
+            for plugin in self.plugins.iter_mut() {
+                try_enqueue_plugin_error(
+                    "fixed_update",
+                    plugin.fixed_update(&mut context),
+                    &mut self.error_queue,
+                );
+            }
+        }
+    }
+
+    fn handle_fixed_update_scripts(&mut self, dt: f32) {
+        for scripted_scene in self.script_processor.scripted_scenes.iter_mut() {
+            let Ok(scene) = self.scenes.try_get_mut(scripted_scene.handle) else {
+                continue;
+            };
+
+            if *scene.enabled {
+                process_scripts(
+                    "on_fixed_update",
+                    scene,
+                    scripted_scene.handle,
+                    &mut self.plugins,
+                    &self.resource_manager,
+                    &scripted_scene.message_sender,
+                    &mut scripted_scene.message_dispatcher,
+                    &mut self.task_pool,
+                    &mut self.graphics_context,
+                    &mut self.user_interfaces,
+                    dt,
+                    self.elapsed_time,
+                    self.fixed_update_alpha,
+                    &self.input_state,
+                    &mut self.error_queue,
+                    |script, context| {
+                        if script.initialized && script.started {
+                            script.on_fixed_update(context)
+                        } else {
+                            Ok(())
+                        }
+                    },
+                )
+            }
         }
     }
 
@@ -1795,6 +1913,7 @@ impl Engine {
             &mut self.user_interfaces,
             dt,
             self.elapsed_time,
+            self.fixed_update_alpha,
             &self.input_state,
             &mut self.error_queue,
         );
@@ -1823,6 +1942,7 @@ impl Engine {
                     dyn_type_constructors: &self.dyn_type_constructors,
                     performance_statistics: &self.performance_statistics,
                     elapsed_time: self.elapsed_time,
+                    fixed_update_alpha: self.fixed_update_alpha,
                     script_processor: &self.script_processor,
                     loop_controller: controller,
                     task_pool: &mut self.task_pool,
@@ -1855,6 +1975,7 @@ impl Engine {
                                 let mut ctx = ScriptContext {
                                     dt,
                                     elapsed_time: self.elapsed_time,
+                                    fixed_update_alpha: self.fixed_update_alpha,
                                     plugins: PluginsRefMut(&mut self.plugins),
                                     handle: node_task_handler.node_handle,
                                     scene,
@@ -1925,6 +2046,7 @@ impl Engine {
                 dyn_type_constructors: &self.dyn_type_constructors,
                 performance_statistics: &self.performance_statistics,
                 elapsed_time: self.elapsed_time,
+                fixed_update_alpha: self.fixed_update_alpha,
                 script_processor: &self.script_processor,
                 loop_controller: controller,
                 task_pool: &mut self.task_pool,
@@ -1966,6 +2088,7 @@ impl Engine {
                         dyn_type_constructors: &self.dyn_type_constructors,
                         performance_statistics: &self.performance_statistics,
                         elapsed_time: self.elapsed_time,
+                        fixed_update_alpha: self.fixed_update_alpha,
                         script_processor: &self.script_processor,
                         loop_controller: controller,
                         task_pool: &mut self.task_pool,
@@ -2018,6 +2141,7 @@ impl Engine {
                 dyn_type_constructors: &self.dyn_type_constructors,
                 performance_statistics: &self.performance_statistics,
                 elapsed_time: self.elapsed_time,
+                fixed_update_alpha: self.fixed_update_alpha,
                 script_processor: &self.script_processor,
                 loop_controller: controller,
                 task_pool: &mut self.task_pool,
@@ -2163,6 +2287,7 @@ impl Engine {
                     dyn_type_constructors: &self.dyn_type_constructors,
                     performance_statistics: &self.performance_statistics,
                     elapsed_time: self.elapsed_time,
+                    fixed_update_alpha: self.fixed_update_alpha,
                     script_processor: &self.script_processor,
                     loop_controller: controller,
                     task_pool: &mut self.task_pool,
@@ -2198,6 +2323,7 @@ impl Engine {
                     dyn_type_constructors: &self.dyn_type_constructors,
                     performance_statistics: &self.performance_statistics,
                     elapsed_time: self.elapsed_time,
+                    fixed_update_alpha: self.fixed_update_alpha,
                     script_processor: &self.script_processor,
                     loop_controller: controller,
                     task_pool: &mut self.task_pool,
@@ -2233,6 +2359,7 @@ impl Engine {
                     dyn_type_constructors: &self.dyn_type_constructors,
                     performance_statistics: &self.performance_statistics,
                     elapsed_time: self.elapsed_time,
+                    fixed_update_alpha: self.fixed_update_alpha,
                     script_processor: &self.script_processor,
                     loop_controller: controller,
                     task_pool: &mut self.task_pool,
@@ -2268,6 +2395,7 @@ impl Engine {
                     dyn_type_constructors: &self.dyn_type_constructors,
                     performance_statistics: &self.performance_statistics,
                     elapsed_time: self.elapsed_time,
+                    fixed_update_alpha: self.fixed_update_alpha,
                     script_processor: &self.script_processor,
                     loop_controller: controller,
                     task_pool: &mut self.task_pool,
@@ -2317,6 +2445,7 @@ impl Engine {
                     &mut self.user_interfaces,
                     dt,
                     self.elapsed_time,
+                    self.fixed_update_alpha,
                     &self.input_state,
                     &mut self.error_queue,
                     |script, context| {
@@ -2331,31 +2460,76 @@ impl Engine {
         }
     }
 
-    /// Handle hot-reloading of resources.
+    /// Handle hot-reloading of resources. Refreshes the data derived from reloaded resources: the
+    /// prefabs that use a reloaded model (directly or via other prefabs) are resolved first, then
+    /// the active scenes are resolved. Materials are synchronized with their shaders when a
+    /// dependency of a material is reloaded. User interfaces are re-laid out when a font is
+    /// reloaded, so the text is formatted using the new glyphs.
     ///
     /// Normally, this is called from `Engine::update()`.
     /// You should only call this manually if you don't use that method.
     pub fn handle_model_events(&mut self) {
+        let mut resolve_scenes = false;
+        let mut invalidate_ui = false;
+
         while let Ok(event) = self.model_events_receiver.try_recv() {
-            if let ResourceEvent::Reloaded(resource) = event {
-                if let Some(model) = resource.try_cast::<Model>() {
+            let (resource, dependency_changed) = match event {
+                ResourceEvent::Reloaded(resource) => (resource, false),
+                ResourceEvent::DependencyReloaded { resource, .. } => (resource, true),
+                _ => continue,
+            };
+
+            if let Some(model) = resource.try_cast::<Model>() {
+                if dependency_changed {
+                    // Dependent models come after their dependencies, so the nested instances
+                    // are restored from already resolved data.
+                    Log::info(format!(
+                        "A dependency of model resource {} was reloaded, resolving...",
+                        model.resource_uuid()
+                    ));
+                    let mut state = model.state();
+                    if let Some(model_data) = state.data() {
+                        model_data.get_scene_mut().resolve();
+                    }
+                } else {
                     Log::info(format!(
                         "A model resource {} was reloaded, propagating changes...",
                         model.resource_uuid()
                     ));
+                }
 
-                    // Build resource dependency graph and resolve it first.
-                    ResourceDependencyGraph::new(model, self.resource_manager.clone()).resolve();
-
-                    Log::info("Propagating changes to active scenes...");
-
-                    // Resolve all scenes.
-                    // TODO: This might be inefficient if there is bunch of scenes loaded,
-                    // however this seems to be very rare case so it should be ok.
-                    for scene in self.scenes.iter_mut() {
-                        scene.resolve();
+                resolve_scenes = true;
+            } else if let Some(material) = resource.try_cast::<Material>() {
+                if dependency_changed {
+                    // The shader might be changed, so the material could contain stale bindings.
+                    Log::info(format!(
+                        "A dependency of material resource {} was reloaded, syncing with shader...",
+                        material.resource_uuid()
+                    ));
+                    let mut state = material.state();
+                    if let Some(material) = state.data() {
+                        material.sync_to_shader();
                     }
                 }
+            } else if resource.try_cast::<Font>().is_some() {
+                invalidate_ui = true;
+            }
+        }
+
+        if resolve_scenes {
+            Log::info("Propagating changes to active scenes...");
+
+            // Resolve all scenes.
+            // TODO: This might be inefficient if there is bunch of scenes loaded,
+            // however this seems to be very rare case so it should be ok.
+            for scene in self.scenes.iter_mut() {
+                scene.resolve();
+            }
+        }
+
+        if invalidate_ui {
+            for ui in self.user_interfaces.iter_mut() {
+                ui.invalidate_layout();
             }
         }
     }
@@ -2420,6 +2594,7 @@ impl Engine {
                         dyn_type_constructors: &self.dyn_type_constructors,
                         performance_statistics: &self.performance_statistics,
                         elapsed_time: self.elapsed_time,
+                        fixed_update_alpha: self.fixed_update_alpha,
                         script_processor: &self.script_processor,
                         loop_controller: controller,
                         task_pool: &mut self.task_pool,
@@ -2448,6 +2623,7 @@ impl Engine {
                         dyn_type_constructors: &self.dyn_type_constructors,
                         performance_statistics: &self.performance_statistics,
                         elapsed_time: self.elapsed_time,
+                        fixed_update_alpha: self.fixed_update_alpha,
                         script_processor: &self.script_processor,
                         loop_controller: controller,
                         task_pool: &mut self.task_pool,
@@ -2791,6 +2967,7 @@ impl Engine {
             dyn_type_constructors: &self.dyn_type_constructors,
             performance_statistics: &Default::default(),
             elapsed_time: self.elapsed_time,
+            fixed_update_alpha: self.fixed_update_alpha,
             script_processor: &self.script_processor,
             loop_controller: controller,
             task_pool: &mut self.task_pool,
@@ -3114,6 +3291,7 @@ mod test {
                 &mut user_interfaces,
                 0.0,
                 0.0,
+                0.0,
                 &Default::default(),
                 &mut Default::default(),
             );
@@ -3288,6 +3466,7 @@ mod test {
                 &mut user_interfaces,
                 0.0,
                 0.0,
+                0.0,
                 &Default::default(),
                 &mut Default::default(),
             );
@@ -3580,6 +3759,7 @@ mod test {
                 &mut user_interfaces,
                 0.0,
                 0.0,
+                0.0,
                 &Default::default(),
                 &mut Default::default(),
             );
@@ -3658,4 +3838,180 @@ mod test {
             }
         }
     }
+
+    #[derive(Clone, Debug, PartialEq, Default, Reflect, Visit)]
+    #[reflect(type_uuid = "4c1e8d0a-7b5f-4f3e-9d2a-6e0b1c7f5a93")]
+    pub struct FixedUpdateScript {
+        updates: usize,
+        fixed_dt: Vec<f32>,
+        heights: Vec<f32>,
+    }
+
+    impl ScriptTrait for FixedUpdateScript {
+        fn on_update(&mut self, _ctx: &mut ScriptContext) -> GameResult {
+            self.updates += 1;
+            Ok(())
+        }
+
+        fn on_fixed_update(&mut self, ctx: &mut ScriptContext) -> GameResult {
+            self.fixed_dt.push(ctx.dt);
+            self.heights
+                .push(ctx.scene.graph[ctx.handle].local_transform().position().y);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_material_synced_on_shader_reload() {
+        use crate::{
+            engine::{Engine, EngineInitParams},
+            graphics::gpu_program::{
+                SamplerFallback, SamplerKind, ShaderProperty, ShaderPropertyKind,
+            },
+            material::{
+                shader::{
+                    Shader, ShaderDefinition, ShaderResource, ShaderResourceDefinition,
+                    ShaderResourceKind,
+                },
+                Material, MaterialProperty, MaterialResource, MaterialResourceBinding,
+                MaterialTextureBinding,
+            },
+        };
+
+        let task_pool = Arc::new(TaskPool::default());
+        let mut engine = Engine::new(EngineInitParams {
+            graphics_context_params: Default::default(),
+            serialization_context: Arc::new(Default::default()),
+            widget_constructors: Arc::new(Default::default()),
+            dyn_type_constructors: Arc::new(Default::default()),
+            resource_manager: ResourceManager::new(Arc::new(FsResourceIo), task_pool.clone()),
+            task_pool,
+        })
+        .unwrap();
+
+        let property_group = |kind_of_b| ShaderResourceDefinition {
+            name: "properties".into(),
+            kind: ShaderResourceKind::PropertyGroup(vec![
+                ShaderProperty::new("a", ShaderPropertyKind::Float { value: 0.0 }),
+                ShaderProperty::new("b", kind_of_b),
+            ]),
+            binding: 0,
+        };
+        let shader = ShaderResource::new_embedded(Shader {
+            definition: ShaderDefinition {
+                resources: vec![
+                    ShaderResourceDefinition {
+                        name: "diffuseTexture".into(),
+                        kind: ShaderResourceKind::Texture {
+                            kind: SamplerKind::Sampler2D,
+                            fallback: SamplerFallback::White,
+                        },
+                        binding: 0,
+                    },
+                    property_group(ShaderPropertyKind::Float { value: 0.0 }),
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let mut material = Material::from_shader(shader.clone());
+        material.set_property("a", 1.0f32);
+        material.set_property("b", 2.0f32);
+        material.bind(
+            "diffuseTexture",
+            MaterialResourceBinding::Texture(MaterialTextureBinding { value: None }),
+        );
+        let material = MaterialResource::new_embedded(material);
+
+        // Simulate a reload: the texture is removed and the type of `b` is changed.
+        shader.data_ref().definition.resources =
+            vec![property_group(ShaderPropertyKind::Int { value: 0 })];
+        engine
+            .resource_manager
+            .state()
+            .event_broadcaster
+            .broadcast_dependency_reloaded(
+                material.clone().into_untyped(),
+                shader.clone().into_untyped(),
+            );
+
+        engine.handle_model_events();
+
+        let material = material.data_ref();
+        assert!(material.binding_ref("diffuseTexture").is_none());
+        let group = material.property_group_ref("properties").unwrap();
+        assert_eq!(group.property_ref("a"), Some(&MaterialProperty::Float(1.0)));
+        assert!(group.property_ref("b").is_none());
+    }
+
+    #[test]
+    fn test_fixed_update() {
+        use crate::{
+            engine::{Engine, EngineInitParams},
+            scene::{
+                collider::{ColliderBuilder, ColliderShape},
+                rigidbody::RigidBodyBuilder,
+            },
+        };
+
+        let task_pool = Arc::new(TaskPool::default());
+        let mut engine = Engine::new(EngineInitParams {
+            graphics_context_params: Default::default(),
+            serialization_context: Arc::new(Default::default()),
+            widget_constructors: Arc::new(Default::default()),
+            dyn_type_constructors: Arc::new(Default::default()),
+            resource_manager: ResourceManager::new(Arc::new(FsResourceIo), task_pool.clone()),
+            task_pool,
+        })
+        .unwrap();
+        engine.set_fixed_time_step(1.0 / 60.0);
+
+        let is_running = Cell::new(true);
+
+        let mut scene = Scene::new();
+        let collider = ColliderBuilder::new(BaseBuilder::new())
+            .with_shape(ColliderShape::ball(0.5))
+            .build(&mut scene.graph);
+        let body = RigidBodyBuilder::new(
+            BaseBuilder::new()
+                .with_child(collider)
+                .with_script(FixedUpdateScript::default()),
+        )
+        .build(&mut scene.graph);
+
+        let scene_handle = engine.scenes.add(scene);
+        engine.register_scripted_scene(scene_handle);
+
+        let update = |engine: &mut Engine, dt: f32| {
+            engine.update(
+                dt,
+                ApplicationLoopController::Headless {
+                    running: &is_running,
+                },
+                &mut 0.0,
+                Default::default(),
+            );
+        };
+
+        // Three fixed updates per each frame.
+        for _ in 0..10 {
+            update(&mut engine, 1.0 / 20.0);
+        }
+        assert!(engine.fixed_update_alpha() < 0.01);
+
+        // A fixed update every second frame.
+        update(&mut engine, 1.0 / 120.0);
+        assert!((engine.fixed_update_alpha() - 0.5).abs() < 0.01);
+        update(&mut engine, 1.0 / 120.0);
+
+        let script = engine.scenes[scene_handle].graph[body]
+            .try_get_script::<FixedUpdateScript>()
+            .unwrap();
+        assert_eq!(script.updates, 12);
+        assert_eq!(script.fixed_dt.len(), 31);
+        assert!(script.fixed_dt.iter().all(|dt| *dt == 1.0 / 60.0));
+        // The body must be moved by physics between fixed updates.
+        assert!(script.heights.windows(2).skip(1).all(|h| h[1] < h[0]));
+    }
 }
@@ -207,6 +207,12 @@ pub struct PluginContext<'a, 'b> {
     /// which the engine "ticks" and this delta time affects elapsed time.
     pub elapsed_time: f32,
 
+    /// Interpolation factor in `[0; 1]` range that tells how far the current frame is between the
+    /// last and the next fixed update (see [`Plugin::fixed_update`]). It can be used to smoothly
+    /// interpolate visual transforms of physical objects, when the frame rate is higher than the
+    /// fixed update rate.
+    pub fixed_update_alpha: f32,
+
     /// Script processor is used to run script methods in a strict order.
     pub script_processor: &'a ScriptProcessor,
 
@@ -617,6 +623,19 @@ pub trait Plugin: PluginAsAny + Visit + Reflect {
         Ok(())
     }
 
+    /// Updates the plugin at a strictly fixed rate with the fixed time step (see
+    /// [`crate::engine::Engine::set_fixed_time_step`]). The method may be called zero or multiple
+    /// times per frame and it is always called right before the physics step, which makes it
+    /// suitable for deterministic logic (lockstep networking, replays, etc.). It is called before
+    /// [`ScriptTrait::on_fixed_update`](crate::script::ScriptTrait::on_fixed_update) of every
+    /// script.
+    fn fixed_update(
+        &mut self,
+        #[allow(unused_variables)] context: &mut PluginContext,
+    ) -> GameResult {
+        Ok(())
+    }
+
     /// called after all Plugin and Script updates
     fn post_update(
         &mut self,
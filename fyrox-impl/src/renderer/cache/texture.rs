@@ -20,19 +20,32 @@
 
 use crate::{
     asset::manager::ResourceManager,
-    core::err_once,
-    core::log::{Log, MessageKind},
+    core::{
+        algebra::Point3,
+        err_once,
+        log::{Log, MessageKind},
+        math::Rect,
+    },
     graphics::{
         error::FrameworkError,
-        gpu_texture::{GpuTexture, GpuTextureDescriptor, GpuTextureKind, PixelKind},
+        gpu_texture::{
+            image_2d_size_bytes, GpuTexture, GpuTextureDescriptor, GpuTextureKind, PixelKind,
+        },
         sampler::{
             GpuSampler, GpuSamplerDescriptor, MagnificationFilter, MinificationFilter, WrapMode,
         },
         server::GraphicsServer,
     },
-    renderer::cache::{TemporaryCache, TimeToLive},
+    material::MaterialResourceBinding,
+    renderer::{
+        bundle::RenderDataBundleStorage,
+        cache::{TemporaryCache, TimeToLive},
+        stats::TextureStreamingStatistics,
+    },
     resource::texture::{Texture, TextureResource},
+    scene::graph::Graph,
 };
+use fyrox_graph::SceneGraph;
 use fyrox_texture::{
     TextureKind, TextureMagnificationFilter, TextureMinificationFilter, TexturePixelKind,
     TextureWrapMode,
@@ -46,11 +59,82 @@ pub struct TextureRenderData {
     pub gpu_sampler: GpuSampler,
     modifications_counter: u64,
     sampler_modifications_counter: u64,
+    streaming: Option<TextureStreamingState>,
+}
+
+/// Settings of the texture streaming. Texture streaming keeps only a part of the mip chain of a
+/// texture in GPU memory. Textures are uploaded with low-resolution mips first and then higher mips
+/// are streamed in depending on the on-screen size of the objects that use the texture. When the
+/// total size of the streamed textures exceeds the memory budget, the highest mips of the textures
+/// with the smallest on-screen size are evicted first.
+///
+/// Texture streaming is disabled by default. It could be enabled at any time after the graphics
+/// context is initialized, ideally before any texture is used, because already uploaded textures
+/// keep their full mip chain:
+///
+/// ```rust,no_run
+/// # use fyrox_impl::{
+/// #     engine::{Engine, GraphicsContext},
+/// #     renderer::cache::texture::TextureStreamingSettings,
+/// # };
+/// fn enable_texture_streaming(engine: &mut Engine) {
+///     if let GraphicsContext::Initialized(ref mut graphics_context) = engine.graphics_context {
+///         graphics_context
+///             .renderer
+///             .texture_cache
+///             .set_streaming_settings(TextureStreamingSettings {
+///                 enabled: true,
+///                 ..Default::default()
+///             });
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureStreamingSettings {
+    /// Defines whether the texture streaming is enabled or not. When disabled, every texture
+    /// is uploaded with its full mip chain. Default is `false`.
+    pub enabled: bool,
+    /// Maximum size (in pixels) of the largest side of the first mip level that will be uploaded
+    /// when a texture is first used. Higher mips will be streamed in later on demand.
+    pub initial_resident_size: u32,
+    /// Maximum amount of GPU memory (in bytes) that can be used by the streamed textures. `None`
+    /// means that there's no limit.
+    pub memory_budget: Option<usize>,
+    /// Maximum amount of textures that can stream in their higher mips per single update. This
+    /// value is used to spread the uploading work across multiple frames.
+    pub max_uploads_per_update: usize,
+}
+
+impl Default for TextureStreamingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            initial_resident_size: 64,
+            memory_budget: Some(1024 * 1024 * 1024),
+            max_uploads_per_update: 8,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct TextureStreamingState {
+    resource: TextureResource,
+    /// Index of the first mip level that is uploaded to GPU.
+    resident_mip: usize,
+    /// Index of the highest detail mip requested from the last update.
+    requested_mip: Option<usize>,
+    /// The largest on-screen size (in pixels) that was requested since the last update.
+    footprint: f32,
+    /// Whether the texture was used without any footprint information (for example, by the UI).
+    used: bool,
+    resident_bytes: usize,
 }
 
 #[derive(Default)]
 pub struct TextureCache {
     cache: TemporaryCache<TextureRenderData>,
+    streaming_settings: TextureStreamingSettings,
+    streaming_statistics: TextureStreamingStatistics,
 }
 
 fn convert_texture_kind(v: TextureKind) -> GpuTextureKind {
@@ -96,6 +180,11 @@ pub fn convert_pixel_kind(texture_kind: TexturePixelKind) -> PixelKind {
         TexturePixelKind::DXT5RGBA => PixelKind::DXT5RGBA,
         TexturePixelKind::R8RGTC => PixelKind::R8RGTC,
         TexturePixelKind::RG8RGTC => PixelKind::RG8RGTC,
+        TexturePixelKind::BC6HRGBF => PixelKind::BC6HRGBF,
+        TexturePixelKind::BC7RGBA => PixelKind::BC7RGBA,
+        TexturePixelKind::ETC2RGB8 => PixelKind::ETC2RGB8,
+        TexturePixelKind::ETC2RGBA8 => PixelKind::ETC2RGBA8,
+        TexturePixelKind::ASTC4x4RGBA => PixelKind::ASTC4x4RGBA,
         TexturePixelKind::RGB32F => PixelKind::RGB32F,
         TexturePixelKind::RGBA32F => PixelKind::RGBA32F,
         TexturePixelKind::Luminance8 => PixelKind::L8,
@@ -154,11 +243,91 @@ fn create_sampler(
     })
 }
 
+/// Returns the index of the highest mip level that can be used as the first resident mip of the
+/// given texture. Only rectangular textures support streaming, `None` is returned for all other
+/// texture kinds.
+fn max_streamable_mip(texture: &Texture) -> Option<usize> {
+    let TextureKind::Rectangle { width, height } = texture.kind() else {
+        return None;
+    };
+    let mip_count = texture.mip_count() as usize;
+    if mip_count <= 1 {
+        return None;
+    }
+    // Compressed textures are stored in 4x4 blocks, so keep at least one full block.
+    let min_size = if convert_pixel_kind(texture.pixel_kind()).is_compressed() {
+        4
+    } else {
+        1
+    };
+    let smallest_side = width.min(height);
+    if smallest_side < min_size {
+        return None;
+    }
+    let max_mip = (smallest_side / min_size).ilog2() as usize;
+    Some(max_mip.min(mip_count - 1))
+}
+
+/// Calculates the index of the first mip level, that has the largest side less or equal than the
+/// given size.
+fn mip_for_size(texture_size: u32, size: f32, max_mip: usize) -> usize {
+    let ratio = texture_size as f32 / size.max(1.0);
+    if ratio <= 1.0 {
+        0
+    } else {
+        (ratio.log2().floor() as usize).min(max_mip)
+    }
+}
+
+/// Returns the GPU texture kind, the mip count and the data of the mip chain of the given texture,
+/// that starts from the given mip level.
+fn mip_chain(texture: &Texture, first_mip: usize) -> (GpuTextureKind, usize, &[u8]) {
+    let mip_count = texture.mip_count() as usize;
+    match texture.kind() {
+        TextureKind::Rectangle { width, height } if first_mip > 0 => {
+            let pixel_kind = convert_pixel_kind(texture.pixel_kind());
+            let offset = (0..first_mip)
+                .map(|mip| {
+                    image_2d_size_bytes(
+                        pixel_kind,
+                        width.wrapping_shr(mip as u32) as usize,
+                        height.wrapping_shr(mip as u32) as usize,
+                    )
+                })
+                .sum::<usize>();
+            (
+                GpuTextureKind::Rectangle {
+                    width: width.wrapping_shr(first_mip as u32) as usize,
+                    height: height.wrapping_shr(first_mip as u32) as usize,
+                },
+                mip_count - first_mip,
+                &texture.data()[offset..],
+            )
+        }
+        kind => (convert_texture_kind(kind), mip_count, texture.data()),
+    }
+}
+
+fn mip_chain_size_bytes(texture: &Texture, first_mip: usize) -> usize {
+    let (_, _, data) = mip_chain(texture, first_mip);
+    data.len()
+}
+
 fn create_gpu_texture(
     server: &dyn GraphicsServer,
     resource_manager: &ResourceManager,
     uuid: &Uuid,
     texture: &Texture,
+) -> Result<TextureRenderData, FrameworkError> {
+    create_gpu_texture_from_mip(server, resource_manager, uuid, texture, 0)
+}
+
+fn create_gpu_texture_from_mip(
+    server: &dyn GraphicsServer,
+    resource_manager: &ResourceManager,
+    uuid: &Uuid,
+    texture: &Texture,
+    first_mip: usize,
 ) -> Result<TextureRenderData, FrameworkError> {
     let path = resource_manager
         .try_get_state(Duration::from_millis(1))
@@ -168,14 +337,15 @@ fn create_gpu_texture(
         .map(|path| path.to_string_lossy())
         .unwrap_or_else(|| Cow::Borrowed(""));
 
+    let (kind, mip_count, data) = mip_chain(texture, first_mip);
     let gpu_texture = server.create_texture(GpuTextureDescriptor {
         name: &name,
-        kind: convert_texture_kind(texture.kind()),
+        kind,
         pixel_kind: convert_pixel_kind(texture.pixel_kind()),
-        mip_count: texture.mip_count() as usize,
-        data: Some(texture.data()),
-        base_level: texture.base_level(),
-        max_level: texture.max_level(),
+        mip_count,
+        data: Some(data),
+        base_level: texture.base_level().saturating_sub(first_mip),
+        max_level: texture.max_level().saturating_sub(first_mip),
     })?;
 
     Ok(TextureRenderData {
@@ -183,9 +353,98 @@ fn create_gpu_texture(
         gpu_sampler: create_sampler(server, texture)?,
         modifications_counter: texture.modifications_count(),
         sampler_modifications_counter: texture.sampler_modifications_count(),
+        streaming: None,
     })
 }
 
+/// Creates a GPU texture with only low-resolution mips uploaded, the rest of the mips will be
+/// streamed in later. Falls back to the full upload if the texture cannot be streamed.
+fn create_streamed_gpu_texture(
+    server: &dyn GraphicsServer,
+    resource_manager: &ResourceManager,
+    settings: &TextureStreamingSettings,
+    resource: &TextureResource,
+    texture: &Texture,
+) -> Result<TextureRenderData, FrameworkError> {
+    let uuid = resource.resource_uuid();
+    let (Some(max_mip), Some(size), true) = (
+        max_streamable_mip(texture),
+        texture.kind().rectangle_size(),
+        settings.enabled,
+    ) else {
+        return create_gpu_texture(server, resource_manager, &uuid, texture);
+    };
+    let first_mip = mip_for_size(
+        size.x.max(size.y),
+        settings.initial_resident_size as f32,
+        max_mip,
+    );
+    let mut data =
+        create_gpu_texture_from_mip(server, resource_manager, &uuid, texture, first_mip)?;
+    data.streaming = Some(TextureStreamingState {
+        resource: resource.clone(),
+        resident_mip: first_mip,
+        requested_mip: None,
+        footprint: 0.0,
+        used: false,
+        resident_bytes: mip_chain_size_bytes(texture, first_mip),
+    });
+    Ok(data)
+}
+
+/// Calculates approximate on-screen size (in pixels) of a sphere with the given world-space center
+/// and radius.
+fn screen_space_footprint(
+    bundle_storage: &RenderDataBundleStorage,
+    viewport: Rect<i32>,
+    center: Point3<f32>,
+    radius: f32,
+) -> f32 {
+    let observer = &bundle_storage.observer_position;
+    let projection = &observer.projection_matrix;
+    let scale = projection[(1, 1)] * viewport.h() as f32 * 0.5;
+    let diameter = 2.0 * radius;
+    if projection[(3, 3)] == 0.0 {
+        // Perspective projection.
+        let view_space_center = observer.view_matrix.transform_point(&center);
+        let distance = (-view_space_center.z - radius).max(observer.z_near);
+        diameter * scale / distance
+    } else {
+        // Orthographic projection.
+        diameter * scale
+    }
+}
+
+struct BudgetCandidate {
+    index: usize,
+    resident_mip: usize,
+    desired_mip: usize,
+    max_mip: usize,
+    footprint: f32,
+    /// Sizes of the mip chain (in bytes) starting at the respective mip level.
+    chain_sizes: Vec<usize>,
+}
+
+/// Increases the desired mip level of the candidates with the smallest footprint first, until the
+/// total size of the textures fits into the given budget (or there's nothing more to evict).
+fn fit_into_budget(candidates: &mut [BudgetCandidate], budget: usize) {
+    let mut total = candidates
+        .iter()
+        .map(|c| c.chain_sizes[c.desired_mip])
+        .sum::<usize>();
+    candidates.sort_by(|a, b| a.footprint.total_cmp(&b.footprint));
+    for candidate in candidates.iter_mut() {
+        while total > budget && candidate.desired_mip < candidate.max_mip {
+            total -= candidate.chain_sizes[candidate.desired_mip];
+            candidate.desired_mip += 1;
+            total += candidate.chain_sizes[candidate.desired_mip];
+        }
+        if total <= budget {
+            break;
+        }
+    }
+}
+
 impl TextureCache {
     /// Unconditionally uploads requested texture into GPU memory, previous GPU texture will be automatically
     /// destroyed.
@@ -195,13 +454,22 @@ impl TextureCache {
         resource_manager: &ResourceManager,
         texture: &TextureResource,
     ) -> Result<(), FrameworkError> {
-        let uuid = texture.resource_uuid();
+        let resource = texture;
         let texture = texture.state();
         if let Some(texture) = texture.data_ref() {
+            let settings = &self.streaming_settings;
             self.cache.get_entry_mut_or_insert_with(
                 &texture.cache_index,
                 Default::default(),
-                || create_gpu_texture(server, resource_manager, &uuid, texture),
+                || {
+                    create_streamed_gpu_texture(
+                        server,
+                        resource_manager,
+                        settings,
+                        resource,
+                        texture,
+                    )
+                },
             )?;
             Ok(())
         } else {
@@ -231,11 +499,22 @@ impl TextureCache {
                     // Data might change from last frame, so we have to check it and upload new if so.
                     let modifications_count = texture.modifications_count();
                     if entry.modifications_counter != modifications_count {
+                        let first_mip = match entry.streaming {
+                            Some(ref mut streaming) => {
+                                let first_mip = max_streamable_mip(texture)
+                                    .map_or(0, |max| streaming.resident_mip.min(max));
+                                streaming.resident_mip = first_mip;
+                                streaming.resident_bytes = mip_chain_size_bytes(texture, first_mip);
+                                first_mip
+                            }
+                            None => 0,
+                        };
+                        let (kind, mip_count, data) = mip_chain(texture, first_mip);
                         if let Err(e) = entry.gpu_texture.set_data(
-                            convert_texture_kind(texture.kind()),
+                            kind,
                             convert_pixel_kind(texture.pixel_kind()),
-                            texture.mip_count() as usize,
-                            Some(texture.data()),
+                            mip_count,
+                            Some(data),
                         ) {
                             Log::writeln(
                                 MessageKind::Error,
@@ -251,6 +530,10 @@ impl TextureCache {
                         entry.gpu_sampler = create_sampler(server, texture).unwrap();
                     }
 
+                    if let Some(ref mut streaming) = entry.streaming {
+                        streaming.used = true;
+                    }
+
                     return Some(entry);
                 }
                 Err(e) => {
@@ -270,6 +553,231 @@ impl TextureCache {
         self.cache.update(dt)
     }
 
+    /// Sets new texture streaming settings. Keep in mind, that the textures that are already
+    /// uploaded to GPU are not affected by the [`TextureStreamingSettings::enabled`] flag.
+    pub fn set_streaming_settings(&mut self, settings: TextureStreamingSettings) {
+        self.streaming_settings = settings;
+    }
+
+    /// Returns current texture streaming settings.
+    pub fn streaming_settings(&self) -> &TextureStreamingSettings {
+        &self.streaming_settings
+    }
+
+    /// Returns the texture streaming statistics collected during the last streaming update.
+    pub fn streaming_statistics(&self) -> TextureStreamingStatistics {
+        self.streaming_statistics
+    }
+
+    /// Requests the texture to have enough mip levels resident in GPU memory to be drawn with the
+    /// given on-screen size (in pixels). If the texture is not in the cache yet, it will be
+    /// uploaded with low-resolution mips only.
+    pub fn request_footprint(
+        &mut self,
+        server: &dyn GraphicsServer,
+        resource_manager: &ResourceManager,
+        texture_resource: &TextureResource,
+        footprint: f32,
+    ) {
+        if !self.streaming_settings.enabled {
+            return;
+        }
+        let texture_data_guard = texture_resource.state();
+        let Some(texture) = texture_data_guard.data_ref() else {
+            return;
+        };
+        let settings = &self.streaming_settings;
+        let Ok(entry) =
+            self.cache
+                .get_mut_or_insert_with(&texture.cache_index, Default::default(), || {
+                    create_streamed_gpu_texture(
+                        server,
+                        resource_manager,
+                        settings,
+                        texture_resource,
+                        texture,
+                    )
+                })
+        else {
+            return;
+        };
+        let (Some(streaming), Some(max_mip), Some(size)) = (
+            entry.streaming.as_mut(),
+            max_streamable_mip(texture),
+            texture.kind().rectangle_size(),
+        ) else {
+            return;
+        };
+        let mip = mip_for_size(size.x.max(size.y), footprint, max_mip);
+        streaming.requested_mip = Some(streaming.requested_mip.map_or(mip, |m| m.min(mip)));
+        streaming.footprint = streaming.footprint.max(footprint);
+    }
+
+    /// Calculates on-screen size of every instance in the given bundle storage and requests the
+    /// respective mip levels for every texture used by the instances.
+    pub fn request_bundle_footprints(
+        &mut self,
+        server: &dyn GraphicsServer,
+        resource_manager: &ResourceManager,
+        bundle_storage: &RenderDataBundleStorage,
+        graph: &Graph,
+        viewport: Rect<i32>,
+    ) {
+        if !self.streaming_settings.enabled {
+            return;
+        }
+        for bundle in bundle_storage.bundles.iter() {
+            let footprint = bundle
+                .instances
+                .iter()
+                .filter_map(|instance| graph.try_get(instance.node_handle).ok())
+                .map(|node| {
+                    let aabb = node.world_bounding_box();
+                    screen_space_footprint(
+                        bundle_storage,
+                        viewport,
+                        Point3::from(aabb.center()),
+                        aabb.half_extents().norm(),
+                    )
+                })
+                .fold(None, |acc: Option<f32>, f| {
+                    Some(acc.map_or(f, |a| a.max(f)))
+                });
+            let Some(footprint) = footprint else {
+                continue;
+            };
+            let material_state = bundle.material.state();
+            let Some(material) = material_state.data_ref() else {
+                continue;
+            };
+            for binding in material.bindings().values() {
+                if let MaterialResourceBinding::Texture(binding) = binding {
+                    if let Some(texture) = binding.value.as_ref() {
+                        self.request_footprint(server, resource_manager, texture, footprint);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Streams in or evicts mip levels of the textures according to the footprint requests made
+    /// since the last update and the memory budget.
+    pub fn update_streaming(
+        &mut self,
+        server: &dyn GraphicsServer,
+        resource_manager: &ResourceManager,
+    ) {
+        let settings = self.streaming_settings.clone();
+        let mut stats = TextureStreamingStatistics {
+            memory_budget: settings.memory_budget,
+            ..Default::default()
+        };
+
+        let mut candidates = Vec::new();
+        for index in 0..self.cache.buffer.len() {
+            let Some(streaming) = self
+                .cache
+                .buffer
+                .get_mut_raw(index)
+                .and_then(|entry| entry.value.streaming.as_mut())
+            else {
+                continue;
+            };
+            let resource = streaming.resource.clone();
+            let state = resource.state();
+            let Some(texture) = state.data_ref() else {
+                continue;
+            };
+            let Some(max_mip) = max_streamable_mip(texture) else {
+                continue;
+            };
+            let (desired_mip, footprint) = match streaming.requested_mip.take() {
+                Some(mip) => (mip, streaming.footprint),
+                // Textures used without any footprint information (UI, for example) must be
+                // fully resident, the rest keep their current state.
+                None if streaming.used => (0, f32::MAX),
+                None => (streaming.resident_mip, streaming.footprint),
+            };
+            streaming.used = false;
+            streaming.footprint = 0.0;
+            candidates.push(BudgetCandidate {
+                index,
+                resident_mip: streaming.resident_mip,
+                desired_mip: desired_mip.min(max_mip),
+                max_mip,
+                footprint,
+                chain_sizes: (0..=max_mip)
+                    .map(|mip| mip_chain_size_bytes(texture, mip))
+                    .collect(),
+            });
+        }
+
+        if let Some(budget) = settings.memory_budget {
+            fit_into_budget(&mut candidates, budget);
+        }
+
+        // Evictions go first to free memory, then the uploads of textures with the largest
+        // footprint.
+        candidates.sort_by(|a, b| {
+            let a_evicts = a.desired_mip > a.resident_mip;
+            let b_evicts = b.desired_mip > b.resident_mip;
+            b_evicts
+                .cmp(&a_evicts)
+                .then_with(|| b.footprint.total_cmp(&a.footprint))
+        });
+
+        let mut uploads = 0;
+        for candidate in candidates.iter() {
+            let Some(entry) = self.cache.buffer.get_mut_raw(candidate.index) else {
+                continue;
+            };
+            let resident_mip = candidate.resident_mip;
+            let resource = entry.value.streaming.as_ref().unwrap().resource.clone();
+            stats.streamed_textures += 1;
+            stats.requested_memory += candidate.chain_sizes[candidate.desired_mip];
+
+            if candidate.desired_mip != resident_mip
+                && (candidate.desired_mip > resident_mip
+                    || uploads < settings.max_uploads_per_update)
+            {
+                let state = resource.state();
+                if let Some(texture) = state.data_ref() {
+                    match create_gpu_texture_from_mip(
+                        server,
+                        resource_manager,
+                        &resource.resource_uuid(),
+                        texture,
+                        candidate.desired_mip,
+                    ) {
+                        Ok(data) => {
+                            entry.value.gpu_texture = data.gpu_texture;
+                            let streaming = entry.value.streaming.as_mut().unwrap();
+                            if candidate.desired_mip < resident_mip {
+                                uploads += 1;
+                                stats.streamed_in += 1;
+                            } else {
+                                stats.evicted += 1;
+                            }
+                            streaming.resident_mip = candidate.desired_mip;
+                            streaming.resident_bytes = candidate.chain_sizes[candidate.desired_mip];
+                        }
+                        Err(e) => Log::err(format!(
+                            "Unable to stream texture mip levels. Reason: {e:?}"
+                        )),
+                    }
+                }
+            }
+
+            let streaming = entry.value.streaming.as_ref().unwrap();
+            if streaming.resident_mip > candidate.desired_mip {
+                stats.pending_textures += 1;
+            }
+            stats.resident_memory += streaming.resident_bytes;
+        }
+
+        self.streaming_statistics = stats;
+    }
+
     pub fn clear(&mut self) {
         self.cache.clear();
     }
@@ -303,6 +811,7 @@ impl TextureCache {
                     gpu_sampler: create_sampler(server, &data)?,
                     modifications_counter: data.modifications_count(),
                     sampler_modifications_counter: data.sampler_modifications_count(),
+                    streaming: None,
                 },
                 index,
                 TimeToLive::default(),
@@ -311,3 +820,69 @@ impl TextureCache {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        fit_into_budget, max_streamable_mip, mip_chain, mip_chain_size_bytes, mip_for_size,
+        BudgetCandidate,
+    };
+    use crate::{graphics::gpu_texture::GpuTextureKind, resource::texture::Texture};
+
+    #[test]
+    fn test_mip_for_size() {
+        assert_eq!(mip_for_size(1024, 2048.0, 10), 0);
+        assert_eq!(mip_for_size(1024, 1024.0, 10), 0);
+        assert_eq!(mip_for_size(1024, 500.0, 10), 1);
+        assert_eq!(mip_for_size(1024, 64.0, 10), 4);
+        assert_eq!(mip_for_size(1024, 0.0, 3), 3);
+    }
+
+    #[test]
+    fn test_mip_chain() {
+        let mut png = Vec::new();
+        image::RgbaImage::new(4, 4)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let texture = Texture::load_from_memory(&png, Default::default()).unwrap();
+        // 4x4 + 2x2 + 1x1 RGBA8 mips.
+        assert_eq!(texture.mip_count(), 3);
+        assert_eq!(max_streamable_mip(&texture), Some(2));
+
+        let (kind, mip_count, data) = mip_chain(&texture, 1);
+        assert!(matches!(
+            kind,
+            GpuTextureKind::Rectangle {
+                width: 2,
+                height: 2
+            }
+        ));
+        assert_eq!(mip_count, 2);
+        assert_eq!(data, &texture.data()[16 * 4..]);
+        assert_eq!(mip_chain_size_bytes(&texture, 2), 4);
+    }
+
+    #[test]
+    fn test_fit_into_budget() {
+        let candidate = |index, footprint| BudgetCandidate {
+            index,
+            resident_mip: 0,
+            desired_mip: 0,
+            max_mip: 2,
+            footprint,
+            chain_sizes: vec![84, 20, 4],
+        };
+        let mut candidates = vec![candidate(0, 100.0), candidate(1, 10.0)];
+        fit_into_budget(&mut candidates, 104);
+        let find = |index| {
+            candidates
+                .iter()
+                .find(|c| c.index == index)
+                .unwrap()
+                .desired_mip
+        };
+        // The smallest texture on screen gives up its mips first.
+        assert_eq!(find(0), 0);
+        assert_eq!(find(1), 1);
+    }
+}
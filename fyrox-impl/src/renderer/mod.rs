@@ -934,6 +934,8 @@ impl Renderer {
             }
         }
 
+        self.texture_cache
+            .update_streaming(&*self.server, resource_manager);
         self.texture_cache.update(dt);
     }
 
@@ -1046,6 +1048,14 @@ impl Renderer {
             bundle_storage.environment_map = None;
         }
 
+        self.texture_cache.request_bundle_footprints(
+            server,
+            resource_manager,
+            &bundle_storage,
+            &scene.graph,
+            observer.viewport,
+        );
+
         server.set_polygon_fill_mode(
             PolygonFace::FrontAndBack,
             scene.rendering_options.polygon_rasterization_mode,
@@ -1485,6 +1495,7 @@ impl Renderer {
 
         self.statistics.geometry_cache_size = self.geometry_cache.alive_count();
         self.statistics.texture_cache_size = self.texture_cache.alive_count();
+        self.statistics.texture_streaming = self.texture_cache.streaming_statistics();
         self.statistics.shader_cache_size = self.shader_cache.alive_count();
         self.statistics.uniform_buffer_cache_size = self.uniform_buffer_cache.alive_count();
         self.statistics.dynamic_surface_cache_size = self.dynamic_surface_cache.alive_count();
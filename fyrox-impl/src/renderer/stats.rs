@@ -75,6 +75,54 @@ impl Display for LightingStatistics {
     }
 }
 
+/// Texture streaming statistics.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TextureStreamingStatistics {
+    /// How many textures are managed by the texture streaming.
+    pub streamed_textures: usize,
+    /// How many textures have fewer mip levels in GPU memory than requested.
+    pub pending_textures: usize,
+    /// Total size (in bytes) of the mip levels of the streamed textures, that are in GPU memory.
+    pub resident_memory: usize,
+    /// Total size (in bytes) of the mip levels of the streamed textures, that were requested to be
+    /// in GPU memory. Could be less than the actual demand, if the demand exceeds the budget.
+    pub requested_memory: usize,
+    /// Memory budget (in bytes) of the texture streaming. `None` means unlimited budget.
+    pub memory_budget: Option<usize>,
+    /// How many textures streamed in their higher mip levels during the last update.
+    pub streamed_in: usize,
+    /// How many textures evicted their higher mip levels during the last update.
+    pub evicted: usize,
+}
+
+impl Display for TextureStreamingStatistics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let to_mb = |bytes: usize| bytes as f32 / (1024.0 * 1024.0);
+        write!(
+            f,
+            "Texture Streaming Statistics:\n\
+            \tStreamed Textures: {}\n\
+            \tPending Textures: {}\n\
+            \tResident Memory: {:.2} Mb\n\
+            \tRequested Memory: {:.2} Mb\n",
+            self.streamed_textures,
+            self.pending_textures,
+            to_mb(self.resident_memory),
+            to_mb(self.requested_memory),
+        )?;
+        match self.memory_budget {
+            Some(budget) => writeln!(f, "\tMemory Budget: {:.2} Mb", to_mb(budget))?,
+            None => writeln!(f, "\tMemory Budget: Unlimited")?,
+        }
+        write!(
+            f,
+            "\tStreamed In: {}\n\
+            \tEvicted: {}\n",
+            self.streamed_in, self.evicted
+        )
+    }
+}
+
 /// Renderer statistics for a scene.
 #[derive(Debug, Copy, Clone, Default)]
 pub struct SceneStatistics {
@@ -150,6 +198,8 @@ pub struct Statistics {
     pub shader_cache_size: usize,
     /// The total number of uniform buffers in the cache.
     pub uniform_buffer_cache_size: usize,
+    /// Statistics of the texture streaming.
+    pub texture_streaming: TextureStreamingStatistics,
     pub(super) frame_counter: usize,
     pub(super) frame_start_time: instant::Instant,
     pub(super) last_fps_commit_time: instant::Instant,
@@ -176,6 +226,7 @@ impl Display for Statistics {
         let dynamic_surface_cache_size = self.dynamic_surface_cache_size;
         let shader_cache_size = self.shader_cache_size;
         let uniform_buffer_cache_size = self.uniform_buffer_cache_size;
+        let texture_streaming = &self.texture_streaming;
         write!(
             f,
             "FPS: {fps}\n\
@@ -188,7 +239,8 @@ impl Display for Statistics {
             Geometry Cache Size: {geometry_cache_size}\n\
             Dynamic Surface Cache Size: {dynamic_surface_cache_size}\n\
             Shader Cache Size: {shader_cache_size}\n
-            Uniform Buffer Cache Size: {uniform_buffer_cache_size}\n",
+            Uniform Buffer Cache Size: {uniform_buffer_cache_size}\n\
+            {texture_streaming}",
         )
     }
 }
@@ -213,6 +265,7 @@ impl Default for Statistics {
             dynamic_surface_cache_size: 0,
             shader_cache_size: 0,
             uniform_buffer_cache_size: 0,
+            texture_streaming: Default::default(),
             frame_counter: 0,
             frame_start_time: instant::Instant::now(),
             last_fps_commit_time: instant::Instant::now(),
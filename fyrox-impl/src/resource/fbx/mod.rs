@@ -55,7 +55,7 @@ use crate::{
                 FbxComponent, FbxMapping, FbxScene,
             },
         },
-        model::{MaterialSearchOptions, ModelImportOptions},
+        model::ModelImportOptions,
         texture::{Texture, TextureImportOptions, TextureResource, TextureResourceExtension},
     },
     scene::{
@@ -310,44 +310,10 @@ async fn create_materials(
 
                 if let Some(filename) = path.file_name() {
                     let texture_path = if texture.content.is_empty() {
-                        match model_import_options.material_search_options {
-                            MaterialSearchOptions::MaterialsDirectory(ref directory) => {
-                                Some(directory.join(filename))
-                            }
-                            MaterialSearchOptions::RecursiveUp => {
-                                let mut texture_path = None;
-                                let mut path = model_path.to_owned();
-                                while let Some(parent) = path.parent() {
-                                    let candidate = parent.join(filename);
-                                    if io.exists(&candidate).await {
-                                        texture_path = Some(candidate);
-                                        break;
-                                    }
-                                    path.pop();
-                                }
-                                texture_path
-                            }
-                            MaterialSearchOptions::WorkingDirectory => {
-                                let mut texture_path = None;
-
-                                let path = Path::new(".");
-
-                                if let Ok(iter) = io.walk_directory(path, usize::MAX).await {
-                                    for dir in iter {
-                                        if io.is_dir(&dir).await {
-                                            let candidate = dir.join(filename);
-                                            if candidate.exists() {
-                                                texture_path = Some(candidate);
-                                                break;
-                                            }
-                                        }
-                                    }
-                                }
-
-                                texture_path
-                            }
-                            MaterialSearchOptions::UsePathDirectly => Some(path.clone()),
-                        }
+                        model_import_options
+                            .material_search_options
+                            .find_file(&path, model_path, &*io)
+                            .await
                     } else {
                         Some(path.clone())
                     };
@@ -0,0 +1,1700 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Exporter of scenes (or parts of them) to glTF 2.0 format. See [`GltfExporter`] docs for more
+//! info.
+
+use crate::{
+    asset::manager::ResourceManager,
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector3, Vector4},
+        log::Log,
+        pool::Handle,
+    },
+    fxhash::{FxHashMap, FxHashSet},
+    graph::SceneGraph,
+    material::MaterialResource,
+    resource::texture::{
+        TextureKind, TextureMagnificationFilter, TextureMinificationFilter, TexturePixelKind,
+        TextureResource, TextureWrapMode,
+    },
+    scene::{
+        animation::AnimationPlayer,
+        camera::{Camera, Projection},
+        graph::Graph,
+        light::{directional::DirectionalLight, point::PointLight, spot::SpotLight, BaseLight},
+        mesh::{
+            buffer::{
+                VertexAttribute, VertexAttributeDataType, VertexAttributeUsage, VertexBuffer,
+                VertexReadTrait,
+            },
+            surface::{Surface, SurfaceData},
+            Mesh,
+        },
+        node::Node,
+        transform::Transform,
+    },
+};
+use base64::{engine::general_purpose::STANDARD as Base64Engine, Engine as _};
+use fyrox_animation::value::{TrackValue, ValueBinding};
+use gltf::binary::{Glb, Header};
+use image::{ColorType, ImageFormat};
+use serde_json::{json, Map, Value};
+use std::{
+    borrow::Cow,
+    fmt::{Display, Formatter},
+    io::Cursor,
+    path::{Component, Path, PathBuf},
+};
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+const UNSIGNED_BYTE: u32 = 5121;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+const KHR_LIGHTS_PUNCTUAL: &str = "KHR_lights_punctual";
+const KHR_MATERIALS_EMISSIVE_STRENGTH: &str = "KHR_materials_emissive_strength";
+
+/// All possible errors that may occur during glTF export.
+#[derive(Debug)]
+pub enum GltfExportError {
+    /// An error occurred while writing a file.
+    Io(std::io::Error),
+    /// Unable to serialize the document.
+    Json(serde_json::Error),
+    /// Unable to write binary glTF.
+    Gltf(gltf::Error),
+    /// The root node handle does not point to a node of the graph.
+    InvalidRoot,
+}
+
+impl std::error::Error for GltfExportError {}
+
+impl Display for GltfExportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GltfExportError::Io(error) => Display::fmt(error, f),
+            GltfExportError::Json(error) => Display::fmt(error, f),
+            GltfExportError::Gltf(error) => Display::fmt(error, f),
+            GltfExportError::InvalidRoot => f.write_str("Invalid root node"),
+        }
+    }
+}
+
+impl From<std::io::Error> for GltfExportError {
+    fn from(error: std::io::Error) -> Self {
+        GltfExportError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for GltfExportError {
+    fn from(error: serde_json::Error) -> Self {
+        GltfExportError::Json(error)
+    }
+}
+
+impl From<gltf::Error> for GltfExportError {
+    fn from(error: gltf::Error) -> Self {
+        GltfExportError::Gltf(error)
+    }
+}
+
+/// Container format of an exported document.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum GltfExportFormat {
+    /// JSON document (`.gltf`) with geometry and animations stored in a separate binary buffer
+    /// (`.bin`). Textures are referenced by relative paths when possible.
+    #[default]
+    Gltf,
+    /// Single binary file (`.glb`), that contains the document, all the buffers and the textures.
+    Glb,
+}
+
+impl GltfExportFormat {
+    /// Selects the format by the extension of the given path. `glb` files are binary, everything
+    /// else is JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("glb") => Self::Glb,
+            _ => Self::Gltf,
+        }
+    }
+}
+
+/// Exports a scene graph, or any part of it, to glTF 2.0 format. The following is exported:
+///
+/// - Node hierarchy with local transforms. Pre- and post-rotations of nodes are baked into the
+///   rotation.
+/// - Meshes. Every surface becomes a primitive with positions, normals, tangents, two sets of
+///   texture coordinates, vertex colors and skinning data (if any).
+/// - Skins. Bones of all surfaces of a mesh are merged into a single skin, inverse bind pose
+///   matrices are taken from the bones.
+/// - Materials, mapped to PBR metallic-roughness model. Materials of the standard shaders as well
+///   as materials imported from glTF are supported. Textures are embedded into the document if
+///   they cannot be referenced by a path.
+/// - Animations of every [`AnimationPlayer`] in the hierarchy. Position, rotation and scale tracks
+///   are resampled with linear interpolation, property tracks are ignored.
+/// - Cameras and lights (using `KHR_lights_punctual` extension).
+///
+/// Both the engine and glTF use Y axis as up axis, so no coordinate system conversion is performed,
+/// which means that a model, that was imported from glTF and then exported back, will keep its
+/// orientation.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// # use fyrox_impl::{resource::gltf::export::GltfExporter, scene::Scene};
+/// # use std::path::Path;
+/// fn export(scene: &Scene) {
+///     GltfExporter::new(&scene.graph)
+///         .with_animation_sample_rate(60.0)
+///         .export_to_file(Path::new("level.glb"))
+///         .unwrap();
+/// }
+/// ```
+pub struct GltfExporter<'a> {
+    graph: &'a Graph,
+    root: Handle<Node>,
+    resource_manager: Option<&'a ResourceManager>,
+    animation_sample_rate: f32,
+}
+
+impl<'a> GltfExporter<'a> {
+    /// Creates a new exporter, that will export the entire graph.
+    pub fn new(graph: &'a Graph) -> Self {
+        Self {
+            graph,
+            root: graph.get_root(),
+            resource_manager: None,
+            animation_sample_rate: 30.0,
+        }
+    }
+
+    /// Sets a root of the hierarchy to export. The root node itself is exported, unless it is
+    /// the root of the graph.
+    pub fn with_root(mut self, root: Handle<Node>) -> Self {
+        self.root = root;
+        self
+    }
+
+    /// Sets a resource manager, that will be used to fetch paths of the textures. Without it every
+    /// texture will be embedded into the document.
+    pub fn with_resource_manager(mut self, resource_manager: &'a ResourceManager) -> Self {
+        self.resource_manager = Some(resource_manager);
+        self
+    }
+
+    /// Sets the amount of samples per second, that will be used to resample animation tracks.
+    /// Key frames of the tracks are always sampled. Default is 30.
+    pub fn with_animation_sample_rate(mut self, sample_rate: f32) -> Self {
+        self.animation_sample_rate = sample_rate;
+        self
+    }
+
+    /// Exports the hierarchy to a file. The format is selected by the extension of the file, see
+    /// [`GltfExportFormat::from_path`]. The binary buffer of `.gltf` files is written next to the
+    /// file, using the same name with `.bin` extension.
+    pub fn export_to_file(&self, path: &Path) -> Result<(), GltfExportError> {
+        let directory = path.parent().unwrap_or(Path::new(""));
+        match GltfExportFormat::from_path(path) {
+            GltfExportFormat::Glb => {
+                let bytes = self.export_glb()?;
+                std::fs::write(path, bytes)?;
+            }
+            GltfExportFormat::Gltf => {
+                let bin_path = path.with_extension("bin");
+                let bin_uri = bin_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let (json, bin) = self.export(GltfExportFormat::Gltf, Some(directory), &bin_uri)?;
+                std::fs::write(path, json)?;
+                if !bin.is_empty() {
+                    std::fs::write(bin_path, bin)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Exports the hierarchy to a binary glTF file and returns its content.
+    pub fn export_glb(&self) -> Result<Vec<u8>, GltfExportError> {
+        let (json, bin) = self.export(GltfExportFormat::Glb, None, "")?;
+        let glb = Glb {
+            header: Header {
+                magic: *b"glTF",
+                version: 2,
+                // Calculated by the writer.
+                length: 0,
+            },
+            json: Cow::Owned(json.into_bytes()),
+            bin: (!bin.is_empty()).then_some(Cow::Owned(bin)),
+        };
+        Ok(glb.to_vec()?)
+    }
+
+    /// Exports the hierarchy to a JSON document and a binary buffer, that is referenced by the
+    /// given uri. Textures are referenced relative to `directory` (if any), textures without a path
+    /// are embedded into the document as data uris.
+    pub fn export_gltf(
+        &self,
+        directory: Option<&Path>,
+        bin_uri: &str,
+    ) -> Result<(String, Vec<u8>), GltfExportError> {
+        self.export(GltfExportFormat::Gltf, directory, bin_uri)
+    }
+
+    fn export(
+        &self,
+        format: GltfExportFormat,
+        directory: Option<&Path>,
+        bin_uri: &str,
+    ) -> Result<(String, Vec<u8>), GltfExportError> {
+        if self.graph.try_get(self.root).is_err() {
+            return Err(GltfExportError::InvalidRoot);
+        }
+
+        let mut document = Document {
+            format,
+            directory: directory.map(Path::to_path_buf),
+            resource_manager: self.resource_manager,
+            ..Default::default()
+        };
+
+        // Assign indices first, so every node can reference any other node (children, joints).
+        let roots = if self.root == self.graph.get_root() {
+            self.graph[self.root].children().to_vec()
+        } else {
+            vec![self.root]
+        };
+        let mut stack = roots.clone();
+        while let Some(handle) = stack.pop() {
+            let node = &self.graph[handle];
+            if node.cast::<AnimationPlayer>().is_some() {
+                continue;
+            }
+            let index = document.node_indices.len();
+            document.node_indices.insert(handle, index);
+            document.node_order.push(handle);
+            stack.extend(node.children().iter().rev());
+        }
+
+        document.nodes = vec![Value::Null; document.node_order.len()];
+        for (index, handle) in document.node_order.clone().into_iter().enumerate() {
+            document.nodes[index] = document.export_node(self.graph, handle);
+        }
+
+        // Animation players could be anywhere in the exported hierarchy, including its roots
+        // (that's where the model importers put them).
+        let mut players = roots.clone();
+        for handle in document.node_order.iter() {
+            players.extend_from_slice(self.graph[*handle].children());
+        }
+        for handle in players {
+            if let Some(player) = self.graph[handle].cast::<AnimationPlayer>() {
+                document.export_animations(self.graph, player, self.animation_sample_rate);
+            }
+        }
+
+        let scene_nodes = roots
+            .iter()
+            .filter_map(|root| document.node_indices.get(root))
+            .collect::<Vec<_>>();
+
+        let mut root = Map::new();
+        root.insert(
+            "asset".to_string(),
+            json!({ "version": "2.0", "generator": "Fyrox" }),
+        );
+        root.insert("scene".to_string(), json!(0));
+        root.insert("scenes".to_string(), json!([{ "nodes": scene_nodes }]));
+        let bin = std::mem::take(&mut document.buffer);
+        if !bin.is_empty() {
+            let mut buffer = Map::new();
+            buffer.insert("byteLength".to_string(), json!(bin.len()));
+            if format == GltfExportFormat::Gltf {
+                buffer.insert("uri".to_string(), json!(bin_uri));
+            }
+            root.insert("buffers".to_string(), json!([buffer]));
+        }
+        for (name, values) in [
+            ("nodes", std::mem::take(&mut document.nodes)),
+            ("meshes", std::mem::take(&mut document.meshes)),
+            ("skins", std::mem::take(&mut document.skins)),
+            ("materials", std::mem::take(&mut document.materials)),
+            ("textures", std::mem::take(&mut document.textures)),
+            ("images", std::mem::take(&mut document.images)),
+            ("samplers", std::mem::take(&mut document.samplers)),
+            ("cameras", std::mem::take(&mut document.cameras)),
+            ("animations", std::mem::take(&mut document.animations)),
+            ("accessors", std::mem::take(&mut document.accessors)),
+            ("bufferViews", std::mem::take(&mut document.buffer_views)),
+        ] {
+            if !values.is_empty() {
+                root.insert(name.to_string(), Value::Array(values));
+            }
+        }
+        if !document.lights.is_empty() {
+            root.insert(
+                "extensions".to_string(),
+                json!({ KHR_LIGHTS_PUNCTUAL: { "lights": document.lights } }),
+            );
+        }
+        if !document.extensions_used.is_empty() {
+            let mut extensions = document.extensions_used.into_iter().collect::<Vec<_>>();
+            extensions.sort_unstable();
+            root.insert("extensionsUsed".to_string(), json!(extensions));
+        }
+
+        Ok((serde_json::to_string(&Value::Object(root))?, bin))
+    }
+}
+
+#[derive(Default)]
+struct Document<'a> {
+    format: GltfExportFormat,
+    directory: Option<PathBuf>,
+    resource_manager: Option<&'a ResourceManager>,
+    node_indices: FxHashMap<Handle<Node>, usize>,
+    node_order: Vec<Handle<Node>>,
+    buffer: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    nodes: Vec<Value>,
+    meshes: Vec<Value>,
+    skins: Vec<Value>,
+    materials: Vec<Value>,
+    textures: Vec<Value>,
+    images: Vec<Value>,
+    samplers: Vec<Value>,
+    cameras: Vec<Value>,
+    lights: Vec<Value>,
+    animations: Vec<Value>,
+    extensions_used: FxHashSet<&'static str>,
+    /// Surface data resource key -> attributes of the primitive (without joints).
+    primitive_cache: FxHashMap<u64, Map<String, Value>>,
+    /// Material resource key -> material index.
+    material_cache: FxHashMap<u64, Option<usize>>,
+    /// Texture resource key -> texture index.
+    texture_cache: FxHashMap<u64, Option<usize>>,
+}
+
+fn quaternion_to_json(rotation: UnitQuaternion<f32>) -> Value {
+    let q = rotation.coords;
+    json!([q.x, q.y, q.z, q.w])
+}
+
+fn vector3_to_json(v: Vector3<f32>) -> Value {
+    json!([v.x, v.y, v.z])
+}
+
+fn node_rotation(node: &Node, rotation: UnitQuaternion<f32>) -> UnitQuaternion<f32> {
+    let transform = node.local_transform();
+    // The engine applies the inverse of the post-rotation, see `Transform::matrix`.
+    **transform.pre_rotation() * rotation * transform.post_rotation().inverse()
+}
+
+/// Checks whether the transform uses anything besides translation, rotation and scale, that
+/// cannot be represented in glTF directly.
+fn has_extended_transform(transform: &Transform) -> bool {
+    **transform.rotation_offset() != Vector3::default()
+        || **transform.rotation_pivot() != Vector3::default()
+        || **transform.scaling_offset() != Vector3::default()
+        || **transform.scaling_pivot() != Vector3::default()
+        || **transform.pre_rotation() != UnitQuaternion::identity()
+        || **transform.post_rotation() != UnitQuaternion::identity()
+}
+
+/// Decomposes the given matrix into translation, rotation and scale. The matrix must not contain
+/// shear, which is always true for the matrices of the engine's transforms.
+fn decompose_matrix(matrix: &Matrix4<f32>) -> (Vector3<f32>, UnitQuaternion<f32>, Vector3<f32>) {
+    let translation = matrix.fixed_view::<3, 1>(0, 3).into_owned();
+    let mut basis = matrix.fixed_view::<3, 3>(0, 0).into_owned();
+    let mut scale = Vector3::new(
+        basis.column(0).norm(),
+        basis.column(1).norm(),
+        basis.column(2).norm(),
+    );
+    if basis.determinant() < 0.0 {
+        scale.x = -scale.x;
+    }
+    for (i, s) in scale.iter().enumerate() {
+        if *s != 0.0 {
+            basis.column_mut(i).unscale_mut(*s);
+        }
+    }
+    let rotation = UnitQuaternion::from_matrix(&basis);
+    (translation, rotation, scale)
+}
+
+/// Finds a vertex attribute with the given usage, data type and number of components.
+fn find_attribute(
+    vertex_buffer: &VertexBuffer,
+    usage: VertexAttributeUsage,
+    data_type: VertexAttributeDataType,
+    size: u8,
+) -> Option<&VertexAttribute> {
+    vertex_buffer.layout().iter().find(|attribute| {
+        attribute.usage == usage && attribute.data_type == data_type && attribute.size == size
+    })
+}
+
+/// Builds a relative uri of a file, that is located at `path`, from the `directory`.
+fn relative_uri(path: &Path, directory: &Path) -> Option<String> {
+    let path = std::path::absolute(path).ok()?;
+    let directory = std::path::absolute(directory).ok()?;
+    let path_components = path.components().collect::<Vec<_>>();
+    let directory_components = directory.components().collect::<Vec<_>>();
+    if path_components.first() != directory_components.first() {
+        return None;
+    }
+    let common = path_components
+        .iter()
+        .zip(directory_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut segments = vec!["..".to_string(); directory_components.len() - common];
+    for component in &path_components[common..] {
+        match component {
+            Component::Normal(name) => segments.push(name.to_string_lossy().to_string()),
+            _ => return None,
+        }
+    }
+    Some(segments.join("/"))
+}
+
+fn encode_png(texture: &TextureResource) -> Option<Vec<u8>> {
+    let state = texture.state();
+    let texture = state.data_ref()?;
+    let TextureKind::Rectangle { width, height } = texture.kind() else {
+        return None;
+    };
+    let color_type = match texture.pixel_kind() {
+        TexturePixelKind::R8 | TexturePixelKind::Luminance8 => ColorType::L8,
+        TexturePixelKind::RG8 | TexturePixelKind::LuminanceAlpha8 => ColorType::La8,
+        TexturePixelKind::RGB8 | TexturePixelKind::SRGB8 => ColorType::Rgb8,
+        TexturePixelKind::RGBA8 | TexturePixelKind::SRGBA8 => ColorType::Rgba8,
+        _ => return None,
+    };
+    let mut bytes = Vec::new();
+    image::write_buffer_with_format(
+        &mut Cursor::new(&mut bytes),
+        texture.mip_level_data(0),
+        width,
+        height,
+        color_type,
+        ImageFormat::Png,
+    )
+    .ok()?;
+    Some(bytes)
+}
+
+fn image_mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        _ => None,
+    }
+}
+
+fn wrap_mode_to_gltf(mode: TextureWrapMode) -> u32 {
+    match mode {
+        TextureWrapMode::Repeat => 10497,
+        TextureWrapMode::MirroredRepeat => 33648,
+        TextureWrapMode::ClampToEdge
+        | TextureWrapMode::ClampToBorder
+        | TextureWrapMode::MirrorClampToEdge => 33071,
+    }
+}
+
+fn min_filter_to_gltf(filter: TextureMinificationFilter) -> u32 {
+    match filter {
+        TextureMinificationFilter::Nearest => 9728,
+        TextureMinificationFilter::Linear => 9729,
+        TextureMinificationFilter::NearestMipMapNearest => 9984,
+        TextureMinificationFilter::LinearMipMapNearest => 9985,
+        TextureMinificationFilter::NearestMipMapLinear => 9986,
+        TextureMinificationFilter::LinearMipMapLinear => 9987,
+    }
+}
+
+fn mag_filter_to_gltf(filter: TextureMagnificationFilter) -> u32 {
+    match filter {
+        TextureMagnificationFilter::Nearest => 9728,
+        TextureMagnificationFilter::Linear => 9729,
+    }
+}
+
+impl Document<'_> {
+    fn push_buffer_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        // Every accessor must be aligned to the size of its components.
+        while !self.buffer.len().is_multiple_of(4) {
+            self.buffer.push(0);
+        }
+        let mut view = Map::new();
+        view.insert("buffer".to_string(), json!(0));
+        view.insert("byteOffset".to_string(), json!(self.buffer.len()));
+        view.insert("byteLength".to_string(), json!(bytes.len()));
+        if let Some(target) = target {
+            view.insert("target".to_string(), json!(target));
+        }
+        self.buffer.extend_from_slice(bytes);
+        self.buffer_views.push(Value::Object(view));
+        self.buffer_views.len() - 1
+    }
+
+    fn push_accessor(&mut self, accessor: Value) -> usize {
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn push_f32_accessor<const N: usize>(
+        &mut self,
+        data: &[[f32; N]],
+        target: Option<u32>,
+        with_bounds: bool,
+    ) -> usize {
+        let bytes = data
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        let view = self.push_buffer_view(&bytes, target);
+        let ty = match N {
+            1 => "SCALAR",
+            2 => "VEC2",
+            3 => "VEC3",
+            4 => "VEC4",
+            16 => "MAT4",
+            _ => unreachable!(),
+        };
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": data.len(),
+            "type": ty,
+        });
+        if with_bounds && !data.is_empty() {
+            let mut min = [f32::MAX; N];
+            let mut max = [f32::MIN; N];
+            for item in data {
+                for i in 0..N {
+                    min[i] = min[i].min(item[i]);
+                    max[i] = max[i].max(item[i]);
+                }
+            }
+            accessor["min"] = json!(min.to_vec());
+            accessor["max"] = json!(max.to_vec());
+        }
+        self.push_accessor(accessor)
+    }
+
+    fn push_u8x4_accessor(&mut self, data: &[[u8; 4]], normalized: bool) -> usize {
+        let bytes = data.iter().flatten().copied().collect::<Vec<_>>();
+        let view = self.push_buffer_view(&bytes, Some(ARRAY_BUFFER));
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_BYTE,
+            "count": data.len(),
+            "type": "VEC4",
+        });
+        if normalized {
+            accessor["normalized"] = json!(true);
+        }
+        self.push_accessor(accessor)
+    }
+
+    fn push_joints_accessor(&mut self, data: &[[u16; 4]]) -> usize {
+        let bytes = data
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        let view = self.push_buffer_view(&bytes, Some(ARRAY_BUFFER));
+        self.push_accessor(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_SHORT,
+            "count": data.len(),
+            "type": "VEC4",
+        }))
+    }
+
+    fn push_indices_accessor(&mut self, indices: &[u32]) -> usize {
+        let bytes = indices
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        let view = self.push_buffer_view(&bytes, Some(ELEMENT_ARRAY_BUFFER));
+        self.push_accessor(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }))
+    }
+
+    fn export_node(&mut self, graph: &Graph, handle: Handle<Node>) -> Value {
+        let node = &graph[handle];
+        let transform = node.local_transform();
+
+        let mut json = Map::new();
+        if !node.name().is_empty() {
+            json.insert("name".to_string(), json!(node.name()));
+        }
+        let (position, rotation, scale) = if has_extended_transform(transform) {
+            decompose_matrix(&transform.matrix())
+        } else {
+            (
+                **transform.position(),
+                **transform.rotation(),
+                **transform.scale(),
+            )
+        };
+        if position != Vector3::default() {
+            json.insert("translation".to_string(), vector3_to_json(position));
+        }
+        if rotation != UnitQuaternion::identity() {
+            json.insert("rotation".to_string(), quaternion_to_json(rotation));
+        }
+        if scale != Vector3::repeat(1.0) {
+            json.insert("scale".to_string(), vector3_to_json(scale));
+        }
+
+        let mut children = node
+            .children()
+            .iter()
+            .filter_map(|child| self.node_indices.get(child).copied())
+            .collect::<Vec<_>>();
+
+        if let Some(mesh) = node.cast::<Mesh>() {
+            let (mesh_index, skin_index) = self.export_mesh(graph, mesh);
+            if let Some(mesh_index) = mesh_index {
+                json.insert("mesh".to_string(), json!(mesh_index));
+            }
+            if let Some(skin_index) = skin_index {
+                json.insert("skin".to_string(), json!(skin_index));
+            }
+        }
+
+        // Cameras and lights of glTF look along -Z axis, while the engine uses +Z for cameras
+        // and -Y for lights, so they're attached to an additional child node that fixes the
+        // orientation.
+        let attachment = if let Some(camera) = node.cast::<Camera>() {
+            let index = self.export_camera(camera);
+            Some((
+                "camera",
+                json!(index),
+                UnitQuaternion::from_axis_angle(&Vector3::y_axis(), std::f32::consts::PI),
+            ))
+        } else {
+            self.export_light(node).map(|index| {
+                (
+                    "extensions",
+                    json!({ KHR_LIGHTS_PUNCTUAL: { "light": index } }),
+                    UnitQuaternion::from_axis_angle(
+                        &Vector3::x_axis(),
+                        -std::f32::consts::FRAC_PI_2,
+                    ),
+                )
+            })
+        };
+        if let Some((key, value, rotation)) = attachment {
+            let mut orientation = Map::new();
+            orientation.insert(
+                "name".to_string(),
+                json!(format!("{}.Orientation", node.name())),
+            );
+            orientation.insert("rotation".to_string(), quaternion_to_json(rotation));
+            orientation.insert(key.to_string(), value);
+            self.nodes.push(Value::Object(orientation));
+            children.push(self.nodes.len() - 1);
+        }
+
+        if !children.is_empty() {
+            json.insert("children".to_string(), json!(children));
+        }
+
+        Value::Object(json)
+    }
+
+    fn export_camera(&mut self, camera: &Camera) -> usize {
+        let json = match camera.projection() {
+            Projection::Perspective(perspective) => json!({
+                "type": "perspective",
+                "perspective": {
+                    "yfov": perspective.fov,
+                    "znear": perspective.z_near.max(f32::EPSILON),
+                    "zfar": perspective.z_far,
+                }
+            }),
+            Projection::Orthographic(orthographic) => json!({
+                "type": "orthographic",
+                "orthographic": {
+                    "xmag": orthographic.vertical_size,
+                    "ymag": orthographic.vertical_size,
+                    "znear": orthographic.z_near,
+                    "zfar": orthographic.z_far,
+                }
+            }),
+        };
+        self.cameras.push(json);
+        self.cameras.len() - 1
+    }
+
+    fn export_light(&mut self, node: &Node) -> Option<usize> {
+        let light_json = |base: &BaseLight, ty: &str| {
+            let color = base.color().as_frgb();
+            json!({
+                "name": node.name(),
+                "type": ty,
+                "color": [color.x, color.y, color.z],
+                "intensity": base.intensity(),
+            })
+        };
+
+        let json = if let Some(light) = node.cast::<PointLight>() {
+            let mut json = light_json(light.base_light_ref(), "point");
+            json["range"] = json!(light.radius());
+            json
+        } else if let Some(light) = node.cast::<SpotLight>() {
+            let mut json = light_json(light.base_light_ref(), "spot");
+            json["range"] = json!(light.distance());
+            let outer = (light.full_cone_angle() * 0.5).min(std::f32::consts::FRAC_PI_2);
+            json["spot"] = json!({
+                "innerConeAngle": (light.hotspot_cone_angle() * 0.5).min(outer),
+                "outerConeAngle": outer,
+            });
+            json
+        } else if let Some(light) = node.cast::<DirectionalLight>() {
+            light_json(light.base_light_ref(), "directional")
+        } else {
+            return None;
+        };
+
+        self.extensions_used.insert(KHR_LIGHTS_PUNCTUAL);
+        self.lights.push(json);
+        Some(self.lights.len() - 1)
+    }
+
+    /// Exports a mesh and its skin (if any) and returns their indices.
+    fn export_mesh(&mut self, graph: &Graph, mesh: &Mesh) -> (Option<usize>, Option<usize>) {
+        // Bones of every surface are merged into a single skin.
+        let mut joints = Vec::<Handle<Node>>::new();
+        for surface in mesh.surfaces() {
+            for bone in surface.bones() {
+                if !joints.contains(bone) {
+                    joints.push(*bone);
+                }
+            }
+        }
+        let skin_index = if joints.is_empty() {
+            None
+        } else if joints
+            .iter()
+            .all(|joint| self.node_indices.contains_key(joint))
+        {
+            let inverse_bind_matrices = joints
+                .iter()
+                .map(|joint| {
+                    let matrix = graph[*joint].inv_bind_pose_transform();
+                    let mut data = [0.0; 16];
+                    data.copy_from_slice(matrix.as_slice());
+                    data
+                })
+                .collect::<Vec<_>>();
+            let accessor = self.push_f32_accessor(&inverse_bind_matrices, None, false);
+            self.skins.push(json!({
+                "joints": joints.iter().map(|joint| self.node_indices[joint]).collect::<Vec<_>>(),
+                "inverseBindMatrices": accessor,
+            }));
+            Some(self.skins.len() - 1)
+        } else {
+            Log::warn(format!(
+                "glTF: Skin of mesh {} is not exported, because some of its bones are not exported.",
+                mesh.name()
+            ));
+            joints.clear();
+            None
+        };
+
+        let primitives = mesh
+            .surfaces()
+            .iter()
+            .filter_map(|surface| self.export_primitive(surface, &joints))
+            .collect::<Vec<_>>();
+        if primitives.is_empty() {
+            return (None, None);
+        }
+
+        let mut json = json!({ "primitives": primitives });
+        if !mesh.name().is_empty() {
+            json["name"] = json!(mesh.name());
+        }
+        self.meshes.push(json);
+        (Some(self.meshes.len() - 1), skin_index)
+    }
+
+    fn export_primitive(&mut self, surface: &Surface, joints: &[Handle<Node>]) -> Option<Value> {
+        let data_resource = surface.data();
+        let state = data_resource.state();
+        let data = state.data_ref()?;
+
+        let key = data_resource.key();
+        let mut attributes = match self.primitive_cache.get(&key) {
+            Some(attributes) => attributes.clone(),
+            None => {
+                let attributes = self.export_attributes(data)?;
+                self.primitive_cache.insert(key, attributes.clone());
+                attributes
+            }
+        };
+
+        let vertex_buffer = &data.vertex_buffer;
+        if !joints.is_empty()
+            && attributes.contains_key("WEIGHTS_0")
+            && find_attribute(
+                vertex_buffer,
+                VertexAttributeUsage::BoneIndices,
+                VertexAttributeDataType::U8,
+                4,
+            )
+            .is_some()
+        {
+            // Bone indices of a surface point to the bones of the surface, but glTF joint indices
+            // point to the joints of the skin, so the indices must be remapped.
+            let remap = surface
+                .bones()
+                .iter()
+                .map(|bone| joints.iter().position(|joint| joint == bone).unwrap_or(0) as u16)
+                .collect::<Vec<_>>();
+            let data = vertex_buffer
+                .iter()
+                .map(|vertex| {
+                    let indices = vertex
+                        .read_4_u8(VertexAttributeUsage::BoneIndices)
+                        .unwrap_or_default();
+                    indices
+                        .map(|i| remap.get(i as usize).copied().unwrap_or(0))
+                        .into()
+                })
+                .collect::<Vec<[u16; 4]>>();
+            attributes.insert(
+                "JOINTS_0".to_string(),
+                json!(self.push_joints_accessor(&data)),
+            );
+        } else {
+            attributes.remove("WEIGHTS_0");
+        }
+
+        let indices = data
+            .geometry_buffer
+            .iter()
+            .flat_map(|triangle| triangle.0)
+            .collect::<Vec<_>>();
+        let mut json = json!({
+            "attributes": attributes,
+            "indices": self.push_indices_accessor(&indices),
+            "mode": 4,
+        });
+        drop(state);
+
+        if let Some(material) = self.export_material(surface.material()) {
+            json["material"] = json!(material);
+        }
+
+        Some(json)
+    }
+
+    fn export_attributes(&mut self, data: &SurfaceData) -> Option<Map<String, Value>> {
+        let vertex_buffer = &data.vertex_buffer;
+        find_attribute(
+            vertex_buffer,
+            VertexAttributeUsage::Position,
+            VertexAttributeDataType::F32,
+            3,
+        )?;
+
+        let mut attributes = Map::new();
+
+        let read_f32 = |usage, size| {
+            find_attribute(vertex_buffer, usage, VertexAttributeDataType::F32, size).map(|_| {
+                vertex_buffer
+                    .iter()
+                    .map(|vertex| match size {
+                        2 => vertex
+                            .read_2_f32(usage)
+                            .map(|v| Vector4::new(v.x, v.y, 0.0, 0.0)),
+                        3 => vertex.read_3_f32(usage).map(|v| v.push(0.0)),
+                        _ => vertex.read_4_f32(usage),
+                    })
+                    .map(|v| v.unwrap_or_default())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        if let Some(positions) = read_f32(VertexAttributeUsage::Position, 3) {
+            let positions = positions
+                .iter()
+                .map(|p| [p.x, p.y, p.z])
+                .collect::<Vec<_>>();
+            let accessor = self.push_f32_accessor(&positions, Some(ARRAY_BUFFER), true);
+            attributes.insert("POSITION".to_string(), json!(accessor));
+        }
+        if let Some(normals) = read_f32(VertexAttributeUsage::Normal, 3) {
+            let normals = normals
+                .iter()
+                .map(|n| {
+                    let n = n
+                        .xyz()
+                        .try_normalize(f32::EPSILON)
+                        .unwrap_or_else(Vector3::y);
+                    [n.x, n.y, n.z]
+                })
+                .collect::<Vec<_>>();
+            let accessor = self.push_f32_accessor(&normals, Some(ARRAY_BUFFER), false);
+            attributes.insert("NORMAL".to_string(), json!(accessor));
+        }
+        if attributes.contains_key("NORMAL") {
+            if let Some(tangents) = read_f32(VertexAttributeUsage::Tangent, 4) {
+                let tangents = tangents
+                    .iter()
+                    .map(|t| {
+                        let xyz = t
+                            .xyz()
+                            .try_normalize(f32::EPSILON)
+                            .unwrap_or_else(Vector3::x);
+                        [xyz.x, xyz.y, xyz.z, if t.w < 0.0 { -1.0 } else { 1.0 }]
+                    })
+                    .collect::<Vec<_>>();
+                let accessor = self.push_f32_accessor(&tangents, Some(ARRAY_BUFFER), false);
+                attributes.insert("TANGENT".to_string(), json!(accessor));
+            }
+        }
+        for (usage, name) in [
+            (VertexAttributeUsage::TexCoord0, "TEXCOORD_0"),
+            (VertexAttributeUsage::TexCoord1, "TEXCOORD_1"),
+        ] {
+            if let Some(tex_coords) = read_f32(usage, 2) {
+                let tex_coords = tex_coords.iter().map(|t| [t.x, t.y]).collect::<Vec<_>>();
+                let accessor = self.push_f32_accessor(&tex_coords, Some(ARRAY_BUFFER), false);
+                attributes.insert(name.to_string(), json!(accessor));
+            }
+        }
+        if find_attribute(
+            vertex_buffer,
+            VertexAttributeUsage::Color,
+            VertexAttributeDataType::U8,
+            4,
+        )
+        .is_some()
+        {
+            let colors = vertex_buffer
+                .iter()
+                .map(|vertex| {
+                    vertex
+                        .read_4_u8(VertexAttributeUsage::Color)
+                        .unwrap_or_default()
+                        .into()
+                })
+                .collect::<Vec<[u8; 4]>>();
+            let accessor = self.push_u8x4_accessor(&colors, true);
+            attributes.insert("COLOR_0".to_string(), json!(accessor));
+        }
+        if let Some(weights) = read_f32(VertexAttributeUsage::BoneWeight, 4) {
+            let weights = weights
+                .iter()
+                .map(|w| {
+                    // Weights must be normalized.
+                    let sum = w.x + w.y + w.z + w.w;
+                    if sum > f32::EPSILON {
+                        (w / sum).into()
+                    } else {
+                        [1.0, 0.0, 0.0, 0.0]
+                    }
+                })
+                .collect::<Vec<[f32; 4]>>();
+            let accessor = self.push_f32_accessor(&weights, Some(ARRAY_BUFFER), false);
+            attributes.insert("WEIGHTS_0".to_string(), json!(accessor));
+        }
+
+        Some(attributes)
+    }
+
+    fn export_material(&mut self, material: &MaterialResource) -> Option<usize> {
+        let key = material.key();
+        if let Some(index) = self.material_cache.get(&key) {
+            return *index;
+        }
+
+        let index = self.export_material_uncached(material);
+        self.material_cache.insert(key, index);
+        index
+    }
+
+    fn export_material_uncached(&mut self, material_resource: &MaterialResource) -> Option<usize> {
+        let state = material_resource.state();
+        let material = state.data_ref()?;
+
+        let properties = material.property_group_ref("properties");
+        let property = |name: &str| properties.and_then(|group| group.property_ref(name));
+
+        let mut pbr = Map::new();
+        let mut json = Map::new();
+
+        if let Some(path) = self
+            .resource_manager
+            .and_then(|rm| rm.resource_path(material_resource.as_ref()))
+        {
+            if let Some(name) = path.file_stem() {
+                json.insert("name".to_string(), json!(name.to_string_lossy()));
+            }
+        }
+
+        if let Some(color) = property("diffuseColor").and_then(|p| p.as_color()) {
+            let color = color.as_frgba();
+            pbr.insert(
+                "baseColorFactor".to_string(),
+                json!([color.x, color.y, color.z, color.w]),
+            );
+        }
+
+        let metallic_texture = material.texture("metallicTexture");
+        let roughness_texture = material.texture("roughnessTexture");
+        let metallic_roughness_texture =
+            material.texture("metallicRoughnessTexture").or_else(|| {
+                // Separate textures of the standard shader can be represented only if it is the same
+                // (packed) texture.
+                match (metallic_texture.as_ref(), roughness_texture.as_ref()) {
+                    (Some(metallic), Some(roughness)) if metallic == roughness => {
+                        Some(metallic.clone())
+                    }
+                    (None, None) => None,
+                    _ => {
+                        Log::warn(
+                            "glTF: Separate metallic and roughness textures cannot be exported, \
+                        use a single packed texture instead.",
+                        );
+                        None
+                    }
+                }
+            });
+
+        // Standard shaders have no metallic and roughness factors, the values come from the
+        // fallback values of the textures - black for metallic and white for roughness.
+        let metallic_factor = property("metallicFactor")
+            .and_then(|p| p.as_float())
+            .unwrap_or(if metallic_roughness_texture.is_some() {
+                1.0
+            } else {
+                0.0
+            });
+        let roughness_factor = property("roughnessFactor")
+            .and_then(|p| p.as_float())
+            .unwrap_or(1.0);
+        pbr.insert("metallicFactor".to_string(), json!(metallic_factor));
+        pbr.insert("roughnessFactor".to_string(), json!(roughness_factor));
+
+        let textures = [
+            ("diffuseTexture", "baseColorTexture", true),
+            ("normalTexture", "normalTexture", false),
+            ("aoTexture", "occlusionTexture", false),
+            ("emissionTexture", "emissiveTexture", false),
+        ]
+        .map(|(name, gltf_name, is_pbr)| (material.texture(name), gltf_name, is_pbr));
+        drop(state);
+
+        for (texture, gltf_name, is_pbr) in textures.into_iter().chain([(
+            metallic_roughness_texture,
+            "metallicRoughnessTexture",
+            true,
+        )]) {
+            let Some(texture) = texture else {
+                continue;
+            };
+            if let Some(index) = self.export_texture(&texture) {
+                let target = if is_pbr { &mut pbr } else { &mut json };
+                target.insert(gltf_name.to_string(), json!({ "index": index }));
+            }
+        }
+
+        let state = material_resource.state();
+        let material = state.data_ref()?;
+        let emission = material
+            .property_group_ref("properties")
+            .and_then(|group| group.property_ref("emissionStrength"))
+            .and_then(|p| p.as_vector3());
+        if let Some(emission) = emission {
+            let strength = emission.max();
+            if strength > 0.0 {
+                if strength > 1.0 {
+                    json.insert(
+                        "emissiveFactor".to_string(),
+                        vector3_to_json(emission / strength),
+                    );
+                    json.insert(
+                        "extensions".to_string(),
+                        json!({ KHR_MATERIALS_EMISSIVE_STRENGTH: { "emissiveStrength": strength } }),
+                    );
+                    self.extensions_used.insert(KHR_MATERIALS_EMISSIVE_STRENGTH);
+                } else {
+                    json.insert("emissiveFactor".to_string(), vector3_to_json(emission));
+                }
+            }
+        }
+
+        json.insert("pbrMetallicRoughness".to_string(), Value::Object(pbr));
+        self.materials.push(Value::Object(json));
+        Some(self.materials.len() - 1)
+    }
+
+    fn export_texture(&mut self, texture: &TextureResource) -> Option<usize> {
+        let key = texture.key();
+        if let Some(index) = self.texture_cache.get(&key) {
+            return *index;
+        }
+        let index = self.export_texture_uncached(texture);
+        if index.is_none() {
+            Log::warn(format!(
+                "glTF: Unable to export texture {}. Only textures with png or jpeg source files \
+                and uncompressed 8-bit textures are supported.",
+                texture.summary()
+            ));
+        }
+        self.texture_cache.insert(key, index);
+        index
+    }
+
+    fn export_texture_uncached(&mut self, texture: &TextureResource) -> Option<usize> {
+        let path = self
+            .resource_manager
+            .and_then(|rm| rm.resource_path(texture.as_ref()));
+
+        let mut image = None;
+        if let Some((path, mime_type)) = path
+            .as_ref()
+            .and_then(|path| Some((path, image_mime_type(path)?)))
+        {
+            image = match self.format {
+                GltfExportFormat::Gltf => self
+                    .directory
+                    .as_ref()
+                    .and_then(|directory| relative_uri(path, directory))
+                    .map(|uri| json!({ "uri": uri })),
+                GltfExportFormat::Glb => std::fs::read(path).ok().map(|bytes| {
+                    json!({
+                        "bufferView": self.push_buffer_view(&bytes, None),
+                        "mimeType": mime_type,
+                    })
+                }),
+            };
+        }
+        let image = match image {
+            Some(image) => image,
+            None => {
+                let bytes = encode_png(texture)?;
+                match self.format {
+                    GltfExportFormat::Gltf => json!({
+                        "uri": format!("data:image/png;base64,{}", Base64Engine.encode(&bytes)),
+                    }),
+                    GltfExportFormat::Glb => json!({
+                        "bufferView": self.push_buffer_view(&bytes, None),
+                        "mimeType": "image/png",
+                    }),
+                }
+            }
+        };
+        self.images.push(image);
+        let source = self.images.len() - 1;
+
+        let state = texture.state();
+        let sampler = state.data_ref().map(|texture| {
+            json!({
+                "magFilter": mag_filter_to_gltf(texture.magnification_filter()),
+                "minFilter": min_filter_to_gltf(texture.minification_filter()),
+                "wrapS": wrap_mode_to_gltf(texture.s_wrap_mode()),
+                "wrapT": wrap_mode_to_gltf(texture.t_wrap_mode()),
+            })
+        });
+        let mut json = json!({ "source": source });
+        if let Some(sampler) = sampler {
+            self.samplers.push(sampler);
+            json["sampler"] = json!(self.samplers.len() - 1);
+        }
+        self.textures.push(json);
+        Some(self.textures.len() - 1)
+    }
+
+    fn export_animations(&mut self, graph: &Graph, player: &AnimationPlayer, sample_rate: f32) {
+        for animation in player.animations().iter() {
+            let mut channels = Vec::new();
+            let mut samplers = Vec::new();
+
+            let tracks_data = animation.tracks_data().state();
+            let Some(tracks_data) = tracks_data.data_ref() else {
+                continue;
+            };
+
+            for track in tracks_data.tracks() {
+                let Some(binding) = animation.track_bindings().get(&track.id()) else {
+                    continue;
+                };
+                let target = binding.target();
+                let (Some(&node_index), Ok(node)) =
+                    (self.node_indices.get(&target), graph.try_get(target))
+                else {
+                    continue;
+                };
+                let path = match track.value_binding() {
+                    ValueBinding::Position => "translation",
+                    ValueBinding::Rotation => "rotation",
+                    ValueBinding::Scale => "scale",
+                    ValueBinding::Property { .. } => continue,
+                };
+
+                let times = sample_times(track, sample_rate);
+                let mut hints = Default::default();
+                let mut values = Vec::with_capacity(times.len());
+                for &time in times.iter() {
+                    let value = track.fetch(time, &mut hints).map(|v| v.value);
+                    values.push(match value {
+                        Some(TrackValue::Vector3(v)) => [v.x, v.y, v.z, 0.0],
+                        Some(TrackValue::UnitQuaternion(q)) => {
+                            let q = node_rotation(node, q).coords;
+                            [q.x, q.y, q.z, q.w]
+                        }
+                        _ => break,
+                    });
+                }
+                if values.len() != times.len() || times.is_empty() {
+                    continue;
+                }
+
+                let input = self.push_f32_accessor(
+                    &times.iter().map(|t| [*t]).collect::<Vec<_>>(),
+                    None,
+                    true,
+                );
+                let output = if path == "rotation" {
+                    self.push_f32_accessor(&values, None, false)
+                } else {
+                    let values = values
+                        .iter()
+                        .map(|v| [v[0], v[1], v[2]])
+                        .collect::<Vec<_>>();
+                    self.push_f32_accessor(&values, None, false)
+                };
+
+                samplers.push(json!({
+                    "input": input,
+                    "output": output,
+                    "interpolation": "LINEAR",
+                }));
+                channels.push(json!({
+                    "sampler": samplers.len() - 1,
+                    "target": { "node": node_index, "path": path },
+                }));
+            }
+
+            if !channels.is_empty() {
+                let mut json = json!({ "channels": channels, "samplers": samplers });
+                if !animation.name().is_empty() {
+                    json["name"] = json!(animation.name());
+                }
+                self.animations.push(json);
+            }
+        }
+    }
+}
+
+/// Returns sorted times of every key of the track, merged with uniformly distributed samples.
+fn sample_times(track: &crate::scene::animation::Track, sample_rate: f32) -> Vec<f32> {
+    let length = track.time_length();
+    let mut times = track
+        .data_container()
+        .curves_ref()
+        .iter()
+        .flat_map(|curve| curve.keys().iter().map(|key| key.location))
+        .collect::<Vec<_>>();
+    if sample_rate > 0.0 {
+        let count = (length * sample_rate).ceil() as usize;
+        times.extend((0..=count).map(|i| (i as f32 / sample_rate).min(length)));
+    }
+    times.retain(|t| t.is_finite() && *t >= 0.0);
+    times.sort_by(|a, b| a.total_cmp(b));
+    times.dedup_by(|a, b| (*a - *b).abs() <= 1.0e-4);
+    times
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        asset::io::FsResourceIo,
+        core::{
+            algebra::{Vector2, Vector4},
+            futures::executor::block_on,
+            math::{
+                curve::{Curve, CurveKey, CurveKeyKind},
+                TriangleDefinition,
+            },
+            uuid::Uuid,
+        },
+        resource::{
+            gltf::{import_from_slice, ImportContext},
+            model::MaterialSearchOptions,
+        },
+        scene::{
+            animation::{prelude::*, AnimationContainer, AnimationPlayerBuilder},
+            base::BaseBuilder,
+            light::{point::PointLightBuilder, BaseLightBuilder},
+            mesh::{
+                buffer::{TriangleBuffer, VertexBuffer},
+                surface::{SurfaceBuilder, SurfaceResource},
+                vertex::AnimatedVertex,
+                MeshBuilder,
+            },
+            pivot::PivotBuilder,
+            transform::TransformBuilder,
+        },
+    };
+    use fyrox_animation::track::TrackBinding;
+    use fyrox_resource::untyped::ResourceKind;
+    use std::sync::Arc;
+
+    fn make_graph() -> (Graph, Handle<Node>) {
+        let mut graph = Graph::new();
+        let light =
+            PointLightBuilder::new(BaseLightBuilder::new(BaseBuilder::new().with_name("Light")))
+                .with_radius(5.0)
+                .build(&mut graph);
+        let mesh = MeshBuilder::new(
+            BaseBuilder::new()
+                .with_name("Cube")
+                .with_child(light)
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(Vector3::new(1.0, 2.0, 3.0))
+                        .build(),
+                ),
+        )
+        .with_surfaces(vec![SurfaceBuilder::new(SurfaceResource::new_ok(
+            Uuid::new_v4(),
+            ResourceKind::Embedded,
+            SurfaceData::make_cube(Matrix4::identity()),
+        ))
+        .build()])
+        .build(&mut graph);
+        (graph, mesh.to_base())
+    }
+
+    #[test]
+    fn test_export_glb() {
+        let (graph, _) = make_graph();
+        let bytes = GltfExporter::new(&graph).export_glb().unwrap();
+        let gltf = gltf::Gltf::from_slice(&bytes).unwrap();
+        let document = &gltf.document;
+        // Mesh, light and an extra node that orients the light.
+        assert_eq!(document.nodes().count(), 3);
+        assert_eq!(document.meshes().count(), 1);
+        assert_eq!(document.materials().count(), 1);
+        assert!(document
+            .extensions_used()
+            .any(|extension| extension == KHR_LIGHTS_PUNCTUAL));
+        let scene = document.default_scene().unwrap();
+        let root = scene.nodes().next().unwrap();
+        assert_eq!(root.name(), Some("Cube"));
+        assert_eq!(root.transform().decomposed().0, [1.0, 2.0, 3.0]);
+        let primitive = root.mesh().unwrap().primitives().next().unwrap();
+        assert_eq!(primitive.indices().unwrap().count(), 36);
+        assert!(primitive.get(&gltf::Semantic::Positions).is_some());
+        assert!(primitive.get(&gltf::Semantic::Normals).is_some());
+        assert!(primitive.get(&gltf::Semantic::TexCoords(0)).is_some());
+    }
+
+    #[test]
+    fn test_export_sub_graph() {
+        let (graph, mesh) = make_graph();
+        let light = graph[mesh].children()[0];
+        let (json, bin) = GltfExporter::new(&graph)
+            .with_root(light)
+            .export_gltf(None, "light.bin")
+            .unwrap();
+        assert!(bin.is_empty());
+        let gltf = gltf::Gltf::from_slice(json.as_bytes()).unwrap();
+        assert_eq!(gltf.document.nodes().count(), 2);
+        assert_eq!(gltf.document.meshes().count(), 0);
+        assert!(GltfExporter::new(&graph)
+            .with_root(Handle::new(123, 1))
+            .export_glb()
+            .is_err());
+    }
+
+    #[test]
+    fn test_export_extended_transform() {
+        let transform = TransformBuilder::new()
+            .with_local_position(Vector3::new(1.0, 2.0, 3.0))
+            .with_local_rotation(UnitQuaternion::from_euler_angles(0.3, 0.7, 0.0))
+            .with_local_scale(Vector3::new(2.0, 1.0, 0.5))
+            .with_rotation_pivot(Vector3::new(0.5, -1.0, 0.25))
+            .with_scaling_pivot(Vector3::new(-0.5, 0.0, 1.0))
+            .with_rotation_offset(Vector3::new(0.1, 0.2, 0.3))
+            .with_pre_rotation(UnitQuaternion::from_euler_angles(0.0, 0.0, 0.4))
+            .with_post_rotation(UnitQuaternion::from_euler_angles(0.2, 0.0, 0.0))
+            .build();
+        let expected = transform.matrix();
+
+        let mut graph = Graph::new();
+        PivotBuilder::new(
+            BaseBuilder::new()
+                .with_name("Pivot")
+                .with_local_transform(transform),
+        )
+        .build(&mut graph);
+
+        let bytes = GltfExporter::new(&graph).export_glb().unwrap();
+        let gltf = gltf::Gltf::from_slice(&bytes).unwrap();
+        let node = gltf.document.nodes().next().unwrap();
+        let actual = Matrix4::from(node.transform().matrix());
+        assert!(
+            (actual - expected).abs().max() < 1.0e-5,
+            "{actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_relative_uri() {
+        assert_eq!(
+            relative_uri(Path::new("/data/textures/a.png"), Path::new("/data/models")),
+            Some("../textures/a.png".to_string())
+        );
+    }
+
+    /// Exports the graph to glb and imports it back.
+    fn round_trip(graph: &Graph) -> Graph {
+        let bytes = GltfExporter::new(graph).export_glb().unwrap();
+        let io = Arc::new(FsResourceIo);
+        let context = ImportContext {
+            io: io.clone(),
+            resource_manager: ResourceManager::new(io, Default::default()),
+            model_path: "test.glb".into(),
+            search_options: MaterialSearchOptions::default(),
+        };
+        let mut imported = Graph::new();
+        block_on(import_from_slice(&bytes, &mut imported, &context)).unwrap();
+        imported
+    }
+
+    fn find(graph: &Graph, name: &str) -> Handle<Node> {
+        graph.find_by_name_from_root(name).unwrap().0
+    }
+
+    fn make_skinned_surface(bones: Vec<Handle<Node>>, bone_indices: [u8; 4]) -> Surface {
+        let vertices = [
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, position)| AnimatedVertex {
+            position,
+            tex_coord: Vector2::default(),
+            normal: Vector3::z(),
+            tangent: Vector4::new(1.0, 0.0, 0.0, 1.0),
+            bone_weights: [0.5, 0.25, 0.25, 0.0],
+            // Every vertex uses the bones in a different order.
+            bone_indices: std::array::from_fn(|k| bone_indices[(k + i) % 3]),
+        })
+        .collect::<Vec<_>>();
+        let data = SurfaceData::new(
+            VertexBuffer::new(vertices.len(), vertices).unwrap(),
+            TriangleBuffer::new(vec![TriangleDefinition([0, 1, 2])]),
+        );
+        SurfaceBuilder::new(SurfaceResource::new_ok(
+            Uuid::new_v4(),
+            ResourceKind::Embedded,
+            data,
+        ))
+        .with_bones(bones)
+        .build()
+    }
+
+    #[test]
+    fn test_skinned_mesh_round_trip() {
+        let mut graph = Graph::new();
+        let bind_pose = |x: f32| {
+            Matrix4::new_translation(&Vector3::new(x, -1.0, 0.5))
+                * Matrix4::from(UnitQuaternion::from_euler_angles(x, 0.0, 0.3))
+        };
+        let bones = (0..3)
+            .map(|i| {
+                PivotBuilder::new(
+                    BaseBuilder::new()
+                        .with_name(format!("Bone{i}"))
+                        .with_inv_bind_pose_transform(bind_pose(i as f32)),
+                )
+                .build(&mut graph)
+                .to_base()
+            })
+            .collect::<Vec<_>>();
+        graph.link_nodes(bones[1], bones[0]);
+        graph.link_nodes(bones[2], bones[1]);
+        // Surfaces reference bones in different order, so the exporter has to merge them into a
+        // single skin and remap joint indices of each surface.
+        let surfaces = vec![
+            make_skinned_surface(vec![bones[1], bones[0]], [0, 1, 0, 0]),
+            make_skinned_surface(vec![bones[2], bones[1], bones[0]], [2, 0, 1, 0]),
+        ];
+        let mesh = MeshBuilder::new(BaseBuilder::new().with_name("Body"))
+            .with_surfaces(surfaces)
+            .build(&mut graph);
+
+        let imported = round_trip(&graph);
+
+        for (i, bone) in bones.iter().enumerate() {
+            let imported_bone = &imported[find(&imported, &format!("Bone{i}"))];
+            let expected = graph[*bone].inv_bind_pose_transform();
+            let actual = imported_bone.inv_bind_pose_transform();
+            assert!(
+                (actual - expected).abs().max() < 1.0e-5,
+                "{actual} != {expected}"
+            );
+        }
+        assert_eq!(
+            imported[find(&imported, "Bone2")].parent(),
+            find(&imported, "Bone1")
+        );
+
+        let imported_mesh = imported[find(&imported, "Body")].cast::<Mesh>().unwrap();
+        let surfaces = graph[mesh].surfaces();
+        assert_eq!(imported_mesh.surfaces().len(), surfaces.len());
+        for (surface, imported_surface) in surfaces.iter().zip(imported_mesh.surfaces()) {
+            let data = surface.data();
+            let data = data.data_ref();
+            let imported_data = imported_surface.data();
+            let imported_data = imported_data.data_ref();
+            assert_eq!(
+                imported_data.vertex_buffer.vertex_count(),
+                data.vertex_buffer.vertex_count()
+            );
+            for (vertex, imported_vertex) in data
+                .vertex_buffer
+                .iter()
+                .zip(imported_data.vertex_buffer.iter())
+            {
+                let indices = vertex.read_4_u8(VertexAttributeUsage::BoneIndices).unwrap();
+                let imported_indices = imported_vertex
+                    .read_4_u8(VertexAttributeUsage::BoneIndices)
+                    .unwrap();
+                let weights = vertex.read_4_f32(VertexAttributeUsage::BoneWeight).unwrap();
+                let imported_weights = imported_vertex
+                    .read_4_f32(VertexAttributeUsage::BoneWeight)
+                    .unwrap();
+                for k in 0..4 {
+                    // Bones without weight are meaningless, their indices could be anything.
+                    if weights[k] == 0.0 {
+                        continue;
+                    }
+                    assert_eq!(imported_weights[k], weights[k]);
+                    // Both indices must point to the same bone.
+                    let bone = surface.bones()[indices[k] as usize];
+                    let imported_bone = imported_surface.bones()[imported_indices[k] as usize];
+                    assert_eq!(imported[imported_bone].name(), graph[bone].name());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_animation_round_trip() {
+        let mut graph = Graph::new();
+        let node = PivotBuilder::new(BaseBuilder::new().with_name("Animated")).build(&mut graph);
+
+        let key = |time, value| CurveKey::new(time, value, CurveKeyKind::Linear);
+        let mut position = TrackDataContainer::new(TrackValueKind::Vector3);
+        position.curves_mut()[0] = Curve::from(vec![key(0.0, 0.0), key(1.0, 2.0), key(2.0, 1.0)]);
+        position.curves_mut()[1] = Curve::from(vec![key(0.0, 1.0), key(2.0, -3.0)]);
+        let mut rotation = TrackDataContainer::new(TrackValueKind::UnitQuaternionEuler);
+        rotation.curves_mut()[1] = Curve::from(vec![key(0.0, 0.0), key(2.0, 1.5)]);
+
+        let mut animation = Animation::default();
+        animation.set_name("Move");
+        animation.set_time_slice(0.0..2.0);
+        let tracks = [
+            Track::new(position, ValueBinding::Position),
+            Track::new(rotation, ValueBinding::Rotation),
+        ];
+        for track in tracks.iter() {
+            animation.add_track_with_binding(TrackBinding::new(node.to_base()), track.clone());
+        }
+        let mut animations = AnimationContainer::new();
+        animations.add(animation);
+        AnimationPlayerBuilder::new(BaseBuilder::new().with_name("Player"))
+            .with_animations(animations)
+            .build(&mut graph);
+
+        let imported = round_trip(&graph);
+        let imported_node = find(&imported, "Animated");
+        let player = imported[find(&imported, "AnimationPlayer")]
+            .cast::<AnimationPlayer>()
+            .unwrap();
+        let imported_animation = player.animations().iter().next().unwrap();
+        assert_eq!(imported_animation.name(), "Move");
+        let tracks_data = imported_animation.tracks_data().state();
+        let imported_tracks = tracks_data.data_ref().unwrap().tracks();
+
+        for track in tracks.iter() {
+            let imported_track = imported_tracks
+                .iter()
+                .find(|t| t.value_binding() == track.value_binding())
+                .unwrap();
+            assert_eq!(
+                imported_animation.track_bindings()[&imported_track.id()].target(),
+                imported_node
+            );
+            for time in [0.0, 0.5, 1.0, 1.5, 2.0] {
+                let expected = track.fetch(time, &mut Default::default()).unwrap().value;
+                let actual = imported_track
+                    .fetch(time, &mut Default::default())
+                    .unwrap()
+                    .value;
+                match (expected, actual) {
+                    (TrackValue::Vector3(expected), TrackValue::Vector3(actual)) => {
+                        assert!(
+                            (actual - expected).norm() < 1.0e-3,
+                            "{time}: {actual} != {expected}"
+                        );
+                    }
+                    (TrackValue::UnitQuaternion(expected), TrackValue::UnitQuaternion(actual)) => {
+                        assert!(
+                            actual.angle_to(&expected) < 1.0e-2,
+                            "{time}: {actual} != {expected}"
+                        );
+                    }
+                    _ => panic!("Track value kind mismatch at {time}"),
+                }
+            }
+        }
+    }
+}
@@ -52,6 +52,7 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 mod animation;
+pub mod export;
 mod iter;
 pub mod material;
 mod node_names;
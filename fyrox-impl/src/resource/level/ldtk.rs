@@ -0,0 +1,738 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Importer for projects made in [LDtk](https://ldtk.io). See [`LdtkLoader`] docs for more info.
+
+use super::{
+    cell_to_grid, pixels_to_units, resolve_path, AtlasDescription, ImportContext, LevelBuilder,
+    LevelImportError, LevelObject, ObjectReference, ObjectSprite, TileSetPlacement,
+    LAYER_DEPTH_STEP,
+};
+use crate::{
+    asset::{
+        io::ResourceIo,
+        loader::{BoxedLoaderFuture, LoaderPayload, ResourceLoader},
+        manager::ResourceManager,
+        state::LoadError,
+    },
+    core::{
+        algebra::{Vector2, Vector3},
+        color::Color,
+        log::Log,
+        math::Rect,
+        pool::Handle,
+        reflect::Reflect,
+        uuid::Uuid,
+    },
+    fxhash::FxHashMap,
+    graph::{NodeMapping, SceneGraph},
+    resource::model::Model,
+    scene::{
+        base::{BaseBuilder, Property, PropertyValue},
+        graph::Graph,
+        node::Node,
+        pivot::PivotBuilder,
+        tilemap::{
+            tileset::TileSetPropertyValue, OrthoTransform, OrthoTransformation, TileCollider, Tiles,
+        },
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Imports projects made in [LDtk](https://ldtk.io) (`*.ldtk`) as model resources. See the
+/// [module docs](super) for the layout of the imported scene.
+///
+/// Every level of the project becomes a pivot node, positioned according to the world layout of
+/// the project. Layers of a level become child nodes of the level:
+///
+/// - Tile layers and auto-layers become tile maps.
+/// - IntGrid layers become hidden tile maps that use colored tiles. Every tile of an IntGrid layer
+///   has `IntGrid` property with the value of the cell and a full-tile collider in the collider
+///   layer with the name of the IntGrid layer. No physics is created for IntGrid layers, because
+///   only the game knows which values are solid; add a collider with
+///   [`TileMapShape`](crate::scene::dim2::collider::TileMapShape) to make them solid.
+/// - Entity layers become pivot nodes with a child node for every entity. Fields of entities are
+///   stored as node properties, references to other entities are converted to
+///   [`PropertyValue::NodeHandle`].
+///
+/// Custom data of tiles is stored in `CustomData` property and enum tags are stored in a property
+/// with the name of the tag enum. Levels that are saved in separate files are supported as well.
+/// One unit of the scene is the default grid size of the project.
+pub struct LdtkLoader {
+    /// Resource manager to request textures of the tile sets.
+    pub resource_manager: ResourceManager,
+}
+
+impl ResourceLoader for LdtkLoader {
+    fn extensions(&self) -> &[&str] {
+        &["ldtk"]
+    }
+
+    fn data_type_uuid(&self) -> Uuid {
+        <Model as Reflect>::type_info().type_uuid
+    }
+
+    fn load(&self, path: PathBuf, io: Arc<dyn ResourceIo>) -> BoxedLoaderFuture {
+        let resource_manager = self.resource_manager.clone();
+        Box::pin(async move {
+            let context = ImportContext {
+                io,
+                resource_manager,
+            };
+            let model = load(&path, &context).await.map_err(LoadError::new)?;
+            Ok(LoaderPayload::new(model))
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Project {
+    default_grid_size: u32,
+    defs: Definitions,
+    #[serde(default)]
+    levels: Vec<Level>,
+    #[serde(default)]
+    worlds: Vec<World>,
+}
+
+#[derive(Deserialize, Debug)]
+struct World {
+    #[serde(default)]
+    levels: Vec<Level>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Definitions {
+    #[serde(default)]
+    tilesets: Vec<TilesetDefinition>,
+    #[serde(default)]
+    enums: Vec<EnumDefinition>,
+    #[serde(default)]
+    layers: Vec<LayerDefinition>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TilesetDefinition {
+    uid: i64,
+    identifier: String,
+    rel_path: Option<String>,
+    px_wid: u32,
+    px_hei: u32,
+    tile_grid_size: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    padding: u32,
+    tags_source_enum_uid: Option<i64>,
+    #[serde(default)]
+    custom_data: Vec<TileCustomData>,
+    #[serde(default)]
+    enum_tags: Vec<TileEnumTag>,
+}
+
+impl TilesetDefinition {
+    fn columns(&self) -> u32 {
+        (self.px_wid.saturating_sub(2 * self.padding) + self.spacing)
+            / (self.tile_grid_size + self.spacing).max(1)
+    }
+
+    fn rows(&self) -> u32 {
+        (self.px_hei.saturating_sub(2 * self.padding) + self.spacing)
+            / (self.tile_grid_size + self.spacing).max(1)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TileCustomData {
+    tile_id: u32,
+    data: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TileEnumTag {
+    enum_value_id: String,
+    tile_ids: Vec<u32>,
+}
+
+#[derive(Deserialize, Debug)]
+struct EnumDefinition {
+    uid: i64,
+    identifier: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LayerDefinition {
+    uid: i64,
+    #[serde(default)]
+    int_grid_values: Vec<IntGridValue>,
+}
+
+#[derive(Deserialize, Debug)]
+struct IntGridValue {
+    value: i32,
+    color: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Level {
+    identifier: String,
+    world_x: i32,
+    world_y: i32,
+    layer_instances: Option<Vec<LayerInstance>>,
+    external_rel_path: Option<String>,
+    #[serde(default)]
+    field_instances: Vec<FieldInstance>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LayerInstance {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__type")]
+    kind: String,
+    #[serde(rename = "__cWid")]
+    width: i32,
+    #[serde(rename = "__gridSize")]
+    grid_size: u32,
+    #[serde(rename = "__tilesetDefUid")]
+    tileset_uid: Option<i64>,
+    #[serde(rename = "__pxTotalOffsetX")]
+    offset_x: i32,
+    #[serde(rename = "__pxTotalOffsetY")]
+    offset_y: i32,
+    layer_def_uid: i64,
+    visible: bool,
+    #[serde(default)]
+    int_grid_csv: Vec<i32>,
+    #[serde(default)]
+    grid_tiles: Vec<TileInstance>,
+    #[serde(default)]
+    auto_layer_tiles: Vec<TileInstance>,
+    #[serde(default)]
+    entity_instances: Vec<EntityInstance>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TileInstance {
+    px: [i32; 2],
+    #[serde(default)]
+    f: u8,
+    t: u32,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct EntityInstance {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    iid: String,
+    px: [i32; 2],
+    #[serde(rename = "__pivot")]
+    pivot: [f32; 2],
+    width: u32,
+    height: u32,
+    #[serde(rename = "__tags", default)]
+    tags: Vec<String>,
+    #[serde(rename = "__tile")]
+    tile: Option<TilesetRect>,
+    #[serde(default)]
+    field_instances: Vec<FieldInstance>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TilesetRect {
+    tileset_uid: i64,
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize, Debug)]
+struct FieldInstance {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__type")]
+    kind: String,
+    #[serde(rename = "__value")]
+    value: Value,
+}
+
+async fn load(path: &Path, context: &ImportContext) -> Result<Model, LevelImportError> {
+    let bytes = context.load_file(path).await?;
+    let mut project = serde_json::from_slice::<Project>(&bytes)?;
+
+    let mut levels = std::mem::take(&mut project.levels);
+    for world in project.worlds.iter_mut() {
+        levels.append(&mut world.levels);
+    }
+    for level in levels.iter_mut() {
+        if level.layer_instances.is_none() {
+            if let Some(external) = level.external_rel_path.as_ref() {
+                let bytes = context.load_file(&resolve_path(path, external)).await?;
+                *level = serde_json::from_slice(&bytes)?;
+            }
+        }
+    }
+
+    let mut scene = Scene::new();
+    let root = scene.graph.get_root();
+    if let Some(name) = path.file_name() {
+        scene.graph[root].set_name(name.to_string_lossy());
+    }
+    import_project(&project, &levels, path, context, &mut scene.graph);
+    Ok(Model::new(NodeMapping::UseNames, scene))
+}
+
+/// Parses LDtk color in `#rrggbb` format.
+fn parse_color(color: &str) -> Color {
+    let value = u32::from_str_radix(color.trim_start_matches('#'), 16).unwrap_or(0xFFFFFF);
+    Color::opaque((value >> 16) as u8, (value >> 8) as u8, value as u8)
+}
+
+/// Converts a field of a level or an entity to node properties. Array fields produce a property
+/// for every element. References to entities are returned separately as `(field name, entity iid)`
+/// pairs, because they can be resolved only when all the entities are spawned.
+fn field_properties(
+    fields: &[FieldInstance],
+    properties: &mut Vec<Property>,
+    references: &mut Vec<ObjectReference>,
+) {
+    for field in fields {
+        let values = match &field.value {
+            Value::Array(values) => values.iter().collect::<Vec<_>>(),
+            value => vec![value],
+        };
+        for value in values {
+            let property_value = match value {
+                Value::Null => continue,
+                Value::Bool(v) => PropertyValue::I32(*v as i32),
+                Value::Number(v) if field.kind.contains("Int") => {
+                    PropertyValue::I32(v.as_i64().unwrap_or_default() as i32)
+                }
+                Value::Number(v) => PropertyValue::F32(v.as_f64().unwrap_or_default() as f32),
+                Value::String(v) => PropertyValue::String(v.clone()),
+                Value::Object(object) => {
+                    if let Some(Value::String(iid)) = object.get("entityIid") {
+                        references.push((field.identifier.clone(), iid.clone()));
+                    } else {
+                        Log::warn(format!(
+                            "Field {} has {} type, which is not supported. It will be ignored.",
+                            field.identifier, field.kind
+                        ));
+                    }
+                    continue;
+                }
+                Value::Array(_) => continue,
+            };
+            properties.push(Property {
+                name: field.identifier.clone(),
+                value: property_value,
+            });
+        }
+    }
+}
+
+struct ProjectImporter<'a> {
+    project: &'a Project,
+    builder: LevelBuilder,
+    tile_sets: FxHashMap<i64, (TileSetPlacement, AtlasDescription)>,
+    int_grids: FxHashMap<i64, (TileSetPlacement, FxHashMap<i32, u32>)>,
+    unit: Vector2<f32>,
+}
+
+impl ProjectImporter<'_> {
+    fn import_tile_sets(&mut self, path: &Path, context: &ImportContext) {
+        for definition in self.project.defs.tilesets.iter() {
+            // Embedded tile sets of LDtk (such as the internal icons) have no image.
+            let Some(rel_path) = definition.rel_path.as_ref() else {
+                continue;
+            };
+            let atlas = AtlasDescription {
+                texture: context.request_texture(&resolve_path(path, rel_path)),
+                image_size: Vector2::new(definition.px_wid, definition.px_hei),
+                tile_size: Vector2::repeat(definition.tile_grid_size),
+                columns: definition.columns(),
+                tile_count: definition.columns() * definition.rows(),
+                margin: definition.padding,
+                spacing: definition.spacing,
+            };
+            let placement = self.builder.add_atlas(&atlas);
+
+            for custom_data in definition.custom_data.iter() {
+                if let Some(handle) = placement.handle(custom_data.tile_id) {
+                    self.builder.set_tile_property(
+                        handle,
+                        "CustomData",
+                        TileSetPropertyValue::String(custom_data.data.as_str().into()),
+                    );
+                }
+            }
+
+            let tags_enum = self
+                .project
+                .defs
+                .enums
+                .iter()
+                .find(|e| Some(e.uid) == definition.tags_source_enum_uid)
+                .map(|e| e.identifier.as_str())
+                .unwrap_or("Tags");
+            for tag in definition.enum_tags.iter() {
+                for handle in tag.tile_ids.iter().filter_map(|id| placement.handle(*id)) {
+                    self.builder.set_tile_property(
+                        handle,
+                        tags_enum,
+                        TileSetPropertyValue::String(tag.enum_value_id.as_str().into()),
+                    );
+                }
+            }
+
+            Log::info(format!(
+                "Tile set {} was imported to page {}.",
+                definition.identifier, placement.page
+            ));
+            self.tile_sets.insert(definition.uid, (placement, atlas));
+        }
+    }
+
+    /// Creates a page with colored tiles for every IntGrid layer definition.
+    fn import_int_grids(&mut self, layer_instances: &[&LayerInstance]) {
+        for definition in self.project.defs.layers.iter() {
+            if definition.int_grid_values.is_empty() {
+                continue;
+            }
+            let Some(identifier) = layer_instances
+                .iter()
+                .find(|l| l.layer_def_uid == definition.uid)
+                .map(|l| l.identifier.clone())
+            else {
+                continue;
+            };
+            let placement = self.builder.add_collection();
+            let mut ids = FxHashMap::default();
+            for (id, value) in definition.int_grid_values.iter().enumerate() {
+                let id = id as u32;
+                let Some(handle) =
+                    self.builder
+                        .add_color_tile(placement, id, parse_color(&value.color))
+                else {
+                    continue;
+                };
+                self.builder.set_tile_property(
+                    handle,
+                    "IntGrid",
+                    TileSetPropertyValue::I32(value.value),
+                );
+                self.builder
+                    .set_tile_collider(handle, &identifier, TileCollider::Rectangle);
+                ids.insert(value.value, id);
+            }
+            self.int_grids.insert(definition.uid, (placement, ids));
+        }
+    }
+
+    fn layer_base(&self, layer: &LayerInstance, name: &str, depth: f32) -> BaseBuilder {
+        let offset = pixels_to_units(
+            Vector2::new(layer.offset_x as f32, layer.offset_y as f32),
+            self.unit,
+        );
+        BaseBuilder::new()
+            .with_name(name)
+            .with_visibility(layer.visible)
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(offset.x, offset.y, depth))
+                    .build(),
+            )
+    }
+
+    fn import_tiles(&mut self, layer: &LayerInstance) -> Tiles {
+        let mut tiles = Tiles::default();
+        let Some((placement, _)) = layer.tileset_uid.and_then(|uid| self.tile_sets.get(&uid))
+        else {
+            return tiles;
+        };
+        let placement = *placement;
+        let grid_size = layer.grid_size.max(1) as i32;
+        // Tiles of a layer are sorted from bottom to top, so the last tile in a cell wins.
+        for tile in layer.grid_tiles.iter().chain(layer.auto_layer_tiles.iter()) {
+            let Some(handle) = placement.handle(tile.t) else {
+                continue;
+            };
+            let transformation = match tile.f & 3 {
+                0 => OrthoTransformation::identity(),
+                1 => OrthoTransformation::identity().x_flipped(),
+                2 => OrthoTransformation::identity().y_flipped(),
+                _ => OrthoTransformation::identity().rotated(2),
+            };
+            let Some(handle) = self.builder.transformed_tile(handle, transformation) else {
+                continue;
+            };
+            let cell = Vector2::new(
+                tile.px[0].div_euclid(grid_size),
+                tile.px[1].div_euclid(grid_size),
+            );
+            tiles.insert(cell_to_grid(cell), handle);
+        }
+        tiles
+    }
+
+    fn import_int_grid(&self, layer: &LayerInstance) -> Tiles {
+        let mut tiles = Tiles::default();
+        let Some((placement, ids)) = self.int_grids.get(&layer.layer_def_uid) else {
+            return tiles;
+        };
+        let width = layer.width.max(1);
+        for (i, value) in layer.int_grid_csv.iter().enumerate() {
+            let Some(handle) = ids.get(value).and_then(|id| placement.handle(*id)) else {
+                continue;
+            };
+            let cell = Vector2::new(i as i32 % width, i as i32 / width);
+            tiles.insert(cell_to_grid(cell), handle);
+        }
+        tiles
+    }
+
+    fn import_entity(&mut self, entity: &EntityInstance) -> LevelObject {
+        let size = Vector2::new(
+            entity.width as f32 / self.unit.x,
+            entity.height as f32 / self.unit.y,
+        );
+        // The position of an entity is the position of its pivot, the center of the entity is
+        // shifted from it according to the pivot.
+        let offset = Vector2::new(
+            (0.5 - entity.pivot[0]) * size.x,
+            (entity.pivot[1] - 0.5) * size.y,
+        );
+
+        let sprite = entity.tile.as_ref().and_then(|tile| {
+            let (_, atlas) = self.tile_sets.get(&tile.tileset_uid)?;
+            let texture = atlas.texture.clone();
+            let image_size = atlas.image_size;
+            let (material, uv_rect) = self.builder.image_sprite(
+                &texture,
+                image_size,
+                Rect::new(tile.x, tile.y, tile.w, tile.h),
+            );
+            Some(ObjectSprite {
+                material,
+                uv_rect,
+                offset,
+                size,
+                flip_x: false,
+                flip_y: false,
+            })
+        });
+
+        let mut properties = Vec::new();
+        let mut references = Vec::new();
+        field_properties(&entity.field_instances, &mut properties, &mut references);
+        for (name, value) in [("width", size.x), ("height", size.y)] {
+            properties.push(Property {
+                name: name.to_string(),
+                value: PropertyValue::F32(value),
+            });
+        }
+
+        let position = pixels_to_units(
+            Vector2::new(entity.px[0] as f32, entity.px[1] as f32),
+            self.unit,
+        );
+        LevelObject {
+            key: entity.iid.clone(),
+            name: entity.identifier.clone(),
+            tag: entity.tags.first().cloned().unwrap_or_default(),
+            position: Vector3::new(position.x, position.y, 0.0),
+            rotation: 0.0,
+            visible: true,
+            sprite,
+            properties,
+            references,
+        }
+    }
+
+    fn import_level(&mut self, level: &Level, parent: Handle<Node>, graph: &mut Graph) {
+        let position = pixels_to_units(
+            Vector2::new(level.world_x as f32, level.world_y as f32),
+            self.unit,
+        );
+        let level_handle: Handle<Node> = PivotBuilder::new(
+            BaseBuilder::new()
+                .with_name(&level.identifier)
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(Vector3::new(position.x, position.y, 0.0))
+                        .build(),
+                ),
+        )
+        .build(graph)
+        .to_base();
+        graph.link_nodes(level_handle, parent);
+
+        let mut properties = Vec::new();
+        let mut references = Vec::new();
+        field_properties(&level.field_instances, &mut properties, &mut references);
+        graph[level_handle].set_properties(properties);
+
+        let layers = level.layer_instances.as_deref().unwrap_or_default();
+        // Layers are sorted from top to bottom.
+        for (index, layer) in layers.iter().enumerate() {
+            let depth = -((layers.len() - 1 - index) as f32) * LAYER_DEPTH_STEP;
+            let tile_scale = Vector2::repeat(layer.grid_size as f32).component_div(&self.unit);
+            let mut handles = Vec::new();
+            match layer.kind.as_str() {
+                "Tiles" | "AutoLayer" => {
+                    let tiles = self.import_tiles(layer);
+                    let base = self.layer_base(layer, &layer.identifier, depth);
+                    handles.push(self.builder.add_tile_map(graph, base, tiles, tile_scale));
+                }
+                "IntGrid" => {
+                    let int_grid = self.import_int_grid(layer);
+                    let name = format!("{} IntGrid", layer.identifier);
+                    let base = self.layer_base(layer, &name, depth).with_visibility(false);
+                    handles.push(self.builder.add_tile_map(graph, base, int_grid, tile_scale));
+                    if !layer.auto_layer_tiles.is_empty() {
+                        let tiles = self.import_tiles(layer);
+                        let base = self.layer_base(layer, &layer.identifier, depth);
+                        handles.push(self.builder.add_tile_map(graph, base, tiles, tile_scale));
+                    }
+                }
+                "Entities" => {
+                    let base = self.layer_base(layer, &layer.identifier, depth);
+                    let handle: Handle<Node> = PivotBuilder::new(base).build(graph).to_base();
+                    for entity in layer.entity_instances.iter() {
+                        let object = self.import_entity(entity);
+                        self.builder.add_object(graph, handle, object);
+                    }
+                    handles.push(handle);
+                }
+                kind => Log::warn(format!(
+                    "Layer {} has unsupported type {kind}, it will be ignored.",
+                    layer.identifier
+                )),
+            }
+            for handle in handles {
+                graph.link_nodes(handle, level_handle);
+            }
+        }
+    }
+}
+
+fn import_project(
+    project: &Project,
+    levels: &[Level],
+    path: &Path,
+    context: &ImportContext,
+    graph: &mut Graph,
+) {
+    let mut importer = ProjectImporter {
+        project,
+        builder: LevelBuilder::new(),
+        tile_sets: Default::default(),
+        int_grids: Default::default(),
+        unit: Vector2::repeat(project.default_grid_size.max(1) as f32),
+    };
+    importer.import_tile_sets(path, context);
+    let layer_instances = levels
+        .iter()
+        .flat_map(|l| l.layer_instances.iter().flatten())
+        .collect::<Vec<_>>();
+    importer.import_int_grids(&layer_instances);
+
+    let root = graph.get_root();
+    for level in levels {
+        importer.import_level(level, root, graph);
+    }
+    importer.builder.finish(graph);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_properties() {
+        let fields = serde_json::from_str::<Vec<FieldInstance>>(
+            r#"[
+                { "__identifier": "hp", "__type": "Int", "__value": 10 },
+                { "__identifier": "speed", "__type": "Float", "__value": 1.5 },
+                { "__identifier": "loot", "__type": "Array<String>", "__value": ["a", "b"] },
+                { "__identifier": "unset", "__type": "String", "__value": null },
+                { "__identifier": "target", "__type": "EntityRef",
+                  "__value": { "entityIid": "abc", "layerIid": "l", "levelIid": "v", "worldIid": "w" } }
+            ]"#,
+        )
+        .unwrap();
+        let mut properties = Vec::new();
+        let mut references = Vec::new();
+        field_properties(&fields, &mut properties, &mut references);
+        assert_eq!(
+            properties,
+            vec![
+                Property {
+                    name: "hp".to_string(),
+                    value: PropertyValue::I32(10)
+                },
+                Property {
+                    name: "speed".to_string(),
+                    value: PropertyValue::F32(1.5)
+                },
+                Property {
+                    name: "loot".to_string(),
+                    value: PropertyValue::String("a".to_string())
+                },
+                Property {
+                    name: "loot".to_string(),
+                    value: PropertyValue::String("b".to_string())
+                },
+            ]
+        );
+        assert_eq!(references, vec![("target".to_string(), "abc".to_string())]);
+    }
+
+    #[test]
+    fn test_tileset_layout() {
+        let definition = serde_json::from_str::<TilesetDefinition>(
+            r#"{ "uid": 1, "identifier": "Tiles", "relPath": "tiles.png", "pxWid": 84,
+                 "pxHei": 44, "tileGridSize": 16, "spacing": 4, "padding": 2,
+                 "tagsSourceEnumUid": null }"#,
+        )
+        .unwrap();
+        assert_eq!(definition.columns(), 4);
+        assert_eq!(definition.rows(), 2);
+        assert_eq!(parse_color("#FF8000"), Color::opaque(255, 128, 0));
+    }
+}
@@ -0,0 +1,905 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Importers for 2D levels that were authored in external level editors. [`TmxLoader`] imports
+//! maps made in [Tiled](https://www.mapeditor.org) (`*.tmx`) and [`LdtkLoader`] imports projects
+//! made in [LDtk](https://ldtk.io) (`*.ldtk`).
+//!
+//! Both loaders produce a [`Model`](crate::resource::model::Model) resource, so an imported level
+//! can be instantiated just like any other prefab. The scene of the model contains one [`TileMap`]
+//! node per tile layer of the source file and all of them share a single embedded [`TileSet`],
+//! which is assembled from the tile sets used by the level:
+//!
+//! - Every source tile set becomes a page of the tile set. Tile sets that are based on a single
+//!   image become atlas pages, tile sets that are made of separate images become freeform pages.
+//! - Custom tile properties become property layers of the tile set.
+//! - Collision shapes of tiles become collider layers of the tile set. Tile layers that use tiles
+//!   with collision shapes get a static rigid body with a [`TileMapShape`] collider for every
+//!   collider layer, so the level is solid right after instantiation.
+//! - Object layers (Tiled) and entity layers (LDtk) are spawned as nodes with the custom properties
+//!   of the objects stored in [`Base::properties`](crate::scene::base::Base::properties).
+//!
+//! One tile of the source grid is one unit of the scene. Since the source editors use Y axis that
+//! points down, Y coordinates are flipped. Layers that are drawn on top of other layers in the
+//! source editor are placed closer to the standard 2D camera (see [`LAYER_DEPTH_STEP`]).
+//!
+//! Imported levels are hot-reloaded as any other model resource: when the source file changes, the
+//! resource manager reloads it and propagates the changes to every instance of the level.
+
+pub mod ldtk;
+pub mod tmx;
+
+pub use ldtk::LdtkLoader;
+pub use tmx::TmxLoader;
+
+use crate::{
+    asset::{io::ResourceIo, manager::ResourceManager, untyped::ResourceKind, Resource},
+    core::{
+        algebra::{UnitQuaternion, Vector2, Vector3},
+        color::Color,
+        io::FileError,
+        log::Log,
+        math::{triangulator::triangulate, Rect, TriangleDefinition},
+        pool::Handle,
+        uuid::Uuid,
+        ImmutableString,
+    },
+    fxhash::{FxHashMap, FxHashSet},
+    graph::SceneGraph,
+    material::{Material, MaterialResource},
+    resource::texture::{Texture, TextureResource},
+    scene::{
+        base::{BaseBuilder, Property, PropertyValue},
+        dim2::{
+            collider::{ColliderBuilder, ColliderShape, GeometrySource, TileMapShape},
+            rectangle::RectangleBuilder,
+            rigidbody::RigidBodyBuilder,
+        },
+        graph::Graph,
+        node::Node,
+        pivot::PivotBuilder,
+        rigidbody::RigidBodyType,
+        tilemap::{
+            tileset::{
+                AnimationTiles, TileBounds, TileData, TileDefinition, TileMaterial,
+                TileMaterialBounds, TileSet, TileSetColliderLayer, TileSetPage, TileSetPageSource,
+                TileSetPropertyLayer, TileSetPropertyValue, TileSetResource,
+            },
+            CustomTileCollider, OrthoTransform, OrthoTransformation, TileCollider,
+            TileDefinitionHandle, TileGridMap, TileMap, TileMapBuilder, Tiles,
+        },
+        transform::TransformBuilder,
+    },
+};
+use std::{
+    fmt::{Display, Formatter},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+/// Distance along Z axis between two adjacent layers of an imported level. Layers that are drawn
+/// on top of other layers in the source editor get smaller Z coordinate.
+pub const LAYER_DEPTH_STEP: f32 = 0.01;
+
+/// Amount of columns of freeform pages that are created for tile sets made of separate images.
+const COLLECTION_COLUMNS: u32 = 16;
+
+/// Name of the collider layer for collision shapes that do not specify a layer explicitly.
+const DEFAULT_COLLIDER_LAYER: &str = "Collision";
+
+const COLLIDER_COLORS: [Color; 4] = [
+    Color::opaque(255, 0, 255),
+    Color::opaque(0, 255, 255),
+    Color::opaque(255, 255, 0),
+    Color::opaque(0, 255, 0),
+];
+
+/// An error that may occur during level import.
+#[derive(Debug)]
+pub enum LevelImportError {
+    /// An i/o error has occurred.
+    Io(FileError),
+    /// The source file is not a valid XML document.
+    Xml(roxmltree::Error),
+    /// The source file is not a valid JSON document or it does not match the expected layout.
+    Json(serde_json::Error),
+    /// Base64-encoded data could not be decoded.
+    Base64(base64::DecodeError),
+    /// Compressed data could not be decompressed.
+    Decompression(String),
+    /// A mandatory attribute of an element is missing.
+    MissingAttribute {
+        /// Name of the element.
+        element: String,
+        /// Name of the attribute.
+        attribute: String,
+    },
+    /// A value could not be parsed.
+    InvalidValue {
+        /// Name of the value.
+        name: String,
+        /// The actual text of the value.
+        value: String,
+    },
+    /// The source file uses a feature that is not supported by the importer.
+    Unsupported(String),
+}
+
+impl Display for LevelImportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(v) => write!(f, "A file load error has occurred {v:?}"),
+            Self::Xml(v) => write!(f, "Unable to parse XML document. Reason: {v}"),
+            Self::Json(v) => write!(f, "Unable to parse JSON document. Reason: {v}"),
+            Self::Base64(v) => write!(f, "Unable to decode base64 data. Reason: {v}"),
+            Self::Decompression(v) => write!(f, "Unable to decompress data. Reason: {v}"),
+            Self::MissingAttribute { element, attribute } => {
+                write!(f, "Element {element} does not have {attribute} attribute.")
+            }
+            Self::InvalidValue { name, value } => write!(f, "Invalid value {value:?} of {name}."),
+            Self::Unsupported(v) => write!(f, "Unsupported feature: {v}."),
+        }
+    }
+}
+
+impl std::error::Error for LevelImportError {}
+
+impl From<FileError> for LevelImportError {
+    fn from(e: FileError) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<roxmltree::Error> for LevelImportError {
+    fn from(e: roxmltree::Error) -> Self {
+        Self::Xml(e)
+    }
+}
+
+impl From<serde_json::Error> for LevelImportError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+impl From<base64::DecodeError> for LevelImportError {
+    fn from(e: base64::DecodeError) -> Self {
+        Self::Base64(e)
+    }
+}
+
+struct ImportContext {
+    io: Arc<dyn ResourceIo>,
+    resource_manager: ResourceManager,
+}
+
+impl ImportContext {
+    async fn load_file(&self, path: &Path) -> Result<Vec<u8>, LevelImportError> {
+        Ok(self.io.load_file(path).await?)
+    }
+
+    async fn load_string(&self, path: &Path) -> Result<String, LevelImportError> {
+        let bytes = self.load_file(path).await?;
+        String::from_utf8(bytes).map_err(|e| LevelImportError::InvalidValue {
+            name: path.to_string_lossy().to_string(),
+            value: e.to_string(),
+        })
+    }
+
+    fn request_texture(&self, path: &Path) -> TextureResource {
+        self.resource_manager.request::<Texture>(path)
+    }
+}
+
+/// Resolves a path that is relative to the file at `base` and removes all `.` and `..` components
+/// from it, so the resulting path could be used to request resources.
+fn resolve_path(base: &Path, relative: &str) -> PathBuf {
+    let joined = base.parent().unwrap_or(Path::new("")).join(relative);
+    let mut result = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                if !result.pop() {
+                    result.push("..");
+                }
+            }
+            other => result.push(other),
+        }
+    }
+    result
+}
+
+/// Converts a position from pixel space of a source editor (Y axis points down) to the local
+/// space of a scene node, where one unit is one tile.
+fn pixels_to_units(position: Vector2<f32>, tile_size: Vector2<f32>) -> Vector2<f32> {
+    Vector2::new(position.x / tile_size.x, -position.y / tile_size.y)
+}
+
+/// Converts a position of a cell from a source editor (Y axis points down) to the position of the
+/// cell of a tile map.
+fn cell_to_grid(cell: Vector2<i32>) -> Vector2<i32> {
+    Vector2::new(cell.x, -1 - cell.y)
+}
+
+/// Location of a source tile set within the tile set of an imported level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TileSetPlacement {
+    page: Vector2<i32>,
+    columns: u32,
+}
+
+impl TileSetPlacement {
+    /// Position of the tile with the given index on the page. Tiles are laid out row by row, just
+    /// like in the source image, so the first tile is in the top-left corner of the page.
+    fn tile_position(&self, id: u32) -> Vector2<i32> {
+        let columns = self.columns.max(1);
+        cell_to_grid(Vector2::new((id % columns) as i32, (id / columns) as i32))
+    }
+
+    fn handle(&self, id: u32) -> Option<TileDefinitionHandle> {
+        TileDefinitionHandle::try_new(self.page, self.tile_position(id))
+    }
+}
+
+/// Description of a tile set that is based on a single image.
+struct AtlasDescription {
+    texture: TextureResource,
+    image_size: Vector2<u32>,
+    tile_size: Vector2<u32>,
+    columns: u32,
+    tile_count: u32,
+    margin: u32,
+    spacing: u32,
+}
+
+impl AtlasDescription {
+    fn tile_bounds(&self, id: u32) -> TileBounds {
+        let columns = self.columns.max(1);
+        let origin = Vector2::new(
+            self.margin + (id % columns) * (self.tile_size.x + self.spacing),
+            self.margin + (id / columns) * (self.tile_size.y + self.spacing),
+        );
+        TileBounds {
+            left_top_corner: origin,
+            right_top_corner: origin + Vector2::new(self.tile_size.x, 0),
+            left_bottom_corner: origin + Vector2::new(0, self.tile_size.y),
+            right_bottom_corner: origin + self.tile_size,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct TileImage {
+    texture: TextureResource,
+    size: Vector2<u32>,
+}
+
+/// A sprite that is attached to an imported object.
+struct ObjectSprite {
+    material: MaterialResource,
+    uv_rect: Rect<f32>,
+    /// Position of the center of the sprite relative to the object.
+    offset: Vector2<f32>,
+    size: Vector2<f32>,
+    flip_x: bool,
+    flip_y: bool,
+}
+
+/// A property that refers to another object: `(property name, object key)` pair.
+type ObjectReference = (String, String);
+
+/// An object of an object layer (or an entity of an entity layer) that will be spawned as a node.
+struct LevelObject {
+    /// A unique key of the object in the source file, it is used to resolve references between
+    /// objects.
+    key: String,
+    name: String,
+    tag: String,
+    position: Vector3<f32>,
+    /// Counter-clockwise rotation in radians.
+    rotation: f32,
+    visible: bool,
+    sprite: Option<ObjectSprite>,
+    properties: Vec<Property>,
+    /// Properties that refer to other objects.
+    references: Vec<ObjectReference>,
+}
+
+/// Assembles the tile set of an imported level and the scene graph nodes that use it.
+struct LevelBuilder {
+    tile_set: TileSet,
+    next_page: i32,
+    page_images: FxHashMap<Vector2<i32>, TileImage>,
+    tile_images: FxHashMap<TileDefinitionHandle, TileImage>,
+    sprite_materials: FxHashMap<u64, MaterialResource>,
+    transformed_page: Option<TileSetPlacement>,
+    transformed: FxHashMap<(TileDefinitionHandle, OrthoTransformation), TileDefinitionHandle>,
+    animation_pages: FxHashMap<u32, (Vector2<i32>, i32)>,
+    tile_maps: Vec<(Handle<Node>, FxHashSet<TileDefinitionHandle>)>,
+    objects: FxHashMap<String, Handle<Node>>,
+    references: Vec<(Handle<Node>, String, String)>,
+}
+
+impl LevelBuilder {
+    fn new() -> Self {
+        Self {
+            tile_set: TileSet::default(),
+            next_page: 0,
+            page_images: Default::default(),
+            tile_images: Default::default(),
+            sprite_materials: Default::default(),
+            transformed_page: None,
+            transformed: Default::default(),
+            animation_pages: Default::default(),
+            tile_maps: Default::default(),
+            objects: Default::default(),
+            references: Default::default(),
+        }
+    }
+
+    fn add_page(&mut self, source: TileSetPageSource) -> Vector2<i32> {
+        let position = Vector2::new(self.next_page, 0);
+        self.next_page += 1;
+        self.tile_set.insert_page(
+            position,
+            TileSetPage {
+                icon: TileDefinitionHandle::try_new(position, Vector2::new(0, -1))
+                    .unwrap_or_default(),
+                source,
+            },
+        );
+        position
+    }
+
+    /// Adds a page for a tile set that is based on a single image. Atlas pages cannot describe
+    /// margins and spacing between tiles, so a freeform page is used in this case.
+    fn add_atlas(&mut self, atlas: &AtlasDescription) -> TileSetPlacement {
+        let mut material = Material::standard_tile();
+        material.bind("diffuseTexture", atlas.texture.clone());
+        let material = MaterialResource::new_embedded(material);
+
+        let placement = TileSetPlacement {
+            page: Vector2::default(),
+            columns: atlas.columns.max(1),
+        };
+        let source = if atlas.margin == 0 && atlas.spacing == 0 {
+            let mut tiles = TileGridMap::default();
+            for id in 0..atlas.tile_count {
+                tiles.insert(placement.tile_position(id), TileData::default());
+            }
+            TileSetPageSource::Atlas(TileMaterial {
+                material,
+                tile_size: atlas.tile_size,
+                tiles,
+            })
+        } else {
+            let mut tiles = TileGridMap::default();
+            for id in 0..atlas.tile_count {
+                tiles.insert(
+                    placement.tile_position(id),
+                    TileDefinition {
+                        material_bounds: TileMaterialBounds {
+                            material: material.clone(),
+                            bounds: atlas.tile_bounds(id),
+                        },
+                        data: TileData::default(),
+                    },
+                );
+            }
+            TileSetPageSource::Freeform(tiles)
+        };
+        let page = self.add_page(source);
+        self.page_images.insert(
+            page,
+            TileImage {
+                texture: atlas.texture.clone(),
+                size: atlas.image_size,
+            },
+        );
+        TileSetPlacement { page, ..placement }
+    }
+
+    /// Adds an empty freeform page for tiles that have their own images or no images at all.
+    fn add_collection(&mut self) -> TileSetPlacement {
+        TileSetPlacement {
+            page: self.add_page(TileSetPageSource::new_free()),
+            columns: COLLECTION_COLUMNS,
+        }
+    }
+
+    fn insert_definition(
+        &mut self,
+        placement: TileSetPlacement,
+        id: u32,
+        definition: TileDefinition,
+    ) -> Option<TileDefinitionHandle> {
+        let handle = placement.handle(id)?;
+        let page = self.tile_set.get_page_mut(placement.page)?;
+        let TileSetPageSource::Freeform(tiles) = &mut page.source else {
+            return None;
+        };
+        tiles.insert(handle.tile(), definition);
+        Some(handle)
+    }
+
+    /// Adds a tile that uses a whole image to a page created by [`Self::add_collection`].
+    fn add_image_tile(
+        &mut self,
+        placement: TileSetPlacement,
+        id: u32,
+        texture: TextureResource,
+        size: Vector2<u32>,
+    ) -> Option<TileDefinitionHandle> {
+        let mut material = Material::standard_tile();
+        material.bind("diffuseTexture", texture.clone());
+        let definition = TileDefinition {
+            material_bounds: TileMaterialBounds {
+                material: MaterialResource::new_embedded(material),
+                bounds: TileBounds {
+                    left_top_corner: Vector2::new(0, 0),
+                    right_top_corner: Vector2::new(size.x, 0),
+                    left_bottom_corner: Vector2::new(0, size.y),
+                    right_bottom_corner: size,
+                },
+            },
+            data: TileData::default(),
+        };
+        let handle = self.insert_definition(placement, id, definition)?;
+        self.tile_images.insert(handle, TileImage { texture, size });
+        Some(handle)
+    }
+
+    /// Adds a tile without an image, that is rendered using the given color only.
+    fn add_color_tile(
+        &mut self,
+        placement: TileSetPlacement,
+        id: u32,
+        color: Color,
+    ) -> Option<TileDefinitionHandle> {
+        let definition = TileDefinition {
+            material_bounds: TileMaterialBounds::default(),
+            data: TileData {
+                color,
+                ..Default::default()
+            },
+        };
+        self.insert_definition(placement, id, definition)
+    }
+
+    /// Sets the value of the property with the given name for the given tile. A new property
+    /// layer is created if there's no layer with the given name. Values of a type that does not
+    /// match the type of the layer are ignored.
+    fn set_tile_property(
+        &mut self,
+        handle: TileDefinitionHandle,
+        name: &str,
+        value: TileSetPropertyValue,
+    ) {
+        let name = ImmutableString::new(name);
+        let uuid = match self.tile_set.find_property_by_name(&name) {
+            Some(layer) => {
+                if layer.prop_type != value.prop_type() {
+                    Log::warn(format!(
+                        "Value of {name} property of tile {handle} is ignored, because its type \
+                        does not match the type of the property layer."
+                    ));
+                    return;
+                }
+                layer.uuid
+            }
+            None => {
+                let uuid = Uuid::new_v4();
+                self.tile_set.properties.push(TileSetPropertyLayer {
+                    uuid,
+                    name,
+                    prop_type: value.prop_type(),
+                    named_values: Default::default(),
+                });
+                uuid
+            }
+        };
+        if let Some(data) = self.tile_set.get_tile_data_mut(handle) {
+            data.properties.insert(uuid, value);
+        }
+    }
+
+    /// Sets the collider of the given tile in the collider layer with the given name. A new
+    /// collider layer is created if there's no layer with the given name.
+    fn set_tile_collider(
+        &mut self,
+        handle: TileDefinitionHandle,
+        name: &str,
+        collider: TileCollider,
+    ) {
+        let name = ImmutableString::new(name);
+        let uuid = match self.tile_set.collider_name_to_uuid(&name) {
+            Some(uuid) => uuid,
+            None => {
+                let uuid = Uuid::new_v4();
+                let color = COLLIDER_COLORS[self.tile_set.colliders.len() % COLLIDER_COLORS.len()];
+                self.tile_set
+                    .colliders
+                    .push(TileSetColliderLayer { uuid, name, color });
+                uuid
+            }
+        };
+        if let Some(data) = self.tile_set.get_tile_data_mut(handle) {
+            data.colliders.insert(uuid, collider);
+        }
+    }
+
+    /// Adds an animation sequence to the tile set. Each frame is a tile and its duration in
+    /// milliseconds. Since animation pages have a fixed frame rate, frames of different durations
+    /// are repeated as many times as needed and animations with different rates are placed on
+    /// different pages.
+    fn add_animation(&mut self, frames: &[(TileDefinitionHandle, u32)]) {
+        fn gcd(a: u32, b: u32) -> u32 {
+            if b == 0 {
+                a
+            } else {
+                gcd(b, a % b)
+            }
+        }
+
+        let step = frames
+            .iter()
+            .fold(0, |acc, (_, duration)| gcd(acc, *duration));
+        if frames.is_empty() || step == 0 {
+            return;
+        }
+
+        let (page, row) = match self.animation_pages.get(&step) {
+            Some(entry) => *entry,
+            None => {
+                let page = self.add_page(TileSetPageSource::Animation(AnimationTiles {
+                    frame_rate: 1000.0 / step as f32,
+                    tiles: Default::default(),
+                }));
+                (page, -1)
+            }
+        };
+        self.animation_pages.insert(step, (page, row - 1));
+
+        let Some(TileSetPageSource::Animation(tiles)) =
+            self.tile_set.get_page_mut(page).map(|p| &mut p.source)
+        else {
+            return;
+        };
+        let mut x = 0;
+        for (handle, duration) in frames {
+            for _ in 0..duration / step {
+                tiles.insert(Vector2::new(x, row), *handle);
+                x += 1;
+            }
+        }
+    }
+
+    /// Returns a handle of a tile that looks like the given tile with the given transformation
+    /// applied. Transformed versions of tiles are created on demand on a separate page.
+    fn transformed_tile(
+        &mut self,
+        handle: TileDefinitionHandle,
+        transform: OrthoTransformation,
+    ) -> Option<TileDefinitionHandle> {
+        if transform.is_identity() {
+            return Some(handle);
+        }
+        if let Some(transformed) = self.transformed.get(&(handle, transform)) {
+            return Some(*transformed);
+        }
+        let definition = self.tile_set.get_definition(handle)?.transformed(transform);
+        let placement = match self.transformed_page {
+            Some(placement) => placement,
+            None => {
+                let placement = self.add_collection();
+                self.transformed_page = Some(placement);
+                placement
+            }
+        };
+        let id = self.transformed.len() as u32;
+        let transformed = self.insert_definition(placement, id, definition)?;
+        self.transformed.insert((handle, transform), transformed);
+        Some(transformed)
+    }
+
+    fn sprite_material(&mut self, texture: &TextureResource) -> MaterialResource {
+        self.sprite_materials
+            .entry(texture.key())
+            .or_insert_with(|| {
+                let mut material = Material::standard_2d();
+                material.bind("diffuseTexture", texture.clone());
+                MaterialResource::new_embedded(material)
+            })
+            .clone()
+    }
+
+    /// Returns a material and texture coordinates of the rectangle with the given pixel coordinates
+    /// within the given image.
+    fn image_sprite(
+        &mut self,
+        texture: &TextureResource,
+        image_size: Vector2<u32>,
+        rect: Rect<u32>,
+    ) -> (MaterialResource, Rect<f32>) {
+        let size = image_size.map(|v| v.max(1) as f32);
+        let uv_rect = Rect::new(
+            rect.position.x as f32 / size.x,
+            rect.position.y as f32 / size.y,
+            rect.size.x as f32 / size.x,
+            rect.size.y as f32 / size.y,
+        );
+        (self.sprite_material(texture), uv_rect)
+    }
+
+    /// Returns a material and texture coordinates to render the given tile as a sprite.
+    fn tile_sprite(
+        &mut self,
+        handle: TileDefinitionHandle,
+    ) -> Option<(MaterialResource, Rect<f32>)> {
+        let image = self
+            .tile_images
+            .get(&handle)
+            .or_else(|| self.page_images.get(&handle.page()))?
+            .clone();
+        let bounds = self.tile_set.get_tile_bounds(handle.into())?.bounds;
+        let rect = Rect::new(
+            bounds.left_top_corner.x,
+            bounds.left_top_corner.y,
+            bounds.right_bottom_corner.x - bounds.left_top_corner.x,
+            bounds.right_bottom_corner.y - bounds.left_top_corner.y,
+        );
+        Some(self.image_sprite(&image.texture, image.size, rect))
+    }
+
+    fn add_tile_map(
+        &mut self,
+        graph: &mut Graph,
+        base_builder: BaseBuilder,
+        tiles: Tiles,
+        tile_scale: Vector2<f32>,
+    ) -> Handle<Node> {
+        let used = tiles.values().copied().collect();
+        let handle = TileMapBuilder::new(base_builder)
+            .with_tiles(&tiles)
+            .with_tile_scale(tile_scale)
+            .build(graph)
+            .to_base();
+        self.tile_maps.push((handle, used));
+        handle
+    }
+
+    fn add_object(
+        &mut self,
+        graph: &mut Graph,
+        parent: Handle<Node>,
+        object: LevelObject,
+    ) -> Handle<Node> {
+        let mut children: Vec<Handle<Node>> = Vec::new();
+        if let Some(sprite) = object.sprite {
+            children.push(
+                RectangleBuilder::new(
+                    BaseBuilder::new().with_name("Sprite").with_local_transform(
+                        TransformBuilder::new()
+                            .with_local_position(sprite.offset.to_homogeneous())
+                            .with_local_scale(Vector3::new(sprite.size.x, sprite.size.y, 1.0))
+                            .build(),
+                    ),
+                )
+                .with_material(sprite.material)
+                .with_uv_rect(sprite.uv_rect)
+                .with_flip_x(sprite.flip_x)
+                .with_flip_y(sprite.flip_y)
+                .build(graph)
+                .to_base(),
+            );
+        }
+
+        let mut base_builder = BaseBuilder::new()
+            .with_name(object.name)
+            .with_tag(object.tag)
+            .with_visibility(object.visible)
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(object.position)
+                    .with_local_rotation(UnitQuaternion::from_axis_angle(
+                        &Vector3::z_axis(),
+                        object.rotation,
+                    ))
+                    .build(),
+            );
+        for child in children {
+            base_builder = base_builder.with_child(child);
+        }
+        let handle: Handle<Node> = PivotBuilder::new(base_builder).build(graph).to_base();
+        graph[handle].set_properties(object.properties);
+        graph.link_nodes(handle, parent);
+
+        for (property, key) in object.references {
+            self.references.push((handle, property, key));
+        }
+        self.objects.insert(object.key, handle);
+        handle
+    }
+
+    /// Finishes the tile set, assigns it to every tile map created by the builder, creates
+    /// colliders for the tile maps and resolves references between objects.
+    fn finish(mut self, graph: &mut Graph) -> TileSetResource {
+        self.tile_set.rebuild_transform_sets();
+        self.tile_set.rebuild_animations();
+
+        let collider_layers = self
+            .tile_set
+            .colliders
+            .iter()
+            .map(|layer| (layer.uuid, layer.name.clone()))
+            .collect::<Vec<_>>();
+
+        let mut solid_tile_maps = Vec::new();
+        for (tile_map, used) in self.tile_maps.iter() {
+            for (uuid, name) in collider_layers.iter() {
+                if used
+                    .iter()
+                    .any(|handle| !self.tile_set.tile_collider(*handle, *uuid).is_none())
+                {
+                    solid_tile_maps.push((*tile_map, name.clone()));
+                }
+            }
+        }
+
+        let tile_set = Resource::new_ok(Uuid::new_v4(), ResourceKind::Embedded, self.tile_set);
+        for (tile_map, _) in self.tile_maps.iter() {
+            if let Some(tile_map) = graph[*tile_map].cast_mut::<TileMap>() {
+                tile_map.set_tile_set(Some(tile_set.clone()));
+            }
+        }
+
+        for (tile_map, layer_name) in solid_tile_maps {
+            let collider = ColliderBuilder::new(BaseBuilder::new().with_name(layer_name.as_str()))
+                .with_shape(ColliderShape::TileMap(TileMapShape {
+                    tile_map: GeometrySource(tile_map),
+                    layer_name,
+                }))
+                .build(graph);
+            let body = RigidBodyBuilder::new(
+                BaseBuilder::new()
+                    .with_name("TileMapBody")
+                    .with_child(collider),
+            )
+            .with_body_type(RigidBodyType::Static)
+            .build(graph);
+            graph.link_nodes(body, tile_map);
+        }
+
+        for (node, property, key) in self.references {
+            let Some(target) = self.objects.get(&key).copied() else {
+                Log::warn(format!(
+                    "Property {property} refers to unknown object {key}, the property is ignored."
+                ));
+                continue;
+            };
+            let mut properties = graph[node].properties.clone_inner();
+            properties.push(Property {
+                name: property,
+                value: PropertyValue::NodeHandle(target),
+            });
+            graph[node].set_properties(properties);
+        }
+
+        tile_set
+    }
+}
+
+/// Combines a set of polygons into a collider of a tile. Coordinates of the polygons must be in
+/// the `[0; 1]` range, where `(0, 0)` is the left-bottom corner of the tile. A single polygon that
+/// covers the whole tile becomes [`TileCollider::Rectangle`].
+fn polygons_to_tile_collider(polygons: &[Vec<Vector2<f32>>]) -> TileCollider {
+    const EPSILON: f32 = 1.0e-3;
+
+    if let [polygon] = polygons {
+        if polygon.len() == 4 {
+            // The polygon covers the whole tile only if its vertices are exactly the corners of
+            // the tile.
+            let corners = [
+                Vector2::new(0.0, 0.0),
+                Vector2::new(1.0, 0.0),
+                Vector2::new(1.0, 1.0),
+                Vector2::new(0.0, 1.0),
+            ];
+            let is_full_tile = corners
+                .iter()
+                .all(|corner| polygon.iter().any(|p| (p - corner).abs().max() < EPSILON));
+            if is_full_tile {
+                return TileCollider::Rectangle;
+            }
+        }
+    }
+
+    let mut collider = CustomTileCollider::default();
+    let mut triangles = Vec::new();
+    for polygon in polygons.iter().filter(|p| p.len() >= 3) {
+        let vertices = polygon
+            .iter()
+            .map(|p| Vector3::new(p.x, p.y, 0.0))
+            .collect::<Vec<_>>();
+        triangulate(&vertices, &mut triangles);
+        let origin = collider.vertices.len() as u32;
+        collider.vertices.extend_from_slice(polygon);
+        collider.triangles.extend(
+            triangles
+                .iter()
+                .map(|t| TriangleDefinition(t.map(|i| origin + i as u32))),
+        );
+    }
+
+    if collider.triangles.is_empty() {
+        TileCollider::None
+    } else {
+        TileCollider::Custom(Resource::new_ok(
+            Uuid::new_v4(),
+            ResourceKind::Embedded,
+            collider,
+        ))
+    }
+}
+
+/// Approximates an ellipse that is inscribed into the given rectangle by a polygon.
+fn ellipse_polygon(center: Vector2<f32>, radius: Vector2<f32>) -> Vec<Vector2<f32>> {
+    const SEGMENTS: usize = 16;
+    (0..SEGMENTS)
+        .map(|i| {
+            let angle = i as f32 * std::f32::consts::TAU / SEGMENTS as f32;
+            center + Vector2::new(radius.x * angle.cos(), radius.y * angle.sin())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polygons_to_tile_collider() {
+        let square = vec![
+            Vector2::new(0.0, 1.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(0.0, 0.0),
+        ];
+        assert!(polygons_to_tile_collider(&[square]).is_rectangle());
+
+        // The bounding box of the quad is the whole tile, but the quad itself is not.
+        let quad = vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(1.0, 0.5),
+            Vector2::new(0.0, 1.0),
+        ];
+        let TileCollider::Custom(custom) = polygons_to_tile_collider(std::slice::from_ref(&quad))
+        else {
+            panic!("Custom collider expected");
+        };
+        let custom = custom.data_ref();
+        assert_eq!(custom.vertices, quad);
+        assert_eq!(custom.triangles.len(), 2);
+
+        assert!(matches!(polygons_to_tile_collider(&[]), TileCollider::None));
+    }
+}
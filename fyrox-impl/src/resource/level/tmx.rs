@@ -0,0 +1,1033 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Importer for maps made in [Tiled](https://www.mapeditor.org). See [`TmxLoader`] docs for more
+//! info.
+
+use super::{
+    cell_to_grid, ellipse_polygon, pixels_to_units, polygons_to_tile_collider, resolve_path,
+    AtlasDescription, ImportContext, LevelBuilder, LevelImportError, LevelObject, ObjectReference,
+    ObjectSprite, TileSetPlacement, DEFAULT_COLLIDER_LAYER, LAYER_DEPTH_STEP,
+};
+use crate::{
+    asset::{
+        io::ResourceIo,
+        loader::{BoxedLoaderFuture, LoaderPayload, ResourceLoader},
+        manager::ResourceManager,
+        state::LoadError,
+    },
+    core::{
+        algebra::{Vector2, Vector3},
+        log::Log,
+        math::Rect,
+        pool::Handle,
+        reflect::Reflect,
+        uuid::Uuid,
+    },
+    fxhash::FxHashMap,
+    graph::{NodeMapping, SceneGraph},
+    resource::model::Model,
+    scene::{
+        base::{BaseBuilder, Property, PropertyValue},
+        graph::Graph,
+        node::Node,
+        pivot::PivotBuilder,
+        tilemap::{
+            tileset::TileSetPropertyValue, OrthoTransform, OrthoTransformation, TileCollider,
+            TileDefinitionHandle, Tiles,
+        },
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use base64::engine::{general_purpose::STANDARD as Base64Engine, Engine as _};
+use roxmltree::{Document, Node as XmlNode};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const ROTATED_HEXAGONAL_120: u32 = 0x1000_0000;
+const FLAGS_MASK: u32 =
+    FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL_120;
+
+/// Imports maps made in [Tiled](https://www.mapeditor.org) (`*.tmx`) as model resources. See the
+/// [module docs](super) for the layout of the imported scene.
+///
+/// Supported features:
+///
+/// - Orthogonal maps, both finite and infinite, with tile layer data in any encoding except
+///   zstd-compressed one.
+/// - Embedded and external (`*.tsx`) tile sets, both image-based and image collections.
+/// - Flipped and rotated tiles.
+/// - Tile animations.
+/// - Custom properties of tiles, layers, objects and the map itself. Properties of `object` type
+///   are converted to [`PropertyValue::NodeHandle`] that points to the node of the object.
+/// - Collision shapes of tiles (rectangles, ellipses and polygons), that are defined in the tile
+///   collision editor. The class of a shape defines the name of the collider layer it goes to,
+///   shapes without a class go to the `Collision` layer.
+/// - Object layers, image layers and group layers.
+pub struct TmxLoader {
+    /// Resource manager to request textures of the tile sets.
+    pub resource_manager: ResourceManager,
+}
+
+impl ResourceLoader for TmxLoader {
+    fn extensions(&self) -> &[&str] {
+        &["tmx"]
+    }
+
+    fn data_type_uuid(&self) -> Uuid {
+        <Model as Reflect>::type_info().type_uuid
+    }
+
+    fn load(&self, path: PathBuf, io: Arc<dyn ResourceIo>) -> BoxedLoaderFuture {
+        let resource_manager = self.resource_manager.clone();
+        Box::pin(async move {
+            let context = ImportContext {
+                io,
+                resource_manager,
+            };
+            let model = load(&path, &context).await.map_err(LoadError::new)?;
+            Ok(LoaderPayload::new(model))
+        })
+    }
+}
+
+async fn load(path: &Path, context: &ImportContext) -> Result<Model, LevelImportError> {
+    let text = context.load_string(path).await?;
+
+    // External tile sets are loaded first, so the document is never held across an await point.
+    let sources = external_tile_sets(&text)?;
+    let mut tile_sets = FxHashMap::default();
+    for source in sources {
+        let tile_set_path = resolve_path(path, &source);
+        let tile_set_text = context.load_string(&tile_set_path).await?;
+        tile_sets.insert(source, (tile_set_path, tile_set_text));
+    }
+
+    let mut scene = Scene::new();
+    let root = scene.graph.get_root();
+    if let Some(name) = path.file_name() {
+        scene.graph[root].set_name(name.to_string_lossy());
+    }
+    import_map(&text, path, &tile_sets, context, &mut scene.graph)?;
+    Ok(Model::new(NodeMapping::UseNames, scene))
+}
+
+fn external_tile_sets(text: &str) -> Result<Vec<String>, LevelImportError> {
+    let document = Document::parse(text)?;
+    Ok(document
+        .root_element()
+        .children()
+        .filter(|n| n.has_tag_name("tileset"))
+        .filter_map(|n| n.attribute("source"))
+        .map(|s| s.to_string())
+        .collect())
+}
+
+fn attribute<'a>(node: XmlNode<'a, '_>, name: &str) -> Result<&'a str, LevelImportError> {
+    node.attribute(name)
+        .ok_or_else(|| LevelImportError::MissingAttribute {
+            element: node.tag_name().name().to_string(),
+            attribute: name.to_string(),
+        })
+}
+
+fn parse_value<T: FromStr>(name: &str, value: &str) -> Result<T, LevelImportError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| LevelImportError::InvalidValue {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+}
+
+fn parse_attribute<T: FromStr>(node: XmlNode, name: &str) -> Result<T, LevelImportError> {
+    parse_value(name, attribute(node, name)?)
+}
+
+fn parse_attribute_or<T: FromStr>(
+    node: XmlNode,
+    name: &str,
+    default: T,
+) -> Result<T, LevelImportError> {
+    match node.attribute(name) {
+        Some(value) => parse_value(name, value),
+        None => Ok(default),
+    }
+}
+
+fn child<'a, 'input>(node: XmlNode<'a, 'input>, name: &str) -> Option<XmlNode<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+/// The class of an element, older versions of Tiled store it in `type` attribute.
+fn class<'a>(node: XmlNode<'a, '_>) -> &'a str {
+    node.attribute("class")
+        .or_else(|| node.attribute("type"))
+        .unwrap_or_default()
+}
+
+fn is_visible(node: XmlNode) -> Result<bool, LevelImportError> {
+    Ok(parse_attribute_or::<u8>(node, "visible", 1)? != 0)
+}
+
+/// A custom property of a Tiled element.
+struct TmxProperty<'a> {
+    name: &'a str,
+    kind: &'a str,
+    value: &'a str,
+}
+
+fn properties<'a>(node: XmlNode<'a, '_>) -> Vec<TmxProperty<'a>> {
+    let Some(properties) = child(node, "properties") else {
+        return Vec::new();
+    };
+    properties
+        .children()
+        .filter(|n| n.has_tag_name("property"))
+        .filter_map(|n| {
+            Some(TmxProperty {
+                name: n.attribute("name")?,
+                kind: n.attribute("type").unwrap_or("string"),
+                // Multiline strings are stored as the text of the element.
+                value: n
+                    .attribute("value")
+                    .or_else(|| n.text())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn tile_property_value(property: &TmxProperty) -> Result<TileSetPropertyValue, LevelImportError> {
+    Ok(match property.kind {
+        "int" => TileSetPropertyValue::I32(parse_value(property.name, property.value)?),
+        "float" => TileSetPropertyValue::F32(parse_value(property.name, property.value)?),
+        "bool" => TileSetPropertyValue::I32((property.value == "true") as i32),
+        _ => TileSetPropertyValue::String(property.value.into()),
+    })
+}
+
+/// Converts custom properties of an element to node properties. Properties that refer to other
+/// objects are returned separately as `(property name, object id)` pairs, because they can be
+/// resolved only when all the objects are spawned.
+fn node_properties(
+    node: XmlNode,
+) -> Result<(Vec<Property>, Vec<ObjectReference>), LevelImportError> {
+    let mut result = Vec::new();
+    let mut references = Vec::new();
+    for property in properties(node) {
+        let value = match property.kind {
+            "int" => PropertyValue::I32(parse_value(property.name, property.value)?),
+            "float" => PropertyValue::F32(parse_value(property.name, property.value)?),
+            "bool" => PropertyValue::I32((property.value == "true") as i32),
+            "object" => {
+                if property.value != "0" {
+                    references.push((property.name.to_string(), property.value.to_string()));
+                }
+                continue;
+            }
+            "class" => {
+                Log::warn(format!(
+                    "Property {} has class type, which is not supported. It will be ignored.",
+                    property.name
+                ));
+                continue;
+            }
+            _ => PropertyValue::String(property.value.to_string()),
+        };
+        result.push(Property {
+            name: property.name.to_string(),
+            value,
+        });
+    }
+    Ok((result, references))
+}
+
+/// Converts Tiled flip flags of a tile to a transformation. Tiled applies the diagonal flip first,
+/// then the horizontal flip and then the vertical flip. Diagonal flip in Tiled swaps X and Y axes,
+/// which becomes anti-diagonal flip after the Y axis is flipped.
+fn flags_to_transformation(flags: u32) -> OrthoTransformation {
+    let mut transformation = OrthoTransformation::identity();
+    if flags & FLIPPED_DIAGONALLY != 0 {
+        transformation = transformation.x_flipped().rotated(1);
+    }
+    if flags & FLIPPED_HORIZONTALLY != 0 {
+        transformation = transformation.x_flipped();
+    }
+    if flags & FLIPPED_VERTICALLY != 0 {
+        transformation = transformation.y_flipped();
+    }
+    transformation
+}
+
+/// Calculates how many tiles fit in the given size (width or height) of an atlas image.
+fn atlas_tile_count(
+    image_size: u32,
+    tile_size: u32,
+    margin: u32,
+    spacing: u32,
+) -> Result<u32, LevelImportError> {
+    let available =
+        image_size
+            .checked_sub(margin)
+            .ok_or_else(|| LevelImportError::InvalidValue {
+                name: format!("margin of the tile set (image size is {image_size})"),
+                value: margin.to_string(),
+            })?;
+    Ok(available.saturating_add(spacing) / tile_size.saturating_add(spacing).max(1))
+}
+
+/// A tile set of the map along with its location in the tile set of the imported level.
+struct TmxTileSet {
+    first_gid: u32,
+    placement: TileSetPlacement,
+}
+
+struct MapImporter<'a> {
+    path: &'a Path,
+    context: &'a ImportContext,
+    builder: LevelBuilder,
+    tile_sets: Vec<TmxTileSet>,
+    tile_size: Vector2<f32>,
+    layer_count: usize,
+}
+
+impl MapImporter<'_> {
+    /// Finds the tile with the given global id, ignoring flip flags.
+    fn tile(&self, gid: u32) -> Option<TileDefinitionHandle> {
+        let gid = gid & !FLAGS_MASK;
+        let tile_set = self
+            .tile_sets
+            .iter()
+            .rev()
+            .find(|tile_set| tile_set.first_gid <= gid)?;
+        tile_set.placement.handle(gid - tile_set.first_gid)
+    }
+
+    fn import_tile_set(
+        &mut self,
+        node: XmlNode,
+        path: &Path,
+        first_gid: u32,
+    ) -> Result<(), LevelImportError> {
+        let tile_size = Vector2::new(
+            parse_attribute::<u32>(node, "tilewidth")?,
+            parse_attribute::<u32>(node, "tileheight")?,
+        );
+
+        let placement = match child(node, "image") {
+            Some(image) => {
+                let margin = parse_attribute_or(node, "margin", 0)?;
+                let spacing = parse_attribute_or(node, "spacing", 0)?;
+                let image_size = Vector2::new(
+                    parse_attribute::<u32>(image, "width")?,
+                    parse_attribute::<u32>(image, "height")?,
+                );
+                let columns = match parse_attribute_or(node, "columns", 0)? {
+                    0 => atlas_tile_count(image_size.x, tile_size.x, margin, spacing)?,
+                    columns => columns,
+                };
+                let tile_count = match parse_attribute_or(node, "tilecount", 0)? {
+                    0 => columns.saturating_mul(atlas_tile_count(
+                        image_size.y,
+                        tile_size.y,
+                        margin,
+                        spacing,
+                    )?),
+                    count => count,
+                };
+                let texture_path = resolve_path(path, attribute(image, "source")?);
+                self.builder.add_atlas(&AtlasDescription {
+                    texture: self.context.request_texture(&texture_path),
+                    image_size,
+                    tile_size,
+                    columns,
+                    tile_count,
+                    margin,
+                    spacing,
+                })
+            }
+            None => self.builder.add_collection(),
+        };
+
+        for tile in node.children().filter(|n| n.has_tag_name("tile")) {
+            let id = parse_attribute::<u32>(tile, "id")?;
+            let mut size = tile_size;
+            if let Some(image) = child(tile, "image") {
+                size = Vector2::new(
+                    parse_attribute(image, "width")?,
+                    parse_attribute(image, "height")?,
+                );
+                let texture_path = resolve_path(path, attribute(image, "source")?);
+                let texture = self.context.request_texture(&texture_path);
+                self.builder.add_image_tile(placement, id, texture, size);
+            }
+            let Some(handle) = placement.handle(id) else {
+                continue;
+            };
+
+            if !class(tile).is_empty() {
+                self.builder.set_tile_property(
+                    handle,
+                    "class",
+                    TileSetPropertyValue::String(class(tile).into()),
+                );
+            }
+            for property in properties(tile) {
+                let value = tile_property_value(&property)?;
+                self.builder.set_tile_property(handle, property.name, value);
+            }
+
+            if let Some(shapes) = child(tile, "objectgroup") {
+                for (layer, collider) in tile_colliders(shapes, size.cast::<f32>())? {
+                    self.builder.set_tile_collider(handle, &layer, collider);
+                }
+            }
+
+            if let Some(animation) = child(tile, "animation") {
+                let mut frames = Vec::new();
+                for frame in animation.children().filter(|n| n.has_tag_name("frame")) {
+                    let id = parse_attribute::<u32>(frame, "tileid")?;
+                    let duration = parse_attribute::<u32>(frame, "duration")?;
+                    if let Some(frame) = placement.handle(id) {
+                        frames.push((frame, duration));
+                    }
+                }
+                self.builder.add_animation(&frames);
+            }
+        }
+
+        self.tile_sets.push(TmxTileSet {
+            first_gid,
+            placement,
+        });
+        Ok(())
+    }
+
+    /// Returns the depth of the next layer. Layers that are defined later are drawn on top.
+    fn next_layer_depth(&mut self) -> f32 {
+        let depth = -(self.layer_count as f32) * LAYER_DEPTH_STEP;
+        self.layer_count += 1;
+        depth
+    }
+
+    fn layer_base(&mut self, node: XmlNode, depth: f32) -> Result<BaseBuilder, LevelImportError> {
+        let offset = Vector2::new(
+            parse_attribute_or(node, "offsetx", 0.0)?,
+            parse_attribute_or(node, "offsety", 0.0)?,
+        );
+        let position = pixels_to_units(offset, self.tile_size);
+        Ok(BaseBuilder::new()
+            .with_name(node.attribute("name").unwrap_or_default())
+            .with_visibility(is_visible(node)?)
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(position.x, position.y, depth))
+                    .build(),
+            ))
+    }
+
+    fn import_layers(
+        &mut self,
+        node: XmlNode,
+        parent: Handle<Node>,
+        graph: &mut Graph,
+    ) -> Result<(), LevelImportError> {
+        for layer in node.children().filter(|n| n.is_element()) {
+            let handle = match layer.tag_name().name() {
+                "layer" => {
+                    let depth = self.next_layer_depth();
+                    let base = self.layer_base(layer, depth)?;
+                    let tiles = self.import_tiles(layer)?;
+                    self.builder
+                        .add_tile_map(graph, base, tiles, Vector2::repeat(1.0))
+                }
+                "objectgroup" => {
+                    let depth = self.next_layer_depth();
+                    let handle = PivotBuilder::new(self.layer_base(layer, depth)?)
+                        .build(graph)
+                        .to_base();
+                    for object in layer.children().filter(|n| n.has_tag_name("object")) {
+                        let object = self.import_object(object)?;
+                        self.builder.add_object(graph, handle, object);
+                    }
+                    handle
+                }
+                "imagelayer" => {
+                    let depth = self.next_layer_depth();
+                    let handle = PivotBuilder::new(self.layer_base(layer, depth)?)
+                        .build(graph)
+                        .to_base();
+                    if let Some(image) = self.import_image_layer(layer)? {
+                        self.builder.add_object(graph, handle, image);
+                    }
+                    handle
+                }
+                "group" => {
+                    let handle = PivotBuilder::new(self.layer_base(layer, 0.0)?)
+                        .build(graph)
+                        .to_base();
+                    self.import_layers(layer, handle, graph)?;
+                    handle
+                }
+                _ => continue,
+            };
+            let (properties, _) = node_properties(layer)?;
+            graph[handle].set_properties(properties);
+            graph.link_nodes(handle, parent);
+        }
+        Ok(())
+    }
+
+    fn import_tiles(&mut self, layer: XmlNode) -> Result<Tiles, LevelImportError> {
+        let mut tiles = Tiles::default();
+        let Some(data) = child(layer, "data") else {
+            return Ok(tiles);
+        };
+
+        let mut regions = Vec::new();
+        let chunks = data
+            .children()
+            .filter(|n| n.has_tag_name("chunk"))
+            .collect::<Vec<_>>();
+        if chunks.is_empty() {
+            let size = Vector2::new(
+                parse_attribute(layer, "width")?,
+                parse_attribute(layer, "height")?,
+            );
+            regions.push((Vector2::new(0, 0), size, decode_data(data, data)?));
+        } else {
+            for chunk in chunks {
+                let origin =
+                    Vector2::new(parse_attribute(chunk, "x")?, parse_attribute(chunk, "y")?);
+                let size = Vector2::new(
+                    parse_attribute(chunk, "width")?,
+                    parse_attribute(chunk, "height")?,
+                );
+                regions.push((origin, size, decode_data(data, chunk)?));
+            }
+        }
+
+        for (origin, size, gids) in regions {
+            let size: Vector2<i32> = size;
+            for (i, gid) in gids.into_iter().enumerate() {
+                if gid & !FLAGS_MASK == 0 {
+                    continue;
+                }
+                let Some(handle) = self.tile(gid) else {
+                    Log::warn(format!("Unknown tile {gid} is ignored."));
+                    continue;
+                };
+                let transformation = flags_to_transformation(gid);
+                let Some(handle) = self.builder.transformed_tile(handle, transformation) else {
+                    continue;
+                };
+                let cell =
+                    origin + Vector2::new(i as i32 % size.x.max(1), i as i32 / size.x.max(1));
+                tiles.insert(cell_to_grid(cell), handle);
+            }
+        }
+        Ok(tiles)
+    }
+
+    fn import_object(&mut self, node: XmlNode) -> Result<LevelObject, LevelImportError> {
+        let id = attribute(node, "id")?;
+        let position = Vector2::new(
+            parse_attribute_or(node, "x", 0.0)?,
+            parse_attribute_or(node, "y", 0.0)?,
+        );
+        let size = Vector2::new(
+            parse_attribute_or::<f32>(node, "width", 0.0)?,
+            parse_attribute_or::<f32>(node, "height", 0.0)?,
+        );
+        let size = Vector2::new(size.x / self.tile_size.x, size.y / self.tile_size.y);
+        let rotation = parse_attribute_or::<f32>(node, "rotation", 0.0)?;
+        let (mut properties, references) = node_properties(node)?;
+
+        let mut sprite = None;
+        if let Some(gid) = node.attribute("gid") {
+            let gid = parse_value::<u32>("gid", gid)?;
+            if let Some((material, uv_rect)) = self
+                .tile(gid)
+                .and_then(|handle| self.builder.tile_sprite(handle))
+            {
+                // Tile objects are aligned by their left-bottom corner.
+                sprite = Some(ObjectSprite {
+                    material,
+                    uv_rect,
+                    offset: size.scale(0.5),
+                    size,
+                    flip_x: gid & FLIPPED_HORIZONTALLY != 0,
+                    flip_y: gid & FLIPPED_VERTICALLY != 0,
+                });
+            }
+        } else if size != Vector2::default() {
+            for (name, value) in [("width", size.x), ("height", size.y)] {
+                properties.push(Property {
+                    name: name.to_string(),
+                    value: PropertyValue::F32(value),
+                });
+            }
+        }
+
+        let position = pixels_to_units(position, self.tile_size);
+        Ok(LevelObject {
+            key: id.to_string(),
+            name: match node.attribute("name") {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ => format!("Object {id}"),
+            },
+            tag: class(node).to_string(),
+            position: Vector3::new(position.x, position.y, 0.0),
+            rotation: -rotation.to_radians(),
+            visible: is_visible(node)?,
+            sprite,
+            properties,
+            references,
+        })
+    }
+
+    fn import_image_layer(
+        &mut self,
+        layer: XmlNode,
+    ) -> Result<Option<LevelObject>, LevelImportError> {
+        let Some(image) = child(layer, "image") else {
+            return Ok(None);
+        };
+        let image_size = Vector2::new(
+            parse_attribute::<u32>(image, "width")?,
+            parse_attribute::<u32>(image, "height")?,
+        );
+        let texture = self
+            .context
+            .request_texture(&resolve_path(self.path, attribute(image, "source")?));
+        let (material, uv_rect) = self.builder.image_sprite(
+            &texture,
+            image_size,
+            Rect::new(0, 0, image_size.x, image_size.y),
+        );
+        let size = Vector2::new(
+            image_size.x as f32 / self.tile_size.x,
+            image_size.y as f32 / self.tile_size.y,
+        );
+        Ok(Some(LevelObject {
+            key: String::new(),
+            name: "Image".to_string(),
+            tag: String::new(),
+            position: Vector3::default(),
+            rotation: 0.0,
+            visible: true,
+            // Image layers are aligned by their left-top corner.
+            sprite: Some(ObjectSprite {
+                material,
+                uv_rect,
+                offset: Vector2::new(size.x * 0.5, -size.y * 0.5),
+                size,
+                flip_x: false,
+                flip_y: false,
+            }),
+            properties: Vec::new(),
+            references: Vec::new(),
+        }))
+    }
+}
+
+/// Decodes global tile ids of a layer or a chunk. `data` is the element that defines encoding and
+/// compression, `node` is the element that contains the actual data.
+fn decode_data(data: XmlNode, node: XmlNode) -> Result<Vec<u32>, LevelImportError> {
+    match data.attribute("encoding") {
+        None => node
+            .children()
+            .filter(|n| n.has_tag_name("tile"))
+            .map(|n| parse_attribute_or(n, "gid", 0))
+            .collect(),
+        Some("csv") => node
+            .text()
+            .unwrap_or_default()
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| parse_value("gid", s))
+            .collect(),
+        Some("base64") => {
+            let text = node.text().unwrap_or_default().trim();
+            let bytes = Base64Engine.decode(text)?;
+            let bytes = match data.attribute("compression") {
+                None => bytes,
+                Some("zlib") => {
+                    inflate::inflate_bytes_zlib(&bytes).map_err(LevelImportError::Decompression)?
+                }
+                Some("gzip") => gunzip(&bytes)?,
+                Some(compression) => {
+                    return Err(LevelImportError::Unsupported(format!(
+                        "{compression} compression of tile layer data"
+                    )))
+                }
+            };
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .collect())
+        }
+        Some(encoding) => Err(LevelImportError::Unsupported(format!(
+            "{encoding} encoding of tile layer data"
+        ))),
+    }
+}
+
+/// Decompresses a gzip stream, see RFC 1952 for the format of the header.
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, LevelImportError> {
+    const FHCRC: u8 = 0x02;
+    const FEXTRA: u8 = 0x04;
+    const FNAME: u8 = 0x08;
+    const FCOMMENT: u8 = 0x10;
+
+    let invalid = || LevelImportError::Decompression("Invalid gzip header".to_string());
+
+    if bytes.len() < 18 || bytes[0] != 0x1f || bytes[1] != 0x8b || bytes[2] != 8 {
+        return Err(invalid());
+    }
+    let flags = bytes[3];
+    let mut position = 10;
+    if flags & FEXTRA != 0 {
+        let length = u16::from_le_bytes([bytes[position], bytes[position + 1]]) as usize;
+        position += 2 + length;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            while *bytes.get(position).ok_or_else(invalid)? != 0 {
+                position += 1;
+            }
+            position += 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        position += 2;
+    }
+    // The stream ends with CRC32 and the size of the uncompressed data.
+    let compressed = bytes.get(position..bytes.len() - 8).ok_or_else(invalid)?;
+    inflate::inflate_bytes(compressed).map_err(LevelImportError::Decompression)
+}
+
+/// Converts collision shapes of a tile to tile colliders, grouped by the name of the collider
+/// layer. Coordinates of the shapes are in pixels relative to the left-top corner of the tile.
+fn tile_colliders(
+    shapes: XmlNode,
+    tile_size: Vector2<f32>,
+) -> Result<Vec<(String, TileCollider)>, LevelImportError> {
+    let mut layers = FxHashMap::<String, Vec<Vec<Vector2<f32>>>>::default();
+    for shape in shapes.children().filter(|n| n.has_tag_name("object")) {
+        let origin = Vector2::new(
+            parse_attribute_or::<f32>(shape, "x", 0.0)?,
+            parse_attribute_or::<f32>(shape, "y", 0.0)?,
+        );
+        let size = Vector2::new(
+            parse_attribute_or::<f32>(shape, "width", 0.0)?,
+            parse_attribute_or::<f32>(shape, "height", 0.0)?,
+        );
+        let points = if let Some(polygon) = child(shape, "polygon") {
+            attribute(polygon, "points")?
+                .split_whitespace()
+                .map(|point| {
+                    let (x, y) =
+                        point
+                            .split_once(',')
+                            .ok_or_else(|| LevelImportError::InvalidValue {
+                                name: "points".to_string(),
+                                value: point.to_string(),
+                            })?;
+                    Ok(Vector2::new(parse_value("x", x)?, parse_value("y", y)?))
+                })
+                .collect::<Result<Vec<_>, LevelImportError>>()?
+        } else if child(shape, "ellipse").is_some() {
+            ellipse_polygon(size.scale(0.5), size.scale(0.5))
+        } else if child(shape, "polyline").is_some()
+            || child(shape, "point").is_some()
+            || child(shape, "text").is_some()
+            || size == Vector2::default()
+        {
+            // Shapes without area cannot be used as colliders.
+            continue;
+        } else {
+            vec![
+                Vector2::new(0.0, 0.0),
+                Vector2::new(size.x, 0.0),
+                Vector2::new(size.x, size.y),
+                Vector2::new(0.0, size.y),
+            ]
+        };
+
+        // Rotation is clockwise around the origin of the shape. Since Y axis of the source points
+        // down, the usual rotation matrix rotates clockwise.
+        let (sin, cos) = parse_attribute_or::<f32>(shape, "rotation", 0.0)?
+            .to_radians()
+            .sin_cos();
+        let polygon = points
+            .into_iter()
+            .map(|p| {
+                let p = origin + Vector2::new(p.x * cos - p.y * sin, p.x * sin + p.y * cos);
+                Vector2::new(p.x / tile_size.x, 1.0 - p.y / tile_size.y)
+            })
+            .collect();
+
+        let layer = match class(shape) {
+            "" => DEFAULT_COLLIDER_LAYER,
+            class => class,
+        };
+        layers.entry(layer.to_string()).or_default().push(polygon);
+    }
+
+    Ok(layers
+        .into_iter()
+        .map(|(layer, polygons)| (layer, polygons_to_tile_collider(&polygons)))
+        .collect())
+}
+
+fn import_map(
+    text: &str,
+    path: &Path,
+    external_tile_sets: &FxHashMap<String, (PathBuf, String)>,
+    context: &ImportContext,
+    graph: &mut Graph,
+) -> Result<(), LevelImportError> {
+    let document = Document::parse(text)?;
+    let map = document.root_element();
+
+    let orientation = map.attribute("orientation").unwrap_or("orthogonal");
+    if orientation != "orthogonal" {
+        return Err(LevelImportError::Unsupported(format!(
+            "{orientation} map orientation"
+        )));
+    }
+
+    let mut importer = MapImporter {
+        path,
+        context,
+        builder: LevelBuilder::new(),
+        tile_sets: Vec::new(),
+        tile_size: Vector2::new(
+            parse_attribute(map, "tilewidth")?,
+            parse_attribute(map, "tileheight")?,
+        ),
+        layer_count: 0,
+    };
+
+    for tile_set in map.children().filter(|n| n.has_tag_name("tileset")) {
+        let first_gid = parse_attribute(tile_set, "firstgid")?;
+        match tile_set.attribute("source") {
+            Some(source) => {
+                let (tile_set_path, tile_set_text) = &external_tile_sets[source];
+                let document = Document::parse(tile_set_text)?;
+                importer.import_tile_set(document.root_element(), tile_set_path, first_gid)?;
+            }
+            None => importer.import_tile_set(tile_set, path, first_gid)?,
+        }
+    }
+    importer
+        .tile_sets
+        .sort_by_key(|tile_set| tile_set.first_gid);
+
+    let root = graph.get_root();
+    importer.import_layers(map, root, graph)?;
+    let (properties, _) = node_properties(map)?;
+    graph[root].set_properties(properties);
+
+    importer.builder.finish(graph);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags_to_transformation() {
+        let v = Vector2::new(1, 2);
+        assert_eq!(
+            v.transformed(flags_to_transformation(FLIPPED_HORIZONTALLY)),
+            Vector2::new(-1, 2)
+        );
+        assert_eq!(
+            v.transformed(flags_to_transformation(FLIPPED_VERTICALLY)),
+            Vector2::new(1, -2)
+        );
+        // Diagonal flip swaps the axes in the Y-down space of the source.
+        assert_eq!(
+            v.transformed(flags_to_transformation(FLIPPED_DIAGONALLY)),
+            Vector2::new(-2, -1)
+        );
+        // Diagonal + horizontal flip is a 90 degrees clockwise rotation in the source.
+        assert_eq!(
+            v.transformed(flags_to_transformation(
+                FLIPPED_DIAGONALLY | FLIPPED_HORIZONTALLY
+            )),
+            Vector2::new(2, -1)
+        );
+    }
+
+    #[test]
+    fn test_atlas_tile_count() {
+        assert_eq!(atlas_tile_count(64, 16, 0, 0).unwrap(), 4);
+        // 1 + 16 + 2 + 16 + 2 + 16 + 1 = 54
+        assert_eq!(atlas_tile_count(54, 16, 1, 2).unwrap(), 3);
+        assert_eq!(atlas_tile_count(16, 16, 16, 0).unwrap(), 0);
+        assert_eq!(
+            atlas_tile_count(u32::MAX, u32::MAX, 0, u32::MAX).unwrap(),
+            1
+        );
+        // Margin larger than the image is malformed.
+        assert!(matches!(
+            atlas_tile_count(16, 16, 17, 0),
+            Err(LevelImportError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_decode_data() {
+        let text = r#"<root>
+            <data encoding="csv">1,2,
+            0,3</data>
+            <data encoding="base64">AQAAAAIAAAA=</data>
+            <data encoding="base64" compression="zlib">eJxjZGBgYAJiAAAYAAQ=</data>
+            <data encoding="base64" compression="gzip">H4sIAGkH1WoC/2NkYGBgAmIAfBeBAwgAAAA=</data>
+            <data><tile gid="5"/><tile/></data>
+        </root>"#;
+        let document = Document::parse(text).unwrap();
+        let data = document
+            .root_element()
+            .children()
+            .filter(|n| n.is_element())
+            .map(|n| decode_data(n, n).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(data[0], vec![1, 2, 0, 3]);
+        assert_eq!(data[1], vec![1, 2]);
+        assert_eq!(data[2], vec![1, 2]);
+        assert_eq!(data[3], vec![1, 2]);
+        assert_eq!(data[4], vec![5, 0]);
+    }
+
+    #[test]
+    fn test_tile_colliders() {
+        let text = r#"<objectgroup>
+            <object id="1" x="0" y="0" width="16" height="16"/>
+            <object id="2" type="Water" x="0" y="8">
+                <polygon points="0,0 16,0 16,8"/>
+            </object>
+        </objectgroup>"#;
+        let document = Document::parse(text).unwrap();
+        let mut colliders =
+            tile_colliders(document.root_element(), Vector2::new(16.0, 16.0)).unwrap();
+        colliders.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(colliders[0].0, DEFAULT_COLLIDER_LAYER);
+        assert!(colliders[0].1.is_rectangle());
+        assert_eq!(colliders[1].0, "Water");
+        let TileCollider::Custom(custom) = &colliders[1].1 else {
+            panic!("Custom collider expected");
+        };
+        let custom = custom.data_ref();
+        assert_eq!(custom.triangles.len(), 1);
+        assert_eq!(
+            custom.vertices,
+            vec![
+                Vector2::new(0.0, 0.5),
+                Vector2::new(1.0, 0.5),
+                Vector2::new(1.0, 0.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_map() {
+        use crate::{
+            asset::io::FsResourceIo,
+            core::SafeLock,
+            resource::texture::loader::TextureLoader,
+            scene::{dim2::rectangle::Rectangle, dim2::rigidbody::RigidBody, tilemap::TileMap},
+        };
+
+        let map = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" name="ground" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <image source="ground.png" width="32" height="32"/>
+  <tile id="1">
+   <objectgroup><object id="1" x="0" y="0" width="16" height="16"/></objectgroup>
+  </tile>
+ </tileset>
+ <layer id="1" name="Ground" width="2" height="2">
+  <data encoding="csv">1,2,0,2147483650</data>
+ </layer>
+ <objectgroup id="2" name="Objects">
+  <object id="3" name="Door" x="16" y="32" width="16" height="16"/>
+  <object id="4" name="Button" gid="1" x="0" y="16" width="16" height="16">
+   <properties><property name="target" type="object" value="3"/></properties>
+  </object>
+ </objectgroup>
+</map>"#;
+        let resource_manager =
+            ResourceManager::new(Arc::new(FsResourceIo), Arc::new(Default::default()));
+        resource_manager
+            .state()
+            .resource_registry
+            .safe_lock()
+            .set_path("test_output/level.registry");
+        resource_manager.add_loader(TextureLoader {
+            default_import_options: Default::default(),
+        });
+        let context = ImportContext {
+            io: Arc::new(FsResourceIo),
+            resource_manager,
+        };
+        let mut graph = Graph::new();
+        import_map(
+            map,
+            Path::new("levels/test.tmx"),
+            &Default::default(),
+            &context,
+            &mut graph,
+        )
+        .unwrap();
+
+        let (tile_map, _) = graph.find_by_name_from_root("Ground").unwrap();
+        let tile_map = graph[tile_map].cast::<TileMap>().unwrap();
+        // Three non-empty cells, the last one is flipped horizontally.
+        assert_eq!(tile_map.tiles().unwrap().data_ref().iter().count(), 3);
+        assert!(graph
+            .find_from_root(&mut |n| n.cast::<RigidBody>().is_some())
+            .is_some());
+
+        let (door, _) = graph.find_by_name_from_root("Door").unwrap();
+        let (button, button_ref) = graph.find_by_name_from_root("Button").unwrap();
+        assert!(button_ref
+            .properties
+            .iter()
+            .any(|p| p.name == "target" && p.value == PropertyValue::NodeHandle(door)));
+        assert!(graph[button]
+            .children()
+            .iter()
+            .any(|c| graph[*c].cast::<Rectangle>().is_some()));
+    }
+}
@@ -25,5 +25,9 @@
 pub mod curve;
 pub mod fbx;
 pub mod gltf;
+pub mod level;
 pub mod model;
+pub mod obj;
+pub mod ply;
+pub mod stl;
 pub mod texture;
@@ -53,7 +53,7 @@ pub struct ModelLoader {
 
 impl ResourceLoader for ModelLoader {
     fn extensions(&self) -> &[&str] {
-        &["rgs", "fbx"]
+        &["rgs", "fbx", "obj", "ply", "stl"]
     }
 
     fn is_native_extension(&self, ext: &str) -> bool {
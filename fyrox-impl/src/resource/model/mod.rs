@@ -36,7 +36,8 @@
 //! # Supported formats
 //!
 //! Currently only FBX (common format in game industry for storing complex 3d models),
-//! RGS (native Fyroxed format), GLTF formats are supported.
+//! RGS (native Fyroxed format), GLTF, OBJ (with MTL materials), PLY (with vertex colors)
+//! and STL formats are supported.
 
 use crate::{
     asset::{
@@ -59,7 +60,12 @@ use crate::{
     engine::SerializationContext,
     generic_animation::AnimationContainer,
     graph::{NodeHandleMap, NodeMapping, NodeWrapper, PrefabData, SceneGraph},
-    resource::fbx::{self, error::FbxError},
+    resource::{
+        fbx::{self, error::FbxError},
+        obj::{self, ObjError},
+        ply::{self, PlyError},
+        stl::{self, StlError},
+    },
     scene::{
         animation::Animation, base::SceneNodeId, graph::Graph, node::Node, transform::Transform,
         Scene, SceneLoader,
@@ -707,6 +713,46 @@ impl MaterialSearchOptions {
     pub fn materials_directory<P: AsRef<Path>>(path: P) -> Self {
         Self::MaterialsDirectory(path.as_ref().to_path_buf())
     }
+
+    /// Tries to resolve a path to an external resource (usually a texture), that is stored in a
+    /// model file located at `model_path`. Returns [`None`] if nothing was found.
+    pub async fn find_file(
+        &self,
+        path: &Path,
+        model_path: &Path,
+        io: &dyn ResourceIo,
+    ) -> Option<PathBuf> {
+        let filename = path.file_name()?;
+        match self {
+            MaterialSearchOptions::MaterialsDirectory(ref directory) => {
+                Some(directory.join(filename))
+            }
+            MaterialSearchOptions::RecursiveUp => {
+                let mut path = model_path.to_owned();
+                while let Some(parent) = path.parent() {
+                    let candidate = parent.join(filename);
+                    if io.exists(&candidate).await {
+                        return Some(candidate);
+                    }
+                    path.pop();
+                }
+                None
+            }
+            MaterialSearchOptions::WorkingDirectory => {
+                let iter = io.walk_directory(Path::new("."), usize::MAX).await.ok()?;
+                for dir in iter {
+                    if io.is_dir(&dir).await {
+                        let candidate = dir.join(filename);
+                        if io.exists(&candidate).await {
+                            return Some(candidate);
+                        }
+                    }
+                }
+                None
+            }
+            MaterialSearchOptions::UsePathDirectly => Some(path.to_path_buf()),
+        }
+    }
 }
 
 /// A set of options that will be applied to a model resource when loading it from external source.
@@ -744,6 +790,12 @@ pub enum ModelLoadError {
     NotSupported(String),
     /// An error occurred while loading FBX file.
     Fbx(FbxError),
+    /// An error occurred while loading OBJ file.
+    Obj(ObjError),
+    /// An error occurred while loading PLY file.
+    Ply(PlyError),
+    /// An error occurred while loading STL file.
+    Stl(StlError),
 }
 
 impl Display for ModelLoadError {
@@ -756,6 +808,9 @@ impl Display for ModelLoadError {
                 write!(f, "Model format is not supported: {v}")
             }
             ModelLoadError::Fbx(v) => v.fmt(f),
+            ModelLoadError::Obj(v) => v.fmt(f),
+            ModelLoadError::Ply(v) => v.fmt(f),
+            ModelLoadError::Stl(v) => v.fmt(f),
         }
     }
 }
@@ -766,6 +821,24 @@ impl From<FbxError> for ModelLoadError {
     }
 }
 
+impl From<ObjError> for ModelLoadError {
+    fn from(obj: ObjError) -> Self {
+        ModelLoadError::Obj(obj)
+    }
+}
+
+impl From<PlyError> for ModelLoadError {
+    fn from(ply: PlyError) -> Self {
+        ModelLoadError::Ply(ply)
+    }
+}
+
+impl From<StlError> for ModelLoadError {
+    fn from(stl: StlError) -> Self {
+        ModelLoadError::Stl(stl)
+    }
+}
+
 impl From<VisitError> for ModelLoadError {
     fn from(e: VisitError) -> Self {
         ModelLoadError::Visit(e)
@@ -794,13 +867,17 @@ impl Model {
             .to_string_lossy()
             .as_ref()
             .to_lowercase();
+        let foreign_scene = || {
+            let mut scene = Scene::new();
+            if let Some(filename) = path.as_ref().file_name() {
+                let root = scene.graph.get_root();
+                scene.graph[root].set_name(filename.to_string_lossy());
+            }
+            scene
+        };
         let (scene, mapping) = match extension.as_ref() {
             "fbx" => {
-                let mut scene = Scene::new();
-                if let Some(filename) = path.as_ref().file_name() {
-                    let root = scene.graph.get_root();
-                    scene.graph[root].set_name(filename.to_string_lossy());
-                }
+                let mut scene = foreign_scene();
                 fbx::load_to_scene(
                     &mut scene,
                     resource_manager,
@@ -813,6 +890,36 @@ impl Model {
                 // any persistent unique ids, and we have to use names.
                 (scene, NodeMapping::UseNames)
             }
+            // OBJ, PLY and STL do not have persistent unique ids either.
+            "obj" => {
+                let mut scene = foreign_scene();
+                obj::load_to_scene(
+                    &mut scene,
+                    resource_manager,
+                    io,
+                    path.as_ref(),
+                    &model_import_options,
+                )
+                .await?;
+                (scene, NodeMapping::UseNames)
+            }
+            "ply" => {
+                let mut scene = foreign_scene();
+                ply::load_to_scene(
+                    &mut scene,
+                    resource_manager,
+                    io,
+                    path.as_ref(),
+                    &model_import_options,
+                )
+                .await?;
+                (scene, NodeMapping::UseNames)
+            }
+            "stl" => {
+                let mut scene = foreign_scene();
+                stl::load_to_scene(&mut scene, io, path.as_ref()).await?;
+                (scene, NodeMapping::UseNames)
+            }
             // Scene can be used directly as model resource. Such scenes can be created in
             // Fyroxed.
             "rgs" => (
@@ -0,0 +1,651 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Contains all methods to load and convert Wavefront OBJ model format, including materials from
+//! MTL material libraries.
+//!
+//! Every object (`o`) or group (`g`) of an OBJ file is converted into a separate mesh node, every
+//! material (`usemtl`) of an object is converted into a separate surface. Polygons are triangulated
+//! as triangle fans, missing normals are calculated.
+//!
+//! Normally you should never use methods from this module directly, use resource manager to load
+//! models and create their instances.
+
+use crate::{
+    asset::{io::ResourceIo, manager::ResourceManager, untyped::ResourceKind},
+    core::{
+        algebra::{Vector2, Vector3, Vector4},
+        color::Color,
+        io::FileError,
+        log::Log,
+        uuid::Uuid,
+    },
+    fxhash::FxHashMap,
+    graph::SceneGraph,
+    material::{
+        shader::{ShaderResource, ShaderResourceExtension},
+        Material, MaterialResource, MaterialResourceBinding, MaterialTextureBinding,
+    },
+    resource::{model::ModelImportOptions, texture::Texture},
+    scene::{
+        base::BaseBuilder,
+        mesh::{
+            surface::{Surface, SurfaceData, SurfaceResource},
+            vertex::StaticVertex,
+            MeshBuilder,
+        },
+        Scene,
+    },
+    utils::raw_mesh::RawMeshBuilder,
+};
+use std::{
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+/// All possible errors that may occur during OBJ or MTL loading.
+#[derive(Debug)]
+pub enum ObjError {
+    /// An error occurred during file loading.
+    FileLoadError(FileError),
+    /// A line of a file has invalid content.
+    Syntax {
+        /// Number of the line (starting from one).
+        line: usize,
+        /// Description of the error.
+        message: String,
+    },
+}
+
+impl std::error::Error for ObjError {}
+
+impl Display for ObjError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjError::FileLoadError(v) => {
+                write!(f, "OBJ: File load error {v:?}.")
+            }
+            ObjError::Syntax { line, message } => {
+                write!(f, "OBJ: Syntax error at line {line}: {message}")
+            }
+        }
+    }
+}
+
+impl From<FileError> for ObjError {
+    fn from(err: FileError) -> Self {
+        ObjError::FileLoadError(err)
+    }
+}
+
+fn syntax_error(line: usize, message: impl Into<String>) -> ObjError {
+    ObjError::Syntax {
+        line,
+        message: message.into(),
+    }
+}
+
+fn parse_floats<const N: usize>(
+    line: usize,
+    tokens: &mut std::str::SplitWhitespace,
+) -> Result<[f32; N], ObjError> {
+    let mut values = [0.0; N];
+    for value in values.iter_mut() {
+        let token = tokens
+            .next()
+            .ok_or_else(|| syntax_error(line, format!("expected {N} numbers")))?;
+        *value = token
+            .parse()
+            .map_err(|_| syntax_error(line, format!("{token} is not a number")))?;
+    }
+    Ok(values)
+}
+
+/// Removes comments and surrounding whitespace from a line.
+fn strip_line(line: &str) -> &str {
+    match line.find('#') {
+        Some(position) => &line[..position],
+        None => line,
+    }
+    .trim()
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct ObjVertex {
+    position: usize,
+    tex_coord: Option<usize>,
+    normal: Option<usize>,
+}
+
+#[derive(Default, Debug)]
+struct ObjGroup {
+    material: Option<String>,
+    triangles: Vec<[ObjVertex; 3]>,
+}
+
+#[derive(Debug)]
+struct ObjObject {
+    name: String,
+    groups: Vec<ObjGroup>,
+}
+
+impl ObjObject {
+    fn new(name: String, material: Option<String>) -> Self {
+        Self {
+            name,
+            groups: vec![ObjGroup {
+                material,
+                triangles: Default::default(),
+            }],
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.groups.iter().all(|group| group.triangles.is_empty())
+    }
+
+    fn current_group(&mut self) -> &mut ObjGroup {
+        self.groups.last_mut().unwrap()
+    }
+}
+
+#[derive(Debug)]
+struct ObjData {
+    positions: Vec<Vector3<f32>>,
+    tex_coords: Vec<Vector2<f32>>,
+    normals: Vec<Vector3<f32>>,
+    objects: Vec<ObjObject>,
+    material_libraries: Vec<String>,
+}
+
+/// Converts an OBJ index (1-based, or negative relative to the end) into a 0-based index.
+fn resolve_index(line: usize, token: &str, count: usize) -> Result<usize, ObjError> {
+    let index = token
+        .parse::<i64>()
+        .map_err(|_| syntax_error(line, format!("{token} is not a valid index")))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        Err(syntax_error(
+            line,
+            format!("index {index} is out of bounds"),
+        ))
+    } else {
+        Ok(resolved as usize)
+    }
+}
+
+impl ObjData {
+    fn parse(text: &str, default_name: &str) -> Result<Self, ObjError> {
+        let mut data = ObjData {
+            positions: Default::default(),
+            tex_coords: Default::default(),
+            normals: Default::default(),
+            objects: vec![ObjObject::new(default_name.to_string(), None)],
+            material_libraries: Default::default(),
+        };
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line_number = line_index + 1;
+            let mut tokens = strip_line(line).split_whitespace();
+            let Some(keyword) = tokens.next() else {
+                continue;
+            };
+
+            match keyword {
+                "v" => {
+                    let [x, y, z] = parse_floats(line_number, &mut tokens)?;
+                    data.positions.push(Vector3::new(x, y, z));
+                }
+                "vt" => {
+                    let u = parse_floats::<1>(line_number, &mut tokens)?[0];
+                    let v = tokens.next().and_then(|v| v.parse().ok()).unwrap_or(0.0);
+                    // Invert Y because OpenGL has origin at left *bottom* corner.
+                    data.tex_coords.push(Vector2::new(u, 1.0 - v));
+                }
+                "vn" => {
+                    let [x, y, z] = parse_floats(line_number, &mut tokens)?;
+                    data.normals.push(Vector3::new(x, y, z));
+                }
+                "f" => {
+                    let polygon = tokens
+                        .map(|token| data.parse_face_vertex(line_number, token))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if polygon.len() < 3 {
+                        return Err(syntax_error(
+                            line_number,
+                            "a face must have at least three vertices",
+                        ));
+                    }
+                    let group = data.objects.last_mut().unwrap().current_group();
+                    for i in 1..polygon.len() - 1 {
+                        group
+                            .triangles
+                            .push([polygon[0], polygon[i], polygon[i + 1]]);
+                    }
+                }
+                "o" | "g" => {
+                    let name = tokens.collect::<Vec<_>>().join(" ");
+                    let current = data.objects.last_mut().unwrap();
+                    let material = current.current_group().material.clone();
+                    if current.is_empty() {
+                        if !name.is_empty() {
+                            current.name = name;
+                        }
+                    } else {
+                        let name = if name.is_empty() {
+                            format!("{default_name}{}", data.objects.len())
+                        } else {
+                            name
+                        };
+                        data.objects.push(ObjObject::new(name, material));
+                    }
+                }
+                "usemtl" => {
+                    let material = Some(tokens.collect::<Vec<_>>().join(" "));
+                    let object = data.objects.last_mut().unwrap();
+                    let group = object.current_group();
+                    if group.triangles.is_empty() {
+                        group.material = material;
+                    } else {
+                        object.groups.push(ObjGroup {
+                            material,
+                            triangles: Default::default(),
+                        });
+                    }
+                }
+                "mtllib" => {
+                    data.material_libraries
+                        .extend(tokens.map(|token| token.to_string()));
+                }
+                // Smoothing groups, lines, points, free-form geometry, etc. are not supported.
+                _ => (),
+            }
+        }
+
+        data.objects.retain(|object| !object.is_empty());
+        for object in data.objects.iter_mut() {
+            object.groups.retain(|group| !group.triangles.is_empty());
+        }
+
+        Ok(data)
+    }
+
+    fn parse_face_vertex(&self, line: usize, token: &str) -> Result<ObjVertex, ObjError> {
+        let mut indices = token.split('/');
+        let position = resolve_index(line, indices.next().unwrap_or(""), self.positions.len())?;
+        let tex_coord = match indices.next() {
+            Some(index) if !index.is_empty() => {
+                Some(resolve_index(line, index, self.tex_coords.len())?)
+            }
+            _ => None,
+        };
+        let normal = match indices.next() {
+            Some(index) if !index.is_empty() => {
+                Some(resolve_index(line, index, self.normals.len())?)
+            }
+            _ => None,
+        };
+        Ok(ObjVertex {
+            position,
+            tex_coord,
+            normal,
+        })
+    }
+
+    fn make_surface_data(&self, group: &ObjGroup) -> SurfaceData {
+        let mut has_normals = true;
+        let mut builder = RawMeshBuilder::<StaticVertex>::new(
+            group.triangles.len() * 3,
+            group.triangles.len() * 3,
+        );
+        for vertex in group.triangles.iter().flatten() {
+            has_normals &= vertex.normal.is_some();
+            builder.insert(StaticVertex {
+                position: self.positions[vertex.position],
+                tex_coord: vertex
+                    .tex_coord
+                    .map(|i| self.tex_coords[i])
+                    .unwrap_or_default(),
+                normal: vertex.normal.map(|i| self.normals[i]).unwrap_or_default(),
+                tangent: Vector4::default(),
+            });
+        }
+        let mut data = SurfaceData::from_raw_mesh(builder.build());
+        if !has_normals {
+            data.calculate_smooth_normals().unwrap();
+        }
+        data.calculate_tangents().unwrap();
+        data
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct MtlMaterial {
+    name: String,
+    diffuse_color: Vector3<f32>,
+    opacity: f32,
+    /// Pairs of (material property name, texture path).
+    textures: Vec<(&'static str, String)>,
+}
+
+impl MtlMaterial {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            diffuse_color: Vector3::repeat(1.0),
+            opacity: 1.0,
+            textures: Default::default(),
+        }
+    }
+}
+
+fn parse_mtl(text: &str) -> Result<Vec<MtlMaterial>, ObjError> {
+    let mut materials = Vec::<MtlMaterial>::new();
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = strip_line(line);
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+
+        if keyword == "newmtl" {
+            materials.push(MtlMaterial::new(tokens.collect::<Vec<_>>().join(" ")));
+            continue;
+        }
+
+        let Some(material) = materials.last_mut() else {
+            continue;
+        };
+
+        let property_name = match keyword {
+            "Kd" => {
+                let [r, g, b] = parse_floats(line_number, &mut tokens)?;
+                material.diffuse_color = Vector3::new(r, g, b);
+                None
+            }
+            "d" => {
+                material.opacity = parse_floats::<1>(line_number, &mut tokens)?[0];
+                None
+            }
+            "Tr" => {
+                material.opacity = 1.0 - parse_floats::<1>(line_number, &mut tokens)?[0];
+                None
+            }
+            "map_Kd" => Some("diffuseTexture"),
+            "map_Bump" | "map_bump" | "bump" | "norm" => Some("normalTexture"),
+            "map_Ke" => Some("emissionTexture"),
+            "map_Ka" => Some("aoTexture"),
+            "map_Pr" => Some("roughnessTexture"),
+            "map_Pm" => Some("metallicTexture"),
+            "disp" => Some("heightTexture"),
+            _ => None,
+        };
+
+        // Texture options (such as `-bm 1.0`) go before the file name, so the file name is the
+        // last token.
+        if let Some(property_name) = property_name {
+            if let Some(file_name) = tokens.last() {
+                material
+                    .textures
+                    .push((property_name, file_name.replace('\\', "/")));
+            }
+        }
+    }
+
+    Ok(materials)
+}
+
+/// Tries to find a file referenced by OBJ or MTL file. At first, the path is checked relative to
+/// the directory of the referencing file and then the material search options are used.
+pub(crate) async fn resolve_path(
+    path: &str,
+    directory: &Path,
+    model_path: &Path,
+    io: &dyn ResourceIo,
+    options: &ModelImportOptions,
+) -> Option<PathBuf> {
+    let candidate = directory.join(path);
+    if io.exists(&candidate).await {
+        Some(candidate)
+    } else {
+        options
+            .material_search_options
+            .find_file(Path::new(path), model_path, io)
+            .await
+    }
+}
+
+async fn load_materials(
+    data: &ObjData,
+    resource_manager: &ResourceManager,
+    io: &dyn ResourceIo,
+    model_path: &Path,
+    options: &ModelImportOptions,
+) -> FxHashMap<String, MaterialResource> {
+    let directory = model_path.parent().unwrap_or(Path::new(""));
+
+    let mut materials = FxHashMap::default();
+    for library in data.material_libraries.iter() {
+        let Some(library_path) = resolve_path(library, directory, model_path, io, options).await
+        else {
+            Log::warn(format!(
+                "Unable to find a material library {library} for 3D model {model_path:?} \
+                using {options:?} option!"
+            ));
+            continue;
+        };
+
+        let mtl_materials = match io.load_file(&library_path).await {
+            Ok(bytes) => parse_mtl(&String::from_utf8_lossy(&bytes)),
+            Err(err) => Err(err.into()),
+        };
+        let mtl_materials = match mtl_materials {
+            Ok(mtl_materials) => mtl_materials,
+            Err(err) => {
+                Log::err(format!(
+                    "Unable to load a material library {library_path:?}. Reason: {err}"
+                ));
+                continue;
+            }
+        };
+
+        let library_directory = library_path.parent().unwrap_or(Path::new(""));
+        for mtl_material in mtl_materials {
+            let mut material = Material::from_shader(ShaderResource::standard());
+
+            material.set_property(
+                "diffuseColor",
+                Color::from(Vector4::new(
+                    mtl_material.diffuse_color.x,
+                    mtl_material.diffuse_color.y,
+                    mtl_material.diffuse_color.z,
+                    mtl_material.opacity,
+                )),
+            );
+
+            for (property_name, texture_path) in mtl_material.textures.iter() {
+                if let Some(path) =
+                    resolve_path(texture_path, library_directory, model_path, io, options).await
+                {
+                    material.bind(
+                        *property_name,
+                        MaterialResourceBinding::Texture(MaterialTextureBinding {
+                            value: Some(resource_manager.request::<Texture>(path)),
+                        }),
+                    );
+                } else {
+                    Log::warn(format!(
+                        "Unable to find a texture {texture_path} for 3D model {model_path:?} \
+                        using {options:?} option!"
+                    ));
+                }
+            }
+
+            materials.insert(mtl_material.name, MaterialResource::new_embedded(material));
+        }
+    }
+    materials
+}
+
+/// Tries to load and convert OBJ from given path.
+///
+/// Normally you should never use this method, use resource manager to load models.
+pub async fn load_to_scene<P: AsRef<Path>>(
+    scene: &mut Scene,
+    resource_manager: ResourceManager,
+    io: &dyn ResourceIo,
+    path: P,
+    model_import_options: &ModelImportOptions,
+) -> Result<(), ObjError> {
+    let path = path.as_ref();
+
+    let bytes = io.load_file(path).await?;
+    let default_name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let data = ObjData::parse(&String::from_utf8_lossy(&bytes), &default_name)?;
+
+    let materials = load_materials(&data, &resource_manager, io, path, model_import_options).await;
+
+    let root = scene.graph.get_root();
+    for object in data.objects.iter() {
+        let surfaces = object
+            .groups
+            .iter()
+            .map(|group| {
+                let mut surface = Surface::new(SurfaceResource::new_ok(
+                    Uuid::new_v4(),
+                    ResourceKind::External,
+                    data.make_surface_data(group),
+                ));
+                if let Some(name) = group.material.as_ref() {
+                    if let Some(material) = materials.get(name) {
+                        surface.set_material(material.clone());
+                    } else {
+                        Log::warn(format!("OBJ: Material {name} is not defined in {path:?}."));
+                    }
+                }
+                surface
+            })
+            .collect();
+
+        let mesh = MeshBuilder::new(BaseBuilder::new().with_name(object.name.as_str()))
+            .with_surfaces(surfaces)
+            .build(&mut scene.graph);
+        scene.graph.link_nodes(mesh, root);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_obj() {
+        let text = r#"
+            # A quad and a triangle.
+            mtllib quad.mtl
+            v 0 0 0
+            v 1 0 0
+            v 1 1 0
+            v 0 1 0
+            vt 0 0
+            vt 1 1
+            vn 0 0 1
+            o Quad
+            usemtl Red
+            f 1/1/1 2/2/1 3/2/1 4/1/1
+            usemtl Blue
+            f -4//-1 -3//-1 -2//-1
+            g Triangle
+            f 1 2 3
+        "#;
+        let data = ObjData::parse(text, "Model").unwrap();
+        assert_eq!(data.positions.len(), 4);
+        assert_eq!(data.material_libraries, vec!["quad.mtl".to_string()]);
+        assert_eq!(data.objects.len(), 2);
+
+        let quad = &data.objects[0];
+        assert_eq!(quad.name, "Quad");
+        assert_eq!(quad.groups.len(), 2);
+        assert_eq!(quad.groups[0].material.as_deref(), Some("Red"));
+        assert_eq!(quad.groups[0].triangles.len(), 2);
+        assert_eq!(
+            quad.groups[0].triangles[1][2],
+            ObjVertex {
+                position: 3,
+                tex_coord: Some(0),
+                normal: Some(0)
+            }
+        );
+        assert_eq!(quad.groups[1].material.as_deref(), Some("Blue"));
+        assert_eq!(quad.groups[1].triangles[0][0].position, 0);
+
+        // Material is inherited by the next group.
+        let triangle = &data.objects[1];
+        assert_eq!(triangle.name, "Triangle");
+        assert_eq!(triangle.groups[0].material.as_deref(), Some("Blue"));
+
+        let surface = data.make_surface_data(&triangle.groups[0]);
+        assert_eq!(surface.vertex_buffer.vertex_count(), 3);
+        assert_eq!(surface.geometry_buffer.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_obj_invalid_index() {
+        assert!(matches!(
+            ObjData::parse("v 0 0 0\nf 1 2 3", "Model"),
+            Err(ObjError::Syntax { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_mtl() {
+        let text = r#"
+            newmtl Red
+            Kd 1.0 0.0 0.0
+            d 0.5
+            map_Kd -bm 1.0 textures\red.png
+            norm red_normal.png
+        "#;
+        let materials = parse_mtl(text).unwrap();
+        assert_eq!(
+            materials,
+            vec![MtlMaterial {
+                name: "Red".to_string(),
+                diffuse_color: Vector3::new(1.0, 0.0, 0.0),
+                opacity: 0.5,
+                textures: vec![
+                    ("diffuseTexture", "textures/red.png".to_string()),
+                    ("normalTexture", "red_normal.png".to_string())
+                ],
+            }]
+        );
+    }
+}
@@ -0,0 +1,794 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Contains all methods to load and convert PLY (Polygon File Format) models, both ASCII and binary
+//! (little and big endian) variants.
+//!
+//! A PLY file is converted into a single mesh node with a single surface. Vertex positions, normals,
+//! texture coordinates and colors are imported, polygons are triangulated as triangle fans. Vertex
+//! colors are stored in [`VertexAttributeUsage::Color`] attribute of the vertex buffer, missing
+//! normals are calculated. A texture, that is referenced by `comment TextureFile <name>` line of the
+//! header (as MeshLab and many photogrammetry tools do), is used as the diffuse texture of the surface.
+//!
+//! Normally you should never use methods from this module directly, use resource manager to load
+//! models and create their instances.
+
+use crate::{
+    asset::{io::ResourceIo, manager::ResourceManager, untyped::ResourceKind},
+    core::{
+        algebra::{Vector2, Vector3, Vector4},
+        io::FileError,
+        log::Log,
+        math::TriangleDefinition,
+        uuid::Uuid,
+    },
+    graph::SceneGraph,
+    material::{
+        shader::{ShaderResource, ShaderResourceExtension},
+        Material, MaterialResource, MaterialResourceBinding, MaterialTextureBinding,
+    },
+    resource::{model::ModelImportOptions, obj, texture::Texture},
+    scene::{
+        base::BaseBuilder,
+        mesh::{
+            buffer::{
+                TriangleBuffer, ValidationError, VertexAttributeDataType,
+                VertexAttributeDescriptor, VertexAttributeUsage, VertexBuffer, VertexFetchError,
+                VertexWriteTrait,
+            },
+            surface::{Surface, SurfaceData, SurfaceResource},
+            vertex::StaticVertex,
+            MeshBuilder,
+        },
+        Scene,
+    },
+};
+use std::{
+    fmt::{Display, Formatter},
+    path::Path,
+};
+
+/// Shader location of the vertex color attribute. It goes right after the attributes of
+/// [`StaticVertex`].
+pub const COLOR_SHADER_LOCATION: u8 = 4;
+
+/// All possible errors that may occur during PLY loading.
+#[derive(Debug)]
+pub enum PlyError {
+    /// An error occurred during file loading.
+    FileLoadError(FileError),
+    /// The header of the file is malformed.
+    InvalidHeader(String),
+    /// The body of the file ended before all the elements declared in the header were read.
+    UnexpectedEndOfFile,
+    /// A value in the body of the file is malformed.
+    InvalidValue(String),
+    /// The file has no `vertex` element or the element has no positions.
+    MissingPositions,
+    /// A face references a non-existent vertex.
+    InvalidIndex(i64),
+    /// Unable to create a vertex buffer.
+    Validation(ValidationError),
+    /// Unable to calculate normals or tangents of the mesh.
+    VertexFetch(VertexFetchError),
+}
+
+impl std::error::Error for PlyError {}
+
+impl Display for PlyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlyError::FileLoadError(v) => {
+                write!(f, "PLY: File load error {v:?}.")
+            }
+            PlyError::InvalidHeader(v) => {
+                write!(f, "PLY: Invalid header: {v}")
+            }
+            PlyError::UnexpectedEndOfFile => {
+                write!(f, "PLY: Unexpected end of file.")
+            }
+            PlyError::InvalidValue(v) => {
+                write!(f, "PLY: Invalid value {v}.")
+            }
+            PlyError::MissingPositions => {
+                write!(f, "PLY: The file has no vertex positions.")
+            }
+            PlyError::InvalidIndex(v) => {
+                write!(f, "PLY: A face references a non-existent vertex {v}.")
+            }
+            PlyError::Validation(v) => {
+                write!(f, "PLY: Unable to create a vertex buffer: {v:?}.")
+            }
+            PlyError::VertexFetch(v) => {
+                write!(f, "PLY: Unable to calculate normals or tangents: {v}.")
+            }
+        }
+    }
+}
+
+impl From<FileError> for PlyError {
+    fn from(err: FileError) -> Self {
+        PlyError::FileLoadError(err)
+    }
+}
+
+impl From<ValidationError> for PlyError {
+    fn from(err: ValidationError) -> Self {
+        PlyError::Validation(err)
+    }
+}
+
+impl From<VertexFetchError> for PlyError {
+    fn from(err: VertexFetchError) -> Self {
+        PlyError::VertexFetch(err)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum PlyScalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl PlyScalar {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "char" | "int8" => Some(Self::I8),
+            "uchar" | "uint8" => Some(Self::U8),
+            "short" | "int16" => Some(Self::I16),
+            "ushort" | "uint16" => Some(Self::U16),
+            "int" | "int32" => Some(Self::I32),
+            "uint" | "uint32" => Some(Self::U32),
+            "float" | "float32" => Some(Self::F32),
+            "double" | "float64" => Some(Self::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum PlyPropertyKind {
+    Scalar(PlyScalar),
+    List { count: PlyScalar, item: PlyScalar },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct PlyProperty {
+    name: String,
+    kind: PlyPropertyKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+#[derive(Debug, PartialEq)]
+struct PlyHeader {
+    format: PlyFormat,
+    elements: Vec<PlyElement>,
+    texture_file: Option<String>,
+}
+
+/// Parses the header and returns it together with the offset of the body.
+fn parse_header(bytes: &[u8]) -> Result<(PlyHeader, usize), PlyError> {
+    const END_HEADER: &[u8] = b"end_header";
+
+    let end = bytes
+        .windows(END_HEADER.len())
+        .position(|window| window == END_HEADER)
+        .ok_or_else(|| PlyError::InvalidHeader("no end_header".to_string()))?;
+    let body_offset = bytes[end..]
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .map_or(bytes.len(), |position| end + position + 1);
+
+    let text = String::from_utf8_lossy(&bytes[..end]);
+    let mut lines = text.lines().map(str::trim);
+    if lines.next() != Some("ply") {
+        return Err(PlyError::InvalidHeader("no ply magic".to_string()));
+    }
+
+    let mut format = None;
+    let mut elements = Vec::<PlyElement>::new();
+    let mut texture_file = None;
+    for line in lines {
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        match tokens.as_slice() {
+            ["format", name, ..] => {
+                format = Some(match *name {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    _ => return Err(PlyError::InvalidHeader(format!("unknown format {name}"))),
+                })
+            }
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| PlyError::InvalidHeader(format!("invalid count {count}")))?,
+                properties: Default::default(),
+            }),
+            ["property", rest @ ..] => {
+                let element = elements.last_mut().ok_or_else(|| {
+                    PlyError::InvalidHeader("property without element".to_string())
+                })?;
+                let scalar = |name: &str| {
+                    PlyScalar::from_name(name)
+                        .ok_or_else(|| PlyError::InvalidHeader(format!("unknown type {name}")))
+                };
+                let (kind, name) = match rest {
+                    ["list", count, item, name] => (
+                        PlyPropertyKind::List {
+                            count: scalar(count)?,
+                            item: scalar(item)?,
+                        },
+                        name,
+                    ),
+                    [ty, name] => (PlyPropertyKind::Scalar(scalar(ty)?), name),
+                    _ => return Err(PlyError::InvalidHeader(format!("invalid line {line}"))),
+                };
+                element.properties.push(PlyProperty {
+                    name: name.to_string(),
+                    kind,
+                });
+            }
+            ["comment", "TextureFile", name @ ..] if !name.is_empty() => {
+                texture_file = Some(name.join(" "));
+            }
+            _ => (),
+        }
+    }
+
+    let format = format.ok_or_else(|| PlyError::InvalidHeader("no format".to_string()))?;
+
+    Ok((
+        PlyHeader {
+            format,
+            elements,
+            texture_file,
+        },
+        body_offset,
+    ))
+}
+
+/// Reads values from the body of a file in any of the supported formats.
+enum ValueReader<'a> {
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary { data: &'a [u8], big_endian: bool },
+}
+
+impl ValueReader<'_> {
+    fn read(&mut self, scalar: PlyScalar) -> Result<f64, PlyError> {
+        match self {
+            ValueReader::Ascii(tokens) => {
+                let token = tokens.next().ok_or(PlyError::UnexpectedEndOfFile)?;
+                token
+                    .parse()
+                    .map_err(|_| PlyError::InvalidValue(token.to_string()))
+            }
+            ValueReader::Binary { data, big_endian } => {
+                let size = scalar.size();
+                if data.len() < size {
+                    return Err(PlyError::UnexpectedEndOfFile);
+                }
+                let (value, rest) = data.split_at(size);
+                *data = rest;
+
+                let mut bytes = [0; 8];
+                bytes[..size].copy_from_slice(value);
+                if *big_endian {
+                    bytes[..size].reverse();
+                }
+
+                Ok(match scalar {
+                    PlyScalar::I8 => bytes[0] as i8 as f64,
+                    PlyScalar::U8 => bytes[0] as f64,
+                    PlyScalar::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    PlyScalar::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    PlyScalar::I32 => {
+                        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    PlyScalar::U32 => {
+                        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    PlyScalar::F32 => {
+                        f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    PlyScalar::F64 => f64::from_le_bytes(bytes),
+                })
+            }
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+struct PlyMesh {
+    positions: Vec<Vector3<f32>>,
+    normals: Option<Vec<Vector3<f32>>>,
+    tex_coords: Option<Vec<Vector2<f32>>>,
+    colors: Option<Vec<Vector4<u8>>>,
+    triangles: Vec<TriangleDefinition>,
+    texture_file: Option<String>,
+}
+
+/// Index of a vertex attribute component, that is read from a vertex property.
+#[derive(Copy, Clone)]
+enum VertexComponent {
+    Position(usize),
+    Normal(usize),
+    TexCoord(usize),
+    Color(usize),
+}
+
+impl VertexComponent {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "x" => Self::Position(0),
+            "y" => Self::Position(1),
+            "z" => Self::Position(2),
+            "nx" => Self::Normal(0),
+            "ny" => Self::Normal(1),
+            "nz" => Self::Normal(2),
+            "u" | "s" | "texture_u" | "texture_s" => Self::TexCoord(0),
+            "v" | "t" | "texture_v" | "texture_t" => Self::TexCoord(1),
+            "red" | "r" | "diffuse_red" => Self::Color(0),
+            "green" | "g" | "diffuse_green" => Self::Color(1),
+            "blue" | "b" | "diffuse_blue" => Self::Color(2),
+            "alpha" | "a" | "diffuse_alpha" => Self::Color(3),
+            _ => return None,
+        })
+    }
+}
+
+impl PlyMesh {
+    fn parse(bytes: &[u8]) -> Result<Self, PlyError> {
+        let (header, body_offset) = parse_header(bytes)?;
+        let body = &bytes[body_offset..];
+
+        let text;
+        let mut reader = match header.format {
+            PlyFormat::Ascii => {
+                text = String::from_utf8_lossy(body);
+                ValueReader::Ascii(text.split_whitespace())
+            }
+            PlyFormat::BinaryLittleEndian | PlyFormat::BinaryBigEndian => ValueReader::Binary {
+                data: body,
+                big_endian: header.format == PlyFormat::BinaryBigEndian,
+            },
+        };
+
+        let mut mesh = PlyMesh {
+            texture_file: header.texture_file.clone(),
+            ..Default::default()
+        };
+        let mut has_positions = false;
+        // Faces could be declared before vertices, so the amount of vertices is taken from the
+        // header.
+        let vertex_count = header
+            .elements
+            .iter()
+            .find(|element| element.name == "vertex")
+            .map(|element| element.count)
+            .unwrap_or_default();
+        for element in header.elements.iter() {
+            match element.name.as_str() {
+                "vertex" => {
+                    has_positions = true;
+                    mesh.read_vertices(element, &mut reader)?
+                }
+                "face" => mesh.read_faces(element, vertex_count, &mut reader)?,
+                _ => skip_element(element, &mut reader)?,
+            }
+        }
+
+        if !has_positions {
+            return Err(PlyError::MissingPositions);
+        }
+
+        Ok(mesh)
+    }
+
+    fn read_vertices(
+        &mut self,
+        element: &PlyElement,
+        reader: &mut ValueReader,
+    ) -> Result<(), PlyError> {
+        let components = element
+            .properties
+            .iter()
+            .map(|property| VertexComponent::from_name(&property.name))
+            .collect::<Vec<_>>();
+        let has =
+            |predicate: fn(&VertexComponent) -> bool| components.iter().flatten().any(predicate);
+        if !has(|c| matches!(c, VertexComponent::Position(_))) {
+            return Err(PlyError::MissingPositions);
+        }
+
+        let mut normals = has(|c| matches!(c, VertexComponent::Normal(_))).then(Vec::new);
+        let mut tex_coords = has(|c| matches!(c, VertexComponent::TexCoord(_))).then(Vec::new);
+        let mut colors = has(|c| matches!(c, VertexComponent::Color(_))).then(Vec::new);
+
+        self.positions.reserve(element.count);
+        for _ in 0..element.count {
+            let mut position = Vector3::default();
+            let mut normal = Vector3::default();
+            let mut tex_coord = Vector2::default();
+            let mut color = Vector4::repeat(u8::MAX);
+
+            for (property, component) in element.properties.iter().zip(components.iter()) {
+                let PlyPropertyKind::Scalar(scalar) = property.kind else {
+                    skip_property(&property.kind, reader)?;
+                    continue;
+                };
+                let value = reader.read(scalar)?;
+                match component {
+                    Some(VertexComponent::Position(i)) => position[*i] = value as f32,
+                    Some(VertexComponent::Normal(i)) => normal[*i] = value as f32,
+                    Some(VertexComponent::TexCoord(i)) => tex_coord[*i] = value as f32,
+                    Some(VertexComponent::Color(i)) => {
+                        let value = if scalar.is_float() {
+                            value * 255.0
+                        } else {
+                            value
+                        };
+                        color[*i] = value.clamp(0.0, 255.0) as u8;
+                    }
+                    None => (),
+                }
+            }
+
+            self.positions.push(position);
+            if let Some(normals) = normals.as_mut() {
+                normals.push(normal);
+            }
+            if let Some(tex_coords) = tex_coords.as_mut() {
+                // Invert Y because OpenGL has origin at left *bottom* corner.
+                tex_coords.push(Vector2::new(tex_coord.x, 1.0 - tex_coord.y));
+            }
+            if let Some(colors) = colors.as_mut() {
+                colors.push(color);
+            }
+        }
+
+        self.normals = normals;
+        self.tex_coords = tex_coords;
+        self.colors = colors;
+
+        Ok(())
+    }
+
+    fn read_faces(
+        &mut self,
+        element: &PlyElement,
+        vertex_count: usize,
+        reader: &mut ValueReader,
+    ) -> Result<(), PlyError> {
+        let mut polygon = Vec::new();
+        for _ in 0..element.count {
+            for property in element.properties.iter() {
+                match property.kind {
+                    PlyPropertyKind::List { count, item }
+                        if property.name == "vertex_indices" || property.name == "vertex_index" =>
+                    {
+                        polygon.clear();
+                        for _ in 0..reader.read(count)? as usize {
+                            let index = reader.read(item)?;
+                            if index < 0.0 || index >= vertex_count as f64 || index.fract() != 0.0 {
+                                return Err(PlyError::InvalidIndex(index as i64));
+                            }
+                            polygon.push(index as u32);
+                        }
+                        for i in 1..polygon.len().saturating_sub(1) {
+                            self.triangles.push(TriangleDefinition([
+                                polygon[0],
+                                polygon[i],
+                                polygon[i + 1],
+                            ]));
+                        }
+                    }
+                    ref kind => skip_property(kind, reader)?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn into_surface_data(self) -> Result<SurfaceData, PlyError> {
+        let vertices = self
+            .positions
+            .iter()
+            .enumerate()
+            .map(|(i, position)| StaticVertex {
+                position: *position,
+                tex_coord: self
+                    .tex_coords
+                    .as_ref()
+                    .map(|tex_coords| tex_coords[i])
+                    .unwrap_or_default(),
+                normal: self
+                    .normals
+                    .as_ref()
+                    .map(|normals| normals[i])
+                    .unwrap_or_default(),
+                tangent: Vector4::default(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut data = SurfaceData::new(
+            VertexBuffer::new(vertices.len(), vertices)?,
+            TriangleBuffer::new(self.triangles),
+        );
+        if self.normals.is_none() {
+            data.calculate_smooth_normals()?;
+        }
+        data.calculate_tangents()?;
+
+        if let Some(colors) = self.colors {
+            let mut vertex_buffer = data.vertex_buffer.modify();
+            vertex_buffer.add_attribute(
+                VertexAttributeDescriptor {
+                    usage: VertexAttributeUsage::Color,
+                    data_type: VertexAttributeDataType::U8,
+                    size: 4,
+                    divisor: 0,
+                    shader_location: COLOR_SHADER_LOCATION,
+                    normalized: true,
+                },
+                Vector4::<u8>::repeat(u8::MAX),
+            )?;
+            for (mut vertex, color) in vertex_buffer.iter_mut().zip(colors) {
+                vertex.write_4_u8(VertexAttributeUsage::Color, color)?;
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+fn skip_property(kind: &PlyPropertyKind, reader: &mut ValueReader) -> Result<(), PlyError> {
+    match *kind {
+        PlyPropertyKind::Scalar(scalar) => {
+            reader.read(scalar)?;
+        }
+        PlyPropertyKind::List { count, item } => {
+            for _ in 0..reader.read(count)? as usize {
+                reader.read(item)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn skip_element(element: &PlyElement, reader: &mut ValueReader) -> Result<(), PlyError> {
+    for _ in 0..element.count {
+        for property in element.properties.iter() {
+            skip_property(&property.kind, reader)?;
+        }
+    }
+    Ok(())
+}
+
+/// Tries to load and convert PLY from given path.
+///
+/// Normally you should never use this method, use resource manager to load models.
+pub async fn load_to_scene<P: AsRef<Path>>(
+    scene: &mut Scene,
+    resource_manager: ResourceManager,
+    io: &dyn ResourceIo,
+    path: P,
+    model_import_options: &ModelImportOptions,
+) -> Result<(), PlyError> {
+    let path = path.as_ref();
+
+    let bytes = io.load_file(path).await?;
+    let mut mesh = PlyMesh::parse(&bytes)?;
+    let texture_file = mesh.texture_file.take();
+    let mut surface = Surface::new(SurfaceResource::new_ok(
+        Uuid::new_v4(),
+        ResourceKind::External,
+        mesh.into_surface_data()?,
+    ));
+
+    if let Some(texture_file) = texture_file {
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let texture_path =
+            obj::resolve_path(&texture_file, directory, path, io, model_import_options).await;
+
+        if let Some(texture_path) = texture_path {
+            let mut material = Material::from_shader(ShaderResource::standard());
+            material.bind(
+                "diffuseTexture",
+                MaterialResourceBinding::Texture(MaterialTextureBinding {
+                    value: Some(resource_manager.request::<Texture>(texture_path)),
+                }),
+            );
+            surface.set_material(MaterialResource::new_embedded(material));
+        } else {
+            Log::warn(format!(
+                "Unable to find a texture {texture_file} for 3D model {path:?} using \
+                {model_import_options:?} option!"
+            ));
+        }
+    }
+
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let root = scene.graph.get_root();
+    let mesh = MeshBuilder::new(BaseBuilder::new().with_name(name))
+        .with_surfaces(vec![surface])
+        .build(&mut scene.graph);
+    scene.graph.link_nodes(mesh, root);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::mesh::buffer::VertexReadTrait;
+
+    const ASCII: &str = "ply
+format ascii 1.0
+comment A quad with vertex colors.
+comment TextureFile quad texture.png
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+0 1 0 255 255 255
+4 0 1 2 3
+";
+
+    #[test]
+    fn test_parse_ascii() {
+        let mesh = PlyMesh::parse(ASCII.as_bytes()).unwrap();
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.positions[2], Vector3::new(1.0, 1.0, 0.0));
+        assert!(mesh.normals.is_none());
+        assert_eq!(mesh.texture_file.as_deref(), Some("quad texture.png"));
+        assert_eq!(
+            mesh.colors.as_ref().unwrap()[1],
+            Vector4::new(0, 255, 0, 255)
+        );
+        assert_eq!(
+            mesh.triangles,
+            vec![TriangleDefinition([0, 1, 2]), TriangleDefinition([0, 2, 3])]
+        );
+
+        let data = mesh.into_surface_data().unwrap();
+        let vertex = data.vertex_buffer.get(2).unwrap();
+        assert_eq!(
+            vertex.read_4_u8(VertexAttributeUsage::Color).unwrap(),
+            Vector4::new(0, 0, 255, 255)
+        );
+        assert_eq!(
+            vertex.read_3_f32(VertexAttributeUsage::Normal).unwrap(),
+            Vector3::new(0.0, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn test_parse_binary() {
+        let mut bytes = b"ply
+format binary_big_endian 1.0
+element vertex 3
+property double x
+property double y
+property double z
+element face 1
+property list uchar ushort vertex_indices
+element edge 1
+property int vertex1
+property int vertex2
+end_header
+"
+        .to_vec();
+        for value in [0.0f64, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        bytes.push(3);
+        for index in [0u16, 1, 2] {
+            bytes.extend_from_slice(&index.to_be_bytes());
+        }
+        bytes.extend_from_slice(&[0; 8]);
+
+        let mesh = PlyMesh::parse(&bytes).unwrap();
+        assert_eq!(mesh.positions[1], Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(mesh.triangles, vec![TriangleDefinition([0, 1, 2])]);
+
+        // Truncated body.
+        assert!(matches!(
+            PlyMesh::parse(&bytes[..bytes.len() - 9]),
+            Err(PlyError::UnexpectedEndOfFile)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_indices() {
+        for (face, index) in [("3 0 1 4", 4), ("3 0 -1 2", -1)] {
+            let text = ASCII.replace("4 0 1 2 3", face);
+            assert!(matches!(
+                PlyMesh::parse(text.as_bytes()),
+                Err(PlyError::InvalidIndex(i)) if i == index
+            ));
+        }
+
+        // Faces declared before vertices are validated using the vertex count from the header.
+        let text = "ply
+format ascii 1.0
+element face 1
+property list uchar int vertex_indices
+element vertex 3
+property float x
+property float y
+property float z
+end_header
+3 0 1 3
+0 0 0
+1 0 0
+0 1 0
+";
+        assert!(matches!(
+            PlyMesh::parse(text.as_bytes()),
+            Err(PlyError::InvalidIndex(3))
+        ));
+    }
+}
@@ -0,0 +1,358 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Contains all methods to load and convert STL (stereolithography) models, both ASCII and binary
+//! variants.
+//!
+//! Every solid of a file is converted into a separate mesh node with a single surface. STL stores
+//! geometry as a "triangle soup" with per-facet normals, vertices with the same position and normal
+//! are merged.
+//!
+//! Normally you should never use methods from this module directly, use resource manager to load
+//! models and create their instances.
+
+use crate::{
+    asset::{io::ResourceIo, untyped::ResourceKind},
+    core::{
+        algebra::{Vector2, Vector3, Vector4},
+        io::FileError,
+        uuid::Uuid,
+    },
+    graph::SceneGraph,
+    scene::{
+        base::BaseBuilder,
+        mesh::{
+            surface::{Surface, SurfaceData, SurfaceResource},
+            vertex::StaticVertex,
+            MeshBuilder,
+        },
+        Scene,
+    },
+    utils::raw_mesh::RawMeshBuilder,
+};
+use std::{
+    fmt::{Display, Formatter},
+    path::Path,
+};
+
+/// Size of a header of binary STL.
+const HEADER_SIZE: usize = 80;
+/// Size of a triangle record of binary STL: normal, three vertices and attribute byte count.
+const TRIANGLE_SIZE: usize = 50;
+
+/// All possible errors that may occur during STL loading.
+#[derive(Debug)]
+pub enum StlError {
+    /// An error occurred during file loading.
+    FileLoadError(FileError),
+    /// The file ended before all the triangles were read.
+    UnexpectedEndOfFile,
+    /// A line of ASCII STL has invalid content.
+    Syntax {
+        /// Number of the line (starting from one).
+        line: usize,
+        /// Description of the error.
+        message: String,
+    },
+}
+
+impl std::error::Error for StlError {}
+
+impl Display for StlError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StlError::FileLoadError(v) => {
+                write!(f, "STL: File load error {v:?}.")
+            }
+            StlError::UnexpectedEndOfFile => {
+                write!(f, "STL: Unexpected end of file.")
+            }
+            StlError::Syntax { line, message } => {
+                write!(f, "STL: Syntax error at line {line}: {message}")
+            }
+        }
+    }
+}
+
+impl From<FileError> for StlError {
+    fn from(err: FileError) -> Self {
+        StlError::FileLoadError(err)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct StlTriangle {
+    normal: Vector3<f32>,
+    vertices: [Vector3<f32>; 3],
+}
+
+#[derive(Debug, PartialEq)]
+struct StlSolid {
+    name: String,
+    triangles: Vec<StlTriangle>,
+}
+
+impl StlSolid {
+    fn into_surface_data(self) -> SurfaceData {
+        let mut builder =
+            RawMeshBuilder::<StaticVertex>::new(self.triangles.len(), self.triangles.len() * 3);
+        for triangle in self.triangles {
+            let [a, b, c] = triangle.vertices;
+            // Some exporters write zero normals, so the normal must be recalculated.
+            let normal = triangle
+                .normal
+                .try_normalize(f32::EPSILON)
+                .or_else(|| (b - a).cross(&(c - a)).try_normalize(f32::EPSILON))
+                .unwrap_or_else(Vector3::y);
+            for position in triangle.vertices {
+                builder.insert(StaticVertex {
+                    position,
+                    tex_coord: Vector2::default(),
+                    normal,
+                    tangent: Vector4::default(),
+                });
+            }
+        }
+        let mut data = SurfaceData::from_raw_mesh(builder.build());
+        data.calculate_tangents().unwrap();
+        data
+    }
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() >= HEADER_SIZE + 4 {
+        // Binary files may also start with `solid` word, so the size is checked first.
+        let count = u32::from_le_bytes(bytes[HEADER_SIZE..HEADER_SIZE + 4].try_into().unwrap());
+        if HEADER_SIZE + 4 + count as usize * TRIANGLE_SIZE == bytes.len() {
+            return true;
+        }
+    }
+    !bytes.trim_ascii_start().starts_with(b"solid")
+}
+
+fn parse_binary(bytes: &[u8], name: &str) -> Result<StlSolid, StlError> {
+    let count_bytes = bytes
+        .get(HEADER_SIZE..HEADER_SIZE + 4)
+        .ok_or(StlError::UnexpectedEndOfFile)?;
+    let count = u32::from_le_bytes(count_bytes.try_into().unwrap()) as usize;
+    let records = bytes
+        .get(HEADER_SIZE + 4..HEADER_SIZE + 4 + count * TRIANGLE_SIZE)
+        .ok_or(StlError::UnexpectedEndOfFile)?;
+
+    let read_vector = |record: &[u8], offset: usize| {
+        let mut vector = Vector3::default();
+        for (i, component) in vector.iter_mut().enumerate() {
+            let start = offset + i * 4;
+            *component = f32::from_le_bytes(record[start..start + 4].try_into().unwrap());
+        }
+        vector
+    };
+
+    let triangles = records
+        .chunks_exact(TRIANGLE_SIZE)
+        .map(|record| StlTriangle {
+            normal: read_vector(record, 0),
+            vertices: [
+                read_vector(record, 12),
+                read_vector(record, 24),
+                read_vector(record, 36),
+            ],
+        })
+        .collect();
+
+    Ok(StlSolid {
+        name: name.to_string(),
+        triangles,
+    })
+}
+
+fn parse_ascii(text: &str, default_name: &str) -> Result<Vec<StlSolid>, StlError> {
+    let syntax_error = |line: usize, message: String| StlError::Syntax { line, message };
+
+    let mut solids = Vec::<StlSolid>::new();
+    let mut normal = Vector3::default();
+    let mut vertices = Vec::with_capacity(3);
+    for (line_index, line) in text.lines().enumerate() {
+        let line_number = line_index + 1;
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+
+        let parse_vector = |tokens: &mut std::str::SplitWhitespace| {
+            let mut vector = Vector3::default();
+            for component in vector.iter_mut() {
+                let token = tokens
+                    .next()
+                    .ok_or_else(|| syntax_error(line_number, "expected 3 numbers".to_string()))?;
+                *component = token
+                    .parse()
+                    .map_err(|_| syntax_error(line_number, format!("{token} is not a number")))?;
+            }
+            Ok::<_, StlError>(vector)
+        };
+
+        match keyword {
+            "solid" => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                solids.push(StlSolid {
+                    name: if name.is_empty() {
+                        default_name.to_string()
+                    } else {
+                        name
+                    },
+                    triangles: Default::default(),
+                });
+            }
+            "facet" => {
+                // facet normal nx ny nz
+                tokens.next();
+                normal = parse_vector(&mut tokens)?;
+                vertices.clear();
+            }
+            "vertex" => vertices.push(parse_vector(&mut tokens)?),
+            "endfacet" => {
+                let solid = solids.last_mut().ok_or_else(|| {
+                    syntax_error(line_number, "facet outside of a solid".to_string())
+                })?;
+                let vertices: [Vector3<f32>; 3] = vertices.as_slice().try_into().map_err(|_| {
+                    syntax_error(line_number, "a facet must have three vertices".to_string())
+                })?;
+                solid.triangles.push(StlTriangle { normal, vertices });
+            }
+            _ => (),
+        }
+    }
+
+    Ok(solids)
+}
+
+/// Tries to load and convert STL from given path.
+///
+/// Normally you should never use this method, use resource manager to load models.
+pub async fn load_to_scene<P: AsRef<Path>>(
+    scene: &mut Scene,
+    io: &dyn ResourceIo,
+    path: P,
+) -> Result<(), StlError> {
+    let path = path.as_ref();
+
+    let bytes = io.load_file(path).await?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let solids = if is_binary(&bytes) {
+        vec![parse_binary(&bytes, &name)?]
+    } else {
+        parse_ascii(&String::from_utf8_lossy(&bytes), &name)?
+    };
+
+    let root = scene.graph.get_root();
+    for solid in solids {
+        let mesh = MeshBuilder::new(BaseBuilder::new().with_name(solid.name.as_str()))
+            .with_surfaces(vec![Surface::new(SurfaceResource::new_ok(
+                Uuid::new_v4(),
+                ResourceKind::External,
+                solid.into_surface_data(),
+            ))])
+            .build(&mut scene.graph);
+        scene.graph.link_nodes(mesh, root);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn triangle() -> StlTriangle {
+        StlTriangle {
+            normal: Vector3::new(0.0, 0.0, 1.0),
+            vertices: [
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_parse_ascii() {
+        let text = "solid Part
+              facet normal 0 0 1
+                outer loop
+                  vertex 0 0 0
+                  vertex 1 0 0
+                  vertex 0 1 0
+                endloop
+              endfacet
+            endsolid Part
+            solid
+            endsolid";
+        assert!(!is_binary(text.as_bytes()));
+        let solids = parse_ascii(text, "Model").unwrap();
+        assert_eq!(
+            solids,
+            vec![
+                StlSolid {
+                    name: "Part".to_string(),
+                    triangles: vec![triangle()],
+                },
+                StlSolid {
+                    name: "Model".to_string(),
+                    triangles: vec![],
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_binary() {
+        // Binary files are allowed to start with `solid` too.
+        let mut bytes = b"solid".to_vec();
+        bytes.resize(HEADER_SIZE, 0);
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        for _ in 0..2 {
+            let triangle = triangle();
+            for vector in [triangle.normal].iter().chain(triangle.vertices.iter()) {
+                for component in vector.iter() {
+                    bytes.extend_from_slice(&component.to_le_bytes());
+                }
+            }
+            bytes.extend_from_slice(&[0; 2]);
+        }
+        assert!(is_binary(&bytes));
+
+        let solid = parse_binary(&bytes, "Model").unwrap();
+        assert_eq!(solid.triangles, vec![triangle(), triangle()]);
+
+        // Duplicated vertices are merged.
+        let data = solid.into_surface_data();
+        assert_eq!(data.vertex_buffer.vertex_count(), 3);
+        assert_eq!(data.geometry_buffer.len(), 2);
+
+        assert!(matches!(
+            parse_binary(&bytes[..bytes.len() - 1], "Model"),
+            Err(StlError::UnexpectedEndOfFile)
+        ));
+    }
+}
@@ -0,0 +1,1111 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Position-based cloth simulation for mesh surfaces. See [`Cloth`] docs for more info.
+
+use crate::{
+    core::{
+        algebra::{Matrix4, Point3, Vector3},
+        color::Color,
+        log::Log,
+        math::aabb::AxisAlignedBoundingBox,
+        pool::Handle,
+        reflect::prelude::*,
+        uuid::{uuid, Uuid},
+        variable::InheritableVariable,
+        visitor::prelude::*,
+    },
+    scene::{
+        base::{Base, BaseBuilder},
+        collider::{Collider, ColliderShape},
+        debug::{Line, SceneDrawingContext},
+        graph::Graph,
+        mesh::{
+            buffer::{VertexAttributeUsage, VertexReadTrait, VertexWriteTrait},
+            surface::{SurfaceData, SurfaceResource},
+            Mesh,
+        },
+        node::{constructor::NodeConstructor, Node, NodeTrait, UpdateContext},
+    },
+};
+use fxhash::FxHashMap;
+use fyrox_graph::{constructor::ConstructorProvider, SceneGraph};
+use std::ops::{Deref, DerefMut};
+
+#[derive(Clone, Debug)]
+struct DistanceConstraint {
+    a: usize,
+    b: usize,
+    rest_length: f32,
+}
+
+impl DistanceConstraint {
+    fn new(a: usize, b: usize, positions: &[Vector3<f32>]) -> Self {
+        Self {
+            a,
+            b,
+            rest_length: positions[a].metric_distance(&positions[b]),
+        }
+    }
+
+    fn solve(&self, positions: &mut [Vector3<f32>], inv_masses: &[f32], stiffness: f32) {
+        let w_sum = inv_masses[self.a] + inv_masses[self.b];
+        if w_sum <= 0.0 {
+            return;
+        }
+        let delta = positions[self.b] - positions[self.a];
+        let length = delta.norm();
+        if length <= f32::EPSILON {
+            return;
+        }
+        let correction = delta.scale((length - self.rest_length) / (length * w_sum) * stiffness);
+        positions[self.a] += correction.scale(inv_masses[self.a]);
+        positions[self.b] -= correction.scale(inv_masses[self.b]);
+    }
+}
+
+enum CollisionShape {
+    Sphere {
+        center: Vector3<f32>,
+        radius: f32,
+    },
+    Capsule {
+        begin: Vector3<f32>,
+        end: Vector3<f32>,
+        radius: f32,
+    },
+}
+
+impl CollisionShape {
+    fn from_collider(collider: &Collider, margin: f32) -> Option<Self> {
+        let transform = collider.global_transform();
+        match collider.shape() {
+            ColliderShape::Ball(ball) => Some(Self::Sphere {
+                center: collider.global_position(),
+                radius: ball.radius + margin,
+            }),
+            ColliderShape::Capsule(capsule) => Some(Self::Capsule {
+                begin: transform
+                    .transform_point(&Point3::from(capsule.begin))
+                    .coords,
+                end: transform.transform_point(&Point3::from(capsule.end)).coords,
+                radius: capsule.radius + margin,
+            }),
+            _ => None,
+        }
+    }
+
+    fn push_out(&self, point: &mut Vector3<f32>) {
+        let (closest, radius) = match *self {
+            CollisionShape::Sphere { center, radius } => (center, radius),
+            CollisionShape::Capsule { begin, end, radius } => {
+                let axis = end - begin;
+                let t = (*point - begin).dot(&axis) / axis.norm_squared().max(f32::EPSILON);
+                (begin + axis.scale(t.clamp(0.0, 1.0)), radius)
+            }
+        };
+        let offset = *point - closest;
+        let distance = offset.norm();
+        if distance < radius && distance > f32::EPSILON {
+            *point = closest + offset.scale(radius / distance);
+        }
+    }
+}
+
+// Runtime state of the simulation. Vertices with the same position are welded into a single
+// particle, otherwise the cloth would tear along UV seams.
+#[derive(Clone, Debug, Default)]
+struct ClothState {
+    original_data: SurfaceResource,
+    simulated_data: SurfaceResource,
+    vertex_to_particle: Vec<usize>,
+    rest_positions: Vec<Vector3<f32>>,
+    positions: Vec<Vector3<f32>>,
+    prev_positions: Vec<Vector3<f32>>,
+    inv_masses: Vec<f32>,
+    triangles: Vec<[usize; 3]>,
+    stretch_constraints: Vec<DistanceConstraint>,
+    bend_constraints: Vec<DistanceConstraint>,
+    accumulator: f32,
+}
+
+impl ClothState {
+    fn new(
+        original_data: SurfaceResource,
+        data: &SurfaceData,
+        pin_weights: &[f32],
+        transform: &Matrix4<f32>,
+    ) -> Option<Self> {
+        let mut particles = FxHashMap::default();
+        let mut vertex_to_particle = Vec::new();
+        let mut rest_positions = Vec::new();
+        let mut inv_masses = Vec::<f32>::new();
+        for (i, vertex) in data.vertex_buffer.iter().enumerate() {
+            let position = vertex.read_3_f32(VertexAttributeUsage::Position).ok()?;
+            let weight = pin_weights.get(i).cloned().unwrap_or(1.0).max(0.0);
+            let key = (
+                position.x.to_bits(),
+                position.y.to_bits(),
+                position.z.to_bits(),
+            );
+            let particle = *particles.entry(key).or_insert_with(|| {
+                rest_positions.push(position);
+                inv_masses.push(weight);
+                rest_positions.len() - 1
+            });
+            // A particle is pinned if any of its vertices is pinned.
+            inv_masses[particle] = inv_masses[particle].min(weight);
+            vertex_to_particle.push(particle);
+        }
+
+        // Triangles with out-of-bounds indices make the surface invalid.
+        let mut triangles = Vec::new();
+        for triangle in data.geometry_buffer.iter() {
+            let [a, b, c] = triangle.0;
+            let t = [
+                *vertex_to_particle.get(a as usize)?,
+                *vertex_to_particle.get(b as usize)?,
+                *vertex_to_particle.get(c as usize)?,
+            ];
+            if t[0] != t[1] && t[1] != t[2] && t[0] != t[2] {
+                triangles.push(t);
+            }
+        }
+
+        // Every edge becomes a stretch constraint, every pair of triangles that share an edge
+        // gives a bend constraint between their opposite vertices.
+        let mut edges = FxHashMap::<(usize, usize), Vec<usize>>::default();
+        for triangle in triangles.iter() {
+            for k in 0..3 {
+                let a = triangle[k];
+                let b = triangle[(k + 1) % 3];
+                let opposite = triangle[(k + 2) % 3];
+                edges
+                    .entry((a.min(b), a.max(b)))
+                    .or_default()
+                    .push(opposite);
+            }
+        }
+        let mut edges = edges.into_iter().collect::<Vec<_>>();
+        // Hash map order is not stable, but the order of constraints affects the result.
+        edges.sort_by_key(|(edge, _)| *edge);
+
+        let mut stretch_constraints = Vec::with_capacity(edges.len());
+        let mut bend_constraints = Vec::new();
+        for ((a, b), opposites) in edges {
+            stretch_constraints.push(DistanceConstraint::new(a, b, &rest_positions));
+            if let [c, d] = opposites[..] {
+                if c != d {
+                    bend_constraints.push(DistanceConstraint::new(c, d, &rest_positions));
+                }
+            }
+        }
+
+        let positions = rest_positions
+            .iter()
+            .map(|p| transform.transform_point(&Point3::from(*p)).coords)
+            .collect::<Vec<_>>();
+
+        Some(Self {
+            simulated_data: SurfaceResource::new_embedded(data.clone()),
+            original_data,
+            vertex_to_particle,
+            rest_positions,
+            prev_positions: positions.clone(),
+            positions,
+            inv_masses,
+            triangles,
+            stretch_constraints,
+            bend_constraints,
+            accumulator: 0.0,
+        })
+    }
+
+    fn write_to_surface(&self, data: &mut SurfaceData, transform: &Matrix4<f32>) {
+        let Some(inv_transform) = transform.try_inverse() else {
+            return;
+        };
+
+        let local_positions = self
+            .positions
+            .iter()
+            .map(|p| inv_transform.transform_point(&Point3::from(*p)).coords)
+            .collect::<Vec<_>>();
+
+        let mut normals = vec![Vector3::<f32>::zeros(); local_positions.len()];
+        for &[a, b, c] in self.triangles.iter() {
+            // Area-weighted normal.
+            let normal = (local_positions[b] - local_positions[a])
+                .cross(&(local_positions[c] - local_positions[a]));
+            normals[a] += normal;
+            normals[b] += normal;
+            normals[c] += normal;
+        }
+
+        let has_normals = data
+            .vertex_buffer
+            .has_attribute(VertexAttributeUsage::Normal);
+        let mut vertex_buffer = data.vertex_buffer.modify();
+        for (i, &particle) in self.vertex_to_particle.iter().enumerate() {
+            let Some(mut vertex) = vertex_buffer.get_mut(i) else {
+                break;
+            };
+            let _ = vertex.write_3_f32(VertexAttributeUsage::Position, local_positions[particle]);
+            if has_normals {
+                if let Some(normal) = normals[particle].try_normalize(f32::EPSILON) {
+                    let _ = vertex.write_3_f32(VertexAttributeUsage::Normal, normal);
+                }
+            }
+        }
+    }
+}
+
+/// Cloth is a position-based dynamics simulation of a surface of a [`Mesh`] node. It could be
+/// used for capes, flags, curtains and other pieces of fabric. The simulation runs on CPU, the
+/// results are written into the vertex buffer of the surface every frame.
+///
+/// ## How it works
+///
+/// Every vertex of the surface becomes a particle (vertices with the same position are welded
+/// together, so UV seams will not tear). Every edge of the surface becomes a stretch constraint
+/// and every pair of adjacent triangles gives a bend constraint. Particles are affected by gravity
+/// and wind, then the constraints are solved [`Self::iterations`] times. The simulation is done in
+/// world space with fixed [`Self::time_step`], which makes it deterministic for the same sequence
+/// of frames.
+///
+/// When the simulation starts, the cloth makes a unique copy of the surface data, so the surface
+/// data resource of other meshes is not affected.
+///
+/// ## Pinning
+///
+/// Each vertex has a weight (inverse mass), that is defined by [`Self::set_pin_weights`]. Weight
+/// 0.0 means that the vertex is pinned and follows the mesh node (for example, a cape attached to
+/// the shoulders of a character). Vertices without an explicit weight have weight 1.0.
+///
+/// ## Collisions
+///
+/// The cloth collides with the colliders from [`Self::set_colliders`] list. Only ball and capsule
+/// shapes are supported, other shapes are ignored.
+///
+/// ## Example
+///
+/// ```rust
+/// # use fyrox_impl::{
+/// #     core::{algebra::Matrix4, pool::Handle},
+/// #     scene::{
+/// #         base::BaseBuilder,
+/// #         cloth::{Cloth, ClothBuilder},
+/// #         graph::Graph,
+/// #         mesh::{
+/// #             surface::{SurfaceBuilder, SurfaceData, SurfaceResource},
+/// #             MeshBuilder,
+/// #         },
+/// #     },
+/// # };
+/// fn create_flag(graph: &mut Graph) -> Handle<Cloth> {
+///     let columns = 10;
+///     let mesh = MeshBuilder::new(BaseBuilder::new())
+///         .with_surfaces(vec![SurfaceBuilder::new(SurfaceResource::new_embedded(
+///             SurfaceData::make_xy_grid(columns, 10, &Matrix4::identity()),
+///         ))
+///         .build()])
+///         .build(graph);
+///
+///     ClothBuilder::new(BaseBuilder::new())
+///         .with_mesh(mesh)
+///         // Pin the top row of vertices.
+///         .with_pinned_vertices(0..=columns)
+///         .build(graph)
+/// }
+/// ```
+#[derive(Visit, Reflect, Debug)]
+#[reflect(
+    derived_type = "Node",
+    type_uuid = "f2b94432-b74d-40bf-b1d7-80df06306484"
+)]
+#[visit(optional)]
+pub struct Cloth {
+    base: Base,
+
+    #[reflect(setter = "set_mesh")]
+    mesh: InheritableVariable<Handle<Mesh>>,
+
+    #[reflect(setter = "set_surface")]
+    surface: InheritableVariable<usize>,
+
+    #[reflect(setter = "set_pin_weights")]
+    pin_weights: InheritableVariable<Vec<f32>>,
+
+    #[reflect(
+        setter = "set_stretch_stiffness",
+        min_value = 0.0,
+        max_value = 1.0,
+        step = 0.01
+    )]
+    stretch_stiffness: InheritableVariable<f32>,
+
+    #[reflect(
+        setter = "set_bend_stiffness",
+        min_value = 0.0,
+        max_value = 1.0,
+        step = 0.01
+    )]
+    bend_stiffness: InheritableVariable<f32>,
+
+    #[reflect(setter = "set_damping", min_value = 0.0, max_value = 1.0, step = 0.01)]
+    damping: InheritableVariable<f32>,
+
+    #[reflect(setter = "set_iterations", min_value = 1.0, step = 1.0)]
+    iterations: InheritableVariable<u32>,
+
+    #[reflect(setter = "set_time_step", min_value = 0.001, step = 0.001)]
+    time_step: InheritableVariable<f32>,
+
+    #[reflect(setter = "set_gravity")]
+    gravity: InheritableVariable<Vector3<f32>>,
+
+    #[reflect(setter = "set_wind")]
+    wind: InheritableVariable<Vector3<f32>>,
+
+    #[reflect(setter = "set_drag", min_value = 0.0, step = 0.01)]
+    drag: InheritableVariable<f32>,
+
+    #[reflect(setter = "set_colliders")]
+    colliders: InheritableVariable<Vec<Handle<Collider>>>,
+
+    #[reflect(setter = "set_collision_margin", min_value = 0.0, step = 0.001)]
+    collision_margin: InheritableVariable<f32>,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    state: Option<ClothState>,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    reset_requested: bool,
+}
+
+impl Clone for Cloth {
+    fn clone(&self) -> Self {
+        // Simulation state is bound to the surface data of the mesh and cannot be shared.
+        Self {
+            base: self.base.clone(),
+            mesh: self.mesh.clone(),
+            surface: self.surface.clone(),
+            pin_weights: self.pin_weights.clone(),
+            stretch_stiffness: self.stretch_stiffness.clone(),
+            bend_stiffness: self.bend_stiffness.clone(),
+            damping: self.damping.clone(),
+            iterations: self.iterations.clone(),
+            time_step: self.time_step.clone(),
+            gravity: self.gravity.clone(),
+            wind: self.wind.clone(),
+            drag: self.drag.clone(),
+            colliders: self.colliders.clone(),
+            collision_margin: self.collision_margin.clone(),
+            state: None,
+            reset_requested: false,
+        }
+    }
+}
+
+impl PartialEq for Cloth {
+    fn eq(&self, other: &Self) -> bool {
+        self.base == other.base
+            && self.mesh == other.mesh
+            && self.surface == other.surface
+            && self.pin_weights == other.pin_weights
+            && self.stretch_stiffness == other.stretch_stiffness
+            && self.bend_stiffness == other.bend_stiffness
+            && self.damping == other.damping
+            && self.iterations == other.iterations
+            && self.time_step == other.time_step
+            && self.gravity == other.gravity
+            && self.wind == other.wind
+            && self.drag == other.drag
+            && self.colliders == other.colliders
+            && self.collision_margin == other.collision_margin
+    }
+}
+
+impl Default for Cloth {
+    fn default() -> Self {
+        ClothBuilder::new(BaseBuilder::new()).build_cloth()
+    }
+}
+
+impl Deref for Cloth {
+    type Target = Base;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl DerefMut for Cloth {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+impl ConstructorProvider<Node, Graph> for Cloth {
+    fn constructor() -> NodeConstructor {
+        NodeConstructor::new::<Self>()
+            .with_variant("Cloth", |_| {
+                ClothBuilder::new(BaseBuilder::new().with_name("Cloth"))
+                    .build_node()
+                    .into()
+            })
+            .with_group("Physics")
+    }
+}
+
+impl Cloth {
+    // Simulation will not try to catch up more than this amount of steps per frame.
+    const MAX_STEPS_PER_FRAME: usize = 8;
+
+    /// Sets a handle of a mesh, which surface will be simulated. The simulation will be restarted.
+    pub fn set_mesh(&mut self, mesh: Handle<Mesh>) -> Handle<Mesh> {
+        self.reset();
+        self.mesh.set_value_and_mark_modified(mesh)
+    }
+
+    /// Returns a handle of the simulated mesh.
+    pub fn mesh(&self) -> Handle<Mesh> {
+        *self.mesh
+    }
+
+    /// Sets an index of the simulated surface of the mesh. The simulation will be restarted.
+    pub fn set_surface(&mut self, surface: usize) -> usize {
+        self.reset();
+        self.surface.set_value_and_mark_modified(surface)
+    }
+
+    /// Returns an index of the simulated surface of the mesh.
+    pub fn surface(&self) -> usize {
+        *self.surface
+    }
+
+    /// Sets per-vertex weights (inverse masses). 0.0 - the vertex is pinned, 1.0 - the vertex is
+    /// free. Vertices without weight are free. The simulation will be restarted.
+    pub fn set_pin_weights(&mut self, weights: Vec<f32>) -> Vec<f32> {
+        self.reset();
+        self.pin_weights.set_value_and_mark_modified(weights)
+    }
+
+    /// Returns per-vertex weights.
+    pub fn pin_weights(&self) -> &[f32] {
+        &self.pin_weights
+    }
+
+    /// Sets stiffness of stretch constraints in `[0; 1]` range. Default is 1.0.
+    pub fn set_stretch_stiffness(&mut self, stiffness: f32) -> f32 {
+        self.stretch_stiffness
+            .set_value_and_mark_modified(stiffness.clamp(0.0, 1.0))
+    }
+
+    /// Returns stiffness of stretch constraints.
+    pub fn stretch_stiffness(&self) -> f32 {
+        *self.stretch_stiffness
+    }
+
+    /// Sets stiffness of bend constraints in `[0; 1]` range. Lower values make the cloth softer.
+    /// Default is 0.1.
+    pub fn set_bend_stiffness(&mut self, stiffness: f32) -> f32 {
+        self.bend_stiffness
+            .set_value_and_mark_modified(stiffness.clamp(0.0, 1.0))
+    }
+
+    /// Returns stiffness of bend constraints.
+    pub fn bend_stiffness(&self) -> f32 {
+        *self.bend_stiffness
+    }
+
+    /// Sets a fraction of velocity, that is lost every simulation step. Default is 0.01.
+    pub fn set_damping(&mut self, damping: f32) -> f32 {
+        self.damping
+            .set_value_and_mark_modified(damping.clamp(0.0, 1.0))
+    }
+
+    /// Returns damping of the cloth.
+    pub fn damping(&self) -> f32 {
+        *self.damping
+    }
+
+    /// Sets amount of solver iterations per simulation step. More iterations make the cloth less
+    /// stretchy, but slower to simulate. Default is 8.
+    pub fn set_iterations(&mut self, iterations: u32) -> u32 {
+        self.iterations
+            .set_value_and_mark_modified(iterations.max(1))
+    }
+
+    /// Returns amount of solver iterations per simulation step.
+    pub fn iterations(&self) -> u32 {
+        *self.iterations
+    }
+
+    /// Sets fixed time step of the simulation (in seconds). Default is 1/60 of a second.
+    pub fn set_time_step(&mut self, time_step: f32) -> f32 {
+        self.time_step
+            .set_value_and_mark_modified(time_step.max(0.001))
+    }
+
+    /// Returns fixed time step of the simulation.
+    pub fn time_step(&self) -> f32 {
+        *self.time_step
+    }
+
+    /// Sets gravity acceleration, that affects the cloth. Default is (0.0, -9.81, 0.0).
+    pub fn set_gravity(&mut self, gravity: Vector3<f32>) -> Vector3<f32> {
+        self.gravity.set_value_and_mark_modified(gravity)
+    }
+
+    /// Returns gravity acceleration.
+    pub fn gravity(&self) -> Vector3<f32> {
+        *self.gravity
+    }
+
+    /// Sets wind velocity in world coordinates. Default is zero.
+    pub fn set_wind(&mut self, wind: Vector3<f32>) -> Vector3<f32> {
+        self.wind.set_value_and_mark_modified(wind)
+    }
+
+    /// Returns wind velocity.
+    pub fn wind(&self) -> Vector3<f32> {
+        *self.wind
+    }
+
+    /// Sets aerodynamic drag coefficient, which defines how strong the wind (and the air in
+    /// general) affects the cloth. Default is 0.5.
+    pub fn set_drag(&mut self, drag: f32) -> f32 {
+        self.drag.set_value_and_mark_modified(drag.max(0.0))
+    }
+
+    /// Returns aerodynamic drag coefficient.
+    pub fn drag(&self) -> f32 {
+        *self.drag
+    }
+
+    /// Sets a list of colliders the cloth collides with. Only ball and capsule shapes are
+    /// supported.
+    pub fn set_colliders(&mut self, colliders: Vec<Handle<Collider>>) -> Vec<Handle<Collider>> {
+        self.colliders.set_value_and_mark_modified(colliders)
+    }
+
+    /// Returns a list of colliders the cloth collides with.
+    pub fn colliders(&self) -> &[Handle<Collider>] {
+        &self.colliders
+    }
+
+    /// Sets a distance, that is kept between the cloth and colliders. Default is 0.01.
+    pub fn set_collision_margin(&mut self, margin: f32) -> f32 {
+        self.collision_margin
+            .set_value_and_mark_modified(margin.max(0.0))
+    }
+
+    /// Returns a distance, that is kept between the cloth and colliders.
+    pub fn collision_margin(&self) -> f32 {
+        *self.collision_margin
+    }
+
+    /// Restores the original surface data of the mesh and restarts the simulation on the next
+    /// update.
+    pub fn reset(&mut self) {
+        self.reset_requested = true;
+    }
+
+    /// Returns current positions of the particles in world coordinates. It is empty if the
+    /// simulation is not started yet.
+    pub fn particle_positions(&self) -> &[Vector3<f32>] {
+        self.state.as_ref().map_or(&[], |state| &state.positions)
+    }
+
+    fn step(&mut self, colliders: &[CollisionShape], transform: &Matrix4<f32>, dt: f32) {
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
+
+        let iterations = *self.iterations;
+        // Makes stiffness independent of the amount of iterations.
+        let iteration_stiffness = |k: f32| 1.0 - (1.0 - k).powf(1.0 / iterations as f32);
+        let stretch_stiffness = iteration_stiffness(*self.stretch_stiffness);
+        let bend_stiffness = iteration_stiffness(*self.bend_stiffness);
+
+        let mut accelerations = vec![*self.gravity; state.positions.len()];
+        if *self.drag > 0.0 {
+            for &[a, b, c] in state.triangles.iter() {
+                let [pa, pb, pc] = [a, b, c].map(|i| state.positions[i]);
+                let velocity = [a, b, c]
+                    .map(|i| state.positions[i] - state.prev_positions[i])
+                    .iter()
+                    .sum::<Vector3<f32>>()
+                    .scale(1.0 / (3.0 * dt));
+                // The length of the cross product is twice the area of the triangle.
+                let area_normal = (pb - pa).cross(&(pc - pa)).scale(0.5);
+                let Some(normal) = area_normal.try_normalize(f32::EPSILON) else {
+                    continue;
+                };
+                let relative_velocity = *self.wind - velocity;
+                let force = area_normal.scale(normal.dot(&relative_velocity) * *self.drag / 3.0);
+                for i in [a, b, c] {
+                    accelerations[i] += force.scale(state.inv_masses[i]);
+                }
+            }
+        }
+
+        let damping = 1.0 - *self.damping;
+        for (i, acceleration) in accelerations.iter().enumerate() {
+            if state.inv_masses[i] > 0.0 {
+                let velocity = (state.positions[i] - state.prev_positions[i]).scale(damping);
+                state.prev_positions[i] = state.positions[i];
+                state.positions[i] += velocity + acceleration.scale(dt * dt);
+            } else {
+                state.prev_positions[i] = state.positions[i];
+                state.positions[i] = transform
+                    .transform_point(&Point3::from(state.rest_positions[i]))
+                    .coords;
+            }
+        }
+
+        for _ in 0..iterations {
+            for constraint in state.stretch_constraints.iter() {
+                constraint.solve(&mut state.positions, &state.inv_masses, stretch_stiffness);
+            }
+            for constraint in state.bend_constraints.iter() {
+                constraint.solve(&mut state.positions, &state.inv_masses, bend_stiffness);
+            }
+            for (position, inv_mass) in state.positions.iter_mut().zip(state.inv_masses.iter()) {
+                if *inv_mass > 0.0 {
+                    for collider in colliders {
+                        collider.push_out(position);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl NodeTrait for Cloth {
+    fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.base.local_bounding_box()
+    }
+
+    fn world_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.base.world_bounding_box()
+    }
+
+    fn id(&self) -> Uuid {
+        <Self as Reflect>::type_info().type_uuid
+    }
+
+    fn update(&mut self, context: &mut UpdateContext) {
+        if !self.is_globally_enabled() {
+            return;
+        }
+
+        let margin = *self.collision_margin;
+        let colliders = self
+            .colliders
+            .iter()
+            .filter_map(|handle| context.nodes.try_get(*handle).ok())
+            .filter_map(|collider| CollisionShape::from_collider(collider, margin))
+            .collect::<Vec<_>>();
+
+        let Ok(mesh) = context.nodes.try_get_mut(*self.mesh) else {
+            return;
+        };
+        let transform = mesh.global_transform();
+        let Some(surface) = mesh.surfaces_mut().get_mut(*self.surface) else {
+            return;
+        };
+
+        if std::mem::take(&mut self.reset_requested) {
+            if let Some(state) = self.state.take() {
+                surface.data.set_value_silent(state.original_data);
+            }
+        }
+
+        let is_bound = self
+            .state
+            .as_ref()
+            .is_some_and(|state| state.simulated_data.key() == surface.data.key());
+        if !is_bound {
+            if !surface.data.is_ok() {
+                return;
+            }
+            let original_data = surface.data().clone();
+            let state = ClothState::new(
+                original_data.clone(),
+                &original_data.data_ref(),
+                &self.pin_weights,
+                &transform,
+            );
+            let Some(state) = state else {
+                Log::err("Cloth: unable to read vertex positions or indices of the surface!");
+                return;
+            };
+            surface.data.set_value_silent(state.simulated_data.clone());
+            self.state = Some(state);
+        }
+
+        let time_step = *self.time_step;
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
+        state.accumulator += context.dt;
+        let mut steps = 0;
+        while state.accumulator >= time_step && steps < Self::MAX_STEPS_PER_FRAME {
+            state.accumulator -= time_step;
+            steps += 1;
+        }
+        // Drop the time that cannot be simulated in this frame.
+        if steps == Self::MAX_STEPS_PER_FRAME {
+            state.accumulator = 0.0;
+        }
+
+        for _ in 0..steps {
+            self.step(&colliders, &transform, time_step);
+        }
+
+        if steps > 0 {
+            if let Some(state) = self.state.as_ref() {
+                let mut data = state.simulated_data.data_ref();
+                state.write_to_surface(&mut data, &transform);
+            }
+        }
+    }
+
+    fn debug_draw(&self, ctx: &mut SceneDrawingContext) {
+        let Some(state) = self.state.as_ref() else {
+            return;
+        };
+        for constraint in state.stretch_constraints.iter() {
+            ctx.add_line(Line {
+                begin: state.positions[constraint.a],
+                end: state.positions[constraint.b],
+                color: Color::GREEN,
+            });
+        }
+    }
+}
+
+/// Allows you to create cloth in declarative manner.
+pub struct ClothBuilder {
+    base_builder: BaseBuilder,
+    mesh: Handle<Mesh>,
+    surface: usize,
+    pin_weights: Vec<f32>,
+    stretch_stiffness: f32,
+    bend_stiffness: f32,
+    damping: f32,
+    iterations: u32,
+    time_step: f32,
+    gravity: Vector3<f32>,
+    wind: Vector3<f32>,
+    drag: f32,
+    colliders: Vec<Handle<Collider>>,
+    collision_margin: f32,
+}
+
+impl ClothBuilder {
+    /// Creates new cloth builder.
+    pub fn new(base_builder: BaseBuilder) -> Self {
+        Self {
+            base_builder,
+            mesh: Default::default(),
+            surface: 0,
+            pin_weights: Default::default(),
+            stretch_stiffness: 1.0,
+            bend_stiffness: 0.1,
+            damping: 0.01,
+            iterations: 8,
+            time_step: 1.0 / 60.0,
+            gravity: Vector3::new(0.0, -9.81, 0.0),
+            wind: Default::default(),
+            drag: 0.5,
+            colliders: Default::default(),
+            collision_margin: 0.01,
+        }
+    }
+
+    /// Sets desired mesh.
+    pub fn with_mesh(mut self, mesh: Handle<Mesh>) -> Self {
+        self.mesh = mesh;
+        self
+    }
+
+    /// Sets desired surface index.
+    pub fn with_surface(mut self, surface: usize) -> Self {
+        self.surface = surface;
+        self
+    }
+
+    /// Sets desired per-vertex weights. See [`Cloth::set_pin_weights`] for more info.
+    pub fn with_pin_weights(mut self, weights: Vec<f32>) -> Self {
+        self.pin_weights = weights;
+        self
+    }
+
+    /// Pins the given vertices (sets their weight to zero). Weights of the other vertices are not
+    /// changed.
+    pub fn with_pinned_vertices(mut self, vertices: impl IntoIterator<Item = usize>) -> Self {
+        for vertex in vertices {
+            if vertex >= self.pin_weights.len() {
+                self.pin_weights.resize(vertex + 1, 1.0);
+            }
+            self.pin_weights[vertex] = 0.0;
+        }
+        self
+    }
+
+    /// Sets desired stiffness of stretch constraints.
+    pub fn with_stretch_stiffness(mut self, stiffness: f32) -> Self {
+        self.stretch_stiffness = stiffness;
+        self
+    }
+
+    /// Sets desired stiffness of bend constraints.
+    pub fn with_bend_stiffness(mut self, stiffness: f32) -> Self {
+        self.bend_stiffness = stiffness;
+        self
+    }
+
+    /// Sets desired damping.
+    pub fn with_damping(mut self, damping: f32) -> Self {
+        self.damping = damping;
+        self
+    }
+
+    /// Sets desired amount of solver iterations.
+    pub fn with_iterations(mut self, iterations: u32) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Sets desired fixed time step.
+    pub fn with_time_step(mut self, time_step: f32) -> Self {
+        self.time_step = time_step;
+        self
+    }
+
+    /// Sets desired gravity.
+    pub fn with_gravity(mut self, gravity: Vector3<f32>) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// Sets desired wind velocity.
+    pub fn with_wind(mut self, wind: Vector3<f32>) -> Self {
+        self.wind = wind;
+        self
+    }
+
+    /// Sets desired aerodynamic drag coefficient.
+    pub fn with_drag(mut self, drag: f32) -> Self {
+        self.drag = drag;
+        self
+    }
+
+    /// Sets desired colliders.
+    pub fn with_colliders(mut self, colliders: Vec<Handle<Collider>>) -> Self {
+        self.colliders = colliders;
+        self
+    }
+
+    /// Sets desired collision margin.
+    pub fn with_collision_margin(mut self, margin: f32) -> Self {
+        self.collision_margin = margin;
+        self
+    }
+
+    /// Creates cloth instance.
+    pub fn build_cloth(self) -> Cloth {
+        Cloth {
+            base: self.base_builder.build_base(),
+            mesh: self.mesh.into(),
+            surface: self.surface.into(),
+            pin_weights: self.pin_weights.into(),
+            stretch_stiffness: self.stretch_stiffness.into(),
+            bend_stiffness: self.bend_stiffness.into(),
+            damping: self.damping.into(),
+            iterations: self.iterations.max(1).into(),
+            time_step: self.time_step.max(0.001).into(),
+            gravity: self.gravity.into(),
+            wind: self.wind.into(),
+            drag: self.drag.into(),
+            colliders: self.colliders.into(),
+            collision_margin: self.collision_margin.into(),
+            state: None,
+            reset_requested: false,
+        }
+    }
+
+    /// Creates cloth node.
+    pub fn build_node(self) -> Node {
+        Node::new(self.build_cloth())
+    }
+
+    /// Creates cloth node and adds it to the graph.
+    pub fn build(self, graph: &mut Graph) -> Handle<Cloth> {
+        graph.add_node(self.build_node()).to_variant()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            algebra::{Matrix4, Vector2, Vector3},
+            math::TriangleDefinition,
+            pool::Handle,
+        },
+        scene::{
+            base::BaseBuilder,
+            cloth::{Cloth, ClothBuilder},
+            collider::{ColliderBuilder, ColliderShape},
+            graph::Graph,
+            mesh::{
+                buffer::{VertexAttributeUsage, VertexReadTrait},
+                surface::{SurfaceBuilder, SurfaceData, SurfaceResource},
+                Mesh, MeshBuilder,
+            },
+            transform::TransformBuilder,
+        },
+    };
+
+    const SIZE: usize = 8;
+
+    fn add_grid(graph: &mut Graph, transform: Matrix4<f32>) -> Handle<Mesh> {
+        MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(SurfaceResource::new_embedded(
+                SurfaceData::make_xy_grid(SIZE, SIZE, &transform),
+            ))
+            .build()])
+            .build(graph)
+    }
+
+    fn simulate_curtain() -> (Graph, Handle<Cloth>, Handle<Mesh>) {
+        let mut graph = Graph::new();
+        let mesh = add_grid(&mut graph, Matrix4::identity());
+        let cloth = ClothBuilder::new(BaseBuilder::new())
+            .with_mesh(mesh)
+            .with_pinned_vertices(0..=SIZE)
+            .build(&mut graph);
+        for _ in 0..120 {
+            graph.update(Vector2::new(800.0, 600.0), 1.0 / 60.0, Default::default());
+        }
+        (graph, cloth, mesh)
+    }
+
+    #[test]
+    fn test_cloth_pinned_curtain() {
+        let (graph, cloth, mesh) = simulate_curtain();
+        let positions = graph[cloth].particle_positions();
+        assert_eq!(positions.len(), (SIZE + 1) * (SIZE + 1));
+
+        // Pinned row stays in place.
+        for (i, position) in positions.iter().take(SIZE + 1).enumerate() {
+            let expected = Vector3::new(i as f32 / SIZE as f32 - 0.5, 0.5, 0.0);
+            assert!(position.metric_distance(&expected) < 1.0e-5);
+        }
+
+        // The rest hangs down without noticeable stretching.
+        let bottom = positions[SIZE * (SIZE + 1)];
+        assert!((bottom.y - -0.5).abs() < 0.05, "{bottom:?}");
+        let spacing = 1.0 / SIZE as f32;
+        for row in 0..SIZE {
+            let a = positions[row * (SIZE + 1)];
+            let b = positions[(row + 1) * (SIZE + 1)];
+            assert!((a.metric_distance(&b) - spacing).abs() < spacing * 0.05);
+        }
+
+        // Results are written into the vertex buffer of the surface.
+        let data = graph[mesh].surfaces()[0].data();
+        let data = data.data_ref();
+        for (vertex, position) in data.vertex_buffer.iter().zip(positions) {
+            let vertex_position = vertex.read_3_f32(VertexAttributeUsage::Position).unwrap();
+            assert!(vertex_position.metric_distance(position) < 1.0e-5);
+        }
+    }
+
+    #[test]
+    fn test_cloth_invalid_indices() {
+        let mut graph = Graph::new();
+        let mut data = SurfaceData::make_xy_grid(SIZE, SIZE, &Matrix4::identity());
+        data.geometry_buffer
+            .modify()
+            .push(TriangleDefinition([0, 1, u32::MAX]));
+        let mesh = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(SurfaceResource::new_embedded(
+                data,
+            ))
+            .build()])
+            .build(&mut graph);
+        let cloth = ClothBuilder::new(BaseBuilder::new())
+            .with_mesh(mesh)
+            .build(&mut graph);
+
+        // The cloth is not simulated, instead of crashing the engine.
+        graph.update(Vector2::new(800.0, 600.0), 1.0 / 60.0, Default::default());
+        assert!(graph[cloth].particle_positions().is_empty());
+    }
+
+    #[test]
+    fn test_cloth_is_deterministic() {
+        let (graph_a, cloth_a, _) = simulate_curtain();
+        let (graph_b, cloth_b, _) = simulate_curtain();
+        assert_eq!(
+            graph_a[cloth_a].particle_positions(),
+            graph_b[cloth_b].particle_positions()
+        );
+    }
+
+    #[test]
+    fn test_cloth_sphere_collision() {
+        let mut graph = Graph::new();
+        // Horizontal sheet above a ball.
+        let mesh = add_grid(
+            &mut graph,
+            Matrix4::new_translation(&Vector3::new(0.0, 1.0, 0.0))
+                * Matrix4::from_euler_angles(std::f32::consts::FRAC_PI_2, 0.0, 0.0),
+        );
+        let radius = 0.3;
+        let ball = ColliderBuilder::new(
+            BaseBuilder::new().with_local_transform(TransformBuilder::new().build()),
+        )
+        .with_shape(ColliderShape::ball(radius))
+        .build(&mut graph);
+        let cloth = ClothBuilder::new(BaseBuilder::new())
+            .with_mesh(mesh)
+            .with_colliders(vec![ball])
+            .build(&mut graph);
+
+        // The sheet touches the ball after ~0.35 seconds of falling.
+        for _ in 0..45 {
+            graph.update(Vector2::new(800.0, 600.0), 1.0 / 60.0, Default::default());
+        }
+
+        let positions = graph[cloth].particle_positions();
+        assert!(positions.iter().all(|p| p.norm() >= radius - 1.0e-3));
+        // The middle of the sheet lies on the ball instead of falling through it.
+        let center = positions[SIZE / 2 * (SIZE + 1) + SIZE / 2];
+        assert!(center.y > 0.0, "{center:?}");
+        assert!((center.norm() - (radius + 0.01)).abs() < 0.02, "{center:?}");
+    }
+}
@@ -42,6 +42,7 @@ use crate::{
         },
         node::{Node, NodeTrait, SyncContext},
         rigidbody::RigidBody,
+        sound::occlusion::SoundOcclusionMaterial,
         Scene,
     },
 };
@@ -652,6 +653,10 @@ pub struct Collider {
     #[reflect(setter = "set_restitution_combine_rule")]
     pub(crate) restitution_combine_rule: InheritableVariable<CoefficientCombineRule>,
 
+    #[visit(optional)]
+    #[reflect(setter = "set_sound_occlusion")]
+    pub(crate) sound_occlusion: InheritableVariable<SoundOcclusionMaterial>,
+
     #[visit(skip)]
     #[reflect(hidden)]
     pub(crate) native: Cell<ColliderHandle>,
@@ -670,6 +675,7 @@ impl Default for Collider {
             solver_groups: Default::default(),
             friction_combine_rule: Default::default(),
             restitution_combine_rule: Default::default(),
+            sound_occlusion: Default::default(),
             native: Cell::new(ColliderHandle::invalid()),
         }
     }
@@ -702,6 +708,7 @@ impl Clone for Collider {
             solver_groups: self.solver_groups.clone(),
             friction_combine_rule: self.friction_combine_rule.clone(),
             restitution_combine_rule: self.restitution_combine_rule.clone(),
+            sound_occlusion: self.sound_occlusion.clone(),
             // Do not copy. The copy will have its own native representation (for example - Rapier's collider)
             native: Cell::new(ColliderHandle::invalid()),
         }
@@ -849,6 +856,21 @@ impl Collider {
         *self.is_sensor
     }
 
+    /// Sets the new sound occlusion material of the collider. It defines how much sound passes
+    /// through the collider when sound occlusion is enabled, see
+    /// [`crate::scene::sound::occlusion::SoundOcclusion`] for more info.
+    pub fn set_sound_occlusion(
+        &mut self,
+        material: SoundOcclusionMaterial,
+    ) -> SoundOcclusionMaterial {
+        self.sound_occlusion.set_value_and_mark_modified(material)
+    }
+
+    /// Returns current sound occlusion material of the collider.
+    pub fn sound_occlusion(&self) -> SoundOcclusionMaterial {
+        *self.sound_occlusion
+    }
+
     /// Sets the new friction combine rule. See [`CoefficientCombineRule`] docs for more info.
     ///
     /// # Performance
@@ -1104,6 +1126,7 @@ pub struct ColliderBuilder {
     solver_groups: InteractionGroups,
     friction_combine_rule: CoefficientCombineRule,
     restitution_combine_rule: CoefficientCombineRule,
+    sound_occlusion: SoundOcclusionMaterial,
 }
 
 impl ColliderBuilder {
@@ -1120,6 +1143,7 @@ impl ColliderBuilder {
             solver_groups: Default::default(),
             friction_combine_rule: Default::default(),
             restitution_combine_rule: Default::default(),
+            sound_occlusion: Default::default(),
         }
     }
 
@@ -1177,6 +1201,12 @@ impl ColliderBuilder {
         self
     }
 
+    /// Sets desired sound occlusion material.
+    pub fn with_sound_occlusion(mut self, material: SoundOcclusionMaterial) -> Self {
+        self.sound_occlusion = material;
+        self
+    }
+
     /// Creates collider node, but does not add it to a graph.
     pub fn build_collider(self) -> Collider {
         Collider {
@@ -1190,6 +1220,7 @@ impl ColliderBuilder {
             solver_groups: self.solver_groups.into(),
             friction_combine_rule: self.friction_combine_rule.into(),
             restitution_combine_rule: self.restitution_combine_rule.into(),
+            sound_occlusion: self.sound_occlusion.into(),
             native: Cell::new(ColliderHandle::invalid()),
         }
     }
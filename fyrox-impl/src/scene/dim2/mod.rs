@@ -26,3 +26,4 @@ pub mod joint;
 pub mod physics;
 pub mod rectangle;
 pub mod rigidbody;
+pub mod vehicle;
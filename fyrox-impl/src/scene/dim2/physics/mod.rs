@@ -996,6 +996,17 @@ impl PhysicsWorld {
         }
     }
 
+    // Returns total mass of the rigid body including the mass of its colliders, it is available
+    // only after the body was added to the physics world.
+    pub(crate) fn rigid_body_mass(
+        &self,
+        rigid_body: &scene::dim2::rigidbody::RigidBody,
+    ) -> Option<f32> {
+        self.bodies
+            .get(rigid_body.native.get())
+            .map(|native| native.mass())
+    }
+
     pub(crate) fn sync_rigid_body_node(
         &mut self,
         rigid_body: &mut scene::dim2::rigidbody::RigidBody,
@@ -0,0 +1,590 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Top-down vehicle for 2D physics. See [`Vehicle`] docs for more info.
+
+use crate::{
+    core::{
+        algebra::{Point3, UnitQuaternion, Vector2, Vector3},
+        color::Color,
+        math::aabb::AxisAlignedBoundingBox,
+        pool::Handle,
+        reflect::prelude::*,
+        uuid::{uuid, Uuid},
+        variable::InheritableVariable,
+        visitor::prelude::*,
+    },
+    scene::{
+        base::{Base, BaseBuilder},
+        debug::{Line, SceneDrawingContext},
+        dim2::rigidbody::RigidBody,
+        graph::Graph,
+        node::{constructor::NodeConstructor, Node, NodeTrait, UpdateContext},
+        vehicle::{Tire, TireInput, WheelState},
+    },
+};
+use fyrox_graph::{constructor::ConstructorProvider, SceneGraph};
+use std::ops::{Deref, DerefMut};
+
+/// A wheel of a 2D [`Vehicle`]. The position is defined in the local coordinates of the vehicle
+/// node, forward direction of a wheel is local +Y axis.
+#[derive(Clone, Debug, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "aac282c2-d320-49ac-b32a-583e89609ba5")]
+pub struct Wheel {
+    /// Position of the wheel.
+    pub position: Vector2<f32>,
+
+    /// Tire of the wheel.
+    pub tire: Tire,
+
+    /// A fraction of the maximum steering angle of the vehicle, that is applied to the wheel.
+    /// Negative values make the wheel turn in the opposite direction (for rear-wheel steering).
+    #[reflect(min_value = -1.0, max_value = 1.0, step = 0.1)]
+    pub steering_factor: f32,
+
+    /// A fraction of the engine torque, that is applied to the wheel.
+    #[reflect(min_value = 0.0, max_value = 1.0, step = 0.1)]
+    pub drive_factor: f32,
+
+    /// A fraction of the brake torque, that is applied to the wheel.
+    #[reflect(min_value = 0.0, max_value = 1.0, step = 0.1)]
+    pub brake_factor: f32,
+
+    /// An optional handle of a node, that will be moved and rotated together with the wheel. It
+    /// should be a direct child of the vehicle node.
+    pub visual: Handle<Node>,
+}
+
+impl Default for Wheel {
+    fn default() -> Self {
+        Self {
+            position: Vector2::default(),
+            tire: Default::default(),
+            steering_factor: 0.0,
+            drive_factor: 1.0,
+            brake_factor: 1.0,
+            visual: Default::default(),
+        }
+    }
+}
+
+impl Wheel {
+    /// Sets the position of the wheel.
+    pub fn with_position(mut self, position: Vector2<f32>) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Sets the steering factor of the wheel.
+    pub fn with_steering_factor(mut self, steering_factor: f32) -> Self {
+        self.steering_factor = steering_factor;
+        self
+    }
+
+    /// Sets the drive factor of the wheel.
+    pub fn with_drive_factor(mut self, drive_factor: f32) -> Self {
+        self.drive_factor = drive_factor;
+        self
+    }
+
+    /// Sets the brake factor of the wheel.
+    pub fn with_brake_factor(mut self, brake_factor: f32) -> Self {
+        self.brake_factor = brake_factor;
+        self
+    }
+
+    /// Sets the tire of the wheel.
+    pub fn with_tire(mut self, tire: Tire) -> Self {
+        self.tire = tire;
+        self
+    }
+
+    /// Sets the visual node of the wheel.
+    pub fn with_visual(mut self, visual: Handle<Node>) -> Self {
+        self.visual = visual;
+        self
+    }
+}
+
+/// Top-down vehicle for 2D physics. It is the same as [`crate::scene::vehicle::Vehicle`], but
+/// since there's no vertical axis in a top-down world, there's no suspension either - every wheel
+/// is always in contact with the ground and the load is distributed evenly between the wheels.
+/// The load is defined by the mass of the chassis and [`Self::gravity`].
+///
+/// The vehicle node must be a direct child of a dynamic 2D [`RigidBody`] (the chassis), the
+/// forces of the tires are applied to the chassis every frame. Usually, the chassis should have
+/// zero gravity scale and some linear and angular damping to simulate rolling resistance.
+///
+/// The vehicle is controlled by three inputs: [`Self::set_steering`], [`Self::set_throttle`] and
+/// [`Self::set_brake`]. These are runtime values and they're not serialized.
+#[derive(Visit, Reflect, Clone, Debug)]
+#[reflect(
+    derived_type = "Node",
+    type_uuid = "3d2df741-3bd6-4314-84ef-b9ab20803351"
+)]
+#[visit(optional)]
+pub struct Vehicle {
+    base: Base,
+
+    #[reflect(setter = "set_wheels")]
+    wheels: InheritableVariable<Vec<Wheel>>,
+
+    #[reflect(setter = "set_max_steering_angle", min_value = 0.0, step = 0.01)]
+    max_steering_angle: InheritableVariable<f32>,
+
+    #[reflect(setter = "set_engine_torque", min_value = 0.0, step = 10.0)]
+    engine_torque: InheritableVariable<f32>,
+
+    #[reflect(setter = "set_brake_torque", min_value = 0.0, step = 10.0)]
+    brake_torque: InheritableVariable<f32>,
+
+    #[reflect(setter = "set_gravity", min_value = 0.0, step = 0.1)]
+    gravity: InheritableVariable<f32>,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    steering: f32,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    throttle: f32,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    brake: f32,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    wheel_states: Vec<WheelState>,
+}
+
+impl Default for Vehicle {
+    fn default() -> Self {
+        VehicleBuilder::new(BaseBuilder::new()).build_vehicle()
+    }
+}
+
+impl PartialEq for Vehicle {
+    fn eq(&self, other: &Self) -> bool {
+        self.base == other.base
+            && self.wheels == other.wheels
+            && self.max_steering_angle == other.max_steering_angle
+            && self.engine_torque == other.engine_torque
+            && self.brake_torque == other.brake_torque
+            && self.gravity == other.gravity
+    }
+}
+
+impl Deref for Vehicle {
+    type Target = Base;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl DerefMut for Vehicle {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+impl ConstructorProvider<Node, Graph> for Vehicle {
+    fn constructor() -> NodeConstructor {
+        NodeConstructor::new::<Self>()
+            .with_variant("Vehicle 2D", |_| {
+                VehicleBuilder::new(BaseBuilder::new().with_name("Vehicle 2D"))
+                    .build_node()
+                    .into()
+            })
+            .with_group("Physics 2D")
+    }
+}
+
+impl Vehicle {
+    /// Sets new set of wheels.
+    pub fn set_wheels(&mut self, wheels: Vec<Wheel>) -> Vec<Wheel> {
+        self.wheels.set_value_and_mark_modified(wheels)
+    }
+
+    /// Returns a reference to the wheels of the vehicle.
+    pub fn wheels(&self) -> &[Wheel] {
+        &self.wheels
+    }
+
+    /// Returns a mutable reference to the wheels of the vehicle.
+    pub fn wheels_mut(&mut self) -> &mut Vec<Wheel> {
+        self.wheels.get_value_mut_and_mark_modified()
+    }
+
+    /// Returns runtime states of the wheels. The states are in the same order as the wheels.
+    /// Suspension-related fields are not used by 2D vehicles.
+    pub fn wheel_states(&self) -> &[WheelState] {
+        &self.wheel_states
+    }
+
+    /// Sets maximum steering angle (in radians).
+    pub fn set_max_steering_angle(&mut self, angle: f32) -> f32 {
+        self.max_steering_angle
+            .set_value_and_mark_modified(angle.max(0.0))
+    }
+
+    /// Returns maximum steering angle (in radians).
+    pub fn max_steering_angle(&self) -> f32 {
+        *self.max_steering_angle
+    }
+
+    /// Sets maximum torque of the engine.
+    pub fn set_engine_torque(&mut self, torque: f32) -> f32 {
+        self.engine_torque
+            .set_value_and_mark_modified(torque.max(0.0))
+    }
+
+    /// Returns maximum torque of the engine.
+    pub fn engine_torque(&self) -> f32 {
+        *self.engine_torque
+    }
+
+    /// Sets maximum torque of the brakes.
+    pub fn set_brake_torque(&mut self, torque: f32) -> f32 {
+        self.brake_torque
+            .set_value_and_mark_modified(torque.max(0.0))
+    }
+
+    /// Returns maximum torque of the brakes.
+    pub fn brake_torque(&self) -> f32 {
+        *self.brake_torque
+    }
+
+    /// Sets the gravity, that is used to calculate the load on the wheels. Default is 9.81.
+    pub fn set_gravity(&mut self, gravity: f32) -> f32 {
+        self.gravity.set_value_and_mark_modified(gravity.max(0.0))
+    }
+
+    /// Returns the gravity, that is used to calculate the load on the wheels.
+    pub fn gravity(&self) -> f32 {
+        *self.gravity
+    }
+
+    /// Sets steering input in `[-1; 1]` range. Positive values turn the vehicle to the left.
+    pub fn set_steering(&mut self, steering: f32) {
+        self.steering = steering.clamp(-1.0, 1.0);
+    }
+
+    /// Returns current steering input.
+    pub fn steering(&self) -> f32 {
+        self.steering
+    }
+
+    /// Sets throttle input in `[-1; 1]` range. Negative values make the vehicle move backwards.
+    pub fn set_throttle(&mut self, throttle: f32) {
+        self.throttle = throttle.clamp(-1.0, 1.0);
+    }
+
+    /// Returns current throttle input.
+    pub fn throttle(&self) -> f32 {
+        self.throttle
+    }
+
+    /// Sets brake input in `[0; 1]` range.
+    pub fn set_brake(&mut self, brake: f32) {
+        self.brake = brake.clamp(0.0, 1.0);
+    }
+
+    /// Returns current brake input.
+    pub fn brake(&self) -> f32 {
+        self.brake
+    }
+}
+
+fn rotate(v: Vector2<f32>, angle: f32) -> Vector2<f32> {
+    let (sin, cos) = angle.sin_cos();
+    Vector2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
+
+impl NodeTrait for Vehicle {
+    fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.base.local_bounding_box()
+    }
+
+    fn world_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.base.world_bounding_box()
+    }
+
+    fn id(&self) -> Uuid {
+        <Self as Reflect>::type_info().type_uuid
+    }
+
+    fn update(&mut self, context: &mut UpdateContext) {
+        let dt = context.dt;
+        if dt <= 0.0 || !self.is_globally_enabled() {
+            return;
+        }
+
+        let chassis_handle = self.parent().to_variant::<RigidBody>();
+        let Ok(chassis) = context.nodes.try_get(chassis_handle) else {
+            return;
+        };
+        let lin_vel = chassis.lin_vel();
+        let ang_vel = chassis.ang_vel();
+        let center = chassis.global_position().xy();
+        let Some(mass) = context.physics2d.rigid_body_mass(chassis) else {
+            return;
+        };
+        let mass_share = mass / self.wheels.len().max(1) as f32;
+        let load = mass_share * *self.gravity;
+
+        self.wheel_states
+            .resize(self.wheels.len(), Default::default());
+
+        let transform = self.global_transform();
+        let mut forces = Vec::with_capacity(self.wheels.len());
+        for (wheel, state) in self.wheels.iter().zip(self.wheel_states.iter_mut()) {
+            state.steering_angle = self.steering * *self.max_steering_angle * wheel.steering_factor;
+
+            let position = transform
+                .transform_point(&Point3::new(wheel.position.x, wheel.position.y, 0.0))
+                .coords
+                .xy();
+            let forward = transform
+                .transform_vector(&rotate(Vector2::y(), state.steering_angle).push(0.0))
+                .xy()
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(Vector2::y);
+            let side = Vector2::new(forward.y, -forward.x);
+
+            let r = position - center;
+            let velocity = lin_vel + Vector2::new(-r.y, r.x).scale(ang_vel);
+
+            let (longitudinal, lateral) = wheel.tire.update(
+                state,
+                &TireInput {
+                    longitudinal_velocity: velocity.dot(&forward),
+                    lateral_velocity: velocity.dot(&side),
+                    load,
+                    drive_torque: self.throttle * *self.engine_torque * wheel.drive_factor,
+                    brake_torque: self.brake * *self.brake_torque * wheel.brake_factor,
+                    mass_share,
+                    dt,
+                },
+            );
+
+            state.in_contact = load > 0.0;
+            state.contact_point = position.push(0.0);
+            state.contact_normal = Vector3::z();
+            state.suspension_force = load;
+
+            forces.push((forward.scale(longitudinal) + side.scale(lateral), position));
+        }
+
+        if let Ok(chassis) = context.nodes.try_get_mut(chassis_handle) {
+            for (force, point) in forces {
+                chassis.apply_force_at_point(force, point);
+            }
+            if self.throttle != 0.0 || self.steering != 0.0 || self.brake != 0.0 {
+                chassis.wake_up();
+            }
+        }
+
+        for (wheel, state) in self.wheels.iter().zip(self.wheel_states.iter()) {
+            let Ok(visual) = context.nodes.try_borrow_mut(wheel.visual) else {
+                continue;
+            };
+            let z = visual.local_transform().position().z;
+            visual
+                .local_transform_mut()
+                .set_position(wheel.position.push(z))
+                .set_rotation(UnitQuaternion::from_axis_angle(
+                    &Vector3::z_axis(),
+                    state.steering_angle,
+                ));
+        }
+    }
+
+    fn debug_draw(&self, ctx: &mut SceneDrawingContext) {
+        let transform = self.global_transform();
+        for (i, wheel) in self.wheels.iter().enumerate() {
+            let steering_angle = self.wheel_states.get(i).map_or(0.0, |s| s.steering_angle);
+            let half_length = wheel.tire.radius;
+            let half_width = wheel.tire.radius * 0.4;
+            let corners = [
+                Vector2::new(-half_width, -half_length),
+                Vector2::new(half_width, -half_length),
+                Vector2::new(half_width, half_length),
+                Vector2::new(-half_width, half_length),
+            ]
+            .map(|corner| {
+                let local = wheel.position + rotate(corner, steering_angle);
+                transform
+                    .transform_point(&Point3::new(local.x, local.y, 0.0))
+                    .coords
+            });
+            for j in 0..corners.len() {
+                ctx.add_line(Line {
+                    begin: corners[j],
+                    end: corners[(j + 1) % corners.len()],
+                    color: Color::RED,
+                });
+            }
+        }
+    }
+}
+
+/// Allows you to create 2D vehicles in declarative manner.
+pub struct VehicleBuilder {
+    base_builder: BaseBuilder,
+    wheels: Vec<Wheel>,
+    max_steering_angle: f32,
+    engine_torque: f32,
+    brake_torque: f32,
+    gravity: f32,
+}
+
+impl VehicleBuilder {
+    /// Creates new vehicle builder.
+    pub fn new(base_builder: BaseBuilder) -> Self {
+        Self {
+            base_builder,
+            wheels: Default::default(),
+            max_steering_angle: 35.0f32.to_radians(),
+            engine_torque: 400.0,
+            brake_torque: 1500.0,
+            gravity: 9.81,
+        }
+    }
+
+    /// Sets desired wheels.
+    pub fn with_wheels(mut self, wheels: Vec<Wheel>) -> Self {
+        self.wheels = wheels;
+        self
+    }
+
+    /// Sets desired maximum steering angle (in radians).
+    pub fn with_max_steering_angle(mut self, angle: f32) -> Self {
+        self.max_steering_angle = angle;
+        self
+    }
+
+    /// Sets desired maximum torque of the engine.
+    pub fn with_engine_torque(mut self, torque: f32) -> Self {
+        self.engine_torque = torque;
+        self
+    }
+
+    /// Sets desired maximum torque of the brakes.
+    pub fn with_brake_torque(mut self, torque: f32) -> Self {
+        self.brake_torque = torque;
+        self
+    }
+
+    /// Sets desired gravity. See [`Vehicle::set_gravity`] for more info.
+    pub fn with_gravity(mut self, gravity: f32) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// Creates vehicle instance.
+    pub fn build_vehicle(self) -> Vehicle {
+        Vehicle {
+            base: self.base_builder.build_base(),
+            wheels: self.wheels.into(),
+            max_steering_angle: self.max_steering_angle.into(),
+            engine_torque: self.engine_torque.into(),
+            brake_torque: self.brake_torque.into(),
+            gravity: self.gravity.into(),
+            steering: 0.0,
+            throttle: 0.0,
+            brake: 0.0,
+            wheel_states: Default::default(),
+        }
+    }
+
+    /// Creates vehicle node.
+    pub fn build_node(self) -> Node {
+        Node::new(self.build_vehicle())
+    }
+
+    /// Creates vehicle node and adds it to the graph.
+    pub fn build(self, graph: &mut Graph) -> Handle<Vehicle> {
+        graph.add_node(self.build_node()).to_variant()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::Vector2,
+        scene::{
+            base::BaseBuilder,
+            dim2::{
+                collider::{ColliderBuilder, ColliderShape},
+                rigidbody::RigidBodyBuilder,
+                vehicle::{VehicleBuilder, Wheel},
+            },
+            graph::Graph,
+        },
+    };
+
+    #[test]
+    fn test_top_down_vehicle_steering() {
+        let mut graph = Graph::new();
+
+        let wheels = [
+            (-0.8, 1.5, 1.0),
+            (0.8, 1.5, 1.0),
+            (-0.8, -1.5, 0.0),
+            (0.8, -1.5, 0.0),
+        ]
+        .into_iter()
+        .map(|(x, y, steering)| {
+            Wheel::default()
+                .with_position(Vector2::new(x, y))
+                .with_steering_factor(steering)
+        })
+        .collect();
+        let vehicle = VehicleBuilder::new(BaseBuilder::new())
+            .with_wheels(wheels)
+            .build(&mut graph);
+        let collider = ColliderBuilder::new(BaseBuilder::new())
+            .with_shape(ColliderShape::cuboid(1.0, 2.0))
+            .with_density(Some(125.0))
+            .build(&mut graph);
+        let chassis =
+            RigidBodyBuilder::new(BaseBuilder::new().with_child(collider).with_child(vehicle))
+                .with_gravity_scale(0.0)
+                .with_can_sleep(false)
+                .build(&mut graph);
+
+        let dt = 1.0 / 60.0;
+        graph[vehicle].set_throttle(1.0);
+        for _ in 0..60 {
+            graph.update(Vector2::new(800.0, 600.0), dt, Default::default());
+        }
+        let lin_vel = graph[chassis].lin_vel();
+        assert!(lin_vel.y > 1.0, "{lin_vel:?}");
+        assert!(lin_vel.x.abs() < 0.1, "{lin_vel:?}");
+
+        // Positive steering turns the vehicle to the left (counter-clockwise).
+        graph[vehicle].set_steering(1.0);
+        for _ in 0..30 {
+            graph.update(Vector2::new(800.0, 600.0), dt, Default::default());
+        }
+        assert!(graph[chassis].ang_vel() > 0.1);
+    }
+}
@@ -62,12 +62,16 @@ use crate::{
         dim2::{self},
         graph::{
             event::{GraphEvent, GraphEventBroadcaster},
-            physics::{PhysicsPerformanceStatistics, PhysicsWorld},
+            physics::{
+                snapshot::{PhysicsSnapshot, PhysicsSnapshotError},
+                PhysicsPerformanceStatistics, PhysicsWorld,
+            },
         },
         mesh::Mesh,
         navmesh,
         node::{container::NodeContainer, Node, SyncContext, UpdateContext},
         pivot::Pivot,
+        rigidbody::RigidBody,
         sound::context::SoundContext,
         transform::TransformBuilder,
     },
@@ -1399,6 +1403,9 @@ impl Graph {
     }
 
     fn sync_native(&mut self, switches: &GraphUpdateSwitches) {
+        // Water volumes register themselves on every sync.
+        self.physics.water_volumes.clear();
+
         let mut sync_context = SyncContext {
             nodes: &self.pool,
             physics: &mut self.physics,
@@ -1450,6 +1457,82 @@ impl Graph {
         }
     }
 
+    fn step_physics(&mut self, dt: f32, switches: &GraphUpdateSwitches) {
+        if switches.physics {
+            self.physics.performance_statistics.reset();
+            self.physics.update(dt, switches.physics_dt);
+            self.performance_statistics.physics = self.physics.performance_statistics.clone();
+        }
+
+        if switches.physics2d {
+            self.physics2d.performance_statistics.reset();
+            self.physics2d.update(dt, switches.physics_dt);
+            self.performance_statistics.physics2d = self.physics2d.performance_statistics.clone();
+        }
+    }
+
+    /// Performs a single physics step (both 3D and 2D) using the given delta time and then writes
+    /// new poses of rigid bodies back to their scene nodes, so the changes are visible right away.
+    /// Unlike [`Self::update`], this method does not update any other nodes. It is used by the
+    /// engine to step physics at a fixed rate, independently of the frame rate. Keep in mind, that
+    /// the engine does this automatically and physics must be disabled in the update switches of
+    /// [`Self::update`] to prevent double stepping.
+    pub fn update_physics(&mut self, dt: f32, switches: &GraphUpdateSwitches) {
+        if switches.paused {
+            return;
+        }
+
+        self.process_node_messages(Some(switches));
+        self.sync_native(switches);
+        self.step_physics(dt, switches);
+
+        self.sync_rigid_body_nodes();
+
+        // Propagate new local transforms of the bodies to global transforms.
+        self.process_node_messages(Some(switches));
+    }
+
+    /// Restores the state of the 3D physics from the given snapshot and moves rigid body nodes to
+    /// their restored positions. See [`PhysicsSnapshot`] docs for more info.
+    pub fn restore_physics_snapshot(
+        &mut self,
+        snapshot: &PhysicsSnapshot,
+    ) -> Result<(), PhysicsSnapshotError> {
+        self.physics.restore_snapshot(snapshot)?;
+        self.sync_rigid_body_nodes();
+        self.process_node_messages(None);
+        Ok(())
+    }
+
+    // Writes poses and velocities of native rigid bodies to their scene nodes.
+    fn sync_rigid_body_nodes(&mut self) {
+        for i in 0..self.pool.get_capacity() {
+            let handle = self.pool.handle_from_index(i);
+            let is_body = self.pool.try_borrow(handle).is_ok_and(|node| {
+                node.is_globally_enabled()
+                    && (node.cast::<RigidBody>().is_some()
+                        || node.cast::<dim2::rigidbody::RigidBody>().is_some())
+            });
+            if !is_body {
+                continue;
+            }
+
+            let Ok((ticket, mut node)) = self.pool.try_take_reserve(handle) else {
+                continue;
+            };
+            let parent_transform = self
+                .pool
+                .try_borrow(node.parent())
+                .map_or_else(|_| Matrix4::identity(), |p| p.global_transform());
+            if let Some(body) = node.cast_mut::<RigidBody>() {
+                self.physics.sync_rigid_body_node(body, parent_transform);
+            } else if let Some(body) = node.cast_mut::<dim2::rigidbody::RigidBody>() {
+                self.physics2d.sync_rigid_body_node(body, parent_transform);
+            }
+            self.pool.put_back(ticket, node);
+        }
+    }
+
     /// Updates nodes in the graph using given delta time.
     ///
     /// # Update Switches
@@ -1472,21 +1555,15 @@ impl Graph {
         self.sync_native(&switches);
         self.performance_statistics.sync_time = instant::Instant::now() - last_time;
 
-        if switches.physics {
-            self.physics.performance_statistics.reset();
-            self.physics.update(dt, switches.physics_dt);
-            self.performance_statistics.physics = self.physics.performance_statistics.clone();
-        }
-
-        if switches.physics2d {
-            self.physics2d.performance_statistics.reset();
-            self.physics2d.update(dt, switches.physics_dt);
-            self.performance_statistics.physics2d = self.physics2d.performance_statistics.clone();
-        }
+        self.step_physics(dt, &switches);
 
         self.performance_statistics.sound_update_time =
             self.sound_context.state().full_render_duration();
 
+        // Sounds will collect their occlusion rays again during the update.
+        self.sound_context.occlusion.debug_rays.clear();
+        self.sound_context.environment.zones.clear();
+
         if let Some(overrides) = switches.node_overrides.as_ref() {
             for handle in overrides {
                 self.update_node(*handle, frame_size, dt, switches.delete_dead_nodes);
@@ -1501,6 +1578,8 @@ impl Graph {
                 );
             }
         }
+
+        self.sound_context.update_environment();
     }
 
     /// Returns capacity of internal pool. Can be used to iterate over all **potentially**
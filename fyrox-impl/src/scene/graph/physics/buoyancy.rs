@@ -0,0 +1,221 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Buoyancy of rigid bodies in water volumes. Water volumes register themselves in the physics
+//! world on every sync and the forces are applied right before every simulation step. See
+//! [`crate::scene::water::WaterVolume`] docs for more info.
+
+use crate::{
+    core::{
+        algebra::{Matrix4, Point3, Vector2, Vector3},
+        math::aabb::AxisAlignedBoundingBox,
+    },
+    scene::{graph::physics::PhysicsWorld, water::WaterSurface},
+};
+use rapier3d::{dynamics::RigidBodyHandle, geometry::Collider, math::Vec3};
+
+/// Amount of sample points along each axis of a collider, the points are used to calculate the
+/// submerged volume of the collider.
+const SAMPLES_PER_AXIS: usize = 4;
+
+/// A snapshot of a water volume, collected during the sync with the physics world.
+#[derive(Clone, Debug)]
+pub(crate) struct WaterVolumeDescriptor {
+    pub(crate) inv_transform: Matrix4<f32>,
+    pub(crate) half_extents: Vector3<f32>,
+    pub(crate) vertical_scale: f32,
+    pub(crate) world_bounds: AxisAlignedBoundingBox,
+    pub(crate) surface: WaterSurface,
+    pub(crate) time: f32,
+    pub(crate) density: f32,
+    pub(crate) linear_drag: f32,
+    pub(crate) angular_drag: f32,
+}
+
+impl WaterVolumeDescriptor {
+    /// Returns the depth of the given point (in world units) under the surface of the water. The
+    /// depth is negative if the point is above the surface. Returns `None` if the point is outside
+    /// the horizontal bounds of the volume or below its bottom.
+    pub(crate) fn depth(&self, point: &Vector3<f32>) -> Option<f32> {
+        let local = self
+            .inv_transform
+            .transform_point(&Point3::from(*point))
+            .coords;
+        if local.x.abs() > self.half_extents.x
+            || local.z.abs() > self.half_extents.z
+            || local.y < -self.half_extents.y
+        {
+            return None;
+        }
+        let surface = self.half_extents.y
+            + self
+                .surface
+                .height(Vector2::new(local.x, local.z), self.time);
+        Some((surface - local.y) * self.vertical_scale)
+    }
+}
+
+/// Buoyancy force and torque, that were added to a rigid body for a single simulation step.
+pub(super) struct BuoyancyForce {
+    body: RigidBodyHandle,
+    force: Vector3<f32>,
+    torque: Vector3<f32>,
+}
+
+// Samples the shape of the collider by a regular grid and keeps the points that are inside the
+// shape. Returns the points (in local coordinates of the collider) and the volume of the shape
+// that is represented by each point.
+fn sample_collider(collider: &Collider) -> (Vec<Vec3>, f32) {
+    let shape = collider.shape();
+    let volume = shape.mass_properties(1.0).mass();
+    if volume <= 0.0 {
+        return (Vec::new(), 0.0);
+    }
+
+    let aabb = shape.compute_local_aabb();
+    let extents = aabb.maxs - aabb.mins;
+    let grid = (0..SAMPLES_PER_AXIS * SAMPLES_PER_AXIS * SAMPLES_PER_AXIS).map(|i| {
+        let cell = |n: usize| (n as f32 + 0.5) / SAMPLES_PER_AXIS as f32;
+        let x = i % SAMPLES_PER_AXIS;
+        let y = (i / SAMPLES_PER_AXIS) % SAMPLES_PER_AXIS;
+        let z = i / (SAMPLES_PER_AXIS * SAMPLES_PER_AXIS);
+        aabb.mins + extents * Vec3::new(cell(x), cell(y), cell(z))
+    });
+
+    let mut points = grid
+        .clone()
+        .filter(|point| shape.contains_local_point(*point))
+        .collect::<Vec<_>>();
+    if points.is_empty() {
+        // Very thin or non-solid shapes (such as triangle meshes) may have no points inside, use
+        // the entire bounding box in this case.
+        points = grid.collect();
+    }
+
+    let sample_volume = volume / points.len() as f32;
+    (points, sample_volume)
+}
+
+impl PhysicsWorld {
+    /// Applies buoyancy force, linear drag and angular damping of every water volume to every
+    /// dynamic rigid body, that is (at least partially) submerged. The forces are integrated by
+    /// the solver the same way as gravity and must be removed after the simulation step using
+    /// [`Self::remove_buoyancy`].
+    pub(super) fn apply_buoyancy(&mut self, dt: f32) -> Vec<BuoyancyForce> {
+        let mut applied = Vec::new();
+        if self.water_volumes.is_empty() {
+            return applied;
+        }
+
+        let gravity = *self.gravity;
+        for (handle, body) in self.bodies.iter_mut() {
+            if !body.is_dynamic() || body.is_sleeping() {
+                continue;
+            }
+
+            let colliders = body
+                .colliders()
+                .iter()
+                .filter_map(|handle| self.colliders.get(*handle))
+                .filter(|collider| !collider.is_sensor())
+                .collect::<Vec<_>>();
+
+            let is_touching_water = colliders.iter().any(|collider| {
+                let aabb = collider.compute_aabb();
+                let aabb = AxisAlignedBoundingBox::from_min_max(aabb.mins.into(), aabb.maxs.into());
+                self.water_volumes
+                    .iter()
+                    .any(|volume| volume.world_bounds.is_intersects_aabb(&aabb))
+            });
+            if !is_touching_water {
+                continue;
+            }
+
+            let center_of_mass: Vector3<f32> = body.center_of_mass().into();
+            let mut force = Vector3::default();
+            let mut torque = Vector3::default();
+            let mut linear_drag = 0.0;
+            let mut angular_drag = 0.0;
+            let mut total_volume = 0.0;
+            let mut submerged_volume = 0.0;
+            for collider in colliders {
+                let (points, sample_volume) = sample_collider(collider);
+                // Every point represents a small cube, it allows to change the displaced volume
+                // smoothly when the point crosses the surface.
+                let sample_size = sample_volume.cbrt();
+                total_volume += sample_volume * points.len() as f32;
+                for point in points {
+                    let point: Vector3<f32> = collider.position().transform_point(point).into();
+                    for volume in self.water_volumes.iter() {
+                        let Some(depth) = volume.depth(&point) else {
+                            continue;
+                        };
+                        let fraction = (depth / sample_size + 0.5).clamp(0.0, 1.0);
+                        if fraction > 0.0 {
+                            let displaced_volume = sample_volume * fraction;
+                            let buoyancy = -gravity.scale(volume.density * displaced_volume);
+                            force += buoyancy;
+                            torque += (point - center_of_mass).cross(&buoyancy);
+                            linear_drag += volume.linear_drag * displaced_volume;
+                            angular_drag += volume.angular_drag * displaced_volume;
+                            submerged_volume += displaced_volume;
+                        }
+                        // Overlapping volumes must not displace the same water twice.
+                        break;
+                    }
+                }
+            }
+
+            if submerged_volume <= 0.0 || total_volume <= 0.0 {
+                continue;
+            }
+
+            body.add_force(force.into(), true);
+            body.add_torque(torque.into(), true);
+            applied.push(BuoyancyForce {
+                body: handle,
+                force,
+                torque,
+            });
+
+            // Drag is proportional to the submerged part of the body.
+            let linear_damping = 1.0 / (1.0 + dt * linear_drag / total_volume);
+            let angular_damping = 1.0 / (1.0 + dt * angular_drag / total_volume);
+            body.set_linvel(body.linvel() * linear_damping, true);
+            body.set_angvel(body.angvel() * angular_damping, true);
+        }
+        applied
+    }
+
+    /// Removes the forces added by [`Self::apply_buoyancy`], other forces of the bodies remain
+    /// untouched.
+    pub(super) fn remove_buoyancy(&mut self, applied: Vec<BuoyancyForce>) {
+        for BuoyancyForce {
+            body,
+            force,
+            torque,
+        } in applied
+        {
+            if let Some(body) = self.bodies.get_mut(body) {
+                body.add_force((-force).into(), false);
+                body.add_torque((-torque).into(), false);
+            }
+        }
+    }
+}
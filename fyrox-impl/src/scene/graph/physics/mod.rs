@@ -20,7 +20,9 @@
 
 //! Scene physics module.
 
+pub(crate) mod buoyancy;
 pub mod character;
+pub mod snapshot;
 
 use crate::{
     core::{
@@ -973,6 +975,10 @@ pub struct PhysicsWorld {
     #[visit(skip)]
     #[reflect(hidden)]
     debug_render_pipeline: Mutex<DebugRenderPipeline>,
+    // Water volumes collected during the sync with the scene graph.
+    #[visit(skip)]
+    #[reflect(hidden)]
+    pub(crate) water_volumes: Vec<buoyancy::WaterVolumeDescriptor>,
 }
 
 impl Clone for PhysicsWorld {
@@ -1134,6 +1140,7 @@ impl PhysicsWorld {
             event_handler: Box::new(()),
             performance_statistics: Default::default(),
             debug_render_pipeline: Default::default(),
+            water_volumes: Default::default(),
         }
     }
 
@@ -1185,6 +1192,12 @@ impl PhysicsWorld {
                 friction_model: FrictionModel::default(),
             };
 
+            let buoyancy = if dt > 0.0 {
+                self.apply_buoyancy(dt)
+            } else {
+                Vec::new()
+            };
+
             if let Err(message) = std::panic::catch_unwind(AssertUnwindSafe(|| {
                 self.pipeline.step(
                     (*self.gravity).into(),
@@ -1207,6 +1220,8 @@ impl PhysicsWorld {
                     err!("A critical error occurred during physics step. The reason is unknown.");
                 }
             }
+
+            self.remove_buoyancy(buoyancy);
         }
 
         self.performance_statistics.step_time += instant::Instant::now() - time;
@@ -1436,6 +1451,14 @@ impl PhysicsWorld {
         }
     }
 
+    // Returns total mass of the rigid body including the mass of its colliders, it is available
+    // only after the body was added to the physics world.
+    pub(crate) fn rigid_body_mass(&self, rigid_body: &scene::rigidbody::RigidBody) -> Option<f32> {
+        self.bodies
+            .get(rigid_body.native.get())
+            .map(|native| native.mass())
+    }
+
     pub(crate) fn sync_rigid_body_node(
         &mut self,
         rigid_body: &mut scene::rigidbody::RigidBody,
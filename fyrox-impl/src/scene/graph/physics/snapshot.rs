@@ -0,0 +1,430 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Snapshots of the physics world, that could be used for rollback networking and deterministic
+//! re-simulation. See [`PhysicsSnapshot`] and [`PhysicsRecording`] docs for more info.
+
+use crate::scene::graph::{physics::PhysicsWorld, Graph, GraphUpdateSwitches};
+use rapier3d::{
+    dynamics::{CCDSolver, ImpulseJointSet, IslandManager, MultibodyJointSet, RigidBodySet},
+    geometry::{ColliderSet, DefaultBroadPhase, NarrowPhase},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    error::Error,
+    fmt::{Debug, Display, Formatter},
+};
+
+/// An error that may occur when restoring or (de)serializing a physics snapshot.
+#[derive(Debug)]
+pub enum PhysicsSnapshotError {
+    /// A snapshot or a recording could not be (de)serialized.
+    Serialization(bincode::Error),
+    /// A set of rigid bodies, colliders or joints of the snapshot does not match the set of the
+    /// physics world. It happens when physical entities were added or removed after the snapshot
+    /// was taken.
+    StructureMismatch,
+}
+
+impl Display for PhysicsSnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Serialization(err) => write!(f, "Unable to serialize a physics snapshot: {err}"),
+            Self::StructureMismatch => write!(
+                f,
+                "Physics snapshot does not match the physics world. \
+                Rigid bodies, colliders or joints were added or removed."
+            ),
+        }
+    }
+}
+
+impl Error for PhysicsSnapshotError {}
+
+impl From<bincode::Error> for PhysicsSnapshotError {
+    fn from(value: bincode::Error) -> Self {
+        Self::Serialization(value)
+    }
+}
+
+/// A full copy of the simulation state of a [`PhysicsWorld`]: rigid bodies, colliders, joints,
+/// contacts, islands and the data of the broad phase. Restoring a snapshot brings the simulation
+/// back to the exact state it had when the snapshot was taken, so the next steps of the simulation
+/// will produce exactly the same results (as long as the same inputs are applied).
+///
+/// A snapshot does not contain the scene nodes, it only contains the native (internal) state of
+/// the physics engine. This means that the snapshot can only be restored in the same physics world
+/// (or its exact copy, for example the same scene loaded on another machine) and only if no rigid
+/// bodies, colliders or joints were added or removed after it was taken. Properties of the world
+/// itself (gravity, integration parameters, etc.) are part of the scene and are not stored.
+///
+/// Snapshots are relatively cheap to take, so a game can keep snapshots of a few last frames for
+/// rollback networking. A snapshot could also be converted into bytes, to send it over the network
+/// or to save it to a file to reproduce a bug.
+///
+/// ## Example
+///
+/// ```rust
+/// # use fyrox_impl::scene::graph::{physics::snapshot::PhysicsSnapshot, Graph};
+/// fn rollback(graph: &mut Graph, snapshot: &PhysicsSnapshot) {
+///     // Restore the state of the physics and re-simulate a few steps with corrected inputs.
+///     graph.restore_physics_snapshot(snapshot).unwrap();
+///     for _ in 0..3 {
+///         // Apply the inputs here.
+///         graph.update_physics(1.0 / 60.0, &Default::default());
+///     }
+/// }
+///
+/// fn save(graph: &Graph) -> Vec<u8> {
+///     graph.physics.take_snapshot().to_bytes().unwrap()
+/// }
+/// ```
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PhysicsSnapshot {
+    bodies: RigidBodySet,
+    colliders: ColliderSet,
+    joints: ImpulseJointSet,
+    multibody_joints: MultibodyJointSet,
+    islands: IslandManager,
+    broad_phase: DefaultBroadPhase,
+    narrow_phase: NarrowPhase,
+    ccd_solver: CCDSolver,
+}
+
+impl Debug for PhysicsSnapshot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PhysicsSnapshot")
+            .field("bodies", &self.bodies.len())
+            .field("colliders", &self.colliders.len())
+            .field("joints", &self.joints.len())
+            .finish()
+    }
+}
+
+impl PhysicsSnapshot {
+    /// Serializes the snapshot into a compact binary form.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, PhysicsSnapshotError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Deserializes a snapshot, previously serialized by [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PhysicsSnapshotError> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+
+    fn matches(&self, world: &PhysicsWorld) -> bool {
+        self.bodies
+            .iter()
+            .map(|(handle, _)| handle)
+            .eq(world.bodies.iter().map(|(handle, _)| handle))
+            && self
+                .colliders
+                .iter()
+                .map(|(handle, _)| handle)
+                .eq(world.colliders.iter().map(|(handle, _)| handle))
+            && self.joints.iter().map(|(handle, _)| handle).eq(world
+                .joints
+                .set
+                .iter()
+                .map(|(handle, _)| handle))
+            && self
+                .multibody_joints
+                .iter()
+                .map(|(handle, ..)| handle)
+                .eq(world.multibody_joints.set.iter().map(|(handle, ..)| handle))
+    }
+}
+
+impl PhysicsWorld {
+    /// Takes a snapshot of the current state of the simulation. See [`PhysicsSnapshot`] docs for
+    /// more info.
+    pub fn take_snapshot(&self) -> PhysicsSnapshot {
+        PhysicsSnapshot {
+            bodies: self.bodies.clone(),
+            colliders: self.colliders.clone(),
+            joints: self.joints.set.clone(),
+            multibody_joints: self.multibody_joints.set.clone(),
+            islands: self.islands.clone(),
+            broad_phase: self.broad_phase.clone(),
+            narrow_phase: self.narrow_phase.clone(),
+            ccd_solver: self.ccd_solver.clone(),
+        }
+    }
+
+    /// Restores the state of the simulation from the given snapshot. Keep in mind, that this
+    /// method does not change the scene nodes, use [`Graph::restore_physics_snapshot`] to restore
+    /// the snapshot and to move rigid body nodes to their restored positions.
+    pub fn restore_snapshot(
+        &mut self,
+        snapshot: &PhysicsSnapshot,
+    ) -> Result<(), PhysicsSnapshotError> {
+        if !snapshot.matches(self) {
+            return Err(PhysicsSnapshotError::StructureMismatch);
+        }
+
+        self.bodies.clone_from(&snapshot.bodies);
+        self.colliders.clone_from(&snapshot.colliders);
+        self.joints.set.clone_from(&snapshot.joints);
+        self.multibody_joints
+            .set
+            .clone_from(&snapshot.multibody_joints);
+        self.islands.clone_from(&snapshot.islands);
+        self.broad_phase.clone_from(&snapshot.broad_phase);
+        self.narrow_phase.clone_from(&snapshot.narrow_phase);
+        self.ccd_solver.clone_from(&snapshot.ccd_solver);
+
+        Ok(())
+    }
+}
+
+/// A single recorded step of the simulation.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedStep<I> {
+    /// Time step of the simulation.
+    pub dt: f32,
+    /// User-defined inputs, that were applied right before the step.
+    pub inputs: I,
+}
+
+/// A recording of the simulation, that consists of an initial snapshot and a set of inputs for
+/// every step after it. The inputs are defined by the game (for example, forces applied to the
+/// bodies or actions of the players), the recording only stores them. A recording could be saved
+/// to a file and replayed later to reproduce a bug.
+///
+/// The physics engine is deterministic, so the replay will produce bit-exact results as long as the
+/// game applies the inputs the same way and the replay runs on the same platform and build.
+///
+/// ## Example
+///
+/// ```rust
+/// # use fyrox_impl::{
+/// #     core::{algebra::Vector3, pool::Handle},
+/// #     graph::SceneGraph,
+/// #     scene::{
+/// #         graph::{physics::snapshot::PhysicsRecording, Graph},
+/// #         rigidbody::RigidBody,
+/// #     },
+/// # };
+/// fn push(graph: &mut Graph, body: Handle<RigidBody>, force: &Vector3<f32>) {
+///     if let Ok(body) = graph.try_get_mut(body) {
+///         body.apply_force(*force);
+///     }
+/// }
+///
+/// fn record(graph: &mut Graph, body: Handle<RigidBody>) -> PhysicsRecording<Vector3<f32>> {
+///     let mut recording = PhysicsRecording::new(graph.physics.take_snapshot());
+///     for i in 0..60 {
+///         let force = Vector3::new(i as f32, 0.0, 0.0);
+///         push(graph, body, &force);
+///         graph.update_physics(1.0 / 60.0, &Default::default());
+///         recording.record(1.0 / 60.0, force);
+///     }
+///     recording
+/// }
+///
+/// fn replay(graph: &mut Graph, body: Handle<RigidBody>, recording: &PhysicsRecording<Vector3<f32>>) {
+///     recording
+///         .replay(graph, |graph, force| push(graph, body, force))
+///         .unwrap();
+/// }
+/// ```
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PhysicsRecording<I> {
+    initial_snapshot: PhysicsSnapshot,
+    steps: Vec<RecordedStep<I>>,
+}
+
+impl<I> PhysicsRecording<I> {
+    /// Creates a new recording, that starts from the given snapshot.
+    pub fn new(initial_snapshot: PhysicsSnapshot) -> Self {
+        Self {
+            initial_snapshot,
+            steps: Default::default(),
+        }
+    }
+
+    /// Adds a new step to the recording.
+    pub fn record(&mut self, dt: f32, inputs: I) {
+        self.steps.push(RecordedStep { dt, inputs });
+    }
+
+    /// Returns the snapshot from which the recording starts.
+    pub fn initial_snapshot(&self) -> &PhysicsSnapshot {
+        &self.initial_snapshot
+    }
+
+    /// Returns recorded steps.
+    pub fn steps(&self) -> &[RecordedStep<I>] {
+        &self.steps
+    }
+
+    /// Removes every step after the given amount of steps. It could be used to discard
+    /// mispredicted steps in rollback networking.
+    pub fn truncate(&mut self, len: usize) {
+        self.steps.truncate(len);
+    }
+
+    /// Restores the initial snapshot and re-simulates every recorded step. The given closure is
+    /// called right before each step and it must apply the recorded inputs the same way the game
+    /// did it during the recording. Keep in mind, that 2D physics is not part of the snapshot.
+    pub fn replay<F>(&self, graph: &mut Graph, mut apply: F) -> Result<(), PhysicsSnapshotError>
+    where
+        F: FnMut(&mut Graph, &I),
+    {
+        graph.restore_physics_snapshot(&self.initial_snapshot)?;
+        let switches = GraphUpdateSwitches::default();
+        for step in self.steps.iter() {
+            apply(graph, &step.inputs);
+            graph.update_physics(step.dt, &switches);
+        }
+        Ok(())
+    }
+}
+
+impl<I> PhysicsRecording<I>
+where
+    I: Serialize + DeserializeOwned,
+{
+    /// Serializes the recording into a compact binary form.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, PhysicsSnapshotError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Deserializes a recording, previously serialized by [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PhysicsSnapshotError> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            algebra::{Vector2, Vector3},
+            pool::Handle,
+        },
+        scene::{
+            base::BaseBuilder,
+            collider::{ColliderBuilder, ColliderShape},
+            graph::{
+                physics::snapshot::{PhysicsRecording, PhysicsSnapshot, PhysicsSnapshotError},
+                Graph,
+            },
+            rigidbody::{RigidBody, RigidBodyBuilder, RigidBodyType},
+            transform::TransformBuilder,
+        },
+    };
+
+    fn add_body(
+        graph: &mut Graph,
+        position: Vector3<f32>,
+        body_type: RigidBodyType,
+    ) -> Handle<RigidBody> {
+        let collider = ColliderBuilder::new(BaseBuilder::new())
+            .with_shape(ColliderShape::cuboid(0.5, 0.5, 0.5))
+            .build(graph);
+        RigidBodyBuilder::new(
+            BaseBuilder::new()
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(position)
+                        .build(),
+                )
+                .with_child(collider),
+        )
+        .with_body_type(body_type)
+        .build(graph)
+    }
+
+    fn push(graph: &mut Graph, body: Handle<RigidBody>, force: f32) {
+        graph[body].apply_force(Vector3::new(force, 0.0, 0.0));
+    }
+
+    fn poses(graph: &Graph, bodies: &[Handle<RigidBody>]) -> Vec<(Vector3<f32>, Vector3<f32>)> {
+        bodies
+            .iter()
+            .map(|body| (graph[*body].global_position(), graph[*body].lin_vel()))
+            .collect()
+    }
+
+    #[test]
+    fn test_physics_snapshot_replay() {
+        let mut graph = Graph::new();
+        add_body(
+            &mut graph,
+            Vector3::new(0.0, -0.5, 0.0),
+            RigidBodyType::Static,
+        );
+        let bodies = (0..3)
+            .map(|i| {
+                add_body(
+                    &mut graph,
+                    Vector3::new(0.1 * i as f32, 0.6 + 1.1 * i as f32, 0.0),
+                    RigidBodyType::Dynamic,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        graph.update(Vector2::new(800.0, 600.0), 1.0 / 60.0, Default::default());
+        for _ in 0..30 {
+            graph.update_physics(1.0 / 60.0, &Default::default());
+        }
+
+        // Make sure that the snapshot survives serialization.
+        let snapshot = graph.physics.take_snapshot();
+        let snapshot = PhysicsSnapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap();
+        let initial_poses = poses(&graph, &bodies);
+
+        let mut recording = PhysicsRecording::new(snapshot);
+        for i in 0..90 {
+            let force = if i < 30 { 20.0 } else { -5.0 };
+            push(&mut graph, bodies[2], force);
+            graph.update_physics(1.0 / 60.0, &Default::default());
+            recording.record(1.0 / 60.0, force);
+        }
+        let recorded_poses = poses(&graph, &bodies);
+        assert_ne!(initial_poses, recorded_poses);
+
+        let recording =
+            PhysicsRecording::<f32>::from_bytes(&recording.to_bytes().unwrap()).unwrap();
+
+        graph
+            .restore_physics_snapshot(recording.initial_snapshot())
+            .unwrap();
+        assert_eq!(poses(&graph, &bodies), initial_poses);
+
+        // The replay must produce exactly the same results.
+        recording
+            .replay(&mut graph, |graph, force| push(graph, bodies[2], *force))
+            .unwrap();
+        assert_eq!(poses(&graph, &bodies), recorded_poses);
+
+        // The snapshot cannot be restored, if a body was added after it was taken.
+        add_body(
+            &mut graph,
+            Vector3::new(5.0, 0.5, 0.0),
+            RigidBodyType::Dynamic,
+        );
+        graph.update_physics(1.0 / 60.0, &Default::default());
+        assert!(matches!(
+            graph.restore_physics_snapshot(recording.initial_snapshot()),
+            Err(PhysicsSnapshotError::StructureMismatch)
+        ));
+    }
+}
@@ -248,6 +248,11 @@ impl ResourceData for SurfaceData {
     fn try_clone_box(&self) -> Option<Box<dyn ResourceData>> {
         Some(Box::new(self.clone()))
     }
+
+    fn memory_usage(&self) -> usize {
+        self.vertex_buffer.vertex_count() as usize * self.vertex_buffer.vertex_size() as usize
+            + size_of_val(self.geometry_buffer.triangles_ref())
+    }
 }
 
 impl SurfaceData {
@@ -506,6 +511,49 @@ impl SurfaceData {
         data
     }
 
+    /// Creates a unit quad at oXY plane, subdivided into a grid with the given amount of columns
+    /// and rows, with given transform. Top row of vertices goes first, vertices in a row are
+    /// ordered from left (-X) to right (+X). Could be useful for cloth (flags, curtains, etc).
+    pub fn make_xy_grid(columns: usize, rows: usize, transform: &Matrix4<f32>) -> Self {
+        let columns = columns.max(1);
+        let rows = rows.max(1);
+
+        let mut vertices = Vec::with_capacity((columns + 1) * (rows + 1));
+        for j in 0..=rows {
+            let v = j as f32 / rows as f32;
+            for i in 0..=columns {
+                let u = i as f32 / columns as f32;
+                vertices.push(StaticVertex {
+                    position: Vector3::new(u - 0.5, 0.5 - v, 0.0),
+                    normal: -Vector3::z(),
+                    tex_coord: Vector2::new(1.0 - u, 1.0 - v),
+                    tangent: Vector4::default(),
+                });
+            }
+        }
+
+        let index = |i: usize, j: usize| (j * (columns + 1) + i) as u32;
+        let mut triangles = Vec::with_capacity(columns * rows * 2);
+        for j in 0..rows {
+            for i in 0..columns {
+                let top_left = index(i, j);
+                let top_right = index(i + 1, j);
+                let bottom_right = index(i + 1, j + 1);
+                let bottom_left = index(i, j + 1);
+                triangles.push(TriangleDefinition([top_left, top_right, bottom_right]));
+                triangles.push(TriangleDefinition([top_left, bottom_right, bottom_left]));
+            }
+        }
+
+        let mut data = Self::new(
+            VertexBuffer::new(vertices.len(), vertices).unwrap(),
+            TriangleBuffer::new(triangles),
+        );
+        data.calculate_tangents().unwrap();
+        data.transform_geometry(transform).unwrap();
+        data
+    }
+
     /// Calculates per-face normals. This method is fast, but have very poor quality, and surface will look facet.
     pub fn calculate_normals(&mut self) -> Result<(), VertexFetchError> {
         let mut vertex_buffer_mut = self.vertex_buffer.modify();
@@ -546,6 +594,43 @@ impl SurfaceData {
         Ok(())
     }
 
+    /// Calculates per-vertex normals by averaging normals of every triangle that shares a vertex.
+    /// Each triangle normal is weighted by the area of the triangle. It is slower than
+    /// [`Self::calculate_normals`], but gives smooth shading for meshes with shared vertices.
+    pub fn calculate_smooth_normals(&mut self) -> Result<(), VertexFetchError> {
+        let mut normals = vec![Vector3::<f32>::zeros(); self.vertex_buffer.vertex_count() as usize];
+
+        for triangle in self.geometry_buffer.iter() {
+            let mut positions = [Vector3::default(); 3];
+            for (position, &index) in positions.iter_mut().zip(triangle.indices()) {
+                *position = self
+                    .vertex_buffer
+                    .get(index as usize)
+                    .unwrap()
+                    .read_3_f32(VertexAttributeUsage::Position)?;
+            }
+
+            // Not normalized on purpose - the length of the cross product is proportional to the
+            // area of the triangle.
+            let normal = (positions[1] - positions[0]).cross(&(positions[2] - positions[0]));
+            for &index in triangle.indices() {
+                normals[index as usize] += normal;
+            }
+        }
+
+        let mut vertex_buffer_mut = self.vertex_buffer.modify();
+        for (mut vertex, normal) in vertex_buffer_mut.iter_mut().zip(normals) {
+            vertex.write_3_f32(
+                VertexAttributeUsage::Normal,
+                normal
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(Vector3::y),
+            )?;
+        }
+
+        Ok(())
+    }
+
     /// Creates sphere of specified radius with given slices and stacks. The larger the `slices` and `stacks`, the smoother the sphere will be.
     /// Typical values are [16..32]. The sphere is then transformed by the given transformation matrix, which could be [`Matrix4::identity`]
     /// to not modify the sphere at all.
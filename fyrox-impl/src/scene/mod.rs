@@ -28,6 +28,7 @@ pub mod accel;
 pub mod animation;
 pub mod base;
 pub mod camera;
+pub mod cloth;
 pub mod collider;
 pub mod debug;
 pub mod decal;
@@ -42,6 +43,7 @@ pub mod particle_system;
 pub mod pivot;
 pub mod probe;
 pub mod ragdoll;
+pub mod replication;
 pub mod rigidbody;
 pub mod skybox;
 pub mod sound;
@@ -49,6 +51,8 @@ pub mod sprite;
 pub mod terrain;
 pub mod tilemap;
 pub mod transform;
+pub mod vehicle;
+pub mod water;
 
 use crate::{
     asset::{self, io::ResourceIo, manager::ResourceManager, untyped::UntypedResource},
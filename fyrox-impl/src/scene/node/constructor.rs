@@ -26,6 +26,7 @@ use crate::{
         self,
         animation::{absm::AnimationBlendingStateMachine, AnimationPlayer},
         camera::Camera,
+        cloth::Cloth,
         decal::Decal,
         dim2::{self, rectangle::Rectangle},
         graph::Graph,
@@ -37,10 +38,11 @@ use crate::{
         pivot::Pivot,
         probe::ReflectionProbe,
         ragdoll::Ragdoll,
-        sound::{listener::Listener, Sound},
+        sound::{listener::Listener, reverb_zone::ReverbZone, Sound},
         sprite::Sprite,
         terrain::Terrain,
         tilemap::TileMap,
+        water::WaterVolume,
     },
 };
 
@@ -58,6 +60,7 @@ pub fn new_node_constructor_container() -> NodeConstructorContainer {
     container.add::<dim2::joint::Joint>();
     container.add::<Rectangle>();
     container.add::<dim2::rigidbody::RigidBody>();
+    container.add::<dim2::vehicle::Vehicle>();
     container.add::<DirectionalLight>();
     container.add::<PointLight>();
     container.add::<SpotLight>();
@@ -65,18 +68,22 @@ pub fn new_node_constructor_container() -> NodeConstructorContainer {
     container.add::<ParticleSystem>();
     container.add::<Sound>();
     container.add::<Listener>();
+    container.add::<ReverbZone>();
     container.add::<Camera>();
     container.add::<scene::collider::Collider>();
     container.add::<Decal>();
     container.add::<scene::joint::Joint>();
     container.add::<Pivot>();
     container.add::<scene::rigidbody::RigidBody>();
+    container.add::<scene::vehicle::Vehicle>();
+    container.add::<WaterVolume>();
     container.add::<Sprite>();
     container.add::<Terrain>();
     container.add::<AnimationPlayer>();
     container.add::<AnimationBlendingStateMachine>();
     container.add::<NavigationalMesh>();
     container.add::<Ragdoll>();
+    container.add::<Cloth>();
     container.add::<TileMap>();
     container.add::<ReflectionProbe>();
 
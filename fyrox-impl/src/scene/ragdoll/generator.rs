@@ -0,0 +1,786 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Automatic generation of ragdolls from skeletons of skinned meshes. See [`RagdollGenerator`]
+//! docs for more info.
+
+use crate::{
+    core::{
+        algebra::{Rotation3, UnitQuaternion, Vector3},
+        math::aabb::AxisAlignedBoundingBox,
+        pool::Handle,
+    },
+    graph::SceneGraph,
+    scene::{
+        base::BaseBuilder,
+        collider::{ColliderBuilder, ColliderShape, InteractionGroups},
+        graph::Graph,
+        joint::{BallJoint, JointBuilder, JointParams},
+        mesh::Mesh,
+        node::Node,
+        ragdoll::{rotation_from_transform, Limb, Ragdoll, RagdollBuilder},
+        rigidbody::{RigidBody, RigidBodyBuilder, RigidBodyType},
+        transform::TransformBuilder,
+    },
+};
+use fxhash::FxHashMap;
+use std::{f32::consts::PI, ops::Range};
+
+/// Shape of a limb in world coordinates.
+enum LimbShape {
+    Capsule {
+        begin: Vector3<f32>,
+        end: Vector3<f32>,
+        radius: f32,
+    },
+    Ball {
+        center: Vector3<f32>,
+        radius: f32,
+    },
+}
+
+impl LimbShape {
+    fn volume(&self) -> f32 {
+        match self {
+            LimbShape::Capsule { begin, end, radius } => {
+                PI * radius * radius * ((end - begin).norm() + 4.0 / 3.0 * radius)
+            }
+            LimbShape::Ball { radius, .. } => 4.0 / 3.0 * PI * radius.powi(3),
+        }
+    }
+}
+
+/// Angular limits (in radians) of a joint, that connects a limb with its parent limb. The limits
+/// are defined in the frame of the joint: X axis is directed along the bone, Y axis is the bend axis
+/// (the axis of the bone, that is the most perpendicular to the bone direction) and Z axis is
+/// perpendicular to both of them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JointLimits {
+    /// Limits of the rotation around the bone (X axis of the joint).
+    pub twist: Range<f32>,
+    /// Limits of the rotation around the bend axis (Y axis of the joint).
+    pub bend: Range<f32>,
+    /// Limits of the rotation around the remaining axis (Z axis of the joint).
+    pub swing: Range<f32>,
+}
+
+/// A limb that will be created by the generator.
+struct LimbPlan {
+    bone: Handle<Node>,
+    position: Vector3<f32>,
+    rotation: UnitQuaternion<f32>,
+    /// Normalized direction from the bone to the end of the limb in world coordinates.
+    direction: Option<Vector3<f32>>,
+    shape: LimbShape,
+    children: Vec<LimbPlan>,
+}
+
+impl LimbPlan {
+    /// Returns rotation of the joint frame (see [`JointLimits`]) in world coordinates.
+    fn joint_rotation(&self) -> UnitQuaternion<f32> {
+        let Some(twist) = self.direction else {
+            return self.rotation;
+        };
+        let bend = [Vector3::x(), Vector3::z(), Vector3::y()]
+            .map(|axis| self.rotation * axis)
+            .into_iter()
+            .min_by(|a, b| a.dot(&twist).abs().total_cmp(&b.dot(&twist).abs()))
+            .unwrap();
+        let Some(bend) = (bend - twist.scale(bend.dot(&twist))).try_normalize(f32::EPSILON) else {
+            return self.rotation;
+        };
+        let swing = twist.cross(&bend);
+        UnitQuaternion::from_rotation_matrix(&Rotation3::from_basis_unchecked(&[
+            twist, bend, swing,
+        ]))
+    }
+}
+
+struct Skeleton {
+    positions: FxHashMap<Handle<Node>, Vector3<f32>>,
+    children: FxHashMap<Handle<Node>, Vec<Handle<Node>>>,
+    parents: FxHashMap<Handle<Node>, Handle<Node>>,
+}
+
+impl Skeleton {
+    fn count_descendants(&self, bone: Handle<Node>) -> usize {
+        self.children.get(&bone).map_or(0, |children| {
+            children
+                .iter()
+                .map(|child| 1 + self.count_descendants(*child))
+                .sum()
+        })
+    }
+}
+
+/// Ragdoll generator creates a [`Ragdoll`] using the bone hierarchy of skinned meshes. Every
+/// significant bone becomes a limb with a rigid body and a capsule collider, that spans from the
+/// bone to its children bones (bones with multiple children, such as hips, get a ball collider).
+/// Bones, that are too short (for example, fingers or "end" bones) are merged with their parents.
+/// Neighbouring limbs are connected with ball joints with limited angles.
+///
+/// Joint limits depend on the role of the limb. The rotation around the bone (twist) is limited
+/// by [`RagdollGenerator::with_twist_limit`]. Limbs in the middle of a chain (a limb with a single
+/// child, whose parent limb also has a single child, such as forearms or shins) get hinge-like
+/// joints, that bend around one axis (see [`RagdollGenerator::with_hinge_limit`]) and barely
+/// swing around the other. The rest of the joints swing freely around both axes within
+/// [`RagdollGenerator::with_joint_limit`]. The heuristic can't tell shoulders from elbows in every
+/// skeleton, so the limits could be overridden for specific bones using
+/// [`RagdollGenerator::with_joint_limits`].
+///
+/// The total mass of the ragdoll is distributed across the limbs proportionally to the volumes of
+/// their colliders. The bodies of the generated ragdoll are kinematic until the ragdoll is
+/// activated, see [`Ragdoll`] docs for more info.
+///
+/// ## Example
+///
+/// ```rust
+/// # use fyrox_impl::{
+/// #     core::pool::Handle,
+/// #     graph::SceneGraph,
+/// #     scene::{
+/// #         graph::Graph,
+/// #         mesh::Mesh,
+/// #         ragdoll::{generator::RagdollGenerator, LimbMode, Ragdoll},
+/// #     },
+/// # };
+/// fn create_ragdoll(character_mesh: Handle<Mesh>, graph: &mut Graph) -> Handle<Ragdoll> {
+///     let ragdoll = RagdollGenerator::new()
+///         .with_total_mass(80.0)
+///         .with_blend_time(0.5)
+///         .generate(&[character_mesh], graph)
+///         .unwrap_or_default();
+///
+///     // Make every limb of the ragdoll follow the animation, while still being physical.
+///     if let Ok(ragdoll) = graph.try_get_mut(ragdoll) {
+///         ragdoll
+///             .root_limb
+///             .get_value_mut_and_mark_modified()
+///             .iterate_recursive_mut(&mut |limb| limb.mode = LimbMode::Powered);
+///     }
+///
+///     ragdoll
+/// }
+/// ```
+pub struct RagdollGenerator {
+    root_bone: Handle<Node>,
+    total_mass: f32,
+    radius_ratio: f32,
+    min_bone_length: f32,
+    joint_limit: f32,
+    twist_limit: f32,
+    hinge_limit: f32,
+    joint_limits: FxHashMap<Handle<Node>, JointLimits>,
+    friction: f32,
+    use_ccd: bool,
+    can_sleep: bool,
+    collision_groups: InteractionGroups,
+    solver_groups: InteractionGroups,
+    blend_time: f32,
+}
+
+impl Default for RagdollGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RagdollGenerator {
+    /// Creates a new ragdoll generator with default settings.
+    pub fn new() -> Self {
+        Self {
+            root_bone: Default::default(),
+            total_mass: 70.0,
+            radius_ratio: 0.25,
+            min_bone_length: 0.03,
+            joint_limit: 45.0f32.to_radians(),
+            twist_limit: 20.0f32.to_radians(),
+            hinge_limit: 90.0f32.to_radians(),
+            joint_limits: Default::default(),
+            friction: 0.5,
+            use_ccd: true,
+            can_sleep: true,
+            collision_groups: Default::default(),
+            solver_groups: Default::default(),
+            blend_time: 0.0,
+        }
+    }
+
+    /// Sets the root bone of the ragdoll. By default, the root is a bone with the largest amount
+    /// of descendant bones, that has no parent bone.
+    pub fn with_root_bone(mut self, root_bone: Handle<Node>) -> Self {
+        self.root_bone = root_bone;
+        self
+    }
+
+    /// Sets the total mass of the ragdoll. Default is 70.0.
+    pub fn with_total_mass(mut self, total_mass: f32) -> Self {
+        self.total_mass = total_mass;
+        self
+    }
+
+    /// Sets the radius of limbs relative to their length. Default is 0.25.
+    pub fn with_radius_ratio(mut self, radius_ratio: f32) -> Self {
+        self.radius_ratio = radius_ratio;
+        self
+    }
+
+    /// Sets the minimum length of a bone relative to the size of the skeleton. Shorter bones are
+    /// merged with their parents. Default is 0.03.
+    pub fn with_min_bone_length(mut self, min_bone_length: f32) -> Self {
+        self.min_bone_length = min_bone_length;
+        self
+    }
+
+    /// Sets the maximum swing angle (in radians) of ball-like joints around both axes, that are
+    /// perpendicular to the bone. Default is 45 degrees.
+    pub fn with_joint_limit(mut self, joint_limit: f32) -> Self {
+        self.joint_limit = joint_limit;
+        self
+    }
+
+    /// Sets the maximum angle (in radians) of every joint around the bone. It is also used as the
+    /// swing limit of hinge-like joints around their secondary axis. Default is 20 degrees.
+    pub fn with_twist_limit(mut self, twist_limit: f32) -> Self {
+        self.twist_limit = twist_limit;
+        self
+    }
+
+    /// Sets the maximum bend angle (in radians) of hinge-like joints (elbows, knees, etc.). Default
+    /// is 90 degrees.
+    pub fn with_hinge_limit(mut self, hinge_limit: f32) -> Self {
+        self.hinge_limit = hinge_limit;
+        self
+    }
+
+    /// Overrides the limits of the joint, that connects the limb of the given bone with its parent
+    /// limb. See [`JointLimits`] docs for more info about the axes.
+    pub fn with_joint_limits(mut self, bone: Handle<Node>, limits: JointLimits) -> Self {
+        self.joint_limits.insert(bone, limits);
+        self
+    }
+
+    fn joint_limits(&self, plan: &LimbPlan, is_hinge: bool) -> JointLimits {
+        if let Some(limits) = self.joint_limits.get(&plan.bone) {
+            return limits.clone();
+        }
+        let twist = -self.twist_limit..self.twist_limit;
+        if is_hinge {
+            JointLimits {
+                twist: twist.clone(),
+                bend: -self.hinge_limit..self.hinge_limit,
+                swing: twist,
+            }
+        } else {
+            JointLimits {
+                twist,
+                bend: -self.joint_limit..self.joint_limit,
+                swing: -self.joint_limit..self.joint_limit,
+            }
+        }
+    }
+
+    /// Sets the friction coefficient of every collider of the ragdoll. Default is 0.5.
+    pub fn with_friction(mut self, friction: f32) -> Self {
+        self.friction = friction;
+        self
+    }
+
+    /// Sets whether the rigid bodies of the ragdoll should use continuous collision detection.
+    /// Default is `true`.
+    pub fn with_ccd_enabled(mut self, use_ccd: bool) -> Self {
+        self.use_ccd = use_ccd;
+        self
+    }
+
+    /// Sets whether the rigid bodies of the ragdoll can sleep. Default is `true`.
+    pub fn with_can_sleep(mut self, can_sleep: bool) -> Self {
+        self.can_sleep = can_sleep;
+        self
+    }
+
+    /// Sets collision groups of every collider of the ragdoll. It could be used to filter out
+    /// collisions between the character capsule and the ragdoll.
+    pub fn with_collision_groups(mut self, collision_groups: InteractionGroups) -> Self {
+        self.collision_groups = collision_groups;
+        self
+    }
+
+    /// Sets solver groups of every collider of the ragdoll.
+    pub fn with_solver_groups(mut self, solver_groups: InteractionGroups) -> Self {
+        self.solver_groups = solver_groups;
+        self
+    }
+
+    /// Sets the blend time of the ragdoll. See [`Ragdoll::blend_time`] for more info.
+    pub fn with_blend_time(mut self, blend_time: f32) -> Self {
+        self.blend_time = blend_time;
+        self
+    }
+
+    fn collect_skeleton(meshes: &[Handle<Mesh>], graph: &Graph) -> Skeleton {
+        let mut positions = FxHashMap::default();
+        for mesh in meshes {
+            if let Ok(mesh) = graph.try_get(*mesh) {
+                for surface in mesh.surfaces() {
+                    for bone in surface.bones() {
+                        if let Ok(bone_ref) = graph.try_get_node(*bone) {
+                            positions.insert(*bone, bone_ref.global_position());
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut children = FxHashMap::<_, Vec<_>>::default();
+        let mut parents = FxHashMap::default();
+        for bone in positions.keys() {
+            // Intermediate nodes, that are not used as bones, are skipped.
+            let mut parent = graph[*bone].parent();
+            while let Ok(parent_ref) = graph.try_get_node(parent) {
+                if positions.contains_key(&parent) {
+                    children.entry(parent).or_default().push(*bone);
+                    parents.insert(*bone, parent);
+                    break;
+                }
+                parent = parent_ref.parent();
+            }
+        }
+        // Make the result independent of the hash map iteration order.
+        for bones in children.values_mut() {
+            bones.sort_by_key(|bone| (bone.index(), bone.generation()));
+        }
+
+        Skeleton {
+            positions,
+            children,
+            parents,
+        }
+    }
+
+    fn plan_limbs(
+        &self,
+        bone: Handle<Node>,
+        is_root: bool,
+        min_length: f32,
+        skeleton: &Skeleton,
+        graph: &Graph,
+    ) -> Vec<LimbPlan> {
+        let position = skeleton.positions[&bone];
+        let children = skeleton
+            .children
+            .get(&bone)
+            .map(|children| children.as_slice())
+            .unwrap_or_default();
+
+        let child_plans = children
+            .iter()
+            .flat_map(|child| self.plan_limbs(*child, false, min_length, skeleton, graph))
+            .collect::<Vec<_>>();
+
+        // Short "end" bones do not define the shape of the limb.
+        let significant_children = children
+            .iter()
+            .map(|child| skeleton.positions[child])
+            .filter(|child_position| (child_position - position).norm() >= min_length)
+            .collect::<Vec<_>>();
+
+        let (end, reach) = if significant_children.is_empty() {
+            // Leaf bones continue the direction of their parent bones.
+            let Some(parent_position) = skeleton
+                .parents
+                .get(&bone)
+                .map(|parent| skeleton.positions[parent])
+            else {
+                return child_plans;
+            };
+            let extension = (position - parent_position).scale(0.5);
+            (position + extension, extension.norm())
+        } else {
+            let end = significant_children
+                .iter()
+                .fold(Vector3::default(), |sum, child_position| {
+                    sum + child_position
+                })
+                .scale(1.0 / significant_children.len() as f32);
+            let reach = significant_children
+                .iter()
+                .map(|child_position| (child_position - position).norm())
+                .fold(0.0, f32::max);
+            (end, reach)
+        };
+
+        if !is_root && reach < min_length {
+            // Merge the bone with its parent limb.
+            return child_plans;
+        }
+
+        let radius = (reach * self.radius_ratio).max(f32::EPSILON);
+        let segment = end - position;
+        let length = segment.norm();
+        let direction = segment.try_normalize(f32::EPSILON);
+        let shape = if length > 2.0 * radius {
+            let direction = segment.scale(1.0 / length);
+            LimbShape::Capsule {
+                begin: position + direction.scale(radius),
+                end: end - direction.scale(radius),
+                radius,
+            }
+        } else {
+            LimbShape::Ball {
+                center: (position + end).scale(0.5),
+                radius: radius.max(length * 0.5),
+            }
+        };
+
+        vec![LimbPlan {
+            bone,
+            position,
+            rotation: rotation_from_transform(&graph[bone].global_transform()),
+            direction,
+            shape,
+            children: child_plans,
+        }]
+    }
+
+    fn create_limb(
+        &self,
+        plan: LimbPlan,
+        parent_body: Handle<RigidBody>,
+        parent_children: usize,
+        density: f32,
+        ragdoll: Handle<Ragdoll>,
+        graph: &mut Graph,
+    ) -> Limb {
+        let name = graph[plan.bone].name_owned();
+        let inv_rotation = plan.rotation.inverse();
+        let to_local = |point: Vector3<f32>| inv_rotation * (point - plan.position);
+
+        let (shape, offset) = match plan.shape {
+            LimbShape::Capsule { begin, end, radius } => (
+                ColliderShape::capsule(to_local(begin), to_local(end), radius),
+                Vector3::default(),
+            ),
+            LimbShape::Ball { center, radius } => (ColliderShape::ball(radius), to_local(center)),
+        };
+
+        let collider = ColliderBuilder::new(
+            BaseBuilder::new()
+                .with_name(format!("{name} Collider"))
+                .with_local_transform(TransformBuilder::new().with_local_position(offset).build()),
+        )
+        .with_shape(shape)
+        .with_density(Some(density))
+        .with_friction(self.friction)
+        .with_collision_groups(self.collision_groups)
+        .with_solver_groups(self.solver_groups)
+        .build(graph);
+
+        let body = RigidBodyBuilder::new(
+            BaseBuilder::new()
+                .with_name(format!("{name} Body"))
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(plan.position)
+                        .with_local_rotation(plan.rotation)
+                        .build(),
+                )
+                .with_child(collider),
+        )
+        // The mass comes from the collider density only.
+        .with_mass(0.0)
+        .with_can_sleep(self.can_sleep)
+        .with_ccd_enabled(self.use_ccd)
+        .with_body_type(RigidBodyType::KinematicPositionBased)
+        .build(graph);
+        graph.link_nodes(body, ragdoll);
+
+        if parent_body.is_some() {
+            let is_hinge = parent_children == 1 && plan.children.len() == 1;
+            let limits = self.joint_limits(&plan, is_hinge);
+            let joint = JointBuilder::new(
+                BaseBuilder::new()
+                    .with_name(format!("{name} Joint"))
+                    .with_local_transform(
+                        TransformBuilder::new()
+                            .with_local_position(plan.position)
+                            .with_local_rotation(plan.joint_rotation())
+                            .build(),
+                    ),
+            )
+            .with_params(JointParams::BallJoint(BallJoint {
+                x_limits_enabled: true,
+                x_limits_angles: limits.twist,
+                y_limits_enabled: true,
+                y_limits_angles: limits.bend,
+                z_limits_enabled: true,
+                z_limits_angles: limits.swing,
+            }))
+            .with_body1(parent_body)
+            .with_body2(body)
+            .with_contacts_enabled(false)
+            .with_auto_rebinding_enabled(false)
+            .build(graph);
+            graph.link_nodes(joint, ragdoll);
+        }
+
+        let child_count = plan.children.len();
+        Limb {
+            bone: plan.bone,
+            physical_bone: body,
+            children: plan
+                .children
+                .into_iter()
+                .map(|child| self.create_limb(child, body, child_count, density, ragdoll, graph))
+                .collect(),
+            mode: Default::default(),
+        }
+    }
+
+    /// Generates a ragdoll using the bones of the given skinned meshes (multiple meshes could
+    /// share the same skeleton) and adds it to the graph. The ragdoll is inactive after creation.
+    /// Returns `None` if the meshes have no bones.
+    pub fn generate(&self, meshes: &[Handle<Mesh>], graph: &mut Graph) -> Option<Handle<Ragdoll>> {
+        graph.update_hierarchical_data();
+
+        let skeleton = Self::collect_skeleton(meshes, graph);
+
+        let root_bone = if skeleton.positions.contains_key(&self.root_bone) {
+            self.root_bone
+        } else {
+            skeleton
+                .positions
+                .keys()
+                .filter(|bone| !skeleton.parents.contains_key(bone))
+                .max_by_key(|bone| {
+                    (
+                        skeleton.count_descendants(**bone),
+                        std::cmp::Reverse(bone.index()),
+                    )
+                })
+                .cloned()?
+        };
+
+        let bounds = AxisAlignedBoundingBox::from_points(
+            &skeleton.positions.values().cloned().collect::<Vec<_>>(),
+        );
+        let min_length = (bounds.max - bounds.min).norm() * self.min_bone_length;
+
+        let root_plan = self
+            .plan_limbs(root_bone, true, min_length, &skeleton, graph)
+            .pop()?;
+
+        fn total_volume(plan: &LimbPlan) -> f32 {
+            plan.shape.volume() + plan.children.iter().map(total_volume).sum::<f32>()
+        }
+        // Every collider has the same density, so the mass of each limb is proportional to its
+        // volume.
+        let density = self.total_mass / total_volume(&root_plan).max(f32::EPSILON);
+
+        let ragdoll = RagdollBuilder::new(BaseBuilder::new().with_name("Ragdoll"))
+            .with_active(false)
+            .with_blend_time(self.blend_time)
+            .build(graph);
+
+        let root_limb = self.create_limb(root_plan, Handle::NONE, 0, density, ragdoll, graph);
+        graph[ragdoll]
+            .root_limb
+            .set_value_and_mark_modified(root_limb);
+
+        Some(ragdoll)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            algebra::{Matrix4, Vector2, Vector3},
+            pool::Handle,
+        },
+        graph::SceneGraph,
+        scene::{
+            base::BaseBuilder,
+            collider::{Collider, ColliderShape},
+            graph::Graph,
+            joint::{Joint, JointParams},
+            mesh::{
+                surface::{SurfaceBuilder, SurfaceData, SurfaceResource},
+                Mesh, MeshBuilder,
+            },
+            node::Node,
+            pivot::PivotBuilder,
+            ragdoll::{
+                generator::{JointLimits, RagdollGenerator},
+                Limb,
+            },
+            transform::TransformBuilder,
+        },
+    };
+
+    fn add_bone(graph: &mut Graph, parent: Handle<Node>, offset: Vector3<f32>) -> Handle<Node> {
+        let bone = PivotBuilder::new(
+            BaseBuilder::new()
+                .with_local_transform(TransformBuilder::new().with_local_position(offset).build()),
+        )
+        .build(graph)
+        .to_base();
+        if parent.is_some() {
+            graph.link_nodes(bone, parent);
+        }
+        bone
+    }
+
+    fn add_mesh(graph: &mut Graph, bones: Vec<Handle<Node>>) -> Handle<Mesh> {
+        MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(SurfaceResource::new_embedded(
+                SurfaceData::make_cube(Matrix4::identity()),
+            ))
+            .with_bones(bones)
+            .build()])
+            .build(graph)
+    }
+
+    #[test]
+    fn test_ragdoll_generation() {
+        let mut graph = Graph::new();
+
+        let hips = add_bone(&mut graph, Handle::NONE, Vector3::new(0.0, 1.0, 0.0));
+        let spine = add_bone(&mut graph, hips, Vector3::new(0.0, 0.4, 0.0));
+        let head = add_bone(&mut graph, spine, Vector3::new(0.0, 0.3, 0.0));
+        // Too short, must be merged with the head.
+        let head_end = add_bone(&mut graph, head, Vector3::new(0.0, 0.02, 0.0));
+        let mut bones = vec![hips, spine, head, head_end];
+        let mut legs = Vec::new();
+        for side in [-1.0, 1.0] {
+            let up_leg = add_bone(&mut graph, hips, Vector3::new(0.15 * side, -0.1, 0.0));
+            let leg = add_bone(&mut graph, up_leg, Vector3::new(0.0, -0.45, 0.0));
+            let foot = add_bone(&mut graph, leg, Vector3::new(0.0, -0.4, 0.0));
+            bones.extend([up_leg, leg, foot]);
+            legs.push((up_leg, leg));
+        }
+        let mesh = add_mesh(&mut graph, bones);
+
+        let head_limits = JointLimits {
+            twist: -0.1..0.1,
+            bend: -0.2..0.3,
+            swing: -0.4..0.5,
+        };
+        let ragdoll = RagdollGenerator::new()
+            .with_total_mass(70.0)
+            .with_joint_limit(0.5)
+            .with_twist_limit(0.25)
+            .with_hinge_limit(1.5)
+            .with_joint_limits(head, head_limits.clone())
+            .generate(&[mesh], &mut graph)
+            .unwrap();
+
+        let mut limbs = Vec::new();
+        graph[ragdoll]
+            .root_limb
+            .iterate_recursive(&mut |limb: &Limb| {
+                limbs.push(limb.clone());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(limbs.len(), 9);
+        assert_eq!(limbs[0].bone, hips);
+        assert_eq!(limbs[0].children.len(), 3);
+        assert!(limbs.iter().all(|limb| limb.bone != head_end));
+
+        let joints = graph
+            .linear_iter()
+            .filter(|node| node.cast::<Joint>().is_some())
+            .count();
+        assert_eq!(joints, 8);
+
+        let joint_of = |bone: Handle<Node>| {
+            let body = limbs
+                .iter()
+                .find(|limb| limb.bone == bone)
+                .unwrap()
+                .physical_bone;
+            let joint = graph
+                .linear_iter()
+                .filter_map(|node| node.cast::<Joint>())
+                .find(|joint| joint.body2() == body)
+                .unwrap();
+            let JointParams::BallJoint(ball) = joint.params() else {
+                panic!("ball joint expected");
+            };
+            let limits = JointLimits {
+                twist: ball.x_limits_angles.clone(),
+                bend: ball.y_limits_angles.clone(),
+                swing: ball.z_limits_angles.clone(),
+            };
+            (**joint.local_transform().rotation(), limits)
+        };
+
+        let (up_leg, leg) = legs[0];
+        // Hips have multiple children, so legs swing freely at the hips.
+        let (_, up_leg_limits) = joint_of(up_leg);
+        assert_eq!(
+            up_leg_limits,
+            JointLimits {
+                twist: -0.25..0.25,
+                bend: -0.5..0.5,
+                swing: -0.5..0.5,
+            }
+        );
+        // Knees are in the middle of the chain and bend around a single axis.
+        let (leg_rotation, leg_limits) = joint_of(leg);
+        assert_eq!(
+            leg_limits,
+            JointLimits {
+                twist: -0.25..0.25,
+                bend: -1.5..1.5,
+                swing: -0.25..0.25,
+            }
+        );
+        // The twist axis is directed along the bone.
+        assert!((leg_rotation * Vector3::x()).metric_distance(&-Vector3::y()) < 1.0e-5);
+        assert_eq!(joint_of(head).1, head_limits);
+
+        // Hips have multiple children, so they're represented by a ball.
+        let hips_collider = graph[limbs[0].physical_bone].children()[0];
+        assert!(matches!(
+            graph
+                .try_get_of_type::<Collider>(hips_collider)
+                .unwrap()
+                .shape(),
+            ColliderShape::Ball(_)
+        ));
+
+        // Mass properties are computed only for dynamic bodies.
+        graph[ragdoll].is_active.set_value_and_mark_modified(true);
+        for _ in 0..2 {
+            graph.update(Vector2::new(800.0, 600.0), 1.0 / 60.0, Default::default());
+        }
+        let total_mass = limbs
+            .iter()
+            .map(|limb| {
+                graph
+                    .physics
+                    .rigid_body_mass(&graph[limb.physical_bone])
+                    .unwrap()
+            })
+            .sum::<f32>();
+        assert!((total_mass - 70.0).abs() < 0.1, "{total_mass}");
+    }
+}
@@ -0,0 +1,659 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Ragdoll is a set of rigid bodies linked with various joints, which can control a set of bones
+//! of a mesh. Ragdolls are used mostly for body physics. See [`Ragdoll`] docs for more info and
+//! usage examples.
+
+use crate::{
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector3},
+        log::Log,
+        math::{aabb::AxisAlignedBoundingBox, Matrix4Ext},
+        pool::{Handle, PoolError},
+        reflect::prelude::*,
+        uuid::{uuid, Uuid},
+        variable::InheritableVariable,
+        visitor::prelude::*,
+    },
+    graph::SceneGraph,
+    scene::{
+        base::{Base, BaseBuilder},
+        collider::Collider,
+        graph::Graph,
+        node::{constructor::NodeConstructor, Node, NodeTrait, UpdateContext},
+        rigidbody::{RigidBody, RigidBodyType},
+    },
+};
+use fxhash::FxHashMap;
+use fyrox_graph::constructor::ConstructorProvider;
+use std::ops::{Deref, DerefMut};
+use strum_macros::{AsRefStr, EnumString, VariantNames};
+
+pub mod generator;
+
+/// Defines how a limb is controlled when its ragdoll is active.
+#[derive(
+    Copy, Clone, Debug, PartialEq, Eq, Default, Visit, Reflect, AsRefStr, EnumString, VariantNames,
+)]
+#[reflect(type_uuid = "a956efba-e0e3-49d9-b43c-25864ae8caf5")]
+pub enum LimbMode {
+    /// The limb is fully controlled by physics.
+    #[default]
+    Physical,
+    /// The limb is controlled by physics, but its rigid body tries to follow the animation pose
+    /// (relative to the parent limb) using "muscles". See [`Ragdoll::muscle_stiffness`] and
+    /// [`Ragdoll::muscle_strength`].
+    Powered,
+    /// The limb always follows the animation pose, its rigid body is kinematic. It could be used
+    /// to create partial ragdolls, for example only arms and head could be physical, while the
+    /// rest of the body is animated.
+    Animated,
+}
+
+/// A part of ragdoll, that has a physical rigid body, a bone and zero or more children limbs.
+/// Multiple limbs together forms a ragdoll.
+#[derive(Clone, Debug, PartialEq, Default, Visit, Reflect)]
+#[reflect(type_uuid = "6d5bc2f7-8acc-4b64-8e4b-65d4551150bf")]
+pub struct Limb {
+    /// A handle of a scene node, that is used as a bone in some other scene node (mesh).
+    pub bone: Handle<Node>,
+    /// A handle to a rigid body scene node.
+    pub physical_bone: Handle<RigidBody>,
+    /// A set of children limbs.
+    pub children: Vec<Limb>,
+    /// Defines how the limb is controlled when the ragdoll is active.
+    #[visit(optional)]
+    pub mode: LimbMode,
+}
+
+impl Limb {
+    /// Iterates recursively across the entire tree of descendant limbs and does the specified action
+    /// with every limb along the way.
+    pub fn iterate_recursive<F>(&self, func: &mut F) -> Result<(), PoolError>
+    where
+        F: FnMut(&Self) -> Result<(), PoolError>,
+    {
+        func(self)?;
+
+        for child in self.children.iter() {
+            child.iterate_recursive(func)?
+        }
+
+        Ok(())
+    }
+
+    /// Iterates recursively across the entire tree of descendant limbs and does the specified action
+    /// with every limb along the way. The action could modify the limbs.
+    pub fn iterate_recursive_mut<F>(&mut self, func: &mut F)
+    where
+        F: FnMut(&mut Self),
+    {
+        func(self);
+
+        for child in self.children.iter_mut() {
+            child.iterate_recursive_mut(func)
+        }
+    }
+}
+
+fn rotation_from_transform(transform: &Matrix4<f32>) -> UnitQuaternion<f32> {
+    UnitQuaternion::from_matrix_eps(&transform.basis(), f32::EPSILON, 16, Default::default())
+}
+
+/// Ragdoll is a set of rigid bodies linked with various joints, which can control a set of bones
+/// of a mesh. Ragdolls are used mostly for body physics.
+///
+/// ## How to create
+///
+/// Usually, bodies have quite complex hierarchy of bones and total count of the bones could be 30+.
+/// Manual creation of such ragdoll is very tedious and counterproductive. That's why the best way
+/// to create a ragdoll is to use the editor, and the ragdoll wizard in particular. Ragdolls could
+/// also be generated from the skeleton of a skinned mesh at runtime using
+/// [`generator::RagdollGenerator`].
+///
+/// ## Blending with animation
+///
+/// Active ragdoll sets the pose of the bones using its rigid bodies, inactive ragdoll moves its
+/// rigid bodies together with the bones. The transition between the poses is controlled by
+/// [`Self::blend_time`]. When the ragdoll is deactivated, the last physical pose is remembered and
+/// smoothly blended into the animation pose, which is useful for "get up" animations. Keep in mind,
+/// that the animation pose is taken from the bones at the moment of the update of the ragdoll, so
+/// an animation must be playing during the transition and it must be applied before the ragdoll.
+///
+/// Every limb can be controlled differently, see [`LimbMode`] docs for more info. It allows you to
+/// create powered ragdolls (that try to follow the animation) and partial ragdolls (where only a
+/// few limbs are physical).
+#[derive(Clone, PartialEq, Reflect, Visit, Debug)]
+#[reflect(
+    derived_type = "Node",
+    type_uuid = "f4441683-dcef-472d-9d7d-4adca4579107"
+)]
+#[visit(optional)]
+pub struct Ragdoll {
+    base: Base,
+    /// A handle to a main rigid body of the character to which this ragdoll belongs to. If set, the
+    /// ragdoll will take control over the collider and will move it together with the root limb.
+    pub character_rigid_body: InheritableVariable<Handle<RigidBody>>,
+    /// A flag, that defines whether the ragdoll is active or not. Active ragdoll enables limb rigid
+    /// bodies and takes control over `character_rigid_body` (if set).
+    pub is_active: InheritableVariable<bool>,
+    /// Root limb of the ragdoll. Usually it is hips of the body and rest of the limbs are forming
+    /// the rest of the hierarchy.
+    pub root_limb: InheritableVariable<Limb>,
+    /// A flag, that defines whether the ragdoll will deactivate colliders when it is not active or not.
+    /// This option could be useful if you want to disable physics of limbs while the ragdoll is active.
+    pub deactivate_colliders: InheritableVariable<bool>,
+    /// Duration (in seconds) of the transition between the animation pose and the physical pose,
+    /// when the ragdoll is activated or deactivated. Zero means that the pose changes instantly.
+    #[reflect(min_value = 0.0, step = 0.05)]
+    pub blend_time: InheritableVariable<f32>,
+    /// Defines how fast powered limbs are trying to reach the animation pose (in 1/s). For example,
+    /// 10.0 means that the limb tries to remove its rotation error in 0.1 seconds.
+    #[reflect(min_value = 0.0, step = 0.1)]
+    pub muscle_stiffness: InheritableVariable<f32>,
+    /// Defines how much the angular velocity of powered limbs is affected by the muscles on every
+    /// update. 0.0 - muscles have no effect, 1.0 - the velocity is fully defined by the muscles.
+    #[reflect(min_value = 0.0, max_value = 1.0, step = 0.01)]
+    pub muscle_strength: InheritableVariable<f32>,
+    #[reflect(hidden)]
+    prev_enabled: bool,
+    #[reflect(hidden)]
+    character_rigid_body_type: Option<RigidBodyType>,
+    #[reflect(hidden)]
+    physics_weight: f32,
+    #[reflect(hidden)]
+    #[visit(skip)]
+    pose_snapshot: FxHashMap<Handle<Node>, Matrix4<f32>>,
+}
+
+impl Default for Ragdoll {
+    fn default() -> Self {
+        RagdollBuilder::new(BaseBuilder::new())
+            .with_active(false)
+            .build_ragdoll()
+    }
+}
+
+impl Deref for Ragdoll {
+    type Target = Base;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl DerefMut for Ragdoll {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+impl ConstructorProvider<Node, Graph> for Ragdoll {
+    fn constructor() -> NodeConstructor {
+        NodeConstructor::new::<Self>()
+            .with_variant("Ragdoll", |_| {
+                RagdollBuilder::new(BaseBuilder::new().with_name("Ragdoll"))
+                    .build_node()
+                    .into()
+            })
+            .with_group("Physics")
+    }
+}
+
+impl Ragdoll {
+    /// Returns current weight of the physical pose, where 0.0 means that the bones are fully
+    /// animated and 1.0 means that the bones are fully controlled by physics. It changes smoothly
+    /// during [`Self::blend_time`] after activation or deactivation of the ragdoll, for example it
+    /// could be used to find out when a "get up" transition is finished.
+    pub fn physics_weight(&self) -> f32 {
+        self.physics_weight
+    }
+
+    fn update_physics_weight(&mut self, dt: f32) {
+        let target = if *self.is_active { 1.0 } else { 0.0 };
+        self.physics_weight = if *self.blend_time > 0.0 {
+            let delta = dt / *self.blend_time;
+            if target > self.physics_weight {
+                (self.physics_weight + delta).min(target)
+            } else {
+                (self.physics_weight - delta).max(target)
+            }
+        } else {
+            target
+        };
+    }
+}
+
+impl NodeTrait for Ragdoll {
+    fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.base.local_bounding_box()
+    }
+
+    fn world_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.base.world_bounding_box()
+    }
+
+    fn id(&self) -> Uuid {
+        <Self as Reflect>::type_info().type_uuid
+    }
+
+    fn update(&mut self, ctx: &mut UpdateContext) {
+        // Get linear and angular velocities of the character rigid body and transfer it onto rag doll bodies when it is just activated.
+        let mut new_lin_vel = None;
+        let mut new_ang_vel = None;
+        if *self.is_active && !self.prev_enabled {
+            if let Ok(character_rigid_body) = ctx.nodes.try_get(*self.character_rigid_body) {
+                new_lin_vel = Some(character_rigid_body.lin_vel());
+                new_ang_vel = Some(character_rigid_body.ang_vel());
+            }
+            self.pose_snapshot.clear();
+        } else if !*self.is_active && self.prev_enabled {
+            // Remember the last physical pose, it will be blended into the animation pose.
+            self.pose_snapshot.clear();
+            let nodes = &*ctx.nodes;
+            let snapshot = &mut self.pose_snapshot;
+            Log::verify(self.root_limb.iterate_recursive(&mut |limb| {
+                let body = nodes.try_get(limb.physical_bone)?;
+                snapshot.insert(limb.bone, body.global_transform());
+                Ok(())
+            }));
+        }
+        self.prev_enabled = *self.is_active;
+
+        self.update_physics_weight(ctx.dt);
+        if self.physics_weight == 0.0 {
+            self.pose_snapshot.clear();
+        }
+
+        let is_active = *self.is_active;
+        let weight = self.physics_weight;
+        let deactivate_colliders = *self.deactivate_colliders;
+        let muscle_stiffness = *self.muscle_stiffness;
+        let muscle_strength = *self.muscle_strength;
+        let self_transform_inverse = self.global_transform().try_inverse().unwrap_or_default();
+        let snapshot = &self.pose_snapshot;
+        Log::verify(self.root_limb.iterate_recursive(&mut |limb| {
+            let is_dynamic = is_active && limb.mode != LimbMode::Animated;
+
+            let physical_pose = if is_dynamic {
+                let mbc = ctx.nodes.begin_multi_borrow();
+                let mut limb_body = mbc.try_get_mut(limb.physical_bone)?;
+
+                // Transfer linear and angular velocities to rag doll bodies.
+                if let Some(lin_vel) = new_lin_vel {
+                    limb_body.set_lin_vel(lin_vel);
+                }
+                if let Some(ang_vel) = new_ang_vel {
+                    limb_body.set_ang_vel(ang_vel);
+                }
+
+                if limb_body.body_type() != RigidBodyType::Dynamic {
+                    limb_body.set_body_type(RigidBodyType::Dynamic);
+                }
+
+                if deactivate_colliders {
+                    for child in limb_body.children() {
+                        if let Ok(mut collider) = mbc.try_get_or_field_mut::<Collider>(*child) {
+                            collider.set_is_sensor(false);
+                        }
+                    }
+                }
+
+                if limb.mode == LimbMode::Powered {
+                    // The bone contains the animation pose at this moment, the parent bone is
+                    // already posed by its limb.
+                    let bone = mbc.try_get(limb.bone)?;
+                    let target = mbc.try_get(bone.parent())?.global_transform()
+                        * bone.local_transform().matrix();
+                    let current = rotation_from_transform(&limb_body.global_transform());
+                    let mut error = rotation_from_transform(&target) * current.inverse();
+                    if error.w < 0.0 {
+                        // Take the shortest arc.
+                        error = UnitQuaternion::new_unchecked(-error.into_inner());
+                    }
+                    let desired_ang_vel = error.scaled_axis().scale(muscle_stiffness);
+                    let ang_vel = limb_body.ang_vel();
+                    limb_body
+                        .set_ang_vel(ang_vel + (desired_ang_vel - ang_vel).scale(muscle_strength));
+                }
+
+                Some(limb_body.global_transform())
+            } else if limb.mode != LimbMode::Animated {
+                snapshot.get(&limb.bone).cloned()
+            } else {
+                None
+            };
+
+            if let Some(physical_pose) = physical_pose.filter(|_| weight > 0.0) {
+                let mbc = ctx.nodes.begin_multi_borrow();
+
+                // Blend the animation pose of the bone with its physical pose. Pre- and
+                // post-rotations are kept intact, so the animation could be applied later.
+                let bone_parent = mbc.try_get(limb.bone)?.parent();
+                let physical_local: Matrix4<f32> = mbc
+                    .try_get(bone_parent)?
+                    .global_transform()
+                    .try_inverse()
+                    .unwrap_or_else(Matrix4::identity)
+                    * physical_pose;
+                let physical_position =
+                    Vector3::new(physical_local[12], physical_local[13], physical_local[14]);
+                let physical_rotation = rotation_from_transform(&physical_local);
+
+                let mut bone = mbc.try_get_mut(limb.bone)?;
+                let transform = bone.local_transform_mut();
+                let pre_rotation = **transform.pre_rotation();
+                let post_rotation = **transform.post_rotation();
+                let animated_rotation = pre_rotation * **transform.rotation() * post_rotation;
+                let animated_position = **transform.position();
+                let rotation = animated_rotation
+                    .try_slerp(&physical_rotation, weight, f32::EPSILON)
+                    .unwrap_or(physical_rotation);
+                transform
+                    .set_position(animated_position.lerp(&physical_position, weight))
+                    .set_rotation(pre_rotation.inverse() * rotation * post_rotation.inverse());
+
+                drop(bone);
+                drop(mbc);
+
+                // Calculate transform of the descendants explicitly, so the next bones in hierarchy will have new transform
+                // that can be used to calculate relative transform.
+                Graph::update_hierarchical_data_recursively(
+                    ctx.nodes,
+                    ctx.sound_context,
+                    ctx.physics,
+                    ctx.physics2d,
+                    limb.bone,
+                );
+            }
+
+            if !is_dynamic {
+                let mbc = ctx.nodes.begin_multi_borrow();
+                let mut limb_body = mbc.try_get_mut(limb.physical_bone)?;
+
+                limb_body.set_body_type(RigidBodyType::KinematicPositionBased);
+                limb_body.set_lin_vel(Default::default());
+                limb_body.set_ang_vel(Default::default());
+
+                if deactivate_colliders {
+                    for child in limb_body.children() {
+                        if let Ok(mut collider) = mbc.try_get_or_field_mut::<Collider>(*child) {
+                            collider.set_is_sensor(!is_active);
+                        }
+                    }
+                }
+
+                // Sync transform of the physical body with respective bone.
+                let bone = mbc.try_get(limb.bone)?;
+                let relative_transform = self_transform_inverse * bone.global_transform();
+
+                let position = Vector3::new(
+                    relative_transform[12],
+                    relative_transform[13],
+                    relative_transform[14],
+                );
+                limb_body
+                    .local_transform_mut()
+                    .set_position(position)
+                    .set_rotation(rotation_from_transform(&relative_transform));
+            }
+
+            Ok(())
+        }));
+
+        if let Ok(root_limb_body) = ctx.nodes.try_borrow(self.root_limb.bone) {
+            let position = root_limb_body.global_position();
+            if let Ok(character_rigid_body) = ctx.nodes.try_get_mut(*self.character_rigid_body) {
+                if *self.is_active {
+                    character_rigid_body.set_lin_vel(Default::default());
+                    character_rigid_body.set_ang_vel(Default::default());
+                    character_rigid_body
+                        .local_transform_mut()
+                        .set_position(position);
+                    let prev =
+                        character_rigid_body.set_body_type(RigidBodyType::KinematicPositionBased);
+                    if self.character_rigid_body_type.is_none() {
+                        self.character_rigid_body_type = Some(prev);
+                    }
+                } else if let Some(character_rigid_body_type) =
+                    self.character_rigid_body_type.take()
+                {
+                    character_rigid_body.set_body_type(character_rigid_body_type);
+                }
+            }
+        }
+    }
+}
+
+/// Ragdoll builder creates [`Ragdoll`] scene nodes.
+pub struct RagdollBuilder {
+    base_builder: BaseBuilder,
+    character_rigid_body: Handle<RigidBody>,
+    is_active: bool,
+    deactivate_colliders: bool,
+    root_limb: Limb,
+    blend_time: f32,
+    muscle_stiffness: f32,
+    muscle_strength: f32,
+}
+
+impl RagdollBuilder {
+    /// Creates a new ragdoll builder.
+    pub fn new(base_builder: BaseBuilder) -> Self {
+        Self {
+            base_builder,
+            character_rigid_body: Default::default(),
+            is_active: true,
+            deactivate_colliders: false,
+            root_limb: Default::default(),
+            blend_time: 0.0,
+            muscle_stiffness: 10.0,
+            muscle_strength: 0.5,
+        }
+    }
+
+    /// Sets the desired character rigid body.
+    pub fn with_character_rigid_body(mut self, handle: Handle<RigidBody>) -> Self {
+        self.character_rigid_body = handle;
+        self
+    }
+
+    /// Sets whether the ragdoll is active or not.
+    pub fn with_active(mut self, active: bool) -> Self {
+        self.is_active = active;
+        self
+    }
+
+    /// Sets the desired root limb.
+    pub fn with_root_limb(mut self, root_limb: Limb) -> Self {
+        self.root_limb = root_limb;
+        self
+    }
+
+    /// Sets whether the ragdoll should deactivate colliders of its limbs when it is not active or not.
+    pub fn with_deactivate_colliders(mut self, value: bool) -> Self {
+        self.deactivate_colliders = value;
+        self
+    }
+
+    /// Sets the desired duration of the transition between the animation pose and the physical
+    /// pose. See [`Ragdoll::blend_time`] for more info.
+    pub fn with_blend_time(mut self, blend_time: f32) -> Self {
+        self.blend_time = blend_time;
+        self
+    }
+
+    /// Sets the desired stiffness of the muscles of powered limbs. See [`Ragdoll::muscle_stiffness`]
+    /// for more info.
+    pub fn with_muscle_stiffness(mut self, stiffness: f32) -> Self {
+        self.muscle_stiffness = stiffness;
+        self
+    }
+
+    /// Sets the desired strength of the muscles of powered limbs. See [`Ragdoll::muscle_strength`]
+    /// for more info.
+    pub fn with_muscle_strength(mut self, strength: f32) -> Self {
+        self.muscle_strength = strength;
+        self
+    }
+
+    /// Builds the ragdoll.
+    pub fn build_ragdoll(self) -> Ragdoll {
+        Ragdoll {
+            base: self.base_builder.build_base(),
+            character_rigid_body: self.character_rigid_body.into(),
+            is_active: self.is_active.into(),
+            root_limb: self.root_limb.into(),
+            deactivate_colliders: self.deactivate_colliders.into(),
+            blend_time: self.blend_time.into(),
+            muscle_stiffness: self.muscle_stiffness.into(),
+            muscle_strength: self.muscle_strength.into(),
+            prev_enabled: self.is_active,
+            character_rigid_body_type: None,
+            physics_weight: if self.is_active { 1.0 } else { 0.0 },
+            pose_snapshot: Default::default(),
+        }
+    }
+
+    /// Creates ragdoll node, but does not add it to a graph.
+    pub fn build_node(self) -> Node {
+        Node::new(self.build_ragdoll())
+    }
+
+    /// Creates the ragdoll node and adds it to the given graph.
+    pub fn build(self, graph: &mut Graph) -> Handle<Ragdoll> {
+        graph.add_node(self.build_node()).to_variant()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            algebra::{Matrix4, UnitQuaternion, Vector2, Vector3},
+            pool::Handle,
+        },
+        graph::SceneGraph,
+        scene::{
+            base::BaseBuilder,
+            graph::Graph,
+            mesh::{
+                surface::{SurfaceBuilder, SurfaceData, SurfaceResource},
+                MeshBuilder,
+            },
+            node::Node,
+            pivot::PivotBuilder,
+            ragdoll::{generator::RagdollGenerator, Ragdoll, RagdollBuilder},
+            transform::TransformBuilder,
+        },
+    };
+
+    // Local heights of hips, spine and head.
+    const POSE: [f32; 3] = [1.0, 0.4, 0.3];
+
+    // Emulates an animation that keeps the skeleton in a static pose.
+    fn animate(graph: &mut Graph, bones: &[Handle<Node>]) {
+        for (bone, height) in bones.iter().zip(POSE) {
+            graph[*bone]
+                .local_transform_mut()
+                .set_position(Vector3::new(0.0, height, 0.0))
+                .set_rotation(UnitQuaternion::identity());
+        }
+    }
+
+    fn update(graph: &mut Graph, bones: &[Handle<Node>], frames: usize) {
+        for _ in 0..frames {
+            animate(graph, bones);
+            graph.update(Vector2::new(800.0, 600.0), 1.0 / 60.0, Default::default());
+        }
+    }
+
+    #[test]
+    fn test_ragdoll_default_matches_builder() {
+        let default = Ragdoll::default();
+        let built = RagdollBuilder::new(BaseBuilder::new()).build_ragdoll();
+        assert!(!*default.is_active);
+        assert_eq!(*default.muscle_stiffness, *built.muscle_stiffness);
+        assert_eq!(*default.muscle_strength, *built.muscle_strength);
+        assert_eq!(*default.blend_time, *built.blend_time);
+        assert_eq!(*default.deactivate_colliders, *built.deactivate_colliders);
+    }
+
+    #[test]
+    fn test_ragdoll_blending() {
+        let mut graph = Graph::new();
+
+        let mut bones: Vec<Handle<Node>> = Vec::new();
+        for height in POSE {
+            let bone = PivotBuilder::new(
+                BaseBuilder::new().with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(Vector3::new(0.0, height, 0.0))
+                        .build(),
+                ),
+            )
+            .build(&mut graph)
+            .to_base();
+            if let Some(parent) = bones.last() {
+                graph.link_nodes(bone, *parent);
+            }
+            bones.push(bone);
+        }
+        let mesh = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(SurfaceResource::new_embedded(
+                SurfaceData::make_cube(Matrix4::identity()),
+            ))
+            .with_bones(bones.clone())
+            .build()])
+            .build(&mut graph);
+
+        let ragdoll = RagdollGenerator::new()
+            .with_blend_time(0.5)
+            .generate(&[mesh], &mut graph)
+            .unwrap();
+        let hips = bones[0];
+
+        // Inactive ragdoll follows the animation.
+        update(&mut graph, &bones, 10);
+        assert_eq!(graph[ragdoll].physics_weight(), 0.0);
+        assert!((graph[hips].global_position().y - 1.0).abs() < 1.0e-4);
+
+        // Let it fall for a while.
+        graph[ragdoll].is_active.set_value_and_mark_modified(true);
+        update(&mut graph, &bones, 60);
+        assert_eq!(graph[ragdoll].physics_weight(), 1.0);
+        assert!(graph[hips].global_position().y < 0.0);
+
+        // Blend the physical pose back into the animation.
+        graph[ragdoll].is_active.set_value_and_mark_modified(false);
+        update(&mut graph, &bones, 1);
+        let fallen = graph[ragdoll].pose_snapshot[&hips][13];
+        update(&mut graph, &bones, 14);
+        assert!((graph[ragdoll].physics_weight() - 0.5).abs() < 1.0e-3);
+        let expected = (1.0 + fallen) * 0.5;
+        let y = graph[hips].global_position().y;
+        assert!((y - expected).abs() < 1.0e-2, "{y} {expected}");
+
+        update(&mut graph, &bones, 15);
+        assert_eq!(graph[ragdoll].physics_weight(), 0.0);
+        assert!((graph[hips].global_position().y - 1.0).abs() < 1.0e-4);
+    }
+}
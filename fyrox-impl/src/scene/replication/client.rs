@@ -0,0 +1,594 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Client side of the replication. See [`ReplicationClient`] docs for more info.
+
+use super::{
+    find_by_path, send_message,
+    snapshot::{EntityState, Snapshot},
+    transport::Transport,
+    ClientId, ClientMessage, FragmentAssembler, NetworkId, ReplicaSource, ReplicationError,
+    ServerMessage,
+};
+use crate::{
+    asset::manager::ResourceManager,
+    core::{log::Log, pool::Handle},
+    graph::SceneGraph,
+    resource::model::{Model, ModelResource, ModelResourceExtension},
+    scene::{node::Node, Scene},
+};
+use fxhash::FxHashMap;
+use std::collections::VecDeque;
+
+/// Maximum amount of inputs sent in a single packet.
+const MAX_INPUTS_PER_PACKET: usize = 32;
+
+/// Game-specific logic of client-side prediction. A client applies its input to the nodes it owns
+/// immediately, without waiting for the server. When an authoritative snapshot arrives, the state
+/// of the owned nodes is rolled back to the snapshot and all the input that was not yet processed
+/// by the server is applied again. The logic must be deterministic and must produce the same
+/// results as the server-side logic, otherwise the owned nodes will jitter.
+pub trait PredictionHandler {
+    /// Applies the input to the owned nodes. Called once when the input is sent (see
+    /// [`ReplicationClient::send_input`]) and then every time the input is replayed after a
+    /// rollback.
+    fn apply_input(&mut self, scene: &mut Scene, owned_nodes: &[Handle<Node>], input: &[u8]);
+
+    /// Called right after the replicated properties of the owned nodes were reset to their
+    /// authoritative values and before the input is replayed. It could be used to reset any
+    /// additional (non-replicated) state that affects the simulation.
+    fn on_rollback(&mut self, _scene: &mut Scene, _owned_nodes: &[Handle<Node>]) {}
+}
+
+/// An event that happened on a client.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientEvent {
+    /// The client is connected to the server.
+    Connected(ClientId),
+    /// The client was disconnected from the server (or the server timed out).
+    Disconnected,
+    /// A replicated node appeared on the client.
+    Spawned {
+        /// Network id of the node.
+        id: NetworkId,
+        /// Handle of the node in the scene of the client.
+        node: Handle<Node>,
+    },
+    /// A replicated node was removed from the scene of the client.
+    Despawned {
+        /// Network id of the node.
+        id: NetworkId,
+    },
+}
+
+/// Current state of a client connection.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The client is waiting for the server to accept the connection.
+    Connecting,
+    /// The client is connected to the server.
+    Connected(ClientId),
+    /// The client is disconnected.
+    Disconnected,
+}
+
+enum Replica {
+    Spawned(Handle<Node>),
+    Loading(ModelResource),
+    /// The node could not be found or created, it is ignored.
+    Missing,
+}
+
+/// Client side of the replication. It connects to a server, applies the received snapshots to
+/// the scene of the client and sends the input of the player to the server. See the
+/// [module docs](super) for more info.
+///
+/// The state of the nodes that are not owned by the client is interpolated between two
+/// snapshots, which means that the client sees the world slightly in the past (see
+/// [`Self::set_interpolation_delay`]). The state of the owned nodes is predicted using a
+/// [`PredictionHandler`].
+pub struct ReplicationClient<T: Transport> {
+    transport: T,
+    server: T::Address,
+    state: ConnectionState,
+    resource_manager: Option<ResourceManager>,
+    prediction_handler: Option<Box<dyn PredictionHandler>>,
+    tick_rate: f32,
+    interpolation_delay: f32,
+    timeout: f32,
+    silence: f32,
+    connect_timer: f32,
+    snapshots: VecDeque<Snapshot>,
+    history_size: usize,
+    render_tick: f64,
+    replicas: FxHashMap<NetworkId, Replica>,
+    next_input: u32,
+    pending_inputs: VecDeque<(u32, Vec<u8>)>,
+    rollback_input: Option<Option<u32>>,
+    fragments: FragmentAssembler,
+}
+
+impl<T: Transport> ReplicationClient<T> {
+    /// Creates a new client that will connect to the server with the given address using the
+    /// given transport. Default interpolation delay is 0.1 seconds and default server timeout is
+    /// 5 seconds.
+    pub fn new(transport: T, server: T::Address) -> Self {
+        Self {
+            transport,
+            server,
+            state: ConnectionState::Connecting,
+            resource_manager: None,
+            prediction_handler: None,
+            tick_rate: 30.0,
+            interpolation_delay: 0.1,
+            timeout: 5.0,
+            silence: 0.0,
+            // Send connection request on the first update.
+            connect_timer: f32::MAX,
+            snapshots: Default::default(),
+            history_size: 32,
+            render_tick: 0.0,
+            replicas: Default::default(),
+            next_input: 0,
+            pending_inputs: Default::default(),
+            rollback_input: None,
+            fragments: Default::default(),
+        }
+    }
+
+    /// Sets a resource manager that will be used to instantiate replicated prefabs.
+    pub fn set_resource_manager(&mut self, resource_manager: ResourceManager) {
+        self.resource_manager = Some(resource_manager);
+    }
+
+    /// Sets a handler that will predict the state of the owned nodes.
+    pub fn set_prediction_handler(&mut self, handler: Box<dyn PredictionHandler>) {
+        self.prediction_handler = Some(handler);
+    }
+
+    /// Sets the delay (in seconds) of the interpolated state. Larger delays make interpolation
+    /// more robust against packet loss, but increase visible latency. The delay should be at least
+    /// two intervals between snapshots.
+    pub fn set_interpolation_delay(&mut self, delay: f32) {
+        self.interpolation_delay = delay.max(0.0);
+    }
+
+    /// Sets the time (in seconds) after which a silent server is considered disconnected.
+    pub fn set_timeout(&mut self, timeout: f32) {
+        self.timeout = timeout;
+    }
+
+    /// Returns current connection state.
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Returns a reference to the transport of the client.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Returns the tick of the newest snapshot received from the server.
+    pub fn latest_tick(&self) -> Option<u64> {
+        self.snapshots.back().map(|s| s.tick)
+    }
+
+    /// Returns a handle of the node with the given network id.
+    pub fn node(&self, id: NetworkId) -> Option<Handle<Node>> {
+        match self.replicas.get(&id)? {
+            Replica::Spawned(handle) => Some(*handle),
+            _ => None,
+        }
+    }
+
+    /// Returns handles of all the nodes owned by this client.
+    pub fn owned_nodes(&self) -> Vec<Handle<Node>> {
+        let (ConnectionState::Connected(client), Some(latest)) =
+            (self.state, self.snapshots.back())
+        else {
+            return Vec::new();
+        };
+        owned_nodes(&self.replicas, latest, client)
+    }
+
+    /// Sends the input to the server and applies it to the owned nodes using the prediction
+    /// handler (if any). The input is resent until the server acknowledges it. Does nothing if
+    /// the client is not connected.
+    pub fn send_input(
+        &mut self,
+        scene: &mut Scene,
+        input: Vec<u8>,
+    ) -> Result<(), ReplicationError> {
+        if !matches!(self.state, ConnectionState::Connected(_)) {
+            return Ok(());
+        }
+
+        if let Some(handler) = self.prediction_handler.as_mut() {
+            let owned_nodes = {
+                let (ConnectionState::Connected(client), Some(latest)) =
+                    (self.state, self.snapshots.back())
+                else {
+                    return Ok(());
+                };
+                owned_nodes(&self.replicas, latest, client)
+            };
+            handler.apply_input(scene, &owned_nodes, &input);
+        }
+
+        self.pending_inputs.push_back((self.next_input, input));
+        self.next_input = self.next_input.wrapping_add(1);
+        self.send_inputs()
+    }
+
+    fn send_inputs(&mut self) -> Result<(), ReplicationError> {
+        let Some((first_sequence, _)) = self.pending_inputs.front() else {
+            return Ok(());
+        };
+        let message = ClientMessage::Inputs {
+            first_sequence: *first_sequence,
+            inputs: self
+                .pending_inputs
+                .iter()
+                .take(MAX_INPUTS_PER_PACKET)
+                .map(|(_, input)| input.clone())
+                .collect(),
+        };
+        send_message(&mut self.transport, &self.server, &message)
+    }
+
+    /// Disconnects from the server.
+    pub fn disconnect(&mut self) -> Result<(), ReplicationError> {
+        if self.state != ConnectionState::Disconnected {
+            self.state = ConnectionState::Disconnected;
+            send_message(
+                &mut self.transport,
+                &self.server,
+                &ClientMessage::Disconnect,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Updates the client: receives snapshots from the server, spawns and removes replicated
+    /// nodes, interpolates the state of the nodes and predicts the state of the owned nodes. This
+    /// method should be called every frame.
+    pub fn update(
+        &mut self,
+        scene: &mut Scene,
+        dt: f32,
+    ) -> Result<Vec<ClientEvent>, ReplicationError> {
+        let mut events = Vec::new();
+
+        if self.state == ConnectionState::Disconnected {
+            return Ok(events);
+        }
+
+        if self.state == ConnectionState::Connecting {
+            self.connect_timer += dt;
+            if self.connect_timer >= 0.25 {
+                self.connect_timer = 0.0;
+                send_message(&mut self.transport, &self.server, &ClientMessage::Connect)?;
+            }
+        }
+
+        self.silence += dt;
+        while let Some((address, packet)) = self.transport.receive()? {
+            if address != self.server {
+                continue;
+            }
+            match bincode::deserialize::<ServerMessage>(&packet) {
+                Ok(message) => self.handle_message(message, &mut events)?,
+                Err(err) => Log::warn(format!(
+                    "Malformed replication packet from {address:?} was ignored. Reason: {err}"
+                )),
+            }
+        }
+
+        if self.silence > self.timeout || self.state == ConnectionState::Disconnected {
+            self.state = ConnectionState::Disconnected;
+            events.push(ClientEvent::Disconnected);
+            return Ok(events);
+        }
+
+        self.sync_replicas(scene, &mut events);
+        self.interpolate(scene, dt);
+        self.predict(scene);
+
+        // Resend the inputs, because previous packets could be lost.
+        self.send_inputs()?;
+
+        Ok(events)
+    }
+
+    fn handle_message(
+        &mut self,
+        message: ServerMessage,
+        events: &mut Vec<ClientEvent>,
+    ) -> Result<(), ReplicationError> {
+        self.silence = 0.0;
+        match message {
+            ServerMessage::Accepted { client, tick_rate } => {
+                if self.state == ConnectionState::Connecting {
+                    self.state = ConnectionState::Connected(client);
+                    self.tick_rate = tick_rate;
+                    events.push(ClientEvent::Connected(client));
+                }
+            }
+            ServerMessage::Snapshot { delta, last_input } => {
+                // Out-of-order snapshots are ignored, they're useless anyway.
+                if self
+                    .latest_tick()
+                    .is_some_and(|latest| delta.tick <= latest)
+                {
+                    return Ok(());
+                }
+                let base = delta
+                    .base_tick
+                    .and_then(|tick| self.snapshots.iter().find(|s| s.tick == tick));
+                let Some(snapshot) = Snapshot::from_delta(base, &delta) else {
+                    return Ok(());
+                };
+                send_message(
+                    &mut self.transport,
+                    &self.server,
+                    &ClientMessage::Ack {
+                        tick: snapshot.tick,
+                    },
+                )?;
+                self.snapshots.push_back(snapshot);
+                while self.snapshots.len() > self.history_size {
+                    self.snapshots.pop_front();
+                }
+                self.rollback_input = Some(last_input);
+            }
+            ServerMessage::Disconnected => {
+                self.state = ConnectionState::Disconnected;
+            }
+            ServerMessage::Fragment {
+                message,
+                index,
+                count,
+                data,
+            } => {
+                let Some(data) = self.fragments.add(message, index, count, data) else {
+                    return Ok(());
+                };
+                match bincode::deserialize::<ServerMessage>(&data) {
+                    Ok(ServerMessage::Fragment { .. }) => {
+                        Log::warn("Nested replication message fragment was ignored.")
+                    }
+                    Ok(message) => self.handle_message(message, events)?,
+                    Err(err) => Log::warn(format!(
+                        "Malformed fragmented replication message was ignored. Reason: {err}"
+                    )),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn sync_replicas(&mut self, scene: &mut Scene, events: &mut Vec<ClientEvent>) {
+        let Some(latest) = self.snapshots.back() else {
+            return;
+        };
+
+        self.replicas.retain(|id, replica| {
+            if latest.entities.contains_key(id) {
+                return true;
+            }
+            if let Replica::Spawned(handle) = replica {
+                if scene.graph.is_valid_handle(*handle) {
+                    scene.graph.remove_node(*handle);
+                }
+            }
+            events.push(ClientEvent::Despawned { id: *id });
+            false
+        });
+
+        for (id, entity) in latest.entities.iter() {
+            let replica = match self.replicas.get_mut(id) {
+                Some(replica) => replica,
+                None => {
+                    let replica = create_replica(entity, scene, self.resource_manager.as_ref());
+                    if let Replica::Spawned(node) = replica {
+                        events.push(ClientEvent::Spawned { id: *id, node });
+                    }
+                    self.replicas.entry(*id).or_insert(replica)
+                }
+            };
+
+            if let Replica::Loading(model) = replica {
+                if model.is_ok() {
+                    let node = model.instantiate(scene);
+                    *replica = Replica::Spawned(node);
+                    events.push(ClientEvent::Spawned { id: *id, node });
+                } else if model.is_failed_to_load() {
+                    Log::err(format!(
+                        "Unable to instantiate replicated node {id:?}, its model failed to load."
+                    ));
+                    *replica = Replica::Missing;
+                }
+            }
+        }
+    }
+
+    fn interpolate(&mut self, scene: &mut Scene, dt: f32) {
+        let (Some(oldest), Some(latest)) = (self.snapshots.front(), self.snapshots.back()) else {
+            return;
+        };
+
+        // The client renders the state slightly in the past, so there are two snapshots to
+        // interpolate between. The clock is re-synchronized if it drifts too far.
+        let delay = (self.interpolation_delay * self.tick_rate) as f64;
+        let target = latest.tick as f64 - delay;
+        self.render_tick += dt as f64 * self.tick_rate as f64;
+        if (self.render_tick - target).abs() > delay.max(1.0) {
+            self.render_tick = target;
+        }
+        self.render_tick = self
+            .render_tick
+            .clamp(oldest.tick as f64, latest.tick as f64);
+
+        let to_index = self
+            .snapshots
+            .iter()
+            .position(|s| s.tick as f64 >= self.render_tick)
+            .unwrap_or(self.snapshots.len() - 1);
+        let to = &self.snapshots[to_index];
+        let from = &self.snapshots[to_index.saturating_sub(1)];
+        let t = if to.tick > from.tick {
+            ((self.render_tick - from.tick as f64) / (to.tick - from.tick) as f64) as f32
+        } else {
+            1.0
+        };
+
+        let client = match self.state {
+            ConnectionState::Connected(client) => Some(client),
+            _ => None,
+        };
+
+        for (id, entity) in latest.entities.iter() {
+            // Owned nodes are predicted.
+            if client.is_some() && entity.owner == client {
+                continue;
+            }
+            let Some(Replica::Spawned(handle)) = self.replicas.get(id) else {
+                continue;
+            };
+            let Ok(node) = scene.graph.try_get_node_mut(*handle) else {
+                continue;
+            };
+            let from = from
+                .entities
+                .get(id)
+                .filter(|e| e.properties == entity.properties);
+            let to = to
+                .entities
+                .get(id)
+                .filter(|e| e.properties == entity.properties);
+            match (from, to) {
+                (Some(from), Some(to)) => {
+                    for ((property, a), b) in
+                        entity.properties.iter().zip(&from.values).zip(&to.values)
+                    {
+                        property.write(node, &a.interpolate(b, t));
+                    }
+                }
+                _ => write_state(node, entity),
+            }
+        }
+    }
+
+    fn predict(&mut self, scene: &mut Scene) {
+        let Some(last_input) = self.rollback_input.take() else {
+            return;
+        };
+        let (ConnectionState::Connected(client), Some(latest)) =
+            (self.state, self.snapshots.back())
+        else {
+            return;
+        };
+
+        if let Some(last_input) = last_input {
+            while self
+                .pending_inputs
+                .front()
+                .is_some_and(|(sequence, _)| *sequence <= last_input)
+            {
+                self.pending_inputs.pop_front();
+            }
+        }
+
+        // Roll back to the authoritative state.
+        for (id, entity) in latest.entities.iter() {
+            if entity.owner != Some(client) {
+                continue;
+            }
+            if let Some(Replica::Spawned(handle)) = self.replicas.get(id) {
+                if let Ok(node) = scene.graph.try_get_node_mut(*handle) {
+                    write_state(node, entity);
+                }
+            }
+        }
+
+        // Replay the input that was not yet processed by the server.
+        if let Some(handler) = self.prediction_handler.as_mut() {
+            let owned_nodes = owned_nodes(&self.replicas, latest, client);
+            handler.on_rollback(scene, &owned_nodes);
+            for (_, input) in self.pending_inputs.iter() {
+                handler.apply_input(scene, &owned_nodes, input);
+            }
+        }
+    }
+}
+
+fn write_state(node: &mut Node, entity: &EntityState) {
+    for (property, value) in entity.properties.iter().zip(entity.values.iter()) {
+        property.write(node, value);
+    }
+}
+
+fn owned_nodes(
+    replicas: &FxHashMap<NetworkId, Replica>,
+    snapshot: &Snapshot,
+    client: ClientId,
+) -> Vec<Handle<Node>> {
+    snapshot
+        .entities
+        .iter()
+        .filter(|(_, entity)| entity.owner == Some(client))
+        .filter_map(|(id, _)| match replicas.get(id)? {
+            Replica::Spawned(handle) => Some(*handle),
+            _ => None,
+        })
+        .collect()
+}
+
+fn create_replica(
+    entity: &EntityState,
+    scene: &mut Scene,
+    resource_manager: Option<&ResourceManager>,
+) -> Replica {
+    match &entity.source {
+        ReplicaSource::ScenePath(path) => match find_by_path(&scene.graph, path) {
+            Some(handle) => Replica::Spawned(handle),
+            None => {
+                Log::err(format!(
+                    "Unable to find replicated node {path} in the scene of the client!"
+                ));
+                Replica::Missing
+            }
+        },
+        ReplicaSource::Model(uuid) => match resource_manager {
+            Some(resource_manager) => {
+                let mut model = resource_manager.find_uuid::<Model>(*uuid);
+                resource_manager.request_resource(&mut model);
+                Replica::Loading(model)
+            }
+            None => {
+                Log::err(format!(
+                    "Unable to instantiate replicated model {uuid}, the client has no resource \
+                    manager!"
+                ));
+                Replica::Missing
+            }
+        },
+    }
+}
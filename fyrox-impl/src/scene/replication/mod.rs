@@ -0,0 +1,835 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Scene graph state replication for multiplayer games. The replication is server-authoritative:
+//! a server simulates the game and periodically sends snapshots of replicated nodes to clients,
+//! clients apply the snapshots to their own copy of the scene.
+//!
+//! ## Overview
+//!
+//! - [`server::ReplicationServer`] accepts clients, takes snapshots of replicated nodes at a fixed
+//!   rate and sends them to the clients. Snapshots are delta-compressed against the last snapshot
+//!   that was acknowledged by a client.
+//! - [`client::ReplicationClient`] connects to a server, reconstructs snapshots from the deltas,
+//!   spawns/removes replicated nodes and interpolates their state between snapshots. Nodes owned
+//!   by the client are predicted instead: the client applies its input immediately and replays
+//!   unacknowledged input on top of every authoritative snapshot (see
+//!   [`client::PredictionHandler`]).
+//! - [`transport::Transport`] abstracts the actual delivery of packets. There are two transports
+//!   out-of-the-box: [`transport::UdpTransport`] and [`transport::LoopbackTransport`], the latter
+//!   works in-memory and is meant to be used in tests and for listen servers.
+//!
+//! ## Replicated properties
+//!
+//! A node becomes replicated when it is registered on a server using
+//! [`server::ReplicationServer::replicate`]. Local position, rotation and scale of the node are
+//! always replicated. Fields of scripts could be replicated by marking them with the
+//! [`REPLICATED_TAG`] tag using reflection attributes:
+//!
+//! ```rust
+//! # use fyrox_impl::{
+//! #     core::{reflect::prelude::*, visitor::prelude::*},
+//! #     script::ScriptTrait,
+//! # };
+//! #[derive(Reflect, Visit, Default, Debug, Clone, PartialEq)]
+//! #[reflect(type_uuid = "67d3b0ba-f1d3-4a25-9d3b-4c4d95a27f8b")]
+//! struct Player {
+//!     #[reflect(tag = "Replicated")]
+//!     health: f32,
+//!     // Not replicated.
+//!     cooldown: f32,
+//! }
+//!
+//! impl ScriptTrait for Player {}
+//! ```
+//!
+//! Any other property of a node could be replicated by its reflection path, see
+//! [`server::ReplicationServer::replicate_with`]. See [`value::ReplicatedValue`] for the list of
+//! supported types.
+//!
+//! ## Matching nodes
+//!
+//! The server and the clients must load the same scene. Replicated nodes from the scene are
+//! matched by the path of their names from the root of the scene, so such nodes must have unique
+//! names among their siblings. Instances of prefabs that were spawned at runtime are instantiated
+//! on the clients automatically, which requires the clients to have a resource manager (see
+//! [`client::ReplicationClient::set_resource_manager`]).
+
+use crate::{
+    core::{
+        log::Log,
+        pool::Handle,
+        reflect::{prelude::*, ResolvePath},
+    },
+    graph::SceneGraph,
+    scene::{graph::Graph, node::Node},
+};
+use fxhash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+    io,
+};
+
+pub mod client;
+pub mod server;
+pub mod snapshot;
+pub mod transport;
+pub mod value;
+
+use snapshot::SnapshotDelta;
+use value::ReplicatedValue;
+
+/// A reflection tag that marks a field of a script as replicated.
+pub const REPLICATED_TAG: &str = "Replicated";
+
+/// A unique identifier of a replicated node, it is the same on the server and all clients.
+#[derive(
+    Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+pub struct NetworkId(pub u32);
+
+/// A unique identifier of a client connected to a server.
+#[derive(
+    Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+pub struct ClientId(pub u32);
+
+/// Describes how a client finds (or creates) a replicated node.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ReplicaSource {
+    /// The node is a part of the scene, it is found by the path of names from the root of the
+    /// scene, for example `Level/Enemies/Boss`.
+    ScenePath(String),
+    /// The node is an instance of a model resource with the given UUID, the client creates a new
+    /// instance of the model.
+    Model(Uuid),
+}
+
+/// A replicated property of a node.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ReplicatedProperty {
+    /// Local position of the node.
+    Position,
+    /// Local rotation of the node.
+    Rotation,
+    /// Local scale of the node.
+    Scale,
+    /// A property of the node with the given reflection path.
+    Node(String),
+    /// A property of a script of the node.
+    Script {
+        /// Index of the script.
+        index: u16,
+        /// Reflection path of the property relative to the script.
+        path: String,
+    },
+}
+
+impl ReplicatedProperty {
+    /// Reads the value of the property of the given node. Returns [`None`] if the property does
+    /// not exist or its type is not supported.
+    pub fn read(&self, node: &Node) -> Option<ReplicatedValue> {
+        let transform = node.local_transform();
+        match self {
+            Self::Position => Some(ReplicatedValue::Vector3(**transform.position())),
+            Self::Rotation => Some(ReplicatedValue::Rotation(**transform.rotation())),
+            Self::Scale => Some(ReplicatedValue::Vector3(**transform.scale())),
+            Self::Node(path) => read_path(node, path),
+            Self::Script { index, path } => {
+                read_path(&**node.script(*index as usize)? as &dyn Reflect, path)
+            }
+        }
+    }
+
+    /// Writes the value to the property of the given node. Returns `false` if the property does
+    /// not exist or its type does not match the type of the value.
+    pub fn write(&self, node: &mut Node, value: &ReplicatedValue) -> bool {
+        let transform = node.local_transform_mut();
+        match (self, value) {
+            (Self::Position, ReplicatedValue::Vector3(position)) => {
+                transform.set_position(*position);
+                true
+            }
+            (Self::Rotation, ReplicatedValue::Rotation(rotation)) => {
+                transform.set_rotation(*rotation);
+                true
+            }
+            (Self::Scale, ReplicatedValue::Vector3(scale)) => {
+                transform.set_scale(*scale);
+                true
+            }
+            (Self::Node(path), _) => write_path(node, path, value),
+            (Self::Script { index, path }, _) => node
+                .script_mut(*index as usize)
+                .is_some_and(|script| write_path(&mut **script as &mut dyn Reflect, path, value)),
+            _ => false,
+        }
+    }
+
+    /// Collects the default set of replicated properties of the node: local transform and every
+    /// field of its scripts that is marked with [`REPLICATED_TAG`].
+    pub fn collect(node: &Node) -> Vec<Self> {
+        let mut properties = vec![Self::Position, Self::Rotation, Self::Scale];
+        for index in 0..node.script_count() {
+            let Some(script) = node.script(index) else {
+                continue;
+            };
+            (&**script as &dyn Reflect).enumerate_fields_recursively(
+                &mut |path, field, value| {
+                    if field.is_none_or(|field| field.tag != REPLICATED_TAG) {
+                        return;
+                    }
+                    if ReplicatedValue::from_reflect(value).is_some() {
+                        properties.push(Self::Script {
+                            index: index as u16,
+                            path: path.to_string(),
+                        });
+                    } else {
+                        Log::warn(format!(
+                            "Field {path} of {} script of {} node is marked as replicated, but its \
+                            type {} is not supported. The field will not be replicated.",
+                            index,
+                            node.name(),
+                            value.type_info_ref().type_name,
+                        ));
+                    }
+                },
+                &[],
+            );
+        }
+        properties
+    }
+}
+
+fn read_path(entity: &dyn Reflect, path: &str) -> Option<ReplicatedValue> {
+    let mut result = None;
+    entity.resolve_path(path, &mut |value| {
+        result = value.ok().and_then(ReplicatedValue::from_reflect);
+    });
+    result
+}
+
+fn write_path(entity: &mut dyn Reflect, path: &str, value: &ReplicatedValue) -> bool {
+    let mut result = false;
+    entity.resolve_path_mut(path, &mut |target| {
+        result = target.is_ok_and(|target| value.apply_to(target));
+    });
+    result
+}
+
+/// Returns the path of names from the root of the graph to the given node.
+fn node_path(graph: &Graph, node: Handle<Node>) -> String {
+    let mut names = Vec::new();
+    let mut current = node;
+    while let Ok(node) = graph.try_get_node(current) {
+        if current == graph.get_root() {
+            break;
+        }
+        names.push(node.name());
+        current = node.parent();
+    }
+    names.reverse();
+    names.join("/")
+}
+
+/// Finds a node using the path of names from the root of the graph.
+fn find_by_path(graph: &Graph, path: &str) -> Option<Handle<Node>> {
+    let mut current = graph.get_root();
+    for name in path.split('/') {
+        current = *graph
+            .try_get_node(current)
+            .ok()?
+            .children()
+            .iter()
+            .find(|child| graph.try_get_node(**child).is_ok_and(|n| n.name() == name))?;
+    }
+    Some(current)
+}
+
+/// An error that may occur during replication.
+#[derive(Debug)]
+pub enum ReplicationError {
+    /// An error of the underlying transport.
+    Io(io::Error),
+    /// A message could not be serialized.
+    Serialization(bincode::Error),
+}
+
+impl Display for ReplicationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Transport error: {err}"),
+            Self::Serialization(err) => write!(f, "Unable to serialize a message: {err}"),
+        }
+    }
+}
+
+impl Error for ReplicationError {}
+
+impl From<io::Error> for ReplicationError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<bincode::Error> for ReplicationError {
+    fn from(value: bincode::Error) -> Self {
+        Self::Serialization(value)
+    }
+}
+
+/// A message from a client to a server.
+#[derive(Serialize, Deserialize, Debug)]
+enum ClientMessage {
+    Connect,
+    Ack {
+        tick: u64,
+    },
+    /// Unacknowledged inputs of the client, starting from the given sequence number. All of them
+    /// are sent every time to deal with packet loss.
+    Inputs {
+        first_sequence: u32,
+        inputs: Vec<Vec<u8>>,
+    },
+    Disconnect,
+}
+
+/// A message from a server to a client.
+#[derive(Serialize, Deserialize, Debug)]
+enum ServerMessage {
+    Accepted {
+        client: ClientId,
+        tick_rate: f32,
+    },
+    Snapshot {
+        delta: SnapshotDelta,
+        /// Sequence number of the last input of the client that was processed before the
+        /// snapshot was taken.
+        last_input: Option<u32>,
+    },
+    Disconnected,
+    /// A part of a message, that does not fit into a single packet. See [`send_server_message`].
+    Fragment {
+        /// Sequence number of the fragmented message.
+        message: u32,
+        index: u16,
+        count: u16,
+        data: Vec<u8>,
+    },
+}
+
+/// Maximum size of the data of a single fragment, the rest of a packet is reserved for the
+/// header of the fragment message.
+const MAX_FRAGMENT_SIZE: usize = transport::MAX_PACKET_SIZE - 64;
+
+/// Maximum amount of partially received messages, older ones are discarded.
+const MAX_PENDING_MESSAGES: usize = 8;
+
+struct PartialMessage {
+    fragments: Vec<Option<Vec<u8>>>,
+    received: usize,
+}
+
+/// Reassembles fragmented messages (see [`send_server_message`]). If any fragment of a message is
+/// lost, the entire message is lost, just like a regular packet.
+#[derive(Default)]
+struct FragmentAssembler {
+    messages: FxHashMap<u32, PartialMessage>,
+    latest: u32,
+}
+
+impl FragmentAssembler {
+    /// Adds the fragment and returns the content of the entire message if all its fragments were
+    /// received.
+    fn add(&mut self, message: u32, index: u16, count: u16, data: Vec<u8>) -> Option<Vec<u8>> {
+        let (index, count) = (index as usize, count as usize);
+        if index >= count {
+            return None;
+        }
+
+        if message.wrapping_sub(self.latest) < u32::MAX / 2 {
+            self.latest = message;
+        }
+        let partial = self
+            .messages
+            .entry(message)
+            .or_insert_with(|| PartialMessage {
+                fragments: vec![None; count],
+                received: 0,
+            });
+        if partial.fragments.len() != count {
+            return None;
+        }
+        if partial.fragments[index].is_none() {
+            partial.fragments[index] = Some(data);
+            partial.received += 1;
+        }
+
+        if partial.received == count {
+            let partial = self.messages.remove(&message)?;
+            return Some(partial.fragments.into_iter().flatten().flatten().collect());
+        }
+
+        // Discard the oldest incomplete messages, their missing fragments were most likely lost.
+        while self.messages.len() > MAX_PENDING_MESSAGES {
+            let latest = self.latest;
+            let oldest = *self
+                .messages
+                .keys()
+                .max_by_key(|id| latest.wrapping_sub(**id))?;
+            self.messages.remove(&oldest);
+        }
+
+        None
+    }
+}
+
+fn send_message<T: transport::Transport, M: Serialize>(
+    transport: &mut T,
+    address: &T::Address,
+    message: &M,
+) -> Result<(), ReplicationError> {
+    let packet = bincode::serialize(message)?;
+    if packet.len() > transport::MAX_PACKET_SIZE {
+        Log::err(format!(
+            "Replication packet of {} bytes exceeds the limit of {} bytes and will not be sent!",
+            packet.len(),
+            transport::MAX_PACKET_SIZE
+        ));
+        return Ok(());
+    }
+    transport.send(address, &packet)?;
+    Ok(())
+}
+
+/// Sends a message from a server to a client. Messages, that do not fit into a single packet (for
+/// example, the first full snapshot of a large scene) are split into multiple fragments, which are
+/// reassembled by the client.
+fn send_server_message<T: transport::Transport>(
+    transport: &mut T,
+    address: &T::Address,
+    message: &ServerMessage,
+    next_message: &mut u32,
+) -> Result<(), ReplicationError> {
+    let packet = bincode::serialize(message)?;
+    if packet.len() <= transport::MAX_PACKET_SIZE {
+        transport.send(address, &packet)?;
+        return Ok(());
+    }
+
+    let Ok(count) = u16::try_from(packet.len().div_ceil(MAX_FRAGMENT_SIZE)) else {
+        Log::err(format!(
+            "Replication message of {} bytes is too large and will not be sent!",
+            packet.len()
+        ));
+        return Ok(());
+    };
+    let id = *next_message;
+    *next_message = next_message.wrapping_add(1);
+    for (index, data) in packet.chunks(MAX_FRAGMENT_SIZE).enumerate() {
+        let fragment = ServerMessage::Fragment {
+            message: id,
+            index: index as u16,
+            count,
+            data: data.to_vec(),
+        };
+        send_message(transport, address, &fragment)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        client::{ClientEvent, ConnectionState, PredictionHandler, ReplicationClient},
+        server::{ReplicationServer, ServerEvent},
+        transport::{LoopbackNetwork, LoopbackTransport, Transport},
+        *,
+    };
+    use crate::{
+        core::{algebra::Vector3, parking_lot::Mutex, visitor::prelude::*, SafeLock},
+        scene::{base::BaseBuilder, pivot::PivotBuilder, transform::TransformBuilder, Scene},
+        script::ScriptTrait,
+    };
+    use std::sync::Arc;
+
+    #[derive(Reflect, Visit, Default, Debug, Clone, PartialEq)]
+    #[reflect(type_uuid = "3b0f4f1c-3f0a-4a77-9c39-8d0f1b4e7d21")]
+    struct Health {
+        #[reflect(tag = "Replicated")]
+        value: i32,
+        regeneration: f32,
+    }
+
+    impl ScriptTrait for Health {}
+
+    const DT: f32 = 1.0 / 30.0;
+
+    fn make_scene() -> (Scene, Handle<Node>, Handle<Node>) {
+        let mut scene = Scene::new();
+        let player =
+            PivotBuilder::new(BaseBuilder::new().with_name("Player").with_script(Health {
+                value: 100,
+                regeneration: 1.0,
+            }))
+            .build(&mut scene.graph)
+            .to_base();
+        let crate_ = PivotBuilder::new(BaseBuilder::new().with_name("Crate"))
+            .build(&mut scene.graph)
+            .to_base();
+        (scene, player, crate_)
+    }
+
+    struct Session {
+        server: ReplicationServer<LoopbackTransport>,
+        server_scene: Scene,
+        client: ReplicationClient<LoopbackTransport>,
+        client_scene: Scene,
+        server_events: Vec<ServerEvent>,
+        client_events: Vec<ClientEvent>,
+    }
+
+    impl Session {
+        fn new(network: &LoopbackNetwork) -> Self {
+            Self {
+                server: ReplicationServer::new(network.bind(0)),
+                server_scene: make_scene().0,
+                client: ReplicationClient::new(network.bind(1), 0),
+                client_scene: make_scene().0,
+                server_events: Default::default(),
+                client_events: Default::default(),
+            }
+        }
+
+        fn step(&mut self) {
+            self.server_events
+                .extend(self.server.update(&self.server_scene.graph, DT).unwrap());
+            self.client_events
+                .extend(self.client.update(&mut self.client_scene, DT).unwrap());
+        }
+
+        fn client_node(&self, name: &str) -> &Node {
+            let (_, node) = self
+                .client_scene
+                .graph
+                .find_by_name_from_root(name)
+                .unwrap();
+            node
+        }
+    }
+
+    #[test]
+    fn test_collect_properties() {
+        let (scene, player, _) = make_scene();
+        let properties = ReplicatedProperty::collect(&scene.graph[player]);
+        assert_eq!(
+            properties[3],
+            ReplicatedProperty::Script {
+                index: 0,
+                path: "value".to_string()
+            }
+        );
+        assert_eq!(properties.len(), 4);
+        assert_eq!(
+            properties[3].read(&scene.graph[player]),
+            Some(ReplicatedValue::I32(100))
+        );
+        assert_eq!(node_path(&scene.graph, player), "Player");
+        assert_eq!(find_by_path(&scene.graph, "Player"), Some(player));
+    }
+
+    #[test]
+    fn test_replication_over_lossy_network() {
+        let network = LoopbackNetwork::default();
+        network.set_packet_loss(0.3);
+        let mut session = Session::new(&network);
+        let (player, _) = session
+            .server_scene
+            .graph
+            .find_by_name_from_root("Player")
+            .unwrap();
+        let (crate_, _) = session
+            .server_scene
+            .graph
+            .find_by_name_from_root("Crate")
+            .unwrap();
+        session
+            .server
+            .replicate(&session.server_scene.graph, player);
+        session
+            .server
+            .replicate(&session.server_scene.graph, crate_);
+
+        for i in 0..60 {
+            session.server_scene.graph[crate_]
+                .local_transform_mut()
+                .set_position(Vector3::new(i as f32, 0.0, 0.0));
+            session.server_scene.graph[player]
+                .try_get_script_mut::<Health>()
+                .unwrap()
+                .value = 100 - i;
+            session.step();
+        }
+        for _ in 0..60 {
+            session.step();
+        }
+
+        assert!(matches!(
+            session.client.state(),
+            ConnectionState::Connected(_)
+        ));
+        assert_eq!(
+            **session.client_node("Crate").local_transform().position(),
+            Vector3::new(59.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            session
+                .client_node("Player")
+                .try_get_script::<Health>()
+                .unwrap()
+                .value,
+            41
+        );
+
+        // Removed nodes are removed on the client too.
+        session.server_scene.graph.remove_node(crate_);
+        network.set_packet_loss(0.0);
+        for _ in 0..10 {
+            session.step();
+        }
+        assert!(session
+            .client_scene
+            .graph
+            .find_by_name_from_root("Crate")
+            .is_none());
+        assert!(session
+            .client_events
+            .iter()
+            .any(|e| matches!(e, ClientEvent::Despawned { id } if *id == NetworkId(1))));
+    }
+
+    #[test]
+    fn test_large_snapshot_is_fragmented() {
+        // Long names make every entity large, so a moderate amount of nodes is enough.
+        const COUNT: usize = 400;
+        let name = |i: usize| format!("{}{i}", "LargeSnapshotPivot".repeat(10));
+
+        let network = LoopbackNetwork::default();
+        let mut session = Session::new(&network);
+        for i in 0..COUNT {
+            let pivot = PivotBuilder::new(
+                BaseBuilder::new().with_name(name(i)).with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(Vector3::new(i as f32, 1.0, 2.0))
+                        .build(),
+                ),
+            )
+            .build(&mut session.server_scene.graph)
+            .to_base();
+            session.server.replicate(&session.server_scene.graph, pivot);
+            PivotBuilder::new(BaseBuilder::new().with_name(name(i)))
+                .build(&mut session.client_scene.graph);
+        }
+
+        for _ in 0..10 {
+            session.step();
+        }
+
+        let snapshot = session.server.history.back().unwrap();
+        let full = ServerMessage::Snapshot {
+            delta: snapshot.delta(None),
+            last_input: None,
+        };
+        assert!(bincode::serialize(&full).unwrap().len() > 64 * 1024);
+        assert!(matches!(
+            session.client.state(),
+            ConnectionState::Connected(_)
+        ));
+        for i in 0..COUNT {
+            let position = **session.client_node(&name(i)).local_transform().position();
+            assert!((position - Vector3::new(i as f32, 1.0, 2.0)).norm() < 1.0e-3);
+        }
+    }
+
+    #[test]
+    fn test_fragment_assembler() {
+        let mut assembler = FragmentAssembler::default();
+        assert_eq!(assembler.add(0, 1, 2, vec![3, 4]), None);
+        // Duplicates and malformed fragments are ignored.
+        assert_eq!(assembler.add(0, 1, 2, vec![3, 4]), None);
+        assert_eq!(assembler.add(0, 2, 2, vec![5]), None);
+        assert_eq!(assembler.add(0, 0, 3, vec![5]), None);
+        assert_eq!(assembler.add(0, 0, 2, vec![1, 2]), Some(vec![1, 2, 3, 4]));
+
+        // Incomplete messages are eventually discarded.
+        for message in 1..=(MAX_PENDING_MESSAGES as u32 + 2) {
+            assert_eq!(assembler.add(message, 0, 2, vec![]), None);
+        }
+        assert_eq!(assembler.messages.len(), MAX_PENDING_MESSAGES);
+        assert!(!assembler.messages.contains_key(&1));
+    }
+
+    /// A transport that fails to send packets to the given address on demand.
+    struct FaultyTransport {
+        inner: LoopbackTransport,
+        unreachable: Arc<Mutex<Option<u32>>>,
+    }
+
+    impl Transport for FaultyTransport {
+        type Address = u32;
+
+        fn send(&mut self, address: &u32, packet: &[u8]) -> io::Result<()> {
+            if *self.unreachable.safe_lock() == Some(*address) {
+                return Err(io::Error::other("unreachable"));
+            }
+            self.inner.send(address, packet)
+        }
+
+        fn receive(&mut self) -> io::Result<Option<(u32, Vec<u8>)>> {
+            self.inner.receive()
+        }
+    }
+
+    #[test]
+    fn test_unreachable_client_does_not_block_others() {
+        let network = LoopbackNetwork::default();
+        let unreachable = Arc::new(Mutex::new(None));
+        let mut server = ReplicationServer::new(FaultyTransport {
+            inner: network.bind(0),
+            unreachable: unreachable.clone(),
+        });
+        let (mut server_scene, _, crate_) = make_scene();
+        server.replicate(&server_scene.graph, crate_);
+
+        let mut clients = [1, 2].map(|address| {
+            (
+                ReplicationClient::new(network.bind(address), 0),
+                make_scene().0,
+            )
+        });
+        let mut step = |server_scene: &Scene| {
+            server.update(&server_scene.graph, DT).unwrap();
+            for (client, scene) in clients.iter_mut() {
+                client.update(scene, DT).unwrap();
+            }
+        };
+        for _ in 0..5 {
+            step(&server_scene);
+        }
+
+        *unreachable.safe_lock() = Some(1);
+        server_scene.graph[crate_]
+            .local_transform_mut()
+            .set_position(Vector3::new(1.0, 2.0, 3.0));
+        for _ in 0..5 {
+            step(&server_scene);
+        }
+
+        let position = |scene: &Scene| {
+            **scene
+                .graph
+                .find_by_name_from_root("Crate")
+                .unwrap()
+                .1
+                .local_transform()
+                .position()
+        };
+        assert_eq!(position(&clients[0].1), Vector3::default());
+        assert_eq!(position(&clients[1].1), Vector3::new(1.0, 2.0, 3.0));
+    }
+
+    struct MoveHandler;
+
+    impl PredictionHandler for MoveHandler {
+        fn apply_input(&mut self, scene: &mut Scene, owned_nodes: &[Handle<Node>], input: &[u8]) {
+            for node in owned_nodes {
+                move_node(&mut scene.graph[*node], input);
+            }
+        }
+    }
+
+    fn move_node(node: &mut Node, input: &[u8]) {
+        let position = **node.local_transform().position();
+        node.local_transform_mut()
+            .set_position(position + Vector3::new(input[0] as f32, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_prediction() {
+        let network = LoopbackNetwork::default();
+        let mut session = Session::new(&network);
+        session.client.set_prediction_handler(Box::new(MoveHandler));
+        let (player, _) = session
+            .server_scene
+            .graph
+            .find_by_name_from_root("Player")
+            .unwrap();
+        let id = session
+            .server
+            .replicate(&session.server_scene.graph, player);
+
+        for _ in 0..5 {
+            session.step();
+        }
+        let client = match session.client.state() {
+            ConnectionState::Connected(client) => client,
+            state => panic!("Unexpected state {state:?}"),
+        };
+        session.server.set_owner(id, Some(client));
+        for _ in 0..5 {
+            session.step();
+        }
+        assert_eq!(session.client.owned_nodes().len(), 1);
+
+        for i in 0..20 {
+            session
+                .client
+                .send_input(&mut session.client_scene, vec![1])
+                .unwrap();
+            // The input is applied immediately, without waiting for the server.
+            assert_eq!(
+                session.client_node("Player").local_transform().position().x,
+                i as f32 + 1.0
+            );
+            session.step();
+            for event in session.server_events.drain(..) {
+                if let ServerEvent::Input { data, .. } = event {
+                    move_node(&mut session.server_scene.graph[player], &data);
+                }
+            }
+        }
+        for _ in 0..10 {
+            session.step();
+        }
+        assert_eq!(
+            session.server_scene.graph[player]
+                .local_transform()
+                .position()
+                .x,
+            20.0
+        );
+        assert_eq!(
+            session.client_node("Player").local_transform().position().x,
+            20.0
+        );
+    }
+}
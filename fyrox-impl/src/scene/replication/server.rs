@@ -0,0 +1,424 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Server side of the replication. See [`ReplicationServer`] docs for more info.
+
+use super::{
+    node_path, send_message, send_server_message,
+    snapshot::{EntityState, Snapshot},
+    transport::Transport,
+    ClientId, ClientMessage, NetworkId, ReplicaSource, ReplicatedProperty, ReplicationError,
+    ServerMessage,
+};
+use crate::{
+    core::{log::Log, pool::Handle},
+    graph::SceneGraph,
+    scene::{graph::Graph, node::Node},
+};
+use fxhash::FxHashMap;
+use std::collections::{BTreeMap, VecDeque};
+
+/// An event that happened on a server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerEvent {
+    /// A new client was connected.
+    ClientConnected(ClientId),
+    /// A client was disconnected (or timed out).
+    ClientDisconnected(ClientId),
+    /// A client sent an input. Inputs of a client are delivered in order and exactly once, the
+    /// game should apply the input to the nodes controlled by the client.
+    Input {
+        /// The client that sent the input.
+        client: ClientId,
+        /// Sequence number of the input.
+        sequence: u32,
+        /// Game-specific input data.
+        data: Vec<u8>,
+    },
+}
+
+struct ReplicatedNode {
+    handle: Handle<Node>,
+    source: ReplicaSource,
+    owner: Option<ClientId>,
+    properties: Vec<ReplicatedProperty>,
+}
+
+struct Connection {
+    id: ClientId,
+    /// The newest snapshot received by the client.
+    acked_tick: Option<u64>,
+    /// Sequence number of the last input that was delivered to the game.
+    last_input: Option<u32>,
+    /// Time (in seconds) since the last message from the client.
+    silence: f32,
+}
+
+/// Server side of the replication. It accepts clients, takes snapshots of the replicated nodes
+/// at a fixed rate (see [`Self::set_tick_rate`]) and sends delta-compressed snapshots to the
+/// clients. See the [module docs](super) for more info.
+///
+/// ## Example
+///
+/// ```rust
+/// # use fyrox_impl::scene::{
+/// #     base::BaseBuilder,
+/// #     pivot::PivotBuilder,
+/// #     replication::{server::{ReplicationServer, ServerEvent}, transport::LoopbackNetwork},
+/// #     Scene,
+/// # };
+/// let network = LoopbackNetwork::default();
+/// let mut server = ReplicationServer::new(network.bind(0));
+///
+/// let mut scene = Scene::new();
+/// let player = PivotBuilder::new(BaseBuilder::new().with_name("Player"))
+///     .build(&mut scene.graph)
+///     .to_base();
+/// server.replicate(&scene.graph, player);
+///
+/// // Call this every frame.
+/// for event in server.update(&scene.graph, 1.0 / 60.0).unwrap() {
+///     if let ServerEvent::Input { client, data, .. } = event {
+///         // Apply the input of the client to the scene.
+///     }
+/// }
+/// ```
+pub struct ReplicationServer<T: Transport> {
+    transport: T,
+    tick_rate: f32,
+    timeout: f32,
+    history_size: usize,
+    accumulator: f32,
+    tick: u64,
+    next_network_id: u32,
+    next_client_id: u32,
+    nodes: BTreeMap<NetworkId, ReplicatedNode>,
+    clients: FxHashMap<T::Address, Connection>,
+    pub(super) history: VecDeque<Snapshot>,
+    next_message: u32,
+}
+
+impl<T: Transport> ReplicationServer<T> {
+    /// Creates a new server that uses the given transport. Default tick rate is 30 snapshots per
+    /// second and default client timeout is 5 seconds.
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            tick_rate: 30.0,
+            timeout: 5.0,
+            history_size: 64,
+            accumulator: 0.0,
+            tick: 0,
+            next_network_id: 0,
+            next_client_id: 0,
+            nodes: Default::default(),
+            clients: Default::default(),
+            history: Default::default(),
+            next_message: 0,
+        }
+    }
+
+    /// Sets the amount of snapshots per second. Clients are informed about the rate when they
+    /// connect.
+    pub fn set_tick_rate(&mut self, tick_rate: f32) {
+        self.tick_rate = tick_rate.max(1.0);
+    }
+
+    /// Returns the amount of snapshots per second.
+    pub fn tick_rate(&self) -> f32 {
+        self.tick_rate
+    }
+
+    /// Sets the time (in seconds) after which a silent client is considered disconnected.
+    pub fn set_timeout(&mut self, timeout: f32) {
+        self.timeout = timeout;
+    }
+
+    /// Returns current tick of the server.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns a reference to the transport of the server.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Returns an iterator over all connected clients.
+    pub fn clients(&self) -> impl Iterator<Item = ClientId> + '_ {
+        self.clients.values().map(|c| c.id)
+    }
+
+    /// Registers the node for replication with its default set of properties (see
+    /// [`ReplicatedProperty::collect`]). Returns a network id of the node, it is the same on
+    /// all the clients. Registering the same node twice returns the same id.
+    pub fn replicate(&mut self, graph: &Graph, node: Handle<Node>) -> NetworkId {
+        self.replicate_with(graph, node, Vec::new())
+    }
+
+    /// Registers the node for replication with its default set of properties (see
+    /// [`ReplicatedProperty::collect`]) and the given extra properties. Returns a network id
+    /// of the node, it is the same on all the clients. Registering the same node twice updates
+    /// its properties and returns the same id.
+    pub fn replicate_with(
+        &mut self,
+        graph: &Graph,
+        node: Handle<Node>,
+        extra_properties: Vec<ReplicatedProperty>,
+    ) -> NetworkId {
+        let node_ref = &graph[node];
+        let source = match node_ref.resource() {
+            Some(resource) if node_ref.is_resource_instance_root() => {
+                ReplicaSource::Model(resource.resource_uuid())
+            }
+            _ => ReplicaSource::ScenePath(node_path(graph, node)),
+        };
+        let mut properties = ReplicatedProperty::collect(node_ref);
+        properties.extend(extra_properties);
+
+        if let Some(id) = self.network_id(node) {
+            if let Some(replicated) = self.nodes.get_mut(&id) {
+                replicated.properties = properties;
+                replicated.source = source;
+            }
+            return id;
+        }
+
+        let id = NetworkId(self.next_network_id);
+        self.next_network_id += 1;
+        self.nodes.insert(
+            id,
+            ReplicatedNode {
+                handle: node,
+                source,
+                owner: None,
+                properties,
+            },
+        );
+        id
+    }
+
+    /// Stops replication of the node with the given id. The node will be removed on the clients.
+    pub fn stop_replication(&mut self, id: NetworkId) {
+        self.nodes.remove(&id);
+    }
+
+    /// Returns the network id of the given node, if it is replicated.
+    pub fn network_id(&self, node: Handle<Node>) -> Option<NetworkId> {
+        self.nodes
+            .iter()
+            .find_map(|(id, n)| (n.handle == node).then_some(*id))
+    }
+
+    /// Returns a handle of the node with the given network id.
+    pub fn node(&self, id: NetworkId) -> Option<Handle<Node>> {
+        self.nodes.get(&id).map(|n| n.handle)
+    }
+
+    /// Sets a client that controls the node. The client will predict the state of the node
+    /// instead of interpolating it.
+    pub fn set_owner(&mut self, id: NetworkId, owner: Option<ClientId>) {
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.owner = owner;
+        }
+    }
+
+    /// Returns a client that controls the node.
+    pub fn owner(&self, id: NetworkId) -> Option<ClientId> {
+        self.nodes.get(&id).and_then(|n| n.owner)
+    }
+
+    /// Takes a snapshot of all the replicated nodes. Nodes that were deleted from the graph are
+    /// no longer replicated.
+    pub fn take_snapshot(&mut self, graph: &Graph) -> Snapshot {
+        self.nodes
+            .retain(|_, node| graph.is_valid_handle(node.handle));
+
+        let entities = self
+            .nodes
+            .iter()
+            .map(|(id, replicated)| {
+                let node = &graph[replicated.handle];
+                let (properties, values) = replicated
+                    .properties
+                    .iter()
+                    .filter_map(|property| {
+                        property.read(node).map(|value| (property.clone(), value))
+                    })
+                    .unzip();
+                (
+                    *id,
+                    EntityState {
+                        source: replicated.source.clone(),
+                        owner: replicated.owner,
+                        properties,
+                        values,
+                    },
+                )
+            })
+            .collect();
+
+        Snapshot {
+            tick: self.tick,
+            entities,
+        }
+    }
+
+    /// Updates the server: sends snapshots of the replicated nodes (if it is time to do so),
+    /// receives messages from the clients and disconnects timed out clients. This method should
+    /// be called every frame. Snapshots are taken before the new input is received, so the game
+    /// should apply the input from the returned events before the next call.
+    pub fn update(&mut self, graph: &Graph, dt: f32) -> Result<Vec<ServerEvent>, ReplicationError> {
+        let mut events = Vec::new();
+
+        self.accumulator += dt;
+        let tick_duration = 1.0 / self.tick_rate;
+        if self.accumulator >= tick_duration {
+            // Skip the ticks that were missed, there's no point to send outdated snapshots.
+            self.accumulator %= tick_duration;
+            self.tick += 1;
+            self.send_snapshots(graph);
+        }
+
+        while let Some((address, packet)) = self.transport.receive()? {
+            match bincode::deserialize::<ClientMessage>(&packet) {
+                Ok(message) => self.handle_message(address, message, &mut events)?,
+                Err(err) => Log::warn(format!(
+                    "Malformed replication packet from {address:?} was ignored. Reason: {err}"
+                )),
+            }
+        }
+
+        let timeout = self.timeout;
+        self.clients.retain(|_, client| {
+            client.silence += dt;
+            if client.silence > timeout {
+                events.push(ServerEvent::ClientDisconnected(client.id));
+                false
+            } else {
+                true
+            }
+        });
+
+        Ok(events)
+    }
+
+    fn send_snapshots(&mut self, graph: &Graph) {
+        let snapshot = self.take_snapshot(graph);
+
+        for (address, client) in self.clients.iter() {
+            let base = client
+                .acked_tick
+                .and_then(|tick| self.history.iter().find(|s| s.tick == tick));
+            let message = ServerMessage::Snapshot {
+                delta: snapshot.delta(base),
+                last_input: client.last_input,
+            };
+            // A client that can't be reached must not prevent the others from getting updates.
+            if let Err(err) = send_server_message(
+                &mut self.transport,
+                address,
+                &message,
+                &mut self.next_message,
+            ) {
+                Log::err(format!(
+                    "Unable to send a snapshot to {address:?}. Reason: {err}"
+                ));
+            }
+        }
+
+        self.history.push_back(snapshot);
+        while self.history.len() > self.history_size {
+            self.history.pop_front();
+        }
+    }
+
+    fn accept(
+        &mut self,
+        address: T::Address,
+        events: &mut Vec<ServerEvent>,
+    ) -> Result<(), ReplicationError> {
+        let client = self.clients.entry(address.clone()).or_insert_with(|| {
+            let id = ClientId(self.next_client_id);
+            self.next_client_id += 1;
+            events.push(ServerEvent::ClientConnected(id));
+            Connection {
+                id,
+                acked_tick: None,
+                last_input: None,
+                silence: 0.0,
+            }
+        });
+        // The message is sent on every connection request, because it could be lost.
+        let message = ServerMessage::Accepted {
+            client: client.id,
+            tick_rate: self.tick_rate,
+        };
+        send_message(&mut self.transport, &address, &message)
+    }
+
+    fn handle_message(
+        &mut self,
+        address: T::Address,
+        message: ClientMessage,
+        events: &mut Vec<ServerEvent>,
+    ) -> Result<(), ReplicationError> {
+        if let Some(client) = self.clients.get_mut(&address) {
+            client.silence = 0.0;
+        }
+
+        match message {
+            ClientMessage::Connect => self.accept(address, events)?,
+            ClientMessage::Ack { tick } => {
+                if let Some(client) = self.clients.get_mut(&address) {
+                    if client.acked_tick.is_none_or(|acked| tick > acked) {
+                        client.acked_tick = Some(tick);
+                    }
+                }
+            }
+            ClientMessage::Inputs {
+                first_sequence,
+                inputs,
+            } => {
+                if let Some(client) = self.clients.get_mut(&address) {
+                    for (i, data) in inputs.into_iter().enumerate() {
+                        let sequence = first_sequence.wrapping_add(i as u32);
+                        if client.last_input.is_none_or(|last| sequence > last) {
+                            client.last_input = Some(sequence);
+                            events.push(ServerEvent::Input {
+                                client: client.id,
+                                sequence,
+                                data,
+                            });
+                        }
+                    }
+                }
+            }
+            ClientMessage::Disconnect => {
+                if let Some(client) = self.clients.remove(&address) {
+                    events.push(ServerEvent::ClientDisconnected(client.id));
+                    send_message(&mut self.transport, &address, &ServerMessage::Disconnected)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
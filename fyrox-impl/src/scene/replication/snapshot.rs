@@ -0,0 +1,235 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Snapshots of replicated state and delta compression. See [`Snapshot`] docs for more info.
+
+use super::{value::ReplicatedValue, ClientId, NetworkId, ReplicaSource, ReplicatedProperty};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// State of a single replicated node.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EntityState {
+    /// Describes how to find (or create) the node on a client.
+    pub source: ReplicaSource,
+    /// A client that controls the node. Such client predicts the state of the node instead of
+    /// interpolating it.
+    pub owner: Option<ClientId>,
+    /// A list of replicated properties of the node.
+    pub properties: Vec<ReplicatedProperty>,
+    /// Values of the replicated properties, the order is the same as in [`Self::properties`].
+    pub values: Vec<ReplicatedValue>,
+}
+
+/// Full state of all replicated nodes at some simulation tick.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    /// A tick of the server at which the snapshot was taken.
+    pub tick: u64,
+    /// States of the replicated nodes.
+    pub entities: BTreeMap<NetworkId, EntityState>,
+}
+
+/// Changes of a single replicated node relative to some base snapshot.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum EntityDelta {
+    /// The node does not exist in the base snapshot (or its layout was changed), full state is
+    /// sent.
+    Full(EntityState),
+    /// The node exists in the base snapshot, only changed values are sent.
+    Changed {
+        /// A new owner of the node, if it was changed.
+        owner: Option<Option<ClientId>>,
+        /// Indices and new values of changed properties.
+        values: Vec<(u16, ReplicatedValue)>,
+    },
+}
+
+/// Delta-compressed snapshot. It contains only changes relative to some base snapshot that is
+/// known to be received by a client.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SnapshotDelta {
+    /// A tick of the snapshot.
+    pub tick: u64,
+    /// A tick of the base snapshot. [`None`] means that the delta contains full state.
+    pub base_tick: Option<u64>,
+    /// Ids of the nodes that were removed since the base snapshot.
+    pub removed: Vec<NetworkId>,
+    /// Changed nodes.
+    pub changed: Vec<(NetworkId, EntityDelta)>,
+}
+
+impl Snapshot {
+    /// Creates a delta that transforms the `base` snapshot to this snapshot. If there's no base
+    /// snapshot, the delta will contain full state.
+    pub fn delta(&self, base: Option<&Snapshot>) -> SnapshotDelta {
+        let mut changed = Vec::new();
+        for (id, entity) in self.entities.iter() {
+            let base_entity = base.and_then(|base| base.entities.get(id));
+            match base_entity {
+                Some(base_entity)
+                    if base_entity.source == entity.source
+                        && base_entity.properties == entity.properties =>
+                {
+                    let values = entity
+                        .values
+                        .iter()
+                        .zip(base_entity.values.iter())
+                        .enumerate()
+                        .filter(|(_, (value, base_value))| value != base_value)
+                        .map(|(i, (value, _))| (i as u16, value.clone()))
+                        .collect::<Vec<_>>();
+                    let owner = (base_entity.owner != entity.owner).then_some(entity.owner);
+                    if !values.is_empty() || owner.is_some() {
+                        changed.push((*id, EntityDelta::Changed { owner, values }));
+                    }
+                }
+                _ => changed.push((*id, EntityDelta::Full(entity.clone()))),
+            }
+        }
+
+        let removed = base
+            .map(|base| {
+                base.entities
+                    .keys()
+                    .filter(|id| !self.entities.contains_key(id))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        SnapshotDelta {
+            tick: self.tick,
+            base_tick: base.map(|base| base.tick),
+            removed,
+            changed,
+        }
+    }
+
+    /// Reconstructs a snapshot from the given delta and its base snapshot. Returns [`None`] if
+    /// the base snapshot does not match the delta.
+    pub fn from_delta(base: Option<&Snapshot>, delta: &SnapshotDelta) -> Option<Snapshot> {
+        let mut entities = match (base, delta.base_tick) {
+            (_, None) => BTreeMap::new(),
+            (Some(base), Some(base_tick)) if base.tick == base_tick => base.entities.clone(),
+            _ => return None,
+        };
+
+        for id in delta.removed.iter() {
+            entities.remove(id);
+        }
+
+        for (id, entity_delta) in delta.changed.iter() {
+            match entity_delta {
+                EntityDelta::Full(state) => {
+                    entities.insert(*id, state.clone());
+                }
+                EntityDelta::Changed { owner, values } => {
+                    let entity = entities.get_mut(id)?;
+                    if let Some(owner) = owner {
+                        entity.owner = *owner;
+                    }
+                    for (index, value) in values {
+                        *entity.values.get_mut(*index as usize)? = value.clone();
+                    }
+                }
+            }
+        }
+
+        Some(Snapshot {
+            tick: delta.tick,
+            entities,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::algebra::Vector3;
+
+    fn entity(x: f32, health: i32) -> EntityState {
+        EntityState {
+            source: ReplicaSource::ScenePath("Player".to_string()),
+            owner: None,
+            properties: vec![
+                ReplicatedProperty::Position,
+                ReplicatedProperty::Script {
+                    index: 0,
+                    path: "health".to_string(),
+                },
+            ],
+            values: vec![
+                ReplicatedValue::Vector3(Vector3::new(x, 0.0, 0.0)),
+                ReplicatedValue::I32(health),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_delta_round_trip() {
+        let base = Snapshot {
+            tick: 1,
+            entities: [
+                (NetworkId(0), entity(0.0, 100)),
+                (NetworkId(1), entity(1.0, 50)),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let current = Snapshot {
+            tick: 2,
+            entities: [
+                (NetworkId(0), entity(0.0, 90)),
+                (NetworkId(2), entity(2.0, 10)),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        let delta = current.delta(Some(&base));
+        assert_eq!(delta.base_tick, Some(1));
+        assert_eq!(delta.removed, vec![NetworkId(1)]);
+        assert_eq!(
+            delta.changed[0],
+            (
+                NetworkId(0),
+                EntityDelta::Changed {
+                    owner: None,
+                    values: vec![(1, ReplicatedValue::I32(90))]
+                }
+            )
+        );
+        assert!(matches!(
+            delta.changed[1],
+            (NetworkId(2), EntityDelta::Full(_))
+        ));
+        assert_eq!(
+            Snapshot::from_delta(Some(&base), &delta),
+            Some(current.clone())
+        );
+
+        // Wrong base.
+        assert_eq!(Snapshot::from_delta(Some(&current), &delta), None);
+
+        let full = current.delta(None);
+        assert_eq!(Snapshot::from_delta(None, &full), Some(current));
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Transports that deliver replication packets. See [`Transport`] docs for more info.
+
+use crate::core::{parking_lot::Mutex, SafeLock};
+use fxhash::FxHashMap;
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    hash::Hash,
+    io::{self, ErrorKind},
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    sync::Arc,
+};
+
+/// Maximum size of a single packet. Larger packets are not sent.
+pub const MAX_PACKET_SIZE: usize = 65507;
+
+/// An unreliable, unordered, packet-based transport. Packets could be lost, duplicated or arrive
+/// in a different order, the replication protocol is designed to handle that.
+pub trait Transport {
+    /// An address of a peer.
+    type Address: Clone + Eq + Hash + Debug;
+
+    /// Sends the packet to the given peer.
+    fn send(&mut self, address: &Self::Address, packet: &[u8]) -> io::Result<()>;
+
+    /// Returns next received packet (if any) together with the address of its sender. This method
+    /// must never block.
+    fn receive(&mut self) -> io::Result<Option<(Self::Address, Vec<u8>)>>;
+}
+
+/// A transport that uses UDP sockets.
+pub struct UdpTransport {
+    socket: UdpSocket,
+    buffer: Vec<u8>,
+}
+
+impl UdpTransport {
+    /// Binds a non-blocking UDP socket to the given address. Use `0.0.0.0:0` (or similar) address
+    /// for clients to let the OS to pick a free port.
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            buffer: vec![0; MAX_PACKET_SIZE],
+        })
+    }
+
+    /// Returns the local address of the socket.
+    pub fn local_address(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+}
+
+impl Transport for UdpTransport {
+    type Address = SocketAddr;
+
+    fn send(&mut self, address: &Self::Address, packet: &[u8]) -> io::Result<()> {
+        match self.socket.send_to(packet, address) {
+            Ok(_) => Ok(()),
+            // The packet is simply dropped, it is fine for an unreliable transport.
+            Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn receive(&mut self) -> io::Result<Option<(Self::Address, Vec<u8>)>> {
+        loop {
+            match self.socket.recv_from(&mut self.buffer) {
+                Ok((size, address)) => return Ok(Some((address, self.buffer[..size].to_vec()))),
+                Err(err) => match err.kind() {
+                    ErrorKind::WouldBlock => return Ok(None),
+                    // On some platforms, an ICMP "port unreachable" message of a previously sent
+                    // packet is reported as an error of the next read, it must not stop receiving.
+                    ErrorKind::ConnectionReset | ErrorKind::Interrupted => continue,
+                    _ => return Err(err),
+                },
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct LoopbackState {
+    queues: FxHashMap<u32, VecDeque<(u32, Vec<u8>)>>,
+    packet_loss: f32,
+    loss_accumulator: f32,
+}
+
+/// An in-memory network that connects [`LoopbackTransport`]s with each other. It is useful for
+/// testing and for listen servers (when a server and a client runs in the same process). The
+/// network could simulate packet loss, see [`Self::set_packet_loss`].
+///
+/// ## Example
+///
+/// ```rust
+/// # use fyrox_impl::scene::replication::transport::{LoopbackNetwork, Transport};
+/// let network = LoopbackNetwork::default();
+/// let mut server = network.bind(0);
+/// let mut client = network.bind(1);
+/// client.send(&0, b"Hello").unwrap();
+/// assert_eq!(server.receive().unwrap(), Some((1, b"Hello".to_vec())));
+/// ```
+#[derive(Clone, Default)]
+pub struct LoopbackNetwork {
+    state: Arc<Mutex<LoopbackState>>,
+}
+
+impl LoopbackNetwork {
+    /// Creates a new transport with the given address.
+    pub fn bind(&self, address: u32) -> LoopbackTransport {
+        self.state.safe_lock().queues.entry(address).or_default();
+        LoopbackTransport {
+            network: self.clone(),
+            address,
+        }
+    }
+
+    /// Sets a fraction of packets (in `[0; 1]` range) that will be dropped. Packets are dropped
+    /// deterministically (for example, every second packet is dropped if the fraction is 0.5),
+    /// so the tests are reproducible.
+    pub fn set_packet_loss(&self, fraction: f32) {
+        let mut state = self.state.safe_lock();
+        state.packet_loss = fraction.clamp(0.0, 1.0);
+        state.loss_accumulator = 0.0;
+    }
+}
+
+/// A transport of a [`LoopbackNetwork`].
+pub struct LoopbackTransport {
+    network: LoopbackNetwork,
+    address: u32,
+}
+
+impl LoopbackTransport {
+    /// Returns the address of the transport.
+    pub fn address(&self) -> u32 {
+        self.address
+    }
+}
+
+impl Transport for LoopbackTransport {
+    type Address = u32;
+
+    fn send(&mut self, address: &Self::Address, packet: &[u8]) -> io::Result<()> {
+        let mut state = self.network.state.safe_lock();
+        state.loss_accumulator += state.packet_loss;
+        if state.loss_accumulator >= 1.0 {
+            state.loss_accumulator -= 1.0;
+            return Ok(());
+        }
+        // Packets to unknown addresses are silently dropped, just like UDP does.
+        if let Some(queue) = state.queues.get_mut(address) {
+            queue.push_back((self.address, packet.to_vec()));
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self) -> io::Result<Option<(Self::Address, Vec<u8>)>> {
+        Ok(self
+            .network
+            .state
+            .safe_lock()
+            .queues
+            .get_mut(&self.address)
+            .and_then(|queue| queue.pop_front()))
+    }
+}
+
+impl Drop for LoopbackTransport {
+    fn drop(&mut self) {
+        self.network.state.safe_lock().queues.remove(&self.address);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packet_loss() {
+        let network = LoopbackNetwork::default();
+        let mut a = network.bind(0);
+        let mut b = network.bind(1);
+        network.set_packet_loss(0.5);
+        for i in 0..4u8 {
+            a.send(&1, &[i]).unwrap();
+        }
+        let mut received = Vec::new();
+        while let Some((from, packet)) = b.receive().unwrap() {
+            assert_eq!(from, 0);
+            received.extend(packet);
+        }
+        assert_eq!(received, vec![0, 2]);
+    }
+
+    #[test]
+    fn test_udp_transport() {
+        let mut a = UdpTransport::bind("127.0.0.1:0").unwrap();
+        let mut b = UdpTransport::bind("127.0.0.1:0").unwrap();
+        let b_address = b.local_address().unwrap();
+        a.send(&b_address, b"Hello").unwrap();
+        for _ in 0..100 {
+            if let Some((from, packet)) = b.receive().unwrap() {
+                assert_eq!(from, a.local_address().unwrap());
+                assert_eq!(packet, b"Hello");
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        panic!("The packet was not delivered!");
+    }
+}
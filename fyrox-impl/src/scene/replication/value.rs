@@ -0,0 +1,171 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Values of replicated properties. See [`ReplicatedValue`] docs for more info.
+
+use crate::core::{
+    algebra::{UnitQuaternion, Vector2, Vector3},
+    math::lerpf,
+    reflect::prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+/// A value of a replicated property in a form that could be sent over network. Only a limited set
+/// of types could be replicated: numbers, booleans, strings, vectors and rotations. Properties of
+/// any other type are ignored (with a warning) when a node is registered for replication.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ReplicatedValue {
+    /// A boolean value.
+    Bool(bool),
+    /// A signed 32-bit integer.
+    I32(i32),
+    /// An unsigned 32-bit integer.
+    U32(u32),
+    /// A signed 64-bit integer.
+    I64(i64),
+    /// An unsigned 64-bit integer.
+    U64(u64),
+    /// A 32-bit floating point number.
+    F32(f32),
+    /// A 64-bit floating point number.
+    F64(f64),
+    /// A string.
+    String(String),
+    /// A 2D vector.
+    Vector2(Vector2<f32>),
+    /// A 3D vector.
+    Vector3(Vector3<f32>),
+    /// A rotation.
+    Rotation(UnitQuaternion<f32>),
+}
+
+macro_rules! define_conversions {
+    ($($variant:ident => $ty:ty),*) => {
+        impl ReplicatedValue {
+            /// Tries to convert a reflected value to a replicated value. Inheritable variables are
+            /// unwrapped automatically. Returns [`None`] if the type of the value is not supported.
+            pub fn from_reflect(value: &dyn Reflect) -> Option<Self> {
+                if let Some(variable) = value.as_inheritable_variable() {
+                    return Self::from_reflect(variable.inner_value_ref());
+                }
+                $(
+                    if let Some(value) = value.downcast_ref::<$ty>() {
+                        return Some(Self::$variant(value.clone()));
+                    }
+                )*
+                None
+            }
+
+            /// Tries to write the value to the given reflected value. Inheritable variables are
+            /// unwrapped automatically. Returns `false` if the types do not match.
+            pub fn apply_to(&self, target: &mut dyn Reflect) -> bool {
+                if let Some(variable) = target.as_inheritable_variable_mut() {
+                    return self.apply_to(variable.inner_value_mut());
+                }
+                match self {
+                    $(
+                        Self::$variant(value) => {
+                            if let Some(target) = target.downcast_mut::<$ty>() {
+                                *target = value.clone();
+                                return true;
+                            }
+                        }
+                    )*
+                }
+                false
+            }
+        }
+    };
+}
+
+define_conversions!(
+    Bool => bool,
+    I32 => i32,
+    U32 => u32,
+    I64 => i64,
+    U64 => u64,
+    F32 => f32,
+    F64 => f64,
+    String => String,
+    Vector2 => Vector2<f32>,
+    Vector3 => Vector3<f32>,
+    Rotation => UnitQuaternion<f32>
+);
+
+impl ReplicatedValue {
+    /// Interpolates the value between `self` and `other` using the given factor in `[0; 1]` range.
+    /// Floating point numbers and vectors are interpolated linearly, rotations are interpolated
+    /// spherically. All the other values are discrete - `self` is returned until the factor
+    /// reaches 1.0. Values of different types are never interpolated, `other` is returned instead.
+    pub fn interpolate(&self, other: &Self, t: f32) -> Self {
+        match (self, other) {
+            (Self::F32(a), Self::F32(b)) => Self::F32(lerpf(*a, *b, t)),
+            (Self::F64(a), Self::F64(b)) => Self::F64(a + (b - a) * t as f64),
+            (Self::Vector2(a), Self::Vector2(b)) => Self::Vector2(a.lerp(b, t)),
+            (Self::Vector3(a), Self::Vector3(b)) => Self::Vector3(a.lerp(b, t)),
+            (Self::Rotation(a), Self::Rotation(b)) => {
+                let fallback = if t < 0.5 { *a } else { *b };
+                Self::Rotation(a.try_slerp(b, t, f32::EPSILON).unwrap_or(fallback))
+            }
+            (a, b) if std::mem::discriminant(a) == std::mem::discriminant(b) => {
+                if t < 1.0 {
+                    a.clone()
+                } else {
+                    b.clone()
+                }
+            }
+            (_, b) => b.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::variable::InheritableVariable;
+
+    #[test]
+    fn test_reflect_conversion() {
+        let mut variable = InheritableVariable::new_modified(Vector3::new(1.0f32, 2.0, 3.0));
+        let value = ReplicatedValue::from_reflect(&variable).unwrap();
+        assert_eq!(value, ReplicatedValue::Vector3(Vector3::new(1.0, 2.0, 3.0)));
+
+        assert!(ReplicatedValue::Vector3(Vector3::new(3.0, 2.0, 1.0)).apply_to(&mut variable));
+        assert_eq!(*variable, Vector3::new(3.0, 2.0, 1.0));
+        assert!(!ReplicatedValue::F32(1.0).apply_to(&mut variable));
+        assert!(ReplicatedValue::from_reflect(&vec![1u8]).is_none());
+    }
+
+    #[test]
+    fn test_interpolation() {
+        assert_eq!(
+            ReplicatedValue::F32(0.0).interpolate(&ReplicatedValue::F32(2.0), 0.25),
+            ReplicatedValue::F32(0.5)
+        );
+        assert_eq!(
+            ReplicatedValue::I32(1).interpolate(&ReplicatedValue::I32(2), 0.9),
+            ReplicatedValue::I32(1)
+        );
+        assert_eq!(
+            ReplicatedValue::I32(1).interpolate(&ReplicatedValue::I32(2), 1.0),
+            ReplicatedValue::I32(2)
+        );
+    }
+}
@@ -22,11 +22,18 @@
 
 use crate::{
     core::{
+        algebra::Vector3,
         log::{Log, MessageKind},
         pool::Handle,
         visitor::prelude::*,
     },
-    scene::{node::Node, sound::Sound},
+    scene::{
+        node::Node,
+        sound::{
+            occlusion::SoundOcclusion, reverb_zone::SoundEnvironment, Sound, SoundError,
+            SoundEventResource,
+        },
+    },
 };
 use fxhash::FxHashSet;
 use fyrox_sound::{
@@ -42,6 +49,10 @@ use std::{sync::MutexGuard, time::Duration};
 pub struct SoundContext {
     #[visit(optional)]
     pub(crate) native: fyrox_sound::context::SoundContext,
+    #[visit(optional)]
+    pub(crate) occlusion: SoundOcclusion,
+    #[visit(optional)]
+    pub(crate) environment: SoundEnvironment,
 }
 
 /// Proxy for guarded access to the sound context.
@@ -80,6 +91,53 @@ impl SoundContextGuard<'_> {
         self.guard.distance_model()
     }
 
+    /// Sets speed of sound in world units per second. It is used to calculate Doppler shift.
+    pub fn set_speed_of_sound(&mut self, speed_of_sound: f32) {
+        self.guard.set_speed_of_sound(speed_of_sound);
+    }
+
+    /// Returns speed of sound.
+    pub fn speed_of_sound(&self) -> f32 {
+        self.guard.speed_of_sound()
+    }
+
+    /// Sets global Doppler factor, 0.0 disables Doppler effect for every sound.
+    pub fn set_doppler_factor(&mut self, doppler_factor: f32) {
+        self.guard.set_doppler_factor(doppler_factor);
+    }
+
+    /// Returns global Doppler factor.
+    pub fn doppler_factor(&self) -> f32 {
+        self.guard.doppler_factor()
+    }
+
+    /// Sets a value of the switch with the given name. Switches are used by sound events to select
+    /// sounds.
+    pub fn set_switch<N: AsRef<str>, V: AsRef<str>>(&mut self, name: N, value: V) {
+        self.guard.set_switch(name, value);
+    }
+
+    /// Returns current value of the switch with the given name.
+    pub fn switch<N: AsRef<str>>(&self, name: N) -> Option<&str> {
+        self.guard.switch(name)
+    }
+
+    /// Removes the switch with the given name.
+    pub fn remove_switch<N: AsRef<str>>(&mut self, name: N) -> Option<String> {
+        self.guard.remove_switch(name)
+    }
+
+    /// Plays the given sound event at the given position. Unlike sound nodes, the sound is not
+    /// a part of the scene graph, it is removed automatically when it finishes playing. See
+    /// [`fyrox_sound::event`] docs for more info.
+    pub fn play_event(
+        &mut self,
+        event: &SoundEventResource,
+        position: Vector3<f32>,
+    ) -> Result<(), SoundError> {
+        self.guard.play_event(event, position).map(|_| ())
+    }
+
     /// Returns amount of time context spent on rendering all sound sources.
     pub fn full_render_duration(&self) -> Duration {
         self.guard.full_render_duration()
@@ -118,7 +176,11 @@ impl Default for SoundContext {
         // There's no need to serialize native sources, because they'll be re-created automatically.
         state.serialization_options.skip_sources = true;
         drop(state);
-        Self { native }
+        Self {
+            native,
+            occlusion: Default::default(),
+            environment: Default::default(),
+        }
     }
 }
 
@@ -131,9 +193,41 @@ impl SoundContext {
     pub fn deep_clone(&self) -> Self {
         Self {
             native: self.native.deep_clone(),
+            occlusion: self.occlusion.clone(),
+            environment: self.environment.clone(),
         }
     }
 
+    /// Returns sound occlusion settings.
+    pub fn occlusion(&self) -> &SoundOcclusion {
+        &self.occlusion
+    }
+
+    /// Returns sound occlusion settings.
+    pub fn occlusion_mut(&mut self) -> &mut SoundOcclusion {
+        &mut self.occlusion
+    }
+
+    /// Returns environmental reverb settings.
+    pub fn environment(&self) -> &SoundEnvironment {
+        &self.environment
+    }
+
+    /// Returns environmental reverb settings.
+    pub fn environment_mut(&mut self) -> &mut SoundEnvironment {
+        &mut self.environment
+    }
+
+    /// Blends reverb presets of the zones collected during the update and applies the result to
+    /// the reverb bus.
+    pub(crate) fn update_environment(&mut self) {
+        self.environment.update(&self.native);
+    }
+
+    pub(crate) fn listener_position(&self) -> Vector3<f32> {
+        self.native.state().listener().position()
+    }
+
     /// Returns locked inner state of the sound context.
     pub fn state(&self) -> SoundContextGuard {
         SoundContextGuard {
@@ -141,6 +235,12 @@ impl SoundContext {
         }
     }
 
+    /// Returns a reference to the underlying sound context. It could be used, for example, to render
+    /// the scene audio to a file via [`fyrox_sound::offline::OfflineRenderer`].
+    pub fn native(&self) -> &fyrox_sound::context::SoundContext {
+        &self.native
+    }
+
     pub(crate) fn remove_sound(&mut self, sound: Handle<SoundSource>, name: &str) {
         let mut state = self.native.state();
         if state.is_valid_handle(sound) {
@@ -152,7 +252,18 @@ impl SoundContext {
 
     pub(crate) fn set_sound_position(&mut self, sound: &Sound) {
         if let Ok(source) = self.native.state().try_get_source_mut(sound.native.get()) {
-            source.set_position(sound.global_position());
+            source
+                .set_position(sound.global_position())
+                .set_direction(sound.look_vector());
+        }
+    }
+
+    pub(crate) fn set_sound_velocity_and_occlusion(&mut self, sound: &Sound) {
+        if let Ok(source) = self.native.state().try_get_source_mut(sound.native.get()) {
+            let (gain, gain_hf) = sound.occlusion();
+            source
+                .set_velocity(sound.velocity())
+                .set_occlusion(gain, gain_hf);
         }
     }
 
@@ -226,6 +337,24 @@ impl SoundContext {
             sound.audio_bus.try_sync_model(|audio_bus| {
                 source.set_bus(audio_bus);
             });
+            sound.cone_inner_angle.try_sync_model(|v| {
+                source.set_cone_inner_angle(v);
+            });
+            sound.cone_outer_angle.try_sync_model(|v| {
+                source.set_cone_outer_angle(v);
+            });
+            sound.cone_outer_gain.try_sync_model(|v| {
+                source.set_cone_outer_gain(v);
+            });
+            sound.cone_outer_gain_hf.try_sync_model(|v| {
+                source.set_cone_outer_gain_hf(v);
+            });
+            sound.doppler_factor.try_sync_model(|v| {
+                source.set_doppler_factor(v);
+            });
+            sound.reverb_send.try_sync_model(|v| {
+                source.set_reverb_send(v);
+            });
         } else {
             match SoundSourceBuilder::new()
                 .with_gain(sound.gain())
@@ -240,6 +369,13 @@ impl SoundContext {
                 .with_max_distance(sound.max_distance())
                 .with_bus(sound.audio_bus())
                 .with_rolloff_factor(sound.rolloff_factor())
+                .with_direction(sound.look_vector())
+                .with_cone_inner_angle(sound.cone_inner_angle())
+                .with_cone_outer_angle(sound.cone_outer_angle())
+                .with_cone_outer_gain(sound.cone_outer_gain())
+                .with_cone_outer_gain_hf(sound.cone_outer_gain_hf())
+                .with_doppler_factor(sound.doppler_factor())
+                .with_reverb_send(sound.reverb_send())
                 .build()
             {
                 Ok(source) => {
@@ -26,6 +26,7 @@
 use crate::scene::node::constructor::NodeConstructor;
 use crate::{
     core::{
+        algebra::Vector3,
         math::aabb::AxisAlignedBoundingBox,
         pool::Handle,
         reflect::prelude::*,
@@ -35,7 +36,7 @@ use crate::{
     scene::{
         base::{Base, BaseBuilder},
         graph::Graph,
-        node::{Node, NodeTrait, SyncContext},
+        node::{Node, NodeTrait, SyncContext, UpdateContext},
     },
 };
 use fyrox_graph::constructor::ConstructorProvider;
@@ -63,6 +64,15 @@ use std::ops::{Deref, DerefMut};
 )]
 pub struct Listener {
     base: Base,
+
+    // Velocity is derived from the movement of the node between updates.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    velocity: Vector3<f32>,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    prev_position: Option<Vector3<f32>>,
 }
 
 impl Deref for Listener {
@@ -119,6 +129,32 @@ impl NodeTrait for Listener {
         native.set_position(self.global_position());
         native.set_orientation_lh(self.look_vector(), self.up_vector());
     }
+
+    fn update(&mut self, context: &mut UpdateContext) {
+        let position = self.global_position();
+        self.velocity = match self.prev_position.replace(position) {
+            Some(prev_position) if context.dt > 0.0 => {
+                (position - prev_position).scale(1.0 / context.dt)
+            }
+            _ => Vector3::default(),
+        };
+        if self.is_globally_enabled() {
+            context
+                .sound_context
+                .native
+                .state()
+                .listener_mut()
+                .set_velocity(self.velocity);
+        }
+    }
+}
+
+impl Listener {
+    /// Returns velocity of the listener in world space. It is calculated automatically from the
+    /// movement of the node and used to calculate Doppler shift of sounds.
+    pub fn velocity(&self) -> Vector3<f32> {
+        self.velocity
+    }
 }
 
 /// Allows you to create listener in declarative manner.
@@ -136,6 +172,8 @@ impl ListenerBuilder {
     pub fn build_listener(self) -> Listener {
         Listener {
             base: self.base_builder.build_base(),
+            velocity: Default::default(),
+            prev_position: None,
         }
     }
 
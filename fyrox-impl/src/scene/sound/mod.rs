@@ -22,7 +22,7 @@
 
 use crate::{
     core::{
-        algebra::Matrix4,
+        algebra::{Matrix4, Vector3},
         math::{aabb::AxisAlignedBoundingBox, m4x4_approx_eq},
         pool::Handle,
         reflect::prelude::*,
@@ -51,6 +51,10 @@ pub use fyrox_sound::{
     effects::*,
     engine::SoundEngine,
     error::SoundError,
+    event::{
+        RandomContainer, SequenceContainer, SoundClip, SoundContainer, SoundEvent,
+        SoundEventResource, SwitchCase, SwitchContainer,
+    },
     hrtf::HrirSphere,
     renderer::{hrtf::*, Renderer},
     source::Status,
@@ -62,6 +66,7 @@ use fyrox_graph::constructor::ConstructorProvider;
 use fyrox_graph::SceneGraph;
 use fyrox_resource::state::ResourceState;
 use fyrox_sound::source::SoundSource;
+use occlusion::OcclusionState;
 use std::{
     cell::Cell,
     ops::{Deref, DerefMut},
@@ -70,6 +75,8 @@ use std::{
 
 pub mod context;
 pub mod listener;
+pub mod occlusion;
+pub mod reverb_zone;
 
 /// Sound source.
 #[derive(Visit, PartialEq, Reflect, Debug)]
@@ -127,6 +134,72 @@ pub struct Sound {
     #[visit(optional)]
     audio_bus: InheritableVariable<String>,
 
+    #[visit(optional)]
+    #[reflect(
+        setter = "set_cone_inner_angle",
+        min_value = 0.0,
+        max_value = 6.3,
+        step = 0.05
+    )]
+    cone_inner_angle: InheritableVariable<f32>,
+
+    #[visit(optional)]
+    #[reflect(
+        setter = "set_cone_outer_angle",
+        min_value = 0.0,
+        max_value = 6.3,
+        step = 0.05
+    )]
+    cone_outer_angle: InheritableVariable<f32>,
+
+    #[visit(optional)]
+    #[reflect(
+        setter = "set_cone_outer_gain",
+        min_value = 0.0,
+        max_value = 1.0,
+        step = 0.05
+    )]
+    cone_outer_gain: InheritableVariable<f32>,
+
+    #[visit(optional)]
+    #[reflect(
+        setter = "set_cone_outer_gain_hf",
+        min_value = 0.0,
+        max_value = 1.0,
+        step = 0.05
+    )]
+    cone_outer_gain_hf: InheritableVariable<f32>,
+
+    #[visit(optional)]
+    #[reflect(setter = "set_doppler_factor", min_value = 0.0, step = 0.05)]
+    doppler_factor: InheritableVariable<f32>,
+
+    // Velocity is derived from the movement of the node between updates.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    velocity: Vector3<f32>,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    prev_position: Option<Vector3<f32>>,
+
+    #[visit(optional)]
+    #[reflect(setter = "set_occlusion_enabled")]
+    occlusion_enabled: InheritableVariable<bool>,
+
+    #[visit(optional)]
+    #[reflect(
+        setter = "set_reverb_send",
+        min_value = 0.0,
+        max_value = 1.0,
+        step = 0.05
+    )]
+    reverb_send: InheritableVariable<f32>,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    occlusion_state: OcclusionState,
+
     #[reflect(hidden)]
     #[visit(skip)]
     pub(crate) native: Cell<Handle<SoundSource>>,
@@ -163,6 +236,16 @@ impl Default for Sound {
             playback_time: Default::default(),
             spatial_blend: InheritableVariable::new_modified(1.0),
             audio_bus: InheritableVariable::new_modified(AudioBusGraph::PRIMARY_BUS.to_string()),
+            cone_inner_angle: InheritableVariable::new_modified(std::f32::consts::TAU),
+            cone_outer_angle: InheritableVariable::new_modified(std::f32::consts::TAU),
+            cone_outer_gain: InheritableVariable::new_modified(0.0),
+            cone_outer_gain_hf: InheritableVariable::new_modified(1.0),
+            doppler_factor: InheritableVariable::new_modified(1.0),
+            velocity: Default::default(),
+            prev_position: None,
+            occlusion_enabled: InheritableVariable::new_modified(true),
+            reverb_send: InheritableVariable::new_modified(1.0),
+            occlusion_state: Default::default(),
             native: Default::default(),
         }
     }
@@ -185,6 +268,16 @@ impl Clone for Sound {
             playback_time: self.playback_time.clone(),
             spatial_blend: self.spatial_blend.clone(),
             audio_bus: self.audio_bus.clone(),
+            cone_inner_angle: self.cone_inner_angle.clone(),
+            cone_outer_angle: self.cone_outer_angle.clone(),
+            cone_outer_gain: self.cone_outer_gain.clone(),
+            cone_outer_gain_hf: self.cone_outer_gain_hf.clone(),
+            doppler_factor: self.doppler_factor.clone(),
+            velocity: self.velocity,
+            prev_position: self.prev_position,
+            occlusion_enabled: self.occlusion_enabled.clone(),
+            reverb_send: self.reverb_send.clone(),
+            occlusion_state: Default::default(),
             // Do not copy. The copy will have its own native representation.
             native: Default::default(),
         }
@@ -381,6 +474,104 @@ impl Sound {
     pub fn audio_bus(&self) -> &str {
         &self.audio_bus
     }
+
+    /// Sets full angle (in radians) of the inner cone around the look vector of the node. The
+    /// listener inside the inner cone hears the sound without any cone attenuation. Default value
+    /// is 2*pi, which makes the sound omnidirectional.
+    pub fn set_cone_inner_angle(&mut self, angle: f32) -> f32 {
+        self.cone_inner_angle
+            .set_value_and_mark_modified(angle.clamp(0.0, std::f32::consts::TAU))
+    }
+
+    /// Returns full angle of the inner cone in radians.
+    pub fn cone_inner_angle(&self) -> f32 {
+        *self.cone_inner_angle
+    }
+
+    /// Sets full angle (in radians) of the outer cone around the look vector of the node. The
+    /// listener outside the outer cone hears the sound attenuated by outer cone gains, between the
+    /// cones the attenuation is interpolated. Default value is 2*pi.
+    pub fn set_cone_outer_angle(&mut self, angle: f32) -> f32 {
+        self.cone_outer_angle
+            .set_value_and_mark_modified(angle.clamp(0.0, std::f32::consts::TAU))
+    }
+
+    /// Returns full angle of the outer cone in radians.
+    pub fn cone_outer_angle(&self) -> f32 {
+        *self.cone_outer_angle
+    }
+
+    /// Sets gain that is applied when the listener is outside the outer cone. Value must be in
+    /// 0..1 range, default is 0.0.
+    pub fn set_cone_outer_gain(&mut self, gain: f32) -> f32 {
+        self.cone_outer_gain
+            .set_value_and_mark_modified(gain.clamp(0.0, 1.0))
+    }
+
+    /// Returns gain outside the outer cone.
+    pub fn cone_outer_gain(&self) -> f32 {
+        *self.cone_outer_gain
+    }
+
+    /// Sets gain of high frequencies that is applied when the listener is outside the outer cone,
+    /// it makes the sound muffled. Value must be in 0..1 range, default is 1.0 (no filtering).
+    pub fn set_cone_outer_gain_hf(&mut self, gain: f32) -> f32 {
+        self.cone_outer_gain_hf
+            .set_value_and_mark_modified(gain.clamp(0.0, 1.0))
+    }
+
+    /// Returns high frequency gain outside the outer cone.
+    pub fn cone_outer_gain_hf(&self) -> f32 {
+        *self.cone_outer_gain_hf
+    }
+
+    /// Sets Doppler factor of the sound, 0.0 disables Doppler effect for the sound. Default is 1.0.
+    pub fn set_doppler_factor(&mut self, factor: f32) -> f32 {
+        self.doppler_factor
+            .set_value_and_mark_modified(factor.max(0.0))
+    }
+
+    /// Returns Doppler factor of the sound.
+    pub fn doppler_factor(&self) -> f32 {
+        *self.doppler_factor
+    }
+
+    /// Defines whether the sound is affected by occlusion or not. Occlusion itself must be enabled
+    /// in the sound context of the scene, see [`occlusion::SoundOcclusion`] for more info. Default
+    /// is `true`.
+    pub fn set_occlusion_enabled(&mut self, enabled: bool) -> bool {
+        self.occlusion_enabled.set_value_and_mark_modified(enabled)
+    }
+
+    /// Returns `true` if the sound is affected by occlusion, `false` - otherwise.
+    pub fn is_occlusion_enabled(&self) -> bool {
+        *self.occlusion_enabled
+    }
+
+    /// Sets how much of the signal of the sound is sent to the environmental reverb of the scene,
+    /// see [`reverb_zone::SoundEnvironment`] for more info. Value must be in 0..1 range, 0.0 - the
+    /// sound is not reverberated. Default is 1.0.
+    pub fn set_reverb_send(&mut self, reverb_send: f32) -> f32 {
+        self.reverb_send
+            .set_value_and_mark_modified(reverb_send.clamp(0.0, 1.0))
+    }
+
+    /// Returns reverb send level of the sound.
+    pub fn reverb_send(&self) -> f32 {
+        *self.reverb_send
+    }
+
+    /// Returns current pair of gain and high frequency gain caused by obstacles between the sound
+    /// and the listener. It is (1.0, 1.0) if the sound is not occluded.
+    pub fn occlusion(&self) -> (f32, f32) {
+        self.occlusion_state.value()
+    }
+
+    /// Returns velocity of the sound in world space. It is calculated automatically from the
+    /// movement of the node and used to calculate Doppler shift.
+    pub fn velocity(&self) -> Vector3<f32> {
+        self.velocity
+    }
 }
 
 impl ConstructorProvider<Node, Graph> for Sound {
@@ -443,6 +634,30 @@ impl NodeTrait for Sound {
     }
 
     fn update(&mut self, context: &mut UpdateContext) {
+        let position = self.global_position();
+        self.velocity = match self.prev_position.replace(position) {
+            Some(prev_position) if context.dt > 0.0 => {
+                (position - prev_position).scale(1.0 / context.dt)
+            }
+            _ => Vector3::default(),
+        };
+        if *self.occlusion_enabled
+            && context.sound_context.occlusion.enabled
+            && self.spatial_blend() > 0.0
+        {
+            let listener_position = context.sound_context.listener_position();
+            self.occlusion_state.update(
+                &mut context.sound_context.occlusion,
+                context.physics,
+                context.nodes,
+                listener_position,
+                position,
+                context.dt,
+            );
+        } else {
+            self.occlusion_state.reset();
+        }
+        context.sound_context.set_sound_velocity_and_occlusion(self);
         context.sound_context.sync_with_sound(self);
     }
 
@@ -485,6 +700,13 @@ pub struct SoundBuilder {
     playback_time: Duration,
     spatial_blend: f32,
     audio_bus: String,
+    cone_inner_angle: f32,
+    cone_outer_angle: f32,
+    cone_outer_gain: f32,
+    cone_outer_gain_hf: f32,
+    doppler_factor: f32,
+    occlusion_enabled: bool,
+    reverb_send: f32,
 }
 
 impl SoundBuilder {
@@ -505,6 +727,13 @@ impl SoundBuilder {
             spatial_blend: 1.0,
             playback_time: Default::default(),
             audio_bus: AudioBusGraph::PRIMARY_BUS.to_string(),
+            cone_inner_angle: std::f32::consts::TAU,
+            cone_outer_angle: std::f32::consts::TAU,
+            cone_outer_gain: 0.0,
+            cone_outer_gain_hf: 1.0,
+            doppler_factor: 1.0,
+            occlusion_enabled: true,
+            reverb_send: 1.0,
         }
     }
 
@@ -573,6 +802,43 @@ impl SoundBuilder {
         fn with_audio_bus(audio_bus: String)
     );
 
+    define_with!(
+        /// Sets desired inner cone angle. See [`Sound::set_cone_inner_angle`] for more info.
+        fn with_cone_inner_angle(cone_inner_angle: f32)
+    );
+
+    define_with!(
+        /// Sets desired outer cone angle. See [`Sound::set_cone_outer_angle`] for more info.
+        fn with_cone_outer_angle(cone_outer_angle: f32)
+    );
+
+    define_with!(
+        /// Sets desired outer cone gain. See [`Sound::set_cone_outer_gain`] for more info.
+        fn with_cone_outer_gain(cone_outer_gain: f32)
+    );
+
+    define_with!(
+        /// Sets desired outer cone high frequency gain. See [`Sound::set_cone_outer_gain_hf`] for
+        /// more info.
+        fn with_cone_outer_gain_hf(cone_outer_gain_hf: f32)
+    );
+
+    define_with!(
+        /// Sets desired Doppler factor. See [`Sound::set_doppler_factor`] for more info.
+        fn with_doppler_factor(doppler_factor: f32)
+    );
+
+    define_with!(
+        /// Sets whether the sound is affected by occlusion. See [`Sound::set_occlusion_enabled`]
+        /// for more info.
+        fn with_occlusion_enabled(occlusion_enabled: bool)
+    );
+
+    define_with!(
+        /// Sets desired reverb send level. See [`Sound::set_reverb_send`] for more info.
+        fn with_reverb_send(reverb_send: f32)
+    );
+
     /// Creates a new [`Sound`] node.
     #[must_use]
     pub fn build_sound(self) -> Sound {
@@ -591,6 +857,16 @@ impl SoundBuilder {
             playback_time: self.playback_time.as_secs_f32().into(),
             spatial_blend: self.spatial_blend.into(),
             audio_bus: self.audio_bus.into(),
+            cone_inner_angle: self.cone_inner_angle.into(),
+            cone_outer_angle: self.cone_outer_angle.into(),
+            cone_outer_gain: self.cone_outer_gain.into(),
+            cone_outer_gain_hf: self.cone_outer_gain_hf.into(),
+            doppler_factor: self.doppler_factor.into(),
+            velocity: Default::default(),
+            prev_position: None,
+            occlusion_enabled: self.occlusion_enabled.into(),
+            reverb_send: self.reverb_send.into(),
+            occlusion_state: Default::default(),
             native: Default::default(),
         }
     }
@@ -606,3 +882,72 @@ impl SoundBuilder {
         graph.add_node(self.build_node()).to_variant()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::Vector2,
+        scene::{
+            base::BaseBuilder,
+            graph::Graph,
+            sound::{AudioBus, DataSource, SoundBufferResource, SoundBuilder, SoundEngine, Status},
+        },
+    };
+    use fyrox_graph::SceneGraph;
+    use fyrox_sound::buffer::SoundBufferResourceExtension;
+
+    #[test]
+    fn test_stolen_voices_are_synced_back() {
+        let mut graph = Graph::new();
+        {
+            let mut state = graph.sound_context.state();
+            let mut bus = AudioBus::new("Limited".to_string());
+            bus.set_max_voices(Some(1));
+            let primary = state.bus_graph_ref().primary_bus_handle();
+            state.bus_graph_mut().add_bus(bus, primary);
+        }
+
+        let buffer = SoundBufferResource::new_generic(DataSource::Raw {
+            sample_rate: 44100,
+            channel_count: 1,
+            samples: vec![0.5; 44100],
+        })
+        .unwrap();
+        let sound = |graph: &mut Graph, play_once: bool| {
+            SoundBuilder::new(BaseBuilder::new())
+                .with_buffer(Some(buffer.clone()))
+                .with_audio_bus("Limited".to_string())
+                .with_status(Status::Playing)
+                .with_play_once(play_once)
+                .build(graph)
+        };
+        let kept = sound(&mut graph, false);
+        let stolen = sound(&mut graph, false);
+        let stolen_play_once = sound(&mut graph, true);
+
+        let update = |graph: &mut Graph| {
+            graph.update(Vector2::new(800.0, 600.0), 1.0 / 60.0, Default::default())
+        };
+
+        // Creates native sources.
+        update(&mut graph);
+
+        let engine = SoundEngine::without_device(44100);
+        engine
+            .state()
+            .add_context(graph.sound_context.native.clone());
+        let mut buf = vec![(0.0, 0.0); fyrox_sound::engine::State::render_buffer_len()];
+        // Stops the voices over the limit.
+        engine.state().render(&mut buf);
+
+        update(&mut graph);
+        assert_eq!(graph[kept].status(), Status::Playing);
+        assert_eq!(graph[stolen].status(), Status::Stopped);
+        assert_eq!(graph[stolen_play_once].status(), Status::Stopped);
+
+        // Stopped play-once sounds must be removed from the graph.
+        update(&mut graph);
+        assert!(graph.try_get(stolen_play_once).is_err());
+        assert!(graph.try_get(stolen).is_ok());
+    }
+}
@@ -0,0 +1,390 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Geometry-aware sound occlusion. See [`SoundOcclusion`] docs for more info.
+
+use crate::{
+    core::{
+        algebra::{Point3, Vector3},
+        color::Color,
+        math::lerpf,
+        reflect::prelude::*,
+        visitor::prelude::*,
+    },
+    scene::{
+        collider::InteractionGroups,
+        debug::{Line, SceneDrawingContext},
+        graph::{
+            physics::{Intersection, PhysicsWorld, RayCastOptions},
+            NodePool,
+        },
+    },
+};
+
+/// Defines how much sound passes through a collider. Every collider has its own material, see
+/// [`crate::scene::collider::Collider::set_sound_occlusion`].
+#[derive(Clone, Copy, Debug, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "5b0c6f9e-2a44-4d0f-9a4b-7f8e3c1d6a25")]
+pub struct SoundOcclusionMaterial {
+    /// A fraction of sound volume that passes through the collider. 1.0 - the collider does not
+    /// occlude sounds at all, 0.0 - the collider blocks sounds completely.
+    #[reflect(min_value = 0.0, max_value = 1.0, step = 0.05)]
+    pub gain: f32,
+
+    /// A fraction of high frequencies that passes through the collider. Lower values make sounds
+    /// behind the collider muffled.
+    #[reflect(min_value = 0.0, max_value = 1.0, step = 0.05)]
+    pub gain_hf: f32,
+}
+
+impl Default for SoundOcclusionMaterial {
+    fn default() -> Self {
+        Self {
+            gain: 0.5,
+            gain_hf: 0.25,
+        }
+    }
+}
+
+impl SoundOcclusionMaterial {
+    /// A material that does not occlude sounds at all.
+    pub const TRANSPARENT: Self = Self {
+        gain: 1.0,
+        gain_hf: 1.0,
+    };
+}
+
+/// A ray between the listener and a sound, that was used to calculate occlusion. It is stored only
+/// if [`SoundOcclusion::debug_draw`] is set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OcclusionDebugRay {
+    /// Position of the listener.
+    pub begin: Vector3<f32>,
+    /// Position of the sound.
+    pub end: Vector3<f32>,
+    /// Points at which the ray enters occluding colliders.
+    pub hits: Vec<Vector3<f32>>,
+    /// Current occlusion gain of the sound.
+    pub gain: f32,
+}
+
+/// Sound occlusion settings of a scene. When enabled, every spatial sound casts rays against the
+/// physics world to the listener and every collider between them attenuates the sound according
+/// to its [`SoundOcclusionMaterial`]. Both volume and high frequencies are attenuated, so sounds
+/// behind walls are quieter and muffled. Ray casting is done a few times per second (see
+/// [`Self::update_rate`]) and the attenuation changes smoothly between the updates.
+///
+/// Colliders that contain the listener or the sound (for example, a capsule of a character that
+/// holds the camera) are ignored, as well as sensors.
+#[derive(Clone, Debug, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "d0e1a0d4-8e6b-4f3c-a5c4-2b7d9f1e0c83")]
+pub struct SoundOcclusion {
+    /// Enables or disables occlusion. Disabled by default.
+    pub enabled: bool,
+
+    /// How many times per second the occlusion of each sound is recalculated.
+    #[reflect(min_value = 0.0, step = 1.0)]
+    pub update_rate: f32,
+
+    /// Time (in seconds) that is needed for attenuation to (mostly) reach its new value. It is
+    /// used to prevent abrupt changes of volume when an obstacle appears or disappears.
+    #[reflect(min_value = 0.0, step = 0.01)]
+    pub smoothing_time: f32,
+
+    /// Collision groups that will be used for ray casting. It can be used to exclude some
+    /// colliders from the occlusion calculation.
+    pub collision_groups: InteractionGroups,
+
+    /// If set, rays of every sound will be collected and can be drawn using [`Self::draw`].
+    pub debug_draw: bool,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    pub(crate) debug_rays: Vec<OcclusionDebugRay>,
+}
+
+impl Default for SoundOcclusion {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            update_rate: 10.0,
+            smoothing_time: 0.1,
+            collision_groups: Default::default(),
+            debug_draw: false,
+            debug_rays: Default::default(),
+        }
+    }
+}
+
+impl SoundOcclusion {
+    /// Returns rays that were used to calculate occlusion on the last update. It is filled only
+    /// if [`Self::debug_draw`] is set.
+    pub fn debug_rays(&self) -> &[OcclusionDebugRay] {
+        &self.debug_rays
+    }
+
+    /// Draws occlusion rays of every sound. Unoccluded rays are green, the more a sound is occluded
+    /// the more red its ray is. Hit points are marked with small spheres.
+    pub fn draw(&self, context: &mut SceneDrawingContext) {
+        for ray in self.debug_rays.iter() {
+            let color = Color::opaque(
+                ((1.0 - ray.gain) * 255.0) as u8,
+                (ray.gain * 255.0) as u8,
+                0,
+            );
+            context.add_line(Line {
+                begin: ray.begin,
+                end: ray.end,
+                color,
+            });
+            for hit in ray.hits.iter() {
+                context.draw_wire_sphere(*hit, 0.1, 8, Color::RED);
+            }
+        }
+    }
+}
+
+/// Occlusion state of a single sound.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct OcclusionState {
+    timer: f32,
+    initialized: bool,
+    target: (f32, f32),
+    current: (f32, f32),
+    hits: Vec<Vector3<f32>>,
+}
+
+impl OcclusionState {
+    /// Updates the occlusion and returns a new pair of gain and high frequency gain.
+    pub(crate) fn update(
+        &mut self,
+        settings: &mut SoundOcclusion,
+        physics: &PhysicsWorld,
+        nodes: &NodePool,
+        listener_position: Vector3<f32>,
+        sound_position: Vector3<f32>,
+        dt: f32,
+    ) -> (f32, f32) {
+        self.timer -= dt;
+        if !self.initialized || self.timer <= 0.0 {
+            self.target = trace(
+                physics,
+                nodes,
+                listener_position,
+                sound_position,
+                settings.collision_groups,
+                &mut self.hits,
+            );
+            self.timer = if settings.update_rate > 0.0 {
+                1.0 / settings.update_rate
+            } else {
+                0.0
+            };
+            if !self.initialized {
+                // Do not fade in the first value, the sound must start with correct attenuation.
+                self.current = self.target;
+                self.initialized = true;
+            }
+        }
+
+        let t = if settings.smoothing_time > 0.0 {
+            1.0 - (-dt / settings.smoothing_time).exp()
+        } else {
+            1.0
+        };
+        self.current = (
+            lerpf(self.current.0, self.target.0, t),
+            lerpf(self.current.1, self.target.1, t),
+        );
+
+        if settings.debug_draw {
+            settings.debug_rays.push(OcclusionDebugRay {
+                begin: listener_position,
+                end: sound_position,
+                hits: self.hits.clone(),
+                gain: self.current.0,
+            });
+        }
+
+        self.current
+    }
+
+    /// Returns current pair of gain and high frequency gain.
+    pub(crate) fn value(&self) -> (f32, f32) {
+        if self.initialized {
+            self.current
+        } else {
+            (1.0, 1.0)
+        }
+    }
+
+    /// Resets the state, so the next update will recalculate the occlusion immediately.
+    pub(crate) fn reset(&mut self) {
+        *self = Default::default();
+    }
+}
+
+fn cast_ray(
+    physics: &PhysicsWorld,
+    from: Vector3<f32>,
+    direction: Vector3<f32>,
+    max_len: f32,
+    groups: InteractionGroups,
+) -> Vec<Intersection> {
+    let mut intersections = Vec::new();
+    physics.cast_ray(
+        RayCastOptions {
+            ray_origin: Point3::from(from),
+            ray_direction: direction,
+            max_len,
+            groups,
+            sort_results: true,
+        },
+        &mut intersections,
+    );
+    intersections
+}
+
+// Returns a pair of gain and high frequency gain caused by the colliders between two points.
+fn trace(
+    physics: &PhysicsWorld,
+    nodes: &NodePool,
+    from: Vector3<f32>,
+    to: Vector3<f32>,
+    groups: InteractionGroups,
+    hits: &mut Vec<Vector3<f32>>,
+) -> (f32, f32) {
+    hits.clear();
+
+    let direction = to - from;
+    let distance = direction.norm();
+    if distance <= f32::EPSILON {
+        return (1.0, 1.0);
+    }
+
+    // Rays are cast in both directions, a collider occludes the sound only if it is hit from both
+    // sides. Otherwise, one of the points is inside the collider and it must be ignored.
+    let forward = cast_ray(physics, from, direction, distance, groups);
+    let backward = cast_ray(physics, to, -direction, distance, groups);
+
+    let mut gain = (1.0, 1.0);
+    for intersection in forward.iter().filter(|i| i.toi > f32::EPSILON) {
+        if !backward
+            .iter()
+            .any(|i| i.collider == intersection.collider && i.toi > f32::EPSILON)
+        {
+            continue;
+        }
+
+        let Ok(collider) = nodes.try_get(intersection.collider) else {
+            continue;
+        };
+        if collider.is_sensor() {
+            continue;
+        }
+
+        let material = collider.sound_occlusion();
+        gain.0 *= material.gain;
+        gain.1 *= material.gain_hf;
+        hits.push(intersection.position.coords);
+    }
+    gain
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::{Vector2, Vector3},
+        scene::{
+            base::BaseBuilder,
+            collider::{ColliderBuilder, ColliderShape},
+            graph::Graph,
+            rigidbody::{RigidBodyBuilder, RigidBodyType},
+            sound::{occlusion::SoundOcclusionMaterial, SoundBuilder},
+            transform::TransformBuilder,
+        },
+    };
+
+    fn add_box(graph: &mut Graph, position: Vector3<f32>, half_extents: Vector3<f32>) {
+        let collider = ColliderBuilder::new(BaseBuilder::new())
+            .with_shape(ColliderShape::cuboid(
+                half_extents.x,
+                half_extents.y,
+                half_extents.z,
+            ))
+            .with_sound_occlusion(SoundOcclusionMaterial {
+                gain: 0.5,
+                gain_hf: 0.25,
+            })
+            .build(graph);
+        RigidBodyBuilder::new(
+            BaseBuilder::new()
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(position)
+                        .build(),
+                )
+                .with_child(collider),
+        )
+        .with_body_type(RigidBodyType::Static)
+        .build(graph);
+    }
+
+    #[test]
+    fn test_sound_occlusion() {
+        let mut graph = Graph::new();
+        graph.sound_context.occlusion_mut().enabled = true;
+
+        // A wall between the listener (at the origin) and the sound.
+        add_box(
+            &mut graph,
+            Vector3::new(0.0, 0.0, 5.0),
+            Vector3::new(2.0, 2.0, 0.1),
+        );
+        // A box that contains the listener must be ignored.
+        add_box(&mut graph, Vector3::default(), Vector3::new(0.5, 0.5, 0.5));
+
+        let occluded = SoundBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 0.0, 10.0))
+                    .build(),
+            ),
+        )
+        .build(&mut graph);
+        let free = SoundBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 0.0, -10.0))
+                    .build(),
+            ),
+        )
+        .build(&mut graph);
+
+        for _ in 0..3 {
+            graph.update(Vector2::new(800.0, 600.0), 1.0, Default::default());
+        }
+
+        let (gain, gain_hf) = graph[occluded].occlusion();
+        assert!((gain - 0.5).abs() < 1.0e-3);
+        assert!((gain_hf - 0.25).abs() < 1.0e-3);
+        assert_eq!(graph[free].occlusion(), (1.0, 1.0));
+    }
+}
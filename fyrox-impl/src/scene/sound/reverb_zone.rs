@@ -0,0 +1,695 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Reverb zones and environmental reverb blending. See [`ReverbZone`] and [`SoundEnvironment`] docs
+//! for more info.
+
+use crate::{
+    core::{
+        algebra::{Point3, Vector3},
+        color::Color,
+        math::{aabb::AxisAlignedBoundingBox, lerpf},
+        pool::Handle,
+        reflect::prelude::*,
+        uuid::{uuid, Uuid},
+        variable::InheritableVariable,
+        visitor::prelude::*,
+    },
+    scene::{
+        base::{Base, BaseBuilder},
+        debug::SceneDrawingContext,
+        graph::Graph,
+        node::{constructor::NodeConstructor, Node, NodeTrait, UpdateContext},
+        sound::{reverb::Reverb, AudioBus, Effect},
+    },
+};
+use fyrox_graph::{constructor::ConstructorProvider, SceneGraph};
+use std::ops::{Deref, DerefMut};
+use strum_macros::{AsRefStr, EnumString, VariantNames};
+
+/// A set of parameters of environmental reverb.
+#[derive(Clone, Copy, Debug, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "b34f9511-9507-4681-a39b-a8567f2e476d")]
+pub struct ReverbPreset {
+    /// Duration of reverberation (in seconds). Larger environments have longer reverberation.
+    #[reflect(min_value = 0.0, step = 0.1)]
+    pub decay_time: f32,
+
+    /// Normalized cutoff frequency of reflections, see [`Reverb::set_fc`] for more info. Lower
+    /// values make reflections muffled.
+    #[reflect(min_value = 0.0, max_value = 1.0, step = 0.01)]
+    pub fc: f32,
+
+    /// Gain of the reverb bus, 0.0 - no reverberation at all.
+    #[reflect(min_value = 0.0, step = 0.05)]
+    pub gain: f32,
+}
+
+impl Default for ReverbPreset {
+    fn default() -> Self {
+        Self::ROOM
+    }
+}
+
+impl ReverbPreset {
+    /// A small room with short reverberation.
+    pub const ROOM: Self = Self {
+        decay_time: 0.6,
+        fc: 0.25,
+        gain: 0.4,
+    };
+
+    /// A large hall with long and bright reverberation.
+    pub const HALL: Self = Self {
+        decay_time: 2.5,
+        fc: 0.2,
+        gain: 0.5,
+    };
+
+    /// A cave with very long and muffled reverberation.
+    pub const CAVE: Self = Self {
+        decay_time: 4.0,
+        fc: 0.1,
+        gain: 0.6,
+    };
+
+    /// Open space without reverberation.
+    pub const OUTDOORS: Self = Self {
+        decay_time: 0.5,
+        fc: 0.25,
+        gain: 0.0,
+    };
+
+    /// Linearly interpolates every parameter of two presets.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            decay_time: lerpf(self.decay_time, other.decay_time, t),
+            fc: lerpf(self.fc, other.fc, t),
+            gain: lerpf(self.gain, other.gain, t),
+        }
+    }
+}
+
+/// A contribution of a single reverb zone, collected during the update of a scene graph.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ReverbZoneContribution {
+    pub(crate) weight: f32,
+    pub(crate) priority: i32,
+    pub(crate) preset: ReverbPreset,
+    /// Distance from the listener to the origin of the zone.
+    pub(crate) distance: f32,
+    pub(crate) handle: Handle<Node>,
+}
+
+/// Environmental reverb settings of a scene. When enabled, every [`ReverbZone`] in the scene
+/// contributes its reverb preset according to the position of the listener and the resulting
+/// parameters are applied to the first [`Reverb`] effect of the reverb bus. If the bus does not
+/// exist, it is created automatically. Every sound sends its signal to the reverb bus, the amount
+/// can be changed by [`super::Sound::set_reverb_send`].
+///
+/// When the listener is not inside of any zone, [`Self::default_preset`] is used.
+#[derive(Clone, Debug, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "68111a83-8b5d-4e08-b2db-48c3c6a369ab")]
+pub struct SoundEnvironment {
+    /// Enables or disables environmental reverb. Disabled by default.
+    pub enabled: bool,
+
+    /// Name of the audio bus with the reverb effect.
+    pub reverb_bus: String,
+
+    /// Reverb preset that is used when the listener is outside of any zone.
+    pub default_preset: ReverbPreset,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    pub(crate) zones: Vec<ReverbZoneContribution>,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    current: Option<ReverbPreset>,
+}
+
+impl Default for SoundEnvironment {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            reverb_bus: Self::DEFAULT_REVERB_BUS.to_string(),
+            default_preset: ReverbPreset::OUTDOORS,
+            zones: Default::default(),
+            current: None,
+        }
+    }
+}
+
+impl SoundEnvironment {
+    /// Default name of the reverb bus.
+    pub const DEFAULT_REVERB_BUS: &'static str = "Reverb";
+
+    /// Returns the reverb preset that was applied on the last update. It is `None` if the
+    /// environment is disabled.
+    pub fn current_preset(&self) -> Option<ReverbPreset> {
+        self.current
+    }
+
+    // Zones with higher priority override zones with lower priority, the rest of the weight goes
+    // to the default preset. Zones with the same priority are ordered by their distance to the
+    // listener (the closest one wins) and then by their handles, so the result does not depend on
+    // the order of the nodes in the graph.
+    fn blend(&mut self) -> ReverbPreset {
+        self.zones.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then_with(|| a.distance.total_cmp(&b.distance))
+                .then_with(|| {
+                    (a.handle.index(), a.handle.generation())
+                        .cmp(&(b.handle.index(), b.handle.generation()))
+                })
+        });
+
+        let mut remaining = 1.0;
+        let mut result = ReverbPreset {
+            decay_time: 0.0,
+            fc: 0.0,
+            gain: 0.0,
+        };
+        for zone in self.zones.iter() {
+            let weight = zone.weight * remaining;
+            result.decay_time += zone.preset.decay_time * weight;
+            result.fc += zone.preset.fc * weight;
+            result.gain += zone.preset.gain * weight;
+            remaining -= weight;
+        }
+        result.decay_time += self.default_preset.decay_time * remaining;
+        result.fc += self.default_preset.fc * remaining;
+        result.gain += self.default_preset.gain * remaining;
+        result
+    }
+
+    pub(crate) fn update(&mut self, native: &fyrox_sound::context::SoundContext) {
+        let mut state = native.state();
+
+        if !self.enabled {
+            if self.current.take().is_some() {
+                state.set_reverb_bus(None::<&str>);
+            }
+            self.zones.clear();
+            return;
+        }
+
+        let preset = self.blend();
+        self.zones.clear();
+
+        state.set_reverb_bus(Some(&self.reverb_bus));
+
+        let graph = state.bus_graph_mut();
+        if !graph.buses_iter().any(|bus| bus.name() == self.reverb_bus) {
+            let mut reverb = Reverb::new();
+            // The bus receives a copy of the signal of every sound, so it must output only the
+            // reflections.
+            reverb.set_dry(0.0);
+            let mut bus = AudioBus::new(self.reverb_bus.clone());
+            bus.add_effect(Effect::Reverb(reverb));
+            graph.add_bus(bus, graph.primary_bus_handle());
+        }
+
+        if let Some(bus) = graph
+            .buses_iter_mut()
+            .find(|bus| bus.name() == self.reverb_bus)
+        {
+            bus.set_gain(preset.gain);
+            if let Some(Effect::Reverb(reverb)) = bus
+                .effects_mut()
+                .find(|effect| matches!(effect, Effect::Reverb(_)))
+            {
+                // Changing decay time re-calculates the filters, so do this only when needed.
+                if reverb.decay_time() != preset.decay_time {
+                    reverb.set_decay_time(preset.decay_time);
+                }
+                if reverb.fc() != preset.fc {
+                    reverb.set_fc(preset.fc);
+                }
+            }
+        }
+
+        self.current = Some(preset);
+    }
+}
+
+/// Shape of a reverb zone.
+#[derive(Clone, Debug, PartialEq, Visit, Reflect, AsRefStr, EnumString, VariantNames)]
+#[reflect(type_uuid = "11456799-ade6-4d8c-9457-1d539789ea0e")]
+pub enum ReverbZoneShape {
+    /// A sphere with the given radius.
+    Sphere {
+        /// Radius of the sphere.
+        #[reflect(min_value = 0.0, step = 0.1)]
+        radius: f32,
+    },
+    /// A box with the given half extents.
+    Box {
+        /// Half extents of the box.
+        half_extents: Vector3<f32>,
+    },
+}
+
+impl Default for ReverbZoneShape {
+    fn default() -> Self {
+        Self::Box {
+            half_extents: Vector3::repeat(5.0),
+        }
+    }
+}
+
+impl ReverbZoneShape {
+    fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
+        match self {
+            ReverbZoneShape::Sphere { radius } => AxisAlignedBoundingBox::from_radius(*radius),
+            ReverbZoneShape::Box { half_extents } => {
+                AxisAlignedBoundingBox::from_min_max(-*half_extents, *half_extents)
+            }
+        }
+    }
+
+    // Returns the closest point of the shape to the given point (in local coordinates).
+    fn closest_point(&self, point: Vector3<f32>) -> Vector3<f32> {
+        match self {
+            ReverbZoneShape::Sphere { radius } => {
+                let distance = point.norm();
+                if distance <= *radius {
+                    point
+                } else {
+                    point.scale(*radius / distance)
+                }
+            }
+            ReverbZoneShape::Box { half_extents } => Vector3::new(
+                point.x.clamp(-half_extents.x, half_extents.x),
+                point.y.clamp(-half_extents.y, half_extents.y),
+                point.z.clamp(-half_extents.z, half_extents.z),
+            ),
+        }
+    }
+}
+
+/// Reverb zone is a volume (sphere or box) with a reverb preset, that is applied to the
+/// environmental reverb of the scene when the listener is inside of the zone. Outside of the zone,
+/// the influence of the preset linearly decreases with the distance to the zone and disappears
+/// completely at [`Self::blend_distance`]. This way the reverb parameters smoothly change when the
+/// listener moves from one environment to another.
+///
+/// Zones could overlap, in this case a zone with higher priority overrides zones with lower
+/// priority. For example, a small room inside a large hall should have higher priority than the
+/// hall. Environmental reverb itself must be enabled in the sound context of the scene, see
+/// [`SoundEnvironment`] for more info.
+///
+/// The shape of the zone is transformed by the global transform of the node.
+#[derive(Visit, Reflect, PartialEq, Default, Clone, Debug)]
+#[reflect(
+    derived_type = "Node",
+    type_uuid = "e3724285-0033-49b5-bec3-1351aa13fd74"
+)]
+pub struct ReverbZone {
+    base: Base,
+
+    #[reflect(setter = "set_shape")]
+    shape: InheritableVariable<ReverbZoneShape>,
+
+    #[reflect(setter = "set_preset")]
+    preset: InheritableVariable<ReverbPreset>,
+
+    #[reflect(setter = "set_blend_distance", min_value = 0.0, step = 0.1)]
+    blend_distance: InheritableVariable<f32>,
+
+    #[reflect(setter = "set_priority")]
+    priority: InheritableVariable<i32>,
+}
+
+impl Deref for ReverbZone {
+    type Target = Base;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl DerefMut for ReverbZone {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+impl ConstructorProvider<Node, Graph> for ReverbZone {
+    fn constructor() -> NodeConstructor {
+        NodeConstructor::new::<Self>()
+            .with_variant("Reverb Zone", |_| {
+                ReverbZoneBuilder::new(BaseBuilder::new().with_name("Reverb Zone"))
+                    .build_node()
+                    .into()
+            })
+            .with_group("Sound")
+    }
+}
+
+impl ReverbZone {
+    /// Sets new shape of the zone.
+    pub fn set_shape(&mut self, shape: ReverbZoneShape) -> ReverbZoneShape {
+        self.shape.set_value_and_mark_modified(shape)
+    }
+
+    /// Returns current shape of the zone.
+    pub fn shape(&self) -> &ReverbZoneShape {
+        &self.shape
+    }
+
+    /// Sets new reverb preset of the zone.
+    pub fn set_preset(&mut self, preset: ReverbPreset) -> ReverbPreset {
+        self.preset.set_value_and_mark_modified(preset)
+    }
+
+    /// Returns current reverb preset of the zone.
+    pub fn preset(&self) -> &ReverbPreset {
+        &self.preset
+    }
+
+    /// Sets a distance (from the surface of the zone) at which the influence of the zone disappears.
+    /// 0.0 means that the reverb changes abruptly when the listener crosses the surface. Default is
+    /// 2.0.
+    pub fn set_blend_distance(&mut self, distance: f32) -> f32 {
+        self.blend_distance
+            .set_value_and_mark_modified(distance.max(0.0))
+    }
+
+    /// Returns current blend distance.
+    pub fn blend_distance(&self) -> f32 {
+        *self.blend_distance
+    }
+
+    /// Sets priority of the zone. Zones with higher priority override zones with lower priority.
+    /// Default is 0.
+    pub fn set_priority(&mut self, priority: i32) -> i32 {
+        self.priority.set_value_and_mark_modified(priority)
+    }
+
+    /// Returns priority of the zone.
+    pub fn priority(&self) -> i32 {
+        *self.priority
+    }
+
+    /// Calculates influence of the zone at the given point in world coordinates. Returns 1.0 if
+    /// the point is inside of the zone and linearly decreases to 0.0 at the blend distance.
+    pub fn weight_at(&self, point: Vector3<f32>) -> f32 {
+        let transform = self.global_transform();
+        let Some(inv_transform) = transform.try_inverse() else {
+            return 0.0;
+        };
+
+        let local_point = inv_transform.transform_point(&Point3::from(point)).coords;
+        let closest = self.shape.closest_point(local_point);
+        if closest == local_point {
+            return 1.0;
+        }
+
+        let distance = (transform.transform_point(&Point3::from(closest)).coords - point).norm();
+        if *self.blend_distance > 0.0 {
+            (1.0 - distance / *self.blend_distance).max(0.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+impl NodeTrait for ReverbZone {
+    fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.shape.local_bounding_box()
+    }
+
+    fn world_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.local_bounding_box()
+            .transform(&self.global_transform())
+    }
+
+    fn id(&self) -> Uuid {
+        <Self as Reflect>::type_info().type_uuid
+    }
+
+    fn update(&mut self, context: &mut UpdateContext) {
+        if !self.is_globally_enabled() || !context.sound_context.environment.enabled {
+            return;
+        }
+
+        let listener_position = context.sound_context.listener_position();
+        let weight = self.weight_at(listener_position);
+        if weight > 0.0 {
+            context
+                .sound_context
+                .environment
+                .zones
+                .push(ReverbZoneContribution {
+                    weight,
+                    priority: *self.priority,
+                    preset: *self.preset,
+                    distance: (self.global_position() - listener_position).norm(),
+                    handle: self.handle(),
+                });
+        }
+    }
+
+    fn debug_draw(&self, ctx: &mut SceneDrawingContext) {
+        match *self.shape {
+            ReverbZoneShape::Sphere { radius } => {
+                ctx.draw_wire_sphere(self.global_position(), radius, 30, Color::BLUE)
+            }
+            ReverbZoneShape::Box { .. } => ctx.draw_oob(
+                &self.shape.local_bounding_box(),
+                self.global_transform(),
+                Color::BLUE,
+            ),
+        }
+    }
+}
+
+/// Allows you to create reverb zones in declarative manner.
+pub struct ReverbZoneBuilder {
+    base_builder: BaseBuilder,
+    shape: ReverbZoneShape,
+    preset: ReverbPreset,
+    blend_distance: f32,
+    priority: i32,
+}
+
+impl ReverbZoneBuilder {
+    /// Creates new reverb zone builder.
+    pub fn new(base_builder: BaseBuilder) -> Self {
+        Self {
+            base_builder,
+            shape: Default::default(),
+            preset: Default::default(),
+            blend_distance: 2.0,
+            priority: 0,
+        }
+    }
+
+    /// Sets desired shape of the zone.
+    pub fn with_shape(mut self, shape: ReverbZoneShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Sets desired reverb preset of the zone.
+    pub fn with_preset(mut self, preset: ReverbPreset) -> Self {
+        self.preset = preset;
+        self
+    }
+
+    /// Sets desired blend distance. See [`ReverbZone::set_blend_distance`] for more info.
+    pub fn with_blend_distance(mut self, blend_distance: f32) -> Self {
+        self.blend_distance = blend_distance;
+        self
+    }
+
+    /// Sets desired priority. See [`ReverbZone::set_priority`] for more info.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Creates reverb zone instance.
+    pub fn build_reverb_zone(self) -> ReverbZone {
+        ReverbZone {
+            base: self.base_builder.build_base(),
+            shape: self.shape.into(),
+            preset: self.preset.into(),
+            blend_distance: self.blend_distance.into(),
+            priority: self.priority.into(),
+        }
+    }
+
+    /// Creates [`ReverbZone`] node.
+    pub fn build_node(self) -> Node {
+        Node::new(self.build_reverb_zone())
+    }
+
+    /// Creates [`ReverbZone`] node and adds it to the scene graph.
+    pub fn build(self, graph: &mut Graph) -> Handle<ReverbZone> {
+        graph.add_node(self.build_node()).to_variant()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::{Vector2, Vector3},
+        scene::{
+            base::BaseBuilder,
+            graph::Graph,
+            sound::{
+                listener::ListenerBuilder,
+                reverb_zone::{ReverbPreset, ReverbZoneBuilder, ReverbZoneShape},
+                Effect,
+            },
+            transform::TransformBuilder,
+        },
+    };
+
+    #[test]
+    fn test_reverb_zone_blending() {
+        let mut graph = Graph::new();
+        graph.sound_context.environment_mut().enabled = true;
+
+        ReverbZoneBuilder::new(BaseBuilder::new())
+            .with_shape(ReverbZoneShape::Box {
+                half_extents: Vector3::repeat(5.0),
+            })
+            .with_preset(ReverbPreset::CAVE)
+            .with_blend_distance(2.0)
+            .build(&mut graph);
+
+        let listener = ListenerBuilder::new(BaseBuilder::new()).build(&mut graph);
+
+        let preset_at = |graph: &mut Graph, x: f32| {
+            graph[listener]
+                .local_transform_mut()
+                .set_position(Vector3::new(x, 0.0, 0.0));
+            graph.update(Vector2::new(800.0, 600.0), 1.0 / 60.0, Default::default());
+            graph.sound_context.environment().current_preset().unwrap()
+        };
+
+        assert_eq!(preset_at(&mut graph, 0.0), ReverbPreset::CAVE);
+        assert_eq!(preset_at(&mut graph, 20.0), ReverbPreset::OUTDOORS);
+        let half = preset_at(&mut graph, 6.0);
+        let expected = ReverbPreset::OUTDOORS.lerp(&ReverbPreset::CAVE, 0.5);
+        assert!((half.decay_time - expected.decay_time).abs() < 1.0e-4);
+        assert!((half.gain - expected.gain).abs() < 1.0e-4);
+
+        // The reverb bus must be created and configured automatically.
+        let state = graph.sound_context.state();
+        let bus = state
+            .bus_graph_ref()
+            .buses_iter()
+            .find(|bus| bus.name() == "Reverb")
+            .unwrap();
+        assert!((bus.gain() - expected.gain).abs() < 1.0e-4);
+        let Some(Effect::Reverb(reverb)) = bus.effect(0) else {
+            panic!("reverb effect expected");
+        };
+        assert!((reverb.decay_time() - expected.decay_time).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn test_reverb_zone_priority() {
+        let mut graph = Graph::new();
+        graph.sound_context.environment_mut().enabled = true;
+
+        ReverbZoneBuilder::new(BaseBuilder::new())
+            .with_shape(ReverbZoneShape::Sphere { radius: 50.0 })
+            .with_preset(ReverbPreset::HALL)
+            .build(&mut graph);
+        ReverbZoneBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(10.0, 0.0, 0.0))
+                    .build(),
+            ),
+        )
+        .with_shape(ReverbZoneShape::Sphere { radius: 2.0 })
+        .with_preset(ReverbPreset::ROOM)
+        .with_priority(1)
+        .build(&mut graph);
+
+        ListenerBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(10.0, 0.0, 0.0))
+                    .build(),
+            ),
+        )
+        .build(&mut graph);
+
+        graph.update(Vector2::new(800.0, 600.0), 1.0 / 60.0, Default::default());
+
+        assert_eq!(
+            graph.sound_context.environment().current_preset(),
+            Some(ReverbPreset::ROOM)
+        );
+    }
+
+    #[test]
+    fn test_reverb_zone_equal_priority() {
+        let preset_with_order = |reversed: bool| {
+            let mut graph = Graph::new();
+            graph.sound_context.environment_mut().enabled = true;
+
+            let mut zones = [
+                (Vector3::new(0.0, 0.0, 0.0), ReverbPreset::HALL),
+                (Vector3::new(3.0, 0.0, 0.0), ReverbPreset::ROOM),
+            ];
+            if reversed {
+                zones.reverse();
+            }
+            for (position, preset) in zones {
+                ReverbZoneBuilder::new(
+                    BaseBuilder::new().with_local_transform(
+                        TransformBuilder::new()
+                            .with_local_position(position)
+                            .build(),
+                    ),
+                )
+                .with_shape(ReverbZoneShape::Sphere { radius: 50.0 })
+                .with_preset(preset)
+                .build(&mut graph);
+            }
+
+            ListenerBuilder::new(
+                BaseBuilder::new().with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(Vector3::new(2.0, 0.0, 0.0))
+                        .build(),
+                ),
+            )
+            .build(&mut graph);
+
+            graph.update(Vector2::new(800.0, 600.0), 1.0 / 60.0, Default::default());
+            graph.sound_context.environment().current_preset()
+        };
+
+        // The listener is inside of both zones, the closest one wins regardless of the order.
+        assert_eq!(preset_with_order(false), Some(ReverbPreset::ROOM));
+        assert_eq!(preset_with_order(true), Some(ReverbPreset::ROOM));
+    }
+}
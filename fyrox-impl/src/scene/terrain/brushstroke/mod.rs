@@ -776,6 +776,11 @@ pub enum BrushTarget {
     },
     /// Modifies the terrain's holes
     HoleMask,
+    /// Draws on a density mask of a given foliage layer
+    FoliageMask {
+        /// The number of the foliage layer to modify
+        layer: usize,
+    },
 }
 
 /// Brush is used to modify terrain. It supports multiple shapes and modes.
@@ -1016,6 +1021,17 @@ impl ChunkData {
             std::mem::swap(a, b);
         }
     }
+    /// Swap the content of this data with the content of the given chunk's foliage density mask.
+    pub fn swap_foliage_mask(&mut self, chunk: &mut Chunk, layer: usize) {
+        let mut data_ref = chunk.foliage_masks[layer].data_ref();
+        if !self.verify_texture_size(&data_ref) {
+            return;
+        }
+        let mut modify = data_ref.modify();
+        for (a, b) in modify.data_mut().iter_mut().zip(self.content.iter_mut()) {
+            std::mem::swap(a, b);
+        }
+    }
     /// Swap the height data of the a chunk from the list with the height data in this object.
     /// The given list of chunks will be searched to find the chunk that matches `grid_position`.
     pub fn swap_height_from_list(&mut self, chunks: &mut [Chunk]) {
@@ -1046,4 +1062,14 @@ impl ChunkData {
             }
         }
     }
+    /// Swap the foliage density mask data of a particular layer of a chunk from the list with the data in this object.
+    /// The given list of chunks will be searched to find the chunk that matches `grid_position`.
+    pub fn swap_foliage_mask_from_list(&mut self, chunks: &mut [Chunk], layer: usize) {
+        for c in chunks {
+            if c.grid_position == self.grid_position {
+                self.swap_foliage_mask(c, layer);
+                break;
+            }
+        }
+    }
 }
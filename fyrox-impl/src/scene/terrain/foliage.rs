@@ -0,0 +1,789 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Foliage (grass, bushes, trees, rocks, etc.) scattering for terrains. See [`FoliageLayer`] docs
+//! for more info.
+
+use crate::{
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector2, Vector3},
+        parking_lot::Mutex,
+        pool::Handle,
+        rand::{prelude::StdRng, Rng, SeedableRng},
+        reflect::prelude::*,
+        visitor::prelude::*,
+        SafeLock,
+    },
+    fxhash::FxHashMap,
+    graphics::ElementRange,
+    renderer::{
+        self,
+        bundle::{RenderContext, SurfaceInstanceData},
+    },
+    resource::texture::TextureResource,
+    scene::{
+        base::BaseBuilder,
+        collider::{ColliderBuilder, ColliderShape},
+        graph::Graph,
+        mesh::{surface::Surface, RenderPath},
+        node::Node,
+        rigidbody::{RigidBodyBuilder, RigidBodyType},
+        terrain::{create_layer_mask, Chunk, Terrain},
+        transform::TransformBuilder,
+    },
+};
+use fyrox_graph::SceneGraph;
+use std::hash::{Hash, Hasher};
+use strum_macros::{AsRefStr, EnumString, VariantNames};
+
+/// A shape of colliders that will be created for every instance of a foliage layer by
+/// [`Terrain::bake_foliage_colliders`]. The size of the shape is multiplied by the scale of
+/// each instance.
+#[derive(Clone, Debug, PartialEq, Default, Visit, Reflect, AsRefStr, EnumString, VariantNames)]
+#[reflect(type_uuid = "b6e2b9f4-2f5a-4a43-9c0f-8a3c6a0f1f36")]
+pub enum FoliageCollider {
+    /// No colliders will be created. This is the best option for small details, such as grass.
+    #[default]
+    None,
+    /// Vertical capsule, which bottom point is at the origin of an instance. Good for trees.
+    Capsule {
+        /// Radius of the capsule.
+        radius: f32,
+        /// Total height of the capsule, including the caps.
+        height: f32,
+    },
+    /// Box, which bottom face is at the origin of an instance. Good for rocks.
+    Cuboid {
+        /// Half extents of the box.
+        half_extents: Vector3<f32>,
+    },
+}
+
+/// Foliage layer defines a single type of detail meshes (grass, trees, etc.) that are scattered
+/// procedurally over a terrain. Every chunk of the terrain has a density mask for each foliage
+/// layer, that defines where the instances could appear; the mask can be painted using
+/// [`crate::scene::terrain::BrushTarget::FoliageMask`] brush target.
+///
+/// ## Scattering
+///
+/// Instances are scattered randomly, but deterministically: the same seed, the same density mask
+/// and the same height map always produce the same set of instances. The amount of candidate
+/// positions is defined by [`Self::density`] and the area of a chunk, then every candidate is
+/// accepted with the probability defined by the density mask. Accepted candidates are then filtered
+/// by the slope and the height rules. Scattering results are cached and recalculated only if
+/// either the layer or the terrain data was changed.
+///
+/// ## Rendering
+///
+/// All instances of a layer share the same surfaces and materials, so they're rendered using
+/// instancing. Instances that are further than [`Self::draw_distance`] from the observer are not
+/// rendered at all, instances in the last [`Self::fade_distance`] meters before the draw distance
+/// are gradually scaled down to make their disappearance smooth.
+#[derive(Debug, Clone, Visit, Reflect, PartialEq)]
+#[reflect(type_uuid = "0d9a7cb5-4b61-4f8f-a1e5-3d4b3f6c1a27")]
+pub struct FoliageLayer {
+    /// Name of the layer, it is used only for convenience.
+    pub name: String,
+
+    /// Set of surfaces that will be rendered for every instance of the layer.
+    pub surfaces: Vec<Surface>,
+
+    /// Maximum amount of instances per square meter (when the density mask is fully opaque).
+    #[reflect(min_value = 0.0)]
+    pub density: f32,
+
+    /// Seed of the random number generator, that is used to scatter the instances.
+    pub seed: u64,
+
+    /// Minimum random scale of instances.
+    #[reflect(min_value = 0.0)]
+    pub min_scale: f32,
+
+    /// Maximum random scale of instances.
+    #[reflect(min_value = 0.0)]
+    pub max_scale: f32,
+
+    /// Whether instances should be randomly rotated around their vertical axis or not.
+    pub random_rotation: bool,
+
+    /// Defines how much the instances should be aligned with the normal of the terrain surface.
+    /// 0.0 - instances are always vertical, 1.0 - instances are fully aligned with the normal.
+    #[reflect(min_value = 0.0, max_value = 1.0)]
+    pub align_to_normal: f32,
+
+    /// Minimum slope angle (in degrees) of the terrain surface where the instances could appear.
+    #[reflect(min_value = 0.0, max_value = 90.0)]
+    pub min_slope: f32,
+
+    /// Maximum slope angle (in degrees) of the terrain surface where the instances could appear.
+    #[reflect(min_value = 0.0, max_value = 90.0)]
+    pub max_slope: f32,
+
+    /// Minimum height of the terrain surface (in local coordinates of the terrain) where the
+    /// instances could appear.
+    pub min_height: f32,
+
+    /// Maximum height of the terrain surface (in local coordinates of the terrain) where the
+    /// instances could appear.
+    pub max_height: f32,
+
+    /// Maximum distance from the observer at which the instances are rendered.
+    #[reflect(min_value = 0.0)]
+    pub draw_distance: f32,
+
+    /// Length of the zone before the draw distance, where the instances are gradually scaled down.
+    #[reflect(min_value = 0.0)]
+    pub fade_distance: f32,
+
+    /// Whether the instances should cast shadows or not.
+    pub cast_shadows: bool,
+
+    /// A shape of colliders that will be created for the instances of the layer. See
+    /// [`Terrain::bake_foliage_colliders`] for more info.
+    pub collider: FoliageCollider,
+}
+
+impl Default for FoliageLayer {
+    fn default() -> Self {
+        Self {
+            name: "Foliage".to_string(),
+            surfaces: Default::default(),
+            density: 1.0,
+            seed: 0,
+            min_scale: 0.8,
+            max_scale: 1.2,
+            random_rotation: true,
+            align_to_normal: 0.0,
+            min_slope: 0.0,
+            max_slope: 45.0,
+            min_height: -f32::MAX,
+            max_height: f32::MAX,
+            draw_distance: 100.0,
+            fade_distance: 10.0,
+            cast_shadows: false,
+            collider: Default::default(),
+        }
+    }
+}
+
+impl FoliageLayer {
+    fn hash_scatter_parameters<H: Hasher>(&self, hasher: &mut H) {
+        self.seed.hash(hasher);
+        for v in [
+            self.density,
+            self.min_scale,
+            self.max_scale,
+            self.align_to_normal,
+            self.min_slope,
+            self.max_slope,
+            self.min_height,
+            self.max_height,
+        ] {
+            v.to_bits().hash(hasher);
+        }
+        self.random_rotation.hash(hasher);
+    }
+
+    /// Calculates a scale factor of an instance at the given distance from the observer. It is
+    /// 1.0 for close instances and goes down to 0.0 at the draw distance.
+    pub fn fade_factor(&self, distance: f32) -> f32 {
+        if distance >= self.draw_distance {
+            0.0
+        } else if self.fade_distance <= 0.0 {
+            1.0
+        } else {
+            ((self.draw_distance - distance) / self.fade_distance).clamp(0.0, 1.0)
+        }
+    }
+}
+
+/// A single instance of a foliage layer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FoliageInstance {
+    /// Position of the instance in local coordinates of the terrain.
+    pub position: Vector3<f32>,
+    /// Rotation of the instance relative to the terrain.
+    pub rotation: UnitQuaternion<f32>,
+    /// Uniform scale of the instance.
+    pub scale: f32,
+}
+
+impl FoliageInstance {
+    /// Returns local transform of the instance relative to the terrain.
+    pub fn local_transform(&self) -> Matrix4<f32> {
+        Matrix4::new_translation(&self.position)
+            * self.rotation.to_homogeneous()
+            * Matrix4::new_scaling(self.scale)
+    }
+}
+
+struct CachedInstances {
+    key: u64,
+    instances: Vec<FoliageInstance>,
+}
+
+/// Scattering results of every foliage layer of every chunk. Cache is not serialized and it is
+/// never shared between terrain copies.
+#[derive(Default)]
+pub(super) struct FoliageCache(Mutex<FxHashMap<(Vector2<i32>, usize), CachedInstances>>);
+
+impl std::fmt::Debug for FoliageCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FoliageCache")
+    }
+}
+
+impl Clone for FoliageCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl PartialEq for FoliageCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Samples the height map of the chunk at the given (fractional) height pixel position. Margins
+/// are included, so it is possible to sample one pixel outside the chunk.
+fn bilinear(chunk: &Chunk, pixel: Vector2<f32>) -> f32 {
+    let data = chunk.height_data();
+    let max = chunk.height_map_size.map(|x| x as i32 - 2);
+    let x0 = (pixel.x.floor() as i32).clamp(-1, max.x);
+    let y0 = (pixel.y.floor() as i32).clamp(-1, max.y);
+    let x1 = (x0 + 1).min(max.x);
+    let y1 = (y0 + 1).min(max.y);
+    let tx = (pixel.x - x0 as f32).clamp(0.0, 1.0);
+    let ty = (pixel.y - y0 as f32).clamp(0.0, 1.0);
+    let h00 = data[Vector2::new(x0, y0)];
+    let h10 = data[Vector2::new(x1, y0)];
+    let h01 = data[Vector2::new(x0, y1)];
+    let h11 = data[Vector2::new(x1, y1)];
+    let a = h00 + (h10 - h00) * tx;
+    let b = h01 + (h11 - h01) * tx;
+    a + (b - a) * ty
+}
+
+impl Terrain {
+    /// Returns a reference to a slice with foliage layers of the terrain.
+    pub fn foliage_layers(&self) -> &[FoliageLayer] {
+        &self.foliage_layers
+    }
+
+    /// Returns a mutable reference to a slice with foliage layers of the terrain.
+    pub fn foliage_layers_mut(&mut self) -> &mut [FoliageLayer] {
+        self.foliage_layers.get_value_mut_and_mark_modified()
+    }
+
+    /// Sets new foliage layers. Every chunk will get new empty density masks for new layers, and
+    /// the density masks of the removed layers will be removed from every chunk.
+    pub fn set_foliage_layers(&mut self, layers: Vec<FoliageLayer>) -> Vec<FoliageLayer> {
+        let count = layers.len();
+        let old = self.foliage_layers.set_value_and_mark_modified(layers);
+        let mask_size = *self.mask_size;
+        for chunk in self.chunks.iter_mut() {
+            chunk.foliage_masks.truncate(count);
+            while chunk.foliage_masks.len() < count {
+                chunk
+                    .foliage_masks
+                    .push(create_layer_mask(mask_size.x, mask_size.y, 0));
+            }
+        }
+        old
+    }
+
+    /// Adds new foliage layer together with its density masks for each chunk. If there's not
+    /// enough masks, then empty masks will be created.
+    pub fn add_foliage_layer(&mut self, layer: FoliageLayer, masks: Vec<TextureResource>) {
+        self.insert_foliage_layer(layer, masks, self.foliage_layers.len())
+    }
+
+    /// Inserts the foliage layer at the given index together with its density masks for each chunk.
+    pub fn insert_foliage_layer(
+        &mut self,
+        layer: FoliageLayer,
+        mut masks: Vec<TextureResource>,
+        index: usize,
+    ) {
+        self.foliage_layers
+            .get_value_mut_and_mark_modified()
+            .insert(index, layer);
+
+        for chunk in self.chunks.iter_mut().rev() {
+            let mask = masks
+                .pop()
+                .unwrap_or_else(|| create_layer_mask(self.mask_size.x, self.mask_size.y, 0));
+            chunk.foliage_masks.insert(index, mask);
+        }
+    }
+
+    /// Removes a foliage layer at the given index together with its respective density masks
+    /// from each chunk.
+    pub fn remove_foliage_layer(&mut self, index: usize) -> (FoliageLayer, Vec<TextureResource>) {
+        let layer = self
+            .foliage_layers
+            .get_value_mut_and_mark_modified()
+            .remove(index);
+        let masks = self
+            .chunks
+            .iter_mut()
+            .map(|chunk| chunk.foliage_masks.remove(index))
+            .collect();
+        (layer, masks)
+    }
+
+    /// Removes the last foliage layer together with its respective density masks from each chunk.
+    pub fn pop_foliage_layer(&mut self) -> Option<(FoliageLayer, Vec<TextureResource>)> {
+        if self.foliage_layers.is_empty() {
+            None
+        } else {
+            Some(self.remove_foliage_layer(self.foliage_layers.len() - 1))
+        }
+    }
+
+    /// Return the value of the foliage density mask at the given mask pixel position.
+    pub fn get_foliage_mask(&self, position: Vector2<i32>, layer: usize) -> Option<u8> {
+        let chunk_pos = self.chunk_containing_mask_pos(position);
+        let chunk = self.find_chunk(chunk_pos)?;
+        let origin = self.chunk_mask_pos_origin(chunk_pos);
+        let pos = (position - origin).map(|x| x as usize);
+        let index = pos.y * self.mask_size.x as usize + pos.x;
+        let texture_data = chunk.foliage_masks.get(layer)?.data_ref();
+        texture_data.data().get(index).copied()
+    }
+
+    fn foliage_cache_key(&self, chunk: &Chunk, layer_index: usize) -> Option<u64> {
+        let layer = self.foliage_layers.get(layer_index)?;
+        let mask = chunk.foliage_masks.get(layer_index)?;
+        let mut hasher = fxhash::FxHasher64::default();
+        layer.hash_scatter_parameters(&mut hasher);
+        chunk.heightmap().key().hash(&mut hasher);
+        chunk
+            .heightmap()
+            .data_ref()
+            .modifications_count()
+            .hash(&mut hasher);
+        mask.key().hash(&mut hasher);
+        mask.data_ref().modifications_count().hash(&mut hasher);
+        if let Some(holes) = chunk.hole_mask.as_ref() {
+            holes.key().hash(&mut hasher);
+            holes.data_ref().modifications_count().hash(&mut hasher);
+        }
+        chunk.physical_size.x.to_bits().hash(&mut hasher);
+        chunk.physical_size.y.to_bits().hash(&mut hasher);
+        Some(hasher.finish())
+    }
+
+    /// Scatters the instances of the given foliage layer over the given chunk. This method does
+    /// not use the cache, see [`Self::foliage_instances`] for cached version.
+    pub fn scatter_foliage(&self, chunk: &Chunk, layer_index: usize) -> Vec<FoliageInstance> {
+        let (Some(layer), Some(mask)) = (
+            self.foliage_layers.get(layer_index),
+            chunk.foliage_masks.get(layer_index),
+        ) else {
+            return Vec::new();
+        };
+
+        let physical_size = chunk.physical_size;
+        let area = physical_size.x * physical_size.y;
+        let candidates = (layer.density.max(0.0) * area).round() as usize;
+        if candidates == 0 {
+            return Vec::new();
+        }
+
+        let mut hasher = fxhash::FxHasher64::default();
+        layer.seed.hash(&mut hasher);
+        chunk.grid_position.hash(&mut hasher);
+        let mut rng = StdRng::seed_from_u64(hasher.finish());
+
+        let mask_data = mask.data_ref();
+        let mask = mask_data.data();
+        let hole_data = chunk.hole_mask.as_ref().map(|holes| holes.data_ref());
+        let holes = hole_data.as_ref().map(|data| data.data());
+        let mask_size = *self.mask_size;
+        let hole_size = self.hole_mask_size();
+        let height_cells = chunk.height_map_size.map(|x| (x - 3) as f32);
+        let cell = Vector2::new(
+            physical_size.x / height_cells.x,
+            physical_size.y / height_cells.y,
+        );
+        let min_cos = layer.max_slope.to_radians().cos();
+        let max_cos = layer.min_slope.to_radians().cos();
+        let min_scale = layer.min_scale.min(layer.max_scale);
+        let max_scale = layer.min_scale.max(layer.max_scale);
+
+        let mut instances = Vec::new();
+        for _ in 0..candidates {
+            // Generate every random value for every candidate, so the results of the candidate
+            // do not depend on whether the previous candidates were rejected or not.
+            let k = Vector2::new(rng.gen::<f32>(), rng.gen::<f32>());
+            let acceptance = rng.gen::<f32>();
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let scale = min_scale + (max_scale - min_scale) * rng.gen::<f32>();
+
+            let mask_pixel = k.component_mul(&mask_size.map(|x| x as f32));
+            let mask_index = (mask_pixel.y as usize).min(mask_size.y as usize - 1)
+                * mask_size.x as usize
+                + (mask_pixel.x as usize).min(mask_size.x as usize - 1);
+            if acceptance * 255.0 >= mask[mask_index] as f32 {
+                continue;
+            }
+
+            if let Some(holes) = holes {
+                let hole_pixel = k.component_mul(&hole_size.map(|x| x as f32));
+                let hole_index = (hole_pixel.y as usize).min(hole_size.y as usize - 1)
+                    * hole_size.x as usize
+                    + (hole_pixel.x as usize).min(hole_size.x as usize - 1);
+                if holes[hole_index] < 128 {
+                    continue;
+                }
+            }
+
+            let height_pixel = k.component_mul(&height_cells);
+            let height = bilinear(chunk, height_pixel);
+            if height < layer.min_height || height > layer.max_height {
+                continue;
+            }
+
+            let dx = bilinear(chunk, height_pixel + Vector2::new(1.0, 0.0))
+                - bilinear(chunk, height_pixel - Vector2::new(1.0, 0.0));
+            let dz = bilinear(chunk, height_pixel + Vector2::new(0.0, 1.0))
+                - bilinear(chunk, height_pixel - Vector2::new(0.0, 1.0));
+            let normal = Vector3::new(-dx / (2.0 * cell.x), 1.0, -dz / (2.0 * cell.y))
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(Vector3::y);
+            if normal.y < min_cos || normal.y > max_cos {
+                continue;
+            }
+
+            let up = Vector3::y().lerp(&normal, layer.align_to_normal.clamp(0.0, 1.0));
+            let tilt = UnitQuaternion::rotation_between(&Vector3::y(), &up)
+                .unwrap_or_else(UnitQuaternion::identity);
+            let yaw = if layer.random_rotation {
+                UnitQuaternion::from_axis_angle(&Vector3::y_axis(), angle)
+            } else {
+                UnitQuaternion::identity()
+            };
+
+            let local = k.component_mul(&physical_size) + chunk.local_position();
+            instances.push(FoliageInstance {
+                position: Vector3::new(local.x, height, local.y),
+                rotation: tilt * yaw,
+                scale,
+            });
+        }
+
+        instances
+    }
+
+    /// Returns the instances of the given foliage layer on the chunk at the given grid position.
+    /// Scattering results are cached and recalculated only when the layer settings or the chunk
+    /// data were changed.
+    pub fn foliage_instances(
+        &self,
+        grid_position: Vector2<i32>,
+        layer_index: usize,
+    ) -> Vec<FoliageInstance> {
+        let Some(chunk) = self.find_chunk(grid_position) else {
+            return Vec::new();
+        };
+        self.with_foliage_instances(chunk, layer_index, |instances| instances.to_vec())
+    }
+
+    fn with_foliage_instances<R>(
+        &self,
+        chunk: &Chunk,
+        layer_index: usize,
+        func: impl FnOnce(&[FoliageInstance]) -> R,
+    ) -> R {
+        let Some(key) = self.foliage_cache_key(chunk, layer_index) else {
+            return func(&[]);
+        };
+        let mut cache = self.foliage_cache.0.safe_lock();
+        let entry = cache
+            .entry((chunk.grid_position, layer_index))
+            .or_insert_with(|| CachedInstances {
+                key: !key,
+                instances: Vec::new(),
+            });
+        if entry.key != key {
+            entry.instances = self.scatter_foliage(chunk, layer_index);
+            entry.key = key;
+        }
+        func(&entry.instances)
+    }
+
+    pub(super) fn collect_foliage_render_data(&self, ctx: &mut RenderContext) {
+        let is_shadow_pass = renderer::is_shadow_pass(ctx.render_pass_name);
+        let global_transform = self.global_transform();
+        let observer_position = ctx.observer_position.translation;
+
+        for (layer_index, layer) in self.foliage_layers.iter().enumerate() {
+            if layer.surfaces.is_empty() || (is_shadow_pass && !layer.cast_shadows) {
+                continue;
+            }
+
+            for chunk in self.chunks.iter() {
+                // Quick rejection of the chunks that are too far from the observer.
+                let chunk_center = global_transform.transform_point(
+                    &(chunk.position()
+                        + Vector3::new(
+                            chunk.physical_size.x * 0.5,
+                            0.0,
+                            chunk.physical_size.y * 0.5,
+                        ))
+                    .into(),
+                );
+                let chunk_radius = chunk.physical_size.norm() * 0.5;
+                if (chunk_center.coords - observer_position).norm() - chunk_radius
+                    > layer.draw_distance
+                {
+                    continue;
+                }
+
+                self.with_foliage_instances(chunk, layer_index, |instances| {
+                    for instance in instances {
+                        let world_position =
+                            global_transform.transform_point(&instance.position.into());
+                        let distance = (world_position.coords - observer_position).norm();
+                        let fade = layer.fade_factor(distance);
+                        if fade <= 0.0 {
+                            continue;
+                        }
+                        if ctx.frustum.is_some_and(|f| {
+                            !f.is_intersects_sphere(world_position.coords, instance.scale)
+                        }) {
+                            continue;
+                        }
+
+                        let world_transform = global_transform
+                            * instance.local_transform()
+                            * Matrix4::new_scaling(fade);
+                        for surface in layer.surfaces.iter() {
+                            ctx.storage.push(
+                                surface.data_ref(),
+                                surface.material(),
+                                RenderPath::Deferred,
+                                layer_index as u64,
+                                SurfaceInstanceData {
+                                    world_transform,
+                                    bone_matrices: Default::default(),
+                                    blend_shapes_weights: Default::default(),
+                                    element_range: ElementRange::Full,
+                                    node_handle: self.handle(),
+                                },
+                            );
+                        }
+                    }
+                });
+            }
+        }
+    }
+
+    /// Creates static rigid bodies with colliders for every instance of every foliage layer that
+    /// has [`FoliageLayer::collider`] set. A single rigid body is created per layer and it is
+    /// attached to the terrain. Returns handles of the created rigid bodies, they should be
+    /// removed manually before baking the colliders again.
+    pub fn bake_foliage_colliders(graph: &mut Graph, terrain: Handle<Node>) -> Vec<Handle<Node>> {
+        let Some(terrain_ref) = graph.try_get_of_type::<Terrain>(terrain).ok() else {
+            return Vec::new();
+        };
+
+        let mut bodies = Vec::new();
+        for (layer_index, layer) in terrain_ref.foliage_layers.iter().enumerate() {
+            if layer.collider == FoliageCollider::None {
+                continue;
+            }
+            let mut colliders = Vec::new();
+            for chunk in terrain_ref.chunks.iter() {
+                terrain_ref.with_foliage_instances(chunk, layer_index, |instances| {
+                    for instance in instances {
+                        let (shape, offset) = match layer.collider {
+                            FoliageCollider::None => continue,
+                            FoliageCollider::Capsule { radius, height } => {
+                                let radius = radius * instance.scale;
+                                let half_height = (height * instance.scale * 0.5 - radius).max(0.0);
+                                (
+                                    ColliderShape::capsule_y(half_height, radius),
+                                    half_height + radius,
+                                )
+                            }
+                            FoliageCollider::Cuboid { half_extents } => {
+                                let half_extents = half_extents * instance.scale;
+                                (
+                                    ColliderShape::cuboid(
+                                        half_extents.x,
+                                        half_extents.y,
+                                        half_extents.z,
+                                    ),
+                                    half_extents.y,
+                                )
+                            }
+                        };
+                        colliders.push(
+                            ColliderBuilder::new(
+                                BaseBuilder::new().with_local_transform(
+                                    TransformBuilder::new()
+                                        .with_local_position(
+                                            instance.position
+                                                + instance.rotation.transform_vector(
+                                                    &Vector3::new(0.0, offset, 0.0),
+                                                ),
+                                        )
+                                        .with_local_rotation(instance.rotation)
+                                        .build(),
+                                ),
+                            )
+                            .with_shape(shape)
+                            .build_node(),
+                        );
+                    }
+                });
+            }
+
+            let body = RigidBodyBuilder::new(
+                BaseBuilder::new().with_name(format!("{}Colliders", layer.name)),
+            )
+            .with_body_type(RigidBodyType::Static)
+            .build_node();
+            bodies.push((body, colliders));
+        }
+
+        bodies
+            .into_iter()
+            .map(|(body, colliders)| {
+                let body = graph.add_node(body);
+                for collider in colliders {
+                    let collider = graph.add_node(collider);
+                    graph.link_nodes(collider, body);
+                }
+                graph.link_nodes(body, terrain);
+                body
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::terrain::TerrainBuilder;
+
+    fn make_terrain(layer: FoliageLayer) -> Terrain {
+        let node = TerrainBuilder::new(BaseBuilder::new())
+            .with_width_chunks(0..2)
+            .with_length_chunks(0..1)
+            .with_height_map_size(Vector2::new(11, 11))
+            .with_mask_size(Vector2::new(4, 4))
+            .with_chunk_size(Vector2::new(8.0, 8.0))
+            .build_node();
+        let mut terrain = node.cast::<Terrain>().unwrap().clone();
+        terrain.set_foliage_layers(vec![layer]);
+        terrain
+    }
+
+    fn fill_mask(terrain: &mut Terrain, value: u8) {
+        for chunk in terrain.chunks_mut() {
+            let mut data = chunk.foliage_masks[0].data_ref();
+            let mut modify = data.modify();
+            modify.data_mut().fill(value);
+        }
+    }
+
+    #[test]
+    fn empty_mask_gives_no_instances() {
+        let terrain = make_terrain(FoliageLayer::default());
+        assert_eq!(terrain.chunks_ref()[0].foliage_masks.len(), 1);
+        assert!(terrain.foliage_instances(Vector2::new(0, 0), 0).is_empty());
+    }
+
+    #[test]
+    fn scattering_is_deterministic() {
+        let mut terrain = make_terrain(FoliageLayer::default());
+        fill_mask(&mut terrain, 255);
+        let a = terrain.foliage_instances(Vector2::new(0, 0), 0);
+        // Full density mask on a flat terrain accepts every candidate.
+        assert_eq!(a.len(), 64);
+        let b = terrain.scatter_foliage(&terrain.chunks_ref()[0], 0);
+        assert_eq!(a, b);
+        // Different chunks have different instances.
+        let c = terrain.foliage_instances(Vector2::new(1, 0), 0);
+        assert_ne!(a, c);
+        for instance in c {
+            assert!((8.0..=16.0).contains(&instance.position.x));
+            assert!((0.8..=1.2).contains(&instance.scale));
+        }
+    }
+
+    #[test]
+    fn cache_is_invalidated() {
+        let mut terrain = make_terrain(FoliageLayer::default());
+        fill_mask(&mut terrain, 255);
+        assert_eq!(terrain.foliage_instances(Vector2::new(0, 0), 0).len(), 64);
+        fill_mask(&mut terrain, 0);
+        assert!(terrain.foliage_instances(Vector2::new(0, 0), 0).is_empty());
+        fill_mask(&mut terrain, 255);
+        terrain.foliage_layers_mut()[0].density = 0.5;
+        assert_eq!(terrain.foliage_instances(Vector2::new(0, 0), 0).len(), 32);
+    }
+
+    #[test]
+    fn height_and_slope_rules() {
+        let mut terrain = make_terrain(FoliageLayer {
+            min_height: 1.0,
+            ..Default::default()
+        });
+        fill_mask(&mut terrain, 255);
+        assert!(terrain.foliage_instances(Vector2::new(0, 0), 0).is_empty());
+
+        terrain.foliage_layers_mut()[0].min_height = -f32::MAX;
+        terrain.foliage_layers_mut()[0].max_slope = 10.0;
+        // Steep slope along X axis: 45 degrees.
+        terrain.for_each_height_map_pixel(|height, position| *height = position.x);
+        assert!(terrain.foliage_instances(Vector2::new(0, 0), 0).is_empty());
+        terrain.foliage_layers_mut()[0].max_slope = 50.0;
+        assert_eq!(terrain.foliage_instances(Vector2::new(0, 0), 0).len(), 64);
+    }
+
+    #[test]
+    fn fade_factor() {
+        let layer = FoliageLayer {
+            draw_distance: 100.0,
+            fade_distance: 10.0,
+            ..Default::default()
+        };
+        assert_eq!(layer.fade_factor(0.0), 1.0);
+        assert_eq!(layer.fade_factor(90.0), 1.0);
+        assert_eq!(layer.fade_factor(95.0), 0.5);
+        assert_eq!(layer.fade_factor(100.0), 0.0);
+    }
+
+    #[test]
+    fn add_remove_layers() {
+        let mut terrain = make_terrain(FoliageLayer::default());
+        terrain.add_foliage_layer(FoliageLayer::default(), Vec::new());
+        assert_eq!(terrain.foliage_layers().len(), 2);
+        assert_eq!(terrain.chunks_ref()[1].foliage_masks.len(), 2);
+        let (_, masks) = terrain.remove_foliage_layer(0);
+        assert_eq!(masks.len(), 2);
+        assert_eq!(terrain.chunks_ref()[0].foliage_masks.len(), 1);
+    }
+}
@@ -0,0 +1,701 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Import and export of whole-terrain height maps and layer masks. A terrain stores its heights
+//! in a set of per-chunk textures with one-pixel margins and shared edges, which is not something
+//! external tools (World Machine, Gaea, etc.) understand. This module merges chunk data into a
+//! single image and splits a single image back into chunks. See [`HeightMapImage`] and
+//! [`LayerMaskImage`] for more info.
+
+use crate::{
+    core::{algebra::Vector2, log::Log},
+    scene::terrain::{resize_f32, resize_u8, Terrain},
+};
+use image::{DynamicImage, ImageBuffer, ImageFormat, Luma, Rgb};
+use std::{
+    fmt::{Display, Formatter},
+    io::Cursor,
+    path::Path,
+};
+
+/// All possible errors that may occur during height map or layer mask import/export.
+#[derive(Debug)]
+pub enum HeightMapError {
+    /// An i/o error has occurred.
+    Io(std::io::Error),
+    /// Image encoding or decoding has failed.
+    Image(image::ImageError),
+    /// Unable to deduce the format of a file from its extension.
+    UnknownFormat(String),
+    /// The amount of pixels does not match the size of the image.
+    InvalidSize {
+        /// Expected amount of pixels.
+        expected: usize,
+        /// Actual amount of pixels.
+        actual: usize,
+    },
+    /// Raw height maps do not store their size, and it could not be deduced, because the
+    /// data is not square.
+    UnknownRawSize(usize),
+    /// A terrain does not have a layer with the given index.
+    InvalidLayer(usize),
+}
+
+impl std::error::Error for HeightMapError {}
+
+impl Display for HeightMapError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeightMapError::Io(v) => write!(f, "An i/o error has occurred: {v}"),
+            HeightMapError::Image(v) => write!(f, "Image error: {v}"),
+            HeightMapError::UnknownFormat(v) => {
+                write!(f, "Unable to deduce height map format from {v} extension.")
+            }
+            HeightMapError::InvalidSize { expected, actual } => {
+                write!(
+                    f,
+                    "Invalid amount of pixels. Expected {expected}, but got {actual}."
+                )
+            }
+            HeightMapError::UnknownRawSize(len) => {
+                write!(
+                    f,
+                    "Unable to deduce size of raw height map of {len} bytes. \
+                    Raw height maps must be square or have explicitly specified size."
+                )
+            }
+            HeightMapError::InvalidLayer(v) => write!(f, "Terrain has no layer {v}."),
+        }
+    }
+}
+
+impl From<std::io::Error> for HeightMapError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<image::ImageError> for HeightMapError {
+    fn from(value: image::ImageError) -> Self {
+        Self::Image(value)
+    }
+}
+
+/// A file format of an external height map.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum HeightMapFormat {
+    /// Headerless array of little-endian 16-bit unsigned integers, row by row. This is the
+    /// format that is used by the most of terrain generators. The size of such height map
+    /// is not stored in the file, so it must be either square or specified explicitly.
+    Raw16,
+    /// 16-bit grayscale PNG image.
+    Png16,
+    /// OpenEXR image with 32-bit floating point values. Heights are stored as is, without
+    /// any normalization.
+    Exr,
+}
+
+impl HeightMapFormat {
+    /// Tries to deduce the format from a file extension (`raw`/`r16`, `png` or `exr`).
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "raw" | "r16" => Some(Self::Raw16),
+            "png" => Some(Self::Png16),
+            "exr" => Some(Self::Exr),
+            _ => None,
+        }
+    }
+
+    fn from_path(path: &Path) -> Result<Self, HeightMapError> {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Self::from_extension(&extension).ok_or(HeightMapError::UnknownFormat(extension))
+    }
+}
+
+/// Defines how 16-bit height values are mapped to the actual heights. `0` corresponds to
+/// `min` and `u16::MAX` corresponds to `max`. Floating point formats ignore the range.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HeightRange {
+    /// The height that corresponds to the lowest possible 16-bit value.
+    pub min: f32,
+    /// The height that corresponds to the highest possible 16-bit value.
+    pub max: f32,
+}
+
+impl Default for HeightRange {
+    fn default() -> Self {
+        Self { min: 0.0, max: 1.0 }
+    }
+}
+
+impl HeightRange {
+    fn normalize(&self, height: f32) -> u16 {
+        let range = self.max - self.min;
+        let k = if range == 0.0 {
+            0.0
+        } else {
+            (height - self.min) / range
+        };
+        (k.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+    }
+
+    fn denormalize(&self, value: u16) -> f32 {
+        self.min + (value as f32 / u16::MAX as f32) * (self.max - self.min)
+    }
+}
+
+/// A height map of a whole terrain. Unlike per-chunk height maps, it has no margins and no
+/// duplicated pixels along the chunk edges, so it could be used by external tools directly.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeightMapImage {
+    size: Vector2<u32>,
+    heights: Vec<f32>,
+}
+
+impl HeightMapImage {
+    /// Creates a new height map of the given size. The amount of heights must be equal to
+    /// `size.x * size.y`.
+    pub fn new(size: Vector2<u32>, heights: Vec<f32>) -> Result<Self, HeightMapError> {
+        let expected = size.x as usize * size.y as usize;
+        if heights.len() != expected {
+            return Err(HeightMapError::InvalidSize {
+                expected,
+                actual: heights.len(),
+            });
+        }
+        Ok(Self { size, heights })
+    }
+
+    /// Returns the size of the height map in pixels.
+    pub fn size(&self) -> Vector2<u32> {
+        self.size
+    }
+
+    /// Returns the heights, row by row.
+    pub fn heights(&self) -> &[f32] {
+        &self.heights
+    }
+
+    /// Returns height at the given pixel, if it is within bounds.
+    pub fn get(&self, position: Vector2<u32>) -> Option<f32> {
+        if position.x < self.size.x && position.y < self.size.y {
+            self.heights
+                .get((position.y * self.size.x + position.x) as usize)
+                .copied()
+        } else {
+            None
+        }
+    }
+
+    /// Calculates the actual range of heights in the height map. It is useful to export the
+    /// height map in 16-bit formats with maximum precision.
+    pub fn height_range(&self) -> HeightRange {
+        let min = self.heights.iter().copied().reduce(f32::min).unwrap_or(0.0);
+        let max = self.heights.iter().copied().reduce(f32::max).unwrap_or(0.0);
+        HeightRange { min, max }
+    }
+
+    /// Creates a resampled copy of the height map.
+    pub fn resized(&self, new_size: Vector2<u32>) -> Self {
+        if new_size == self.size || self.heights.is_empty() {
+            return self.clone();
+        }
+        Self {
+            size: new_size,
+            heights: resize_f32(self.heights.clone(), self.size, new_size),
+        }
+    }
+
+    /// Encodes the height map in the given format. `range` defines how the heights are mapped
+    /// to 16-bit values, it is ignored by floating point formats.
+    pub fn encode(
+        &self,
+        format: HeightMapFormat,
+        range: HeightRange,
+    ) -> Result<Vec<u8>, HeightMapError> {
+        match format {
+            HeightMapFormat::Raw16 => Ok(self
+                .heights
+                .iter()
+                .flat_map(|h| range.normalize(*h).to_le_bytes())
+                .collect()),
+            HeightMapFormat::Png16 => {
+                let pixels = self.heights.iter().map(|h| range.normalize(*h)).collect();
+                let image =
+                    ImageBuffer::<Luma<u16>, Vec<u16>>::from_raw(self.size.x, self.size.y, pixels)
+                        .ok_or(HeightMapError::InvalidSize {
+                            expected: self.size.x as usize * self.size.y as usize,
+                            actual: self.heights.len(),
+                        })?;
+                write_image(DynamicImage::ImageLuma16(image), ImageFormat::Png)
+            }
+            HeightMapFormat::Exr => {
+                let pixels = self.heights.iter().flat_map(|h| [*h, *h, *h]).collect();
+                let image =
+                    ImageBuffer::<Rgb<f32>, Vec<f32>>::from_raw(self.size.x, self.size.y, pixels)
+                        .ok_or(HeightMapError::InvalidSize {
+                        expected: self.size.x as usize * self.size.y as usize,
+                        actual: self.heights.len(),
+                    })?;
+                write_image(DynamicImage::ImageRgb32F(image), ImageFormat::OpenExr)
+            }
+        }
+    }
+
+    /// Decodes a height map in the given format. `range` defines how 16-bit values are mapped
+    /// to the heights, it is ignored by floating point formats. `raw_size` is used only by
+    /// [`HeightMapFormat::Raw16`], if it is not specified the height map is considered square.
+    pub fn decode(
+        bytes: &[u8],
+        format: HeightMapFormat,
+        range: HeightRange,
+        raw_size: Option<Vector2<u32>>,
+    ) -> Result<Self, HeightMapError> {
+        match format {
+            HeightMapFormat::Raw16 => {
+                let count = bytes.len() / 2;
+                let size = match raw_size {
+                    Some(size) => size,
+                    None => {
+                        let side = (count as f64).sqrt() as u32;
+                        if side as usize * side as usize != count || !bytes.len().is_multiple_of(2)
+                        {
+                            return Err(HeightMapError::UnknownRawSize(bytes.len()));
+                        }
+                        Vector2::new(side, side)
+                    }
+                };
+                let heights = bytes
+                    .chunks_exact(2)
+                    .map(|pair| range.denormalize(u16::from_le_bytes([pair[0], pair[1]])))
+                    .collect();
+                Self::new(size, heights)
+            }
+            HeightMapFormat::Png16 => {
+                let image =
+                    image::load_from_memory_with_format(bytes, ImageFormat::Png)?.into_luma16();
+                let size = Vector2::new(image.width(), image.height());
+                let heights = image
+                    .into_raw()
+                    .into_iter()
+                    .map(|v| range.denormalize(v))
+                    .collect();
+                Self::new(size, heights)
+            }
+            HeightMapFormat::Exr => {
+                let image =
+                    image::load_from_memory_with_format(bytes, ImageFormat::OpenExr)?.into_rgb32f();
+                let size = Vector2::new(image.width(), image.height());
+                let heights = image.pixels().map(|p| p.0[0]).collect();
+                Self::new(size, heights)
+            }
+        }
+    }
+
+    /// Saves the height map to a file. The format is deduced from the extension of the file.
+    pub fn save<P: AsRef<Path>>(&self, path: P, range: HeightRange) -> Result<(), HeightMapError> {
+        let path = path.as_ref();
+        let bytes = self.encode(HeightMapFormat::from_path(path)?, range)?;
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    /// Loads a height map from a file. The format is deduced from the extension of the file.
+    /// See [`Self::decode`] for more info about the arguments.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        range: HeightRange,
+        raw_size: Option<Vector2<u32>>,
+    ) -> Result<Self, HeightMapError> {
+        let path = path.as_ref();
+        let format = HeightMapFormat::from_path(path)?;
+        Self::decode(&std::fs::read(path)?, format, range, raw_size)
+    }
+}
+
+/// A blending mask of a single terrain layer for the whole terrain (also known as splat map).
+/// Every pixel defines the opacity of the layer, where `0` is fully transparent and `255` is
+/// fully opaque.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayerMaskImage {
+    size: Vector2<u32>,
+    pixels: Vec<u8>,
+}
+
+impl LayerMaskImage {
+    /// Creates a new mask of the given size. The amount of pixels must be equal to
+    /// `size.x * size.y`.
+    pub fn new(size: Vector2<u32>, pixels: Vec<u8>) -> Result<Self, HeightMapError> {
+        let expected = size.x as usize * size.y as usize;
+        if pixels.len() != expected {
+            return Err(HeightMapError::InvalidSize {
+                expected,
+                actual: pixels.len(),
+            });
+        }
+        Ok(Self { size, pixels })
+    }
+
+    /// Returns the size of the mask in pixels.
+    pub fn size(&self) -> Vector2<u32> {
+        self.size
+    }
+
+    /// Returns the pixels of the mask, row by row.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Creates a resampled copy of the mask.
+    pub fn resized(&self, new_size: Vector2<u32>) -> Self {
+        if new_size == self.size || self.pixels.is_empty() {
+            return self.clone();
+        }
+        Self {
+            size: new_size,
+            pixels: resize_u8(self.pixels.clone(), self.size, new_size),
+        }
+    }
+
+    /// Encodes the mask as 8-bit grayscale PNG image.
+    pub fn encode_png(&self) -> Result<Vec<u8>, HeightMapError> {
+        let image = ImageBuffer::<Luma<u8>, Vec<u8>>::from_raw(
+            self.size.x,
+            self.size.y,
+            self.pixels.clone(),
+        )
+        .ok_or(HeightMapError::InvalidSize {
+            expected: self.size.x as usize * self.size.y as usize,
+            actual: self.pixels.len(),
+        })?;
+        write_image(DynamicImage::ImageLuma8(image), ImageFormat::Png)
+    }
+
+    /// Decodes a mask from an image in any supported format. Only the luminance of the image
+    /// is used.
+    pub fn decode(bytes: &[u8]) -> Result<Self, HeightMapError> {
+        let image = image::load_from_memory(bytes)?.into_luma8();
+        let size = Vector2::new(image.width(), image.height());
+        Self::new(size, image.into_raw())
+    }
+
+    /// Saves the mask as 8-bit grayscale PNG image.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), HeightMapError> {
+        std::fs::write(path, self.encode_png()?)?;
+        Ok(())
+    }
+
+    /// Loads a mask from an image file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, HeightMapError> {
+        Self::decode(&std::fs::read(path)?)
+    }
+}
+
+fn write_image(image: DynamicImage, format: ImageFormat) -> Result<Vec<u8>, HeightMapError> {
+    let mut bytes = Vec::new();
+    image.write_to(&mut Cursor::new(&mut bytes), format)?;
+    Ok(bytes)
+}
+
+impl Terrain {
+    /// Returns the size (in pixels) of a whole-terrain height map. Chunks share their edge
+    /// pixels, so this is not just a sum of chunk sizes.
+    pub fn height_map_image_size(&self) -> Vector2<u32> {
+        // Each chunk contributes `height_map_size - 3` pixels: 2 pixels of margins and 1 pixel
+        // that is shared with the next chunk. The last row and column are added separately.
+        let stride = self.height_map_size.map(|x| x - 3);
+        Vector2::new(
+            self.width_chunks.len() as u32 * stride.x + 1,
+            self.length_chunks.len() as u32 * stride.y + 1,
+        )
+    }
+
+    /// Returns the size (in pixels) of a whole-terrain layer mask.
+    pub fn layer_mask_image_size(&self) -> Vector2<u32> {
+        Vector2::new(
+            self.width_chunks.len() as u32 * self.mask_size.x,
+            self.length_chunks.len() as u32 * self.mask_size.y,
+        )
+    }
+
+    fn chunk_image_index(&self, grid_position: Vector2<i32>) -> Vector2<u32> {
+        Vector2::new(
+            (grid_position.x - self.width_chunks.start) as u32,
+            (grid_position.y - self.length_chunks.start) as u32,
+        )
+    }
+
+    /// Merges the height maps of every chunk into a single height map. The first pixel of the
+    /// resulting image corresponds to the chunk with the smallest grid position.
+    pub fn export_height_map(&self) -> HeightMapImage {
+        let size = self.height_map_image_size();
+        let stride = self.height_map_size.map(|x| x - 3);
+        let mut heights = vec![0.0; size.x as usize * size.y as usize];
+        for chunk in self.chunks.iter() {
+            let origin = self
+                .chunk_image_index(chunk.grid_position)
+                .component_mul(&stride);
+            let data = chunk.height_data();
+            for y in 0..=stride.y {
+                for x in 0..=stride.x {
+                    let index = (origin.y + y) * size.x + origin.x + x;
+                    heights[index as usize] = data[Vector2::new(x as i32, y as i32)];
+                }
+            }
+        }
+        HeightMapImage { size, heights }
+    }
+
+    /// Splits the given height map between the chunks of the terrain. The height map is
+    /// resampled if its size does not match [`Self::height_map_image_size`]. Shared edges and
+    /// margins of neighbouring chunks are filled with the same data, so the result is equivalent
+    /// to calling [`Self::align_chunk_margins`] for every chunk. The margins along the outer
+    /// border of the terrain repeat the edge pixels.
+    pub fn import_height_map(&mut self, image: &HeightMapImage) {
+        let size = self.height_map_image_size();
+        let image = if image.size != size {
+            Log::warn(format!(
+                "Height map size {}x{} does not match terrain size {}x{}, it will be resampled.",
+                image.size.x, image.size.y, size.x, size.y
+            ));
+            image.resized(size)
+        } else {
+            image.clone()
+        };
+
+        let stride = self.height_map_size.map(|x| x - 3);
+        let chunk_size = *self.height_map_size;
+        for chunk in self.chunks.iter() {
+            let origin = self
+                .chunk_image_index(chunk.grid_position)
+                .component_mul(&stride)
+                .map(|x| x as i32);
+            let mut texture_data = chunk.heightmap.as_ref().unwrap().data_ref();
+            let mut texture_modifier = texture_data.modify();
+            let height_map = texture_modifier.data_mut_of_type::<f32>().unwrap();
+            for iy in 0..chunk_size.y as i32 {
+                // Chunk pixel coordinates start at -1, because of margins.
+                let gy = (origin.y + iy - 1).clamp(0, size.y as i32 - 1);
+                for ix in 0..chunk_size.x as i32 {
+                    let gx = (origin.x + ix - 1).clamp(0, size.x as i32 - 1);
+                    height_map[(iy * chunk_size.x as i32 + ix) as usize] =
+                        image.heights[(gy * size.x as i32 + gx) as usize];
+                }
+            }
+            drop(texture_modifier);
+            drop(texture_data);
+            chunk.update_quad_tree();
+        }
+
+        self.bounding_box_dirty.set(true);
+    }
+
+    /// Merges the blending masks of the given layer of every chunk into a single mask. Returns
+    /// `None` if there is no such layer.
+    pub fn export_layer_mask(&self, layer: usize) -> Option<LayerMaskImage> {
+        if layer >= self.layers.len() {
+            return None;
+        }
+        let size = self.layer_mask_image_size();
+        let mask_size = *self.mask_size;
+        let mut pixels = vec![0; size.x as usize * size.y as usize];
+        for chunk in self.chunks.iter() {
+            let origin = self
+                .chunk_image_index(chunk.grid_position)
+                .component_mul(&mask_size);
+            let texture_data = chunk.layer_masks.get(layer)?.data_ref();
+            let mask = texture_data.data();
+            for y in 0..mask_size.y {
+                let src = (y * mask_size.x) as usize;
+                let dst = ((origin.y + y) * size.x + origin.x) as usize;
+                pixels[dst..dst + mask_size.x as usize]
+                    .copy_from_slice(&mask[src..src + mask_size.x as usize]);
+            }
+        }
+        Some(LayerMaskImage { size, pixels })
+    }
+
+    /// Splits the given mask between the chunks of the terrain and uses it as the blending mask
+    /// of the given layer. The mask is resampled if its size does not match
+    /// [`Self::layer_mask_image_size`].
+    pub fn import_layer_mask(
+        &mut self,
+        layer: usize,
+        image: &LayerMaskImage,
+    ) -> Result<(), HeightMapError> {
+        if layer >= self.layers.len() {
+            return Err(HeightMapError::InvalidLayer(layer));
+        }
+        let size = self.layer_mask_image_size();
+        let image = image.resized(size);
+        let mask_size = *self.mask_size;
+        for chunk in self.chunks.iter() {
+            let origin = self
+                .chunk_image_index(chunk.grid_position)
+                .component_mul(&mask_size);
+            let Some(mask) = chunk.layer_masks.get(layer) else {
+                return Err(HeightMapError::InvalidLayer(layer));
+            };
+            let mut texture_data = mask.data_ref();
+            let mut texture_modifier = texture_data.modify();
+            let mask = texture_modifier.data_mut_of_type::<u8>().unwrap();
+            for y in 0..mask_size.y {
+                let dst = (y * mask_size.x) as usize;
+                let src = ((origin.y + y) * size.x + origin.x) as usize;
+                mask[dst..dst + mask_size.x as usize]
+                    .copy_from_slice(&image.pixels[src..src + mask_size.x as usize]);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{
+        base::BaseBuilder,
+        terrain::{Layer, TerrainBuilder},
+    };
+
+    fn make_terrain() -> Terrain {
+        let node = TerrainBuilder::new(BaseBuilder::new())
+            .with_width_chunks(-1..1)
+            .with_length_chunks(0..2)
+            .with_height_map_size(Vector2::new(11, 11))
+            .with_mask_size(Vector2::new(4, 4))
+            .with_block_size(Vector2::new(9, 9))
+            .with_layers(vec![Layer::default()])
+            .build_node();
+        node.cast::<Terrain>().unwrap().clone()
+    }
+
+    fn test_image(size: Vector2<u32>) -> HeightMapImage {
+        let heights = (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| (x * 3 + y * 7) as f32 / 100.0))
+            .collect();
+        HeightMapImage::new(size, heights).unwrap()
+    }
+
+    #[test]
+    fn height_map_image_size() {
+        let terrain = make_terrain();
+        assert_eq!(terrain.height_map_image_size(), Vector2::new(17, 17));
+        assert_eq!(terrain.layer_mask_image_size(), Vector2::new(8, 8));
+    }
+
+    #[test]
+    fn import_export_height_map() {
+        let mut terrain = make_terrain();
+        let image = test_image(terrain.height_map_image_size());
+        terrain.import_height_map(&image);
+        assert_eq!(terrain.export_height_map(), image);
+    }
+
+    #[test]
+    fn import_height_map_aligns_margins() {
+        let mut terrain = make_terrain();
+        let image = test_image(terrain.height_map_image_size());
+        terrain.import_height_map(&image);
+        let imported = terrain
+            .chunks_ref()
+            .iter()
+            .map(|c| c.heightmap_owned())
+            .collect::<Vec<_>>();
+        let positions = terrain
+            .chunks_ref()
+            .iter()
+            .map(|c| c.grid_position())
+            .collect::<Vec<_>>();
+        for position in positions {
+            terrain.align_chunk_margins(position);
+        }
+        let aligned = terrain
+            .chunks_ref()
+            .iter()
+            .map(|c| c.heightmap_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(imported, aligned);
+    }
+
+    #[test]
+    fn raw16_round_trip() {
+        let image = test_image(Vector2::new(4, 4));
+        let range = image.height_range();
+        let bytes = image.encode(HeightMapFormat::Raw16, range).unwrap();
+        assert_eq!(bytes.len(), 32);
+        let decoded = HeightMapImage::decode(&bytes, HeightMapFormat::Raw16, range, None).unwrap();
+        assert_eq!(decoded.size(), image.size());
+        for (a, b) in decoded.heights().iter().zip(image.heights()) {
+            assert!((a - b).abs() < 1.0e-4);
+        }
+    }
+
+    #[test]
+    fn raw16_non_square() {
+        let result = HeightMapImage::decode(
+            &[0; 6],
+            HeightMapFormat::Raw16,
+            HeightRange::default(),
+            None,
+        );
+        assert!(matches!(result, Err(HeightMapError::UnknownRawSize(6))));
+    }
+
+    #[test]
+    fn png16_round_trip() {
+        let image = test_image(Vector2::new(5, 3));
+        let range = image.height_range();
+        let bytes = image.encode(HeightMapFormat::Png16, range).unwrap();
+        let decoded = HeightMapImage::decode(&bytes, HeightMapFormat::Png16, range, None).unwrap();
+        assert_eq!(decoded.size(), image.size());
+        for (a, b) in decoded.heights().iter().zip(image.heights()) {
+            assert!((a - b).abs() < 1.0e-4);
+        }
+    }
+
+    #[test]
+    fn exr_round_trip() {
+        let image = test_image(Vector2::new(3, 5));
+        let bytes = image
+            .encode(HeightMapFormat::Exr, HeightRange::default())
+            .unwrap();
+        let decoded =
+            HeightMapImage::decode(&bytes, HeightMapFormat::Exr, HeightRange::default(), None)
+                .unwrap();
+        assert_eq!(decoded, image);
+    }
+
+    #[test]
+    fn import_export_layer_mask() {
+        let mut terrain = make_terrain();
+        let size = terrain.layer_mask_image_size();
+        let pixels = (0..size.x * size.y).map(|i| i as u8).collect();
+        let mask = LayerMaskImage::new(size, pixels).unwrap();
+        terrain.import_layer_mask(0, &mask).unwrap();
+        assert_eq!(terrain.export_layer_mask(0).unwrap(), mask);
+        assert!(terrain.import_layer_mask(1, &mask).is_err());
+        let decoded = LayerMaskImage::decode(&mask.encode_png().unwrap()).unwrap();
+        assert_eq!(decoded, mask);
+    }
+}
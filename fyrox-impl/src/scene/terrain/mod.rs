@@ -54,7 +54,11 @@ use crate::{
         graph::Graph,
         mesh::RenderPath,
         node::{Node, NodeTrait},
-        terrain::{geometry::TerrainGeometry, quadtree::QuadTree},
+        terrain::{
+            foliage::{FoliageCache, FoliageLayer},
+            geometry::TerrainGeometry,
+            quadtree::QuadTree,
+        },
         Scene,
     },
 };
@@ -73,7 +77,9 @@ use std::{
 };
 
 pub mod brushstroke;
+pub mod foliage;
 mod geometry;
+pub mod heightmap;
 mod quadtree;
 
 use crate::scene::node::constructor::NodeConstructor;
@@ -372,6 +378,9 @@ pub struct Chunk {
     /// Layer blending masks of the chunk.
     #[reflect(hidden)]
     pub layer_masks: Vec<TextureResource>,
+    /// Density masks of foliage layers of the chunk.
+    #[reflect(hidden)]
+    pub foliage_masks: Vec<TextureResource>,
     #[reflect(hidden)]
     height_map_modifications_count: u64,
 }
@@ -382,6 +391,7 @@ impl PartialEq for Chunk {
             && self.height_map_size == other.height_map_size
             && self.grid_position == other.grid_position
             && self.layer_masks == other.layer_masks
+            && self.foliage_masks == other.foliage_masks
     }
 }
 
@@ -401,6 +411,11 @@ impl Clone for Chunk {
                 .iter()
                 .map(|m| m.deep_clone())
                 .collect::<Vec<_>>(),
+            foliage_masks: self
+                .foliage_masks
+                .iter()
+                .map(|m| m.deep_clone())
+                .collect::<Vec<_>>(),
             quad_tree: Mutex::new(make_quad_tree(
                 &self.heightmap,
                 self.height_map_size,
@@ -433,6 +448,7 @@ impl Visit for Chunk {
                 self.position = self.position()
             }
             self.block_size.visit("BlockSize", &mut region)?;
+            let _ = self.foliage_masks.visit("FoliageMasks", &mut region);
         }
 
         self.quad_tree = Mutex::new(make_quad_tree(
@@ -457,6 +473,7 @@ impl Default for Chunk {
             block_size: Vector2::new(32, 32),
             grid_position: Default::default(),
             layer_masks: Default::default(),
+            foliage_masks: Default::default(),
             height_map_modifications_count: 0,
         }
     }
@@ -1085,6 +1102,13 @@ pub struct Terrain {
     #[reflect(immutable_collection)]
     chunks: InheritableVariable<Vec<Chunk>>,
 
+    /// Foliage layers of the terrain. See [`FoliageLayer`] docs for more info.
+    #[reflect(setter = "set_foliage_layers")]
+    foliage_layers: InheritableVariable<Vec<FoliageLayer>>,
+
+    #[reflect(hidden)]
+    foliage_cache: FoliageCache,
+
     #[reflect(hidden)]
     bounding_box_dirty: Cell<bool>,
 
@@ -1110,6 +1134,8 @@ impl Default for Terrain {
             block_size: Vector2::new(33, 33).into(),
             mask_size: Vector2::new(256, 256).into(),
             chunks: Default::default(),
+            foliage_layers: Default::default(),
+            foliage_cache: Default::default(),
             bounding_box_dirty: Cell::new(true),
             bounding_box: Cell::new(Default::default()),
             geometry: Default::default(),
@@ -1136,6 +1162,7 @@ impl Visit for Terrain {
             self.block_size.visit("BlockSize", &mut region)?;
             self.mask_size.visit("MaskSize", &mut region)?;
             self.chunks.visit("Chunks", &mut region)?;
+            let _ = self.foliage_layers.visit("FoliageLayers", &mut region);
         }
 
         if region.is_reading() {
@@ -1571,6 +1598,11 @@ impl Terrain {
                                 )
                             })
                             .collect::<Vec<_>>(),
+                        foliage_masks: self
+                            .foliage_layers
+                            .iter()
+                            .map(|_| create_layer_mask(self.mask_size.x, self.mask_size.y, 0))
+                            .collect::<Vec<_>>(),
                     };
                     created_chunks.push(new_chunk.grid_position);
                     new_chunk
@@ -1748,7 +1780,9 @@ impl Terrain {
     pub fn interpolate_value(&self, position: Vector2<f32>, target: BrushTarget) -> f32 {
         let grid_square = match target {
             BrushTarget::HeightMap => self.get_height_grid_square(position),
-            BrushTarget::LayerMask { .. } => self.get_mask_grid_square(position),
+            BrushTarget::LayerMask { .. } | BrushTarget::FoliageMask { .. } => {
+                self.get_mask_grid_square(position)
+            }
             BrushTarget::HoleMask => self.get_hole_grid_square(position),
         };
         let p = grid_square.grid_position;
@@ -1778,6 +1812,12 @@ impl Terrain {
                 self.get_layer_mask(p10, layer).unwrap_or(0) as f32 / 255.0,
                 self.get_layer_mask(p11, layer).unwrap_or(0) as f32 / 255.0,
             ),
+            BrushTarget::FoliageMask { layer } => (
+                self.get_foliage_mask(p00, layer).unwrap_or(0) as f32 / 255.0,
+                self.get_foliage_mask(p01, layer).unwrap_or(0) as f32 / 255.0,
+                self.get_foliage_mask(p10, layer).unwrap_or(0) as f32 / 255.0,
+                self.get_foliage_mask(p11, layer).unwrap_or(0) as f32 / 255.0,
+            ),
             BrushTarget::HoleMask => (
                 self.get_hole_mask(p00).unwrap_or(0) as f32 / 255.0,
                 self.get_hole_mask(p01).unwrap_or(0) as f32 / 255.0,
@@ -2134,7 +2174,11 @@ impl Terrain {
         new_size = new_size.sup(&Vector2::repeat(1));
 
         for chunk in self.chunks.iter_mut() {
-            for mask in chunk.layer_masks.iter_mut() {
+            for mask in chunk
+                .layer_masks
+                .iter_mut()
+                .chain(chunk.foliage_masks.iter_mut())
+            {
                 let data = mask.data_ref();
                 let new_mask = resize_u8(data.data().to_vec(), *self.mask_size, new_size);
                 let new_mask_texture = TextureResource::from_bytes(
@@ -2210,12 +2254,13 @@ impl Terrain {
     pub fn texture_data(&self, target: BrushTarget) -> TerrainTextureData {
         let chunk_size = match target {
             BrushTarget::HeightMap => self.height_map_size(),
-            BrushTarget::LayerMask { .. } => self.mask_size(),
+            BrushTarget::LayerMask { .. } | BrushTarget::FoliageMask { .. } => self.mask_size(),
             BrushTarget::HoleMask => self.hole_mask_size(),
         };
         let kind = match target {
             BrushTarget::HeightMap => TerrainTextureKind::Height,
             BrushTarget::LayerMask { .. } => TerrainTextureKind::Mask,
+            BrushTarget::FoliageMask { .. } => TerrainTextureKind::Mask,
             BrushTarget::HoleMask => TerrainTextureKind::Mask,
         };
         let resources: FxHashMap<Vector2<i32>, TextureResource> = match target {
@@ -2242,6 +2287,11 @@ impl Terrain {
                 .iter()
                 .map(|c| (c.grid_position(), c.layer_masks[layer].clone()))
                 .collect(),
+            BrushTarget::FoliageMask { layer } => self
+                .chunks_ref()
+                .iter()
+                .map(|c| (c.grid_position(), c.foliage_masks[layer].clone()))
+                .collect(),
         };
         TerrainTextureData {
             chunk_size,
@@ -2275,12 +2325,16 @@ impl Terrain {
         };
         let position = match stroke.brush().target {
             BrushTarget::HeightMap => self.local_to_height_pixel(position),
-            BrushTarget::LayerMask { .. } => self.local_to_mask_pixel(position),
+            BrushTarget::LayerMask { .. } | BrushTarget::FoliageMask { .. } => {
+                self.local_to_mask_pixel(position)
+            }
             BrushTarget::HoleMask => self.local_to_hole_pixel(position),
         };
         let scale = match stroke.brush().target {
             BrushTarget::HeightMap => self.height_grid_scale(),
-            BrushTarget::LayerMask { .. } => self.mask_grid_scale(),
+            BrushTarget::LayerMask { .. } | BrushTarget::FoliageMask { .. } => {
+                self.mask_grid_scale()
+            }
             BrushTarget::HoleMask => self.hole_grid_scale(),
         };
         stroke.stamp(position, scale, value);
@@ -2302,17 +2356,23 @@ impl Terrain {
         };
         let start = match stroke.brush().target {
             BrushTarget::HeightMap => self.local_to_height_pixel(start),
-            BrushTarget::LayerMask { .. } => self.local_to_mask_pixel(start),
+            BrushTarget::LayerMask { .. } | BrushTarget::FoliageMask { .. } => {
+                self.local_to_mask_pixel(start)
+            }
             BrushTarget::HoleMask => self.local_to_hole_pixel(start),
         };
         let end = match stroke.brush().target {
             BrushTarget::HeightMap => self.local_to_height_pixel(end),
-            BrushTarget::LayerMask { .. } => self.local_to_mask_pixel(end),
+            BrushTarget::LayerMask { .. } | BrushTarget::FoliageMask { .. } => {
+                self.local_to_mask_pixel(end)
+            }
             BrushTarget::HoleMask => self.local_to_hole_pixel(end),
         };
         let scale = match stroke.brush().target {
             BrushTarget::HeightMap => self.height_grid_scale(),
-            BrushTarget::LayerMask { .. } => self.mask_grid_scale(),
+            BrushTarget::LayerMask { .. } | BrushTarget::FoliageMask { .. } => {
+                self.mask_grid_scale()
+            }
             BrushTarget::HoleMask => self.hole_grid_scale(),
         };
         stroke.smear(start, end, scale, value);
@@ -2565,6 +2625,8 @@ impl NodeTrait for Terrain {
             }
         }
 
+        self.collect_foliage_render_data(ctx);
+
         RdcControlFlow::Continue
     }
 
@@ -2614,6 +2676,7 @@ pub struct TerrainBuilder {
     height_map_size: Vector2<u32>,
     block_size: Vector2<u32>,
     layers: Vec<Layer>,
+    foliage_layers: Vec<FoliageLayer>,
 }
 
 fn create_layer_mask(width: u32, height: u32, value: u8) -> TextureResource {
@@ -2647,6 +2710,7 @@ impl TerrainBuilder {
             height_map_size: Vector2::new(259, 259),
             block_size: Vector2::new(33, 33),
             layers: Default::default(),
+            foliage_layers: Default::default(),
         }
     }
 
@@ -2692,6 +2756,12 @@ impl TerrainBuilder {
         self
     }
 
+    /// Sets desired foliage layers that will be used for the terrain.
+    pub fn with_foliage_layers(mut self, foliage_layers: Vec<FoliageLayer>) -> Self {
+        self.foliage_layers = foliage_layers;
+        self
+    }
+
     /// Sets desired block size. Block - is a smallest renderable piece of terrain which will be used for
     /// level-of-detail functionality.
     pub fn with_block_size(mut self, block_size: Vector2<u32>) -> Self {
@@ -2746,6 +2816,11 @@ impl TerrainBuilder {
                             )
                         })
                         .collect::<Vec<_>>(),
+                    foliage_masks: self
+                        .foliage_layers
+                        .iter()
+                        .map(|_| create_layer_mask(self.mask_size.x, self.mask_size.y, 0))
+                        .collect::<Vec<_>>(),
                     block_size: self.block_size,
                 };
 
@@ -2759,6 +2834,8 @@ impl TerrainBuilder {
             holes_enabled: self.holes_enabled,
             layers: self.layers.into(),
             chunks: chunks.into(),
+            foliage_layers: self.foliage_layers.into(),
+            foliage_cache: Default::default(),
             bounding_box_dirty: Cell::new(true),
             bounding_box: Default::default(),
             mask_size: self.mask_size.into(),
@@ -0,0 +1,994 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Raycast vehicle is a simple and robust model of a wheeled vehicle, where every wheel is a ray
+//! cast down from the chassis. See [`Vehicle`] docs for more info.
+
+use crate::{
+    core::{
+        algebra::{Matrix4, Point3, Unit, UnitQuaternion, Vector3},
+        color::Color,
+        math::{
+            aabb::AxisAlignedBoundingBox,
+            curve::{Curve, CurveKey, CurveKeyKind},
+        },
+        pool::Handle,
+        reflect::prelude::*,
+        uuid::{uuid, Uuid},
+        variable::InheritableVariable,
+        visitor::prelude::*,
+    },
+    scene::{
+        base::{Base, BaseBuilder},
+        collider::InteractionGroups,
+        debug::{Line, SceneDrawingContext},
+        graph::{physics::RayCastOptions, Graph},
+        node::{constructor::NodeConstructor, Node, NodeTrait, UpdateContext},
+        rigidbody::RigidBody,
+    },
+};
+use fyrox_graph::{constructor::ConstructorProvider, SceneGraph};
+use std::ops::{Deref, DerefMut};
+
+/// Tire defines how a wheel interacts with the ground. Friction of the tire is defined by two
+/// curves: longitudinal friction maps slip ratio (relative difference between the speed of the
+/// wheel surface and the speed of the ground) to friction coefficient, lateral friction maps slip
+/// angle (in radians) to friction coefficient. The resulting force is the friction coefficient
+/// multiplied by the load on the wheel.
+#[derive(Clone, Debug, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "38a8b62f-9fdf-40ca-b360-2a2af8fd4fd9")]
+pub struct Tire {
+    /// Radius of the wheel.
+    #[reflect(min_value = 0.01, step = 0.01)]
+    pub radius: f32,
+
+    /// Mass of the wheel. It defines the moment of inertia of the wheel, lighter wheels spin up
+    /// (and lock up) faster.
+    #[reflect(min_value = 0.01, step = 0.1)]
+    pub mass: f32,
+
+    /// Maps slip ratio to friction coefficient along the rolling direction of the wheel.
+    pub longitudinal_friction: Curve,
+
+    /// Maps slip angle (in radians) to friction coefficient along the axle of the wheel.
+    pub lateral_friction: Curve,
+}
+
+impl Default for Tire {
+    fn default() -> Self {
+        Self {
+            radius: 0.35,
+            mass: 20.0,
+            longitudinal_friction: Curve::from(vec![
+                CurveKey::new(0.0, 0.0, CurveKeyKind::Linear),
+                CurveKey::new(0.1, 1.0, CurveKeyKind::Linear),
+                CurveKey::new(1.0, 0.8, CurveKeyKind::Linear),
+            ]),
+            lateral_friction: Curve::from(vec![
+                CurveKey::new(0.0, 0.0, CurveKeyKind::Linear),
+                CurveKey::new(0.14, 1.0, CurveKeyKind::Linear),
+                CurveKey::new(1.0, 0.75, CurveKeyKind::Linear),
+            ]),
+        }
+    }
+}
+
+pub(crate) struct TireInput {
+    pub(crate) longitudinal_velocity: f32,
+    pub(crate) lateral_velocity: f32,
+    pub(crate) load: f32,
+    pub(crate) drive_torque: f32,
+    pub(crate) brake_torque: f32,
+    pub(crate) mass_share: f32,
+    pub(crate) dt: f32,
+}
+
+impl Tire {
+    // Slip ratio is unstable at low speeds, so the speed is clamped from below.
+    const MIN_SLIP_VELOCITY: f32 = 1.0;
+
+    fn inertia(&self) -> f32 {
+        (0.5 * self.mass * self.radius * self.radius).max(f32::EPSILON)
+    }
+
+    /// Updates the angular velocity of the wheel and returns a pair of longitudinal and lateral
+    /// forces, that the tire applies to the chassis.
+    pub(crate) fn update(&self, state: &mut WheelState, input: &TireInput) -> (f32, f32) {
+        let dt = input.dt;
+        let radius = self.radius.max(f32::EPSILON);
+        let inertia = self.inertia();
+
+        state.angular_velocity += input.drive_torque / inertia * dt;
+        let brake_delta = input.brake_torque.abs() / inertia * dt;
+        if state.angular_velocity.abs() <= brake_delta {
+            state.angular_velocity = 0.0;
+        } else {
+            state.angular_velocity -= brake_delta.copysign(state.angular_velocity);
+        }
+
+        let mut forces = (0.0, 0.0);
+        if input.load > 0.0 && dt > 0.0 {
+            let mass_share = input.mass_share.max(f32::EPSILON);
+            let mut hint = 0;
+
+            let longitudinal_velocity = input.longitudinal_velocity;
+            let slip_velocity = state.angular_velocity * radius - longitudinal_velocity;
+            state.slip_ratio =
+                slip_velocity / longitudinal_velocity.abs().max(Self::MIN_SLIP_VELOCITY);
+            let longitudinal = self
+                .longitudinal_friction
+                .value_at(state.slip_ratio.abs(), &mut hint)
+                * input.load
+                * slip_velocity.signum();
+            // Friction must not change the sign of the slip in a single step, otherwise the wheel
+            // will jitter.
+            let max_longitudinal =
+                slip_velocity.abs() / (dt * (radius * radius / inertia + 1.0 / mass_share));
+            let longitudinal = longitudinal.clamp(-max_longitudinal, max_longitudinal);
+            state.angular_velocity -= longitudinal * radius / inertia * dt;
+
+            let lateral_velocity = input.lateral_velocity;
+            state.slip_angle = lateral_velocity.atan2(longitudinal_velocity.abs());
+            hint = 0;
+            let lateral = -self
+                .lateral_friction
+                .value_at(state.slip_angle.abs(), &mut hint)
+                * input.load
+                * lateral_velocity.signum();
+            let max_lateral = lateral_velocity.abs() * mass_share / dt;
+            let lateral = lateral.clamp(-max_lateral, max_lateral);
+
+            // Friction circle - the tire cannot provide more grip than the load allows in total.
+            let max_friction = input.load
+                * max_friction(&self.longitudinal_friction)
+                    .max(max_friction(&self.lateral_friction));
+            let magnitude = (longitudinal * longitudinal + lateral * lateral).sqrt();
+            forces = if magnitude > max_friction && magnitude > 0.0 {
+                let k = max_friction / magnitude;
+                (longitudinal * k, lateral * k)
+            } else {
+                (longitudinal, lateral)
+            };
+        } else {
+            state.slip_ratio = 0.0;
+            state.slip_angle = 0.0;
+        }
+
+        state.rotation_angle =
+            (state.rotation_angle + state.angular_velocity * dt) % std::f32::consts::TAU;
+
+        forces
+    }
+}
+
+fn max_friction(curve: &Curve) -> f32 {
+    curve
+        .keys()
+        .iter()
+        .fold(0.0, |max, key| f32::max(max, key.value))
+}
+
+/// Runtime state of a wheel. It is updated by the vehicle every frame and could be used to drive
+/// effects (skid marks, tire squeal sounds, etc.).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WheelState {
+    /// `true` if the wheel touches the ground.
+    pub in_contact: bool,
+    /// Contact point in world coordinates. Valid only if the wheel touches the ground.
+    pub contact_point: Vector3<f32>,
+    /// Contact normal in world coordinates. Valid only if the wheel touches the ground.
+    pub contact_normal: Vector3<f32>,
+    /// Current length of the suspension.
+    pub suspension_length: f32,
+    /// Current force of the suspension.
+    pub suspension_force: f32,
+    /// Current steering angle of the wheel (in radians).
+    pub steering_angle: f32,
+    /// Angular velocity of the wheel around its axle (in radians per second).
+    pub angular_velocity: f32,
+    /// Rotation angle of the wheel around its axle (in radians).
+    pub rotation_angle: f32,
+    /// Current slip ratio of the tire.
+    pub slip_ratio: f32,
+    /// Current slip angle of the tire (in radians).
+    pub slip_angle: f32,
+}
+
+/// A wheel of a [`Vehicle`]. All positions and directions are defined in the local coordinates of
+/// the vehicle node.
+#[derive(Clone, Debug, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "cbf3f654-30a2-40f6-b605-982ba877fcc2")]
+pub struct Wheel {
+    /// A point at which the suspension is attached to the chassis.
+    pub position: Vector3<f32>,
+
+    /// Direction in which the suspension extends. Usually it points down.
+    pub suspension_direction: Vector3<f32>,
+
+    /// Axle of the wheel. The wheel rolls around this axis, forward direction of the wheel is
+    /// `axle x -suspension_direction`.
+    pub axle: Vector3<f32>,
+
+    /// Tire of the wheel.
+    pub tire: Tire,
+
+    /// Length of the suspension when it is not compressed.
+    #[reflect(min_value = 0.0, step = 0.01)]
+    pub suspension_rest_length: f32,
+
+    /// Stiffness of the suspension spring.
+    #[reflect(min_value = 0.0, step = 100.0)]
+    pub suspension_stiffness: f32,
+
+    /// Damping of the suspension.
+    #[reflect(min_value = 0.0, step = 100.0)]
+    pub suspension_damping: f32,
+
+    /// Maximum force that the suspension can apply to the chassis.
+    #[reflect(min_value = 0.0, step = 100.0)]
+    pub max_suspension_force: f32,
+
+    /// A fraction of the maximum steering angle of the vehicle, that is applied to the wheel.
+    /// Negative values make the wheel turn in the opposite direction (for rear-wheel steering).
+    #[reflect(min_value = -1.0, max_value = 1.0, step = 0.1)]
+    pub steering_factor: f32,
+
+    /// A fraction of the engine torque, that is applied to the wheel.
+    #[reflect(min_value = 0.0, max_value = 1.0, step = 0.1)]
+    pub drive_factor: f32,
+
+    /// A fraction of the brake torque, that is applied to the wheel.
+    #[reflect(min_value = 0.0, max_value = 1.0, step = 0.1)]
+    pub brake_factor: f32,
+
+    /// An optional handle of a node, that will be moved and rotated together with the wheel. It
+    /// should be a direct child of the vehicle node.
+    pub visual: Handle<Node>,
+}
+
+impl Default for Wheel {
+    fn default() -> Self {
+        Self {
+            position: Vector3::default(),
+            suspension_direction: -Vector3::y(),
+            axle: Vector3::x(),
+            tire: Default::default(),
+            suspension_rest_length: 0.3,
+            suspension_stiffness: 35000.0,
+            suspension_damping: 4500.0,
+            max_suspension_force: 60000.0,
+            steering_factor: 0.0,
+            drive_factor: 1.0,
+            brake_factor: 1.0,
+            visual: Default::default(),
+        }
+    }
+}
+
+impl Wheel {
+    /// Sets the position of the wheel.
+    pub fn with_position(mut self, position: Vector3<f32>) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Sets the steering factor of the wheel.
+    pub fn with_steering_factor(mut self, steering_factor: f32) -> Self {
+        self.steering_factor = steering_factor;
+        self
+    }
+
+    /// Sets the drive factor of the wheel.
+    pub fn with_drive_factor(mut self, drive_factor: f32) -> Self {
+        self.drive_factor = drive_factor;
+        self
+    }
+
+    /// Sets the brake factor of the wheel.
+    pub fn with_brake_factor(mut self, brake_factor: f32) -> Self {
+        self.brake_factor = brake_factor;
+        self
+    }
+
+    /// Sets the tire of the wheel.
+    pub fn with_tire(mut self, tire: Tire) -> Self {
+        self.tire = tire;
+        self
+    }
+
+    /// Sets the visual node of the wheel.
+    pub fn with_visual(mut self, visual: Handle<Node>) -> Self {
+        self.visual = visual;
+        self
+    }
+
+    fn local_down(&self) -> Vector3<f32> {
+        self.suspension_direction
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(|| -Vector3::y())
+    }
+
+    fn local_axle(&self) -> Vector3<f32> {
+        self.axle
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::x)
+    }
+
+    fn steering_rotation(&self, steering_angle: f32) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_axis_angle(&Unit::new_unchecked(-self.local_down()), steering_angle)
+    }
+}
+
+/// Anti-roll bar connects two wheels of the same axle and transfers the load between them, which
+/// reduces the body roll in corners.
+#[derive(Clone, Debug, Default, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "c41a4288-48c4-4adb-9151-28cca5010893")]
+pub struct AntiRollBar {
+    /// Index of the left wheel.
+    pub left_wheel: usize,
+
+    /// Index of the right wheel.
+    pub right_wheel: usize,
+
+    /// Stiffness of the bar.
+    #[reflect(min_value = 0.0, step = 100.0)]
+    pub stiffness: f32,
+}
+
+/// Raycast vehicle is a wheeled vehicle, where every wheel is modelled as a ray cast along the
+/// suspension direction. It is much more stable and cheaper than a vehicle made of rigid bodies
+/// and joints.
+///
+/// ## How to use
+///
+/// The vehicle node must be a direct child of a dynamic [`RigidBody`] (the chassis), the chassis
+/// should also have a collider. Every frame, the vehicle casts a ray for every wheel, calculates
+/// suspension and tire forces and applies them to the chassis. Colliders of the chassis are
+/// ignored by the rays.
+///
+/// The vehicle is controlled by three inputs: [`Self::set_steering`], [`Self::set_throttle`] and
+/// [`Self::set_brake`]. These are runtime values and they're not serialized.
+///
+/// ## Wheels
+///
+/// Every wheel has its own suspension, tire and a set of factors that define how the wheel reacts
+/// to the inputs. For example, a rear-wheel-drive car has steering factor of 1.0 on the front
+/// wheels and drive factor of 1.0 on the rear wheels. Every wheel can also have a visual node,
+/// which is moved and rotated according to the state of the wheel.
+///
+/// ## Example
+///
+/// ```rust
+/// # use fyrox_impl::{
+/// #     core::{algebra::Vector3, pool::Handle},
+/// #     scene::{
+/// #         base::BaseBuilder,
+/// #         graph::Graph,
+/// #         node::Node,
+/// #         vehicle::{Vehicle, VehicleBuilder, Wheel},
+/// #     },
+/// # };
+/// # use fyrox_graph::SceneGraph;
+/// fn create_vehicle(graph: &mut Graph, chassis: Handle<Node>) -> Handle<Vehicle> {
+///     let vehicle = VehicleBuilder::new(BaseBuilder::new())
+///         .with_wheels(vec![
+///             Wheel::default()
+///                 .with_position(Vector3::new(-0.8, 0.0, 1.2))
+///                 .with_steering_factor(1.0)
+///                 .with_drive_factor(0.0),
+///             Wheel::default()
+///                 .with_position(Vector3::new(0.8, 0.0, 1.2))
+///                 .with_steering_factor(1.0)
+///                 .with_drive_factor(0.0),
+///             Wheel::default().with_position(Vector3::new(-0.8, 0.0, -1.2)),
+///             Wheel::default().with_position(Vector3::new(0.8, 0.0, -1.2)),
+///         ])
+///         .build(graph);
+///     graph.link_nodes(vehicle, chassis);
+///     vehicle
+/// }
+/// ```
+#[derive(Visit, Reflect, Clone, Debug)]
+#[reflect(
+    derived_type = "Node",
+    type_uuid = "e4e23cc3-04a7-4b9b-94b1-a86e434fefc8"
+)]
+#[visit(optional)]
+pub struct Vehicle {
+    base: Base,
+
+    #[reflect(setter = "set_wheels")]
+    wheels: InheritableVariable<Vec<Wheel>>,
+
+    #[reflect(setter = "set_anti_roll_bars")]
+    anti_roll_bars: InheritableVariable<Vec<AntiRollBar>>,
+
+    #[reflect(setter = "set_max_steering_angle", min_value = 0.0, step = 0.01)]
+    max_steering_angle: InheritableVariable<f32>,
+
+    #[reflect(setter = "set_engine_torque", min_value = 0.0, step = 10.0)]
+    engine_torque: InheritableVariable<f32>,
+
+    #[reflect(setter = "set_brake_torque", min_value = 0.0, step = 10.0)]
+    brake_torque: InheritableVariable<f32>,
+
+    #[reflect(setter = "set_collision_groups")]
+    collision_groups: InheritableVariable<InteractionGroups>,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    steering: f32,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    throttle: f32,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    brake: f32,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    wheel_states: Vec<WheelState>,
+}
+
+impl Default for Vehicle {
+    fn default() -> Self {
+        VehicleBuilder::new(BaseBuilder::new()).build_vehicle()
+    }
+}
+
+impl PartialEq for Vehicle {
+    fn eq(&self, other: &Self) -> bool {
+        self.base == other.base
+            && self.wheels == other.wheels
+            && self.anti_roll_bars == other.anti_roll_bars
+            && self.max_steering_angle == other.max_steering_angle
+            && self.engine_torque == other.engine_torque
+            && self.brake_torque == other.brake_torque
+            && self.collision_groups == other.collision_groups
+    }
+}
+
+impl Deref for Vehicle {
+    type Target = Base;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl DerefMut for Vehicle {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+impl ConstructorProvider<Node, Graph> for Vehicle {
+    fn constructor() -> NodeConstructor {
+        NodeConstructor::new::<Self>()
+            .with_variant("Vehicle", |_| {
+                VehicleBuilder::new(BaseBuilder::new().with_name("Vehicle"))
+                    .build_node()
+                    .into()
+            })
+            .with_group("Physics")
+    }
+}
+
+impl Vehicle {
+    /// Sets new set of wheels.
+    pub fn set_wheels(&mut self, wheels: Vec<Wheel>) -> Vec<Wheel> {
+        self.wheels.set_value_and_mark_modified(wheels)
+    }
+
+    /// Returns a reference to the wheels of the vehicle.
+    pub fn wheels(&self) -> &[Wheel] {
+        &self.wheels
+    }
+
+    /// Returns a mutable reference to the wheels of the vehicle.
+    pub fn wheels_mut(&mut self) -> &mut Vec<Wheel> {
+        self.wheels.get_value_mut_and_mark_modified()
+    }
+
+    /// Returns runtime states of the wheels. The states are in the same order as the wheels.
+    pub fn wheel_states(&self) -> &[WheelState] {
+        &self.wheel_states
+    }
+
+    /// Sets new set of anti-roll bars.
+    pub fn set_anti_roll_bars(&mut self, bars: Vec<AntiRollBar>) -> Vec<AntiRollBar> {
+        self.anti_roll_bars.set_value_and_mark_modified(bars)
+    }
+
+    /// Returns a reference to the anti-roll bars of the vehicle.
+    pub fn anti_roll_bars(&self) -> &[AntiRollBar] {
+        &self.anti_roll_bars
+    }
+
+    /// Sets maximum steering angle (in radians).
+    pub fn set_max_steering_angle(&mut self, angle: f32) -> f32 {
+        self.max_steering_angle
+            .set_value_and_mark_modified(angle.max(0.0))
+    }
+
+    /// Returns maximum steering angle (in radians).
+    pub fn max_steering_angle(&self) -> f32 {
+        *self.max_steering_angle
+    }
+
+    /// Sets maximum torque of the engine.
+    pub fn set_engine_torque(&mut self, torque: f32) -> f32 {
+        self.engine_torque
+            .set_value_and_mark_modified(torque.max(0.0))
+    }
+
+    /// Returns maximum torque of the engine.
+    pub fn engine_torque(&self) -> f32 {
+        *self.engine_torque
+    }
+
+    /// Sets maximum torque of the brakes.
+    pub fn set_brake_torque(&mut self, torque: f32) -> f32 {
+        self.brake_torque
+            .set_value_and_mark_modified(torque.max(0.0))
+    }
+
+    /// Returns maximum torque of the brakes.
+    pub fn brake_torque(&self) -> f32 {
+        *self.brake_torque
+    }
+
+    /// Sets collision groups, that are used to filter colliders hit by the wheel rays.
+    pub fn set_collision_groups(&mut self, groups: InteractionGroups) -> InteractionGroups {
+        self.collision_groups.set_value_and_mark_modified(groups)
+    }
+
+    /// Returns current collision groups.
+    pub fn collision_groups(&self) -> InteractionGroups {
+        *self.collision_groups
+    }
+
+    /// Sets steering input in `[-1; 1]` range. Positive values turn the vehicle to the left.
+    pub fn set_steering(&mut self, steering: f32) {
+        self.steering = steering.clamp(-1.0, 1.0);
+    }
+
+    /// Returns current steering input.
+    pub fn steering(&self) -> f32 {
+        self.steering
+    }
+
+    /// Sets throttle input in `[-1; 1]` range. Negative values make the vehicle move backwards.
+    pub fn set_throttle(&mut self, throttle: f32) {
+        self.throttle = throttle.clamp(-1.0, 1.0);
+    }
+
+    /// Returns current throttle input.
+    pub fn throttle(&self) -> f32 {
+        self.throttle
+    }
+
+    /// Sets brake input in `[0; 1]` range.
+    pub fn set_brake(&mut self, brake: f32) {
+        self.brake = brake.clamp(0.0, 1.0);
+    }
+
+    /// Returns current brake input.
+    pub fn brake(&self) -> f32 {
+        self.brake
+    }
+}
+
+impl NodeTrait for Vehicle {
+    fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.base.local_bounding_box()
+    }
+
+    fn world_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.base.world_bounding_box()
+    }
+
+    fn id(&self) -> Uuid {
+        <Self as Reflect>::type_info().type_uuid
+    }
+
+    fn update(&mut self, context: &mut UpdateContext) {
+        let dt = context.dt;
+        if dt <= 0.0 || !self.is_globally_enabled() {
+            return;
+        }
+
+        let chassis_handle = self.parent().to_variant::<RigidBody>();
+        let Ok(chassis) = context.nodes.try_get(chassis_handle) else {
+            return;
+        };
+        let lin_vel = chassis.lin_vel();
+        let ang_vel = chassis.ang_vel();
+        let center = chassis.global_position();
+        let Some(mass) = context.physics.rigid_body_mass(chassis) else {
+            return;
+        };
+        let mass_share = mass / self.wheels.len().max(1) as f32;
+
+        self.wheel_states
+            .resize(self.wheels.len(), Default::default());
+
+        let transform = self.global_transform();
+        let mut forces = Vec::new();
+        let mut suspensions = Vec::with_capacity(self.wheels.len());
+        let mut intersections = Vec::new();
+        for (wheel, state) in self.wheels.iter().zip(self.wheel_states.iter_mut()) {
+            state.steering_angle = self.steering * *self.max_steering_angle * wheel.steering_factor;
+
+            let origin = transform
+                .transform_point(&Point3::from(wheel.position))
+                .coords;
+            let down = transform
+                .transform_vector(&wheel.local_down())
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(|| -Vector3::y());
+            let up = -down;
+            let axle = transform
+                .transform_vector(
+                    &(wheel.steering_rotation(state.steering_angle) * wheel.local_axle()),
+                )
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(Vector3::x);
+            suspensions.push((origin, up));
+
+            let rest_length = wheel.suspension_rest_length.max(0.0);
+            let radius = wheel.tire.radius.max(0.0);
+
+            intersections.clear();
+            context.physics.cast_ray(
+                RayCastOptions {
+                    ray_origin: Point3::from(origin),
+                    ray_direction: down,
+                    max_len: rest_length + radius,
+                    groups: *self.collision_groups,
+                    sort_results: true,
+                },
+                &mut intersections,
+            );
+            let hit = intersections.iter().find(|intersection| {
+                context
+                    .nodes
+                    .try_get(intersection.collider)
+                    .is_ok_and(|collider| {
+                        collider.parent() != self.base.parent() && !collider.is_sensor()
+                    })
+            });
+
+            let prev_length = if state.in_contact {
+                state.suspension_length
+            } else {
+                rest_length
+            };
+            let mut input = TireInput {
+                longitudinal_velocity: 0.0,
+                lateral_velocity: 0.0,
+                load: 0.0,
+                drive_torque: self.throttle * *self.engine_torque * wheel.drive_factor,
+                brake_torque: self.brake * *self.brake_torque * wheel.brake_factor,
+                mass_share,
+                dt,
+            };
+
+            if let Some(hit) = hit {
+                let length = (hit.toi - radius).clamp(0.0, rest_length);
+                let compression_velocity = (prev_length - length) / dt;
+                let suspension_force = (wheel.suspension_stiffness * (rest_length - length)
+                    + wheel.suspension_damping * compression_velocity)
+                    .clamp(0.0, wheel.max_suspension_force);
+
+                let normal = hit.normal.try_normalize(f32::EPSILON).unwrap_or(up);
+                let contact = hit.position.coords;
+                let velocity = lin_vel + ang_vel.cross(&(contact - center));
+                let forward = axle.cross(&up);
+                let longitudinal_dir = (forward - normal.scale(forward.dot(&normal)))
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or(forward);
+                let lateral_dir = (axle - normal.scale(axle.dot(&normal)))
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or(axle);
+
+                input.longitudinal_velocity = velocity.dot(&longitudinal_dir);
+                input.lateral_velocity = velocity.dot(&lateral_dir);
+                input.load = suspension_force;
+                let (longitudinal, lateral) = wheel.tire.update(state, &input);
+
+                forces.push((
+                    up.scale(suspension_force)
+                        + longitudinal_dir.scale(longitudinal)
+                        + lateral_dir.scale(lateral),
+                    contact,
+                ));
+
+                state.in_contact = true;
+                state.contact_point = contact;
+                state.contact_normal = normal;
+                state.suspension_length = length;
+                state.suspension_force = suspension_force;
+            } else {
+                wheel.tire.update(state, &input);
+
+                state.in_contact = false;
+                state.suspension_length = rest_length;
+                state.suspension_force = 0.0;
+            }
+        }
+
+        for bar in self.anti_roll_bars.iter() {
+            let (Some(left), Some(right)) = (
+                self.wheels.get(bar.left_wheel),
+                self.wheels.get(bar.right_wheel),
+            ) else {
+                continue;
+            };
+            let left_state = &self.wheel_states[bar.left_wheel];
+            let right_state = &self.wheel_states[bar.right_wheel];
+            let left_compression = left.suspension_rest_length - left_state.suspension_length;
+            let right_compression = right.suspension_rest_length - right_state.suspension_length;
+            let force = (left_compression - right_compression) * bar.stiffness;
+            if left_state.in_contact {
+                let (origin, up) = suspensions[bar.left_wheel];
+                forces.push((up.scale(force), origin));
+            }
+            if right_state.in_contact {
+                let (origin, up) = suspensions[bar.right_wheel];
+                forces.push((up.scale(-force), origin));
+            }
+        }
+
+        if let Ok(chassis) = context.nodes.try_get_mut(chassis_handle) {
+            for (force, point) in forces {
+                chassis.apply_force_at_point(force, point);
+            }
+            if self.throttle != 0.0 || self.steering != 0.0 || self.brake != 0.0 {
+                chassis.wake_up();
+            }
+        }
+
+        for (wheel, state) in self.wheels.iter().zip(self.wheel_states.iter()) {
+            let Ok(visual) = context.nodes.try_borrow_mut(wheel.visual) else {
+                continue;
+            };
+            let steering_rotation = wheel.steering_rotation(state.steering_angle);
+            let spin = UnitQuaternion::from_axis_angle(
+                &Unit::new_unchecked(wheel.local_axle()),
+                state.rotation_angle,
+            );
+            visual
+                .local_transform_mut()
+                .set_position(wheel.position + wheel.local_down().scale(state.suspension_length))
+                .set_rotation(steering_rotation * spin);
+        }
+    }
+
+    fn debug_draw(&self, ctx: &mut SceneDrawingContext) {
+        let transform = self.global_transform();
+        for (i, wheel) in self.wheels.iter().enumerate() {
+            let state = self.wheel_states.get(i);
+            let length = state.map_or(wheel.suspension_rest_length, |s| s.suspension_length);
+            let steering_angle = state.map_or(0.0, |s| s.steering_angle);
+
+            let origin = transform
+                .transform_point(&Point3::from(wheel.position))
+                .coords;
+            let center = wheel.position + wheel.local_down().scale(length);
+            ctx.add_line(Line {
+                begin: origin,
+                end: transform.transform_point(&Point3::from(center)).coords,
+                color: Color::GREEN,
+            });
+
+            // Circles are drawn in XY plane, so the wheel is rotated to make its axle point along Z.
+            let axle = wheel.steering_rotation(steering_angle) * wheel.local_axle();
+            let rotation = UnitQuaternion::rotation_between(&Vector3::z(), &axle)
+                .unwrap_or_else(|| UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.0));
+            let wheel_transform =
+                transform * Matrix4::new_translation(&center) * rotation.to_homogeneous();
+            let color = if state.is_some_and(|s| s.in_contact) {
+                Color::RED
+            } else {
+                Color::ORANGE
+            };
+            ctx.draw_circle(
+                Vector3::default(),
+                wheel.tire.radius,
+                16,
+                wheel_transform,
+                color,
+            );
+        }
+    }
+}
+
+/// Allows you to create vehicles in declarative manner.
+pub struct VehicleBuilder {
+    base_builder: BaseBuilder,
+    wheels: Vec<Wheel>,
+    anti_roll_bars: Vec<AntiRollBar>,
+    max_steering_angle: f32,
+    engine_torque: f32,
+    brake_torque: f32,
+    collision_groups: InteractionGroups,
+}
+
+impl VehicleBuilder {
+    /// Creates new vehicle builder.
+    pub fn new(base_builder: BaseBuilder) -> Self {
+        Self {
+            base_builder,
+            wheels: Default::default(),
+            anti_roll_bars: Default::default(),
+            max_steering_angle: 35.0f32.to_radians(),
+            engine_torque: 400.0,
+            brake_torque: 1500.0,
+            collision_groups: Default::default(),
+        }
+    }
+
+    /// Sets desired wheels.
+    pub fn with_wheels(mut self, wheels: Vec<Wheel>) -> Self {
+        self.wheels = wheels;
+        self
+    }
+
+    /// Sets desired anti-roll bars.
+    pub fn with_anti_roll_bars(mut self, bars: Vec<AntiRollBar>) -> Self {
+        self.anti_roll_bars = bars;
+        self
+    }
+
+    /// Sets desired maximum steering angle (in radians).
+    pub fn with_max_steering_angle(mut self, angle: f32) -> Self {
+        self.max_steering_angle = angle;
+        self
+    }
+
+    /// Sets desired maximum torque of the engine.
+    pub fn with_engine_torque(mut self, torque: f32) -> Self {
+        self.engine_torque = torque;
+        self
+    }
+
+    /// Sets desired maximum torque of the brakes.
+    pub fn with_brake_torque(mut self, torque: f32) -> Self {
+        self.brake_torque = torque;
+        self
+    }
+
+    /// Sets desired collision groups.
+    pub fn with_collision_groups(mut self, groups: InteractionGroups) -> Self {
+        self.collision_groups = groups;
+        self
+    }
+
+    /// Creates vehicle instance.
+    pub fn build_vehicle(self) -> Vehicle {
+        Vehicle {
+            base: self.base_builder.build_base(),
+            wheels: self.wheels.into(),
+            anti_roll_bars: self.anti_roll_bars.into(),
+            max_steering_angle: self.max_steering_angle.into(),
+            engine_torque: self.engine_torque.into(),
+            brake_torque: self.brake_torque.into(),
+            collision_groups: self.collision_groups.into(),
+            steering: 0.0,
+            throttle: 0.0,
+            brake: 0.0,
+            wheel_states: Default::default(),
+        }
+    }
+
+    /// Creates vehicle node.
+    pub fn build_node(self) -> Node {
+        Node::new(self.build_vehicle())
+    }
+
+    /// Creates vehicle node and adds it to the graph.
+    pub fn build(self, graph: &mut Graph) -> Handle<Vehicle> {
+        graph.add_node(self.build_node()).to_variant()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::{Vector2, Vector3},
+        scene::{
+            base::BaseBuilder,
+            collider::{ColliderBuilder, ColliderShape},
+            graph::Graph,
+            rigidbody::{RigidBodyBuilder, RigidBodyType},
+            transform::TransformBuilder,
+            vehicle::{VehicleBuilder, Wheel},
+        },
+    };
+
+    #[test]
+    fn test_vehicle_rests_on_suspension_and_drives() {
+        let mut graph = Graph::new();
+
+        let ground_collider = ColliderBuilder::new(BaseBuilder::new())
+            .with_shape(ColliderShape::cuboid(50.0, 0.5, 50.0))
+            .build(&mut graph);
+        RigidBodyBuilder::new(
+            BaseBuilder::new()
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(Vector3::new(0.0, -0.5, 0.0))
+                        .build(),
+                )
+                .with_child(ground_collider),
+        )
+        .with_body_type(RigidBodyType::Static)
+        .build(&mut graph);
+
+        let wheels = [(-0.8, 1.5), (0.8, 1.5), (-0.8, -1.5), (0.8, -1.5)]
+            .into_iter()
+            .map(|(x, z)| Wheel::default().with_position(Vector3::new(x, 0.0, z)))
+            .collect();
+        let vehicle = VehicleBuilder::new(BaseBuilder::new())
+            .with_wheels(wheels)
+            .build(&mut graph);
+        // 1000 kg chassis.
+        let chassis_collider = ColliderBuilder::new(BaseBuilder::new())
+            .with_shape(ColliderShape::cuboid(1.0, 0.25, 2.0))
+            .with_density(Some(125.0))
+            .build(&mut graph);
+        let chassis = RigidBodyBuilder::new(
+            BaseBuilder::new()
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(Vector3::new(0.0, 0.8, 0.0))
+                        .build(),
+                )
+                .with_child(chassis_collider)
+                .with_child(vehicle),
+        )
+        .with_can_sleep(false)
+        .build(&mut graph);
+
+        let dt = 1.0 / 60.0;
+        for _ in 0..300 {
+            graph.update(Vector2::new(800.0, 600.0), dt, Default::default());
+        }
+
+        assert!(graph[vehicle]
+            .wheel_states()
+            .iter()
+            .all(|state| state.in_contact));
+        let height = graph[chassis].global_position().y;
+        assert!(height > 0.4 && height < 0.7, "{height}");
+        assert!(graph[chassis].lin_vel().norm() < 0.1);
+
+        graph[vehicle].set_throttle(1.0);
+        for _ in 0..120 {
+            graph.update(Vector2::new(800.0, 600.0), dt, Default::default());
+        }
+
+        let lin_vel = graph[chassis].lin_vel();
+        assert!(lin_vel.z > 1.0, "{lin_vel:?}");
+        assert!(lin_vel.x.abs() < 0.1, "{lin_vel:?}");
+    }
+}
@@ -0,0 +1,550 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Water volumes with buoyancy. See [`WaterVolume`] docs for more info.
+
+use crate::{
+    core::{
+        algebra::{Vector2, Vector3},
+        color::Color,
+        math::aabb::AxisAlignedBoundingBox,
+        pool::Handle,
+        reflect::prelude::*,
+        uuid::{uuid, Uuid},
+        variable::InheritableVariable,
+        visitor::prelude::*,
+    },
+    scene::{
+        base::{Base, BaseBuilder},
+        debug::{Line, SceneDrawingContext},
+        graph::{physics::buoyancy::WaterVolumeDescriptor, Graph},
+        node::{constructor::NodeConstructor, Node, NodeTrait, SyncContext, UpdateContext},
+    },
+};
+use fyrox_graph::{constructor::ConstructorProvider, SceneGraph};
+use std::ops::{Deref, DerefMut};
+use strum_macros::{AsRefStr, EnumString, VariantNames};
+
+/// A single sine wave travelling along the surface of the water.
+#[derive(Clone, Debug, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "25ebe25c-3812-4ad0-9804-ff0d6b62940d")]
+pub struct Wave {
+    /// Height of the wave crest above the calm surface.
+    #[reflect(min_value = 0.0, step = 0.01)]
+    pub amplitude: f32,
+
+    /// Distance between two neighbouring crests.
+    #[reflect(min_value = 0.01, step = 0.1)]
+    pub wavelength: f32,
+
+    /// Speed of the wave (in units per second).
+    #[reflect(step = 0.1)]
+    pub speed: f32,
+
+    /// Direction of the wave in the local XZ plane of the water volume.
+    pub direction: Vector2<f32>,
+}
+
+impl Default for Wave {
+    fn default() -> Self {
+        Self {
+            amplitude: 0.25,
+            wavelength: 10.0,
+            speed: 2.0,
+            direction: Vector2::new(1.0, 0.0),
+        }
+    }
+}
+
+impl Wave {
+    /// Calculates height of the wave at the given point of the local XZ plane at the given time.
+    pub fn height(&self, position: Vector2<f32>, time: f32) -> f32 {
+        let Some(direction) = self.direction.try_normalize(f32::EPSILON) else {
+            return 0.0;
+        };
+        if self.wavelength <= f32::EPSILON {
+            return 0.0;
+        }
+        let wave_number = std::f32::consts::TAU / self.wavelength;
+        self.amplitude * (wave_number * (direction.dot(&position) - self.speed * time)).sin()
+    }
+}
+
+/// Shape of the surface of a water volume.
+#[derive(Clone, Debug, Default, PartialEq, Visit, Reflect, AsRefStr, EnumString, VariantNames)]
+#[reflect(type_uuid = "93fb4599-7041-41c2-bf3d-1dbabbb4641e")]
+pub enum WaterSurface {
+    /// Flat surface at the top of the volume.
+    #[default]
+    Planar,
+    /// A sum of sine waves on top of the volume.
+    Waves {
+        /// A set of waves that form the surface.
+        waves: Vec<Wave>,
+    },
+}
+
+impl WaterSurface {
+    /// Calculates height of the surface (relative to the top of the volume) at the given point of
+    /// the local XZ plane at the given time.
+    pub fn height(&self, position: Vector2<f32>, time: f32) -> f32 {
+        match self {
+            WaterSurface::Planar => 0.0,
+            WaterSurface::Waves { waves } => {
+                waves.iter().map(|wave| wave.height(position, time)).sum()
+            }
+        }
+    }
+
+    /// Returns the maximum possible height of the surface relative to the top of the volume.
+    pub fn max_height(&self) -> f32 {
+        match self {
+            WaterSurface::Planar => 0.0,
+            WaterSurface::Waves { waves } => waves.iter().map(|wave| wave.amplitude.abs()).sum(),
+        }
+    }
+}
+
+/// Water volume is a box filled with water, that applies buoyancy, drag and angular damping to
+/// every dynamic [`crate::scene::rigidbody::RigidBody`] whose colliders are submerged into the
+/// water. The volume spans [`Self::size`] around the node, the surface of the water is at the top
+/// of the volume and it could be either flat or formed by a set of waves (see [`WaterSurface`]).
+///
+/// Buoyancy force is calculated using the submerged volume of the colliders, so it follows the
+/// Archimedes' principle: a body floats if the density of its colliders is less than the density
+/// of the water. Keep in mind, that the density is defined in kilograms per cubic meter, which
+/// means that the colliders must have realistic densities too (for example, 600 for wood), see
+/// [`crate::scene::collider::Collider::set_density`]. Sensor colliders are ignored.
+///
+/// The forces are applied by the physics world right before every simulation step, so they work
+/// correctly with fixed physics time step as well.
+///
+/// ## Example
+///
+/// ```rust
+/// # use fyrox_impl::{
+/// #     core::{algebra::{Vector2, Vector3}, pool::Handle},
+/// #     scene::{
+/// #         base::BaseBuilder,
+/// #         graph::Graph,
+/// #         water::{Wave, WaterSurface, WaterVolume, WaterVolumeBuilder},
+/// #     },
+/// # };
+/// fn create_sea(graph: &mut Graph) -> Handle<WaterVolume> {
+///     WaterVolumeBuilder::new(BaseBuilder::new())
+///         .with_size(Vector3::new(200.0, 20.0, 200.0))
+///         .with_surface(WaterSurface::Waves {
+///             waves: vec![
+///                 Wave::default(),
+///                 Wave {
+///                     amplitude: 0.1,
+///                     wavelength: 3.0,
+///                     speed: 1.0,
+///                     direction: Vector2::new(0.7, 0.7),
+///                 },
+///             ],
+///         })
+///         .build(graph)
+/// }
+/// ```
+#[derive(Visit, Reflect, PartialEq, Clone, Debug)]
+#[reflect(
+    derived_type = "Node",
+    type_uuid = "f159def7-ca79-442d-bf51-61d5e6fae944"
+)]
+#[visit(optional)]
+pub struct WaterVolume {
+    base: Base,
+
+    #[reflect(setter = "set_size")]
+    size: InheritableVariable<Vector3<f32>>,
+
+    #[reflect(setter = "set_surface")]
+    surface: InheritableVariable<WaterSurface>,
+
+    #[reflect(setter = "set_density", min_value = 0.0, step = 10.0)]
+    density: InheritableVariable<f32>,
+
+    #[reflect(setter = "set_linear_drag", min_value = 0.0, step = 0.1)]
+    linear_drag: InheritableVariable<f32>,
+
+    #[reflect(setter = "set_angular_drag", min_value = 0.0, step = 0.1)]
+    angular_drag: InheritableVariable<f32>,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    time: f32,
+}
+
+impl Default for WaterVolume {
+    fn default() -> Self {
+        WaterVolumeBuilder::new(BaseBuilder::new()).build_water_volume()
+    }
+}
+
+impl Deref for WaterVolume {
+    type Target = Base;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl DerefMut for WaterVolume {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+impl ConstructorProvider<Node, Graph> for WaterVolume {
+    fn constructor() -> NodeConstructor {
+        NodeConstructor::new::<Self>()
+            .with_variant("Water Volume", |_| {
+                WaterVolumeBuilder::new(BaseBuilder::new().with_name("Water Volume"))
+                    .build_node()
+                    .into()
+            })
+            .with_group("Physics")
+    }
+}
+
+impl WaterVolume {
+    /// Sets new size of the volume. The surface of the water is at the top of the volume.
+    pub fn set_size(&mut self, size: Vector3<f32>) -> Vector3<f32> {
+        self.size
+            .set_value_and_mark_modified(size.map(|v| v.max(0.0)))
+    }
+
+    /// Returns current size of the volume.
+    pub fn size(&self) -> Vector3<f32> {
+        *self.size
+    }
+
+    /// Sets new shape of the surface of the water.
+    pub fn set_surface(&mut self, surface: WaterSurface) -> WaterSurface {
+        self.surface.set_value_and_mark_modified(surface)
+    }
+
+    /// Returns current shape of the surface of the water.
+    pub fn surface(&self) -> &WaterSurface {
+        &self.surface
+    }
+
+    /// Sets density of the water (in kilograms per cubic meter). Default is 1000.0.
+    pub fn set_density(&mut self, density: f32) -> f32 {
+        self.density.set_value_and_mark_modified(density.max(0.0))
+    }
+
+    /// Returns current density of the water.
+    pub fn density(&self) -> f32 {
+        *self.density
+    }
+
+    /// Sets linear drag of the water. It defines how fast a fully submerged body loses its
+    /// linear velocity, partially submerged bodies are affected proportionally. Default is 3.0.
+    pub fn set_linear_drag(&mut self, drag: f32) -> f32 {
+        self.linear_drag.set_value_and_mark_modified(drag.max(0.0))
+    }
+
+    /// Returns current linear drag of the water.
+    pub fn linear_drag(&self) -> f32 {
+        *self.linear_drag
+    }
+
+    /// Sets angular drag of the water. It defines how fast a fully submerged body loses its
+    /// angular velocity, partially submerged bodies are affected proportionally. Default is 1.0.
+    pub fn set_angular_drag(&mut self, drag: f32) -> f32 {
+        self.angular_drag.set_value_and_mark_modified(drag.max(0.0))
+    }
+
+    /// Returns current angular drag of the water.
+    pub fn angular_drag(&self) -> f32 {
+        *self.angular_drag
+    }
+
+    /// Calculates depth of the given point (in world coordinates) under the surface of the water.
+    /// The depth is negative if the point is above the surface. Returns `None` if the point is
+    /// outside the volume horizontally or below its bottom. Could be used to implement effects,
+    /// such as splashes or underwater fog.
+    pub fn depth_at(&self, point: Vector3<f32>) -> Option<f32> {
+        self.descriptor().and_then(|volume| volume.depth(&point))
+    }
+
+    fn local_bounds(&self) -> AxisAlignedBoundingBox {
+        let half_extents = self.size.scale(0.5);
+        AxisAlignedBoundingBox::from_min_max(
+            -half_extents,
+            Vector3::new(
+                half_extents.x,
+                half_extents.y + self.surface.max_height(),
+                half_extents.z,
+            ),
+        )
+    }
+
+    fn descriptor(&self) -> Option<WaterVolumeDescriptor> {
+        let transform = self.global_transform();
+        let inv_transform = transform.try_inverse()?;
+        Some(WaterVolumeDescriptor {
+            inv_transform,
+            half_extents: self.size.scale(0.5),
+            vertical_scale: transform.fixed_view::<3, 1>(0, 1).norm(),
+            world_bounds: self.local_bounds().transform(&transform),
+            surface: (*self.surface).clone(),
+            time: self.time,
+            density: *self.density,
+            linear_drag: *self.linear_drag,
+            angular_drag: *self.angular_drag,
+        })
+    }
+}
+
+impl NodeTrait for WaterVolume {
+    fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.local_bounds()
+    }
+
+    fn world_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.local_bounding_box()
+            .transform(&self.global_transform())
+    }
+
+    fn id(&self) -> Uuid {
+        <Self as Reflect>::type_info().type_uuid
+    }
+
+    fn sync_native(&self, _self_handle: Handle<Node>, context: &mut SyncContext) {
+        if self.is_globally_enabled() {
+            if let Some(descriptor) = self.descriptor() {
+                context.physics.water_volumes.push(descriptor);
+            }
+        }
+    }
+
+    fn update(&mut self, context: &mut UpdateContext) {
+        self.time += context.dt;
+    }
+
+    fn debug_draw(&self, ctx: &mut SceneDrawingContext) {
+        let half_extents = self.size.scale(0.5);
+        let transform = self.global_transform();
+        ctx.draw_oob(
+            &AxisAlignedBoundingBox::from_min_max(-half_extents, half_extents),
+            transform,
+            Color::BLUE,
+        );
+
+        // Draw the surface as a grid of lines.
+        const RESOLUTION: usize = 16;
+        let point = |i: usize, j: usize| {
+            let x = half_extents.x * (2.0 * i as f32 / RESOLUTION as f32 - 1.0);
+            let z = half_extents.z * (2.0 * j as f32 / RESOLUTION as f32 - 1.0);
+            let y = half_extents.y + self.surface.height(Vector2::new(x, z), self.time);
+            transform
+                .transform_point(&Vector3::new(x, y, z).into())
+                .coords
+        };
+        for i in 0..=RESOLUTION {
+            for j in 0..RESOLUTION {
+                ctx.add_line(Line {
+                    begin: point(i, j),
+                    end: point(i, j + 1),
+                    color: Color::opaque(0, 120, 255),
+                });
+                ctx.add_line(Line {
+                    begin: point(j, i),
+                    end: point(j + 1, i),
+                    color: Color::opaque(0, 120, 255),
+                });
+            }
+        }
+    }
+}
+
+/// Allows you to create water volumes in declarative manner.
+pub struct WaterVolumeBuilder {
+    base_builder: BaseBuilder,
+    size: Vector3<f32>,
+    surface: WaterSurface,
+    density: f32,
+    linear_drag: f32,
+    angular_drag: f32,
+}
+
+impl WaterVolumeBuilder {
+    /// Creates new water volume builder.
+    pub fn new(base_builder: BaseBuilder) -> Self {
+        Self {
+            base_builder,
+            size: Vector3::new(10.0, 2.0, 10.0),
+            surface: Default::default(),
+            density: 1000.0,
+            linear_drag: 3.0,
+            angular_drag: 1.0,
+        }
+    }
+
+    /// Sets desired size of the volume.
+    pub fn with_size(mut self, size: Vector3<f32>) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Sets desired shape of the surface of the water.
+    pub fn with_surface(mut self, surface: WaterSurface) -> Self {
+        self.surface = surface;
+        self
+    }
+
+    /// Sets desired density of the water. See [`WaterVolume::set_density`] for more info.
+    pub fn with_density(mut self, density: f32) -> Self {
+        self.density = density;
+        self
+    }
+
+    /// Sets desired linear drag. See [`WaterVolume::set_linear_drag`] for more info.
+    pub fn with_linear_drag(mut self, drag: f32) -> Self {
+        self.linear_drag = drag;
+        self
+    }
+
+    /// Sets desired angular drag. See [`WaterVolume::set_angular_drag`] for more info.
+    pub fn with_angular_drag(mut self, drag: f32) -> Self {
+        self.angular_drag = drag;
+        self
+    }
+
+    /// Creates water volume instance.
+    pub fn build_water_volume(self) -> WaterVolume {
+        WaterVolume {
+            base: self.base_builder.build_base(),
+            size: self.size.into(),
+            surface: self.surface.into(),
+            density: self.density.into(),
+            linear_drag: self.linear_drag.into(),
+            angular_drag: self.angular_drag.into(),
+            time: 0.0,
+        }
+    }
+
+    /// Creates [`WaterVolume`] node.
+    pub fn build_node(self) -> Node {
+        Node::new(self.build_water_volume())
+    }
+
+    /// Creates [`WaterVolume`] node and adds it to the scene graph.
+    pub fn build(self, graph: &mut Graph) -> Handle<WaterVolume> {
+        graph.add_node(self.build_node()).to_variant()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            algebra::{Vector2, Vector3},
+            pool::Handle,
+        },
+        scene::{
+            base::BaseBuilder,
+            collider::{ColliderBuilder, ColliderShape},
+            graph::Graph,
+            rigidbody::{RigidBody, RigidBodyBuilder},
+            transform::TransformBuilder,
+            water::{WaterSurface, WaterVolumeBuilder, Wave},
+        },
+    };
+
+    fn add_box(graph: &mut Graph, x: f32, density: f32) -> Handle<RigidBody> {
+        let collider = ColliderBuilder::new(BaseBuilder::new())
+            .with_shape(ColliderShape::cuboid(0.5, 0.5, 0.5))
+            .with_density(Some(density))
+            .build(graph);
+        RigidBodyBuilder::new(
+            BaseBuilder::new()
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(Vector3::new(x, 1.0, 0.0))
+                        .build(),
+                )
+                .with_child(collider),
+        )
+        .build(graph)
+    }
+
+    #[test]
+    fn test_water_volume_buoyancy() {
+        let mut graph = Graph::new();
+
+        // The surface is at y = 0.
+        WaterVolumeBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, -5.0, 0.0))
+                    .build(),
+            ),
+        )
+        .with_size(Vector3::new(20.0, 10.0, 20.0))
+        .build(&mut graph);
+
+        let half = add_box(&mut graph, -4.0, 500.0);
+        let quarter = add_box(&mut graph, 0.0, 250.0);
+        let sinking = add_box(&mut graph, 4.0, 2000.0);
+
+        for _ in 0..600 {
+            graph.update(Vector2::new(800.0, 600.0), 1.0 / 60.0, Default::default());
+        }
+
+        // A body floats when the weight of displaced water is equal to its own weight.
+        let height = graph[half].global_position().y;
+        assert!(height.abs() < 0.05, "{height}");
+        assert!(graph[half].lin_vel().norm() < 0.05);
+        let height = graph[quarter].global_position().y;
+        assert!((height - 0.25).abs() < 0.05, "{height}");
+        let height = graph[sinking].global_position().y;
+        assert!(height < -2.0, "{height}");
+    }
+
+    #[test]
+    fn test_water_surface_waves() {
+        let surface = WaterSurface::Waves {
+            waves: vec![Wave {
+                amplitude: 0.5,
+                wavelength: 4.0,
+                speed: 1.0,
+                direction: Vector2::new(1.0, 0.0),
+            }],
+        };
+        assert_eq!(surface.max_height(), 0.5);
+        assert!(surface.height(Vector2::new(0.0, 0.0), 0.0).abs() < 1.0e-5);
+        assert!((surface.height(Vector2::new(1.0, 3.0), 0.0) - 0.5).abs() < 1.0e-5);
+        // The crest moves along the direction of the wave.
+        assert!((surface.height(Vector2::new(2.0, 0.0), 1.0) - 0.5).abs() < 1.0e-5);
+
+        let mut graph = Graph::new();
+        let water = WaterVolumeBuilder::new(BaseBuilder::new())
+            .with_size(Vector3::new(10.0, 2.0, 10.0))
+            .with_surface(surface)
+            .build(&mut graph);
+        graph.update(Vector2::new(800.0, 600.0), 0.0, Default::default());
+        let depth = graph[water].depth_at(Vector3::new(1.0, 0.0, 0.0)).unwrap();
+        assert!((depth - 1.5).abs() < 1.0e-5, "{depth}");
+        assert!(graph[water].depth_at(Vector3::new(6.0, 0.0, 0.0)).is_none());
+    }
+}
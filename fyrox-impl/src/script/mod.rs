@@ -325,6 +325,10 @@ pub struct ScriptContext<'a, 'b, 'c> {
     /// which the engine "ticks" and this delta time affects elapsed time.
     pub elapsed_time: f32,
 
+    /// Interpolation factor in `[0; 1]` range that tells how far the current frame is between the
+    /// last and the next fixed update (see [`ScriptTrait::on_fixed_update`]).
+    pub fixed_update_alpha: f32,
+
     /// A slice of references to all registered plugins. For example you can store some "global" data
     /// in a plugin and access it later in scripts. A simplest example would be something like this:
     ///
@@ -646,6 +650,21 @@ pub trait ScriptTrait: BaseScript {
         Ok(())
     }
 
+    /// Performs a single fixed update tick of the script. Unlike [`Self::on_update`], `ctx.dt` is always
+    /// equal to the fixed time step of the engine (see [`crate::engine::Engine::set_fixed_time_step`])
+    /// and the method is called right before each physics step, zero or multiple times per frame. Use
+    /// it for deterministic logic, such as physics-driven movement, lockstep networking or replays.
+    ///
+    /// Keep in mind, that rigid bodies are moved only by fixed updates, so their positions may lag
+    /// behind the rendered frame. Use [`ScriptContext::fixed_update_alpha`] to interpolate the
+    /// transform of a *visual* node (not the rigid body itself) between two physics steps.
+    fn on_fixed_update(
+        &mut self,
+        #[allow(unused_variables)] ctx: &mut ScriptContext,
+    ) -> GameResult {
+        Ok(())
+    }
+
     /// Allows you to react to certain script messages. It could be used for communication between scripts; to
     /// bypass borrowing issues. If you need to receive messages of a particular type, you must subscribe to a type
     /// explicitly. Usually it is done in [`ScriptTrait::on_start`] method:
@@ -4,7 +4,7 @@
 
 #![warn(missing_docs)]
 
-use crate::shader::{ShaderResource, ShaderResourceExtension};
+use crate::shader::{ShaderResource, ShaderResourceExtension, ShaderResourceKind};
 use fxhash::FxHashMap;
 use fyrox_core::{
     algebra::{Matrix2, Matrix3, Matrix4, Vector2, Vector3, Vector4},
@@ -992,6 +992,48 @@ impl Material {
         &self.shader
     }
 
+    /// Removes the resource bindings and the properties that do not match the shader anymore:
+    /// the ones that are missing in the shader or have a different type. It should be called
+    /// when the shader was changed (for example, reloaded), so the material won't keep stale
+    /// values. Returns `false` if the shader is not loaded, the material is left unchanged in
+    /// this case.
+    pub fn sync_to_shader(&mut self) -> bool {
+        let shader_data = self.shader.data_ref();
+        let Some(shader) = shader_data.as_loaded_ref() else {
+            return false;
+        };
+
+        self.resource_bindings.retain(|name, binding| {
+            let Some(definition) = shader
+                .definition
+                .resources
+                .iter()
+                .find(|resource| &resource.name == name)
+            else {
+                return false;
+            };
+
+            match (binding, &definition.kind) {
+                (MaterialResourceBinding::Texture(_), ShaderResourceKind::Texture { .. }) => true,
+                (
+                    MaterialResourceBinding::PropertyGroup(group),
+                    ShaderResourceKind::PropertyGroup(shader_properties),
+                ) => {
+                    group.properties.retain(|name, property| {
+                        shader_properties.iter().any(|shader_property| {
+                            &shader_property.name == name
+                                && shader_property.kind.as_ref() == property.as_ref()
+                        })
+                    });
+                    true
+                }
+                _ => false,
+            }
+        });
+
+        true
+    }
+
     /// Returns immutable reference to internal property storage.
     pub fn bindings(&self) -> &FxHashMap<ImmutableString, MaterialResourceBinding> {
         &self.resource_bindings
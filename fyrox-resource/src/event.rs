@@ -19,14 +19,29 @@
 // SOFTWARE.
 
 //! Resource event handling.
+//!
+//! ## Dependency-aware reloading
+//!
+//! When a resource is reloaded, every resource that uses it (directly or via a chain of other
+//! resources) may contain data derived from the old version. For example, a prefab that
+//! instantiates a model keeps copies of the model nodes, and a material keeps values of the shader
+//! properties. The resource manager tracks reloads using [`ReloadPropagator`] and broadcasts
+//! [`ResourceEvent::DependencyReloaded`] for every such dependent resource, so the subscribers
+//! could refresh the derived data.
 
-use crate::core::{
-    parking_lot::Mutex,
-    pool::{Handle, Pool},
-    SafeLock,
+use crate::{
+    collect_used_resources,
+    core::{
+        parking_lot::Mutex,
+        pool::{Handle, Pool},
+        SafeLock,
+    },
+    state::ResourceState,
+    UntypedResource,
 };
-use crate::UntypedResource;
+use fxhash::{FxHashMap, FxHashSet};
 use std::{
+    collections::VecDeque,
     path::PathBuf,
     sync::{mpsc::Sender, Arc},
 };
@@ -45,6 +60,16 @@ pub enum ResourceEvent {
 
     /// Occurs when a resource was removed from a resource container.
     Removed(PathBuf),
+
+    /// Occurs when a resource that the given resource depends on (directly or indirectly) was
+    /// reloaded. The resource itself is not reloaded, but the data derived from the dependency
+    /// should be refreshed. See the module docs for more info.
+    DependencyReloaded {
+        /// A resource that depends on the reloaded resource.
+        resource: UntypedResource,
+        /// The reloaded resource.
+        dependency: UntypedResource,
+    },
 }
 
 /// Type alias for event sender.
@@ -101,13 +126,286 @@ impl ResourceEventBroadcaster {
             ResourceEvent::Loaded(resource)
         })
     }
+
+    /// Sends a [`ResourceEvent::DependencyReloaded`] event to all "subscribers" in the broadcaster.
+    pub fn broadcast_dependency_reloaded(
+        &self,
+        resource: UntypedResource,
+        dependency: UntypedResource,
+    ) {
+        self.broadcast(ResourceEvent::DependencyReloaded {
+            resource,
+            dependency,
+        })
+    }
+}
+
+/// Collects the resources used by the given resource. Dependencies of embedded resources are
+/// attributed to the owner, because embedded resources are not tracked by the resource manager.
+fn collect_dependencies(resource: &UntypedResource, dependencies: &mut FxHashSet<UntypedResource>) {
+    let mut used = FxHashSet::default();
+    if let ResourceState::Ok { ref data } = resource.lock().state {
+        collect_used_resources(data, &mut used);
+    }
+    for dependency in used {
+        if dependencies.insert(dependency.clone()) && dependency.is_embedded() {
+            collect_dependencies(&dependency, dependencies);
+        }
+    }
+}
+
+/// Finds all the resources from the given set, that depend on the `reloaded` resource directly or
+/// indirectly. Returns pairs `(dependent, reloaded)`. The resulting list is sorted topologically,
+/// which means that every resource comes after all of its dependencies that were affected by the
+/// reload. This order should be used to refresh the dependent resources. Resources that form a
+/// dependency cycle cannot be sorted, they're placed at the end of the list.
+pub fn find_dependents<'a>(
+    reloaded: &[UntypedResource],
+    resources: impl IntoIterator<Item = &'a UntypedResource>,
+) -> Vec<(UntypedResource, UntypedResource)> {
+    // Reverse dependency graph: resource -> its users.
+    let mut users = FxHashMap::<UntypedResource, Vec<UntypedResource>>::default();
+    for resource in resources {
+        let mut dependencies = FxHashSet::default();
+        collect_dependencies(resource, &mut dependencies);
+        for dependency in dependencies {
+            if &dependency != resource {
+                users.entry(dependency).or_default().push(resource.clone());
+            }
+        }
+    }
+
+    // Collect all the affected resources first. Every resource is attributed to the first reloaded
+    // resource it is reachable from.
+    let mut affected = Vec::<(UntypedResource, UntypedResource)>::new();
+    let mut indices = FxHashMap::<UntypedResource, usize>::default();
+    for root in reloaded {
+        let mut queue = VecDeque::from([root.clone()]);
+        while let Some(resource) = queue.pop_front() {
+            for user in users.get(&resource).into_iter().flatten() {
+                if user != root && !indices.contains_key(user) {
+                    indices.insert(user.clone(), affected.len());
+                    affected.push((user.clone(), root.clone()));
+                    queue.push_back(user.clone());
+                }
+            }
+        }
+    }
+
+    // Then sort the affected subgraph using Kahn's algorithm.
+    let affected_users = |index: usize| {
+        users
+            .get(&affected[index].0)
+            .into_iter()
+            .flatten()
+            .filter_map(|user| indices.get(user).copied())
+    };
+    let mut in_degree = vec![0usize; affected.len()];
+    for index in 0..affected.len() {
+        for user in affected_users(index) {
+            in_degree[user] += 1;
+        }
+    }
+    let mut queue = (0..affected.len())
+        .filter(|index| in_degree[*index] == 0)
+        .collect::<VecDeque<_>>();
+    let mut order = Vec::with_capacity(affected.len());
+    while let Some(index) = queue.pop_front() {
+        order.push(index);
+        for user in affected_users(index) {
+            in_degree[user] -= 1;
+            if in_degree[user] == 0 {
+                queue.push_back(user);
+            }
+        }
+    }
+    order.extend((0..affected.len()).filter(|index| in_degree[*index] > 0));
+
+    order
+        .into_iter()
+        .map(|index| affected[index].clone())
+        .collect()
+}
+
+/// Collects reloaded resources and broadcasts [`ResourceEvent::DependencyReloaded`] for every
+/// resource that depends on them. It is used by the resource manager internally, see the module
+/// docs for more info.
+#[derive(Clone, Default)]
+pub struct ReloadPropagator {
+    reloaded: Arc<Mutex<Vec<UntypedResource>>>,
+}
+
+impl ReloadPropagator {
+    /// Creates a new propagator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks the given resource as reloaded. Its dependents will be found on the next update.
+    pub fn mark_reloaded(&self, resource: UntypedResource) {
+        self.reloaded.safe_lock().push(resource);
+    }
+
+    /// Processes all the pending reloads. `resources` is a set of resources, that will be checked
+    /// for dependencies. Returns the amount of dependent resources found.
+    pub fn update<'a>(
+        &self,
+        resources: impl IntoIterator<Item = &'a UntypedResource>,
+        broadcaster: &ResourceEventBroadcaster,
+    ) -> usize {
+        let reloaded = std::mem::take(&mut *self.reloaded.safe_lock());
+        if reloaded.is_empty() {
+            return 0;
+        }
+
+        let dependents = find_dependents(&reloaded, resources);
+        let count = dependents.len();
+        for (resource, dependency) in dependents {
+            broadcaster.broadcast_dependency_reloaded(resource, dependency);
+        }
+        count
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use super::*;
+    use crate::{
+        core::{reflect::prelude::*, uuid::Uuid, visitor::prelude::*},
+        untyped::ResourceKind,
+        ResourceData,
+    };
     use std::sync::mpsc::channel;
+    use std::{error::Error, path::Path};
 
-    use super::*;
+    #[derive(Debug, Default, Clone, PartialEq, Visit, Reflect)]
+    #[reflect(type_uuid = "8e2b4f0c-61d7-4a3e-9c5b-d07f1a2e6b94")]
+    struct Dependent {
+        dependency: Option<UntypedResource>,
+    }
+
+    impl ResourceData for Dependent {
+        fn save(&mut self, _path: &Path) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        fn can_be_saved(&self) -> bool {
+            false
+        }
+
+        fn try_clone_box(&self) -> Option<Box<dyn ResourceData>> {
+            Some(Box::new(self.clone()))
+        }
+    }
+
+    fn external(dependency: Option<&UntypedResource>) -> UntypedResource {
+        UntypedResource::new_ok(
+            Uuid::new_v4(),
+            ResourceKind::External,
+            Dependent {
+                dependency: dependency.cloned(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_find_dependents() {
+        let shader = external(None);
+        let material = external(Some(&shader));
+        let model = external(Some(&material));
+        let unrelated = external(None);
+        let resources = [
+            model.clone(),
+            unrelated.clone(),
+            material.clone(),
+            shader.clone(),
+        ];
+
+        let dependents = find_dependents(std::slice::from_ref(&shader), &resources);
+        assert_eq!(
+            dependents,
+            [(material, shader.clone()), (model, shader.clone())]
+        );
+    }
+
+    #[test]
+    fn test_find_dependents_diamond() {
+        #[derive(Debug, Default, Clone, PartialEq, Visit, Reflect)]
+        #[reflect(type_uuid = "3f9c1d2a-7b64-4e8f-a5d0-6c2e9b1f8a37")]
+        struct MultiDependent {
+            dependencies: Vec<UntypedResource>,
+        }
+
+        impl ResourceData for MultiDependent {
+            fn save(&mut self, _path: &Path) -> Result<(), Box<dyn Error>> {
+                Ok(())
+            }
+
+            fn can_be_saved(&self) -> bool {
+                false
+            }
+
+            fn try_clone_box(&self) -> Option<Box<dyn ResourceData>> {
+                Some(Box::new(self.clone()))
+            }
+        }
+
+        // A depends on B and C, C depends on B.
+        let b = external(None);
+        let c = external(Some(&b));
+        let a = UntypedResource::new_ok(
+            Uuid::new_v4(),
+            ResourceKind::External,
+            MultiDependent {
+                dependencies: vec![b.clone(), c.clone()],
+            },
+        );
+
+        // A is a direct user of B, but it must be refreshed after C anyway.
+        let dependents = find_dependents(std::slice::from_ref(&b), [&a, &b, &c]);
+        assert_eq!(dependents, [(c, b.clone()), (a, b)]);
+    }
+
+    #[test]
+    fn test_find_dependents_through_embedded_resource() {
+        let shader = external(None);
+        let material = UntypedResource::new_embedded(Dependent {
+            dependency: Some(shader.clone()),
+        });
+        let model = external(Some(&material));
+
+        let dependents = find_dependents(std::slice::from_ref(&shader), [&shader, &model]);
+        assert_eq!(dependents, [(model, shader)]);
+    }
+
+    #[test]
+    fn test_reload_propagator() {
+        let broadcaster = ResourceEventBroadcaster::new();
+        let propagator = ReloadPropagator::new();
+        let (sender, receiver) = channel();
+        broadcaster.add(sender);
+
+        let texture = external(None);
+        let material = external(Some(&texture));
+
+        propagator.mark_reloaded(texture.clone());
+        assert_eq!(propagator.update([&texture, &material], &broadcaster), 1);
+
+        match receiver.recv() {
+            Ok(ResourceEvent::DependencyReloaded {
+                resource,
+                dependency,
+            }) => {
+                assert_eq!(resource, material);
+                assert_eq!(dependency, texture);
+            }
+            _ => panic!("DependencyReloaded event expected"),
+        }
+
+        // Every reload is processed only once.
+        assert_eq!(propagator.update([&texture, &material], &broadcaster), 0);
+        assert!(receiver.try_recv().is_err());
+    }
 
     #[test]
     fn resource_event_broadcaster_add_and_remove() {
@@ -65,7 +65,9 @@ pub mod metadata;
 pub mod options;
 pub mod registry;
 pub mod state;
+pub mod streaming;
 pub mod untyped;
+pub mod validation;
 
 /// Implements [`ResourceData`] trait for the given resource type and creates a simple loader for the
 /// resource type with the specified name. The loader will use the specified extension. This macro
@@ -144,6 +146,14 @@ pub trait ResourceData: Debug + Visit + Send + Reflect {
     /// Tries to clone the resource data. This method can return `None` if the underlying type is
     /// non-cloneable.
     fn try_clone_box(&self) -> Option<Box<dyn ResourceData>>;
+
+    /// Returns an approximate amount of memory (in bytes) occupied by the resource data. It is used
+    /// by the resource manager to respect memory budgets (see [`crate::streaming`] for more info).
+    /// Default implementation returns zero, which means that the resource type does not participate
+    /// in memory budgeting.
+    fn memory_usage(&self) -> usize {
+        0
+    }
 }
 
 /// Extension trait for a resource data of a particular type, which adds additional functionality,
@@ -439,7 +449,7 @@ where
     #[inline]
     pub fn data_ref(&self) -> ResourceDataRef<'_, T> {
         ResourceDataRef {
-            guard: self.untyped.lock(),
+            guard: self.untyped.lock_and_touch(),
             phantom: Default::default(),
         }
     }
@@ -743,7 +753,7 @@ mod tests {
         manager::ResourceManager,
         metadata::ResourceMetadata,
         registry::ResourceRegistry,
-        state::LoadError,
+        state::{LoadError, ResourceState},
         ResourceData,
     };
     use fyrox_core::{
@@ -805,6 +815,10 @@ mod tests {
         fn try_clone_box(&self) -> Option<Box<dyn ResourceData>> {
             Some(Box::new(self.clone()))
         }
+
+        fn memory_usage(&self) -> usize {
+            size_of::<Self>()
+        }
     }
 
     struct MyDataLoader {}
@@ -835,6 +849,7 @@ mod tests {
     const TEST_FOLDER1: &str = "./test_output1";
     const TEST_FOLDER2: &str = "./test_output2";
     const TEST_FOLDER3: &str = "./test_output3";
+    const TEST_FOLDER4: &str = "./test_output4";
 
     fn make_file_path(root: &str, n: usize) -> PathBuf {
         Path::new(root).join(format!("test{n}.{}", MyDataLoader::EXT))
@@ -971,4 +986,62 @@ mod tests {
             std::fs::exists(append_extension(new_res2_path, ResourceMetadata::EXTENSION)).unwrap()
         );
     }
+
+    #[test]
+    fn test_memory_budget_eviction() {
+        write_test_resources(TEST_FOLDER4, 0..3);
+        let resource_manager =
+            ResourceManager::new(Arc::new(FsResourceIo), Arc::new(TaskPool::new()));
+        resource_manager
+            .state()
+            .resource_registry
+            .safe_lock()
+            .set_path(Path::new(TEST_FOLDER4).join("resources.registry"));
+        resource_manager.add_loader(MyDataLoader {});
+        resource_manager.update_or_load_registry();
+        resource_manager.set_memory_budget::<MyData>(Some(2 * size_of::<MyData>()));
+        resource_manager
+            .state()
+            .memory_budgets
+            .set_min_idle_time(0.5);
+
+        let resources = (0..3)
+            .map(|i| {
+                block_on(resource_manager.request::<MyData>(make_file_path(TEST_FOLDER4, i)))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // All the resources are held by the test, the budget must be respected anyway.
+        let update = |dt: f32| resource_manager.state().update(dt);
+        let is_unloaded = |resource: &Resource<MyData>| {
+            matches!(resource.header().state, ResourceState::Unloaded)
+        };
+
+        update(0.3);
+        // Keep the last resource busy.
+        assert_eq!(resources[2].data_ref().data, 2);
+        update(0.3);
+        update(0.3);
+
+        let type_uuid = <MyData as Reflect>::type_info().type_uuid;
+        assert_eq!(
+            resource_manager
+                .state()
+                .memory_budgets
+                .memory_usage(type_uuid),
+            2 * size_of::<MyData>()
+        );
+        assert!(is_unloaded(&resources[0]));
+        assert!(resources[1].is_ok());
+        assert!(resources[2].is_ok());
+
+        // Access to the evicted resource through the held handle must reload it.
+        assert!(resources[0].state().data().is_none());
+        update(0.0);
+        let resource = block_on(resources[0].clone()).unwrap();
+        assert_eq!(resource.data_ref().data, 0);
+        assert!(resources[0].is_ok());
+        assert_eq!(resources[0].data_ref().data, 0);
+    }
 }
@@ -38,17 +38,19 @@ use crate::{
         SafeLock,
     },
     entry::{TimedEntry, DEFAULT_RESOURCE_LIFETIME},
-    event::{ResourceEvent, ResourceEventBroadcaster},
+    event::{ReloadPropagator, ResourceEvent, ResourceEventBroadcaster},
     io::ResourceIo,
     loader::{ResourceLoader, ResourceLoadersContainer},
     metadata::ResourceMetadata,
     options::OPTIONS_EXTENSION,
     registry::{RegistryUpdate, ResourceRegistry, ResourceRegistryRefMut},
     state::{LoadError, ResourceDataWrapper, ResourceState},
+    streaming::{LoadPriority, LoadQueue, ResourceMemoryBudgets},
     untyped::ResourceKind,
+    validation::{self, RemapReport, ResourceReferenceReport},
     Resource, TypedResourceData, UntypedResource,
 };
-use fxhash::FxHashSet;
+use fxhash::{FxHashMap, FxHashSet};
 use fyrox_core::uuid::Uuid;
 use fyrox_core::{
     futures::executor::block_on, make_relative_path, notify::Event, ok_or_return, some_or_continue,
@@ -59,7 +61,7 @@ use std::{
     io::Error,
     marker::PhantomData,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{atomic::Ordering, Arc},
     time::Duration,
 };
 
@@ -98,10 +100,14 @@ pub struct ResourceManagerState {
     /// Resource registry, contains associations `UUID -> File Path`. Any access to the registry
     /// must be async, use task pool for this.
     pub resource_registry: Arc<Mutex<ResourceRegistry>>,
+    /// Per-type memory budgets of resources. See [`ResourceMemoryBudgets`] docs for more info.
+    pub memory_budgets: ResourceMemoryBudgets,
 
     resources: Vec<TimedEntry<UntypedResource>>,
+    load_queue: LoadQueue,
     task_pool: Arc<TaskPool>,
     watcher: Option<FileSystemWatcher>,
+    reload_propagator: ReloadPropagator,
 }
 
 /// Resource manager controls loading and lifetime of resource in the engine. Resource manager can hold
@@ -593,13 +599,27 @@ impl ResourceManager {
     /// This method will panic, if type UUID of `T` does not match the actual type UUID of the resource. If this
     /// is undesirable, use [`Self::try_request`] instead.
     pub fn request<T>(&self, path: impl AsRef<Path>) -> Resource<T>
+    where
+        T: TypedResourceData,
+    {
+        self.request_with_priority(path, LoadPriority::Normal)
+    }
+
+    /// The same as [`Self::request`], but allows you to specify a loading priority of the resource.
+    /// The priority matters only if the amount of concurrent loads is limited, see
+    /// [`ResourceManagerState::set_max_concurrent_loads`] for more info.
+    pub fn request_with_priority<T>(
+        &self,
+        path: impl AsRef<Path>,
+        priority: LoadPriority,
+    ) -> Resource<T>
     where
         T: TypedResourceData,
     {
         let path = path.as_ref();
         let mut state = self.state();
 
-        let untyped = state.request(path);
+        let untyped = state.request_with_priority(path, priority);
 
         let data_type_uuid_matches = untyped
             .type_uuid_non_blocking()
@@ -750,6 +770,20 @@ impl ResourceManager {
         self.state().uuid_to_resource_path(resource_uuid)
     }
 
+    /// Sets a memory budget (in bytes) for the resources of the given type. `None` removes the
+    /// budget. See [`crate::streaming`] module docs for more info.
+    pub fn set_memory_budget<T: TypedResourceData>(&self, budget: Option<usize>) {
+        self.state()
+            .memory_budgets
+            .set_budget(<T as Reflect>::type_info().type_uuid, budget);
+    }
+
+    /// Sets the maximum amount of resources that could be loaded concurrently. See
+    /// [`ResourceManagerState::set_max_concurrent_loads`] for more info.
+    pub fn set_max_concurrent_loads(&self, max: Option<usize>) {
+        self.state().set_max_concurrent_loads(max);
+    }
+
     /// Same as [`Self::request`], but returns untyped resource.
     pub fn request_untyped<P>(&self, path: P) -> UntypedResource
     where
@@ -880,6 +914,19 @@ impl ResourceManager {
         join_all(resources).await;
     }
 
+    /// Loads every native resource and looks for references to missing resources. See
+    /// [`crate::validation::validate_references`] docs for more info.
+    pub async fn validate_references(&self) -> ResourceReferenceReport {
+        validation::validate_references(self).await
+    }
+
+    /// Replaces references to the resources with UUIDs from the `remap` table in every native
+    /// resource and saves the modified resources. See [`crate::validation::remap_references`]
+    /// docs for more info.
+    pub async fn remap_references(&self, remap: &FxHashMap<Uuid, UntypedResource>) -> RemapReport {
+        validation::remap_references(self, remap).await
+    }
+
     /// Checks if there's a loader for the given resource path.
     pub fn is_supported_resource(&self, path: &Path) -> bool {
         self.state().is_supported_resource(path)
@@ -946,11 +993,14 @@ impl ResourceManagerState {
         Self {
             resources: Default::default(),
             loaders: Default::default(),
+            reload_propagator: Default::default(),
             event_broadcaster: Default::default(),
             constructors_container: Default::default(),
             watcher: None,
             built_in_resources: Default::default(),
             resource_registry: Arc::new(Mutex::new(ResourceRegistry::new(io.clone()))),
+            memory_budgets: Default::default(),
+            load_queue: Default::default(),
             task_pool,
             resource_io: io,
         }
@@ -1323,7 +1373,8 @@ impl ResourceManagerState {
     /// Update resource containers and do hot-reloading.
     ///
     /// Resources are removed if they're not used
-    /// or reloaded if they have changed in disk.
+    /// or reloaded if they have changed in disk. Resources that depend on reloaded resources are
+    /// reported via [`ResourceEvent::DependencyReloaded`] events.
     ///
     /// Normally, this is called from `Engine::update()`.
     /// You should only call this manually if you don't use that method.
@@ -1356,6 +1407,56 @@ impl ResourceManagerState {
                 true
             }
         });
+
+        let registry = self.resource_registry.clone();
+        let to_reload = self
+            .memory_budgets
+            .update(dt, &self.resources, &registry.safe_lock());
+        for mut resource in to_reload {
+            // The data of an evicted resource is needed again, it must be loaded as soon as possible.
+            resource.make_pending();
+            self.spawn_loading_task(resource, false, LoadPriority::High);
+        }
+
+        self.dispatch_loading_tasks();
+
+        // Let the users of reloaded resources know that they need to refresh their data.
+        let dependents = self
+            .reload_propagator
+            .update(self.iter(), &self.event_broadcaster);
+        if dependents > 0 {
+            info!(
+                "{dependents} resources were invalidated because their dependencies were reloaded."
+            );
+        }
+    }
+
+    /// Sets the maximum amount of resources that could be loaded concurrently. `None` (default)
+    /// means that every resource starts loading immediately after it was requested. If the amount
+    /// is limited, the loading requests are queued and processed in the order of their priorities
+    /// (see [`LoadPriority`]). Keep in mind, that the queue is processed in [`Self::update`], which
+    /// means that the manager must be updated periodically for the queued resources to load.
+    pub fn set_max_concurrent_loads(&mut self, max: Option<usize>) {
+        self.load_queue.max_concurrent_loads = max.map(|max| max.max(1));
+        self.dispatch_loading_tasks();
+    }
+
+    /// Returns the maximum amount of resources that could be loaded concurrently. See
+    /// [`Self::set_max_concurrent_loads`] for more info.
+    pub fn max_concurrent_loads(&self) -> Option<usize> {
+        self.load_queue.max_concurrent_loads
+    }
+
+    /// Returns the amount of resources that are waiting in the loading queue.
+    pub fn count_queued_resources(&self) -> usize {
+        self.load_queue.len()
+    }
+
+    /// Changes the loading priority of the given resource, if it is waiting in the loading queue.
+    /// It could be used to load the resources that became needed (for example, became visible)
+    /// before the background ones.
+    pub fn set_load_priority(&mut self, resource: &UntypedResource, priority: LoadPriority) {
+        self.load_queue.set_priority(resource, priority);
     }
 
     fn add_resource_and_notify(&mut self, resource: UntypedResource) {
@@ -1499,6 +1600,17 @@ impl ResourceManagerState {
     /// Panics if the path is invalid, such as if it includes a directory that does not exist
     /// or contains invalid characters.
     pub fn request<P>(&mut self, path: P) -> UntypedResource
+    where
+        P: AsRef<Path>,
+    {
+        self.request_with_priority(path, LoadPriority::Normal)
+    }
+
+    /// The same as [`Self::request`], but allows you to specify a loading priority of the resource.
+    /// The priority matters only if the amount of concurrent loads is limited (see
+    /// [`Self::set_max_concurrent_loads`]). If the resource is already queued, its priority will be
+    /// raised to the given one.
+    pub fn request_with_priority<P>(&mut self, path: P, priority: LoadPriority) -> UntypedResource
     where
         P: AsRef<Path>,
     {
@@ -1509,7 +1621,7 @@ impl ResourceManagerState {
 
         let path = self.resource_io.canonicalize_path(path).unwrap();
 
-        self.find_or_load(path)
+        self.find_or_load(path, priority)
     }
 
     /// Tries to load the resource for the given UUID.
@@ -1533,10 +1645,20 @@ impl ResourceManagerState {
     /// a resource, begin loading, and return the resource.
     /// If the given path does not correspond to any registered UUID,
     /// create and return an error resource.
-    fn find_or_load(&mut self, path: PathBuf) -> UntypedResource {
+    fn find_or_load(&mut self, path: PathBuf, priority: LoadPriority) -> UntypedResource {
         match self.find_by_resource_path(&path) {
-            Some(existing) => existing.clone(),
-            None => self.load_resource(path),
+            Some(existing) => {
+                let mut existing = existing.clone();
+                if existing.is_unloaded() {
+                    // The resource could be evicted to respect its memory budget, load it again.
+                    existing.make_pending();
+                    self.spawn_loading_task(existing.clone(), false, priority);
+                } else {
+                    self.load_queue.raise_priority(&existing, priority);
+                }
+                existing
+            }
+            None => self.load_resource(path, priority),
         }
     }
 
@@ -1559,21 +1681,44 @@ impl ResourceManagerState {
         }
     }
 
-    fn load_resource(&mut self, path: PathBuf) -> UntypedResource {
+    fn load_resource(&mut self, path: PathBuf, priority: LoadPriority) -> UntypedResource {
         let uuid = match self.find_uuid_or_register_new(path.clone()) {
             Ok(uuid) => uuid,
             Err(err) => return UntypedResource::new_load_error(ResourceKind::External, path, err),
         };
         let resource = UntypedResource::new_pending(uuid, ResourceKind::External);
         self.add_resource_and_notify(resource.clone());
-        self.spawn_loading_task(resource.clone(), false);
+        self.spawn_loading_task(resource.clone(), false, priority);
         resource
     }
 
+    /// Add a task to the task pool to load the given resource, or put it in the loading queue if
+    /// the amount of concurrent loads is limited.
+    fn spawn_loading_task(
+        &mut self,
+        resource: UntypedResource,
+        reload: bool,
+        priority: LoadPriority,
+    ) {
+        if self.load_queue.max_concurrent_loads.is_some() {
+            self.load_queue.push(resource, reload, priority);
+            self.dispatch_loading_tasks();
+        } else {
+            self.start_loading_task(resource, reload);
+        }
+    }
+
+    fn dispatch_loading_tasks(&mut self) {
+        while let Some((resource, reload)) = self.load_queue.pop() {
+            self.start_loading_task(resource, reload);
+        }
+    }
+
     /// Add a task to the task pool to load the given resource.
     /// Panic if the given resource is unregistered or embedded.
-    fn spawn_loading_task(&self, mut resource: UntypedResource, reload: bool) {
+    fn start_loading_task(&self, mut resource: UntypedResource, reload: bool) {
         let event_broadcaster = self.event_broadcaster.clone();
+        let reload_propagator = self.reload_propagator.clone();
         let loaders = self.loaders.clone();
         let registry = self.resource_registry.clone();
         let io = self.resource_io.clone();
@@ -1589,7 +1734,10 @@ impl ResourceManagerState {
             return;
         }
 
-        self.task_pool.spawn_task(async move {
+        let in_flight = self.load_queue.in_flight.clone();
+        in_flight.fetch_add(1, Ordering::SeqCst);
+
+        let task = async move {
             let Some(path) = registry
                 .safe_lock()
                 .uuid_to_path(resource.resource_uuid())
@@ -1625,6 +1773,10 @@ impl ResourceManagerState {
 
                         drop(header);
 
+                        if reload {
+                            reload_propagator.mark_reloaded(resource.clone());
+                        }
+
                         event_broadcaster.broadcast_loaded_or_reloaded(resource, reload);
 
                         Log::info(format!(
@@ -1636,15 +1788,11 @@ impl ResourceManagerState {
                         if reload {
                             if resource.is_ok() {
                                 info!("Resource {path:?} failed to reload, keeping the existing version. Reason: {error}");
-                            }
-                            else
-                            {
+                            } else {
                                 info!("Resource {path:?} failed to reload. Reason: {error}");
                                 resource.commit_error(path.to_path_buf(), error);
                             }
-                        }
-                        else
-                        {
+                        } else {
                             info!("Resource {path:?} failed to load. Reason: {error}");
                             resource.commit_error(path.to_path_buf(), error);
                         }
@@ -1654,6 +1802,11 @@ impl ResourceManagerState {
                 let error = format!("There's no resource loader for {path:?} resource!",);
                 resource.commit_error(path, error);
             }
+        };
+
+        self.task_pool.spawn_task(async move {
+            task.await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
         });
     }
 
@@ -1718,7 +1871,7 @@ impl ResourceManagerState {
             if resource.is_unloaded() {
                 // If the resource is in the unloaded state, start it loading, because it has been requested.
                 resource.make_pending();
-                self.spawn_loading_task(resource.clone(), false);
+                self.spawn_loading_task(resource.clone(), false, LoadPriority::Normal);
             }
         } else if let Some(r) = self
             .built_in_resources
@@ -1736,7 +1889,7 @@ impl ResourceManagerState {
             // resources and begin the loading process.
             resource.make_pending();
             self.add_resource_and_notify(resource.clone());
-            self.spawn_loading_task(resource.clone(), false);
+            self.spawn_loading_task(resource.clone(), false, LoadPriority::Normal);
         }
     }
 
@@ -1807,7 +1960,7 @@ impl ResourceManagerState {
                 header.state.switch_to_pending_state();
             }
             drop(header);
-            self.spawn_loading_task(resource, true)
+            self.spawn_loading_task(resource, true, LoadPriority::Normal)
         }
     }
 
@@ -0,0 +1,391 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Memory budgets and loading priorities of resources. This module contains the tools for open-world
+//! scenarios, where the entire set of resources cannot fit into memory at once.
+//!
+//! ## Memory Budgets
+//!
+//! Every resource type could have its own memory budget (see [`ResourceMemoryBudgets::set_budget`]).
+//! When the total memory usage (see [`crate::ResourceData::memory_usage`]) of the loaded resources of
+//! a type exceeds its budget, the resource manager starts evicting the least recently used external
+//! resources of the type that weren't used for at least [`ResourceMemoryBudgets::min_idle_time`]
+//! seconds. An evicted resource keeps its handles valid, but its data is unloaded (the resource is
+//! switched to [`ResourceState::Unloaded`] state). As soon as the data is accessed again via typed
+//! [`crate::Resource`] methods (or the resource is requested from the resource manager again), the
+//! resource manager reloads the data transparently.
+//!
+//! ## Loading Priorities
+//!
+//! By default, every resource starts loading immediately after it was requested. It is possible
+//! to limit the amount of concurrent loading tasks (see
+//! [`crate::manager::ResourceManager::set_max_concurrent_loads`]), in this case the requests are
+//! queued and processed in the order of their priorities (see [`LoadPriority`]). Resources, that
+//! are needed by the visible area could be requested with higher priority, so they'll be loaded
+//! before the background ones.
+
+use crate::{
+    core::{log::Log, uuid::Uuid},
+    entry::TimedEntry,
+    registry::ResourceRegistry,
+    state::ResourceState,
+    untyped::ResourceKind,
+    UntypedResource,
+};
+use fxhash::{FxHashMap, FxHashSet};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Priority of a resource loading request. Requests with higher priority are processed first, if
+/// the amount of concurrent loading tasks is limited.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LoadPriority {
+    /// The resource is not needed right now, for example it is used by some distant part of a level.
+    Background,
+    /// Default priority.
+    #[default]
+    Normal,
+    /// The resource is needed as soon as possible, for example it is used by the visible area.
+    High,
+}
+
+struct LoadRequest {
+    resource: UntypedResource,
+    reload: bool,
+    priority: LoadPriority,
+    sequence: u64,
+}
+
+/// A queue of loading requests, that is used when the amount of concurrent loading tasks is limited.
+#[derive(Default)]
+pub(crate) struct LoadQueue {
+    requests: Vec<LoadRequest>,
+    sequence: u64,
+    pub(crate) in_flight: Arc<AtomicUsize>,
+    pub(crate) max_concurrent_loads: Option<usize>,
+}
+
+impl LoadQueue {
+    pub(crate) fn push(&mut self, resource: UntypedResource, reload: bool, priority: LoadPriority) {
+        if let Some(request) = self.requests.iter_mut().find(|r| r.resource == resource) {
+            request.priority = request.priority.max(priority);
+            request.reload |= reload;
+        } else {
+            self.sequence += 1;
+            self.requests.push(LoadRequest {
+                resource,
+                reload,
+                priority,
+                sequence: self.sequence,
+            });
+        }
+    }
+
+    pub(crate) fn set_priority(&mut self, resource: &UntypedResource, priority: LoadPriority) {
+        if let Some(request) = self.requests.iter_mut().find(|r| &r.resource == resource) {
+            request.priority = priority;
+        }
+    }
+
+    pub(crate) fn raise_priority(&mut self, resource: &UntypedResource, priority: LoadPriority) {
+        if let Some(request) = self.requests.iter_mut().find(|r| &r.resource == resource) {
+            request.priority = request.priority.max(priority);
+        }
+    }
+
+    pub(crate) fn has_free_slot(&self) -> bool {
+        self.max_concurrent_loads
+            .is_none_or(|max| self.in_flight.load(Ordering::SeqCst) < max)
+    }
+
+    /// Extracts the request with the highest priority (or the oldest one among the requests with
+    /// the same priority), if there's a free slot for it.
+    pub(crate) fn pop(&mut self) -> Option<(UntypedResource, bool)> {
+        if !self.has_free_slot() {
+            return None;
+        }
+
+        let index = self
+            .requests
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| {
+                a.priority
+                    .cmp(&b.priority)
+                    .then_with(|| b.sequence.cmp(&a.sequence))
+            })
+            .map(|(index, _)| index)?;
+
+        let request = self.requests.remove(index);
+        Some((request.resource, request.reload))
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.requests.len()
+    }
+}
+
+#[derive(Default)]
+struct AccessRecord {
+    counter: u64,
+    idle_time: f32,
+}
+
+struct EvictionCandidate {
+    resource: UntypedResource,
+    type_uuid: Uuid,
+    size: usize,
+    idle_time: f32,
+}
+
+/// Per-type memory budgets of resources. See the module docs for more info.
+pub struct ResourceMemoryBudgets {
+    budgets: FxHashMap<Uuid, usize>,
+    usage: FxHashMap<Uuid, usize>,
+    records: FxHashMap<Uuid, AccessRecord>,
+    evicted: FxHashMap<Uuid, u64>,
+    min_idle_time: f32,
+    timer: f32,
+}
+
+impl Default for ResourceMemoryBudgets {
+    fn default() -> Self {
+        Self {
+            budgets: Default::default(),
+            usage: Default::default(),
+            records: Default::default(),
+            evicted: Default::default(),
+            min_idle_time: Self::DEFAULT_MIN_IDLE_TIME,
+            timer: 0.0,
+        }
+    }
+}
+
+impl ResourceMemoryBudgets {
+    /// Default amount of time (in seconds) a resource must not be used to become a candidate for
+    /// eviction.
+    pub const DEFAULT_MIN_IDLE_TIME: f32 = 5.0;
+
+    /// Interval (in seconds) between two consecutive memory usage checks.
+    pub const UPDATE_INTERVAL: f32 = 0.25;
+
+    /// Sets a memory budget (in bytes) for resources with the given data type UUID. `None` removes
+    /// the budget.
+    pub fn set_budget(&mut self, type_uuid: Uuid, budget: Option<usize>) {
+        match budget {
+            Some(budget) => {
+                self.budgets.insert(type_uuid, budget);
+            }
+            None => {
+                self.budgets.remove(&type_uuid);
+            }
+        }
+    }
+
+    /// Returns a memory budget (in bytes) for resources with the given data type UUID.
+    pub fn budget(&self, type_uuid: Uuid) -> Option<usize> {
+        self.budgets.get(&type_uuid).cloned()
+    }
+
+    /// Sets the amount of time (in seconds) a resource must not be used to become a candidate for
+    /// eviction.
+    pub fn set_min_idle_time(&mut self, time: f32) {
+        self.min_idle_time = time.max(0.0);
+    }
+
+    /// Returns the amount of time (in seconds) a resource must not be used to become a candidate
+    /// for eviction.
+    pub fn min_idle_time(&self) -> f32 {
+        self.min_idle_time
+    }
+
+    /// Returns total memory usage (in bytes) of the loaded resources with the given data type UUID.
+    /// The value is calculated only for the types that have a budget and it is updated every
+    /// [`Self::UPDATE_INTERVAL`] seconds.
+    pub fn memory_usage(&self, type_uuid: Uuid) -> usize {
+        self.usage.get(&type_uuid).cloned().unwrap_or_default()
+    }
+
+    /// Returns `true` if the resource was evicted and its data wasn't reloaded yet.
+    pub fn is_evicted(&self, resource: &UntypedResource) -> bool {
+        resource
+            .try_lock()
+            .is_some_and(|header| self.evicted.contains_key(&header.uuid))
+    }
+
+    /// Tracks resource usage and evicts least recently used resources if there's not enough
+    /// memory. Returns a list of previously evicted resources that were accessed again and must be
+    /// reloaded.
+    pub(crate) fn update(
+        &mut self,
+        dt: f32,
+        resources: &[TimedEntry<UntypedResource>],
+        registry: &ResourceRegistry,
+    ) -> Vec<UntypedResource> {
+        let mut to_reload = Vec::new();
+
+        if !self.evicted.is_empty() {
+            for entry in resources {
+                let Some(header) = entry.value.try_lock() else {
+                    continue;
+                };
+                if let Some(counter) = self.evicted.get(&header.uuid) {
+                    if !matches!(header.state, ResourceState::Unloaded) {
+                        // Someone has requested the resource already.
+                        self.evicted.remove(&header.uuid);
+                    } else if *counter != header.access_counter.value() {
+                        self.evicted.remove(&header.uuid);
+                        drop(header);
+                        to_reload.push(entry.value.clone());
+                    }
+                }
+            }
+        }
+
+        if self.budgets.is_empty() {
+            return to_reload;
+        }
+
+        self.timer += dt;
+        if self.timer < Self::UPDATE_INTERVAL {
+            return to_reload;
+        }
+        let elapsed = std::mem::take(&mut self.timer);
+
+        self.usage.clear();
+        let mut alive = FxHashSet::default();
+        let mut candidates = Vec::new();
+        for entry in resources {
+            let Some(header) = entry.value.try_lock() else {
+                continue;
+            };
+
+            alive.insert(header.uuid);
+
+            let record = self.records.entry(header.uuid).or_default();
+            let counter = header.access_counter.value();
+            if record.counter != counter {
+                record.counter = counter;
+                record.idle_time = 0.0;
+            } else {
+                record.idle_time += elapsed;
+            }
+
+            let ResourceState::Ok { ref data } = header.state else {
+                continue;
+            };
+            let type_uuid = data.inner_ref().type_info_ref().type_uuid;
+            if !self.budgets.contains_key(&type_uuid) {
+                continue;
+            }
+
+            let size = data.inner_ref().memory_usage();
+            *self.usage.entry(type_uuid).or_default() += size;
+
+            // Only external resources can be reloaded transparently.
+            if header.kind == ResourceKind::External
+                && record.idle_time >= self.min_idle_time
+                && registry.uuid_to_path(header.uuid).is_some()
+            {
+                candidates.push(EvictionCandidate {
+                    resource: entry.value.clone(),
+                    type_uuid,
+                    size,
+                    idle_time: record.idle_time,
+                });
+            }
+        }
+
+        self.records.retain(|uuid, _| alive.contains(uuid));
+        self.evicted.retain(|uuid, _| alive.contains(uuid));
+
+        // Least recently used resources go first.
+        candidates.sort_by(|a, b| b.idle_time.total_cmp(&a.idle_time));
+
+        for candidate in candidates {
+            let (Some(budget), Some(usage)) = (
+                self.budgets.get(&candidate.type_uuid),
+                self.usage.get_mut(&candidate.type_uuid),
+            ) else {
+                continue;
+            };
+
+            if *usage <= *budget {
+                continue;
+            }
+
+            let mut header = candidate.resource.lock();
+            if header.state.is_ok() {
+                header.state = ResourceState::Unloaded;
+                self.evicted
+                    .insert(header.uuid, header.access_counter.value());
+                *usage -= candidate.size;
+
+                Log::info(format!(
+                    "Resource {} was evicted to respect the memory budget. {} bytes were freed.",
+                    header.uuid, candidate.size
+                ));
+            }
+        }
+
+        to_reload
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_load_queue_order() {
+        let mut queue = LoadQueue {
+            max_concurrent_loads: Some(1),
+            ..Default::default()
+        };
+
+        let background = UntypedResource::new_unloaded(Uuid::new_v4());
+        let normal_a = UntypedResource::new_unloaded(Uuid::new_v4());
+        let normal_b = UntypedResource::new_unloaded(Uuid::new_v4());
+        let high = UntypedResource::new_unloaded(Uuid::new_v4());
+
+        queue.push(background.clone(), false, LoadPriority::Background);
+        queue.push(normal_a.clone(), false, LoadPriority::Normal);
+        queue.push(normal_b.clone(), false, LoadPriority::Normal);
+        queue.push(high.clone(), false, LoadPriority::Normal);
+        queue.set_priority(&high, LoadPriority::High);
+
+        let mut order = Vec::new();
+        while let Some((resource, _)) = queue.pop() {
+            order.push(resource);
+        }
+
+        assert_eq!(order, [high, normal_a, normal_b, background]);
+
+        queue.push(
+            UntypedResource::new_unloaded(Uuid::new_v4()),
+            false,
+            LoadPriority::Normal,
+        );
+        queue.in_flight.store(1, Ordering::SeqCst);
+        assert!(queue.pop().is_none());
+        assert_eq!(queue.len(), 1);
+    }
+}
@@ -108,6 +108,32 @@ impl Display for ResourceKind {
     }
 }
 
+/// A counter, that is incremented every time when the data of a resource is accessed via typed
+/// [`Resource`] methods. The resource manager uses it to find resources that weren't used for a
+/// while and could be evicted to respect memory budgets. See [`crate::streaming`] for more info.
+#[derive(Reflect, Debug, Default, Clone)]
+#[reflect(hide_all, type_uuid = "5b0e1f3c-9a8d-4c27-8e64-1d2f7a3b9c05")]
+pub struct AccessCounter(u64);
+
+impl PartialEq for AccessCounter {
+    fn eq(&self, _other: &Self) -> bool {
+        // The counter is a runtime statistics, it must not affect comparison of resources.
+        true
+    }
+}
+
+impl AccessCounter {
+    /// Increments the counter.
+    pub fn touch(&mut self) {
+        self.0 = self.0.wrapping_add(1);
+    }
+
+    /// Returns current value of the counter.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
 /// Header of a resource, it contains a common data about the resource, such as its data type uuid,
 /// its kind, etc.
 #[derive(Reflect, PartialEq, Clone, Debug)]
@@ -119,6 +145,9 @@ pub struct ResourceHeader {
     pub kind: ResourceKind,
     /// Actual state of the resource. See [`ResourceState`] for more info.
     pub state: ResourceState,
+    /// A counter of data accesses. See [`AccessCounter`] docs for more info.
+    #[reflect(hidden)]
+    pub access_counter: AccessCounter,
 }
 
 impl Default for ResourceHeader {
@@ -127,6 +156,7 @@ impl Default for ResourceHeader {
             uuid: Uuid::new_v4(),
             kind: Default::default(),
             state: Default::default(),
+            access_counter: Default::default(),
         }
     }
 }
@@ -137,6 +167,7 @@ impl From<Uuid> for ResourceHeader {
             uuid,
             kind: ResourceKind::External,
             state: ResourceState::Unloaded,
+            access_counter: Default::default(),
         }
     }
 }
@@ -395,7 +426,14 @@ impl UntypedResource {
     }
     /// Lock the shared header of this resource.
     pub fn typed_lock<T: TypedResourceData>(&self) -> ResourceHeaderGuard<'_, T> {
-        self.lock().into()
+        self.lock_and_touch().into()
+    }
+    /// Lock the shared header of this resource and mark the resource as used. See [`AccessCounter`]
+    /// for more info.
+    pub fn lock_and_touch(&self) -> MutexGuard<'_, ResourceHeader> {
+        let mut header = self.lock();
+        header.access_counter.touch();
+        header
     }
     /// Lock the shared header of this resource.
     pub fn lock(&self) -> MutexGuard<'_, ResourceHeader> {
@@ -403,7 +441,10 @@ impl UntypedResource {
     }
     /// Attempt to lock the shared header. None if the header is already locked.
     pub fn try_typed_lock<T: TypedResourceData>(&self) -> Option<ResourceHeaderGuard<'_, T>> {
-        self.try_lock().map(|g| g.into())
+        self.try_lock().map(|mut g| {
+            g.access_counter.touch();
+            g.into()
+        })
     }
     /// Attempt to lock the shared header. None if the header is already locked.
     pub fn try_lock(&self) -> Option<MutexGuard<'_, ResourceHeader>> {
@@ -415,6 +456,7 @@ impl UntypedResource {
             uuid: resource_uuid,
             kind: ResourceKind::External,
             state: ResourceState::Unloaded,
+            access_counter: Default::default(),
         }
         .into()
     }
@@ -424,6 +466,7 @@ impl UntypedResource {
             uuid: resource_uuid,
             kind,
             state: ResourceState::new_pending(),
+            access_counter: Default::default(),
         }
         .into()
     }
@@ -438,6 +481,7 @@ impl UntypedResource {
             uuid: resource_uuid,
             kind,
             state: ResourceState::new_ok(data),
+            access_counter: Default::default(),
         }
         .into()
     }
@@ -452,6 +496,7 @@ impl UntypedResource {
             uuid: resource_uuid,
             kind,
             state: ResourceState::new_ok_untyped(data),
+            access_counter: Default::default(),
         }
         .into()
     }
@@ -468,6 +513,7 @@ impl UntypedResource {
             uuid: Uuid::new_v4(),
             kind,
             state: ResourceState::new_load_error(path, error),
+            access_counter: Default::default(),
         }
         .into()
     }
@@ -713,6 +759,7 @@ mod test {
             state: ResourceState::Ok {
                 data: ResourceDataWrapper(Box::new(stub)),
             },
+            access_counter: Default::default(),
         });
         assert!(Pin::new(&mut r).poll(&mut cx).is_ready());
 
@@ -723,6 +770,7 @@ mod test {
                 path: Default::default(),
                 error: Default::default(),
             },
+            access_counter: Default::default(),
         });
         assert!(Pin::new(&mut r).poll(&mut cx).is_ready());
     }
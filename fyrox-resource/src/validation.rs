@@ -0,0 +1,508 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Resource reference validation and repair. See [`validate_references`] and [`remap_references`]
+//! docs for more info.
+//!
+//! Resources reference each other by UUIDs, that are mapped to actual paths by the resource registry.
+//! When a file is moved or deleted outside the editor, every resource that used it keeps a reference
+//! to a UUID that cannot be resolved anymore. Such references fail to load silently (with just an
+//! error message in the log), which makes them hard to find in large projects. This module allows
+//! you to find such references across the entire project and to remap them to other resources in
+//! bulk.
+
+use crate::{
+    core::{futures::future::join_all, reflect::prelude::*, uuid::Uuid},
+    manager::ResourceManager,
+    state::ResourceState,
+    untyped::UntypedResource,
+};
+use fxhash::{FxHashMap, FxHashSet};
+use std::{
+    fmt::{Display, Formatter},
+    path::PathBuf,
+};
+
+/// A reference to a resource found in some entity, along with the location of the reference.
+#[derive(Debug, Clone)]
+pub struct ResourceReference {
+    /// The referenced resource.
+    pub resource: UntypedResource,
+    /// Reflection path of the field that holds the reference, for example
+    /// `graph.pool[3].surfaces[0].material.untyped`.
+    pub field_path: String,
+}
+
+/// A reason why a reference is considered dangling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DanglingReason {
+    /// There's no resource with the given UUID in the registry.
+    UnknownUuid,
+    /// The registry has the UUID, but the file it points to does not exist.
+    MissingFile(PathBuf),
+}
+
+impl Display for DanglingReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DanglingReason::UnknownUuid => write!(f, "unknown uuid"),
+            DanglingReason::MissingFile(path) => write!(f, "missing file {}", path.display()),
+        }
+    }
+}
+
+/// A reference to a resource that cannot be resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DanglingReference {
+    /// UUID of the missing resource.
+    pub uuid: Uuid,
+    /// Why the reference cannot be resolved.
+    pub reason: DanglingReason,
+    /// Path of the resource that holds the reference.
+    pub owner: PathBuf,
+    /// Reflection path of the field that holds the reference. See [`ResourceReference::field_path`].
+    pub field_path: String,
+}
+
+impl Display for DanglingReference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} references {} ({})",
+            self.owner.display(),
+            self.field_path,
+            self.uuid,
+            self.reason
+        )
+    }
+}
+
+/// A result of [`validate_references`].
+#[derive(Debug, Default, Clone)]
+pub struct ResourceReferenceReport {
+    /// Paths of all the native resources that were scanned.
+    pub scanned: Vec<PathBuf>,
+    /// All the references that cannot be resolved.
+    pub dangling: Vec<DanglingReference>,
+    /// Resources that could not be scanned, because they failed to load, along with the reason.
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl ResourceReferenceReport {
+    /// Returns `true` if there are no dangling references, and every resource was scanned.
+    pub fn is_ok(&self) -> bool {
+        self.dangling.is_empty() && self.failed.is_empty()
+    }
+
+    /// Returns a set of UUIDs of all the missing resources.
+    pub fn missing_uuids(&self) -> FxHashSet<Uuid> {
+        self.dangling.iter().map(|r| r.uuid).collect()
+    }
+
+    /// Returns an iterator over the dangling references to the resource with the given UUID.
+    pub fn references_to(&self, uuid: Uuid) -> impl Iterator<Item = &DanglingReference> {
+        self.dangling.iter().filter(move |r| r.uuid == uuid)
+    }
+}
+
+/// A result of [`remap_references`].
+#[derive(Debug, Default, Clone)]
+pub struct RemapReport {
+    /// Paths of the resources that were modified and saved.
+    pub modified: Vec<PathBuf>,
+    /// Total amount of replaced references.
+    pub replaced: usize,
+    /// Resources that could not be loaded or saved, along with the reason.
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+#[inline(always)]
+fn is_plain_data(entity: &dyn Reflect) -> bool {
+    #[inline(always)]
+    fn type_is<T: Reflect>(entity: &dyn Reflect) -> bool {
+        entity.downcast_ref::<T>().is_some()
+    }
+
+    // Same as in `collect_used_resources` - skip potentially large chunks of numeric data.
+    type_is::<Vec<u8>>(entity)
+        || type_is::<Vec<u16>>(entity)
+        || type_is::<Vec<u32>>(entity)
+        || type_is::<Vec<u64>>(entity)
+        || type_is::<Vec<i8>>(entity)
+        || type_is::<Vec<i16>>(entity)
+        || type_is::<Vec<i32>>(entity)
+        || type_is::<Vec<i64>>(entity)
+        || type_is::<Vec<f32>>(entity)
+        || type_is::<Vec<f64>>(entity)
+}
+
+fn push_field(path: &mut String, name: &str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    path.push_str(name);
+}
+
+fn push_index(path: &mut String, index: usize) {
+    use std::fmt::Write;
+    let _ = write!(path, "[{index}]");
+}
+
+fn collect_references_recursive(
+    entity: &dyn Reflect,
+    path: &mut String,
+    references: &mut Vec<ResourceReference>,
+) {
+    if is_plain_data(entity) {
+        return;
+    }
+
+    if let Some(resource) = entity.downcast_ref::<UntypedResource>() {
+        references.push(ResourceReference {
+            resource: resource.clone(),
+            field_path: path.clone(),
+        });
+        return;
+    }
+
+    let len = path.len();
+
+    if let Some(array) = entity.as_array() {
+        for i in 0..array.reflect_len() {
+            if let Some(item) = array.reflect_index(i) {
+                push_index(path, i);
+                collect_references_recursive(item, path, references);
+                path.truncate(len);
+            }
+        }
+        return;
+    }
+
+    if let Some(inheritable) = entity.as_inheritable_variable() {
+        collect_references_recursive(inheritable.inner_value_ref(), path, references);
+        return;
+    }
+
+    if let Some(hash_map) = entity.as_hash_map() {
+        for i in 0..hash_map.reflect_len() {
+            if let Some((key, value)) = hash_map.reflect_get_at(i) {
+                push_index(path, i);
+                collect_references_recursive(key, path, references);
+                collect_references_recursive(value, path, references);
+                path.truncate(len);
+            }
+        }
+        return;
+    }
+
+    entity.fields_ref(&mut |fields| {
+        for field in fields {
+            push_field(path, field.name);
+            collect_references_recursive(field.value, path, references);
+            path.truncate(len);
+        }
+    })
+}
+
+/// Collects all resource references of the given entity along with the paths of the fields that
+/// hold them. Unlike [`crate::collect_used_resources`], this function does not deduplicate the
+/// references, so a single resource will be listed as many times as it is used. Internally, it
+/// uses reflection, so fields marked with `#[reflect(hidden)]` are still visited, but the fields
+/// that are not reflected at all will be ignored.
+pub fn collect_resource_references(entity: &dyn Reflect) -> Vec<ResourceReference> {
+    let mut references = Vec::new();
+    collect_references_recursive(entity, &mut String::new(), &mut references);
+    references
+}
+
+/// Replaces every resource reference of the given entity, whose UUID is in the `remap` table, with
+/// the respective resource from the table. Returns the amount of replaced references. Replaced
+/// values of inheritable variables are marked as modified, so they won't be overwritten by the
+/// property inheritance.
+///
+/// ## Type safety
+///
+/// The data type of a missing resource is unknown, so it is up to the caller to ensure that the new
+/// resource has the same data type as the old one. Typed resources with mismatching data will panic
+/// on first access.
+pub fn remap_resource_references(
+    entity: &mut dyn Reflect,
+    remap: &FxHashMap<Uuid, UntypedResource>,
+) -> usize {
+    if is_plain_data(entity) {
+        return 0;
+    }
+
+    if let Some(resource) = entity.downcast_mut::<UntypedResource>() {
+        return match remap.get(&resource.resource_uuid()) {
+            Some(new_resource) => {
+                *resource = new_resource.clone();
+                1
+            }
+            None => 0,
+        };
+    }
+
+    let mut count = 0;
+
+    if let Some(array) = entity.as_array_mut() {
+        for i in 0..array.reflect_len() {
+            if let Some(item) = array.reflect_index_mut(i) {
+                count += remap_resource_references(item, remap);
+            }
+        }
+        return count;
+    }
+
+    if let Some(inheritable) = entity.as_inheritable_variable_mut() {
+        count = remap_resource_references(inheritable.inner_value_mut(), remap);
+        if count > 0 {
+            inheritable.mark_modified();
+        }
+        return count;
+    }
+
+    if let Some(hash_map) = entity.as_hash_map_mut() {
+        // Keys cannot be modified in place, so only values are remapped.
+        for i in 0..hash_map.reflect_len() {
+            if let Some(value) = hash_map.reflect_get_nth_value_mut(i) {
+                count += remap_resource_references(value, remap);
+            }
+        }
+        return count;
+    }
+
+    entity.fields_mut(&mut |fields| {
+        for field in fields {
+            count += remap_resource_references(field.value, remap);
+        }
+    });
+
+    count
+}
+
+/// Checks whether the given reference can be resolved by the resource manager. Embedded resources
+/// are always valid. Returns [`None`] if the reference is valid.
+pub fn check_reference(
+    resource_manager: &ResourceManager,
+    resource: &UntypedResource,
+) -> Option<DanglingReason> {
+    if resource.is_embedded() {
+        return None;
+    }
+
+    let state = resource_manager.state();
+    let uuid = resource.resource_uuid();
+    match state.uuid_to_resource_path(uuid) {
+        None => Some(DanglingReason::UnknownUuid),
+        Some(path) => {
+            if state.built_in_resources.find_by_uuid(uuid).is_none()
+                && !state.resource_io.exists_sync(&path)
+            {
+                Some(DanglingReason::MissingFile(path))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+async fn load_native_resources(
+    resource_manager: &ResourceManager,
+) -> Vec<(PathBuf, Result<UntypedResource, String>)> {
+    let paths = resource_manager.state().collect_native_resources();
+
+    let resources = join_all(
+        paths
+            .iter()
+            .map(|path| resource_manager.request_untyped(path)),
+    )
+    .await;
+    paths
+        .into_iter()
+        .zip(resources)
+        .map(|(path, result)| (path, result.map_err(|err| format!("{err:?}"))))
+        .collect()
+}
+
+/// Loads every native resource of the project (scenes, materials, user interfaces, etc.) and
+/// checks every resource reference in them. See [`ResourceReferenceReport`] docs for more info.
+/// The registry must be loaded before calling this function.
+pub async fn validate_references(resource_manager: &ResourceManager) -> ResourceReferenceReport {
+    let mut report = ResourceReferenceReport::default();
+
+    for (path, result) in load_native_resources(resource_manager).await {
+        let resource = match result {
+            Ok(resource) => resource,
+            Err(err) => {
+                report.failed.push((path, err));
+                continue;
+            }
+        };
+
+        // Collect the references first and only then check them, because the check requires
+        // the resource manager state to be locked and the resource must not be locked at the
+        // same time.
+        let references = {
+            let header = resource.lock();
+            match header.state {
+                ResourceState::Ok { ref data } => {
+                    collect_resource_references(data.inner_ref() as &dyn Reflect)
+                }
+                _ => Vec::new(),
+            }
+        };
+
+        for reference in references {
+            if let Some(reason) = check_reference(resource_manager, &reference.resource) {
+                report.dangling.push(DanglingReference {
+                    uuid: reference.resource.resource_uuid(),
+                    reason,
+                    owner: path.clone(),
+                    field_path: reference.field_path,
+                });
+            }
+        }
+
+        report.scanned.push(path);
+    }
+
+    report
+}
+
+/// Replaces all the references to the resources with the UUIDs from the `remap` table in every
+/// native resource of the project, and saves the modified resources. Typical usage is to fix the
+/// dangling references found by [`validate_references`]. See [`remap_resource_references`] docs
+/// for more info.
+pub async fn remap_references(
+    resource_manager: &ResourceManager,
+    remap: &FxHashMap<Uuid, UntypedResource>,
+) -> RemapReport {
+    let mut report = RemapReport::default();
+
+    if remap.is_empty() {
+        return report;
+    }
+
+    for (path, result) in load_native_resources(resource_manager).await {
+        let resource = match result {
+            Ok(resource) => resource,
+            Err(err) => {
+                report.failed.push((path, err));
+                continue;
+            }
+        };
+
+        let replaced = {
+            let mut header = resource.lock();
+            match header.state {
+                ResourceState::Ok { ref mut data } => {
+                    remap_resource_references(data.inner_mut() as &mut dyn Reflect, remap)
+                }
+                _ => 0,
+            }
+        };
+
+        if replaced == 0 {
+            continue;
+        }
+
+        match resource.save(&path) {
+            Ok(()) => {
+                report.replaced += replaced;
+                report.modified.push(path);
+            }
+            Err(err) => report.failed.push((path, err.to_string())),
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Reflect, Debug, Default, Clone, PartialEq)]
+    #[reflect(type_uuid = "b6a1f2f0-3c59-4f0e-9d41-7f0c2e8a5d13")]
+    struct Level {
+        background: UntypedResource,
+        layers: Vec<Layer>,
+        numbers: Vec<u32>,
+    }
+
+    #[derive(Reflect, Debug, Default, Clone, PartialEq)]
+    #[reflect(type_uuid = "0e5d8c7a-94b2-4a61-8f3e-2c1b6d9f4a70")]
+    struct Layer {
+        texture: UntypedResource,
+    }
+
+    #[test]
+    fn test_collect_resource_references() {
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+        let level = Level {
+            background: UntypedResource::new_unloaded(a),
+            layers: vec![
+                Layer::default(),
+                Layer {
+                    texture: UntypedResource::new_unloaded(b),
+                },
+            ],
+            numbers: vec![1, 2, 3],
+        };
+
+        let references = collect_resource_references(&level);
+        let paths = references
+            .iter()
+            .map(|r| (r.field_path.as_str(), r.resource.resource_uuid()))
+            .filter(|(_, uuid)| *uuid == a || *uuid == b)
+            .collect::<Vec<_>>();
+        assert_eq!(references.len(), 3);
+        assert_eq!(paths, [("background", a), ("layers[1].texture", b)]);
+    }
+
+    #[test]
+    fn test_remap_resource_references() {
+        let missing = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let replacement = UntypedResource::new_unloaded(Uuid::new_v4());
+        let mut level = Level {
+            background: UntypedResource::new_unloaded(missing),
+            layers: vec![
+                Layer {
+                    texture: UntypedResource::new_unloaded(missing),
+                },
+                Layer {
+                    texture: UntypedResource::new_unloaded(other),
+                },
+            ],
+            numbers: vec![],
+        };
+
+        let mut remap = FxHashMap::default();
+        remap.insert(missing, replacement.clone());
+
+        assert_eq!(remap_resource_references(&mut level, &remap), 2);
+        assert_eq!(level.background, replacement);
+        assert_eq!(level.layers[0].texture, replacement);
+        assert_eq!(level.layers[1].texture.resource_uuid(), other);
+    }
+}
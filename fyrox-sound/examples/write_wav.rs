@@ -20,25 +20,20 @@
 
 use fyrox_resource::io::FsResourceIo;
 use fyrox_sound::buffer::SoundBufferResourceExtension;
-use fyrox_sound::engine::State;
 use fyrox_sound::{
     buffer::{DataSource, SoundBufferResource},
     context::SoundContext,
     engine::SoundEngine,
+    offline::OfflineRenderer,
     pool::Handle,
     source::{SoundSource, SoundSourceBuilder, Status},
 };
 
 fn main() {
-    // Initialize sound engine without output device.
-    let engine = SoundEngine::without_device(SoundEngine::DEFAULT_SAMPLE_RATE);
-
-    // Create new context.
+    // Create new context. It is not registered in a sound engine, so it will be rendered only by the
+    // offline renderer below.
     let context = SoundContext::new();
 
-    // Register context in the engine.
-    engine.state().add_context(context.clone());
-
     // Load sound buffer.
     let door_open_buffer = SoundBufferResource::new_generic(
         fyrox_sound::futures::executor::block_on(DataSource::from_file(
@@ -61,29 +56,9 @@ fn main() {
     // and returns pool handle to it by which it can be accessed later on if needed.
     let _source_handle: Handle<SoundSource> = context.state().add_source(source);
 
-    // Create output wav file. The sample rate is currently fixed.
-    let wav_spec = hound::WavSpec {
-        channels: 2,
-        sample_rate: engine.sample_rate(),
-        bits_per_sample: 32,
-        sample_format: hound::SampleFormat::Float,
-    };
-    let mut wav_writer = hound::WavWriter::create("output.wav", wav_spec).unwrap();
-
-    // Create an output buffer.
-    let buf_len = State::render_buffer_len();
-    let mut buf = vec![(0.0f32, 0.0f32); buf_len];
-    let mut samples_written = 0;
-
-    // Wait until sound will play completely.
-    while samples_written < 3 * engine.sample_rate() {
-        engine.state().render(&mut buf);
-        for &(l, r) in buf.iter() {
-            wav_writer.write_sample(l).unwrap();
-            wav_writer.write_sample(r).unwrap();
-        }
-        samples_written += buf_len as u32;
-    }
-
-    wav_writer.finalize().unwrap();
+    // Render 3 seconds of audio and write it to a file.
+    let sample_rate = SoundEngine::DEFAULT_SAMPLE_RATE;
+    let mut renderer = OfflineRenderer::new(sample_rate);
+    let output = renderer.render(&context, 3 * sample_rate as usize);
+    output.write_wav("output.wav").unwrap();
 }
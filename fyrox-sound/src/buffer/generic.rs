@@ -69,6 +69,38 @@ impl DerefMut for Samples {
     }
 }
 
+/// A region of a sound buffer, that will be repeated while a sound source is looping. Looping
+/// sources play the sound from the beginning, and once the end of the region is reached, the
+/// playback jumps to the start of the region. Loop regions are usually stored in the metadata
+/// of sound files (`smpl` chunk of WAV files, `LOOPSTART` and `LOOPLENGTH`/`LOOPEND` tags of
+/// Ogg files) and read automatically, but they can also be set manually.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Visit, Reflect)]
+#[reflect(type_uuid = "3c2a5d6e-8b7f-4f1a-9d0e-6a4b2c8e1f57")]
+pub struct LoopRegion {
+    /// The first sample (per channel) of the region.
+    pub start: usize,
+    /// The sample (per channel) right after the last sample of the region.
+    pub end: usize,
+}
+
+impl LoopRegion {
+    /// Creates a new loop region, that starts and ends at the given samples (per channel). The
+    /// end is exclusive.
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns the length of the region in samples (per channel).
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Returns `true` if the region has zero length.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 /// Generic sound buffer that contains decoded samples and allows random access.
 #[derive(Debug, Clone, Default, PartialEq, Visit, Reflect)]
 #[reflect(type_uuid = "e5bf74a5-13d9-4518-96f2-0e4f3413966e")]
@@ -84,6 +116,8 @@ pub struct GenericBuffer {
     pub(crate) sample_rate: usize,
     #[visit(skip)]
     pub(crate) channel_duration_in_samples: usize,
+    #[visit(skip)]
+    pub(crate) loop_region: Option<LoopRegion>,
 }
 
 impl GenericBuffer {
@@ -112,6 +146,7 @@ impl GenericBuffer {
                         samples: Samples(samples),
                         channel_count,
                         sample_rate,
+                        loop_region: None,
                     })
                 }
             }
@@ -126,6 +161,7 @@ impl GenericBuffer {
                     sample_rate: decoder.get_sample_rate(),
                     channel_count: decoder.get_channel_count(),
                     channel_duration_in_samples: decoder.channel_duration_in_samples(),
+                    loop_region: decoder.loop_region(),
                     samples: Samples(decoder.into_samples()),
                 })
             }
@@ -178,4 +214,21 @@ impl GenericBuffer {
     pub fn channel_duration_in_samples(&self) -> usize {
         self.channel_duration_in_samples
     }
+
+    /// Returns the loop region of the buffer (if any). See [`LoopRegion`] docs for more info.
+    #[inline]
+    pub fn loop_region(&self) -> Option<LoopRegion> {
+        self.loop_region
+    }
+
+    /// Sets a new loop region of the buffer. The region is clamped to the duration of the buffer,
+    /// empty regions are ignored. See [`LoopRegion`] docs for more info.
+    pub fn set_loop_region(&mut self, loop_region: Option<LoopRegion>) {
+        self.loop_region = loop_region
+            .map(|region| LoopRegion {
+                start: region.start.min(self.channel_duration_in_samples),
+                end: region.end.min(self.channel_duration_in_samples),
+            })
+            .filter(|region| !region.is_empty());
+    }
 }
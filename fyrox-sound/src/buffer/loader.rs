@@ -51,7 +51,14 @@ pub struct SoundBufferLoader {
 
 impl ResourceLoader for SoundBufferLoader {
     fn extensions(&self) -> &[&str] {
-        &["wav", "ogg"]
+        #[cfg(feature = "opus")]
+        {
+            &["wav", "ogg", "flac", "mp3", "opus", "webm", "mka"]
+        }
+        #[cfg(not(feature = "opus"))]
+        {
+            &["wav", "ogg", "flac", "mp3"]
+        }
     }
 
     fn data_type_uuid(&self) -> Uuid {
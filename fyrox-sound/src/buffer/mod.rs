@@ -351,4 +351,8 @@ impl ResourceData for SoundBuffer {
     fn try_clone_box(&self) -> Option<Box<dyn ResourceData>> {
         None
     }
+
+    fn memory_usage(&self) -> usize {
+        size_of_val(self.samples())
+    }
 }
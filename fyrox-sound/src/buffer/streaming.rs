@@ -49,7 +49,10 @@
 
 use crate::buffer::generic::Samples;
 use crate::{
-    buffer::{generic::GenericBuffer, DataSource, RawStreamingDataSource},
+    buffer::{
+        generic::{GenericBuffer, LoopRegion},
+        DataSource, RawStreamingDataSource,
+    },
     decoder::Decoder,
     error::SoundError,
 };
@@ -85,7 +88,7 @@ impl PartialEq for StreamingBuffer {
 enum StreamingSource {
     #[default]
     Null,
-    Decoder(Decoder),
+    Decoder(Box<Decoder>),
     Raw(Box<dyn RawStreamingDataSource>),
 }
 
@@ -94,7 +97,7 @@ impl StreamingSource {
     fn new(data_source: DataSource) -> Result<Self, SoundError> {
         match data_source {
             DataSource::File { .. } | DataSource::Memory(_) => {
-                Ok(Self::Decoder(Decoder::new(data_source)?))
+                Ok(Self::Decoder(Box::new(Decoder::new(data_source)?)))
             }
             DataSource::RawStreaming(raw) => Ok(Self::Raw(raw)),
             // It makes no sense to stream raw data which is already loaded into memory.
@@ -136,11 +139,21 @@ impl StreamingSource {
         }
     }
 
-    fn time_seek(&mut self, location: Duration) -> Result<(), SoundError> {
+    fn frame_seek(&mut self, frame: usize) -> Result<(), SoundError> {
         match self {
             StreamingSource::Null => Ok(()),
-            StreamingSource::Decoder(decoder) => decoder.time_seek(location),
-            StreamingSource::Raw(raw) => raw.time_seek(location),
+            StreamingSource::Decoder(decoder) => decoder.frame_seek(frame as u64),
+            StreamingSource::Raw(raw) => {
+                let sample_rate = raw.sample_rate().max(1);
+                raw.time_seek(Duration::from_secs_f64(frame as f64 / sample_rate as f64))
+            }
+        }
+    }
+
+    fn loop_region(&self) -> Option<LoopRegion> {
+        match self {
+            StreamingSource::Decoder(decoder) => decoder.loop_region(),
+            StreamingSource::Raw(_) | StreamingSource::Null => None,
         }
     }
 
@@ -200,6 +213,7 @@ impl StreamingBuffer {
                 sample_rate: streaming_source.sample_rate(),
                 channel_count: streaming_source.channel_count(),
                 channel_duration_in_samples: streaming_source.channel_duration_in_samples(),
+                loop_region: streaming_source.loop_region(),
             },
             use_count: 0,
             streaming_source,
@@ -217,9 +231,11 @@ impl StreamingBuffer {
         self.streaming_source.rewind()
     }
 
+    /// Moves the decoder to the given sample (per channel), the next block will start exactly at
+    /// this sample.
     #[inline]
-    pub(crate) fn time_seek(&mut self, location: Duration) -> Result<(), SoundError> {
-        self.streaming_source.time_seek(location)
+    pub(crate) fn frame_seek(&mut self, frame: usize) -> Result<(), SoundError> {
+        self.streaming_source.frame_seek(frame)
     }
 }
 
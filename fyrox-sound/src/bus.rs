@@ -107,6 +107,8 @@ pub struct AudioBus {
     pub(crate) name: String,
     effects: Vec<Effect>,
     gain: f32,
+    #[visit(optional)]
+    max_voices: Option<usize>,
 
     #[reflect(hidden)]
     child_buses: Vec<Handle<AudioBus>>,
@@ -126,6 +128,7 @@ impl Default for AudioBus {
             child_buses: Default::default(),
             effects: Default::default(),
             gain: 1.0,
+            max_voices: None,
             ping_pong_buffer: Default::default(),
             parent_bus: Default::default(),
         }
@@ -174,10 +177,29 @@ impl AudioBus {
         self.gain
     }
 
+    /// Sets maximum amount of simultaneously playing sound sources of the audio bus. When the limit
+    /// is exceeded, sources with the lowest priority (see [`crate::source::SoundSource::set_priority`])
+    /// are stopped first; among sources with the same priority the oldest ones are stopped. `None` -
+    /// unlimited (default).
+    pub fn set_max_voices(&mut self, max_voices: Option<usize>) {
+        self.max_voices = max_voices;
+    }
+
+    /// Returns maximum amount of simultaneously playing sound sources of the audio bus.
+    pub fn max_voices(&self) -> Option<usize> {
+        self.max_voices
+    }
+
     pub(crate) fn input_buffer(&mut self) -> &mut [(f32, f32)] {
         self.ping_pong_buffer.input_mut()
     }
 
+    /// Returns the signal of the bus after its effects were applied and the output of its child buses was
+    /// mixed in. Valid only after the bus graph was rendered.
+    pub(crate) fn rendered_buffer(&self) -> &[(f32, f32)] {
+        self.ping_pong_buffer.input_ref()
+    }
+
     pub(crate) fn begin_render(&mut self, buffer_size: usize) {
         if self.ping_pong_buffer.capacity() < buffer_size {
             self.ping_pong_buffer.resize(buffer_size);
@@ -32,19 +32,25 @@
 
 use crate::bus::AudioBusGraph;
 use crate::{
+    error::SoundError,
+    event::{SoundEventResource, SoundEventState},
     listener::Listener,
     pool::Ticket,
     renderer::{render_source_default, Renderer},
-    source::{SoundSource, Status},
+    source::{SoundSource, SoundSourceBuilder, Status},
 };
 use fyrox_core::pool::PoolError;
 use fyrox_core::{
+    algebra::Vector3,
+    log::Log,
     pool::{Handle, Pool},
     reflect::prelude::*,
+    uuid::Uuid,
     visitor::prelude::*,
     SafeLock,
 };
 use std::{
+    collections::HashMap,
     sync::{Arc, Mutex, MutexGuard},
     time::Duration,
 };
@@ -122,7 +128,7 @@ pub struct SerializationOptions {
 }
 
 /// Internal state of context.
-#[derive(Default, Debug, Clone, PartialEq, Reflect)]
+#[derive(Debug, Clone, PartialEq, Reflect)]
 #[reflect(type_uuid = "10f5a7ce-efe4-4bcc-aabc-c399e8fd1a3c")]
 pub struct State {
     sources: Pool<SoundSource>,
@@ -132,13 +138,49 @@ pub struct State {
     bus_graph: AudioBusGraph,
     distance_model: DistanceModel,
     paused: bool,
+    #[reflect(min_value = 0.0, step = 1.0)]
+    speed_of_sound: f32,
+    #[reflect(min_value = 0.0, step = 0.05)]
+    doppler_factor: f32,
+    reverb_bus: Option<String>,
+    // Temporary buffer for sources, that are sent to the reverb bus.
+    #[reflect(hidden)]
+    send_buffer: Vec<(f32, f32)>,
+    #[reflect(hidden)]
+    switches: HashMap<String, String>,
+    #[reflect(hidden)]
+    event_states: HashMap<Uuid, SoundEventState>,
     /// A set of flags, that can be used to define what should be skipped during the
     /// serialization of a sound context.
     #[reflect(hidden)]
     pub serialization_options: SerializationOptions,
 }
 
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            sources: Pool::new(),
+            listener: Listener::new(),
+            render_duration: Default::default(),
+            renderer: Renderer::Default,
+            bus_graph: AudioBusGraph::new(),
+            distance_model: DistanceModel::InverseDistance,
+            paused: false,
+            speed_of_sound: Self::DEFAULT_SPEED_OF_SOUND,
+            doppler_factor: 1.0,
+            reverb_bus: None,
+            send_buffer: Default::default(),
+            switches: Default::default(),
+            event_states: Default::default(),
+            serialization_options: Default::default(),
+        }
+    }
+}
+
 impl State {
+    /// Speed of sound in the air (in meters per second), it is used by default.
+    pub const DEFAULT_SPEED_OF_SOUND: f32 = 343.3;
+
     /// Extracts a source from the context and reserves its handle. It is used to temporarily take
     /// ownership over source, and then put node back using given ticket.
     pub fn take_reserve(
@@ -182,6 +224,41 @@ impl State {
         self.distance_model
     }
 
+    /// Sets speed of sound in world units per second. It is used to calculate Doppler shift of
+    /// moving sound sources. Default is [`Self::DEFAULT_SPEED_OF_SOUND`].
+    pub fn set_speed_of_sound(&mut self, speed_of_sound: f32) {
+        self.speed_of_sound = speed_of_sound.max(0.0);
+    }
+
+    /// Returns speed of sound.
+    pub fn speed_of_sound(&self) -> f32 {
+        self.speed_of_sound
+    }
+
+    /// Sets global Doppler factor. It allows you to exaggerate (values > 1.0) or weaken (values < 1.0)
+    /// Doppler effect for every sound source, 0.0 disables the effect. Default is 1.0.
+    pub fn set_doppler_factor(&mut self, doppler_factor: f32) {
+        self.doppler_factor = doppler_factor.max(0.0);
+    }
+
+    /// Returns global Doppler factor.
+    pub fn doppler_factor(&self) -> f32 {
+        self.doppler_factor
+    }
+
+    /// Sets the name of an audio bus, that is used for environmental reverb. The signal of every
+    /// source is sent to this bus in addition to its own bus, scaled by the reverb send level of the
+    /// source (see [`SoundSource::set_reverb_send`]). Usually the bus contains a single
+    /// [`crate::effects::reverb::Reverb`] effect with zero dry part. `None` disables the sends.
+    pub fn set_reverb_bus<S: AsRef<str>>(&mut self, bus: Option<S>) {
+        self.reverb_bus = bus.map(|bus| bus.as_ref().to_string());
+    }
+
+    /// Returns the name of the reverb bus (if any).
+    pub fn reverb_bus(&self) -> Option<&str> {
+        self.reverb_bus.as_deref()
+    }
+
     /// Returns amount of time context spent on rendering all sound sources.
     pub fn full_render_duration(&self) -> Duration {
         self.render_duration
@@ -207,6 +284,154 @@ impl State {
         self.sources.spawn(source)
     }
 
+    /// Sets a value of the switch with the given name. Switches are used by sound events to select
+    /// sounds, see [`crate::event::SwitchContainer`] for more info.
+    pub fn set_switch<N: AsRef<str>, V: AsRef<str>>(&mut self, name: N, value: V) {
+        self.switches
+            .insert(name.as_ref().to_string(), value.as_ref().to_string());
+    }
+
+    /// Returns current value of the switch with the given name.
+    pub fn switch<N: AsRef<str>>(&self, name: N) -> Option<&str> {
+        self.switches.get(name.as_ref()).map(|v| v.as_str())
+    }
+
+    /// Removes the switch with the given name, sound events will use default cases for it.
+    pub fn remove_switch<N: AsRef<str>>(&mut self, name: N) -> Option<String> {
+        self.switches.remove(name.as_ref())
+    }
+
+    /// Plays the given sound event at the given position and returns a handle of the new sound
+    /// source. The source will be removed automatically when it finishes playing. The method fails
+    /// if the event is not loaded, is on cooldown, has nothing to play or if it has reached a voice
+    /// limit (its own or of its audio bus) and no voice could be stolen. See [`crate::event`] docs
+    /// for more info.
+    pub fn play_event(
+        &mut self,
+        event: &SoundEventResource,
+        position: Vector3<f32>,
+    ) -> Result<Handle<SoundSource>, SoundError> {
+        let event_uuid = event.resource_uuid();
+        let event_state = event.state();
+        let event = event_state.data_ref().ok_or(SoundError::EventIsNotLoaded)?;
+
+        // Runtime state is modified only if the event was actually played.
+        let mut runtime_state = self
+            .event_states
+            .get(&event_uuid)
+            .cloned()
+            .unwrap_or_default();
+        let selected = event.select(
+            &mut runtime_state,
+            &self.switches,
+            &mut fyrox_core::rand::thread_rng(),
+        )?;
+
+        if let Some(max_voices) = event.max_voices {
+            let is_event_voice = |s: &SoundSource| s.event == Some(event_uuid);
+            if self.count_voices(is_event_voice) >= max_voices {
+                if !event.steal_voices {
+                    return Err(SoundError::VoiceLimitReached);
+                }
+                let victim = self
+                    .find_victim(is_event_voice, i32::MAX)
+                    .ok_or(SoundError::VoiceLimitReached)?;
+                self.steal_voice(victim);
+            }
+        }
+
+        let bus_max_voices = self
+            .bus_graph
+            .buses_iter()
+            .find(|bus| bus.name() == event.bus)
+            .and_then(|bus| bus.max_voices());
+        if let Some(max_voices) = bus_max_voices {
+            let is_bus_voice = |s: &SoundSource| s.bus == event.bus;
+            if self.count_voices(is_bus_voice) >= max_voices {
+                let victim = self
+                    .find_victim(is_bus_voice, event.priority)
+                    .ok_or(SoundError::VoiceLimitReached)?;
+                self.steal_voice(victim);
+            }
+        }
+
+        let mut source = SoundSourceBuilder::new()
+            .with_buffer(selected.buffer)
+            .with_gain(selected.gain)
+            .with_pitch(selected.pitch)
+            .with_position(position)
+            .with_spatial_blend_factor(event.spatial_blend)
+            .with_priority(event.priority)
+            .with_bus(&event.bus)
+            .with_play_once(true)
+            .with_status(Status::Playing)
+            .build()?;
+        source.event = Some(event_uuid);
+
+        runtime_state.last_play_time = Some(fyrox_core::instant::Instant::now());
+        self.event_states.insert(event_uuid, runtime_state);
+
+        Ok(self.sources.spawn(source))
+    }
+
+    fn count_voices<F: Fn(&SoundSource) -> bool>(&self, filter: F) -> usize {
+        self.sources
+            .iter()
+            .filter(|s| s.status() == Status::Playing && filter(s))
+            .count()
+    }
+
+    // Finds a playing source with the lowest priority (not higher than the given one). Among the
+    // sources with the same priority the oldest one is selected.
+    fn find_victim<F: Fn(&SoundSource) -> bool>(
+        &self,
+        filter: F,
+        max_priority: i32,
+    ) -> Option<Handle<SoundSource>> {
+        self.sources
+            .pair_iter()
+            .filter(|(_, s)| {
+                s.status() == Status::Playing && s.priority() <= max_priority && filter(s)
+            })
+            .min_by(|(_, a), (_, b)| {
+                a.priority()
+                    .cmp(&b.priority())
+                    .then_with(|| b.playback_time().cmp(&a.playback_time()))
+            })
+            .map(|(handle, _)| handle)
+    }
+
+    fn steal_voice(&mut self, handle: Handle<SoundSource>) {
+        Log::verify(self.sources[handle].stop());
+    }
+
+    // Stops sources of the audio buses, that have exceeded their voice limits. This is needed for
+    // sources that were added without using sound events. Culled sources are stopped (not removed),
+    // so their owners (for example, scene sound nodes) see the `Stopped` status on the next sync.
+    fn enforce_bus_voice_limits(&mut self) {
+        let limits = self
+            .bus_graph
+            .buses_iter()
+            .filter_map(|bus| bus.max_voices().map(|max| (bus.name().to_string(), max)))
+            .collect::<Vec<_>>();
+        for (bus, max_voices) in limits {
+            let mut voices = self
+                .sources
+                .pair_iter()
+                .filter(|(_, s)| s.status() == Status::Playing && s.bus == bus)
+                .map(|(handle, s)| (handle, s.priority(), s.playback_time()))
+                .collect::<Vec<_>>();
+            if voices.len() <= max_voices {
+                continue;
+            }
+            // Most important voices first: higher priority, then the newest.
+            voices.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.2.cmp(&b.2)));
+            for (handle, _, _) in voices.into_iter().skip(max_voices) {
+                self.steal_voice(handle);
+            }
+        }
+    }
+
     /// Removes sound source from the context.
     pub fn remove_source(&mut self, source: Handle<SoundSource>) {
         self.sources.free(source);
@@ -274,6 +499,8 @@ impl State {
                 !done
             });
 
+            self.enforce_bus_voice_limits();
+
             self.bus_graph.begin_render(output_device_buffer.len());
 
             // Render sounds to respective audio buses.
@@ -282,30 +509,64 @@ impl State {
                 .iter_mut()
                 .filter(|s| s.status() == Status::Playing)
             {
-                if let Some(bus_input_buffer) = self.bus_graph.try_get_bus_input_buffer(&source.bus)
+                if self
+                    .bus_graph
+                    .try_get_bus_input_buffer(&source.bus)
+                    .is_none()
                 {
-                    source.render(sample_rate, output_device_buffer.len());
-
-                    match self.renderer {
-                        Renderer::Default => {
-                            // Simple rendering path. Much faster (4-5 times) than HRTF path.
-                            render_source_default(
-                                source,
-                                &self.listener,
-                                self.distance_model,
-                                bus_input_buffer,
-                            );
-                        }
-                        Renderer::HrtfRenderer(ref mut hrtf_renderer) => {
-                            hrtf_renderer.render_source(
-                                sample_rate,
-                                source,
-                                &self.listener,
-                                self.distance_model,
-                                bus_input_buffer,
-                            );
-                        }
+                    continue;
+                }
+
+                source.update_spatial_state(
+                    &self.listener,
+                    self.speed_of_sound,
+                    self.doppler_factor,
+                );
+                source.render(sample_rate, output_device_buffer.len());
+
+                let reverb_bus = self
+                    .reverb_bus
+                    .as_deref()
+                    .filter(|bus| source.reverb_send() > 0.0 && *bus != source.bus);
+
+                if let Some(reverb_bus) =
+                    reverb_bus.filter(|bus| self.bus_graph.try_get_bus_input_buffer(bus).is_some())
+                {
+                    // The source is rendered once to a temporary buffer and then mixed to both
+                    // buses.
+                    self.send_buffer.clear();
+                    self.send_buffer
+                        .resize(output_device_buffer.len(), (0.0, 0.0));
+                    render_source(
+                        &mut self.renderer,
+                        sample_rate,
+                        source,
+                        &self.listener,
+                        self.distance_model,
+                        &mut self.send_buffer,
+                    );
+
+                    if let Some(bus_input_buffer) =
+                        self.bus_graph.try_get_bus_input_buffer(&source.bus)
+                    {
+                        mix(&self.send_buffer, 1.0, bus_input_buffer);
+                    }
+                    if let Some(reverb_input_buffer) =
+                        self.bus_graph.try_get_bus_input_buffer(reverb_bus)
+                    {
+                        mix(&self.send_buffer, source.reverb_send(), reverb_input_buffer);
                     }
+                } else if let Some(bus_input_buffer) =
+                    self.bus_graph.try_get_bus_input_buffer(&source.bus)
+                {
+                    render_source(
+                        &mut self.renderer,
+                        sample_rate,
+                        source,
+                        &self.listener,
+                        self.distance_model,
+                        bus_input_buffer,
+                    );
                 }
             }
 
@@ -316,6 +577,32 @@ impl State {
     }
 }
 
+fn render_source(
+    renderer: &mut Renderer,
+    sample_rate: u32,
+    source: &mut SoundSource,
+    listener: &Listener,
+    distance_model: DistanceModel,
+    out_buf: &mut [(f32, f32)],
+) {
+    match renderer {
+        Renderer::Default => {
+            // Simple rendering path. Much faster (4-5 times) than HRTF path.
+            render_source_default(source, listener, distance_model, out_buf);
+        }
+        Renderer::HrtfRenderer(ref mut hrtf_renderer) => {
+            hrtf_renderer.render_source(sample_rate, source, listener, distance_model, out_buf);
+        }
+    }
+}
+
+fn mix(input: &[(f32, f32)], gain: f32, output: &mut [(f32, f32)]) {
+    for ((out_left, out_right), (left, right)) in output.iter_mut().zip(input) {
+        *out_left += *left * gain;
+        *out_right += *right * gain;
+    }
+}
+
 impl SoundContext {
     /// TODO: This is magic constant that gives 1024 + 1 number when summed with
     ///       HRTF length for faster FFT calculations. Find a better way of selecting this.
@@ -331,16 +618,7 @@ impl SoundContext {
     /// because separate thread also uses context.
     pub fn new() -> Self {
         Self {
-            state: Some(Arc::new(Mutex::new(State {
-                sources: Pool::new(),
-                listener: Listener::new(),
-                render_duration: Default::default(),
-                renderer: Renderer::Default,
-                bus_graph: AudioBusGraph::new(),
-                distance_model: DistanceModel::InverseDistance,
-                paused: false,
-                serialization_options: Default::default(),
-            }))),
+            state: Some(Arc::new(Mutex::new(State::default()))),
         }
     }
 
@@ -397,6 +675,9 @@ impl Visit for State {
         self.renderer.visit("Renderer", &mut region)?;
         self.paused.visit("Paused", &mut region)?;
         self.distance_model.visit("DistanceModel", &mut region)?;
+        let _ = self.speed_of_sound.visit("SpeedOfSound", &mut region);
+        let _ = self.doppler_factor.visit("DopplerFactor", &mut region);
+        let _ = self.reverb_bus.visit("ReverbBus", &mut region);
 
         Ok(())
     }
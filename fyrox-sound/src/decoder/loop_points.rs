@@ -0,0 +1,167 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Reads loop points from metadata of sound files. The following sources are supported:
+//!
+//! - `smpl` chunk of RIFF WAVE files, the first loop of the chunk is used.
+//! - `LOOPSTART` tag with either `LOOPLENGTH` or `LOOPEND` tag, the values are given in samples.
+//!   This convention is used in Ogg (Vorbis and Opus) files, but the tags are read from any
+//!   format that supports them.
+
+use crate::buffer::generic::LoopRegion;
+use std::io::{Read, Seek, SeekFrom};
+use symphonia::core::meta::Tag;
+
+/// Tries to read the loop region from `smpl` chunk of a RIFF WAVE file. The position of the
+/// stream is restored after reading. Returns `None` if the stream is not a WAVE file or if it has
+/// no loops.
+pub(crate) fn read_riff_loop_region<S: Read + Seek>(stream: &mut S) -> Option<LoopRegion> {
+    let position = stream.stream_position().ok()?;
+    let result = find_smpl_loop(stream);
+    stream.seek(SeekFrom::Start(position)).ok()?;
+    result
+}
+
+fn read_u32<S: Read>(stream: &mut S) -> Option<u32> {
+    let mut bytes = [0; 4];
+    stream.read_exact(&mut bytes).ok()?;
+    Some(u32::from_le_bytes(bytes))
+}
+
+fn find_smpl_loop<S: Read + Seek>(stream: &mut S) -> Option<LoopRegion> {
+    let mut header = [0; 12];
+    stream.read_exact(&mut header).ok()?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return None;
+    }
+
+    loop {
+        let mut id = [0; 4];
+        stream.read_exact(&mut id).ok()?;
+        let size = read_u32(stream)?;
+        // Chunks are aligned to two bytes.
+        let padded_size = size as i64 + (size & 1) as i64;
+
+        if &id == b"smpl" {
+            // Skip manufacturer, product, sample period, unity note, pitch fraction, SMPTE
+            // format and offset.
+            stream.seek(SeekFrom::Current(28)).ok()?;
+            let loop_count = read_u32(stream)?;
+            // Sampler data.
+            read_u32(stream)?;
+            if loop_count == 0 || size < 36 + 24 {
+                return None;
+            }
+            // Cue point id, loop type.
+            stream.seek(SeekFrom::Current(8)).ok()?;
+            let start = read_u32(stream)? as usize;
+            // The end of a loop is inclusive.
+            let end = read_u32(stream)? as usize + 1;
+            return (end > start).then_some(LoopRegion::new(start, end));
+        }
+
+        stream.seek(SeekFrom::Current(padded_size)).ok()?;
+    }
+}
+
+/// Tries to build a loop region from `LOOPSTART`, `LOOPLENGTH` and `LOOPEND` tags. Returns `None`
+/// if there is no `LOOPSTART` tag or the region is empty.
+pub(crate) fn loop_region_from_tags<'a>(
+    tags: impl IntoIterator<Item = &'a Tag>,
+) -> Option<LoopRegion> {
+    let mut start = None;
+    let mut length = None;
+    let mut end = None;
+    for tag in tags {
+        let Ok(value) = tag.value.to_string().trim().parse::<usize>() else {
+            continue;
+        };
+        if tag.key.eq_ignore_ascii_case("LOOPSTART") {
+            start = Some(value);
+        } else if tag.key.eq_ignore_ascii_case("LOOPLENGTH") {
+            length = Some(value);
+        } else if tag.key.eq_ignore_ascii_case("LOOPEND") {
+            end = Some(value);
+        }
+    }
+    let start = start?;
+    let end = length.map(|length| start + length).or(end)?;
+    (end > start).then_some(LoopRegion::new(start, end))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+    use symphonia::core::meta::Value;
+
+    fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(data);
+        if !data.len().is_multiple_of(2) {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_smpl_chunk() {
+        let mut smpl = vec![0; 28];
+        // Loop count, sampler data.
+        smpl.extend_from_slice(&1u32.to_le_bytes());
+        smpl.extend_from_slice(&0u32.to_le_bytes());
+        // Cue point id, type, start, end, fraction, play count.
+        for value in [0u32, 0, 100, 199, 0, 0] {
+            smpl.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let mut body = b"WAVE".to_vec();
+        body.extend(chunk(b"fmt ", &[0; 16]));
+        body.extend(chunk(b"data", &[0; 7]));
+        body.extend(chunk(b"smpl", &smpl));
+        let mut file = chunk(b"RIFF", &body);
+        file.extend_from_slice(b"trailing");
+
+        let mut cursor = Cursor::new(file);
+        assert_eq!(
+            read_riff_loop_region(&mut cursor),
+            Some(LoopRegion::new(100, 200))
+        );
+        assert_eq!(cursor.position(), 0);
+
+        let mut not_wav = Cursor::new(b"OggS0000000000000000".to_vec());
+        assert_eq!(read_riff_loop_region(&mut not_wav), None);
+    }
+
+    #[test]
+    fn test_loop_tags() {
+        let tag = |key: &str, value: &str| Tag::new(None, key, Value::String(value.to_string()));
+        assert_eq!(
+            loop_region_from_tags(&[tag("LOOPSTART", "10"), tag("LOOPLENGTH", "20")]),
+            Some(LoopRegion::new(10, 30))
+        );
+        assert_eq!(
+            loop_region_from_tags(&[tag("loopend", "50"), tag("loopstart", "10")]),
+            Some(LoopRegion::new(10, 50))
+        );
+        assert_eq!(loop_region_from_tags(&[tag("LOOPSTART", "10")]), None);
+    }
+}
@@ -18,24 +18,69 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use std::io::Seek;
-use std::time::Duration;
-
 use symphonia::core::audio::{AudioBuffer, Signal};
-use symphonia::core::codecs::{Decoder as SymphoniaDecoder, DecoderOptions};
-use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::codecs::{
+    CodecRegistry, Decoder as SymphoniaDecoder, DecoderOptions, CODEC_TYPE_OPUS,
+};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, Packet, SeekMode, SeekTo};
 use symphonia::core::io::{MediaSourceStream, MediaSourceStreamOptions};
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
-use symphonia::core::units::Time;
+use symphonia::core::units::TimeBase;
 use symphonia::default;
 
-use crate::{buffer::DataSource, error::SoundError};
+use crate::{
+    buffer::{generic::LoopRegion, DataSource},
+    decoder::seek_table::{packet_hash, SeekPoint, SeekTable},
+    error::SoundError,
+};
+
+mod loop_points;
+#[cfg(feature = "opus")]
+mod opus;
+mod seek_table;
+
+/// Returns the registry with all the codecs supported by the engine: the default codecs of
+/// Symphonia and the codecs implemented on top of external libraries (Opus).
+fn codec_registry() -> &'static CodecRegistry {
+    #[cfg(feature = "opus")]
+    {
+        static REGISTRY: std::sync::OnceLock<CodecRegistry> = std::sync::OnceLock::new();
+        REGISTRY.get_or_init(|| {
+            let mut registry = CodecRegistry::new();
+            default::register_enabled_codecs(&mut registry);
+            registry.register_all::<opus::OpusDecoder>();
+            registry
+        })
+    }
+    #[cfg(not(feature = "opus"))]
+    {
+        default::get_codecs()
+    }
+}
 
 pub(crate) struct Decoder {
     reader: Box<dyn FormatReader>,
     decoder: Box<dyn SymphoniaDecoder>,
+    track_id: u32,
+    time_base: Option<TimeBase>,
     samples: std::vec::IntoIter<f32>,
+    seek_table: SeekTable,
+    // Index of the next packet in the seek table, `None` if the position in the stream is unknown.
+    cursor: Option<usize>,
+    // A packet that was read while seeking and has to be decoded first.
+    pending_packet: Option<Packet>,
+    // A sample (per channel) to which the decoder was moved. All the samples before it are
+    // discarded.
+    seek_target: Option<u64>,
+    // Amount of samples (per channel) to discard after seeking, used only if the seek table cannot
+    // be used.
+    skip: u64,
+    // Codec delay (in timestamps of the track), that is not removed by the demuxer. Ogg demuxer
+    // reports pre-skip of Opus streams, but does not trim it.
+    pre_skip: u64,
+    loop_region: Option<LoopRegion>,
     pub channel_count: usize,
     pub sample_rate: usize,
     pub channel_duration_in_samples: usize,
@@ -50,6 +95,7 @@ impl std::fmt::Debug for Decoder {
                 "channel_duration_in_samples",
                 &self.channel_duration_in_samples,
             )
+            .field("loop_region", &self.loop_region)
             .finish()
     }
 }
@@ -58,20 +104,17 @@ impl Iterator for Decoder {
     type Item = f32;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(sample) = self.samples.next() {
-            Some(sample)
-        } else {
-            self.samples = Self::samples(&mut self.reader, &mut self.decoder).ok()?;
-            self.samples.next()
+        loop {
+            if let Some(sample) = self.samples.next() {
+                return Some(sample);
+            }
+            self.samples = self.decode_next_packet().ok()?;
         }
     }
 }
 
 impl Decoder {
     pub fn new(mut source: DataSource) -> Result<Self, SoundError> {
-        let initial_stream_position = source.stream_position()?;
-
-        let codec_registry = default::get_codecs();
         let probe = default::get_probe();
 
         let mut hint = Hint::new();
@@ -85,66 +128,208 @@ impl Decoder {
             }
         };
 
+        let riff_loop_region = loop_points::read_riff_loop_region(&mut source);
+
         let media_source_stream =
             MediaSourceStream::new(Box::new(source), MediaSourceStreamOptions::default());
 
-        let res = probe.format(
+        let mut res = probe.format(
             &hint,
             media_source_stream,
-            &FormatOptions::default(),
+            &FormatOptions {
+                // Removes encoder delay and padding, this is essential for sample-accurate
+                // seeking and looping.
+                enable_gapless: true,
+                ..Default::default()
+            },
             &MetadataOptions::default(),
         )?;
 
         let mut reader = res.format;
-        let tracks = reader.tracks();
-        let first_track = tracks.first().ok_or(SoundError::InvalidHeader)?;
-        let codec_params = &first_track.codec_params;
-        let mut decoder = codec_registry.make(codec_params, &DecoderOptions::default())?;
-
-        // Get duration
-        let mut last_packet = None;
-        while let Ok(packet) = reader.next_packet() {
-            last_packet = Some(packet);
-        }
-        reader.seek(
-            SeekMode::Accurate,
-            SeekTo::Time {
-                time: Time::new(initial_stream_position, 0.0),
-                track_id: None,
+        let track = reader.tracks().first().ok_or(SoundError::InvalidHeader)?;
+        let track_id = track.id;
+        let codec_params = track.codec_params.clone();
+        let decoder = codec_registry().make(&codec_params, &DecoderOptions::default())?;
+        let sample_rate = codec_params.sample_rate.ok_or(SoundError::InvalidHeader)? as usize;
+
+        let loop_region = riff_loop_region.or_else(|| {
+            let container_tags = reader
+                .metadata()
+                .current()
+                .and_then(|revision| loop_points::loop_region_from_tags(revision.tags()));
+            container_tags.or_else(|| {
+                res.metadata.get().and_then(|metadata| {
+                    metadata
+                        .current()
+                        .and_then(|revision| loop_points::loop_region_from_tags(revision.tags()))
+                })
+            })
+        });
+
+        let mut decoder = Self {
+            reader,
+            decoder,
+            track_id,
+            time_base: codec_params.time_base,
+            samples: Vec::new().into_iter(),
+            seek_table: Default::default(),
+            cursor: None,
+            pending_packet: None,
+            seek_target: None,
+            skip: 0,
+            pre_skip: match codec_params.codec {
+                CODEC_TYPE_OPUS => codec_params.delay.unwrap_or_default() as u64,
+                _ => 0,
             },
-        )?;
+            loop_region,
+            channel_count: codec_params.channels.unwrap_or_default().count(),
+            sample_rate,
+            channel_duration_in_samples: 0,
+        };
+
+        // Scan the entire stream to get its exact duration and to build the seek table.
+        let mut duration = 0;
+        loop {
+            let mut packet = match decoder.reader.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(_)) => break,
+                Err(err) => return Err(err.into()),
+            };
+            if packet.track_id() != track_id {
+                continue;
+            }
+            decoder.trim_pre_skip(&mut packet);
+            let frame = decoder.ts_to_frames(packet.ts());
+            decoder.seek_table.push(SeekPoint {
+                frame,
+                trim_start: packet.trim_start(),
+                trim_end: packet.trim_end(),
+                hash: packet_hash(&packet),
+            });
+            duration = duration.max(frame + decoder.ts_to_frames(packet.dur()));
+        }
+        decoder.channel_duration_in_samples = duration as usize;
+        decoder.loop_region = decoder
+            .loop_region
+            .map(|region| LoopRegion {
+                start: region.start.min(decoder.channel_duration_in_samples),
+                end: region.end.min(decoder.channel_duration_in_samples),
+            })
+            .filter(|region| !region.is_empty());
+
+        decoder.rewind()?;
+
+        Ok(decoder)
+    }
 
-        let channel_duration_in_samples = last_packet.map(|p| p.ts as usize).unwrap_or_default();
+    fn ts_to_frames(&self, ts: u64) -> u64 {
+        match self.time_base {
+            Some(time_base) => {
+                (ts as u128 * time_base.numer as u128 * self.sample_rate as u128
+                    / time_base.denom as u128) as u64
+            }
+            None => ts,
+        }
+    }
 
-        let samples = Self::samples(&mut reader, &mut decoder)?;
+    fn frames_to_ts(&self, frames: u64) -> u64 {
+        match self.time_base {
+            Some(time_base) => {
+                (frames as u128 * time_base.denom as u128
+                    / (time_base.numer as u128 * self.sample_rate as u128).max(1))
+                    as u64
+            }
+            None => frames,
+        }
+    }
 
-        let params = &reader
-            .tracks()
-            .first()
-            .ok_or(SoundError::InvalidHeader)?
-            .codec_params;
+    fn next_track_packet(&mut self) -> Result<Packet, SoundError> {
+        if let Some(packet) = self.pending_packet.take() {
+            return Ok(packet);
+        }
+        loop {
+            let mut packet = self.reader.next_packet()?;
+            if packet.track_id() == self.track_id {
+                self.trim_pre_skip(&mut packet);
+                return Ok(packet);
+            }
+        }
+    }
 
-        Ok(Self {
-            samples,
-            channel_count: params.channels.unwrap_or_default().count(),
-            sample_rate: params.sample_rate.ok_or(SoundError::InvalidHeader)? as usize,
-            reader,
-            decoder,
-            channel_duration_in_samples,
-        })
+    /// Removes the pre-skip (see [`Self::pre_skip`]) from the packet, the same way as demuxers
+    /// remove encoder delay when gapless playback is enabled.
+    fn trim_pre_skip(&self, packet: &mut Packet) {
+        if packet.ts < self.pre_skip {
+            let trim = (self.pre_skip - packet.ts).min(packet.dur);
+            packet.trim_start += trim as u32;
+            packet.dur -= trim;
+            packet.ts = 0;
+        } else {
+            packet.ts -= self.pre_skip;
+        }
     }
 
-    fn samples(
-        reader: &mut Box<dyn FormatReader>,
-        decoder: &mut Box<dyn SymphoniaDecoder>,
-    ) -> Result<std::vec::IntoIter<f32>, SoundError> {
-        let packet = reader.next_packet()?;
-        let decoded = decoder.decode(&packet)?;
+    fn decode_next_packet(&mut self) -> Result<std::vec::IntoIter<f32>, SoundError> {
+        let packet = self.next_track_packet()?;
+
+        // Prefer the information from the seek table, because packet timestamps (and thus
+        // trimming) could be inaccurate after coarse seeking.
+        let mut packet = packet;
+        let mut packet_end = None;
+        match self.cursor {
+            Some(cursor)
+                if self.seek_table.get(cursor).map(|point| point.hash)
+                    == Some(packet_hash(&packet)) =>
+            {
+                let point = self.seek_table.get(cursor).copied().unwrap_or_default();
+                packet.trim_start = point.trim_start;
+                packet.trim_end = point.trim_end;
+                // When the stream is decoded continuously, the packet ends where the next one
+                // starts.
+                packet_end = Some(
+                    self.seek_table
+                        .get(cursor + 1)
+                        .map_or(self.channel_duration_in_samples as u64, |next| next.frame),
+                );
+                self.cursor = Some(cursor + 1);
+            }
+            _ => self.cursor = None,
+        }
+
+        // Decoders trim encoder delay and padding by themselves.
+        let decoded = match self.decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // Malformed packets are skipped.
+            Err(SymphoniaError::DecodeError(_)) => return Ok(Vec::new().into_iter()),
+            Err(err) => return Err(err.into()),
+        };
         let mut buffer: AudioBuffer<f32> = decoded.make_equivalent();
         decoded.convert(&mut buffer);
-        let samples = Self::interleaved(&mut buffer);
 
-        Ok(samples.into_iter())
+        let frames = buffer.frames() as u64;
+        let skip = match (self.seek_target, packet_end) {
+            (Some(target), Some(end)) => {
+                // Some decoders produce less samples (or no samples at all) for the first packets
+                // after reset, so the decoded samples are aligned to the end of the packet.
+                let start = end.saturating_sub(frames);
+                if end >= target {
+                    self.seek_target = None;
+                }
+                target.saturating_sub(start).min(frames)
+            }
+            (Some(_), None) => {
+                self.seek_target = None;
+                0
+            }
+            (None, _) => {
+                let skip = self.skip.min(frames);
+                self.skip -= skip;
+                skip
+            }
+        };
+        buffer.trim(skip as usize, 0);
+
+        Ok(Self::interleaved(&mut buffer).into_iter())
     }
 
     fn interleaved(buffer: &mut AudioBuffer<f32>) -> Vec<f32> {
@@ -167,17 +352,68 @@ impl Decoder {
     }
 
     pub fn rewind(&mut self) -> Result<(), SoundError> {
-        self.time_seek(Duration::from_secs(0))
+        self.frame_seek(0)
     }
 
-    pub fn time_seek(&mut self, location: Duration) -> Result<(), SoundError> {
-        self.reader.seek(
-            SeekMode::Accurate,
-            SeekTo::Time {
-                time: location.into(),
-                track_id: None,
-            },
-        )?;
+    /// Moves the decoder to the given sample (per channel). Seeking is sample-accurate: the next
+    /// sample produced by the decoder will be the requested one.
+    pub fn frame_seek(&mut self, frame: u64) -> Result<(), SoundError> {
+        let frame = frame.min(self.channel_duration_in_samples as u64);
+
+        self.decoder.reset();
+        self.samples = Vec::new().into_iter();
+        self.pending_packet = None;
+        self.cursor = None;
+        self.seek_target = None;
+        self.skip = 0;
+
+        // Decoding starts a bit earlier than the requested position, because some codecs need
+        // a few packets to restore their state after reset (overlapping windows of Vorbis and
+        // Opus, bit reservoir of MP3, etc.). The decoded samples before the requested position
+        // are discarded.
+        let preroll = (self.sample_rate / 5) as u64;
+
+        // Try coarse seeking first, it is much faster for long streams, but it could land at
+        // an inaccurate position. The exact position is then found using the seek table. If
+        // coarse seeking has overshot the requested position, fallback to accurate seeking.
+        for mode in [SeekMode::Coarse, SeekMode::Accurate] {
+            let seeked_to = match self.reader.seek(
+                mode,
+                SeekTo::TimeStamp {
+                    ts: self.frames_to_ts(frame.saturating_sub(preroll)) + self.pre_skip,
+                    track_id: self.track_id,
+                },
+            ) {
+                Ok(seeked_to) => seeked_to,
+                Err(_) if mode == SeekMode::Coarse => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            let packet = match self.next_track_packet() {
+                Ok(packet) => packet,
+                // Seeking to the end of the stream.
+                Err(SoundError::DecoderError(_)) => return Ok(()),
+                Err(err) => return Err(err),
+            };
+
+            let frame_hint = self.ts_to_frames(seeked_to.actual_ts.saturating_sub(self.pre_skip));
+            match self.seek_table.find(&packet, frame_hint) {
+                Some(index) => {
+                    let point_frame = self.seek_table.get(index).map_or(0, |point| point.frame);
+                    if point_frame > frame && mode == SeekMode::Coarse {
+                        continue;
+                    }
+                    self.cursor = Some(index);
+                    self.seek_target = Some(frame);
+                }
+                None => {
+                    self.skip = self
+                        .ts_to_frames(seeked_to.required_ts.saturating_sub(seeked_to.actual_ts));
+                }
+            }
+            self.pending_packet = Some(packet);
+            break;
+        }
 
         Ok(())
     }
@@ -190,6 +426,10 @@ impl Decoder {
         self.sample_rate
     }
 
+    pub fn loop_region(&self) -> Option<LoopRegion> {
+        self.loop_region
+    }
+
     pub fn into_samples(self) -> Vec<f32> {
         self.collect()
     }
@@ -198,3 +438,44 @@ impl Decoder {
         self.channel_duration_in_samples
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn load(path: &str) -> Decoder {
+        let data = std::fs::read(path).unwrap();
+        Decoder::new(DataSource::from_memory(data)).unwrap()
+    }
+
+    fn check_seek_accuracy(path: &str) {
+        let mut decoder = load(path);
+        let channel_count = decoder.channel_count;
+        let samples = decoder.by_ref().collect::<Vec<_>>();
+        assert_eq!(
+            samples.len(),
+            decoder.channel_duration_in_samples * channel_count
+        );
+
+        let duration = decoder.channel_duration_in_samples;
+        for frame in [0, 1, 4095, 12345, duration / 2, duration - 64] {
+            decoder.frame_seek(frame as u64).unwrap();
+            let expected = &samples[frame * channel_count..(frame + 64) * channel_count];
+            let actual = decoder.by_ref().take(expected.len()).collect::<Vec<_>>();
+            assert_eq!(actual.len(), expected.len(), "seek to {frame} is truncated");
+            for (a, b) in actual.iter().zip(expected) {
+                assert!((a - b).abs() < 1.0e-3, "seek to {frame} is inaccurate");
+            }
+        }
+    }
+
+    #[test]
+    fn test_seek_accuracy_wav() {
+        check_seek_accuracy("examples/data/drop.wav");
+    }
+
+    #[test]
+    fn test_seek_accuracy_ogg() {
+        check_seek_accuracy("examples/data/waterfall.ogg");
+    }
+}
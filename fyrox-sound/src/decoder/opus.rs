@@ -0,0 +1,318 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Opus decoder. Symphonia is able to demux Opus streams from Ogg and WebM (Matroska) containers,
+//! but it does not have an Opus codec, so the decoding is done using `libopus`.
+
+use audiopus::{
+    coder::{Decoder as LibOpusDecoder, GenericCtl},
+    packet::Packet as OpusPacket,
+    Channels, MutSignals, SampleRate,
+};
+use symphonia::core::{
+    audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec},
+    codecs::{
+        CodecDescriptor, CodecParameters, Decoder, DecoderOptions, FinalizeResult, CODEC_TYPE_OPUS,
+    },
+    errors::{decode_error, unsupported_error, Error, Result},
+    formats::Packet,
+    support_codec,
+};
+
+/// Opus streams are always decoded at 48 kHz.
+const SAMPLE_RATE: u32 = 48_000;
+
+/// Maximum duration of an Opus packet is 120 ms.
+const MAX_FRAMES_PER_PACKET: usize = SAMPLE_RATE as usize * 120 / 1000;
+
+pub(crate) struct OpusDecoder {
+    params: CodecParameters,
+    decoder: LibOpusDecoder,
+    channel_count: usize,
+    interleaved: Vec<f32>,
+    buffer: AudioBuffer<f32>,
+}
+
+// Symphonia requires decoders to be `Send + Sync`. The libopus decoder is `Send`, but not `Sync`,
+// because some of its methods (`set_gain`, `gain`, etc.) call into libopus through a shared
+// reference.
+//
+// SAFETY: The libopus decoder is a private field, that is accessed only from the methods that take
+// `&mut self` (`decode`, `reset`). The only access through a shared reference is `set_gain` in
+// `make_decoder`, which is done before the decoder is moved into the wrapper. `&self` methods of
+// the wrapper (`codec_params`, `last_decoded`) never touch the libopus state, so it can't be
+// accessed from multiple threads at once.
+unsafe impl Sync for OpusDecoder {}
+
+impl OpusDecoder {
+    fn make_decoder(channel_count: usize, gain: i32) -> Result<LibOpusDecoder> {
+        let channels = match channel_count {
+            1 => Channels::Mono,
+            2 => Channels::Stereo,
+            _ => return unsupported_error("opus: only mono and stereo streams are supported"),
+        };
+        let decoder = LibOpusDecoder::new(SampleRate::Hz48000, channels)
+            .map_err(|_| Error::DecodeError("opus: unable to create decoder"))?;
+        if gain != 0 {
+            decoder
+                .set_gain(gain)
+                .map_err(|_| Error::DecodeError("opus: invalid output gain"))?;
+        }
+        Ok(decoder)
+    }
+
+    /// Fetches output gain (Q7.8 in dB) from the identification header (`OpusHead`), that is
+    /// stored in the extra data of the codec.
+    fn output_gain(params: &CodecParameters) -> i32 {
+        match params.extra_data.as_deref() {
+            Some(head) if head.len() >= 18 && head.starts_with(b"OpusHead") => {
+                i16::from_le_bytes([head[16], head[17]]) as i32
+            }
+            _ => 0,
+        }
+    }
+}
+
+impl Decoder for OpusDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        if params.codec != CODEC_TYPE_OPUS {
+            return unsupported_error("opus: invalid codec type");
+        }
+
+        let Some(channels) = params.channels else {
+            return unsupported_error("opus: unknown channel layout");
+        };
+        let channel_count = channels.count();
+
+        let decoder = Self::make_decoder(channel_count, Self::output_gain(params))?;
+
+        Ok(Self {
+            params: params.clone(),
+            decoder,
+            channel_count,
+            interleaved: vec![0.0; MAX_FRAMES_PER_PACKET * channel_count],
+            buffer: AudioBuffer::new(
+                MAX_FRAMES_PER_PACKET as u64,
+                SignalSpec::new(SAMPLE_RATE, channels),
+            ),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor]
+    where
+        Self: Sized,
+    {
+        &[support_codec!(CODEC_TYPE_OPUS, "opus", "Opus")]
+    }
+
+    fn reset(&mut self) {
+        let _ = self.decoder.reset_state();
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        self.buffer.clear();
+
+        let input = OpusPacket::try_from(packet.buf())
+            .map_err(|_| Error::DecodeError("opus: empty packet"))?;
+        let output = MutSignals::try_from(self.interleaved.as_mut_slice())
+            .map_err(|_| Error::DecodeError("opus: invalid output buffer"))?;
+        let frames = match self.decoder.decode_float(Some(input), output, false) {
+            Ok(frames) => frames,
+            Err(_) => return decode_error("opus: malformed packet"),
+        };
+
+        self.buffer.render_reserved(Some(frames));
+        for channel in 0..self.channel_count {
+            for (i, sample) in self.buffer.chan_mut(channel).iter_mut().enumerate() {
+                *sample = self.interleaved[i * self.channel_count + channel];
+            }
+        }
+        // Remove pre-skip and end padding.
+        self.buffer
+            .trim(packet.trim_start() as usize, packet.trim_end() as usize);
+
+        Ok(self.buffer.as_audio_buffer_ref())
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        Default::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buffer.as_audio_buffer_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{buffer::DataSource, decoder::Decoder};
+    use audiopus::{coder::Encoder, Application, Bitrate, Channels, SampleRate};
+
+    const FRAME_SIZE: usize = 960;
+    const CHANNEL_COUNT: usize = 2;
+
+    fn ogg_crc(data: &[u8]) -> u32 {
+        let mut crc = 0u32;
+        for byte in data {
+            crc ^= (*byte as u32) << 24;
+            for _ in 0..8 {
+                crc = if crc & 0x8000_0000 != 0 {
+                    (crc << 1) ^ 0x04c1_1db7
+                } else {
+                    crc << 1
+                };
+            }
+        }
+        crc
+    }
+
+    /// Writes an Ogg page with a single packet.
+    fn write_page(out: &mut Vec<u8>, sequence: u32, granule: u64, flags: u8, packet: &[u8]) {
+        let start = out.len();
+        out.extend_from_slice(b"OggS");
+        out.push(0);
+        out.push(flags);
+        out.extend_from_slice(&granule.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&sequence.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        let mut lacing = vec![255u8; packet.len() / 255];
+        lacing.push((packet.len() % 255) as u8);
+        out.push(lacing.len() as u8);
+        out.extend_from_slice(&lacing);
+        out.extend_from_slice(packet);
+        let crc = ogg_crc(&out[start..]);
+        out[start + 22..start + 26].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    /// Stereo signal with different tones in each channel.
+    fn signal(frame_count: usize) -> Vec<f32> {
+        (0..frame_count)
+            .flat_map(|i| {
+                let t = i as f32 / 48_000.0;
+                [
+                    0.5 * (std::f32::consts::TAU * 440.0 * t).sin(),
+                    0.25 * (std::f32::consts::TAU * 660.0 * t).sin(),
+                ]
+            })
+            .collect()
+    }
+
+    /// Encodes the signal into an Ogg Opus stream.
+    fn encode(samples: &[f32]) -> Vec<u8> {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        encoder
+            .set_bitrate(Bitrate::BitsPerSecond(256_000))
+            .unwrap();
+        let pre_skip = encoder.lookahead().unwrap() as u64;
+
+        let mut head = b"OpusHead".to_vec();
+        head.push(1);
+        head.push(CHANNEL_COUNT as u8);
+        head.extend_from_slice(&(pre_skip as u16).to_le_bytes());
+        head.extend_from_slice(&48_000u32.to_le_bytes());
+        head.extend_from_slice(&0i16.to_le_bytes());
+        head.push(0);
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend_from_slice(&0u32.to_le_bytes());
+        tags.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut out = Vec::new();
+        write_page(&mut out, 0, 0, 0x02, &head);
+        write_page(&mut out, 1, 0, 0, &tags);
+
+        // The encoder delays the signal by `pre_skip` samples, so the input is padded to get the
+        // whole signal out of it.
+        let frame_count = samples.len() / CHANNEL_COUNT;
+        let mut input = samples.to_vec();
+        let padded_len = (frame_count + pre_skip as usize).div_ceil(FRAME_SIZE) * FRAME_SIZE;
+        input.resize(padded_len * CHANNEL_COUNT, 0.0);
+
+        let chunks = input.chunks(FRAME_SIZE * CHANNEL_COUNT);
+        let chunk_count = chunks.len();
+        let mut packet = vec![0; 4000];
+        for (i, chunk) in chunks.enumerate() {
+            let size = encoder.encode_float(chunk, &mut packet).unwrap();
+            let last = i + 1 == chunk_count;
+            // Granule position counts decoded samples, including the pre-skip ones.
+            let granule = if last {
+                pre_skip + frame_count as u64
+            } else {
+                ((i + 1) * FRAME_SIZE) as u64
+            };
+            let flags = if last { 0x04 } else { 0 };
+            write_page(&mut out, i as u32 + 2, granule, flags, &packet[..size]);
+        }
+        out
+    }
+
+    fn relative_error(actual: &[f32], expected: &[f32]) -> f32 {
+        let error = actual
+            .iter()
+            .zip(expected)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f32>();
+        let energy = expected.iter().map(|s| s * s).sum::<f32>();
+        (error / energy).sqrt()
+    }
+
+    #[test]
+    fn test_decode() {
+        let frame_count = 48_000;
+        let expected = signal(frame_count);
+        let decoder = Decoder::new(DataSource::from_memory(encode(&expected))).unwrap();
+        assert_eq!(decoder.channel_count, CHANNEL_COUNT);
+        assert_eq!(decoder.sample_rate, 48_000);
+        assert_eq!(decoder.channel_duration_in_samples, frame_count);
+
+        let samples = decoder.into_samples();
+        assert_eq!(samples.len(), expected.len());
+        // Pre-skip must be removed, otherwise the decoded signal will be shifted.
+        assert!(relative_error(&samples, &expected) < 0.05);
+    }
+
+    #[test]
+    fn test_seek() {
+        let frame_count = 48_000;
+        let data = encode(&signal(frame_count));
+        let mut decoder = Decoder::new(DataSource::from_memory(data)).unwrap();
+        let samples = decoder.by_ref().collect::<Vec<_>>();
+
+        for frame in [0, 1, 4095, 12345, frame_count / 2, frame_count - 64] {
+            decoder.frame_seek(frame as u64).unwrap();
+            let expected = &samples[frame * CHANNEL_COUNT..(frame + 64) * CHANNEL_COUNT];
+            let actual = decoder.by_ref().take(expected.len()).collect::<Vec<_>>();
+            assert_eq!(actual.len(), expected.len(), "seek to {frame} is truncated");
+            // The state of libopus decoder is restored only approximately during preroll, so
+            // the samples are slightly different from the continuously decoded ones.
+            for (a, b) in actual.iter().zip(expected) {
+                assert!((a - b).abs() < 1.0e-2, "seek to {frame} is inaccurate");
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Seek table is used to find exact position of a packet in a stream after seeking. Some formats
+//! (VBR MP3 without a table of contents, for example) does not store enough information for
+//! fast and accurate seeking - fast (coarse) seeking estimates the position using average bitrate
+//! and the timestamps reported after such seeking are inaccurate too. The seek table is built
+//! once, when the stream is scanned to calculate its duration, and then it is used to identify
+//! packets by their content.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+use symphonia::core::formats::Packet;
+
+/// Location of a single packet in the stream.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct SeekPoint {
+    /// Index of the first sample (per channel) of the packet, after trimming.
+    pub frame: u64,
+    /// Amount of samples (per channel) that should be discarded from the start of the decoded
+    /// packet.
+    pub trim_start: u32,
+    /// Amount of samples (per channel) that should be discarded from the end of the decoded
+    /// packet.
+    pub trim_end: u32,
+    /// Hash of the packet data.
+    pub hash: u64,
+}
+
+#[derive(Default, Debug)]
+pub(crate) struct SeekTable {
+    points: Vec<SeekPoint>,
+}
+
+pub(crate) fn packet_hash(packet: &Packet) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    packet.buf().hash(&mut hasher);
+    hasher.finish()
+}
+
+impl SeekTable {
+    pub fn push(&mut self, point: SeekPoint) {
+        self.points.push(point)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&SeekPoint> {
+        self.points.get(index)
+    }
+
+    /// Finds an index of a point, that corresponds to the given packet. The `frame_hint` is an
+    /// estimated position of the packet; it is used to pick the right point when there are
+    /// multiple packets with the same content (silence, for example).
+    pub fn find(&self, packet: &Packet, frame_hint: u64) -> Option<usize> {
+        let hash = packet_hash(packet);
+        self.points
+            .iter()
+            .enumerate()
+            .filter(|(_, point)| point.hash == hash)
+            .min_by_key(|(_, point)| point.frame.abs_diff(frame_hint))
+            .map(|(index, _)| index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        let packets =
+            [b"a", b"b", b"a", b"c"].map(|data| Packet::new_from_slice(0, 0, 0, data.as_slice()));
+        let mut table = SeekTable::default();
+        for (i, packet) in packets.iter().enumerate() {
+            table.push(SeekPoint {
+                frame: i as u64 * 100,
+                trim_start: 0,
+                trim_end: 0,
+                hash: packet_hash(packet),
+            });
+        }
+        assert_eq!(table.find(&packets[3], 0), Some(3));
+        // Duplicates are resolved using the hint.
+        assert_eq!(table.find(&packets[0], 20), Some(0));
+        assert_eq!(table.find(&packets[0], 180), Some(2));
+        let unknown = Packet::new_from_slice(0, 0, 0, b"d");
+        assert_eq!(table.find(&unknown, 0), None);
+    }
+}
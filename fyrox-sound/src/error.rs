@@ -69,6 +69,22 @@ pub enum SoundError {
 
     /// A buffer is not loaded yet, consider to `await` it before use.
     BufferIsNotLoaded,
+
+    /// A sound event is not loaded yet or failed to load.
+    EventIsNotLoaded,
+
+    /// A sound event has nothing to play in its current state (for example, a switch has no
+    /// matching case or a clip has no buffer).
+    EventIsEmpty,
+
+    /// A sound event was played again before its cooldown has expired.
+    EventCooldown,
+
+    /// A sound event or an audio bus has reached its voice limit and no voice could be stolen.
+    VoiceLimitReached,
+
+    /// An error occurred while writing a WAV file.
+    WavError(hound::Error),
 }
 
 impl From<std::io::Error> for SoundError {
@@ -89,6 +105,12 @@ impl From<symphonia::core::errors::Error> for SoundError {
     }
 }
 
+impl From<hound::Error> for SoundError {
+    fn from(e: hound::Error) -> Self {
+        SoundError::WavError(e)
+    }
+}
+
 impl Display for SoundError {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         match self {
@@ -109,6 +131,11 @@ impl Display for SoundError {
             SoundError::DecoderError(de) => write!(f, "internal decoder error: {de:?}"),
             SoundError::BufferFailedToLoad => write!(f, "a buffer failed to load"),
             SoundError::BufferIsNotLoaded => write!(f, "a buffer is not loaded yet"),
+            SoundError::EventIsNotLoaded => write!(f, "a sound event is not loaded"),
+            SoundError::EventIsEmpty => write!(f, "a sound event has nothing to play"),
+            SoundError::EventCooldown => write!(f, "a sound event is on cooldown"),
+            SoundError::VoiceLimitReached => write!(f, "voice limit is reached"),
+            SoundError::WavError(e) => write!(f, "wav writer error: {e}"),
         }
     }
 }
@@ -0,0 +1,592 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Sound events allow you to describe audio behaviour declaratively, without writing any code.
+//!
+//! # Overview
+//!
+//! [`SoundEvent`] is a resource, that contains a tree of containers ([`SoundContainer`]). Every
+//! time when an event is played (see [`crate::context::State::play_event`]), the tree is traversed
+//! from the root and exactly one [`SoundClip`] is selected. Containers define how the clip is
+//! selected:
+//!
+//! - [`RandomContainer`] - picks a random child (with optional weights) and can avoid repeating
+//!   recently played children. Useful for footsteps, gunshots, impacts, etc.
+//! - [`SequenceContainer`] - plays its children one after another, each time the event is played.
+//! - [`SwitchContainer`] - picks a child by the current value of a named switch (see
+//!   [`crate::context::State::set_switch`]). For example, a footstep event could use a `Surface`
+//!   switch with `Grass`, `Wood`, `Metal` values.
+//!
+//! On top of that, every event can randomize volume and pitch of the played sound, has a cooldown
+//! and a limit of simultaneously playing voices. Audio buses could also limit the amount of voices,
+//! see [`crate::bus::AudioBus::set_max_voices`]. When a limit is reached, the sound with the lowest
+//! priority (or the oldest one) is stopped to free a voice for the new one.
+
+use crate::{buffer::SoundBufferResource, bus::AudioBusGraph, error::SoundError};
+use fyrox_core::{
+    io::FileError,
+    numeric_range::RangeExt,
+    rand::Rng,
+    reflect::prelude::*,
+    visitor::{error::VisitError, prelude::*},
+};
+use fyrox_resource::{io::ResourceIo, manager::ResourceManager, Resource, ResourceData};
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt::{Display, Formatter},
+    ops::Range,
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+use strum_macros::{AsRefStr, EnumString, VariantNames};
+
+pub mod loader;
+
+/// A leaf of a sound event, it plays a single sound buffer.
+#[derive(Clone, Debug, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "8e0f8d52-3c59-4c7a-9b43-2d6f3a1c7e94")]
+pub struct SoundClip {
+    /// A buffer to play.
+    pub buffer: Option<SoundBufferResource>,
+
+    /// Gain multiplier of the clip. It is multiplied with randomized volume of the event.
+    #[reflect(min_value = 0.0, step = 0.05)]
+    pub gain: f32,
+
+    /// Pitch multiplier of the clip. It is multiplied with randomized pitch of the event.
+    #[reflect(min_value = 0.0, step = 0.05)]
+    pub pitch: f32,
+}
+
+impl Default for SoundClip {
+    fn default() -> Self {
+        Self {
+            buffer: None,
+            gain: 1.0,
+            pitch: 1.0,
+        }
+    }
+}
+
+impl SoundClip {
+    /// Creates a new clip that plays the given buffer.
+    pub fn new(buffer: SoundBufferResource) -> Self {
+        Self {
+            buffer: Some(buffer),
+            ..Default::default()
+        }
+    }
+}
+
+/// Picks a random child every time when the event is played.
+#[derive(Clone, Debug, Default, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "a2d4c5b0-6f1e-4b8a-8c3d-5e7f9a0b1c2d")]
+pub struct RandomContainer {
+    /// Children of the container.
+    pub children: Vec<SoundContainer>,
+
+    /// Weights of the children. A child with greater weight is picked more often. Children without
+    /// a weight (when there are fewer weights than children) have weight of 1.0.
+    pub weights: Vec<f32>,
+
+    /// Amount of recently played children that won't be picked again. It allows you to avoid
+    /// playing the same variation twice in a row. It is clamped to the amount of children minus
+    /// one, so the container can always pick something.
+    pub avoid_repeating_last: usize,
+}
+
+impl RandomContainer {
+    fn pick<R: Rng>(&self, state: &mut ContainerState, rng: &mut R) -> usize {
+        let count = self.children.len();
+        let history_len = self.avoid_repeating_last.min(count.saturating_sub(1));
+        while state.history.len() > history_len {
+            state.history.pop_front();
+        }
+
+        let weight = |i: usize| -> f32 {
+            if state.history.contains(&i) {
+                0.0
+            } else {
+                self.weights.get(i).copied().unwrap_or(1.0).max(0.0)
+            }
+        };
+        let total = (0..count).map(weight).sum::<f32>();
+        let index = if total > 0.0 {
+            let mut value = rng.gen_range(0.0..total);
+            (0..count)
+                .find(|&i| {
+                    let w = weight(i);
+                    if w > 0.0 && value < w {
+                        true
+                    } else {
+                        value -= w;
+                        false
+                    }
+                })
+                // Floating point error could make us miss the last candidate.
+                .unwrap_or_else(|| (0..count).rev().find(|&i| weight(i) > 0.0).unwrap_or(0))
+        } else {
+            // Every remaining child has zero weight, pick any of them.
+            let candidates = (0..count)
+                .filter(|i| !state.history.contains(i))
+                .collect::<Vec<_>>();
+            candidates[rng.gen_range(0..candidates.len())]
+        };
+
+        if history_len > 0 {
+            if state.history.len() == history_len {
+                state.history.pop_front();
+            }
+            state.history.push_back(index);
+        }
+
+        index
+    }
+}
+
+/// Plays its children one after another, every time when the event is played. After the last
+/// child the sequence starts over.
+#[derive(Clone, Debug, Default, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "4f6b2e8a-1d3c-4a5b-9e7f-0c1d2e3f4a5b")]
+pub struct SequenceContainer {
+    /// Children of the container.
+    pub children: Vec<SoundContainer>,
+}
+
+/// A case of a [`SwitchContainer`].
+#[derive(Clone, Debug, Default, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "7c8d9e0f-2a3b-4c5d-8e6f-1a2b3c4d5e6f")]
+pub struct SwitchCase {
+    /// A value of the switch, that selects this case.
+    pub value: String,
+
+    /// A container that will be played when the case is selected.
+    pub container: SoundContainer,
+}
+
+/// Picks a child by the current value of a named switch. Switches are set in the sound context,
+/// see [`crate::context::State::set_switch`].
+#[derive(Clone, Debug, Default, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "b3c4d5e6-7f8a-4b9c-8d0e-2f3a4b5c6d7e")]
+pub struct SwitchContainer {
+    /// Name of the switch.
+    pub switch: String,
+
+    /// Cases of the switch.
+    pub cases: Vec<SwitchCase>,
+
+    /// Index of a case that will be played if the switch has no value or no case matches the
+    /// value of the switch. If not set, nothing will be played in this case.
+    pub default_case: Option<usize>,
+}
+
+/// A node of a sound event tree. See [module docs](self) for more info.
+#[derive(Clone, Debug, PartialEq, Visit, Reflect, AsRefStr, EnumString, VariantNames)]
+#[reflect(type_uuid = "e5f6a7b8-9c0d-4e1f-a2b3-c4d5e6f7a8b9")]
+pub enum SoundContainer {
+    /// See [`SoundClip`] docs.
+    Clip(SoundClip),
+    /// See [`RandomContainer`] docs.
+    Random(RandomContainer),
+    /// See [`SequenceContainer`] docs.
+    Sequence(SequenceContainer),
+    /// See [`SwitchContainer`] docs.
+    Switch(SwitchContainer),
+}
+
+impl Default for SoundContainer {
+    fn default() -> Self {
+        Self::Clip(Default::default())
+    }
+}
+
+// Runtime state of a container, it is stored in the sound context.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct ContainerState {
+    next: usize,
+    history: VecDeque<usize>,
+}
+
+// Containers are identified by their path (indices of children) from the root.
+type ContainerPath = Vec<usize>;
+
+impl SoundContainer {
+    fn select<R: Rng>(
+        &self,
+        path: &mut ContainerPath,
+        states: &mut HashMap<ContainerPath, ContainerState>,
+        switches: &HashMap<String, String>,
+        rng: &mut R,
+    ) -> Option<SoundClip> {
+        let (index, child) = match self {
+            SoundContainer::Clip(clip) => return Some(clip.clone()),
+            SoundContainer::Random(random) => {
+                if random.children.is_empty() {
+                    return None;
+                }
+                let state = states.entry(path.clone()).or_default();
+                let index = random.pick(state, rng);
+                (index, &random.children[index])
+            }
+            SoundContainer::Sequence(sequence) => {
+                if sequence.children.is_empty() {
+                    return None;
+                }
+                let state = states.entry(path.clone()).or_default();
+                let index = state.next % sequence.children.len();
+                state.next = (index + 1) % sequence.children.len();
+                (index, &sequence.children[index])
+            }
+            SoundContainer::Switch(switch) => {
+                let value = switches.get(&switch.switch);
+                let index = switch
+                    .cases
+                    .iter()
+                    .position(|case| Some(&case.value) == value)
+                    .or(switch.default_case)?;
+                (index, &switch.cases.get(index)?.container)
+            }
+        };
+
+        path.push(index);
+        let clip = child.select(path, states, switches, rng);
+        path.pop();
+        clip
+    }
+}
+
+/// Sound event is a resource that describes how to play a sound. See [module docs](self) for more
+/// info.
+#[derive(Clone, Debug, PartialEq, Visit, Reflect)]
+#[reflect(type_uuid = "0d1e2f3a-4b5c-4d6e-8f7a-9b0c1d2e3f4a")]
+pub struct SoundEvent {
+    /// Root container of the event.
+    pub root: SoundContainer,
+
+    /// A range of random volume (gain) of the played sounds.
+    pub volume: Range<f32>,
+
+    /// A range of random pitch of the played sounds.
+    pub pitch: Range<f32>,
+
+    /// Minimum amount of time (in seconds) between two consecutive plays of the event. Attempts to
+    /// play the event during cooldown are ignored.
+    #[reflect(min_value = 0.0, step = 0.01)]
+    pub cooldown: f32,
+
+    /// Maximum amount of simultaneously playing voices of the event. `None` - unlimited.
+    pub max_voices: Option<usize>,
+
+    /// Defines what happens when the event has reached its voice limit. If `true`, the oldest voice
+    /// of the event will be stopped, otherwise the new voice won't be played.
+    pub steal_voices: bool,
+
+    /// Priority of the played sounds. It is used to decide which sound should be stopped, when an
+    /// audio bus has reached its voice limit. See [`crate::source::SoundSource::set_priority`].
+    pub priority: i32,
+
+    /// Name of an audio bus, that will be used by the played sounds.
+    pub bus: String,
+
+    /// Spatial blend factor of the played sounds. See
+    /// [`crate::source::SoundSource::set_spatial_blend`].
+    #[reflect(min_value = 0.0, max_value = 1.0, step = 0.05)]
+    pub spatial_blend: f32,
+}
+
+impl Default for SoundEvent {
+    fn default() -> Self {
+        Self {
+            root: Default::default(),
+            volume: 1.0..1.0,
+            pitch: 1.0..1.0,
+            cooldown: 0.0,
+            max_voices: None,
+            steal_voices: true,
+            priority: 0,
+            bus: AudioBusGraph::PRIMARY_BUS.to_string(),
+            spatial_blend: 1.0,
+        }
+    }
+}
+
+/// A set of parameters of a sound selected by [`SoundEvent`].
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SelectedSound {
+    pub buffer: SoundBufferResource,
+    pub gain: f32,
+    pub pitch: f64,
+}
+
+impl SoundEvent {
+    /// Loads a sound event from the given file.
+    pub async fn from_file(
+        path: &Path,
+        io: &dyn ResourceIo,
+        resource_manager: ResourceManager,
+    ) -> Result<Self, SoundEventResourceError> {
+        let bytes = io.load_file(path).await?;
+        let mut visitor = Visitor::load_from_memory(&bytes)?;
+        visitor.blackboard.register(Arc::new(resource_manager));
+        let mut event = SoundEvent::default();
+        event.visit("SoundEvent", &mut visitor)?;
+        Ok(event)
+    }
+
+    // Checks cooldown, selects a clip and randomizes its parameters.
+    pub(crate) fn select<R: Rng>(
+        &self,
+        state: &mut SoundEventState,
+        switches: &HashMap<String, String>,
+        rng: &mut R,
+    ) -> Result<SelectedSound, SoundError> {
+        if let Some(last_play_time) = state.last_play_time {
+            if fyrox_core::instant::Instant::now() - last_play_time
+                < Duration::from_secs_f32(self.cooldown.max(0.0))
+            {
+                return Err(SoundError::EventCooldown);
+            }
+        }
+
+        let clip = self
+            .root
+            .select(&mut Vec::new(), &mut state.containers, switches, rng)
+            .ok_or(SoundError::EventIsEmpty)?;
+        let buffer = clip.buffer.ok_or(SoundError::EventIsEmpty)?;
+
+        Ok(SelectedSound {
+            buffer,
+            gain: clip.gain * self.volume.random(rng),
+            pitch: (clip.pitch * self.pitch.random(rng)) as f64,
+        })
+    }
+}
+
+/// Runtime state of a sound event. It is stored in the sound context.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct SoundEventState {
+    containers: HashMap<ContainerPath, ContainerState>,
+    pub last_play_time: Option<fyrox_core::instant::Instant>,
+}
+
+impl ResourceData for SoundEvent {
+    fn save(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut visitor = Visitor::new();
+        self.visit("SoundEvent", &mut visitor)?;
+        visitor.save_ascii_to_file(path)?;
+        Ok(())
+    }
+
+    fn can_be_saved(&self) -> bool {
+        true
+    }
+
+    fn try_clone_box(&self) -> Option<Box<dyn ResourceData>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// An error that may occur during sound event resource loading.
+#[derive(Debug)]
+pub enum SoundEventResourceError {
+    /// An i/o error has occurred.
+    Io(FileError),
+
+    /// An error that may occur due to version incompatibilities.
+    Visit(VisitError),
+}
+
+impl Display for SoundEventResourceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(v) => {
+                write!(f, "A file load error has occurred {v:?}")
+            }
+            Self::Visit(v) => {
+                write!(
+                    f,
+                    "An error that may occur due to version incompatibilities. {v:?}"
+                )
+            }
+        }
+    }
+}
+
+impl From<FileError> for SoundEventResourceError {
+    fn from(e: FileError) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<VisitError> for SoundEventResourceError {
+    fn from(e: VisitError) -> Self {
+        Self::Visit(e)
+    }
+}
+
+/// Sound event resource.
+pub type SoundEventResource = Resource<SoundEvent>;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        buffer::{DataSource, SoundBufferResourceExtension},
+        bus::AudioBus,
+        context::SoundContext,
+        source::Status,
+    };
+    use fyrox_core::{algebra::Vector3, rand::thread_rng};
+
+    fn clip(value: f32) -> SoundContainer {
+        let buffer = SoundBufferResource::new_generic(DataSource::Raw {
+            sample_rate: 100,
+            channel_count: 1,
+            samples: vec![value; 1000],
+        })
+        .unwrap();
+        SoundContainer::Clip(SoundClip {
+            buffer: Some(buffer),
+            gain: value,
+            pitch: 1.0,
+        })
+    }
+
+    fn select(event: &SoundEvent, state: &mut SoundEventState, switches: &[(&str, &str)]) -> f32 {
+        let switches = switches
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        event
+            .select(state, &switches, &mut thread_rng())
+            .unwrap()
+            .gain
+    }
+
+    #[test]
+    fn test_containers() {
+        let mut state = SoundEventState::default();
+        let sequence = SoundEvent {
+            root: SoundContainer::Sequence(SequenceContainer {
+                children: vec![clip(1.0), clip(2.0), clip(3.0)],
+            }),
+            ..Default::default()
+        };
+        let played = (0..4)
+            .map(|_| select(&sequence, &mut state, &[]))
+            .collect::<Vec<_>>();
+        assert_eq!(played, [1.0, 2.0, 3.0, 1.0]);
+
+        let mut state = SoundEventState::default();
+        let random = SoundEvent {
+            root: SoundContainer::Random(RandomContainer {
+                children: vec![clip(1.0), clip(2.0), clip(3.0)],
+                weights: vec![],
+                avoid_repeating_last: 2,
+            }),
+            ..Default::default()
+        };
+        let played = (0..30)
+            .map(|_| select(&random, &mut state, &[]))
+            .collect::<Vec<_>>();
+        for window in played.windows(3) {
+            assert!(window[0] != window[1] && window[1] != window[2] && window[0] != window[2]);
+        }
+
+        let mut state = SoundEventState::default();
+        let switch = SoundEvent {
+            root: SoundContainer::Switch(SwitchContainer {
+                switch: "Surface".to_string(),
+                cases: vec![
+                    SwitchCase {
+                        value: "Grass".to_string(),
+                        container: clip(1.0),
+                    },
+                    SwitchCase {
+                        value: "Wood".to_string(),
+                        container: clip(2.0),
+                    },
+                ],
+                default_case: Some(0),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(select(&switch, &mut state, &[("Surface", "Wood")]), 2.0);
+        assert_eq!(select(&switch, &mut state, &[("Surface", "Metal")]), 1.0);
+        assert_eq!(select(&switch, &mut state, &[]), 1.0);
+    }
+
+    #[test]
+    fn test_event_cooldown_and_voice_limits() {
+        let context = SoundContext::new();
+        let mut state = context.state();
+
+        let event = SoundEventResource::new_embedded(SoundEvent {
+            root: clip(1.0),
+            cooldown: 1000.0,
+            ..Default::default()
+        });
+        assert!(state.play_event(&event, Vector3::default()).is_ok());
+        assert!(matches!(
+            state.play_event(&event, Vector3::default()),
+            Err(SoundError::EventCooldown)
+        ));
+
+        // Event limit steals the oldest voice.
+        let event = SoundEventResource::new_embedded(SoundEvent {
+            root: clip(1.0),
+            max_voices: Some(2),
+            ..Default::default()
+        });
+        let first = state.play_event(&event, Vector3::default()).unwrap();
+        state
+            .source_mut(first)
+            .set_playback_time(Duration::from_secs(1));
+        let second = state.play_event(&event, Vector3::default()).unwrap();
+        let third = state.play_event(&event, Vector3::default()).unwrap();
+        assert_eq!(state.source(first).status(), Status::Stopped);
+        assert_eq!(state.source(second).status(), Status::Playing);
+        assert_eq!(state.source(third).status(), Status::Playing);
+
+        // Bus limit steals voices with lower priority and rejects voices with lower priority.
+        let mut bus = AudioBus::new("Limited".to_string());
+        bus.set_max_voices(Some(1));
+        let primary = state.bus_graph_ref().primary_bus_handle();
+        state.bus_graph_mut().add_bus(bus, primary);
+        let event = |priority| {
+            SoundEventResource::new_embedded(SoundEvent {
+                root: clip(1.0),
+                priority,
+                bus: "Limited".to_string(),
+                ..Default::default()
+            })
+        };
+        let low = state.play_event(&event(0), Vector3::default()).unwrap();
+        let high = state.play_event(&event(1), Vector3::default()).unwrap();
+        assert_eq!(state.source(low).status(), Status::Stopped);
+        assert_eq!(state.source(high).status(), Status::Playing);
+        assert!(matches!(
+            state.play_event(&event(0), Vector3::default()),
+            Err(SoundError::VoiceLimitReached)
+        ));
+    }
+}
@@ -96,7 +96,9 @@ pub mod dsp;
 pub mod effects;
 pub mod engine;
 pub mod error;
+pub mod event;
 pub mod listener;
+pub mod offline;
 pub mod renderer;
 pub mod source;
 
@@ -0,0 +1,368 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Offline rendering module.
+//!
+//! # Overview
+//!
+//! Offline renderer advances a [`SoundContext`] by an exact number of samples without an output device and
+//! collects the mixed signal (and, optionally, the signal of each audio bus, so called stems). It could be
+//! used to write deterministic audio regression tests that do not require a sound card or to export audio
+//! of recorded cutscenes.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use fyrox_sound::{context::SoundContext, offline::OfflineRenderer};
+//! let context = SoundContext::new();
+//!
+//! // Add some sources to the context here.
+//!
+//! let mut renderer = OfflineRenderer::new(44100).with_stems(true);
+//!
+//! // Render 5 seconds of audio.
+//! let output = renderer.render(&context, 5 * 44100);
+//!
+//! output.write_wav("mix.wav").unwrap();
+//! output.write_stems("stems").unwrap();
+//! ```
+//!
+//! # Important notes
+//!
+//! Make sure that the context is not registered in a [`crate::engine::SoundEngine`] with an output device,
+//! otherwise the context will be advanced by both the output device and the offline renderer.
+
+use crate::{bus::AudioBus, context::SoundContext, error::SoundError};
+use fyrox_core::pool::Handle;
+use std::path::Path;
+
+/// Signal of a single audio bus, collected by the [`OfflineRenderer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioStem {
+    /// A handle of the audio bus.
+    pub bus: Handle<AudioBus>,
+    /// Name of the audio bus at the moment when it was first rendered.
+    pub name: String,
+    /// Interleaved stereo samples of the bus.
+    pub samples: Vec<(f32, f32)>,
+}
+
+/// Result of offline rendering.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OfflineRenderOutput {
+    /// Sample rate of the rendered signal.
+    pub sample_rate: u32,
+    /// Mixed stereo signal of the context, exactly the same as it would be sent to an output device.
+    pub mix: Vec<(f32, f32)>,
+    /// Signal of each audio bus. Empty if stems capturing is disabled.
+    pub stems: Vec<AudioStem>,
+}
+
+impl OfflineRenderOutput {
+    /// Tries to find a stem of an audio bus with the given name.
+    pub fn stem<S: AsRef<str>>(&self, name: S) -> Option<&AudioStem> {
+        self.stems.iter().find(|stem| stem.name == name.as_ref())
+    }
+
+    /// Writes the mixed signal to a 32-bit floating point stereo WAV file.
+    pub fn write_wav<P: AsRef<Path>>(&self, path: P) -> Result<(), SoundError> {
+        write_wav(path.as_ref(), self.sample_rate, &self.mix)
+    }
+
+    /// Writes each stem to a separate WAV file in the given directory. Files are named after the audio
+    /// buses (`<bus name>.wav`). The directory will be created if it does not exist.
+    pub fn write_stems<P: AsRef<Path>>(&self, dir: P) -> Result<(), SoundError> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        for stem in self.stems.iter() {
+            write_wav(
+                &dir.join(format!("{}.wav", stem.name)),
+                self.sample_rate,
+                &stem.samples,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn write_wav(path: &Path, sample_rate: u32, samples: &[(f32, f32)]) -> Result<(), SoundError> {
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for &(left, right) in samples {
+        writer.write_sample(left)?;
+        writer.write_sample(right)?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Offline renderer advances a sound context by an exact number of samples and collects its output. The
+/// context is always rendered in blocks of the same size as the output device uses, the samples of a block
+/// that were not requested yet are kept by the renderer and returned first on the next call of
+/// [`Self::render`]. This means that consecutive calls produce continuous signal, no matter how many samples
+/// were requested on each call. See [module docs](self) for more info.
+#[derive(Debug)]
+pub struct OfflineRenderer {
+    sample_rate: u32,
+    capture_stems: bool,
+    block: Vec<(f32, f32)>,
+    block_stems: Vec<AudioStem>,
+    position: usize,
+    rendered_samples: u64,
+}
+
+impl OfflineRenderer {
+    /// Creates new offline renderer with the given sample rate. Stems capturing is disabled by default.
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            capture_stems: false,
+            block: Default::default(),
+            block_stems: Default::default(),
+            // Forces the renderer to render a new block on first request.
+            position: 0,
+            rendered_samples: 0,
+        }
+    }
+
+    /// Enables or disables capturing of the signal of each audio bus.
+    pub fn with_stems(mut self, capture_stems: bool) -> Self {
+        self.capture_stems = capture_stems;
+        self
+    }
+
+    /// Returns sample rate of the renderer.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Returns `true` if the renderer captures the signal of each audio bus.
+    pub fn is_capturing_stems(&self) -> bool {
+        self.capture_stems
+    }
+
+    /// Returns total amount of samples (per channel) returned by the renderer so far.
+    pub fn rendered_samples(&self) -> u64 {
+        self.rendered_samples
+    }
+
+    /// Returns total duration of the signal returned by the renderer so far, in seconds.
+    pub fn rendered_time(&self) -> f64 {
+        self.rendered_samples as f64 / self.sample_rate as f64
+    }
+
+    /// Advances the context by the given amount of samples (per channel) and returns the rendered signal.
+    ///
+    /// ## Deadlocks
+    ///
+    /// This method internally locks the context, so it must be called when the context is unlocked.
+    pub fn render(&mut self, context: &SoundContext, sample_count: usize) -> OfflineRenderOutput {
+        let mut output = OfflineRenderOutput {
+            sample_rate: self.sample_rate,
+            mix: Vec::with_capacity(sample_count),
+            stems: Default::default(),
+        };
+
+        while output.mix.len() < sample_count {
+            if self.position >= self.block.len() {
+                self.render_block(context);
+            }
+
+            let count = (sample_count - output.mix.len()).min(self.block.len() - self.position);
+            let range = self.position..(self.position + count);
+
+            if self.capture_stems {
+                for block_stem in self.block_stems.iter() {
+                    let stem = match output
+                        .stems
+                        .iter()
+                        .position(|stem| stem.bus == block_stem.bus)
+                    {
+                        Some(index) => &mut output.stems[index],
+                        None => {
+                            // The bus could be added in the middle of rendering, so its stem must be
+                            // aligned with the mix.
+                            output.stems.push(AudioStem {
+                                bus: block_stem.bus,
+                                name: block_stem.name.clone(),
+                                samples: vec![(0.0, 0.0); output.mix.len()],
+                            });
+                            output.stems.last_mut().unwrap()
+                        }
+                    };
+                    stem.samples
+                        .extend_from_slice(&block_stem.samples[range.clone()]);
+                }
+            }
+
+            output.mix.extend_from_slice(&self.block[range]);
+            self.position += count;
+
+            // Removed buses produce silence.
+            for stem in output.stems.iter_mut() {
+                stem.samples.resize(output.mix.len(), (0.0, 0.0));
+            }
+        }
+
+        self.rendered_samples += sample_count as u64;
+
+        output
+    }
+
+    fn render_block(&mut self, context: &SoundContext) {
+        self.block.clear();
+        self.block
+            .resize(SoundContext::SAMPLES_PER_CHANNEL, (0.0, 0.0));
+
+        let mut state = context.state();
+
+        state.render(self.sample_rate, &mut self.block);
+
+        self.block_stems.clear();
+        if self.capture_stems {
+            let paused = state.is_paused();
+            for (handle, bus) in state.bus_graph_ref().buses_pair_iter() {
+                let mut samples = vec![(0.0, 0.0); self.block.len()];
+                if !paused {
+                    for (dest, src) in samples.iter_mut().zip(bus.rendered_buffer()) {
+                        *dest = *src;
+                    }
+                }
+                self.block_stems.push(AudioStem {
+                    bus: handle,
+                    name: bus.name().to_owned(),
+                    samples,
+                });
+            }
+        }
+
+        self.position = 0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        buffer::{DataSource, SoundBufferResource, SoundBufferResourceExtension},
+        bus::AudioBus,
+        context::SoundContext,
+        offline::OfflineRenderer,
+        source::{SoundSourceBuilder, Status},
+    };
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    fn make_context() -> SoundContext {
+        make_context_with_send(1.0)
+    }
+
+    fn make_context_with_send(reverb_send: f32) -> SoundContext {
+        let context = SoundContext::new();
+
+        let samples = (0..SAMPLE_RATE)
+            .map(|i| (i as f32 * 0.05).sin())
+            .collect::<Vec<_>>();
+        let buffer = SoundBufferResource::new_generic(DataSource::Raw {
+            sample_rate: SAMPLE_RATE as usize,
+            channel_count: 1,
+            samples,
+        })
+        .unwrap();
+
+        let mut state = context.state();
+        let primary_bus = state.bus_graph_ref().primary_bus_handle();
+        state
+            .bus_graph_mut()
+            .add_bus(AudioBus::new("Music".to_string()), primary_bus);
+        let source = SoundSourceBuilder::new()
+            .with_buffer(buffer)
+            .with_bus("Music")
+            .with_reverb_send(reverb_send)
+            .with_status(Status::Playing)
+            .build()
+            .unwrap();
+        state.add_source(source);
+        drop(state);
+
+        context
+    }
+
+    #[test]
+    fn test_offline_render_is_deterministic_and_continuous() {
+        let mut renderer = OfflineRenderer::new(SAMPLE_RATE);
+        let whole = renderer.render(&make_context(), 5000);
+        assert_eq!(whole.mix.len(), 5000);
+        assert!(whole.mix.iter().any(|(l, r)| *l != 0.0 && *r != 0.0));
+
+        // Rendering the same in small chunks must produce the same signal.
+        let context = make_context();
+        let mut renderer = OfflineRenderer::new(SAMPLE_RATE);
+        let mut chunked = Vec::new();
+        for _ in 0..10 {
+            chunked.extend(renderer.render(&context, 500).mix);
+        }
+        assert_eq!(chunked, whole.mix);
+        assert_eq!(renderer.rendered_samples(), 5000);
+    }
+
+    #[test]
+    fn test_offline_render_stems() {
+        let mut renderer = OfflineRenderer::new(SAMPLE_RATE).with_stems(true);
+        let output = renderer.render(&make_context(), 3000);
+
+        assert_eq!(output.stems.len(), 2);
+        let music = output.stem("Music").unwrap();
+        assert_eq!(music.samples.len(), 3000);
+        // Primary bus has unit gain and no effects, so its stem is the mix itself.
+        assert_eq!(output.stem("Primary").unwrap().samples, output.mix);
+        assert_eq!(music.samples, output.mix);
+    }
+
+    #[test]
+    fn test_reverb_send() {
+        let context = make_context_with_send(0.5);
+        {
+            let mut state = context.state();
+            let primary_bus = state.bus_graph_ref().primary_bus_handle();
+            state
+                .bus_graph_mut()
+                .add_bus(AudioBus::new("Reverb".to_string()), primary_bus);
+            state.set_reverb_bus(Some("Reverb"));
+        }
+
+        let mut renderer = OfflineRenderer::new(SAMPLE_RATE).with_stems(true);
+        let output = renderer.render(&context, 3000);
+
+        let music = output.stem("Music").unwrap();
+        let reverb = output.stem("Reverb").unwrap();
+        assert!(music.samples.iter().any(|(l, _)| *l != 0.0));
+        for ((music_left, music_right), (reverb_left, reverb_right)) in
+            music.samples.iter().zip(reverb.samples.iter())
+        {
+            assert_eq!(music_left * 0.5, *reverb_left);
+            assert_eq!(music_right * 0.5, *reverb_right);
+        }
+    }
+}
@@ -94,7 +94,7 @@ pub(crate) fn render_source_default(
 ) {
     let distance_gain = lerpf(
         1.0,
-        source.calculate_distance_gain(listener, distance_model),
+        source.calculate_distance_gain(listener, distance_model) * source.spatial_gain,
         source.spatial_blend(),
     );
     let panning = lerpf(
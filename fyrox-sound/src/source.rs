@@ -55,7 +55,9 @@ use crate::{
     error::SoundError,
     listener::Listener,
 };
-use fyrox_core::{algebra::Vector3, log::Log, reflect::prelude::*, visitor::prelude::*};
+use fyrox_core::{
+    algebra::Vector3, log::Log, math::lerpf, reflect::prelude::*, uuid::Uuid, visitor::prelude::*,
+};
 use std::time::Duration;
 
 /// Status (state) of sound source.
@@ -130,6 +132,61 @@ pub struct SoundSource {
     max_distance: f32,
     #[reflect(min_value = 0.0, step = 0.05)]
     rolloff_factor: f32,
+    #[visit(optional)]
+    velocity: Vector3<f32>,
+    #[visit(optional)]
+    direction: Vector3<f32>,
+    #[visit(optional)]
+    #[reflect(min_value = 0.0, max_value = 6.3, step = 0.05)]
+    cone_inner_angle: f32,
+    #[visit(optional)]
+    #[reflect(min_value = 0.0, max_value = 6.3, step = 0.05)]
+    cone_outer_angle: f32,
+    #[visit(optional)]
+    #[reflect(min_value = 0.0, max_value = 1.0, step = 0.05)]
+    cone_outer_gain: f32,
+    #[visit(optional)]
+    #[reflect(min_value = 0.0, max_value = 1.0, step = 0.05)]
+    cone_outer_gain_hf: f32,
+    #[visit(optional)]
+    #[reflect(min_value = 0.0, step = 0.05)]
+    doppler_factor: f32,
+    // Pitch multiplier caused by Doppler effect, it is updated right before rendering.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    doppler_pitch: f64,
+    #[visit(optional)]
+    priority: i32,
+    #[visit(optional)]
+    #[reflect(min_value = 0.0, max_value = 1.0, step = 0.05)]
+    reverb_send: f32,
+    // Resource uuid of a sound event, that has created the source.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    pub(crate) event: Option<Uuid>,
+    // Attenuation caused by geometry between the source and the listener. It is set externally
+    // (usually by a scene) and it is not serialized.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    occlusion_gain: f32,
+    #[reflect(hidden)]
+    #[visit(skip)]
+    occlusion_gain_hf: f32,
+    // Combined cone and occlusion attenuation of the source, it is updated right before rendering.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    pub(crate) spatial_gain: f32,
+    #[reflect(hidden)]
+    #[visit(skip)]
+    spatial_gain_hf: f32,
+    // Previous high frequency gain and state of the low-pass filter used to attenuate high
+    // frequencies.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    prev_spatial_gain_hf: Option<f32>,
+    #[reflect(hidden)]
+    #[visit(skip)]
+    spatial_filter_state: (f32, f32),
     // Some data that needed for iterative overlap-save convolution.
     #[reflect(hidden)]
     #[visit(skip)]
@@ -168,6 +225,23 @@ impl Default for SoundSource {
             position: Vector3::new(0.0, 0.0, 0.0),
             max_distance: f32::MAX,
             rolloff_factor: 1.0,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            direction: Vector3::new(0.0, 0.0, 1.0),
+            cone_inner_angle: std::f32::consts::TAU,
+            cone_outer_angle: std::f32::consts::TAU,
+            cone_outer_gain: 0.0,
+            cone_outer_gain_hf: 1.0,
+            doppler_factor: 1.0,
+            doppler_pitch: 1.0,
+            priority: 0,
+            reverb_send: 1.0,
+            event: None,
+            occlusion_gain: 1.0,
+            occlusion_gain_hf: 1.0,
+            spatial_gain: 1.0,
+            spatial_gain_hf: 1.0,
+            prev_spatial_gain_hf: None,
+            spatial_filter_state: (0.0, 0.0),
             prev_left_samples: Default::default(),
             prev_right_samples: Default::default(),
             prev_sampling_vector: Vector3::new(0.0, 0.0, 1.0),
@@ -177,6 +251,12 @@ impl Default for SoundSource {
 }
 
 impl SoundSource {
+    // Doppler shift is limited to avoid infinite playback speed.
+    const MAX_DOPPLER_PITCH: f32 = 4.0;
+
+    // High frequency gain of cones and occlusion is applied to frequencies above this one.
+    const SPATIAL_FILTER_REFERENCE_FREQUENCY: f32 = 5000.0;
+
     /// Sets new name of the sound source.
     pub fn set_name<N: AsRef<str>>(&mut self, name: N) {
         name.as_ref().clone_into(&mut self.name);
@@ -310,7 +390,9 @@ impl SoundSource {
     }
 
     /// Enabled or disables sound looping. Looping sound will never stop by itself, but can be stopped or paused
-    /// by calling `stop` or `pause` methods. Useful for music, ambient sounds, etc.
+    /// by calling `stop` or `pause` methods. Useful for music, ambient sounds, etc. If the buffer has a
+    /// [`crate::buffer::generic::LoopRegion`], the sound plays from the beginning and then repeats
+    /// the region.
     pub fn set_looping(&mut self, looping: bool) -> &mut Self {
         self.looping = looping;
         self
@@ -396,6 +478,127 @@ impl SoundSource {
         self.max_distance
     }
 
+    /// Sets velocity of the source in world space (units per second). It is used only to calculate
+    /// Doppler shift, it does not move the source. Scene sound nodes derive it automatically from
+    /// their movement.
+    pub fn set_velocity(&mut self, velocity: Vector3<f32>) -> &mut Self {
+        self.velocity = velocity;
+        self
+    }
+
+    /// Returns velocity of the source.
+    pub fn velocity(&self) -> Vector3<f32> {
+        self.velocity
+    }
+
+    /// Sets direction of the source in world space. The direction is the axis of the sound cone
+    /// (see [`Self::set_cone_inner_angle`]) and it does not need to be normalized. Default is
+    /// (0, 0, 1).
+    pub fn set_direction(&mut self, direction: Vector3<f32>) -> &mut Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Returns direction of the source.
+    pub fn direction(&self) -> Vector3<f32> {
+        self.direction
+    }
+
+    /// Sets full angle (in radians) of the inner cone around the direction of the source. The
+    /// listener inside the inner cone hears the source without any cone attenuation. Default
+    /// value is 2*pi, which makes the source omnidirectional.
+    pub fn set_cone_inner_angle(&mut self, angle: f32) -> &mut Self {
+        self.cone_inner_angle = angle.clamp(0.0, std::f32::consts::TAU);
+        self
+    }
+
+    /// Returns full angle of the inner cone in radians.
+    pub fn cone_inner_angle(&self) -> f32 {
+        self.cone_inner_angle
+    }
+
+    /// Sets full angle (in radians) of the outer cone around the direction of the source. The
+    /// listener outside the outer cone hears the source attenuated by [`Self::set_cone_outer_gain`]
+    /// and [`Self::set_cone_outer_gain_hf`], between the cones the attenuation is interpolated.
+    /// Default value is 2*pi.
+    pub fn set_cone_outer_angle(&mut self, angle: f32) -> &mut Self {
+        self.cone_outer_angle = angle.clamp(0.0, std::f32::consts::TAU);
+        self
+    }
+
+    /// Returns full angle of the outer cone in radians.
+    pub fn cone_outer_angle(&self) -> f32 {
+        self.cone_outer_angle
+    }
+
+    /// Sets gain that is applied to the source when the listener is outside the outer cone.
+    /// Value must be in 0..1 range, default is 0.0.
+    pub fn set_cone_outer_gain(&mut self, gain: f32) -> &mut Self {
+        self.cone_outer_gain = gain.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Returns gain outside the outer cone.
+    pub fn cone_outer_gain(&self) -> f32 {
+        self.cone_outer_gain
+    }
+
+    /// Sets gain of high frequencies (above ~5 kHz) that is applied when the listener is outside
+    /// the outer cone. It works as a low-pass filter and allows you to make sounds muffled
+    /// "behind" the source. Value must be in 0..1 range, default is 1.0 (no filtering).
+    pub fn set_cone_outer_gain_hf(&mut self, gain: f32) -> &mut Self {
+        self.cone_outer_gain_hf = gain.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Returns high frequency gain outside the outer cone.
+    pub fn cone_outer_gain_hf(&self) -> f32 {
+        self.cone_outer_gain_hf
+    }
+
+    /// Sets Doppler factor of the source. It scales the strength of Doppler effect for this source
+    /// only (see also [`crate::context::State::set_doppler_factor`]), 0.0 disables the effect.
+    /// Default is 1.0.
+    pub fn set_doppler_factor(&mut self, factor: f32) -> &mut Self {
+        self.doppler_factor = factor.max(0.0);
+        self
+    }
+
+    /// Returns Doppler factor of the source.
+    pub fn doppler_factor(&self) -> f32 {
+        self.doppler_factor
+    }
+
+    /// Sets priority of the source. When an audio bus or a sound event reaches its voice limit,
+    /// sources with lower priority are stopped first. Default is 0.
+    pub fn set_priority(&mut self, priority: i32) -> &mut Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Returns priority of the source.
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// Sets how much of the signal of the source is sent to the reverb bus of the context (see
+    /// [`crate::context::State::set_reverb_bus`]). The value is clamped to `[0.0; 1.0]` range,
+    /// 0.0 - the source is not reverberated at all. Default is 1.0.
+    pub fn set_reverb_send(&mut self, reverb_send: f32) -> &mut Self {
+        self.reverb_send = reverb_send.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Returns reverb send level of the source.
+    pub fn reverb_send(&self) -> f32 {
+        self.reverb_send
+    }
+
+    /// Returns uuid of a sound event resource, that has created the source (if any).
+    pub fn event(&self) -> Option<Uuid> {
+        self.event
+    }
+
     /// Sets new name of the target audio bus. The name must be valid, otherwise the sound won't play!
     /// Default is [`AudioBusGraph::PRIMARY_BUS`].
     pub fn set_bus<S: AsRef<str>>(&mut self, bus: S) {
@@ -431,6 +634,135 @@ impl SoundSource {
         }
     }
 
+    // Doppler shift formula from OpenAL Specification as well. Velocities are clamped to the speed
+    // of sound, which makes sources that are faster than sound silent instead of invalid.
+    pub(crate) fn calculate_doppler_pitch(
+        &self,
+        listener: &Listener,
+        speed_of_sound: f32,
+        doppler_factor: f32,
+    ) -> f32 {
+        let doppler_factor = doppler_factor * self.doppler_factor;
+        if doppler_factor <= 0.0 || speed_of_sound <= 0.0 {
+            return 1.0;
+        }
+        let Some(to_listener) = (listener.position() - self.position).try_normalize(f32::EPSILON)
+        else {
+            return 1.0;
+        };
+        let max_speed = speed_of_sound / doppler_factor;
+        let listener_speed = listener.velocity().dot(&to_listener).min(max_speed);
+        let source_speed = self.velocity.dot(&to_listener).min(max_speed);
+        let denominator = speed_of_sound - doppler_factor * source_speed;
+        if denominator <= f32::EPSILON {
+            return Self::MAX_DOPPLER_PITCH;
+        }
+        ((speed_of_sound - doppler_factor * listener_speed) / denominator)
+            .clamp(0.0, Self::MAX_DOPPLER_PITCH)
+    }
+
+    // Returns a pair of gain and high frequency gain caused by sound cones.
+    pub(crate) fn calculate_cone_gains(&self, listener: &Listener) -> (f32, f32) {
+        let outer_angle = self.cone_outer_angle.max(self.cone_inner_angle);
+        if self.cone_inner_angle >= std::f32::consts::TAU {
+            return (1.0, 1.0);
+        }
+        let (Some(direction), Some(to_listener)) = (
+            self.direction.try_normalize(f32::EPSILON),
+            (listener.position() - self.position).try_normalize(f32::EPSILON),
+        ) else {
+            return (1.0, 1.0);
+        };
+        // Full angle of a cone whose surface touches the listener.
+        let angle = 2.0 * direction.dot(&to_listener).clamp(-1.0, 1.0).acos();
+        if angle <= self.cone_inner_angle {
+            (1.0, 1.0)
+        } else if angle >= outer_angle {
+            (self.cone_outer_gain, self.cone_outer_gain_hf)
+        } else {
+            let t = (angle - self.cone_inner_angle) / (outer_angle - self.cone_inner_angle);
+            (
+                lerpf(1.0, self.cone_outer_gain, t),
+                lerpf(1.0, self.cone_outer_gain_hf, t),
+            )
+        }
+    }
+
+    // Updates listener-dependent parameters of the source, must be called before rendering.
+    pub(crate) fn update_spatial_state(
+        &mut self,
+        listener: &Listener,
+        speed_of_sound: f32,
+        doppler_factor: f32,
+    ) {
+        let doppler_pitch = self.calculate_doppler_pitch(listener, speed_of_sound, doppler_factor);
+        self.doppler_pitch = lerpf(1.0, doppler_pitch, self.spatial_blend) as f64;
+        let (cone_gain, cone_gain_hf) = self.calculate_cone_gains(listener);
+        self.spatial_gain = cone_gain * self.occlusion_gain;
+        self.spatial_gain_hf = lerpf(
+            1.0,
+            cone_gain_hf * self.occlusion_gain_hf,
+            self.spatial_blend,
+        );
+    }
+
+    /// Sets attenuation caused by obstacles between the source and the listener. `gain` scales
+    /// the volume and `gain_hf` scales high frequencies (above ~5 kHz) of the source, both values
+    /// must be in 0..1 range. The sound engine does not know anything about the geometry of the
+    /// world, so occlusion must be calculated externally. Scene sound nodes do this automatically
+    /// when occlusion is enabled in the scene sound context. Default is (1.0, 1.0) - no occlusion.
+    pub fn set_occlusion(&mut self, gain: f32, gain_hf: f32) -> &mut Self {
+        self.occlusion_gain = gain.clamp(0.0, 1.0);
+        self.occlusion_gain_hf = gain_hf.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Returns a pair of occlusion gain and occlusion high frequency gain. See
+    /// [`Self::set_occlusion`] for more info.
+    pub fn occlusion(&self) -> (f32, f32) {
+        (self.occlusion_gain, self.occlusion_gain_hf)
+    }
+
+    /// Returns current pitch multiplier caused by Doppler effect.
+    pub fn doppler_pitch(&self) -> f64 {
+        self.doppler_pitch
+    }
+
+    // Final playback speed of the source (excluding resampling).
+    fn effective_pitch(&self) -> f64 {
+        self.pitch * self.doppler_pitch
+    }
+
+    // Attenuates high frequencies of the rendered samples when the listener is outside the cone
+    // or the source is occluded. It is a simple one-pole low-pass filter, whose output is mixed
+    // with the original signal.
+    fn apply_spatial_filter(&mut self, sample_rate: u32) {
+        let new_gain_hf = self.spatial_gain_hf;
+        let prev_gain_hf = *self.prev_spatial_gain_hf.get_or_insert(new_gain_hf);
+        self.prev_spatial_gain_hf = Some(new_gain_hf);
+        if prev_gain_hf >= 1.0 && new_gain_hf >= 1.0 {
+            self.spatial_filter_state = (0.0, 0.0);
+            return;
+        }
+
+        let k = 1.0
+            - (-2.0 * std::f32::consts::PI * Self::SPATIAL_FILTER_REFERENCE_FREQUENCY
+                / sample_rate as f32)
+                .exp();
+        let step = 1.0 / self.frame_samples.len().max(1) as f32;
+        let mut t = 0.0;
+        let (mut low_left, mut low_right) = self.spatial_filter_state;
+        for (left, right) in self.frame_samples.iter_mut() {
+            low_left += k * (*left - low_left);
+            low_right += k * (*right - low_right);
+            let gain_hf = lerpf(prev_gain_hf, new_gain_hf, t);
+            *left = low_left + gain_hf * (*left - low_left);
+            *right = low_right + gain_hf * (*right - low_right);
+            t += step;
+        }
+        self.spatial_filter_state = (low_left, low_right);
+    }
+
     pub(crate) fn calculate_panning(&self, listener: &Listener) -> f32 {
         (listener.position() - self.position)
             .try_normalize(f32::EPSILON)
@@ -463,39 +795,35 @@ impl SoundSource {
 
     /// Sets playback duration.
     pub fn set_playback_time(&mut self, time: Duration) {
-        if let Some(buffer) = self.buffer.as_ref() {
+        if let Some(buffer) = self.buffer.clone() {
             if let Some(buffer) = buffer.state().data() {
-                if let SoundBuffer::Streaming(ref mut streaming) = *buffer {
-                    // Make sure decoder is at right position.
-                    if streaming
-                        .time_seek(time.clamp(Duration::from_secs(0), streaming.duration()))
-                        .is_err()
-                    {
-                        Log::warn("error while setting decoder position");
-                    }
-                }
-                // Set absolute position first.
-                self.playback_pos = (time.as_secs_f64() * buffer.sample_rate as f64)
-                    .clamp(0.0, buffer.duration().as_secs_f64());
-                // Then adjust buffer read position.
-                self.buf_read_pos = match *buffer {
-                    SoundBuffer::Streaming(ref mut streaming) => {
-                        // Make sure to load correct data into buffer from decoder.
-                        streaming.read_next_block();
-                        // Streaming sources has different buffer read position because
-                        // buffer contains only small portion of data.
-                        self.playback_pos % (StreamingBuffer::STREAM_SAMPLE_COUNT as f64)
-                    }
-                    SoundBuffer::Generic(_) => self.playback_pos,
-                };
-                assert!(
-                    self.buf_read_pos * (buffer.channel_count() as f64)
-                        < buffer.samples().len() as f64
-                );
+                let position = time.as_secs_f64() * buffer.sample_rate() as f64;
+                self.seek_to_sample(buffer, position);
             }
         }
     }
 
+    // Moves playback position to the given sample (per channel) with sample accuracy. Streaming
+    // buffers are refilled starting from the new position.
+    fn seek_to_sample(&mut self, buffer: &mut SoundBuffer, position: f64) {
+        let last_sample = buffer.channel_duration_in_samples().saturating_sub(1) as f64;
+        let position = position.clamp(0.0, last_sample);
+        self.playback_pos = position;
+        self.buf_read_pos = match buffer {
+            SoundBuffer::Streaming(ref mut streaming) => {
+                // Make sure decoder is at right position.
+                if streaming.frame_seek(position as usize).is_err() {
+                    Log::warn("error while setting decoder position");
+                }
+                // Make sure to load correct data into buffer from decoder. The block now
+                // starts exactly at the requested sample.
+                streaming.read_next_block();
+                position.fract()
+            }
+            SoundBuffer::Generic(_) => position,
+        };
+    }
+
     pub(crate) fn render(&mut self, sample_rate: u32, amount: usize) {
         if self.frame_samples.capacity() < amount {
             self.frame_samples = Vec::with_capacity(amount);
@@ -513,15 +841,51 @@ impl SoundSource {
         }
         // Fill the remaining part of frame_samples.
         self.frame_samples.resize(amount, (0.0, 0.0));
+
+        self.apply_spatial_filter(sample_rate);
     }
 
     fn render_playing(&mut self, sample_rate: u32, buffer: &mut SoundBuffer, amount: usize) {
         let mut count = 0;
         loop {
-            count += self.render_until_block_end(sample_rate, buffer, amount - count);
+            let loop_region = if self.looping {
+                buffer.loop_region()
+            } else {
+                None
+            };
+
+            // Do not render past the end of the loop region, so the jump to its start is
+            // sample-accurate.
+            let mut block_amount = amount - count;
+            if let Some(region) = loop_region {
+                if self.playback_pos < region.end as f64 {
+                    let step = self.effective_pitch() * buffer.sample_rate() as f64
+                        / f64::from(sample_rate);
+                    let remaining = ((region.end as f64 - self.playback_pos) / step).ceil();
+                    block_amount = block_amount.min((remaining as usize).max(1));
+                }
+            }
+
+            let rendered = self.render_until_block_end(sample_rate, buffer, block_amount);
+            count += rendered;
+
+            if let Some(region) = loop_region {
+                if self.playback_pos >= region.end as f64 {
+                    let overshoot = self.playback_pos - region.end as f64;
+                    self.seek_to_sample(buffer, region.start as f64 + overshoot);
+                    if count == amount {
+                        break;
+                    }
+                    continue;
+                }
+            }
+
             if count == amount {
                 break;
             }
+            if rendered == block_amount {
+                continue;
+            }
 
             let channel_count = buffer.channel_count();
             let len = buffer.samples().len();
@@ -543,6 +907,9 @@ impl SoundSource {
                     self.status = Status::Stopped;
                     return;
                 }
+                if let Some(region) = loop_region {
+                    self.seek_to_sample(buffer, region.start as f64);
+                }
             } else {
                 self.buf_read_pos -= len as f64 / channel_count as f64;
             }
@@ -567,7 +934,8 @@ impl SoundSource {
         // However such auto-resampling has poor quality, but it is fast.
         let resampling_multiplier = buffer.sample_rate as f64 / f64::from(sample_rate);
 
-        let step = self.pitch * resampling_multiplier;
+        // Doppler shift is also applied here by changing playback speed of the source.
+        let step = self.effective_pitch() * resampling_multiplier;
         if step == 1.0 {
             if self.buf_read_pos < 0.0 {
                 // This can theoretically happen if we change pitch on the fly.
@@ -738,6 +1106,15 @@ pub struct SoundSourceBuilder {
     rolloff_factor: f32,
     spatial_blend: f32,
     bus: String,
+    velocity: Vector3<f32>,
+    direction: Vector3<f32>,
+    cone_inner_angle: f32,
+    cone_outer_angle: f32,
+    cone_outer_gain: f32,
+    cone_outer_gain_hf: f32,
+    doppler_factor: f32,
+    priority: i32,
+    reverb_send: f32,
 }
 
 impl Default for SoundSourceBuilder {
@@ -765,6 +1142,15 @@ impl SoundSourceBuilder {
             rolloff_factor: 1.0,
             spatial_blend: 1.0,
             bus: AudioBusGraph::PRIMARY_BUS.to_string(),
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            direction: Vector3::new(0.0, 0.0, 1.0),
+            cone_inner_angle: std::f32::consts::TAU,
+            cone_outer_angle: std::f32::consts::TAU,
+            cone_outer_gain: 0.0,
+            cone_outer_gain_hf: 1.0,
+            doppler_factor: 1.0,
+            priority: 0,
+            reverb_send: 1.0,
         }
     }
 
@@ -858,6 +1244,60 @@ impl SoundSourceBuilder {
         self
     }
 
+    /// See [`SoundSource::set_velocity`]
+    pub fn with_velocity(mut self, velocity: Vector3<f32>) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    /// See [`SoundSource::set_direction`]
+    pub fn with_direction(mut self, direction: Vector3<f32>) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// See [`SoundSource::set_cone_inner_angle`]
+    pub fn with_cone_inner_angle(mut self, angle: f32) -> Self {
+        self.cone_inner_angle = angle;
+        self
+    }
+
+    /// See [`SoundSource::set_cone_outer_angle`]
+    pub fn with_cone_outer_angle(mut self, angle: f32) -> Self {
+        self.cone_outer_angle = angle;
+        self
+    }
+
+    /// See [`SoundSource::set_cone_outer_gain`]
+    pub fn with_cone_outer_gain(mut self, gain: f32) -> Self {
+        self.cone_outer_gain = gain;
+        self
+    }
+
+    /// See [`SoundSource::set_cone_outer_gain_hf`]
+    pub fn with_cone_outer_gain_hf(mut self, gain: f32) -> Self {
+        self.cone_outer_gain_hf = gain;
+        self
+    }
+
+    /// See [`SoundSource::set_doppler_factor`]
+    pub fn with_doppler_factor(mut self, factor: f32) -> Self {
+        self.doppler_factor = factor;
+        self
+    }
+
+    /// See [`SoundSource::set_priority`]
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// See [`SoundSource::set_reverb_send`]
+    pub fn with_reverb_send(mut self, reverb_send: f32) -> Self {
+        self.reverb_send = reverb_send;
+        self
+    }
+
     /// Sets desired output bus for the sound source.
     pub fn with_bus<S: AsRef<str>>(mut self, bus: S) -> Self {
         self.bus = bus.as_ref().to_string();
@@ -884,12 +1324,112 @@ impl SoundSourceBuilder {
             prev_left_samples: Default::default(),
             prev_right_samples: Default::default(),
             bus: self.bus,
+            velocity: self.velocity,
+            direction: self.direction,
+            priority: self.priority,
             ..Default::default()
         };
 
+        source
+            .set_cone_inner_angle(self.cone_inner_angle)
+            .set_cone_outer_angle(self.cone_outer_angle)
+            .set_cone_outer_gain(self.cone_outer_gain)
+            .set_cone_outer_gain_hf(self.cone_outer_gain_hf)
+            .set_doppler_factor(self.doppler_factor)
+            .set_reverb_send(self.reverb_send);
+
         source.set_buffer(self.buffer)?;
         source.set_playback_time(self.playback_time);
 
         Ok(source)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::buffer::{generic::LoopRegion, DataSource, SoundBufferResourceExtension};
+
+    #[test]
+    fn test_loop_region() {
+        let buffer = SoundBufferResource::new_generic(DataSource::Raw {
+            sample_rate: 100,
+            channel_count: 1,
+            samples: (0..10).map(|i| i as f32).collect(),
+        })
+        .unwrap();
+        buffer
+            .state()
+            .data()
+            .unwrap()
+            .set_loop_region(Some(LoopRegion::new(4, 8)));
+
+        let mut source = SoundSourceBuilder::new()
+            .with_buffer(buffer)
+            .with_looping(true)
+            .with_status(Status::Playing)
+            .build()
+            .unwrap();
+        source.render(100, 16);
+        let samples = source
+            .frame_samples()
+            .iter()
+            .map(|(l, _)| *l as usize)
+            .collect::<Vec<_>>();
+        assert_eq!(samples, [0, 1, 2, 3, 4, 5, 6, 7, 4, 5, 6, 7, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_doppler_pitch() {
+        let listener = Listener::new();
+        let mut source = SoundSourceBuilder::new()
+            .with_position(Vector3::new(0.0, 0.0, 10.0))
+            .with_velocity(Vector3::new(0.0, 0.0, -34.33))
+            .build()
+            .unwrap();
+        // Approaching source sounds higher.
+        let pitch = source.calculate_doppler_pitch(&listener, 343.3, 1.0);
+        assert!((pitch - 1.0 / 0.9).abs() < 1.0e-4);
+
+        // Receding source sounds lower.
+        source.set_velocity(Vector3::new(0.0, 0.0, 34.33));
+        let pitch = source.calculate_doppler_pitch(&listener, 343.3, 1.0);
+        assert!((pitch - 1.0 / 1.1).abs() < 1.0e-4);
+
+        // Movement perpendicular to the line of sight causes no shift.
+        source.set_velocity(Vector3::new(50.0, 0.0, 0.0));
+        assert_eq!(source.calculate_doppler_pitch(&listener, 343.3, 1.0), 1.0);
+
+        source.set_velocity(Vector3::new(0.0, 0.0, -34.33));
+        source.set_doppler_factor(0.0);
+        assert_eq!(source.calculate_doppler_pitch(&listener, 343.3, 1.0), 1.0);
+    }
+
+    #[test]
+    fn test_cone_gains() {
+        let listener = Listener::new();
+        let mut source = SoundSourceBuilder::new()
+            .with_position(Vector3::new(0.0, 0.0, 10.0))
+            .with_direction(Vector3::new(0.0, 0.0, -1.0))
+            .with_cone_inner_angle(90.0f32.to_radians())
+            .with_cone_outer_angle(180.0f32.to_radians())
+            .with_cone_outer_gain(0.2)
+            .with_cone_outer_gain_hf(0.5)
+            .build()
+            .unwrap();
+
+        // Facing the listener.
+        assert_eq!(source.calculate_cone_gains(&listener), (1.0, 1.0));
+
+        // Facing away from the listener.
+        source.set_direction(Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(source.calculate_cone_gains(&listener), (0.2, 0.5));
+
+        // Between the inner and the outer cones (full angle is 135 degrees).
+        let half_angle = 67.5f32.to_radians();
+        source.set_direction(Vector3::new(half_angle.sin(), 0.0, -half_angle.cos()));
+        let (gain, gain_hf) = source.calculate_cone_gains(&listener);
+        assert!((gain - 0.6).abs() < 1.0e-4);
+        assert!((gain_hf - 0.75).abs() < 1.0e-4);
+    }
+}
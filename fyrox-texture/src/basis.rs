@@ -0,0 +1,448 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Basis Universal support for KTX2 containers. Basis textures (ETC1S with BasisLZ
+//! supercompression and UASTC) are stored in an intermediate format that must be transcoded to a
+//! format supported by GPU before it can be used. The transcoder works with `.basis` files only,
+//! so the payload of a KTX2 container is repacked into an in-memory `.basis` file first: both
+//! containers store the same slices, the only difference is the layout of the metadata.
+
+use crate::{CompressionOptions, TextureError, TexturePixelKind};
+use basis_universal::{TranscodeParameters, Transcoder, TranscoderTextureFormat};
+use std::borrow::Cow;
+
+/// Intermediate format of Basis Universal texture.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum BasisFormat {
+    /// Low-quality, highly compressed format. Slices are compressed with BasisLZ.
+    Etc1s,
+    /// High-quality format, which is essentially a subset of ASTC 4x4.
+    Uastc,
+}
+
+/// Basis Universal texture stored in a KTX2 container.
+pub(crate) struct BasisTexture<'a> {
+    format: BasisFormat,
+    has_alpha: bool,
+    srgb: bool,
+    width: u32,
+    height: u32,
+    level_count: u32,
+    images_per_level: u32,
+    levels: Vec<Cow<'a, [u8]>>,
+    global_data: &'a [u8],
+}
+
+// Size of `basis_file_header`.
+const FILE_HEADER_SIZE: usize = 77;
+// Size of `basis_slice_desc`.
+const SLICE_DESC_SIZE: usize = 23;
+// Size of the fixed part of BasisLZ global data (before image descriptors).
+const GLOBAL_HEADER_SIZE: usize = 20;
+// Size of `ktxBasisLzEtc1sImageDesc`.
+const IMAGE_DESC_SIZE: usize = 20;
+
+const BASIS_SIGNATURE: u16 = ((b'B' as u16) << 8) | b's' as u16;
+const BASIS_VERSION: u16 = 0x13;
+const BASIS_TEX_FORMAT_ETC1S: u8 = 0;
+const BASIS_TEX_FORMAT_UASTC: u8 = 1;
+const BASIS_HEADER_FLAG_ETC1S: u16 = 1;
+const BASIS_HEADER_FLAG_HAS_ALPHA_SLICES: u16 = 4;
+const BASIS_HEADER_FLAG_SRGB: u16 = 16;
+const BASIS_TEX_TYPE_2D_ARRAY: u8 = 1;
+const BASIS_SLICE_FLAG_HAS_ALPHA: u8 = 1;
+
+// Channel identifiers of KTX2 data format descriptor that indicate the presence of alpha.
+const ETC1S_CHANNEL_AAA: u8 = 15;
+const UASTC_CHANNEL_RGBA: u8 = 3;
+const UASTC_CHANNEL_RRRG: u8 = 5;
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn write_u24(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes()[..3]);
+}
+
+fn malformed() -> TextureError {
+    TextureError::Transcoding("malformed Basis Universal data".to_string())
+}
+
+struct SliceDesc {
+    image_index: u32,
+    level_index: u32,
+    flags: u8,
+    width: u32,
+    height: u32,
+    offset: usize,
+    size: usize,
+}
+
+impl<'a> BasisTexture<'a> {
+    /// Checks whether the given KTX2 container stores a Basis Universal texture and prepares it
+    /// for transcoding. Returns `Ok(None)` if the texture is stored in some other format.
+    pub fn from_ktx2(reader: &'a ktx2::Reader<&'a [u8]>) -> Result<Option<Self>, TextureError> {
+        let header = reader.header();
+        if header.format.is_some() {
+            return Ok(None);
+        }
+
+        let Some(dfd) = reader
+            .dfd_blocks()
+            .next()
+            .and_then(|block| ktx2::DfdBlockBasic::parse(block.data).ok())
+        else {
+            return Ok(None);
+        };
+
+        let format = match dfd.header.color_model {
+            Some(ktx2::ColorModel::ETC1S) => BasisFormat::Etc1s,
+            Some(ktx2::ColorModel::UASTC) => BasisFormat::Uastc,
+            _ => return Ok(None),
+        };
+
+        let has_alpha = dfd.sample_information().any(|sample| match format {
+            BasisFormat::Etc1s => sample.channel_type == ETC1S_CHANNEL_AAA,
+            BasisFormat::Uastc => {
+                sample.channel_type == UASTC_CHANNEL_RGBA
+                    || sample.channel_type == UASTC_CHANNEL_RRRG
+            }
+        });
+
+        if header.pixel_depth > 1 {
+            // Basis Universal does not support volume textures.
+            return Err(TextureError::UnsupportedFormat);
+        }
+
+        let mut levels = Vec::new();
+        for level in reader.levels() {
+            let data = match (format, header.supercompression_scheme) {
+                (BasisFormat::Etc1s, Some(ktx2::SupercompressionScheme::BasisLZ))
+                | (BasisFormat::Uastc, None) => Cow::Borrowed(level.data),
+                (BasisFormat::Uastc, Some(ktx2::SupercompressionScheme::Zstandard)) => {
+                    let mut decompressed =
+                        Vec::with_capacity(level.uncompressed_byte_length as usize);
+                    ruzstd::decoding::FrameDecoder::new()
+                        .decode_all_to_vec(level.data, &mut decompressed)
+                        .map_err(|e| TextureError::Decompression(e.to_string()))?;
+                    Cow::Owned(decompressed)
+                }
+                (_, scheme) => {
+                    return Err(TextureError::UnsupportedSupercompression(format!(
+                        "{scheme:?}"
+                    )))
+                }
+            };
+            levels.push(data);
+        }
+
+        Ok(Some(Self {
+            format,
+            has_alpha,
+            srgb: dfd.header.transfer_function == Some(ktx2::TransferFunction::SRGB),
+            width: header.pixel_width,
+            height: header.pixel_height.max(1),
+            level_count: levels.len() as u32,
+            images_per_level: header.layer_count.max(1) * header.face_count.max(1),
+            levels,
+            global_data: reader.supercompression_global_data(),
+        }))
+    }
+
+    /// Returns intermediate format of the texture.
+    #[cfg(test)]
+    pub fn format(&self) -> BasisFormat {
+        self.format
+    }
+
+    /// Returns the amount of mip levels of the texture.
+    pub fn level_count(&self) -> u32 {
+        self.level_count
+    }
+
+    /// Selects the GPU format that is the closest to the given compression options. Basis
+    /// Universal cannot be transcoded to HDR or single/two-channel formats, such textures are
+    /// transcoded to uncompressed RGBA.
+    fn target_format(
+        &self,
+        compression: CompressionOptions,
+    ) -> (TranscoderTextureFormat, TexturePixelKind) {
+        match compression {
+            CompressionOptions::Speed if !self.has_alpha => {
+                (TranscoderTextureFormat::BC1_RGB, TexturePixelKind::DXT1RGB)
+            }
+            CompressionOptions::Speed | CompressionOptions::Quality => (
+                TranscoderTextureFormat::BC3_RGBA,
+                TexturePixelKind::DXT5RGBA,
+            ),
+            CompressionOptions::BC7 => {
+                (TranscoderTextureFormat::BC7_RGBA, TexturePixelKind::BC7RGBA)
+            }
+            // ETC1 is a subset of ETC2, so it can be used as-is.
+            CompressionOptions::ETC2 if !self.has_alpha => (
+                TranscoderTextureFormat::ETC1_RGB,
+                TexturePixelKind::ETC2RGB8,
+            ),
+            CompressionOptions::ETC2 => (
+                TranscoderTextureFormat::ETC2_RGBA,
+                TexturePixelKind::ETC2RGBA8,
+            ),
+            CompressionOptions::ASTC => (
+                TranscoderTextureFormat::ASTC_4x4_RGBA,
+                TexturePixelKind::ASTC4x4RGBA,
+            ),
+            CompressionOptions::NoCompression
+            | CompressionOptions::BC4
+            | CompressionOptions::BC5
+            | CompressionOptions::BC6H => (
+                TranscoderTextureFormat::RGBA32,
+                if self.srgb {
+                    TexturePixelKind::SRGBA8
+                } else {
+                    TexturePixelKind::RGBA8
+                },
+            ),
+        }
+    }
+
+    fn slices(&self) -> Result<Vec<SliceDesc>, TextureError> {
+        let mut slices = Vec::new();
+        let mut level_offset = 0;
+        for (level_index, level) in self.levels.iter().enumerate() {
+            let level_index = level_index as u32;
+            let width = (self.width >> level_index).max(1);
+            let height = (self.height >> level_index).max(1);
+            for image_index in 0..self.images_per_level {
+                let mut add_slice = |flags, offset: usize, size: usize| {
+                    if offset + size > level.len() {
+                        return Err(malformed());
+                    }
+                    slices.push(SliceDesc {
+                        image_index,
+                        level_index,
+                        flags,
+                        width,
+                        height,
+                        offset: level_offset + offset,
+                        size,
+                    });
+                    Ok(())
+                };
+
+                match self.format {
+                    BasisFormat::Etc1s => {
+                        // Image descriptors are sorted by level, then by layer and face.
+                        let index = (level_index * self.images_per_level + image_index) as usize;
+                        let desc = GLOBAL_HEADER_SIZE + index * IMAGE_DESC_SIZE;
+                        let field = |n: usize| read_u32(self.global_data, desc + n * 4);
+                        let (Some(rgb_offset), Some(rgb_length)) = (field(1), field(2)) else {
+                            return Err(malformed());
+                        };
+                        add_slice(0, rgb_offset as usize, rgb_length as usize)?;
+                        if self.has_alpha {
+                            let (Some(alpha_offset), Some(alpha_length)) = (field(3), field(4))
+                            else {
+                                return Err(malformed());
+                            };
+                            add_slice(
+                                BASIS_SLICE_FLAG_HAS_ALPHA,
+                                alpha_offset as usize,
+                                alpha_length as usize,
+                            )?;
+                        }
+                    }
+                    BasisFormat::Uastc => {
+                        let size = level.len() / self.images_per_level as usize;
+                        let flags = if self.has_alpha {
+                            BASIS_SLICE_FLAG_HAS_ALPHA
+                        } else {
+                            0
+                        };
+                        add_slice(flags, image_index as usize * size, size)?;
+                    }
+                }
+            }
+            level_offset += level.len();
+        }
+        Ok(slices)
+    }
+
+    /// Repacks the texture into an in-memory `.basis` file. The layout of the file is: header,
+    /// slice descriptors, codebooks and Huffman tables (ETC1S only), slices data.
+    fn make_basis_file(&self) -> Result<Vec<u8>, TextureError> {
+        let slices = self.slices()?;
+
+        let (endpoint_count, selector_count, codebooks) = match self.format {
+            BasisFormat::Etc1s => {
+                let data = self.global_data;
+                let (
+                    Some(endpoint_count),
+                    Some(selector_count),
+                    Some(endpoints_length),
+                    Some(selectors_length),
+                    Some(tables_length),
+                ) = (
+                    read_u16(data, 0),
+                    read_u16(data, 2),
+                    read_u32(data, 4),
+                    read_u32(data, 8),
+                    read_u32(data, 12),
+                )
+                else {
+                    return Err(malformed());
+                };
+                let start = GLOBAL_HEADER_SIZE
+                    + (self.level_count * self.images_per_level) as usize * IMAGE_DESC_SIZE;
+                let lengths = [endpoints_length, selectors_length, tables_length];
+                let mut codebooks = [&[][..]; 3];
+                let mut offset = start;
+                for (codebook, length) in codebooks.iter_mut().zip(lengths) {
+                    *codebook = data
+                        .get(offset..offset + length as usize)
+                        .ok_or_else(malformed)?;
+                    offset += length as usize;
+                }
+                (endpoint_count, selector_count, codebooks)
+            }
+            BasisFormat::Uastc => (0, 0, [&[][..]; 3]),
+        };
+
+        let slice_descs_offset = FILE_HEADER_SIZE;
+        let endpoints_offset = slice_descs_offset + slices.len() * SLICE_DESC_SIZE;
+        let selectors_offset = endpoints_offset + codebooks[0].len();
+        let tables_offset = selectors_offset + codebooks[1].len();
+        let slices_offset = tables_offset + codebooks[2].len();
+        let total_size = slices_offset + self.levels.iter().map(|level| level.len()).sum::<usize>();
+
+        let (tex_format, mut flags) = match self.format {
+            BasisFormat::Etc1s => (BASIS_TEX_FORMAT_ETC1S, BASIS_HEADER_FLAG_ETC1S),
+            BasisFormat::Uastc => (BASIS_TEX_FORMAT_UASTC, 0),
+        };
+        if self.has_alpha {
+            flags |= BASIS_HEADER_FLAG_HAS_ALPHA_SLICES;
+        }
+        if self.srgb {
+            flags |= BASIS_HEADER_FLAG_SRGB;
+        }
+
+        let mut out = Vec::with_capacity(total_size);
+        out.extend_from_slice(&BASIS_SIGNATURE.to_le_bytes());
+        out.extend_from_slice(&BASIS_VERSION.to_le_bytes());
+        out.extend_from_slice(&(FILE_HEADER_SIZE as u16).to_le_bytes());
+        // Header CRC, it is checked only by explicit validation.
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&((total_size - FILE_HEADER_SIZE) as u32).to_le_bytes());
+        // Data CRC.
+        out.extend_from_slice(&0u16.to_le_bytes());
+        write_u24(&mut out, slices.len() as u32);
+        write_u24(&mut out, self.images_per_level);
+        out.push(tex_format);
+        out.extend_from_slice(&flags.to_le_bytes());
+        out.push(BASIS_TEX_TYPE_2D_ARRAY);
+        // Microseconds per frame.
+        write_u24(&mut out, 0);
+        // Reserved and user data.
+        out.extend_from_slice(&[0; 12]);
+        out.extend_from_slice(&endpoint_count.to_le_bytes());
+        out.extend_from_slice(&(endpoints_offset as u32).to_le_bytes());
+        write_u24(&mut out, codebooks[0].len() as u32);
+        out.extend_from_slice(&selector_count.to_le_bytes());
+        out.extend_from_slice(&(selectors_offset as u32).to_le_bytes());
+        write_u24(&mut out, codebooks[1].len() as u32);
+        out.extend_from_slice(&(tables_offset as u32).to_le_bytes());
+        out.extend_from_slice(&(codebooks[2].len() as u32).to_le_bytes());
+        out.extend_from_slice(&(slice_descs_offset as u32).to_le_bytes());
+        // Extended data.
+        out.extend_from_slice(&[0; 8]);
+        debug_assert_eq!(out.len(), FILE_HEADER_SIZE);
+
+        for slice in slices.iter() {
+            write_u24(&mut out, slice.image_index);
+            out.push(slice.level_index as u8);
+            out.push(slice.flags);
+            out.extend_from_slice(&(slice.width as u16).to_le_bytes());
+            out.extend_from_slice(&(slice.height as u16).to_le_bytes());
+            out.extend_from_slice(&(slice.width.div_ceil(4) as u16).to_le_bytes());
+            out.extend_from_slice(&(slice.height.div_ceil(4) as u16).to_le_bytes());
+            out.extend_from_slice(&((slices_offset + slice.offset) as u32).to_le_bytes());
+            out.extend_from_slice(&(slice.size as u32).to_le_bytes());
+            // Slice data CRC.
+            out.extend_from_slice(&0u16.to_le_bytes());
+        }
+
+        for codebook in codebooks {
+            out.extend_from_slice(codebook);
+        }
+        for level in self.levels.iter() {
+            out.extend_from_slice(level);
+        }
+        debug_assert_eq!(out.len(), total_size);
+
+        Ok(out)
+    }
+
+    /// Transcodes the texture to the GPU format that is the closest to the given compression
+    /// options. Returns the pixel kind of the transcoded data and the data itself. The data is
+    /// laid out the same way as in KTX2 container: mip levels go one after another, each level
+    /// contains all the layers and faces.
+    pub fn transcode(
+        &self,
+        compression: CompressionOptions,
+    ) -> Result<(TexturePixelKind, Vec<u8>), TextureError> {
+        let (target_format, pixel_kind) = self.target_format(compression);
+        let file = self.make_basis_file()?;
+
+        let mut transcoder = Transcoder::new();
+        transcoder
+            .prepare_transcoding(&file)
+            .map_err(|_| malformed())?;
+
+        let mut bytes = Vec::new();
+        for level_index in 0..self.level_count {
+            for image_index in 0..self.images_per_level {
+                let image = transcoder
+                    .transcode_image_level(
+                        &file,
+                        target_format,
+                        TranscodeParameters {
+                            image_index,
+                            level_index,
+                            ..Default::default()
+                        },
+                    )
+                    .map_err(|e| TextureError::Transcoding(format!("{e:?}")))?;
+                bytes.extend_from_slice(&image);
+            }
+        }
+
+        transcoder.end_transcoding();
+
+        Ok((pixel_kind, bytes))
+    }
+}
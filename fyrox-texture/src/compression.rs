@@ -0,0 +1,761 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Block compression encoders for the formats that are not covered by `tbc` crate: BC6H, BC7,
+//! ETC2 and ASTC. Every encoder uses a single fixed block mode, which makes the encoders simple and
+//! fast, at the cost of some quality compared to specialized offline tools.
+
+/// Size of a compressed block in bytes for 128-bit block formats.
+const BLOCK_SIZE: usize = 16;
+
+/// Weights for 4-bit indices of BC6H and BC7 formats.
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// Intensity modifiers of ETC1/ETC2 individual mode.
+const ETC_MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+/// Alpha modifiers of EAC format.
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+/// ASTC block mode for 4x4 weight grid with 2-bit weights and a single plane.
+const ASTC_BLOCK_MODE_4X4_QUANT_4: u32 = 0x42;
+
+/// ASTC color endpoint mode for LDR RGBA direct.
+const ASTC_CEM_LDR_RGBA_DIRECT: u32 = 12;
+
+/// Unquantized values of 2-bit ASTC weights.
+const ASTC_WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+
+#[derive(Default)]
+struct BitWriter {
+    bits: u128,
+    position: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        let mask = (1u128 << count) - 1;
+        self.bits |= (value as u128 & mask) << self.position;
+        self.position += count;
+    }
+
+    fn finish(self) -> [u8; BLOCK_SIZE] {
+        self.bits.to_le_bytes()
+    }
+}
+
+/// Fetches a 4x4 block of pixels (in row-major order) that starts at the given position. Edge
+/// pixels are replicated for blocks, that are partially outside of the image.
+fn fetch_block<T: Copy>(
+    pixels: &[T],
+    width: usize,
+    height: usize,
+    bx: usize,
+    by: usize,
+) -> [T; 16] {
+    std::array::from_fn(|i| {
+        let x = (bx + i % 4).min(width - 1);
+        let y = (by + i / 4).min(height - 1);
+        pixels[y * width + x]
+    })
+}
+
+fn encode_image<T, const N: usize>(
+    pixels: &[T],
+    width: usize,
+    height: usize,
+    encode_block: impl Fn([T; 16]) -> [u8; N],
+) -> Vec<u8>
+where
+    T: Copy,
+{
+    let mut out = Vec::with_capacity(width.div_ceil(4) * height.div_ceil(4) * N);
+    for by in (0..height).step_by(4) {
+        for bx in (0..width).step_by(4) {
+            out.extend_from_slice(&encode_block(fetch_block(pixels, width, height, bx, by)));
+        }
+    }
+    out
+}
+
+fn squared_distance<const C: usize>(a: &[f32; C], b: &[f32; C]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+/// Finds two endpoints of a line segment that approximates the given set of points the best, using
+/// principal component analysis.
+fn principal_endpoints<const C: usize>(points: &[[f32; C]; 16]) -> ([f32; C], [f32; C]) {
+    let mut mean = [0.0; C];
+    let mut min = [f32::MAX; C];
+    let mut max = [f32::MIN; C];
+    for point in points {
+        for c in 0..C {
+            mean[c] += point[c] / 16.0;
+            min[c] = min[c].min(point[c]);
+            max[c] = max[c].max(point[c]);
+        }
+    }
+
+    let mut covariance = [[0.0; C]; C];
+    for point in points {
+        for (i, row) in covariance.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value += (point[i] - mean[i]) * (point[j] - mean[j]);
+            }
+        }
+    }
+
+    // Power iteration, starting from the diagonal of the bounding box.
+    let mut axis: [f32; C] = std::array::from_fn(|c| max[c] - min[c]);
+    for _ in 0..8 {
+        let next: [f32; C] = std::array::from_fn(|i| {
+            covariance[i]
+                .iter()
+                .zip(axis.iter())
+                .map(|(a, b)| a * b)
+                .sum()
+        });
+        let length = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if length <= f32::EPSILON {
+            break;
+        }
+        axis = next.map(|v| v / length);
+    }
+    let length = axis.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if length <= f32::EPSILON {
+        return (mean, mean);
+    }
+    axis = axis.map(|v| v / length);
+
+    let mut t_min = f32::MAX;
+    let mut t_max = f32::MIN;
+    for point in points {
+        let t = (0..C).map(|c| (point[c] - mean[c]) * axis[c]).sum::<f32>();
+        t_min = t_min.min(t);
+        t_max = t_max.max(t);
+    }
+
+    (
+        std::array::from_fn(|c| mean[c] + axis[c] * t_min),
+        std::array::from_fn(|c| mean[c] + axis[c] * t_max),
+    )
+}
+
+/// Returns an index of the palette entry that is the closest to the given point.
+fn closest_index<const C: usize>(palette: &[[f32; C]], point: &[f32; C]) -> usize {
+    let mut best = 0;
+    let mut best_distance = f32::MAX;
+    for (i, entry) in palette.iter().enumerate() {
+        let distance = squared_distance(entry, point);
+        if distance < best_distance {
+            best_distance = distance;
+            best = i;
+        }
+    }
+    best
+}
+
+fn interpolate(a: u32, b: u32, weight: u32) -> u32 {
+    (a * (64 - weight) + b * weight + 32) >> 6
+}
+
+fn quantize_bc7_endpoint(endpoint: &[f32; 4]) -> ([u32; 4], u32) {
+    let mut best = ([0; 4], 0);
+    let mut best_error = f32::MAX;
+    for p_bit in 0..2 {
+        let quantized =
+            endpoint.map(|v| ((v - p_bit as f32) / 2.0).round().clamp(0.0, 127.0) as u32);
+        let error = quantized
+            .iter()
+            .zip(endpoint)
+            .map(|(q, v)| {
+                let d = ((q << 1) | p_bit) as f32 - v;
+                d * d
+            })
+            .sum::<f32>();
+        if error < best_error {
+            best_error = error;
+            best = (quantized, p_bit);
+        }
+    }
+    best
+}
+
+/// Encodes a block of RGBA pixels into BC7 format using mode 6 (single subset, 7-bit endpoints
+/// with unique p-bits, 4-bit indices).
+fn encode_bc7_block(block: [[u8; 4]; 16]) -> [u8; BLOCK_SIZE] {
+    let points = block.map(|p| p.map(|c| c as f32));
+    let (e0, e1) = principal_endpoints(&points);
+    let mut endpoints = [quantize_bc7_endpoint(&e0), quantize_bc7_endpoint(&e1)];
+
+    let unquantize = |(q, p): &([u32; 4], u32)| q.map(|q| (q << 1) | p);
+    let (c0, c1) = (unquantize(&endpoints[0]), unquantize(&endpoints[1]));
+    let palette = WEIGHTS_4
+        .map(|w| std::array::from_fn::<f32, 4, _>(|c| interpolate(c0[c], c1[c], w) as f32));
+    let mut indices = points.map(|p| closest_index(&palette, &p) as u32);
+
+    // The most significant bit of the anchor index is implicitly zero.
+    if indices[0] & 8 != 0 {
+        endpoints.swap(0, 1);
+        indices = indices.map(|i| 15 - i);
+    }
+
+    let mut writer = BitWriter::default();
+    writer.write(1 << 6, 7);
+    for c in 0..4 {
+        writer.write(endpoints[0].0[c], 7);
+        writer.write(endpoints[1].0[c], 7);
+    }
+    writer.write(endpoints[0].1, 1);
+    writer.write(endpoints[1].1, 1);
+    writer.write(indices[0], 3);
+    for index in &indices[1..] {
+        writer.write(*index, 4);
+    }
+    writer.finish()
+}
+
+/// Encodes the given RGBA8 image into BC7 format.
+pub(crate) fn encode_bc7(pixels: &[[u8; 4]], width: usize, height: usize) -> Vec<u8> {
+    encode_image(pixels, width, height, encode_bc7_block)
+}
+
+/// Converts the given non-negative floating-point value into half-precision float bits. Negative
+/// values and NaNs are converted to zero, too large values are clamped to the largest finite value.
+pub(crate) fn f32_to_f16_bits(value: f32) -> u16 {
+    const MAX_HALF: u16 = 0x7BFF;
+    if value.is_nan() || value <= 0.0 {
+        return 0;
+    }
+    if value >= 65504.0 {
+        return MAX_HALF;
+    }
+    let bits = value.to_bits();
+    let exponent = ((bits >> 23) & 0xFF) as i32 - 127 + 15;
+    let mantissa = bits & 0x7F_FFFF;
+    if exponent <= 0 {
+        // Subnormal half.
+        if exponent < -10 {
+            return 0;
+        }
+        let shift = (14 - exponent) as u32;
+        let mantissa = mantissa | 0x80_0000;
+        return ((mantissa + (1 << (shift - 1))) >> shift) as u16;
+    }
+    let half = ((exponent as u32) << 10) | (mantissa >> 13);
+    let half = half + ((mantissa >> 12) & 1);
+    (half as u16).min(MAX_HALF)
+}
+
+fn bc6h_unquantize(value: u32) -> u32 {
+    match value {
+        0 => 0,
+        1023 => 0xFFFF,
+        _ => ((value << 16) + 0x8000) >> 10,
+    }
+}
+
+fn bc6h_finish_unquantize(value: u32) -> u32 {
+    (value * 31) >> 6
+}
+
+/// Encodes a block of RGB half-float pixels into BC6H (unsigned) format using mode 11 (single
+/// region, 10-bit endpoints, 4-bit indices).
+fn encode_bc6h_block(block: [[u16; 3]; 16]) -> [u8; BLOCK_SIZE] {
+    let points = block.map(|p| p.map(|c| c as f32));
+    let (e0, e1) = principal_endpoints(&points);
+    let quantize = |e: [f32; 3]| e.map(|v| ((v - 15.0) / 31.0).round().clamp(0.0, 1023.0) as u32);
+    let mut endpoints = [quantize(e0), quantize(e1)];
+
+    let (u0, u1) = (
+        endpoints[0].map(bc6h_unquantize),
+        endpoints[1].map(bc6h_unquantize),
+    );
+    let palette = WEIGHTS_4.map(|w| {
+        std::array::from_fn::<f32, 3, _>(|c| {
+            bc6h_finish_unquantize(interpolate(u0[c], u1[c], w)) as f32
+        })
+    });
+    let mut indices = points.map(|p| closest_index(&palette, &p) as u32);
+
+    if indices[0] & 8 != 0 {
+        endpoints.swap(0, 1);
+        indices = indices.map(|i| 15 - i);
+    }
+
+    let mut writer = BitWriter::default();
+    writer.write(0b00011, 5);
+    for endpoint in endpoints.iter() {
+        for value in endpoint {
+            writer.write(*value, 10);
+        }
+    }
+    writer.write(indices[0], 3);
+    for index in &indices[1..] {
+        writer.write(*index, 4);
+    }
+    writer.finish()
+}
+
+/// Encodes the given RGB half-float image into BC6H (unsigned) format.
+pub(crate) fn encode_bc6h(pixels: &[[u16; 3]], width: usize, height: usize) -> Vec<u8> {
+    encode_image(pixels, width, height, encode_bc6h_block)
+}
+
+/// Returns positions of the pixels of the given ETC sub-block.
+fn etc_subblock(flip: bool, subblock: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..8).map(move |i| {
+        if flip {
+            (i % 4, subblock * 2 + i / 4)
+        } else {
+            (subblock * 2 + i / 4, i % 4)
+        }
+    })
+}
+
+struct EtcSubblock {
+    color: [u32; 3],
+    table: u32,
+    error: u32,
+}
+
+fn encode_etc_subblock(block: &[[u8; 3]; 16], flip: bool, subblock: usize) -> EtcSubblock {
+    let mut sum = [0u32; 3];
+    for (x, y) in etc_subblock(flip, subblock) {
+        for (s, c) in sum.iter_mut().zip(block[y * 4 + x]) {
+            *s += c as u32;
+        }
+    }
+    let color = sum.map(|s| ((s as f32 / 8.0) / 17.0).round().min(15.0) as u32);
+    let base = color.map(|c| (c * 17) as i32);
+
+    let mut best = EtcSubblock {
+        color,
+        table: 0,
+        error: u32::MAX,
+    };
+    for (table, [a, b]) in ETC_MODIFIERS.iter().enumerate() {
+        let error = etc_subblock(flip, subblock)
+            .map(|(x, y)| {
+                [*a, *b, -*a, -*b]
+                    .iter()
+                    .map(|modifier| etc_pixel_error(&base, *modifier, &block[y * 4 + x]))
+                    .min()
+                    .unwrap()
+            })
+            .sum::<u32>();
+        if error < best.error {
+            best.error = error;
+            best.table = table as u32;
+        }
+    }
+    best
+}
+
+fn etc_pixel_error(base: &[i32; 3], modifier: i32, pixel: &[u8; 3]) -> u32 {
+    base.iter()
+        .zip(pixel)
+        .map(|(b, p)| {
+            let d = (b + modifier).clamp(0, 255) - *p as i32;
+            (d * d) as u32
+        })
+        .sum()
+}
+
+/// Encodes a block of RGB pixels into ETC2 RGB format using individual mode, which is backward
+/// compatible with ETC1.
+fn encode_etc2_rgb_block(block: [[u8; 3]; 16]) -> [u8; 8] {
+    let (flip, subblocks) = [false, true]
+        .into_iter()
+        .map(|flip| {
+            (
+                flip,
+                [
+                    encode_etc_subblock(&block, flip, 0),
+                    encode_etc_subblock(&block, flip, 1),
+                ],
+            )
+        })
+        .min_by_key(|(_, subblocks)| subblocks[0].error as u64 + subblocks[1].error as u64)
+        .unwrap();
+
+    let [s0, s1] = &subblocks;
+    let mut high = (s0.color[0] << 28)
+        | (s1.color[0] << 24)
+        | (s0.color[1] << 20)
+        | (s1.color[1] << 16)
+        | (s0.color[2] << 12)
+        | (s1.color[2] << 8)
+        | (s0.table << 5)
+        | (s1.table << 2);
+    if flip {
+        high |= 1;
+    }
+
+    let mut low = 0u32;
+    for (subblock_index, subblock) in subblocks.iter().enumerate() {
+        let base = subblock.color.map(|c| (c * 17) as i32);
+        let [a, b] = ETC_MODIFIERS[subblock.table as usize];
+        for (x, y) in etc_subblock(flip, subblock_index) {
+            let pixel = &block[y * 4 + x];
+            // Index values: 0 => +a, 1 => +b, 2 => -a, 3 => -b.
+            let index = [a, b, -a, -b]
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, m)| etc_pixel_error(&base, **m, pixel))
+                .unwrap()
+                .0 as u32;
+            let bit = x * 4 + y;
+            low |= ((index >> 1) << (16 + bit)) | ((index & 1) << bit);
+        }
+    }
+
+    let mut out = [0; 8];
+    out[..4].copy_from_slice(&high.to_be_bytes());
+    out[4..].copy_from_slice(&low.to_be_bytes());
+    out
+}
+
+/// Encodes a block of alpha values into EAC format.
+fn encode_eac_alpha_block(alpha: [u8; 16]) -> [u8; 8] {
+    let min = *alpha.iter().min().unwrap() as i32;
+    let max = *alpha.iter().max().unwrap() as i32;
+
+    let (base, multiplier, table) = if min == max {
+        // Table 13 has zero modifier at index 4.
+        (min, 1, 13)
+    } else {
+        let mut best = (0, 1, 0);
+        let mut best_error = u32::MAX;
+        for (table, modifiers) in EAC_MODIFIERS.iter().enumerate() {
+            let range = modifiers[7] - modifiers[3];
+            let ideal = ((max - min) as f32 / range as f32).ceil() as i32;
+            for multiplier in (ideal - 1).max(1)..=(ideal + 1).min(15) {
+                let base = ((min + max) as f32 * 0.5
+                    - multiplier as f32 * (modifiers[7] + modifiers[3]) as f32 * 0.5)
+                    .round()
+                    .clamp(0.0, 255.0) as i32;
+                let error = alpha
+                    .iter()
+                    .map(|a| {
+                        modifiers
+                            .iter()
+                            .map(|m| {
+                                let d = (base + m * multiplier).clamp(0, 255) - *a as i32;
+                                (d * d) as u32
+                            })
+                            .min()
+                            .unwrap()
+                    })
+                    .sum::<u32>();
+                if error < best_error {
+                    best_error = error;
+                    best = (base, multiplier, table as i32);
+                }
+            }
+        }
+        best
+    };
+
+    let modifiers = &EAC_MODIFIERS[table as usize];
+    let mut bits = ((base as u64) << 56) | ((multiplier as u64) << 52) | ((table as u64) << 48);
+    for x in 0..4 {
+        for y in 0..4 {
+            let a = alpha[y * 4 + x] as i32;
+            let index = modifiers
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, m)| ((base + *m * multiplier).clamp(0, 255) - a).abs())
+                .unwrap()
+                .0 as u64;
+            let pixel = x * 4 + y;
+            bits |= index << (45 - 3 * pixel);
+        }
+    }
+    bits.to_be_bytes()
+}
+
+/// Encodes the given RGB8 image into ETC2 RGB format.
+pub(crate) fn encode_etc2_rgb(pixels: &[[u8; 3]], width: usize, height: usize) -> Vec<u8> {
+    encode_image(pixels, width, height, encode_etc2_rgb_block)
+}
+
+/// Encodes the given RGBA8 image into ETC2 RGBA format (EAC alpha + ETC2 color).
+pub(crate) fn encode_etc2_rgba(pixels: &[[u8; 4]], width: usize, height: usize) -> Vec<u8> {
+    encode_image(pixels, width, height, |block| {
+        let mut out = [0; BLOCK_SIZE];
+        out[..8].copy_from_slice(&encode_eac_alpha_block(block.map(|p| p[3])));
+        out[8..].copy_from_slice(&encode_etc2_rgb_block(block.map(|p| [p[0], p[1], p[2]])));
+        out
+    })
+}
+
+/// Encodes a block of RGBA pixels into ASTC 4x4 format using a single partition, LDR RGBA direct
+/// endpoints with 8-bit precision and 2-bit weights.
+fn encode_astc_block(block: [[u8; 4]; 16]) -> [u8; BLOCK_SIZE] {
+    let points = block.map(|p| p.map(|c| c as f32));
+    let (e0, e1) = principal_endpoints(&points);
+    let quantize = |e: [f32; 4]| e.map(|v| v.round().clamp(0.0, 255.0) as u32);
+    let (mut c0, mut c1) = (quantize(e0), quantize(e1));
+
+    // The decoder applies blue contraction if the sum of the second endpoint is less than the
+    // sum of the first one, so the endpoints must be ordered to prevent it.
+    let swapped = c1[0] + c1[1] + c1[2] < c0[0] + c0[1] + c0[2];
+    if swapped {
+        std::mem::swap(&mut c0, &mut c1);
+    }
+
+    let palette = ASTC_WEIGHTS_2
+        .map(|w| std::array::from_fn::<f32, 4, _>(|c| interpolate(c0[c], c1[c], w) as f32));
+    let indices = points.map(|p| closest_index(&palette, &p) as u32);
+
+    let mut writer = BitWriter::default();
+    writer.write(ASTC_BLOCK_MODE_4X4_QUANT_4, 11);
+    // Single partition.
+    writer.write(0, 2);
+    writer.write(ASTC_CEM_LDR_RGBA_DIRECT, 4);
+    for c in 0..4 {
+        writer.write(c0[c], 8);
+        writer.write(c1[c], 8);
+    }
+
+    // Weights are stored in reverse bit order starting from the end of the block.
+    let mut bits = writer.bits;
+    for (i, index) in indices.iter().enumerate() {
+        for bit in 0..2 {
+            if (index >> bit) & 1 != 0 {
+                bits |= 1u128 << (127 - (2 * i + bit));
+            }
+        }
+    }
+    bits.to_le_bytes()
+}
+
+/// Encodes the given RGBA8 image into ASTC 4x4 format.
+pub(crate) fn encode_astc_4x4(pixels: &[[u8; 4]], width: usize, height: usize) -> Vec<u8> {
+    encode_image(pixels, width, height, encode_astc_block)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn read_bits(block: &[u8], offset: u32, count: u32) -> u32 {
+        let bits = u128::from_le_bytes(block.try_into().unwrap());
+        ((bits >> offset) & ((1u128 << count) - 1)) as u32
+    }
+
+    fn decode_bc7_mode6(block: &[u8]) -> [[u8; 4]; 16] {
+        assert_eq!(read_bits(block, 0, 7), 1 << 6);
+        let p0 = read_bits(block, 63, 1);
+        let p1 = read_bits(block, 64, 1);
+        let e0: [u32; 4] =
+            std::array::from_fn(|c| (read_bits(block, 7 + c as u32 * 14, 7) << 1) | p0);
+        let e1: [u32; 4] =
+            std::array::from_fn(|c| (read_bits(block, 14 + c as u32 * 14, 7) << 1) | p1);
+        std::array::from_fn(|i| {
+            let index = if i == 0 {
+                read_bits(block, 65, 3)
+            } else {
+                read_bits(block, 68 + (i as u32 - 1) * 4, 4)
+            };
+            std::array::from_fn(|c| interpolate(e0[c], e1[c], WEIGHTS_4[index as usize]) as u8)
+        })
+    }
+
+    fn decode_etc_individual(block: &[u8]) -> [[u8; 3]; 16] {
+        let high = u32::from_be_bytes(block[..4].try_into().unwrap());
+        let low = u32::from_be_bytes(block[4..].try_into().unwrap());
+        assert_eq!(high & 2, 0);
+        let flip = high & 1 != 0;
+        std::array::from_fn(|i| {
+            let (x, y) = (i % 4, i / 4);
+            let subblock = if flip { y / 2 } else { x / 2 };
+            let shift = if subblock == 0 { 0 } else { 4 };
+            let color = [28, 20, 12].map(|offset| ((high >> (offset - shift)) & 0xF) * 17);
+            let table = (high >> if subblock == 0 { 5 } else { 2 }) & 7;
+            let bit = x * 4 + y;
+            let index = (((low >> (16 + bit)) & 1) << 1) | ((low >> bit) & 1);
+            let [a, b] = ETC_MODIFIERS[table as usize];
+            let modifier = [a, b, -a, -b][index as usize];
+            color.map(|c| (c as i32 + modifier).clamp(0, 255) as u8)
+        })
+    }
+
+    fn gradient_block() -> [[u8; 4]; 16] {
+        std::array::from_fn(|i| {
+            let t = (i * 16) as u8;
+            [t, 255 - t, 128, 255 - t / 2]
+        })
+    }
+
+    fn max_error<const C: usize>(a: &[[u8; C]; 16], b: &[[u8; C]; 16]) -> i32 {
+        a.iter()
+            .zip(b)
+            .flat_map(|(a, b)| a.iter().zip(b).map(|(a, b)| (*a as i32 - *b as i32).abs()))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_bc7_roundtrip() {
+        let block = gradient_block();
+        let encoded = encode_bc7(&block, 4, 4);
+        assert_eq!(encoded.len(), 16);
+        let decoded = decode_bc7_mode6(&encoded);
+        assert!(max_error(&block, &decoded) <= 8);
+    }
+
+    #[test]
+    fn test_etc2_rgb_roundtrip() {
+        let block = gradient_block().map(|p| [p[0], p[0], p[0]]);
+        let encoded = encode_etc2_rgb(&block, 4, 4);
+        assert_eq!(encoded.len(), 8);
+        let decoded = decode_etc_individual(&encoded);
+        assert!(max_error(&block, &decoded) <= 48);
+
+        // Uniform alpha must be preserved exactly.
+        let rgba = encode_etc2_rgba(&[[10, 20, 30, 77]; 16], 4, 4);
+        assert_eq!(rgba.len(), 16);
+        assert_eq!(rgba[0], 77);
+    }
+
+    #[test]
+    fn test_astc_block_layout() {
+        let encoded = encode_astc_4x4(&[[200, 100, 50, 255]; 16], 4, 4);
+        assert_eq!(read_bits(&encoded, 0, 11), ASTC_BLOCK_MODE_4X4_QUANT_4);
+        assert_eq!(read_bits(&encoded, 11, 2), 0);
+        assert_eq!(read_bits(&encoded, 13, 4), ASTC_CEM_LDR_RGBA_DIRECT);
+        for (c, expected) in [200, 100, 50, 255].into_iter().enumerate() {
+            assert_eq!(read_bits(&encoded, 17 + c as u32 * 16, 8), expected);
+            assert_eq!(read_bits(&encoded, 25 + c as u32 * 16, 8), expected);
+        }
+    }
+
+    /// Returns a block, whose first two rows are filled with the first color and last two rows
+    /// with the second one.
+    fn two_color_block<T: Copy>(a: T, b: T) -> [T; 16] {
+        std::array::from_fn(|i| if i < 8 { a } else { b })
+    }
+
+    // Expected blocks of the following tests are assembled by hand from the bit layouts given in
+    // the Khronos Data Format Specification. BC7 and ETC2 blocks were additionally checked by
+    // decoding them with the reference decoders of Basis Universal.
+
+    #[test]
+    fn test_bc7_known_block() {
+        // Mode 6, endpoints (16, 32, 48, 64) and (80, 48, 16, 127) with zero p-bits, indices 0
+        // and 15.
+        let block = two_color_block([32, 64, 96, 128], [160, 96, 32, 254]);
+        assert_eq!(
+            encode_bc7(&block, 4, 4),
+            [
+                0x40, 0x08, 0x14, 0x04, 0x83, 0x41, 0x80, 0x7F, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF,
+                0xFF, 0xFF
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bc6h_known_block() {
+        // Mode 11, endpoints (495, 0, 0) and (0, 495, 495) that are unquantized exactly to 1.0
+        // and 0.0, indices 0 and 15.
+        let block = two_color_block([0x3C00, 0, 0], [0, 0x3C00, 0x3C00]);
+        assert_eq!(
+            encode_bc6h(&block, 4, 4),
+            [
+                0xE3, 0x3D, 0x00, 0x00, 0x00, 0xE0, 0xBD, 0xF7, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF,
+                0xFF, 0xFF
+            ]
+        );
+    }
+
+    #[test]
+    fn test_etc2_known_block() {
+        // Individual mode without flip: the left half has base color (8, 4, 2) and the right one
+        // (15, 1, 7), both use table 0 with modifiers +2 and -2 respectively. Uniform alpha is
+        // encoded with table 13 and multiplier 1, all pixels use zero modifier (index 4).
+        let block: [[u8; 4]; 16] = std::array::from_fn(|i| {
+            if i % 4 < 2 {
+                [138, 70, 36, 77]
+            } else {
+                [253, 15, 117, 77]
+            }
+        });
+        assert_eq!(
+            encode_etc2_rgba(&block, 4, 4),
+            [
+                0x4D, 0x1D, 0x92, 0x49, 0x24, 0x92, 0x49, 0x24, 0x8F, 0x41, 0x27, 0x00, 0xFF, 0x00,
+                0x00, 0x00
+            ]
+        );
+    }
+
+    #[test]
+    fn test_astc_known_block() {
+        // Block mode 0x42, single partition, CEM 12 with 8-bit endpoints (20, 40, 60, 128) and
+        // (200, 100, 50, 255), the endpoints are swapped to avoid blue contraction. Weights 3 and
+        // 0 are stored in reverse bit order at the end of the block.
+        let block = two_color_block([200, 100, 50, 255], [20, 40, 60, 128]);
+        assert_eq!(
+            encode_astc_4x4(&block, 4, 4),
+            [
+                0x42, 0x80, 0x29, 0x90, 0x51, 0xC8, 0x78, 0x64, 0x00, 0xFF, 0x01, 0x00, 0x00, 0x00,
+                0xFF, 0xFF
+            ]
+        );
+    }
+
+    #[test]
+    fn test_partial_blocks() {
+        // 5x3 image is covered by 2x1 blocks.
+        let pixels = [[255u8; 4]; 15];
+        assert_eq!(encode_bc7(&pixels, 5, 3).len(), 32);
+        assert_eq!(encode_astc_4x4(&pixels, 5, 3).len(), 32);
+        assert_eq!(encode_bc6h(&[[0x3C00; 3]; 15], 5, 3).len(), 32);
+        assert_eq!(f32_to_f16_bits(1.0), 0x3C00);
+        assert_eq!(f32_to_f16_bits(1.0e6), 0x7BFF);
+    }
+}
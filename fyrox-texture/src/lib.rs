@@ -26,12 +26,14 @@
 //!
 //! ## Supported formats
 //!
-//! To load images and decode them, Fyrox uses image and ddsfile crates. Here is the list of
-//! supported formats: png, tga, bmp, dds, jpg, gif, tiff, dds.
+//! To load images and decode them, Fyrox uses image, ddsfile and ktx2 crates. Here is the list of
+//! supported formats: png, tga, bmp, dds, ktx2, jpg, gif, tiff.
 //!
 //! ## Compressed textures
 //!
-//! Fyrox supports most commonly used formats of compressed textures: DXT1, DXT3, DXT5.
+//! Fyrox supports most commonly used formats of compressed textures: DXT1, DXT3, DXT5, BC4, BC5,
+//! BC6H, BC7, ETC2 and ASTC (4x4 blocks). Desktop GPUs usually support BCn formats only, while mobile
+//! GPUs usually support ETC2 and ASTC only, see [`CompressionOptions::for_platform`].
 //!
 //! ## Render target
 //!
@@ -51,6 +53,7 @@ use fyrox_core::{
     futures::io::Error,
     io::FileError,
     num_traits::Bounded,
+    platform::TargetPlatform,
     reflect::prelude::*,
     sparse::AtomicIndex,
     uuid::Uuid,
@@ -73,6 +76,9 @@ use std::{
 };
 use strum_macros::{AsRefStr, EnumString, VariantNames};
 
+#[cfg(not(target_arch = "wasm32"))]
+mod basis;
+mod compression;
 pub mod loader;
 
 /// Texture kind.
@@ -302,6 +308,11 @@ impl ResourceData for Texture {
             | TexturePixelKind::DXT5RGBA
             | TexturePixelKind::R8RGTC
             | TexturePixelKind::RG8RGTC
+            | TexturePixelKind::BC6HRGBF
+            | TexturePixelKind::BC7RGBA
+            | TexturePixelKind::ETC2RGB8
+            | TexturePixelKind::ETC2RGBA8
+            | TexturePixelKind::ASTC4x4RGBA
             | TexturePixelKind::BGR8
             | TexturePixelKind::BGRA8
             | TexturePixelKind::RGB16F
@@ -328,6 +339,10 @@ impl ResourceData for Texture {
     fn try_clone_box(&self) -> Option<Box<dyn ResourceData>> {
         Some(Box::new(self.clone()))
     }
+
+    fn memory_usage(&self) -> usize {
+        self.bytes.len()
+    }
 }
 
 impl Visit for Texture {
@@ -1104,6 +1119,21 @@ pub enum TexturePixelKind {
 
     SRGBA8 = 25,
     SRGB8 = 26,
+
+    /// Compressed unsigned floating-point RGB texture (BC6H).
+    BC6HRGBF = 27,
+
+    /// Compressed RGBA texture (BC7).
+    BC7RGBA = 28,
+
+    /// Compressed RGB texture (ETC2).
+    ETC2RGB8 = 29,
+
+    /// Compressed RGBA texture (ETC2 with EAC alpha).
+    ETC2RGBA8 = 30,
+
+    /// Compressed RGBA texture (ASTC with 4x4 blocks).
+    ASTC4x4RGBA = 31,
 }
 
 impl TexturePixelKind {
@@ -1136,6 +1166,11 @@ impl TexturePixelKind {
             24 => Ok(Self::R16F),
             25 => Ok(Self::SRGBA8),
             26 => Ok(Self::SRGB8),
+            27 => Ok(Self::BC6HRGBF),
+            28 => Ok(Self::BC7RGBA),
+            29 => Ok(Self::ETC2RGB8),
+            30 => Ok(Self::ETC2RGBA8),
+            31 => Ok(Self::ASTC4x4RGBA),
             _ => Err(format!("Invalid texture kind {id}!")),
         }
     }
@@ -1170,7 +1205,12 @@ impl TexturePixelKind {
             | Self::DXT3RGBA
             | Self::DXT5RGBA
             | Self::R8RGTC
-            | Self::RG8RGTC => None,
+            | Self::RG8RGTC
+            | Self::BC6HRGBF
+            | Self::BC7RGBA
+            | Self::ETC2RGB8
+            | Self::ETC2RGBA8
+            | Self::ASTC4x4RGBA => None,
         }
     }
 }
@@ -1186,6 +1226,21 @@ pub enum TextureError {
     Image(image::ImageError),
     /// An error occurred during file loading.
     FileLoadError(FileError),
+    /// KTX2 container is malformed.
+    Ktx2(ktx2::ParseError),
+    /// KTX2 container uses a supercompression scheme that is not supported.
+    UnsupportedSupercompression(String),
+    /// Supercompressed mip level of KTX2 container cannot be decompressed.
+    Decompression(String),
+    /// Basis Universal texture cannot be transcoded.
+    Transcoding(String),
+    /// Requested compression cannot be applied to an image with the given pixel kind.
+    IncompatibleCompression {
+        /// Requested compression.
+        compression: CompressionOptions,
+        /// Pixel kind of the source image.
+        pixel_kind: TexturePixelKind,
+    },
 }
 
 impl Display for TextureError {
@@ -1203,6 +1258,27 @@ impl Display for TextureError {
             TextureError::FileLoadError(v) => {
                 write!(f, "A file load error has occurred {v:?}")
             }
+            TextureError::Ktx2(v) => {
+                write!(f, "Malformed KTX2 container: {v:?}")
+            }
+            TextureError::UnsupportedSupercompression(v) => {
+                write!(f, "Unsupported KTX2 supercompression scheme {v}")
+            }
+            TextureError::Decompression(v) => {
+                write!(f, "Unable to decompress a mip level: {v}")
+            }
+            TextureError::Transcoding(v) => {
+                write!(f, "Unable to transcode a Basis Universal texture: {v}")
+            }
+            TextureError::IncompatibleCompression {
+                compression,
+                pixel_kind,
+            } => {
+                write!(
+                    f,
+                    "{compression:?} compression cannot be applied to {pixel_kind:?} image"
+                )
+            }
         }
     }
 }
@@ -1215,6 +1291,12 @@ impl From<FileError> for TextureError {
     }
 }
 
+impl From<ktx2::ParseError> for TextureError {
+    fn from(v: ktx2::ParseError) -> Self {
+        Self::Ktx2(v)
+    }
+}
+
 impl From<image::ImageError> for TextureError {
     fn from(v: ImageError) -> Self {
         Self::Image(v)
@@ -1227,6 +1309,11 @@ impl From<std::io::Error> for TextureError {
     }
 }
 
+/// File identifier of KTX2 containers.
+const KTX2_MAGIC: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
 fn ceil_div_4(x: u32) -> u32 {
     x.div_ceil(4)
 }
@@ -1272,6 +1359,63 @@ pub enum CompressionOptions {
     /// This option is faster than `NoCompression` speed by lower requirements of memory
     /// bandwidth.
     Quality = 2,
+
+    /// A single-channel image will be encoded via BC4 compression. Loading of an image with
+    /// any other amount of channels fails with [`TextureError::IncompatibleCompression`].
+    /// Compression ratio is 1:2.
+    BC4 = 3,
+
+    /// A two-channel image will be encoded via BC5 compression. Loading of an image with any
+    /// other amount of channels fails with [`TextureError::IncompatibleCompression`].
+    /// Suitable for normal maps that store only X and Y components.
+    BC5 = 4,
+
+    /// An image will be encoded via BC6H compression, which preserves high dynamic range of
+    /// floating-point images. Compression ratio is 1:6 for 16-bit floating-point RGB images.
+    BC6H = 5,
+
+    /// An image will be encoded via BC7 compression, which has high quality for both color and
+    /// alpha. Compression ratio is 1:4 (including alpha).
+    BC7 = 6,
+
+    /// An image will be encoded via ETC2 compression (ETC2 + EAC for images with alpha). This
+    /// format is supported by most mobile GPUs, but rarely on desktop.
+    /// Compression ratio is 1:6 (without alpha) or 1:4 (with alpha).
+    ETC2 = 7,
+
+    /// An image will be encoded via ASTC compression with 4x4 blocks. This format is supported
+    /// by most modern mobile GPUs, but rarely on desktop. Compression ratio is 1:4 (including alpha).
+    ASTC = 8,
+}
+
+impl CompressionOptions {
+    /// Returns compression options that are the closest to the current ones and are supported by
+    /// the GPUs of the given platform. For example, BCn formats are replaced by ETC2 or ASTC on
+    /// Android and vice versa on PC. This method is used when exporting a project for a specific
+    /// platform.
+    pub fn for_platform(self, platform: TargetPlatform) -> Self {
+        match platform {
+            TargetPlatform::Android => match self {
+                Self::Speed | Self::BC4 | Self::BC5 => Self::ETC2,
+                Self::Quality | Self::BC7 => Self::ASTC,
+                // There's no widely supported HDR format on mobile devices.
+                Self::BC6H => Self::NoCompression,
+                Self::NoCompression | Self::ETC2 | Self::ASTC => self,
+            },
+            TargetPlatform::PC => match self {
+                Self::ETC2 => Self::Speed,
+                Self::ASTC => Self::BC7,
+                Self::NoCompression
+                | Self::Speed
+                | Self::Quality
+                | Self::BC4
+                | Self::BC5
+                | Self::BC6H
+                | Self::BC7 => self,
+            },
+            TargetPlatform::WebAssembly => self,
+        }
+    }
 }
 
 fn transmute_slice<T>(bytes: &[u8]) -> &'_ [T] {
@@ -1316,12 +1460,87 @@ fn compress_rg8_bc4<T: tbc::color::ColorRedGreen8>(
     tbc::encode_image_bc4_rg8_conv_u8::<T>(transmute_slice::<T>(bytes), width, height)
 }
 
+/// Converts pixels of an uncompressed 8-bit image into RGBA8 pixels. Returns `None` if the pixel
+/// kind is not an 8-bit format.
+fn to_rgba8(pixel_kind: TexturePixelKind, bytes: &[u8]) -> Option<Vec<[u8; 4]>> {
+    let pixels = match pixel_kind {
+        TexturePixelKind::R8 => bytes.iter().map(|r| [*r, 0, 0, 255]).collect(),
+        TexturePixelKind::Luminance8 => bytes.iter().map(|l| [*l, *l, *l, 255]).collect(),
+        TexturePixelKind::RG8 => bytes
+            .chunks_exact(2)
+            .map(|p| [p[0], p[1], 0, 255])
+            .collect(),
+        TexturePixelKind::LuminanceAlpha8 => bytes
+            .chunks_exact(2)
+            .map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        TexturePixelKind::RGB8 | TexturePixelKind::SRGB8 => bytes
+            .chunks_exact(3)
+            .map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        TexturePixelKind::BGR8 => bytes
+            .chunks_exact(3)
+            .map(|p| [p[2], p[1], p[0], 255])
+            .collect(),
+        TexturePixelKind::RGBA8 | TexturePixelKind::SRGBA8 => bytes
+            .chunks_exact(4)
+            .map(|p| [p[0], p[1], p[2], p[3]])
+            .collect(),
+        TexturePixelKind::BGRA8 => bytes
+            .chunks_exact(4)
+            .map(|p| [p[2], p[1], p[0], p[3]])
+            .collect(),
+        _ => return None,
+    };
+    Some(pixels)
+}
+
+/// Converts pixels of an uncompressed image into RGB pixels with half-precision floating-point
+/// components. 8-bit images are converted to `[0.0; 1.0]` range.
+fn to_rgb16f(pixel_kind: TexturePixelKind, bytes: &[u8]) -> Option<Vec<[u16; 3]>> {
+    let pixels = match pixel_kind {
+        TexturePixelKind::RGB16F => transmute_slice::<[u16; 3]>(bytes).to_vec(),
+        TexturePixelKind::RGB32F => transmute_slice::<[f32; 3]>(bytes)
+            .iter()
+            .map(|p| p.map(compression::f32_to_f16_bits))
+            .collect(),
+        TexturePixelKind::RGBA32F => transmute_slice::<[f32; 4]>(bytes)
+            .iter()
+            .map(|p| [p[0], p[1], p[2]].map(compression::f32_to_f16_bits))
+            .collect(),
+        _ => to_rgba8(pixel_kind, bytes)?
+            .iter()
+            .map(|p| [p[0], p[1], p[2]].map(|c| compression::f32_to_f16_bits(c as f32 / 255.0)))
+            .collect(),
+    };
+    Some(pixels)
+}
+
 fn data_hash(data: &[u8]) -> u64 {
     let mut hasher = FxHasher::default();
     data.hash(&mut hasher);
     hasher.finish()
 }
 
+/// BC4 and BC5 are requested explicitly for a specific kind of data, so unlike other options
+/// they must not silently fall back to uncompressed storage.
+fn is_compression_compatible(
+    pixel_kind: TexturePixelKind,
+    compression: CompressionOptions,
+) -> bool {
+    match compression {
+        CompressionOptions::BC4 => matches!(
+            pixel_kind,
+            TexturePixelKind::R8 | TexturePixelKind::Luminance8
+        ),
+        CompressionOptions::BC5 => matches!(
+            pixel_kind,
+            TexturePixelKind::RG8 | TexturePixelKind::LuminanceAlpha8
+        ),
+        _ => true,
+    }
+}
+
 fn try_compress(
     pixel_kind: TexturePixelKind,
     bytes: &[u8],
@@ -1360,6 +1579,53 @@ fn try_compress(
             compress_rg8_bc4::<tbc::color::RedGreen8>(bytes, w, h),
             TexturePixelKind::RG8RGTC,
         )),
+        (TexturePixelKind::R8 | TexturePixelKind::Luminance8, CompressionOptions::BC4) => Some((
+            compress_r8_bc4::<tbc::color::Red8>(bytes, w, h),
+            TexturePixelKind::R8RGTC,
+        )),
+        (TexturePixelKind::RG8 | TexturePixelKind::LuminanceAlpha8, CompressionOptions::BC5) => {
+            Some((
+                compress_rg8_bc4::<tbc::color::RedGreen8>(bytes, w, h),
+                TexturePixelKind::RG8RGTC,
+            ))
+        }
+        (_, CompressionOptions::BC6H) => Some((
+            compression::encode_bc6h(&to_rgb16f(pixel_kind, bytes)?, w, h),
+            TexturePixelKind::BC6HRGBF,
+        )),
+        (_, CompressionOptions::BC7) => Some((
+            compression::encode_bc7(&to_rgba8(pixel_kind, bytes)?, w, h),
+            TexturePixelKind::BC7RGBA,
+        )),
+        (_, CompressionOptions::ETC2) => {
+            let pixels = to_rgba8(pixel_kind, bytes)?;
+            let has_alpha = matches!(
+                pixel_kind,
+                TexturePixelKind::RGBA8
+                    | TexturePixelKind::SRGBA8
+                    | TexturePixelKind::BGRA8
+                    | TexturePixelKind::LuminanceAlpha8
+            );
+            if has_alpha {
+                Some((
+                    compression::encode_etc2_rgba(&pixels, w, h),
+                    TexturePixelKind::ETC2RGBA8,
+                ))
+            } else {
+                let pixels = pixels
+                    .iter()
+                    .map(|p| [p[0], p[1], p[2]])
+                    .collect::<Vec<_>>();
+                Some((
+                    compression::encode_etc2_rgb(&pixels, w, h),
+                    TexturePixelKind::ETC2RGB8,
+                ))
+            }
+        }
+        (_, CompressionOptions::ASTC) => Some((
+            compression::encode_astc_4x4(&to_rgba8(pixel_kind, bytes)?, w, h),
+            TexturePixelKind::ASTC4x4RGBA,
+        )),
         _ => None,
     }
 }
@@ -1403,14 +1669,24 @@ fn bytes_in_mip_level(kind: TextureKind, pixel_kind: TexturePixelKind, mip: usiz
         | TexturePixelKind::DXT3RGBA
         | TexturePixelKind::DXT5RGBA
         | TexturePixelKind::R8RGTC
-        | TexturePixelKind::RG8RGTC => {
+        | TexturePixelKind::RG8RGTC
+        | TexturePixelKind::BC6HRGBF
+        | TexturePixelKind::BC7RGBA
+        | TexturePixelKind::ETC2RGB8
+        | TexturePixelKind::ETC2RGBA8
+        | TexturePixelKind::ASTC4x4RGBA => {
             let block_size = match pixel_kind {
                 TexturePixelKind::DXT1RGB
                 | TexturePixelKind::DXT1RGBA
-                | TexturePixelKind::R8RGTC => 8,
+                | TexturePixelKind::R8RGTC
+                | TexturePixelKind::ETC2RGB8 => 8,
                 TexturePixelKind::DXT3RGBA
                 | TexturePixelKind::DXT5RGBA
-                | TexturePixelKind::RG8RGTC => 16,
+                | TexturePixelKind::RG8RGTC
+                | TexturePixelKind::BC6HRGBF
+                | TexturePixelKind::BC7RGBA
+                | TexturePixelKind::ETC2RGBA8
+                | TexturePixelKind::ASTC4x4RGBA => 16,
                 _ => unreachable!(),
             };
             match kind {
@@ -1469,15 +1745,16 @@ where
 }
 
 impl Texture {
-    /// Tries to load a texture from given data in one of the following formats: PNG, BMP, TGA, JPG, DDS, GIF. Use
-    /// this method if you want to load a texture from embedded data.
+    /// Tries to load a texture from given data in one of the following formats: PNG, BMP, TGA, JPG, DDS, KTX2, GIF.
+    /// Use this method if you want to load a texture from embedded data.
     ///
     /// # On-demand compression and mip-map generation
     ///
     /// The data can be compressed if needed to improve performance on GPU side. Mip-maps can be generated as well.
-    /// **CAVEAT:** Compression and mip-map generation **won't** be taken into account in case of **DDS** textures,
-    /// because DDS can already contain such data, you should generate mips and compress DDS textures manually using
-    /// some offline tool like DirectXTexTool or similar.
+    /// **CAVEAT:** Compression and mip-map generation **won't** be taken into account in case of **DDS** and **KTX2**
+    /// textures, because these containers can already contain such data, you should generate mips and compress such
+    /// textures manually using some offline tool like DirectXTexTool, toktx or similar. KTX2 textures supercompressed
+    /// with Zstandard are supported, BasisLZ and ZLIB supercompression schemes are not supported.
     ///
     /// # Important notes
     ///
@@ -1494,6 +1771,10 @@ impl Texture {
         data: &[u8],
         import_options: TextureImportOptions,
     ) -> Result<Self, TextureError> {
+        if data.starts_with(&KTX2_MAGIC) {
+            return Self::load_ktx2(data, import_options);
+        }
+
         // DDS is special. It can contain various kinds of textures as well as textures with
         // various pixel formats.
         //
@@ -1624,6 +1905,13 @@ impl Texture {
             };
             let mut final_pixel_kind = src_pixel_kind;
 
+            if !is_compression_compatible(src_pixel_kind, import_options.compression) {
+                return Err(TextureError::IncompatibleCompression {
+                    compression: import_options.compression,
+                    pixel_kind: src_pixel_kind,
+                });
+            }
+
             let mut mip_count = 0;
             let mut bytes = Vec::with_capacity(
                 width as usize * height as usize * src_pixel_kind.size_in_bytes().unwrap_or(4),
@@ -1728,6 +2016,131 @@ impl Texture {
         }
     }
 
+    fn load_ktx2(data: &[u8], import_options: TextureImportOptions) -> Result<Self, TextureError> {
+        let reader = ktx2::Reader::new(data)?;
+        let header = reader.header();
+
+        // Basis Universal textures have no format, they must be transcoded to one first.
+        #[cfg(not(target_arch = "wasm32"))]
+        let content = match basis::BasisTexture::from_ktx2(&reader)? {
+            Some(basis) => {
+                let (pixel_kind, bytes) = basis.transcode(import_options.compression)?;
+                (pixel_kind, bytes, basis.level_count())
+            }
+            None => Self::read_ktx2_levels(&reader)?,
+        };
+        #[cfg(target_arch = "wasm32")]
+        let content = Self::read_ktx2_levels(&reader)?;
+        let (pixel_kind, bytes, mip_count) = content;
+
+        let kind = if header.face_count == 6 {
+            TextureKind::Cube {
+                size: header.pixel_width,
+            }
+        } else if header.pixel_depth > 0 {
+            TextureKind::Volume {
+                width: header.pixel_width,
+                height: header.pixel_height,
+                depth: header.pixel_depth,
+            }
+        } else if header.pixel_height == 0 {
+            TextureKind::Line {
+                length: header.pixel_width,
+            }
+        } else {
+            TextureKind::Rectangle {
+                width: header.pixel_width,
+                height: header.pixel_height,
+            }
+        };
+
+        Ok(Self {
+            pixel_kind,
+            modifications_counter: 0,
+            minification_filter: import_options.minification_filter,
+            magnification_filter: import_options.magnification_filter,
+            s_wrap_mode: import_options.s_wrap_mode,
+            t_wrap_mode: import_options.t_wrap_mode,
+            r_wrap_mode: import_options.r_wrap_mode,
+            base_level: import_options.base_level,
+            max_level: import_options.max_level,
+            min_lod: import_options.min_lod,
+            max_lod: import_options.max_lod,
+            anisotropy: import_options.anisotropy,
+            mip_count: mip_count.max(1),
+            bytes: bytes.into(),
+            kind,
+            is_render_target: false,
+            cache_index: Default::default(),
+            lod_bias: import_options.lod_bias,
+            sampler_properties_modifications: 1,
+        })
+    }
+
+    /// Reads mip levels of a KTX2 container that stores a texture in one of the formats supported
+    /// by the engine. Returns pixel kind, concatenated data of all levels and the amount of levels.
+    fn read_ktx2_levels(
+        reader: &ktx2::Reader<&[u8]>,
+    ) -> Result<(TexturePixelKind, Vec<u8>, u32), TextureError> {
+        let header = reader.header();
+
+        let pixel_kind = match header.format.ok_or(TextureError::UnsupportedFormat)? {
+            ktx2::Format::R8_UNORM => TexturePixelKind::R8,
+            ktx2::Format::R8G8_UNORM => TexturePixelKind::RG8,
+            ktx2::Format::R8G8B8_UNORM => TexturePixelKind::RGB8,
+            ktx2::Format::R8G8B8_SRGB => TexturePixelKind::SRGB8,
+            ktx2::Format::R8G8B8A8_UNORM => TexturePixelKind::RGBA8,
+            ktx2::Format::R8G8B8A8_SRGB => TexturePixelKind::SRGBA8,
+            ktx2::Format::B8G8R8_UNORM => TexturePixelKind::BGR8,
+            ktx2::Format::B8G8R8A8_UNORM => TexturePixelKind::BGRA8,
+            ktx2::Format::R16_UNORM => TexturePixelKind::R16,
+            ktx2::Format::R16G16_UNORM => TexturePixelKind::RG16,
+            ktx2::Format::R16G16B16_UNORM => TexturePixelKind::RGB16,
+            ktx2::Format::R16G16B16A16_UNORM => TexturePixelKind::RGBA16,
+            ktx2::Format::R16_SFLOAT => TexturePixelKind::R16F,
+            ktx2::Format::R16G16B16_SFLOAT => TexturePixelKind::RGB16F,
+            ktx2::Format::R32_SFLOAT => TexturePixelKind::R32F,
+            ktx2::Format::R32G32B32_SFLOAT => TexturePixelKind::RGB32F,
+            ktx2::Format::R32G32B32A32_SFLOAT => TexturePixelKind::RGBA32F,
+            ktx2::Format::BC1_RGB_UNORM_BLOCK => TexturePixelKind::DXT1RGB,
+            ktx2::Format::BC1_RGBA_UNORM_BLOCK => TexturePixelKind::DXT1RGBA,
+            ktx2::Format::BC2_UNORM_BLOCK => TexturePixelKind::DXT3RGBA,
+            ktx2::Format::BC3_UNORM_BLOCK => TexturePixelKind::DXT5RGBA,
+            ktx2::Format::BC4_UNORM_BLOCK => TexturePixelKind::R8RGTC,
+            ktx2::Format::BC5_UNORM_BLOCK => TexturePixelKind::RG8RGTC,
+            ktx2::Format::BC6H_UFLOAT_BLOCK => TexturePixelKind::BC6HRGBF,
+            ktx2::Format::BC7_UNORM_BLOCK => TexturePixelKind::BC7RGBA,
+            ktx2::Format::ETC2_R8G8B8_UNORM_BLOCK => TexturePixelKind::ETC2RGB8,
+            ktx2::Format::ETC2_R8G8B8A8_UNORM_BLOCK => TexturePixelKind::ETC2RGBA8,
+            ktx2::Format::ASTC_4x4_UNORM_BLOCK => TexturePixelKind::ASTC4x4RGBA,
+            _ => return Err(TextureError::UnsupportedFormat),
+        };
+
+        let mut bytes = Vec::new();
+        let mut mip_count = 0;
+        for level in reader.levels() {
+            match header.supercompression_scheme {
+                None => bytes.extend_from_slice(level.data),
+                Some(ktx2::SupercompressionScheme::Zstandard) => {
+                    let mut decompressed =
+                        Vec::with_capacity(level.uncompressed_byte_length as usize);
+                    ruzstd::decoding::FrameDecoder::new()
+                        .decode_all_to_vec(level.data, &mut decompressed)
+                        .map_err(|e| TextureError::Decompression(e.to_string()))?;
+                    bytes.extend_from_slice(&decompressed);
+                }
+                Some(scheme) => {
+                    return Err(TextureError::UnsupportedSupercompression(format!(
+                        "{scheme:?}"
+                    )))
+                }
+            }
+            mip_count += 1;
+        }
+
+        Ok((pixel_kind, bytes, mip_count))
+    }
+
     /// Tries to load a texture from a file.
     ///
     /// # Notes
@@ -2073,3 +2486,193 @@ impl TextureDataRefMut<'_> {
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        CompressionOptions, Texture, TextureError, TextureImportOptions, TextureKind,
+        TexturePixelKind,
+    };
+    use fyrox_core::platform::TargetPlatform;
+
+    fn make_ktx2(levels: &[Vec<u8>]) -> Vec<u8> {
+        let compressed = levels
+            .iter()
+            .map(|level| {
+                ruzstd::encoding::compress_to_vec(
+                    level.as_slice(),
+                    ruzstd::encoding::CompressionLevel::Fastest,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let dfd_offset = ktx2::Header::LENGTH + levels.len() * ktx2::LevelIndex::LENGTH;
+        let dfd_length = 4;
+        let header = ktx2::Header {
+            format: Some(ktx2::Format::R8G8B8A8_UNORM),
+            type_size: 1,
+            pixel_width: 4,
+            pixel_height: 4,
+            pixel_depth: 0,
+            layer_count: 0,
+            face_count: 1,
+            level_count: levels.len() as u32,
+            supercompression_scheme: Some(ktx2::SupercompressionScheme::Zstandard),
+            index: ktx2::Index {
+                dfd_byte_offset: dfd_offset as u32,
+                dfd_byte_length: dfd_length,
+                kvd_byte_offset: 0,
+                kvd_byte_length: 0,
+                sgd_byte_offset: 0,
+                sgd_byte_length: 0,
+            },
+        };
+
+        let mut data = header.as_bytes().to_vec();
+        let mut offset = dfd_offset + dfd_length as usize;
+        for (level, compressed) in levels.iter().zip(compressed.iter()) {
+            data.extend_from_slice(
+                &ktx2::LevelIndex {
+                    byte_offset: offset as u64,
+                    byte_length: compressed.len() as u64,
+                    uncompressed_byte_length: level.len() as u64,
+                }
+                .as_bytes(),
+            );
+            offset += compressed.len();
+        }
+        data.extend_from_slice(&dfd_length.to_le_bytes());
+        for compressed in compressed {
+            data.extend_from_slice(&compressed);
+        }
+        data
+    }
+
+    #[test]
+    fn test_load_ktx2_zstd() {
+        let level0 = (0..64).collect::<Vec<u8>>();
+        let level1 = vec![7u8; 16];
+        let data = make_ktx2(&[level0.clone(), level1.clone()]);
+
+        let texture = Texture::load_from_memory(&data, TextureImportOptions::default()).unwrap();
+        assert_eq!(texture.pixel_kind(), TexturePixelKind::RGBA8);
+        assert_eq!(texture.mip_count(), 2);
+        assert_eq!(
+            texture.kind(),
+            TextureKind::Rectangle {
+                width: 4,
+                height: 4
+            }
+        );
+        assert_eq!(texture.data(), [level0, level1].concat().as_slice());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn check_basis_ktx2(data: &[u8], format: crate::basis::BasisFormat) {
+        let reader = ktx2::Reader::new(data).unwrap();
+        let basis = crate::basis::BasisTexture::from_ktx2(&reader)
+            .unwrap()
+            .unwrap();
+        assert_eq!(basis.format(), format);
+
+        // 16x16 image, left half is red and right half is blue.
+        let texture = Texture::load_from_memory(data, TextureImportOptions::default()).unwrap();
+        assert_eq!(texture.pixel_kind(), TexturePixelKind::RGBA8);
+        assert_eq!(texture.mip_count(), 5);
+        assert_eq!(
+            texture.kind(),
+            TextureKind::Rectangle {
+                width: 16,
+                height: 16
+            }
+        );
+        assert_eq!(texture.data().len(), (256 + 64 + 16 + 4 + 1) * 4);
+        let pixel = |x: usize, y: usize| &texture.data()[(y * 16 + x) * 4..][..4];
+        let is_close = |a: &[u8], b: [u8; 4]| a.iter().zip(b).all(|(a, b)| a.abs_diff(b) < 16);
+        assert!(is_close(pixel(0, 0), [255, 0, 0, 255]));
+        assert!(is_close(pixel(15, 15), [0, 0, 255, 255]));
+
+        // The image is opaque, so DXT1 must be used.
+        let options = TextureImportOptions::default().with_compression(CompressionOptions::Speed);
+        let texture = Texture::load_from_memory(data, options).unwrap();
+        assert_eq!(texture.pixel_kind(), TexturePixelKind::DXT1RGB);
+        // 16 + 4 + 1 + 1 + 1 blocks, 8 bytes each.
+        assert_eq!(texture.data().len(), 23 * 8);
+
+        let options = TextureImportOptions::default().with_compression(CompressionOptions::ASTC);
+        let texture = Texture::load_from_memory(data, options).unwrap();
+        assert_eq!(texture.pixel_kind(), TexturePixelKind::ASTC4x4RGBA);
+        assert_eq!(texture.data().len(), 23 * 16);
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_load_ktx2_etc1s() {
+        check_basis_ktx2(
+            include_bytes!("../test_data/etc1s.ktx2"),
+            crate::basis::BasisFormat::Etc1s,
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_load_ktx2_uastc() {
+        check_basis_ktx2(
+            include_bytes!("../test_data/uastc.ktx2"),
+            crate::basis::BasisFormat::Uastc,
+        );
+    }
+
+    fn encode_png(image: image::DynamicImage) -> Vec<u8> {
+        let mut data = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut data, image::ImageFormat::Png).unwrap();
+        data.into_inner()
+    }
+
+    #[test]
+    fn test_bc4_bc5_pixel_kinds() {
+        let luma = encode_png(image::DynamicImage::new_luma8(8, 8));
+        let luma_alpha = encode_png(image::DynamicImage::new_luma_a8(8, 8));
+        let rgb = encode_png(image::DynamicImage::new_rgb8(8, 8));
+
+        let bc4 = TextureImportOptions::default().with_compression(CompressionOptions::BC4);
+        let bc5 = TextureImportOptions::default().with_compression(CompressionOptions::BC5);
+
+        let texture = Texture::load_from_memory(&luma, bc4.clone()).unwrap();
+        assert_eq!(texture.pixel_kind(), TexturePixelKind::R8RGTC);
+        let texture = Texture::load_from_memory(&luma_alpha, bc5.clone()).unwrap();
+        assert_eq!(texture.pixel_kind(), TexturePixelKind::RG8RGTC);
+
+        for (data, options) in [
+            (&luma_alpha, bc4.clone()),
+            (&rgb, bc4),
+            (&luma, bc5.clone()),
+            (&rgb, bc5),
+        ] {
+            assert!(matches!(
+                Texture::load_from_memory(data, options),
+                Err(TextureError::IncompatibleCompression { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_compression_for_platform() {
+        assert_eq!(
+            CompressionOptions::Quality.for_platform(TargetPlatform::Android),
+            CompressionOptions::ASTC
+        );
+        assert_eq!(
+            CompressionOptions::BC5.for_platform(TargetPlatform::Android),
+            CompressionOptions::ETC2
+        );
+        assert_eq!(
+            CompressionOptions::ASTC.for_platform(TargetPlatform::PC),
+            CompressionOptions::BC7
+        );
+        assert_eq!(
+            CompressionOptions::BC7.for_platform(TargetPlatform::WebAssembly),
+            CompressionOptions::BC7
+        );
+    }
+}
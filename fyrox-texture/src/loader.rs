@@ -21,14 +21,18 @@
 //! Texture loader.
 
 use crate::{Texture, TextureImportOptions};
+use fyrox_core::append_extension;
+use fyrox_core::io::FileError;
+use fyrox_core::platform::TargetPlatform;
 use fyrox_core::reflect::Reflect;
 use fyrox_core::uuid::Uuid;
 use fyrox_resource::{
     io::ResourceIo, loader::BoxedImportOptionsLoaderFuture, loader::BoxedLoaderFuture,
     loader::LoaderPayload, loader::ResourceLoader, options::try_get_import_settings,
-    options::try_get_import_settings_opaque, options::BaseImportOptions, state::LoadError,
+    options::try_get_import_settings_opaque, options::BaseImportOptions,
+    options::OPTIONS_EXTENSION, state::LoadError,
 };
-use std::{path::PathBuf, sync::Arc};
+use std::{future::Future, path::PathBuf, pin::Pin, sync::Arc};
 
 /// Default implementation for texture loading.
 pub struct TextureLoader {
@@ -39,7 +43,7 @@ pub struct TextureLoader {
 impl ResourceLoader for TextureLoader {
     fn extensions(&self) -> &[&str] {
         &[
-            "jpg", "jpeg", "tga", "gif", "bmp", "png", "tiff", "tif", "dds",
+            "jpg", "jpeg", "tga", "gif", "bmp", "png", "tiff", "tif", "dds", "ktx2",
         ]
     }
 
@@ -64,6 +68,38 @@ impl ResourceLoader for TextureLoader {
         })
     }
 
+    fn convert(
+        &self,
+        src_path: PathBuf,
+        dest_path: PathBuf,
+        platform: TargetPlatform,
+        io: Arc<dyn ResourceIo>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), FileError>>>> {
+        let default_import_options = self.default_import_options.clone();
+        Box::pin(async move {
+            io.copy_file(&src_path, &dest_path).await?;
+
+            // Replace the compression format with the one that is supported by the target
+            // platform. The texture itself will be compressed on load.
+            let mut import_options = try_get_import_settings(&src_path, &*io)
+                .await
+                .unwrap_or(default_import_options);
+            let compression = import_options.compression.for_platform(platform);
+            if compression != import_options.compression {
+                import_options.compression = compression;
+                let options_path = append_extension(&dest_path, OPTIONS_EXTENSION);
+                if !import_options.save(&options_path) {
+                    return Err(FileError::Custom(format!(
+                        "Unable to save import options to {}",
+                        options_path.display()
+                    )));
+                }
+            }
+
+            Ok(())
+        })
+    }
+
     fn try_load_import_settings(
         &self,
         resource_path: PathBuf,
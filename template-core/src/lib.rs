@@ -319,21 +319,24 @@ fyrox-build-tools = {{ workspace = true }}
         base_path.join("export-cli/src/main.rs"),
         format!(
             r#"//! Exporter command line interface (CLI) with your game connected to it as a plugin.
-//! This tool can be used to automate project export in CI/CD.
-//! Typical usage: `cargo run --package export-cli -- --target-platform pc`
+//! This tool can be used to automate project export in CI/CD. It runs without a window, writes
+//! a machine-readable report and returns a non-zero exit code on failure.
+//! Typical usage: `cargo run --package export-cli -- --target-platform pc --profile release`
 //!             or `cargo run --package export-cli -- --help` for the docs.
 
 use {name}::Game;
 use fyrox::core::log::Log;
 use fyrox::engine::executor::Executor;
-use fyrox::event_loop::EventLoop;
-use fyrox_build_tools::export::cli_export;
+use fyrox_build_tools::export::headless::headless_export;
+use std::process::ExitCode;
 
-fn main() {{
+fn main() -> ExitCode {{
     Log::set_file_name("{name}Export.log");
-    let mut executor = Executor::new(EventLoop::new().ok());
-    executor.add_plugin(Game::default());
-    cli_export(executor.resource_manager.clone())
+    headless_export(|| {{
+        let mut executor = Executor::new(None);
+        executor.add_plugin(Game::default());
+        executor.resource_manager.clone()
+    }})
 }}"#,
         ),
     )